@@ -89,3 +89,31 @@ def get_client_mount_source(**kwargs):
     """
 
     return graphql_query(query, variables=kwargs)["clientMountSource"]
+
+
+def get_failover_resources(**kwargs):
+    query = """
+        query FailoverResources($fqdn: String!) {
+          server {
+            failoverResources(fqdn: $fqdn) {
+              ha_label: haLabel
+              peer
+              failback
+            }
+          }
+        }
+    """
+
+    return graphql_query(query, variables=kwargs)["server"]["failoverResources"]
+
+
+def detect_filesystems():
+    query = """
+        mutation DetectFilesystems {
+          filesystem {
+            detect
+          }
+        }
+    """
+
+    return graphql_query(query)["filesystem"]["detect"]
@@ -0,0 +1,85 @@
+# -*- coding: utf-8 -*-
+# Generated by Django 1.11.23 on 2020-12-16 15:02
+from __future__ import unicode_literals
+
+from django.db import migrations, models
+import django.db.models.deletion
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ("chroma_core", "0032_forgetlustreclientjob"),
+    ]
+
+    operations = [
+        migrations.CreateModel(
+            name="ClearHostResourceBanJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("fqdn", models.CharField(help_text="Host to clear the ban from", max_length=256)),
+                ("ha_label", models.CharField(help_text="Resource to clear the ban of", max_length=512)),
+                ("node_name", models.CharField(help_text="Node the resource was banned from", max_length=256)),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+        migrations.CreateModel(
+            name="FailbackHostResourceJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("fqdn", models.CharField(help_text="Host to run the failback from", max_length=256)),
+                ("ha_label", models.CharField(help_text="Resource to move", max_length=512)),
+                ("node_name", models.CharField(help_text="Node to move the resource back to", max_length=256)),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+        migrations.CreateModel(
+            name="FailoverHostResourceJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("fqdn", models.CharField(help_text="Host to run the failover from", max_length=256)),
+                ("ha_label", models.CharField(help_text="Resource to move", max_length=512)),
+                ("node_name", models.CharField(help_text="Node to move the resource away from", max_length=256)),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+    ]
@@ -0,0 +1,142 @@
+# -*- coding: utf-8 -*-
+# Generated by Django 1.11.23 on 2020-12-23 10:12
+from __future__ import unicode_literals
+
+from django.db import migrations, models
+import django.db.models.deletion
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ("chroma_core", "0036_zpool_alerts_and_scrub_jobs"),
+    ]
+
+    operations = [
+        migrations.CreateModel(
+            name="AddLNetNetworkJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("fqdn", models.CharField(help_text="Host to configure the LNet network on", max_length=256)),
+                ("net", models.CharField(help_text="LNet network, i.e. tcp0", max_length=64)),
+                (
+                    "interfaces",
+                    models.CharField(help_text="Comma separated interfaces to add the network on", max_length=512),
+                ),
+                ("peer_credits", models.IntegerField(help_text="Peer credits of the network", null=True)),
+                ("credits", models.IntegerField(help_text="Credits of the network", null=True)),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+        migrations.CreateModel(
+            name="RemoveLNetNetworkJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("fqdn", models.CharField(help_text="Host to remove the LNet network from", max_length=256)),
+                ("net", models.CharField(help_text="LNet network, i.e. tcp0", max_length=64)),
+                (
+                    "interfaces",
+                    models.CharField(
+                        blank=True, help_text="Comma separated interfaces to remove. All if empty", max_length=512
+                    ),
+                ),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+        migrations.CreateModel(
+            name="AddLNetRouteJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("fqdn", models.CharField(help_text="Host to add the LNet route on", max_length=256)),
+                ("net", models.CharField(help_text="Remote LNet network", max_length=64)),
+                ("gateway", models.CharField(help_text="NID of the router", max_length=256)),
+                ("hop", models.IntegerField(help_text="Number of hops to the remote network", null=True)),
+                ("priority", models.IntegerField(help_text="Priority of the route", null=True)),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+        migrations.CreateModel(
+            name="RemoveLNetRouteJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("fqdn", models.CharField(help_text="Host to remove the LNet route from", max_length=256)),
+                ("net", models.CharField(help_text="Remote LNet network", max_length=64)),
+                ("gateway", models.CharField(help_text="NID of the router", max_length=256)),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+        migrations.CreateModel(
+            name="ImportLNetConfigJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("fqdn", models.CharField(help_text="Host to import the LNet configuration on", max_length=256)),
+                ("config", models.TextField(help_text="LNet configuration in lnetctl YAML format")),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+    ]
@@ -0,0 +1,139 @@
+# -*- coding: utf-8 -*-
+# Generated by Django 1.11.23 on 2021-01-11 09:47
+from __future__ import unicode_literals
+
+from django.db import migrations, models
+import django.contrib.postgres.fields.jsonb
+import django.db.models.deletion
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ("chroma_core", "0040_changelog_alerts"),
+    ]
+
+    operations = [
+        migrations.CreateModel(
+            name="CheckUpgradeHealthJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                (
+                    "hosts",
+                    django.contrib.postgres.fields.jsonb.JSONField(help_text="Hosts of the HA pair being upgraded"),
+                ),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+        migrations.CreateModel(
+            name="RestartLustreJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("fqdn", models.CharField(help_text="Host to restart Lustre on", max_length=256)),
+                (
+                    "reboot",
+                    models.BooleanField(
+                        default=False, help_text="Reboot the host instead of reloading the Lustre modules"
+                    ),
+                ),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+        migrations.CreateModel(
+            name="UpgradeFailbackJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("fqdn", models.CharField(help_text="Host to move target resources back to", max_length=256)),
+                ("node_name", models.CharField(help_text="Corosync node name of the host", max_length=256)),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+        migrations.CreateModel(
+            name="UpgradeFailoverJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("fqdn", models.CharField(help_text="Host to move target resources away from", max_length=256)),
+                ("node_name", models.CharField(help_text="Corosync node name of the host", max_length=256)),
+                (
+                    "moved",
+                    django.contrib.postgres.fields.jsonb.JSONField(
+                        default=list, help_text="Target resources failed over when the job ran"
+                    ),
+                ),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+        migrations.CreateModel(
+            name="VerifyLustreVersionJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("fqdn", models.CharField(help_text="Host to check the Lustre version of", max_length=256)),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+    ]
@@ -0,0 +1,100 @@
+# -*- coding: utf-8 -*-
+# Generated by Django 1.11.23 on 2021-01-13 14:21
+from __future__ import unicode_literals
+
+import django.contrib.postgres.fields.jsonb
+from django.db import migrations, models
+import django.db.models.deletion
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ("chroma_core", "0041_upgrade_jobs"),
+    ]
+
+    operations = [
+        migrations.CreateModel(
+            name="CreateTargetResourceJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("fqdn", models.CharField(help_text="Host to create the resources from", max_length=256)),
+                ("ha_label", models.CharField(help_text="Resource of the target", max_length=512)),
+                ("mountpoint", models.CharField(help_text="Where the target is mounted", max_length=512)),
+                ("hosts", django.contrib.postgres.fields.jsonb.JSONField(help_text="Hosts the target can run on")),
+                (
+                    "resources",
+                    django.contrib.postgres.fields.jsonb.JSONField(
+                        help_text="Resources to create with their constraints, in order"
+                    ),
+                ),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+        migrations.CreateModel(
+            name="DetectFilesystemJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("fs_name", models.CharField(help_text="Filesystem to detect", max_length=8)),
+                (
+                    "targets",
+                    django.contrib.postgres.fields.jsonb.JSONField(
+                        help_text="Targets to wait for before detecting the filesystem"
+                    ),
+                ),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+        migrations.CreateModel(
+            name="FormatLustreTargetJob",
+            fields=[
+                (
+                    "job_ptr",
+                    models.OneToOneField(
+                        auto_created=True,
+                        on_delete=django.db.models.deletion.CASCADE,
+                        parent_link=True,
+                        primary_key=True,
+                        serialize=False,
+                        to="chroma_core.Job",
+                    ),
+                ),
+                ("fqdn", models.CharField(help_text="Host to format the target on", max_length=256)),
+                ("label", models.CharField(help_text="Name of the target", max_length=64)),
+                (
+                    "config",
+                    django.contrib.postgres.fields.jsonb.JSONField(help_text="Arguments of the target_format action"),
+                ),
+            ],
+            options={
+                "ordering": ["id"],
+            },
+            bases=("chroma_core.job",),
+        ),
+    ]
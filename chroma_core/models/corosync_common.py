@@ -131,6 +131,53 @@ class CorosyncStoppedAlert(AlertStateBase):
         return [self.alert_item.host]
 
 
+class CorosyncRingFaultyAlert(AlertStateBase):
+    """Raised by the iml-corosync service when a corosync ring or knet link is faulty on a host.
+
+    Losing one ring is survivable when redundant rings are configured, but
+    losing the last one will partition the cluster.
+    """
+
+    default_severity = logging.ERROR
+
+    def alert_message(self):
+        return "Corosync ring faulty on server %s" % self.alert_item
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+    @property
+    def affected_objects(self):
+        """
+        :return: A list of objects that are affected by this alert
+        """
+        return [self.alert_item]
+
+
+class CorosyncQuorumLostAlert(AlertStateBase):
+    """Raised by the iml-corosync service when the partition a host is in is not quorate.
+
+    Pacemaker will stop resources in a partition without quorum.
+    """
+
+    default_severity = logging.CRITICAL
+
+    def alert_message(self):
+        return "Corosync quorum lost on server %s" % self.alert_item
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+    @property
+    def affected_objects(self):
+        """
+        :return: A list of objects that are affected by this alert
+        """
+        return [self.alert_item]
+
+
 class AutoConfigureCorosyncJob(StateChangeJob):
     state_transition = StateChangeJob.StateTransition(None, None, None)
     stateful_object = "corosync_configuration"
@@ -3,6 +3,7 @@
 # license that can be found in the LICENSE file.
 
 import functools
+import logging
 import operator
 from django.db import models
 from django.db.models import CASCADE
@@ -10,6 +11,7 @@ from chroma_core.lib.job import DependOn, DependAll, Step
 from chroma_core.models import ManagedMgs, ManagedMdt, ManagedOst, FilesystemMember, ManagedTarget, ManagedHost
 from chroma_core.models import StatefulObject, StateChangeJob, Job, AdvertisedJob
 from chroma_core.models import DeletableDowncastableMetaclass
+from chroma_core.models import AlertStateBase
 from chroma_core.lib.cache import ObjectCache
 from chroma_core.lib.util import target_label_split
 from django.db.models import Q
@@ -587,3 +589,49 @@ class RemoveOstPoolStep(Step):
         self.invoke_rust_agent_expect_result(
             host, "ostpool_remove", {"filesystem": fs_name, "name": pool_name, "ost": ost_label}
         )
+
+
+class QuotaSoftLimitAlert(AlertStateBase):
+    """Raised by the iml-quota service when users, groups or projects exceed a soft limit.
+
+    The alert message lists the offending ids, limits and usage are stored in the quota_limit and quota_usage tables.
+    """
+
+    default_severity = logging.WARNING
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+    def alert_message(self):
+        return "Quota soft limit exceeded on filesystem %s" % self.alert_item.name
+
+    @property
+    def affected_objects(self):
+        """
+        :return: A list of objects that are affected by this alert
+        """
+        return [self.alert_item]
+
+
+class QuotaGraceExpiredAlert(AlertStateBase):
+    """Raised by the iml-quota service when users, groups or projects stay over a soft limit past the grace period.
+
+    Once the grace period has run out, the soft limit is enforced like a hard limit.
+    """
+
+    default_severity = logging.ERROR
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+    def alert_message(self):
+        return "Quota grace period expired on filesystem %s" % self.alert_item.name
+
+    @property
+    def affected_objects(self):
+        """
+        :return: A list of objects that are affected by this alert
+        """
+        return [self.alert_item]
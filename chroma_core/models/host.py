@@ -7,8 +7,10 @@
 import json
 import logging
 import datetime
+import time
 
 from django.db import models
+from django.contrib.postgres import fields
 from django.db import transaction
 from django.db import IntegrityError
 from django.db.models import CASCADE
@@ -1348,6 +1350,159 @@ class UpdateJob(Job):
         app_label = "chroma_core"
 
 
+class RestartLustreStep(Step):
+    def run(self, kwargs):
+        fqdn = kwargs["fqdn"]
+
+        if kwargs["reboot"]:
+            from chroma_core.services.job_scheduler.agent_rpc import AgentRpc
+
+            old_rust_session_id = self.invoke_rust_local_action_expect_result("get_session", fqdn)
+
+            self.invoke_agent(fqdn, "reboot_server")
+
+            AgentRpc.await_restart(fqdn, kwargs["timeout"])
+            self.invoke_rust_local_action_expect_result(
+                "await_next_session", (fqdn, old_rust_session_id, kwargs["timeout"])
+            )
+        else:
+            self.invoke_rust_agent_expect_result(fqdn, "lustre_modules_reload", None)
+
+
+class RestartLustreJob(Job):
+    fqdn = models.CharField(max_length=256, help_text="Host to restart Lustre on")
+    reboot = models.BooleanField(default=False, help_text="Reboot the host instead of reloading the Lustre modules")
+
+    @classmethod
+    def long_description(cls, stateful_object):
+        return help_text["restart_lustre"]
+
+    def description(self):
+        if self.reboot:
+            return "Reboot %s" % self.fqdn
+
+        return "Reload Lustre modules on %s" % self.fqdn
+
+    def get_steps(self):
+        return [
+            (
+                RestartLustreStep,
+                {"fqdn": self.fqdn, "reboot": self.reboot, "timeout": settings.INSTALLATION_REBOOT_TIMEOUT},
+            )
+        ]
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+
+class VerifyLustreVersionStep(Step):
+    idempotent = True
+
+    def run(self, kwargs):
+        fqdn = kwargs["fqdn"]
+
+        if not self.invoke_rust_agent_expect_result(fqdn, "kernel_module_loaded", "lustre"):
+            raise RuntimeError("The lustre module is not loaded on %s" % fqdn)
+
+        module_version = self.invoke_rust_agent_expect_result(fqdn, "kernel_module_version", "lustre")
+        package_version = self.invoke_rust_agent_expect_result(fqdn, "package_version", "lustre")
+
+        if not module_version or module_version != package_version:
+            raise RuntimeError(
+                "Lustre %s is running on %s, but %s is installed" % (module_version, fqdn, package_version)
+            )
+
+        self.log("Lustre %s is running on %s" % (module_version, fqdn))
+
+
+class VerifyLustreVersionJob(Job):
+    fqdn = models.CharField(max_length=256, help_text="Host to check the Lustre version of")
+
+    @classmethod
+    def long_description(cls, stateful_object):
+        return help_text["verify_lustre_version"]
+
+    def description(self):
+        return "Verify Lustre version on %s" % self.fqdn
+
+    def get_steps(self):
+        return [(VerifyLustreVersionStep, {"fqdn": self.fqdn})]
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+
+class CheckUpgradeHealthStep(Step):
+    idempotent = True
+    database = True
+
+    def run(self, kwargs):
+        from chroma_core.models.jobs import Job
+        from chroma_core.models.target import get_host_targets
+
+        hosts = ", ".join(kwargs["hosts"])
+        deadline = time.time() + kwargs["timeout"]
+
+        # Alerts raised while the pair was upgraded count too, not only those raised during this check
+        since = Job.objects.get(id=self.job_id).created_at
+
+        items = list(ManagedHost.objects.filter(fqdn__in=kwargs["hosts"]))
+        items.extend([x for host in list(items) for x in get_host_targets(host.id)])
+
+        while True:
+            alerts = AlertState.objects.none()
+
+            for x in items:
+                alerts = alerts | AlertState.filter_by_item(x)
+
+            alerts = alerts.filter(severity__gte=logging.ERROR, begin__gte=since)
+
+            if not alerts.exists():
+                self.log("No alerts were raised on %s or its targets during the upgrade" % hosts)
+                return
+
+            if time.time() > deadline:
+                self.log(
+                    "Alerts raised on %s or its targets during the upgrade did not clear: %s"
+                    % (hosts, "; ".join(x.message() for x in alerts))
+                )
+                self.log("Pausing the upgrade, the remaining hosts will not be upgraded")
+                self.cancel_remaining_jobs()
+                return
+
+            time.sleep(10)
+
+    def cancel_remaining_jobs(self):
+        from chroma_core.models.command import Command
+        from chroma_core.services.job_scheduler.job_scheduler_client import JobSchedulerClient
+
+        for command in Command.objects.filter(jobs__id=self.job_id):
+            for job in command.jobs.exclude(id=self.job_id).exclude(state="complete"):
+                JobSchedulerClient.cancel_job(job.id)
+
+
+class CheckUpgradeHealthJob(Job):
+    hosts = fields.JSONField(help_text="Hosts of the HA pair being upgraded")
+
+    @classmethod
+    def long_description(cls, stateful_object):
+        return help_text["check_upgrade_health"]
+
+    def description(self):
+        return "Check the health of %s, pausing the upgrade if alerts do not clear" % ", ".join(
+            self.hosts
+        )
+
+    def get_steps(self):
+        return [(CheckUpgradeHealthStep, {"hosts": self.hosts, "timeout": settings.INSTALLATION_REBOOT_TIMEOUT})]
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+
 class ResetConfParamsStep(Step):
     database = True
 
@@ -1457,6 +1612,139 @@ class UpdatesAvailableAlert(AlertStateBase):
         return "Updates are ready for server %s" % self.alert_item
 
 
+class DiskPredictiveFailureAlert(AlertStateBase):
+    """Raised by the iml-disk-health service when a drive on a host reports that it expects to fail.
+
+    The alert message lists the failing drives, drive health itself is stored in the disk_health table.
+    """
+
+    default_severity = logging.ERROR
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+    def alert_message(self):
+        return "Predictive drive failure on server %s" % self.alert_item
+
+    @property
+    def affected_objects(self):
+        """
+        :return: A list of objects that are affected by this alert
+        """
+        return [self.alert_item]
+
+
+class ZpoolDegradedAlert(AlertStateBase):
+    """Raised by the iml-device service when a zpool on a host reports DEGRADED health."""
+
+    default_severity = logging.WARNING
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+    def alert_message(self):
+        return "Zpool degraded on server %s" % self.alert_item
+
+    @property
+    def affected_objects(self):
+        """
+        :return: A list of objects that are affected by this alert
+        """
+        return [self.alert_item]
+
+
+class ZpoolFaultedAlert(AlertStateBase):
+    """Raised by the iml-device service when a zpool on a host reports FAULTED health."""
+
+    default_severity = logging.ERROR
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+    def alert_message(self):
+        return "Zpool faulted on server %s" % self.alert_item
+
+    @property
+    def affected_objects(self):
+        """
+        :return: A list of objects that are affected by this alert
+        """
+        return [self.alert_item]
+
+
+class TunableDriftAlert(AlertStateBase):
+    """Raised by the iml-tunables service when tunables read back from a host differ from their desired values.
+
+    The alert message lists the differing parameters, desired tunables are stored in the tunable table.
+    """
+
+    default_severity = logging.WARNING
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+    def alert_message(self):
+        return "Tunables differ from their desired values on server %s" % self.alert_item
+
+    @property
+    def affected_objects(self):
+        """
+        :return: A list of objects that are affected by this alert
+        """
+        return [self.alert_item]
+
+
+class ChangelogLagAlert(AlertStateBase):
+    """Raised by the iml-changelog service when changelog users on a host have too many uncleared records.
+
+    The threshold is set by CHANGELOG_LAG_THRESHOLD, the alert message lists the users.
+    """
+
+    default_severity = logging.WARNING
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+    def alert_message(self):
+        return "Changelog users are falling behind on server %s" % self.alert_item
+
+    @property
+    def affected_objects(self):
+        """
+        :return: A list of objects that are affected by this alert
+        """
+        return [self.alert_item]
+
+
+class ChangelogUserAbandonedAlert(AlertStateBase):
+    """Raised by the iml-changelog service when changelog users on a host have uncleared records
+    but have not cleared any for CHANGELOG_IDLE_THRESHOLD seconds.
+
+    Records of abandoned users are never purged and eventually fill the MDT.
+    """
+
+    default_severity = logging.ERROR
+
+    class Meta:
+        app_label = "chroma_core"
+        proxy = True
+
+    def alert_message(self):
+        return "Changelog users have stopped clearing records on server %s" % self.alert_item
+
+    @property
+    def affected_objects(self):
+        """
+        :return: A list of objects that are affected by this alert
+        """
+        return [self.alert_item]
+
+
 class NoNidsPresent(Exception):
     pass
 
@@ -1615,3 +1903,51 @@ class UnmountSnapshotJob(Job):
     class Meta:
         app_label = "chroma_core"
         ordering = ["id"]
+
+
+class StartZpoolScrubJob(Job):
+    fqdn = models.CharField(max_length=256, help_text="Host the zpool is imported on")
+    zpool = models.CharField(max_length=256, help_text="Zpool to scrub")
+
+    @classmethod
+    def long_description(cls, stateful_object):
+        return help_text["start_zpool_scrub"]
+
+    def description(self):
+        return "Start scrub of zpool '{}' on '{}'".format(self.zpool, self.fqdn)
+
+    def get_steps(self):
+        return [(StartZpoolScrubStep, {"host": self.fqdn, "zpool": self.zpool})]
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+
+class StartZpoolScrubStep(Step):
+    def run(self, kwargs):
+        self.invoke_rust_agent_expect_result(kwargs["host"], "zpool_scrub_start", kwargs["zpool"])
+
+
+class StopZpoolScrubJob(Job):
+    fqdn = models.CharField(max_length=256, help_text="Host the zpool is imported on")
+    zpool = models.CharField(max_length=256, help_text="Zpool to stop scrubbing")
+
+    @classmethod
+    def long_description(cls, stateful_object):
+        return help_text["stop_zpool_scrub"]
+
+    def description(self):
+        return "Stop scrub of zpool '{}' on '{}'".format(self.zpool, self.fqdn)
+
+    def get_steps(self):
+        return [(StopZpoolScrubStep, {"host": self.fqdn, "zpool": self.zpool})]
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+
+class StopZpoolScrubStep(Step):
+    def run(self, kwargs):
+        self.invoke_rust_agent_expect_result(kwargs["host"], "zpool_scrub_stop", kwargs["zpool"])
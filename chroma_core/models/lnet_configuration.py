@@ -516,3 +516,182 @@ class GetLNetStateJob(Job):
 
     def description(self):
         return "Get LNet state for %s" % self.host
+
+
+class AddLNetNetworkStep(Step):
+    def run(self, kwargs):
+        self.invoke_rust_agent_expect_result(
+            kwargs["fqdn"],
+            "lnet_net_add",
+            {
+                "net": kwargs["net"],
+                "interfaces": kwargs["interfaces"],
+                "peer_credits": kwargs["peer_credits"],
+                "credits": kwargs["credits"],
+            },
+        )
+
+
+class AddLNetNetworkJob(Job):
+    fqdn = models.CharField(max_length=256, help_text="Host to configure the LNet network on")
+    net = models.CharField(max_length=64, help_text="LNet network, i.e. tcp0")
+    interfaces = models.CharField(max_length=512, help_text="Comma separated interfaces to add the network on")
+    peer_credits = models.IntegerField(null=True, help_text="Peer credits of the network")
+    credits = models.IntegerField(null=True, help_text="Credits of the network")
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls, stateful_object):
+        return help_text["add_lnet_network"]
+
+    def description(self):
+        return "Add LNet network '{}' on '{}' ({})".format(self.net, self.fqdn, self.interfaces)
+
+    def get_steps(self):
+        return [
+            (
+                AddLNetNetworkStep,
+                {
+                    "fqdn": self.fqdn,
+                    "net": self.net,
+                    "interfaces": self.interfaces.split(","),
+                    "peer_credits": self.peer_credits,
+                    "credits": self.credits,
+                },
+            )
+        ]
+
+
+class RemoveLNetNetworkStep(Step):
+    def run(self, kwargs):
+        self.invoke_rust_agent_expect_result(
+            kwargs["fqdn"], "lnet_net_del", {"net": kwargs["net"], "interfaces": kwargs["interfaces"]}
+        )
+
+
+class RemoveLNetNetworkJob(Job):
+    fqdn = models.CharField(max_length=256, help_text="Host to remove the LNet network from")
+    net = models.CharField(max_length=64, help_text="LNet network, i.e. tcp0")
+    interfaces = models.CharField(
+        max_length=512, blank=True, help_text="Comma separated interfaces to remove. All if empty"
+    )
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls, stateful_object):
+        return help_text["remove_lnet_network"]
+
+    def description(self):
+        return "Remove LNet network '{}' from '{}'".format(self.net, self.fqdn)
+
+    def get_steps(self):
+        return [
+            (
+                RemoveLNetNetworkStep,
+                {"fqdn": self.fqdn, "net": self.net, "interfaces": [x for x in self.interfaces.split(",") if x]},
+            )
+        ]
+
+
+class AddLNetRouteStep(Step):
+    def run(self, kwargs):
+        self.invoke_rust_agent_expect_result(
+            kwargs["fqdn"],
+            "lnet_route_add",
+            {
+                "net": kwargs["net"],
+                "gateway": kwargs["gateway"],
+                "hop": kwargs["hop"],
+                "priority": kwargs["priority"],
+            },
+        )
+
+
+class AddLNetRouteJob(Job):
+    fqdn = models.CharField(max_length=256, help_text="Host to add the LNet route on")
+    net = models.CharField(max_length=64, help_text="Remote LNet network")
+    gateway = models.CharField(max_length=256, help_text="NID of the router")
+    hop = models.IntegerField(null=True, help_text="Number of hops to the remote network")
+    priority = models.IntegerField(null=True, help_text="Priority of the route")
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls, stateful_object):
+        return help_text["add_lnet_route"]
+
+    def description(self):
+        return "Add LNet route to '{}' via '{}' on '{}'".format(self.net, self.gateway, self.fqdn)
+
+    def get_steps(self):
+        return [
+            (
+                AddLNetRouteStep,
+                {
+                    "fqdn": self.fqdn,
+                    "net": self.net,
+                    "gateway": self.gateway,
+                    "hop": self.hop,
+                    "priority": self.priority,
+                },
+            )
+        ]
+
+
+class RemoveLNetRouteStep(Step):
+    def run(self, kwargs):
+        self.invoke_rust_agent_expect_result(
+            kwargs["fqdn"], "lnet_route_del", {"net": kwargs["net"], "gateway": kwargs["gateway"]}
+        )
+
+
+class RemoveLNetRouteJob(Job):
+    fqdn = models.CharField(max_length=256, help_text="Host to remove the LNet route from")
+    net = models.CharField(max_length=64, help_text="Remote LNet network")
+    gateway = models.CharField(max_length=256, help_text="NID of the router")
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls, stateful_object):
+        return help_text["remove_lnet_route"]
+
+    def description(self):
+        return "Remove LNet route to '{}' via '{}' on '{}'".format(self.net, self.gateway, self.fqdn)
+
+    def get_steps(self):
+        return [(RemoveLNetRouteStep, {"fqdn": self.fqdn, "net": self.net, "gateway": self.gateway})]
+
+
+class ImportLNetConfigStep(Step):
+    def run(self, kwargs):
+        self.invoke_rust_agent_expect_result(kwargs["fqdn"], "lnet_import", kwargs["config"])
+
+
+class ImportLNetConfigJob(Job):
+    fqdn = models.CharField(max_length=256, help_text="Host to import the LNet configuration on")
+    config = models.TextField(help_text="LNet configuration in lnetctl YAML format")
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls, stateful_object):
+        return help_text["import_lnet_config"]
+
+    def description(self):
+        return "Import LNet configuration on '{}'".format(self.fqdn)
+
+    def get_steps(self):
+        return [(ImportLNetConfigStep, {"fqdn": self.fqdn, "config": self.config})]
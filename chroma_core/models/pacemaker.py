@@ -14,6 +14,7 @@ from chroma_core.models import StateChangeJob
 from chroma_core.models import Job
 from chroma_core.models import SchedulingError
 from chroma_core.models import StateLock
+from chroma_core.models.sparse_model import VariantDescriptor
 from chroma_core.lib.job import DependOn, DependAll, Step
 from chroma_help.help import help_text
 
@@ -67,11 +68,17 @@ class PacemakerConfiguration(DeletableStatefulObject):
 class StonithNotEnabledAlert(AlertStateBase):
     default_severity = logging.ERROR
 
+    # The cluster nodes no fence device is able to fence, if stonith is enabled
+    variant_fields = [VariantDescriptor("reason", str, None, None, "")]
+
     class Meta:
         app_label = "chroma_core"
         proxy = True
 
     def alert_message(self):
+        if self.reason:
+            return help_text["stonith_nodes_not_covered"] % (self.alert_item, self.reason)
+
         return help_text["stonith_not_enabled"] % self.alert_item
 
     def end_event(self):
@@ -5,13 +5,15 @@
 
 import json
 import logging
+import time
 from chroma_core.lib.cache import ObjectCache
 from django.db import models, transaction
 from django.db.models import CASCADE
+from django.contrib.postgres import fields
 from chroma_core.lib.job import DependOn, DependAny, DependAll, Step, job_log
 from chroma_core.models.event import AlertEvent
 from chroma_core.models.alert import AlertStateBase
-from chroma_core.models.jobs import StateChangeJob, StateLock, AdvertisedJob
+from chroma_core.models.jobs import StateChangeJob, StateLock, AdvertisedJob, Job
 from chroma_core.models.host import ManagedHost, HostContactAlert
 from chroma_core.models import StatefulObject
 from chroma_core.models.pacemaker import PacemakerConfiguration
@@ -707,6 +709,283 @@ class FailoverTargetJob(MigrateTargetJob):
         return """Forcibly migrate the target to its failover server. Clients attempting to access data on the target while the migration is occurring may experience delays until the migration completes."""
 
 
+class FailoverHostResourceStep(Step):
+    idempotent = True
+
+    def run(self, kwargs):
+        self.invoke_rust_agent_expect_result(
+            kwargs["fqdn"], "ha_resource_ban", [kwargs["ha_label"], kwargs["node_name"]]
+        )
+
+
+class FailoverHostResourceJob(Job):
+    fqdn = models.CharField(max_length=256, help_text="Host to run the failover from")
+    ha_label = models.CharField(max_length=512, help_text="Resource to move")
+    node_name = models.CharField(max_length=256, help_text="Node to move the resource away from")
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls, stateful_object):
+        return help_text["failover_host_resource"]
+
+    def description(self):
+        return "Failover %s away from %s" % (self.ha_label, self.node_name)
+
+    def get_steps(self):
+        return [
+            (
+                FailoverHostResourceStep,
+                {"fqdn": self.fqdn, "ha_label": self.ha_label, "node_name": self.node_name},
+            )
+        ]
+
+
+class FailbackHostResourceStep(Step):
+    idempotent = True
+
+    def run(self, kwargs):
+        args = [kwargs["ha_label"], kwargs["node_name"]]
+
+        self.invoke_rust_agent_expect_result(kwargs["fqdn"], "ha_resource_clear", args)
+        self.invoke_rust_agent_expect_result(kwargs["fqdn"], "ha_resource_move", args)
+
+
+class FailbackHostResourceJob(Job):
+    fqdn = models.CharField(max_length=256, help_text="Host to run the failback from")
+    ha_label = models.CharField(max_length=512, help_text="Resource to move")
+    node_name = models.CharField(max_length=256, help_text="Node to move the resource back to")
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls, stateful_object):
+        return help_text["failback_host_resource"]
+
+    def description(self):
+        return "Failback %s to %s" % (self.ha_label, self.node_name)
+
+    def get_steps(self):
+        return [
+            (
+                FailbackHostResourceStep,
+                {"fqdn": self.fqdn, "ha_label": self.ha_label, "node_name": self.node_name},
+            )
+        ]
+
+
+class ClearHostResourceBanStep(Step):
+    idempotent = True
+
+    def run(self, kwargs):
+        self.invoke_rust_agent_expect_result(
+            kwargs["fqdn"], "ha_resource_clear", [kwargs["ha_label"], kwargs["node_name"]]
+        )
+
+
+class ClearHostResourceBanJob(Job):
+    fqdn = models.CharField(max_length=256, help_text="Host to clear the ban from")
+    ha_label = models.CharField(max_length=512, help_text="Resource to clear the ban of")
+    node_name = models.CharField(max_length=256, help_text="Node the resource was banned from")
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls, stateful_object):
+        return help_text["clear_host_resource_ban"]
+
+    def description(self):
+        return "Clear ban of %s from %s" % (self.ha_label, self.node_name)
+
+    def get_steps(self):
+        return [
+            (
+                ClearHostResourceBanStep,
+                {"fqdn": self.fqdn, "ha_label": self.ha_label, "node_name": self.node_name},
+            )
+        ]
+
+
+class UpgradeFailoverJob(Job):
+    fqdn = models.CharField(max_length=256, help_text="Host to move target resources away from")
+    node_name = models.CharField(max_length=256, help_text="Corosync node name of the host")
+    moved = fields.JSONField(default=list, help_text="Target resources failed over when the job ran")
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls, stateful_object):
+        return help_text["upgrade_failover"]
+
+    def description(self):
+        return "Failover target resources away from %s" % self.fqdn
+
+    def get_steps(self):
+        from chroma_core.lib.graphql import get_failover_resources
+
+        # Resolved now rather than when the upgrade was requested, as resources may have moved since
+        self.moved = get_failover_resources(fqdn=self.fqdn)
+        self.save()
+
+        return [
+            (
+                FailoverHostResourceStep,
+                {"fqdn": x["peer"], "ha_label": x["ha_label"], "node_name": self.node_name},
+            )
+            for x in self.moved
+        ]
+
+
+class UpgradeFailbackJob(Job):
+    fqdn = models.CharField(max_length=256, help_text="Host to move target resources back to")
+    node_name = models.CharField(max_length=256, help_text="Corosync node name of the host")
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls, stateful_object):
+        return help_text["upgrade_failback"]
+
+    def description(self):
+        return "Failback target resources to %s" % self.fqdn
+
+    def get_steps(self):
+        from chroma_core.models.command import Command
+
+        job_ids = Command.objects.filter(jobs__id=self.id).values_list("jobs__id", flat=True)
+
+        steps = []
+
+        for job in UpgradeFailoverJob.objects.filter(id__in=job_ids, fqdn=self.fqdn):
+            for x in job.moved:
+                step = FailbackHostResourceStep if x["failback"] else ClearHostResourceBanStep
+
+                steps.append((step, {"fqdn": self.fqdn, "ha_label": x["ha_label"], "node_name": self.node_name}))
+
+        return steps
+
+
+class FormatLustreTargetStep(Step):
+    def run(self, kwargs):
+        self.invoke_rust_agent_expect_result(kwargs["fqdn"], "target_format", kwargs["config"])
+
+
+class FormatLustreTargetJob(Job):
+    fqdn = models.CharField(max_length=256, help_text="Host to format the target on")
+    label = models.CharField(max_length=64, help_text="Name of the target")
+    config = fields.JSONField(help_text="Arguments of the target_format action")
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls, stateful_object):
+        return help_text["format_lustre_target"]
+
+    def description(self):
+        return "Format %s on %s" % (self.label, self.fqdn)
+
+    def get_steps(self):
+        return [(FormatLustreTargetStep, {"fqdn": self.fqdn, "config": self.config})]
+
+
+class CreateTargetMountpointStep(Step):
+    idempotent = True
+
+    def run(self, kwargs):
+        self.invoke_rust_agent_expect_result(kwargs["fqdn"], "target_mountpoint_create", kwargs["mountpoint"])
+
+
+class CreateTargetResourceStep(Step):
+    idempotent = True
+
+    def run(self, kwargs):
+        for resource in kwargs["resources"]:
+            self.invoke_rust_agent_expect_result(kwargs["fqdn"], "ha_resource_create", resource)
+
+        self.invoke_rust_agent_expect_result(kwargs["fqdn"], "ha_resource_start", kwargs["ha_label"])
+
+
+class CreateTargetResourceJob(Job):
+    fqdn = models.CharField(max_length=256, help_text="Host to create the resources from")
+    ha_label = models.CharField(max_length=512, help_text="Resource of the target")
+    mountpoint = models.CharField(max_length=512, help_text="Where the target is mounted")
+    hosts = fields.JSONField(help_text="Hosts the target can run on")
+    resources = fields.JSONField(help_text="Resources to create with their constraints, in order")
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls, stateful_object):
+        return help_text["create_target_resource"]
+
+    def description(self):
+        return "Create resource %s" % self.ha_label
+
+    def get_steps(self):
+        steps = [(CreateTargetMountpointStep, {"fqdn": x, "mountpoint": self.mountpoint}) for x in self.hosts]
+
+        steps.append(
+            (CreateTargetResourceStep, {"fqdn": self.fqdn, "ha_label": self.ha_label, "resources": self.resources})
+        )
+
+        return steps
+
+
+class DetectFilesystemStep(Step):
+    def run(self, kwargs):
+        from chroma_core.lib.graphql import detect_filesystems, get_targets
+
+        fs_name = kwargs["fs_name"]
+        deadline = time.time() + settings.INSTALLATION_REBOOT_TIMEOUT
+
+        while True:
+            mounted = set(x["name"] for x in get_targets(fsname=fs_name) if x["state"] == "mounted")
+            missing = [x for x in kwargs["targets"] if x not in mounted]
+
+            if not missing:
+                break
+
+            if time.time() > deadline:
+                raise RuntimeError("Targets of %s did not start: %s" % (fs_name, ", ".join(missing)))
+
+            time.sleep(5)
+
+        detect_filesystems()
+
+
+class DetectFilesystemJob(Job):
+    fs_name = models.CharField(max_length=8, help_text="Filesystem to detect")
+    targets = fields.JSONField(help_text="Targets to wait for before detecting the filesystem")
+
+    class Meta:
+        app_label = "chroma_core"
+        ordering = ["id"]
+
+    @classmethod
+    def long_description(cls, stateful_object):
+        return help_text["detect_filesystem"]
+
+    def description(self):
+        return "Detect filesystem %s" % self.fs_name
+
+    def get_steps(self):
+        return [(DetectFilesystemStep, {"fs_name": self.fs_name, "targets": self.targets})]
+
+
 class TargetOfflineAlert(AlertStateBase):
     # When a target is offline, some or all files in the filesystem are inaccessible,
     # therefore the filesystem is considered not fully available, therefore it's ERROR.
@@ -228,7 +228,7 @@ class Linux(Plugin):
                 else:
                     return None
 
-            for expected_item in ["vgs", "lvs", "zfspools", "zfsdatasets", "devs", "local_fs", "mds", "mpath"]:
+            for expected_item in ["vgs", "lvs", "zfspools", "zfsdatasets", "devs", "local_fs", "mds", "mpath", "dms"]:
                 if expected_item not in devices.keys():
                     devices[expected_item] = {}
 
@@ -262,7 +262,12 @@ class Linux(Plugin):
             for mp_name, mp in devices["mpath"].items():
                 mpath_block_devices.add(mp["block_device"])
 
-            special_block_devices = lv_block_devices | mpath_block_devices
+            # dm-crypt, dm-integrity and VDO mappings
+            dm_block_devices = set()
+            for dm_name, dm in devices["dms"].items():
+                dm_block_devices.add(dm["block_device"])
+
+            special_block_devices = lv_block_devices | mpath_block_devices | dm_block_devices
 
             for uuid, md_info in devices["mds"].items():
                 special_block_devices.add(md_info["block_device"])
@@ -366,6 +371,8 @@ class Linux(Plugin):
                     node, created = self.update_or_create(LinuxDeviceNode, host_id=host_id, path=bdev["path"])
                 elif bdev["major_minor"] in mpath_block_devices:
                     node, created = self.update_or_create(LinuxDeviceNode, host_id=host_id, path=bdev["path"])
+                elif bdev["major_minor"] in dm_block_devices:
+                    node, created = self.update_or_create(LinuxDeviceNode, host_id=host_id, path=bdev["path"])
                 elif bdev["parent"]:
                     node, created = self.update_or_create(LinuxDeviceNode, host_id=host_id, path=bdev["path"])
                 else:
@@ -449,6 +456,7 @@ class Linux(Plugin):
 
             # Create Partitions (devices that have 'parent' set)
             partition_identifiers = []
+            partition_by_major_minor = {}
 
             for bdev in [x for x in devices["devs"].values() if x["parent"]]:
                 this_node = self.major_minor_to_node_resource[bdev["major_minor"]]
@@ -471,6 +479,35 @@ class Linux(Plugin):
 
                 this_node.add_parent(partition)
                 partition_identifiers.append(partition.id_tuple())
+                partition_by_major_minor[bdev["major_minor"]] = partition
+
+            # dm mappings can be stacked (e.g. LUKS on dm-integrity), so link them
+            # once their parents have a logical drive.
+            def dm_logical_drive(mm):
+                return partition_by_major_minor.get(mm) or self.major_minor_to_node_resource[mm].logical_drive
+
+            pending = list(devices["dms"].values())
+            while pending:
+                remaining = []
+                for dm in pending:
+                    parent_mms = [p for p in dm["parents"] if p in self.major_minor_to_node_resource]
+                    if any(dm_logical_drive(p) is None for p in parent_mms):
+                        remaining.append(dm)
+                        continue
+
+                    dm_node = self.major_minor_to_node_resource[dm["block_device"]]
+                    # All the parents of a dm device sit on the same logical_drive
+                    if parent_mms:
+                        dm_node.logical_drive = dm_logical_drive(parent_mms[0])
+
+                    for p in parent_mms:
+                        dm_node.add_parent(self.major_minor_to_node_resource[p])
+
+                if len(remaining) == len(pending):
+                    log.warning("Could not resolve parents of dm devices: %s" % [x["name"] for x in remaining])
+                    break
+
+                pending = remaining
 
             # Finally remove any of the partitions that are no longer present.
             initiate_device_poll |= self.remove_missing_devices(host_id, Partition, partition_identifiers)
@@ -5,7 +5,7 @@
 
 from collections import namedtuple, defaultdict
 
-from django.db import transaction
+from django.db import connection, transaction
 from django.db.models import Q
 from django.utils import timezone
 
@@ -20,6 +20,27 @@ from iml_common.lib.date_time import IMLDateTime
 log = log_register(__name__)
 
 
+def get_unfenced_nodes(host):
+    """Return the names of the nodes in host's cluster that no fence device can fence
+
+    Fencing coverage is validated by the agent and stored by the iml-corosync service.
+    """
+    with connection.cursor() as cursor:
+        cursor.execute(
+            """
+            SELECT sn.node FROM corosync_stonith_node sn
+            INNER JOIN corosync_node_managed_host nh
+            ON nh.cluster_id = sn.cluster_id
+            WHERE nh.host_id = %s
+            AND CARDINALITY(sn.devices) = 0
+            ORDER BY sn.node
+            """,
+            [host.id],
+        )
+
+        return [x[0] for x in cursor.fetchall()]
+
+
 class Service(ChromaService):
     """Corosync host offline detection service
 
@@ -132,7 +153,22 @@ class Service(ChromaService):
                 log.warning("Unknown nodes in report from %s: %s" % (fqdn, unknown_nodes))
 
             if stonith_enabled is not None:
-                StonithNotEnabledAlert.notify(host.corosync_configuration, stonith_enabled is False)
+                unfenced = get_unfenced_nodes(host)
+
+                if stonith_enabled is False or not unfenced:
+                    reason = ""
+                else:
+                    reason = ", ".join(unfenced)
+
+                active = stonith_enabled is False or bool(reason)
+
+                # The reason is only recorded when the alert is raised, so lower it first if it no longer applies.
+                if active and any(
+                    a.reason != reason for a in StonithNotEnabledAlert.filter_by_item(host.corosync_configuration)
+                ):
+                    StonithNotEnabledAlert.notify(host.corosync_configuration, False)
+
+                StonithNotEnabledAlert.notify(host.corosync_configuration, active, reason=reason)
 
             CorosyncNoPeersAlert.notify(host.corosync_configuration, len(cluster_nodes) == 1)
             # CorosyncToManyPeersAlert.notify(host.corosync_configuration, len(cluster_nodes) > 2)
@@ -185,9 +185,13 @@ help_text = {
     "format_target": "Format target.",
     "migrate_target": "Migrate target.",
     "failover_target": "Migrate target to another host",
+    "failover_host_resource": "Move a resource away from a host and ban it from returning",
+    "failback_host_resource": "Clear bans on a resource and move it back to its host",
+    "clear_host_resource_ban": "Clear the bans on a resource without moving it",
     "continue_as_anonymous": "Click this link to continue as an anonymous user. This user has restricted privileges on how they can use the Integrated Manager for Lustre software.",
     "stonith_not_enabled": "stonith-enabled is false on %s. This can cause device corruption. Target creation is forbidden in this state. Ensure that stonith-enabled is set to true.",
     "stonith_enabled": "stonith-enabled set to true on %s",
+    "stonith_nodes_not_covered": "No working fence device on %s can fence the nodes: %s. Check the fence device configuration.",
     "creating_ostpool": "Creating OST Pool",
     "destroying_ostpool": "Destroying OST Pool",
     "updating_ostpool": "Updating OST Pool",
@@ -198,4 +202,19 @@ help_text = {
     "unmount_snapshot": "Unmounting Snapshot",
     "create_snapshot": "Create snapshot with the given name",
     "destroy_snapshot": "Destroy existing snapshot",
+    "start_zpool_scrub": "Start a scrub of the zpool",
+    "stop_zpool_scrub": "Stop a running scrub of the zpool",
+    "add_lnet_network": "Add an LNet network on the given interfaces and persist it to /etc/lnet.conf",
+    "remove_lnet_network": "Remove an LNet network and persist the change to /etc/lnet.conf",
+    "add_lnet_route": "Add a route to a remote LNet network and persist it to /etc/lnet.conf",
+    "remove_lnet_route": "Remove a route to a remote LNet network and persist the change to /etc/lnet.conf",
+    "import_lnet_config": "Apply an LNet YAML configuration and persist it to /etc/lnet.conf",
+    "restart_lustre": "Reboot the server or reload the Lustre modules so the updated Lustre is running",
+    "verify_lustre_version": "Check the running Lustre modules match the installed Lustre packages",
+    "check_upgrade_health": "Wait for the alerts raised on an HA pair during its upgrade to clear, or cancel the rest of the upgrade",
+    "upgrade_failover": "Move the target resources running on a host to their failover hosts before it is upgraded",
+    "upgrade_failback": "Move the target resources failed over for an upgrade back to the upgraded host",
+    "format_lustre_target": "Format a device as a Lustre target",
+    "create_target_resource": "Create the HA resources of a Lustre target and start it",
+    "detect_filesystem": "Wait for the targets of a new filesystem to start and detect it",
 }
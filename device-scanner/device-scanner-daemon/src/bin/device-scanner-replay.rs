@@ -0,0 +1,94 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Replays a recording made with `DEVICE_SCANNER_RECORD` and prints the resulting device graph.
+//!
+//! Usage: `device-scanner-replay [--each] <recording>`
+//!
+//! With `--each`, the device graph is printed after every command that changes it,
+//! the same as a `Stream` client of the daemon would see it.
+
+use device_scanner_daemon::{recorder, state};
+use std::io::Write;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut each = false;
+    let mut path = None;
+
+    for x in std::env::args().skip(1) {
+        match x.as_str() {
+            "--each" => each = true,
+            _ => path = Some(x),
+        }
+    }
+
+    let path = path.ok_or("Usage: device-scanner-replay [--each] <recording>")?;
+
+    let records = recorder::parse_records(&std::fs::read_to_string(path)?)?;
+
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+
+    let state = recorder::replay(records, |state| {
+        if each {
+            stdout.write_all(&state::produce_device_graph(state)?)?;
+        }
+
+        Ok(())
+    })?;
+
+    if !each {
+        stdout.write_all(&state::produce_device_graph(&state)?)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use device_scanner_daemon::{recorder, state};
+    use device_types::devices::Device;
+    use insta::assert_debug_snapshot;
+
+    /// Lists the ids of `device` and its descendants, indented by depth,
+    /// along with where each is mounted.
+    fn summarize(device: &Device, depth: usize, xs: &mut Vec<String>) {
+        let id = device
+            .get_id()
+            .map(|x| x.0)
+            .unwrap_or_else(|| "unknown".to_string());
+
+        let x = match device.get_mount() {
+            Some(m) => format!("{}{} on {}", "  ".repeat(depth), id, m.target.0.display()),
+            None => format!("{}{}", "  ".repeat(depth), id),
+        };
+
+        xs.push(x);
+
+        for c in device.children().into_iter().flatten() {
+            summarize(c, depth + 1, xs);
+        }
+    }
+
+    #[test]
+    fn test_replay_recording() {
+        let records =
+            recorder::parse_records(include_str!("../../fixtures/recording.jsonl")).unwrap();
+
+        let mut steps = vec![];
+
+        recorder::replay(records, |x| {
+            let mut xs = vec![];
+
+            summarize(&state::produce_device_tree(x)?, 0, &mut xs);
+
+            steps.push(xs);
+
+            Ok(())
+        })
+        .unwrap();
+
+        assert_debug_snapshot!(steps);
+    }
+}
@@ -2,12 +2,8 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
-use crate::{
-    error,
-    reducers::{mount::update_mount, udev::update_udev, zed::update_zed_events},
-    state,
-};
-use device_types::{state::State, Command};
+use crate::{error, recorder::Recorder, reducers::update_state, state};
+use device_types::{state::State, stream::Publisher, Command};
 use futures::{
     channel::mpsc::UnboundedReceiver, channel::mpsc::UnboundedSender, future::join_all, StreamExt,
     TryStreamExt,
@@ -19,8 +15,12 @@ use tokio::{
 use tokio_util::codec::{FramedRead, LinesCodec};
 
 pub enum WriterCmd {
+    /// A client streaming the full device graph on each change
     Add(UnixStream),
-    Msg(bytes::Bytes),
+    /// A client streaming device graph patches
+    AddPatched(UnixStream),
+    /// The full device graph, and the patch message if anything changed structurally
+    Msg(bytes::Bytes, Option<bytes::Bytes>),
 }
 
 fn is_error(xs: &[Result<(), std::io::Error>], idx: usize) -> bool {
@@ -33,25 +33,41 @@ fn is_error(xs: &[Result<(), std::io::Error>], idx: usize) -> bool {
     }
 }
 
+async fn write_all(writers: Vec<UnixStream>, x: &[u8]) -> Vec<UnixStream> {
+    let mut writers = writers;
+
+    let xs = join_all(writers.iter_mut().map(|writer| writer.write_all(x))).await;
+
+    writers
+        .into_iter()
+        .enumerate()
+        .filter(|(idx, _)| is_error(&xs, *idx))
+        .map(|(_, w)| w)
+        .collect()
+}
+
 pub async fn writer(mut rx: UnboundedReceiver<WriterCmd>) {
     let mut writers = vec![];
+    let mut patch_writers = vec![];
 
     while let Some(cmd) = rx.next().await {
         match cmd {
             WriterCmd::Add(w) => writers.push(w),
-            WriterCmd::Msg(x) => {
+            WriterCmd::AddPatched(w) => patch_writers.push(w),
+            WriterCmd::Msg(x, patch) => {
                 tracing::trace!("Starting write to all clients");
 
-                let xs = join_all(writers.iter_mut().map(|writer| writer.write_all(&x))).await;
+                writers = write_all(writers, &x).await;
 
-                writers = writers
-                    .into_iter()
-                    .enumerate()
-                    .filter(|(idx, _)| is_error(&xs, *idx))
-                    .map(|(_, w)| w)
-                    .collect();
+                if let Some(patch) = patch {
+                    patch_writers = write_all(patch_writers, &patch).await;
+                }
 
-                tracing::trace!("{} clients remain.", writers.len());
+                tracing::trace!(
+                    "{} clients remain, {} patch clients remain.",
+                    writers.len(),
+                    patch_writers.len()
+                );
             }
         }
     }
@@ -60,6 +76,7 @@ pub async fn writer(mut rx: UnboundedReceiver<WriterCmd>) {
 pub async fn reader(
     mut listener: UnixListener,
     tx: UnboundedSender<WriterCmd>,
+    mut recorder: Option<Recorder>,
 ) -> Result<(), error::Error> {
     let mut listener = listener
         .incoming()
@@ -67,6 +84,8 @@ pub async fn reader(
 
     let mut state = State::new();
 
+    let mut publisher = Publisher::new();
+
     while let Some(sock) = listener.try_next().await? {
         let (x, sock) = FramedRead::new(sock, LinesCodec::new()).into_future().await;
 
@@ -77,6 +96,14 @@ pub async fn reader(
 
             tracing::debug!("Incoming Command: {:?}", cmd);
 
+            if let Some(r) = recorder.as_mut() {
+                if let Err(e) = r.record(&cmd).await {
+                    tracing::warn!("Could not record command, recording stopped: {}", e);
+
+                    recorder = None;
+                }
+            }
+
             match cmd {
                 Command::Stream => {
                     let output = state::produce_device_graph(&state)?;
@@ -87,6 +114,15 @@ pub async fn reader(
 
                     continue;
                 }
+                Command::StreamPatches => {
+                    let output = state::to_line(&publisher.snapshot())?;
+
+                    sock.write_all(&output).await?;
+
+                    tx.unbounded_send(WriterCmd::AddPatched(sock))?;
+
+                    continue;
+                }
                 Command::GetMounts => {
                     let v = serde_json::to_string(&state.local_mounts)?;
                     let b = bytes::BytesMut::from((v + "\n").as_str());
@@ -98,26 +134,23 @@ pub async fn reader(
 
                     continue;
                 }
-                Command::UdevCommand(x) => {
+                Command::UdevCommand(_) | Command::MountCommand(_) | Command::PoolCommand(_) => {
                     sock.shutdown(std::net::Shutdown::Both)?;
 
-                    state.uevents = update_udev(&state.uevents, x);
+                    state = update_state(state, cmd)?;
                 }
-                Command::MountCommand(x) => {
-                    sock.shutdown(std::net::Shutdown::Both)?;
+            };
 
-                    state.local_mounts = update_mount(state.local_mounts, x);
-                }
-                Command::PoolCommand(x) => {
-                    sock.shutdown(std::net::Shutdown::Both)?;
+            let root = state::produce_device_tree(&state)?;
 
-                    state.zed_events = update_zed_events(state.zed_events, x)?
-                }
-            };
+            let output = state::to_line(&(&root, &state.local_mounts))?;
 
-            let output = state::produce_device_graph(&state)?;
+            let patch = publisher
+                .update(root, state.local_mounts.clone())
+                .map(|x| state::to_line(&x))
+                .transpose()?;
 
-            tx.unbounded_send(WriterCmd::Msg(output))?;
+            tx.unbounded_send(WriterCmd::Msg(output, patch))?;
 
             tracing::debug!("sent new output");
         }
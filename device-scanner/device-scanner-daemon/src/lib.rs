@@ -4,5 +4,6 @@
 
 pub mod daemon;
 pub mod error;
+pub mod recorder;
 pub mod reducers;
 pub mod state;
@@ -2,7 +2,7 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
-use device_scanner_daemon::daemon;
+use device_scanner_daemon::{daemon, recorder::Recorder};
 use futures::channel::mpsc;
 use std::{
     convert::TryFrom,
@@ -25,11 +25,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let listener = UnixListener::try_from(addr)?;
 
+    let recorder = Recorder::from_env().await?;
+
     let (tx, rx) = mpsc::unbounded();
 
     tokio::spawn(daemon::writer(rx));
 
-    daemon::reader(listener, tx).await?;
+    daemon::reader(listener, tx, recorder).await?;
 
     Ok(())
 }
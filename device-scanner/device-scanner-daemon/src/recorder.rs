@@ -0,0 +1,223 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Opt-in recording of the `Command`s received by the daemon.
+//!
+//! When `DEVICE_SCANNER_RECORD` is set to a file path, each incoming `Command` that changes
+//! state is appended to that file as a line of JSON along with the time it was received.
+//! Read-only commands, such as a client starting a stream, are not recorded.
+//! A recording can be fed back through the reducers with `device-scanner-replay`
+//! to rebuild the device graph of the host it was taken on.
+
+use crate::{
+    error::Result,
+    reducers::{is_read_only, update_state},
+    state,
+};
+use device_types::{state::State, Command};
+use std::{
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    fs::{File, OpenOptions},
+    io::AsyncWriteExt,
+};
+
+/// Env var holding the path to record incoming commands to.
+pub static RECORD_PATH_VAR: &str = "DEVICE_SCANNER_RECORD";
+
+/// A single recorded `Command`.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Record<T> {
+    /// Milliseconds since the epoch when the command was received
+    pub time: u64,
+    pub cmd: T,
+}
+
+pub struct Recorder(File);
+
+impl Recorder {
+    /// Starts a new recorder if `DEVICE_SCANNER_RECORD` is set.
+    pub async fn from_env() -> Result<Option<Self>> {
+        match std::env::var(RECORD_PATH_VAR) {
+            Ok(path) if !path.is_empty() => Ok(Some(Self::open(path).await?)),
+            _ => Ok(None),
+        }
+    }
+    /// Opens `path` for recording. An existing recording is appended to.
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+
+        tracing::info!("Recording commands to {}", path.display());
+
+        Ok(Recorder(file))
+    }
+    /// Appends `cmd` to the recording, unless it is read-only.
+    pub async fn record(&mut self, cmd: &Command) -> Result<()> {
+        if is_read_only(cmd) {
+            return Ok(());
+        }
+
+        let x = state::to_line(&Record {
+            time: now_millis(),
+            cmd,
+        })?;
+
+        self.0.write_all(&x).await?;
+
+        Ok(())
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Parses a recording, one `Record` per line. Blank lines are skipped.
+pub fn parse_records(x: &str) -> Result<Vec<Record<Command>>> {
+    x.lines()
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(|x| serde_json::from_str(x).map_err(Into::into))
+        .collect()
+}
+
+/// Feeds `records` through the reducers in order, calling `f` with the
+/// `State` after each command that changes it.
+pub fn replay(
+    records: Vec<Record<Command>>,
+    mut f: impl FnMut(&State) -> Result<()>,
+) -> Result<State> {
+    records.into_iter().try_fold(State::new(), |state, x| {
+        // Recordings made before read-only commands were skipped may still contain them.
+        let changes_state = !is_read_only(&x.cmd);
+
+        let state = update_state(state, x.cmd)?;
+
+        if changes_state {
+            f(&state)?;
+        }
+
+        Ok(state)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use device_types::{
+        devices::Device,
+        mount::{FsType, MountCommand, MountOpts, MountPoint},
+        udev::UdevCommand,
+        uevent::UEvent,
+        DevicePath,
+    };
+    use im::{ordset, vector};
+
+    fn uevent() -> UEvent {
+        UEvent {
+            major: "8".to_string(),
+            minor: "0".to_string(),
+            seqnum: 1205,
+            paths: ordset![
+                "/dev/sda".into(),
+                "/dev/disk/by-id/scsi-36001405943dd5f394fb4b5ba71ec818f".into()
+            ],
+            devname: "/dev/sda".into(),
+            devpath: "/devices/platform/host2/session1/target2:0:0/2:0:0:0/block/sda".into(),
+            devtype: "disk".to_string(),
+            vendor: Some("LIO-ORG".to_string()),
+            model: Some("mdt1".to_string()),
+            serial: Some("36001405943dd5f394fb4b5ba71ec818f".to_string()),
+            fs_type: Some("ext4".to_string()),
+            fs_usage: Some("filesystem".to_string()),
+            fs_uuid: Some("b4550256-cf48-4013-8363-bfee5f52da12".to_string()),
+            fs_label: None,
+            part_entry_number: None,
+            part_entry_mm: None,
+            size: Some(5_368_709_120),
+            rotational: Some(true),
+            scsi80: Some("SLIO-ORG mdt1".to_string()),
+            scsi83: Some("36001405943dd5f394fb4b5ba71ec818f".to_string()),
+            read_only: Some(false),
+            bios_boot: None,
+            zfs_reserved: None,
+            is_mpath: None,
+            dm_slave_mms: vector![],
+            dm_vg_size: None,
+            md_devs: ordset![],
+            dm_multipath_devpath: None,
+            dm_name: None,
+            dm_uuid: None,
+            dm_lv_name: None,
+            lv_uuid: None,
+            dm_vg_name: None,
+            vg_uuid: None,
+            md_uuid: None,
+            nvme_nsid: None,
+            nvme_wwid: None,
+            nvme_subsysnqn: None,
+            nvme_controllers: ordset![],
+        }
+    }
+
+    #[test]
+    fn test_replay() {
+        let cmds = vec![
+            Command::UdevCommand(UdevCommand::Add(uevent())),
+            Command::Stream,
+            Command::MountCommand(MountCommand::AddMount(
+                MountPoint("/mnt/mdt1".into()),
+                DevicePath("/dev/sda".into()),
+                FsType("ext4".to_string()),
+                MountOpts("rw,relatime".to_string()),
+            )),
+        ];
+
+        let recording: String = cmds
+            .into_iter()
+            .enumerate()
+            .map(|(i, cmd)| {
+                serde_json::to_string(&Record {
+                    time: 1_608_544_000_000 + i as u64,
+                    cmd,
+                })
+                .unwrap()
+                    + "\n"
+            })
+            .collect();
+
+        let records = parse_records(&recording).unwrap();
+
+        assert_eq!(records.len(), 3);
+
+        let mut steps = 0;
+
+        let state = replay(records, |_| {
+            steps += 1;
+
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(steps, 2);
+        assert_eq!(state.local_mounts.len(), 1);
+
+        let root = state::produce_device_tree(&state).unwrap();
+
+        let disk = root.find_device_by_devpath(&DevicePath("/dev/sda".into()));
+
+        assert!(matches!(disk, Some(Device::ScsiDevice(_))));
+    }
+}
@@ -5,3 +5,35 @@
 pub mod mount;
 pub mod udev;
 pub mod zed;
+
+use crate::error::Result;
+use device_types::{state::State, Command};
+
+/// Returns true for commands that only read state, such as the streaming commands.
+pub fn is_read_only(cmd: &Command) -> bool {
+    match cmd {
+        Command::Stream | Command::StreamPatches | Command::GetMounts => true,
+        Command::UdevCommand(_) | Command::MountCommand(_) | Command::PoolCommand(_) => false,
+    }
+}
+
+/// Updates `state` in response to a `Command`.
+///
+/// Commands that only read state are ignored, and `state` is returned unchanged.
+pub fn update_state(mut state: State, cmd: Command) -> Result<State> {
+    match cmd {
+        Command::UdevCommand(x) => {
+            state.uevents = udev::update_udev(&state.uevents, x);
+        }
+        Command::MountCommand(x) => {
+            state.local_mounts = mount::update_mount(state.local_mounts, x);
+        }
+        Command::PoolCommand(x) => {
+            state.zpool_statuses = zed::update_zpool_statuses(state.zpool_statuses, &x)?;
+            state.zed_events = zed::update_zed_events(state.zed_events, x)?;
+        }
+        Command::Stream | Command::StreamPatches | Command::GetMounts => {}
+    };
+
+    Ok(state)
+}
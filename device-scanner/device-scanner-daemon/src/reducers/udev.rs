@@ -59,11 +59,16 @@ mod tests {
             md_devs: ordset![],
             dm_multipath_devpath: None,
             dm_name: Some("mpathd1".to_string()),
+            dm_uuid: Some("part1-mpath-3600140550e41a841db244a992c31e7df".to_string()),
             dm_lv_name: None,
             lv_uuid: None,
             dm_vg_name: None,
             vg_uuid: None,
             md_uuid: None,
+            nvme_nsid: None,
+            nvme_wwid: None,
+            nvme_subsysnqn: None,
+            nvme_controllers: ordset![],
         };
 
         let mut ev2 = ev.clone();
@@ -106,5 +106,110 @@ pub fn update_zed_events(
 
             Ok(zed_events)
         }
+        PoolCommand::SetVdevErrors(..) | PoolCommand::SetScan(..) => Ok(zed_events),
+    }
+}
+
+/// Updates the pool status portion of the device map in response to `PoolCommand`s.
+pub fn update_zpool_statuses(
+    mut statuses: state::ZpoolStatuses,
+    cmd: &PoolCommand,
+) -> Result<state::ZpoolStatuses> {
+    match cmd {
+        PoolCommand::AddPools(pools) => {
+            statuses.retain(|guid, _| pools.iter().any(|p| p.guid == *guid));
+
+            Ok(statuses)
+        }
+        PoolCommand::RemovePool(guid) => {
+            let guid = guid_to_u64(guid.clone())?;
+
+            statuses.remove(&guid);
+
+            Ok(statuses)
+        }
+        PoolCommand::SetVdevErrors(guid, vdev_guid, errors) => {
+            let guid = guid_to_u64(guid.clone())?;
+            let vdev_guid: result::Result<u64, std::num::ParseIntError> = vdev_guid.clone().into();
+
+            statuses
+                .entry(guid)
+                .or_default()
+                .vdev_errors
+                .insert(vdev_guid?, *errors);
+
+            Ok(statuses)
+        }
+        PoolCommand::SetScan(guid, scan) => {
+            let guid = guid_to_u64(guid.clone())?;
+
+            statuses.entry(guid).or_default().scan = Some(scan.clone());
+
+            Ok(statuses)
+        }
+        _ => Ok(statuses),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::update_zpool_statuses;
+    use device_types::{
+        state,
+        zed::{vdev, zpool, PoolCommand},
+    };
+
+    #[test]
+    fn test_update_zpool_statuses() {
+        let statuses = state::ZpoolStatuses::new();
+
+        let statuses = update_zpool_statuses(
+            statuses,
+            &PoolCommand::SetVdevErrors(
+                zpool::Guid("0x2D28F440E514007F".to_string()),
+                vdev::Guid("0x7B0E2A0C3E1C91B4".to_string()),
+                vdev::Errors {
+                    read: 0,
+                    write: 0,
+                    checksum: 3,
+                },
+            ),
+        )
+        .unwrap();
+
+        let statuses = update_zpool_statuses(
+            statuses,
+            &PoolCommand::SetScan(
+                zpool::Guid("0x2D28F440E514007F".to_string()),
+                zpool::Scan {
+                    kind: zpool::ScanKind::Scrub,
+                    state: zpool::ScanState::Scanning,
+                    time: 1_608_287_340,
+                },
+            ),
+        )
+        .unwrap();
+
+        let status = statuses.get(&0x2D28_F440_E514_007F).unwrap();
+
+        assert_eq!(
+            status
+                .vdev_errors
+                .get(&0x7B0E_2A0C_3E1C_91B4)
+                .map(|x| x.checksum),
+            Some(3)
+        );
+        assert_eq!(
+            status.scan.as_ref().map(|x| x.state),
+            Some(zpool::ScanState::Scanning)
+        );
+
+        let statuses = update_zpool_statuses(
+            statuses,
+            &PoolCommand::RemovePool(zpool::Guid("0x2D28F440E514007F".to_string())),
+        )
+        .unwrap();
+
+        assert!(statuses.is_empty());
     }
 }
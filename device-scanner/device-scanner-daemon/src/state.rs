@@ -10,10 +10,10 @@
 use crate::error::{self, Result};
 use device_types::{
     devices::{
-        Dataset, Device, LogicalVolume, MdRaid, Mpath, Partition, Root, ScsiDevice, VolumeGroup,
-        Zpool,
+        Dataset, Device, DmDevice, DmKind, LogicalVolume, MdRaid, Mpath, NvmeNamespace, Partition,
+        Root, ScsiDevice, VolumeGroup, Zpool,
     },
-    get_vdev_paths,
+    get_vdev_health, get_vdev_paths,
     mount::Mount,
     state,
     uevent::UEvent,
@@ -36,6 +36,13 @@ fn is_dm(x: &UEvent) -> bool {
         .all(|x| x.is_some())
 }
 
+fn is_dm_virtual(x: &UEvent) -> bool {
+    x.dm_uuid
+        .as_deref()
+        .and_then(DmKind::from_dm_uuid)
+        .is_some()
+}
+
 fn is_partition(x: &UEvent) -> bool {
     x.part_entry_mm.is_some()
 }
@@ -44,6 +51,10 @@ fn is_mdraid(x: &UEvent) -> bool {
     x.md_uuid.is_some()
 }
 
+fn is_nvme(x: &UEvent) -> bool {
+    x.nvme_subsysnqn.is_some()
+}
+
 fn format_major_minor(major: &str, minor: &str) -> String {
     format!("{}:{}", major, minor)
 }
@@ -96,7 +107,7 @@ fn get_partitions(
             let mount = find_mount(&x.paths, ys);
 
             Ok(Device::Partition(Partition {
-                serial: x.scsi83.clone(),
+                serial: x.scsi83.clone().or_else(|| x.nvme_wwid.clone()),
                 scsi80: x.scsi80.clone(),
                 partition_number: x
                     .part_entry_number
@@ -174,6 +185,37 @@ fn get_scsis(b: &Buckets, ys: &HashSet<Mount>) -> Result<HashSet<Device>> {
         .collect()
 }
 
+fn get_nvmes(b: &Buckets, ys: &HashSet<Mount>) -> Result<HashSet<Device>> {
+    b.nvmes
+        .iter()
+        .map(|x| {
+            let mount = find_mount(&x.paths, ys);
+
+            Ok(Device::NvmeNamespace(NvmeNamespace {
+                wwid: x.nvme_wwid.clone(),
+                subsysnqn: x
+                    .nvme_subsysnqn
+                    .clone()
+                    .ok_or_else(|| error::none_error("Expected nvme_subsysnqn"))?,
+                nsid: x
+                    .nvme_nsid
+                    .ok_or_else(|| error::none_error("Expected nvme_nsid"))?,
+                controllers: x.nvme_controllers.clone(),
+                devpath: x.devpath.clone(),
+                major: x.major.clone(),
+                minor: x.minor.clone(),
+                size: x.size.ok_or_else(|| error::none_error("Expected size"))?,
+                filesystem_type: x.fs_type.clone(),
+                fs_uuid: x.fs_uuid.clone(),
+                fs_label: x.fs_label.clone(),
+                paths: x.paths.clone(),
+                children: ordset![],
+                mount: mount.map(ToOwned::to_owned),
+            }))
+        })
+        .collect()
+}
+
 fn get_mpaths(
     b: &Buckets,
     ys: &HashSet<Mount>,
@@ -208,6 +250,46 @@ fn get_mpaths(
         .collect()
 }
 
+fn get_dm_devices(
+    b: &Buckets,
+    ys: &HashSet<Mount>,
+    major: &str,
+    minor: &str,
+) -> Result<HashSet<Device>> {
+    b.dm_virtuals
+        .iter()
+        .filter(|&x| find_by_major_minor(&x.dm_slave_mms, major, minor))
+        .map(|x| {
+            let mount = find_mount(&x.paths, ys);
+
+            let uuid = x
+                .dm_uuid
+                .clone()
+                .ok_or_else(|| error::none_error("Expected dm_uuid"))?;
+
+            Ok(Device::DmDevice(DmDevice {
+                kind: DmKind::from_dm_uuid(&uuid)
+                    .ok_or_else(|| error::none_error("Unknown dm target"))?,
+                dm_name: x
+                    .dm_name
+                    .clone()
+                    .ok_or_else(|| error::none_error("Expected dm_name"))?,
+                uuid,
+                size: x.size.ok_or_else(|| error::none_error("Expected size"))?,
+                major: x.major.clone(),
+                minor: x.minor.clone(),
+                devpath: x.devpath.clone(),
+                paths: x.paths.clone(),
+                filesystem_type: x.fs_type.clone(),
+                fs_uuid: x.fs_uuid.clone(),
+                fs_label: x.fs_label.clone(),
+                children: ordset![],
+                mount: mount.map(ToOwned::to_owned),
+            }))
+        })
+        .collect()
+}
+
 fn get_mds(
     b: &Buckets,
     ys: &HashSet<Mount>,
@@ -253,6 +335,8 @@ fn get_pools(
         .map(|x| {
             let mount = find_mount(&ordset![x.name.clone().into()], ys);
 
+            let status = b.pool_statuses.get(&x.guid).cloned().unwrap_or_default();
+
             Ok(Device::Zpool(Zpool {
                 guid: x.guid,
                 health: x.health.clone(),
@@ -260,6 +344,8 @@ fn get_pools(
                 mount: mount.map(ToOwned::to_owned),
                 props: x.props.clone(),
                 state: x.state.clone(),
+                vdevs: get_vdev_health(&x.vdev, &status.vdev_errors),
+                scan: status.scan,
                 vdev: x.vdev.clone(),
                 size: x.size.parse()?,
                 children: ordset![],
@@ -301,7 +387,9 @@ fn build_device_graph<'a>(ptr: &mut Device, b: &Buckets<'a>, ys: &HashSet<Mount>
         Device::Root(r) => {
             let ss = get_scsis(&b, &ys)?;
 
-            for mut x in ss {
+            let ns = get_nvmes(&b, &ys)?;
+
+            for mut x in HashSet::unions(vec![ss, ns]) {
                 build_device_graph(&mut x, b, ys)?;
 
                 r.children.insert(x);
@@ -315,6 +403,13 @@ fn build_device_graph<'a>(ptr: &mut Device, b: &Buckets<'a>, ys: &HashSet<Mount>
             minor,
             paths,
             ..
+        })
+        | Device::DmDevice(DmDevice {
+            children,
+            major,
+            minor,
+            paths,
+            ..
         }) => {
             let vs = get_vgs(&b, major, minor)?;
 
@@ -324,7 +419,9 @@ fn build_device_graph<'a>(ptr: &mut Device, b: &Buckets<'a>, ys: &HashSet<Mount>
 
             let pools = get_pools(&b, &ys, &paths)?;
 
-            for mut x in HashSet::unions(vec![vs, ps, mds, pools]) {
+            let dms = get_dm_devices(&b, &ys, major, minor)?;
+
+            for mut x in HashSet::unions(vec![vs, ps, mds, pools, dms]) {
                 build_device_graph(&mut x, b, ys)?;
 
                 children.insert(x);
@@ -339,6 +436,13 @@ fn build_device_graph<'a>(ptr: &mut Device, b: &Buckets<'a>, ys: &HashSet<Mount>
             minor,
             ..
         })
+        | Device::NvmeNamespace(NvmeNamespace {
+            children,
+            paths,
+            major,
+            minor,
+            ..
+        })
         | Device::Partition(Partition {
             children,
             paths,
@@ -348,7 +452,7 @@ fn build_device_graph<'a>(ptr: &mut Device, b: &Buckets<'a>, ys: &HashSet<Mount>
         }) => {
             let xs = get_partitions(&b, &ys, &major, &minor)?;
 
-            // This should only be present for scsi devs
+            // This should only be present for scsi and non-native multipath nvme devs
             let ms = get_mpaths(&b, &ys, major, minor)?;
 
             let vs = get_vgs(b, major, minor)?;
@@ -357,7 +461,9 @@ fn build_device_graph<'a>(ptr: &mut Device, b: &Buckets<'a>, ys: &HashSet<Mount>
 
             let pools = get_pools(&b, &ys, &paths)?;
 
-            for mut x in HashSet::unions(vec![xs, ms, vs, mds, pools]) {
+            let dms = get_dm_devices(&b, &ys, major, minor)?;
+
+            for mut x in HashSet::unions(vec![xs, ms, vs, mds, pools, dms]) {
                 build_device_graph(&mut x, b, ys)?;
 
                 children.insert(x);
@@ -387,7 +493,9 @@ fn build_device_graph<'a>(ptr: &mut Device, b: &Buckets<'a>, ys: &HashSet<Mount>
 
             let pools = get_pools(&b, &ys, &paths)?;
 
-            for mut x in HashSet::unions(vec![ps, pools]) {
+            let dms = get_dm_devices(&b, &ys, &major, &minor)?;
+
+            for mut x in HashSet::unions(vec![ps, pools, dms]) {
                 build_device_graph(&mut x, b, ys)?;
 
                 children.insert(x);
@@ -410,7 +518,9 @@ fn build_device_graph<'a>(ptr: &mut Device, b: &Buckets<'a>, ys: &HashSet<Mount>
 
             let pools = get_pools(&b, &ys, &paths)?;
 
-            for mut x in HashSet::unions(vec![vs, ps, mds, pools]) {
+            let dms = get_dm_devices(&b, &ys, major, minor)?;
+
+            for mut x in HashSet::unions(vec![vs, ps, mds, pools, dms]) {
                 build_device_graph(&mut x, b, ys)?;
 
                 children.insert(x);
@@ -436,20 +546,30 @@ fn build_device_graph<'a>(ptr: &mut Device, b: &Buckets<'a>, ys: &HashSet<Mount>
 #[derive(Debug)]
 struct Buckets<'a> {
     dms: Vector<&'a UEvent>,
+    dm_virtuals: Vector<&'a UEvent>,
     mds: Vector<&'a UEvent>,
     mpaths: Vector<&'a UEvent>,
+    nvmes: Vector<&'a UEvent>,
     partitions: Vector<&'a UEvent>,
     pools: Vector<&'a libzfs_types::Pool>,
+    pool_statuses: &'a state::ZpoolStatuses,
     rest: Vector<&'a UEvent>,
 }
 
-fn bucket_devices<'a>(xs: &Vector<&'a UEvent>, ys: &'a state::ZedEvents) -> Buckets<'a> {
+fn bucket_devices<'a>(
+    xs: &Vector<&'a UEvent>,
+    ys: &'a state::ZedEvents,
+    zs: &'a state::ZpoolStatuses,
+) -> Buckets<'a> {
     let buckets = Buckets {
         dms: vector![],
+        dm_virtuals: vector![],
         mds: vector![],
         mpaths: vector![],
+        nvmes: vector![],
         partitions: vector![],
         pools: vector![],
+        pool_statuses: zs,
         rest: vector![],
     };
 
@@ -460,8 +580,12 @@ fn bucket_devices<'a>(xs: &Vector<&'a UEvent>, ys: &'a state::ZedEvents) -> Buck
             acc.mds.push_back(x)
         } else if is_mpath(&x) {
             acc.mpaths.push_back(x)
+        } else if is_dm_virtual(&x) {
+            acc.dm_virtuals.push_back(x)
         } else if is_partition(&x) {
             acc.partitions.push_back(x)
+        } else if is_nvme(&x) {
+            acc.nvmes.push_back(x)
         } else {
             acc.rest.push_back(x)
         }
@@ -478,15 +602,26 @@ fn build_device_list(xs: &state::UEvents) -> Vector<&UEvent> {
     xs.values().filter(|y| keep_usable(y)).collect()
 }
 
-pub fn produce_device_graph(state: &state::State) -> Result<bytes::Bytes> {
+pub fn produce_device_tree(state: &state::State) -> Result<Device> {
     let dev_list = build_device_list(&state.uevents);
-    let dev_list = bucket_devices(&dev_list, &state.zed_events);
+    let dev_list = bucket_devices(&dev_list, &state.zed_events, &state.zpool_statuses);
 
     let mut root = Device::Root(Root::default());
 
     build_device_graph(&mut root, &dev_list, &state.local_mounts)?;
 
-    let v = serde_json::to_string(&(&root, &state.local_mounts))?;
+    Ok(root)
+}
+
+pub fn produce_device_graph(state: &state::State) -> Result<bytes::Bytes> {
+    let root = produce_device_tree(state)?;
+
+    to_line(&(&root, &state.local_mounts))
+}
+
+/// Serializes `x` as a single line of JSON.
+pub fn to_line(x: &impl serde::Serialize) -> Result<bytes::Bytes> {
+    let v = serde_json::to_string(x)?;
     let b = bytes::BytesMut::from((v + "\n").as_str());
     Ok(b.freeze())
 }
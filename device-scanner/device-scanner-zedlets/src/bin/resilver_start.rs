@@ -0,0 +1,12 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use device_scanner_zedlets::{get_time, send_data, zpool, Result};
+use device_types::zed::{zpool::ScanKind, ZedCommand};
+
+fn main() -> Result<()> {
+    let x = ZedCommand::ScanStart(zpool::get_guid()?, ScanKind::Resilver, get_time()?);
+
+    send_data(x)
+}
@@ -0,0 +1,17 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use device_scanner_zedlets::{send_data, vdev, zpool, Result};
+use device_types::zed::ZedCommand;
+
+fn main() -> Result<()> {
+    let x = ZedCommand::VdevStateChange(
+        zpool::get_name()?,
+        zpool::get_guid()?,
+        vdev::get_guid()?,
+        vdev::get_state()?,
+    );
+
+    send_data(x)
+}
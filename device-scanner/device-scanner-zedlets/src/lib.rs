@@ -3,7 +3,7 @@
 // license that can be found in the LICENSE file.
 
 use device_types::zed::ZedCommand;
-use std::{env, error, fmt, io, io::prelude::*, os::unix::net::UnixStream};
+use std::{env, error, fmt, io, io::prelude::*, num, os::unix::net::UnixStream};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -12,6 +12,7 @@ pub enum Error {
     SerdeJson(serde_json::Error),
     Io(io::Error),
     Var(env::VarError),
+    ParseInt(num::ParseIntError),
 }
 
 impl fmt::Display for Error {
@@ -20,6 +21,7 @@ impl fmt::Display for Error {
             Error::Io(ref err) => write!(f, "{}", err),
             Error::SerdeJson(ref err) => write!(f, "{}", err),
             Error::Var(ref err) => write!(f, "{}", err),
+            Error::ParseInt(ref err) => write!(f, "{}", err),
         }
     }
 }
@@ -30,6 +32,7 @@ impl error::Error for Error {
             Error::Io(ref err) => Some(err),
             Error::SerdeJson(ref err) => Some(err),
             Error::Var(ref err) => Some(err),
+            Error::ParseInt(ref err) => Some(err),
         }
     }
 }
@@ -52,6 +55,19 @@ impl From<env::VarError> for Error {
     }
 }
 
+impl From<num::ParseIntError> for Error {
+    fn from(err: num::ParseIntError) -> Self {
+        Error::ParseInt(err)
+    }
+}
+
+/// The time the event was posted, in seconds since the epoch
+pub fn get_time() -> Result<i64> {
+    let x = env::var("ZEVENT_TIME_SECS")?.parse()?;
+
+    Ok(x)
+}
+
 pub fn send_data(z: ZedCommand) -> Result<()> {
     let x = serde_json::to_string(&z)?;
 
@@ -98,14 +114,34 @@ pub mod zfs {
 
 pub mod vdev {
     use super::Error;
+    use device_types::zed::vdev;
     use std::env;
 
-    pub fn get_guid() -> Result<String, Error> {
-        env::var("ZEVENT_VDEV_GUID").map_err(Error::Var)
+    pub fn get_guid() -> Result<vdev::Guid, Error> {
+        env::var("ZEVENT_VDEV_GUID")
+            .map(vdev::Guid)
+            .map_err(Error::Var)
+    }
+
+    pub fn get_state() -> Result<vdev::State, Error> {
+        env::var("ZEVENT_VDEV_STATE_STR")
+            .map(vdev::State)
+            .map_err(Error::Var)
+    }
+
+    fn get_count(name: &str) -> Result<u64, Error> {
+        let x = env::var(name)?.parse()?;
+
+        Ok(x)
     }
 
-    pub fn get_state() -> Result<String, Error> {
-        env::var("ZEVENT_VDEV_STATE_STR").map_err(Error::Var)
+    /// The cumulative error counts carried on checksum and io ereports
+    pub fn get_errors() -> Result<vdev::Errors, Error> {
+        Ok(vdev::Errors {
+            read: get_count("ZEVENT_VDEV_READ_ERRORS")?,
+            write: get_count("ZEVENT_VDEV_WRITE_ERRORS")?,
+            checksum: get_count("ZEVENT_VDEV_CKSUM_ERRORS")?,
+        })
     }
 }
 
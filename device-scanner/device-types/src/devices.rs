@@ -2,7 +2,11 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
-use crate::{mount, DevicePath};
+use crate::{
+    mount,
+    zed::{vdev, zpool},
+    DevicePath,
+};
 use im::{ordset, OrdSet};
 use std::path::PathBuf;
 
@@ -96,6 +100,91 @@ pub struct Mpath {
     pub mount: Option<mount::Mount>,
 }
 
+/// A controller an NVMe namespace is reachable through.
+///
+/// With native NVMe multipath a namespace will have one controller per path.
+#[derive(
+    Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize, Clone,
+)]
+pub struct NvmeController {
+    pub name: String,
+    pub transport: String,
+    pub address: Option<String>,
+    pub state: Option<String>,
+    /// The ANA state of the namespace through this controller, if ANA is supported.
+    pub ana_state: Option<String>,
+}
+
+#[derive(
+    Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize, Clone,
+)]
+pub struct NvmeNamespace {
+    pub wwid: Option<String>,
+    pub subsysnqn: String,
+    pub nsid: u32,
+    pub controllers: OrdSet<NvmeController>,
+    pub major: String,
+    pub minor: String,
+    pub devpath: PathBuf,
+    pub size: u64,
+    pub filesystem_type: Option<String>,
+    pub fs_uuid: Option<String>,
+    pub fs_label: Option<String>,
+    pub paths: Paths,
+    pub mount: Option<mount::Mount>,
+    pub children: Children,
+}
+
+/// The kind of device-mapper target backing a `DmDevice`.
+#[derive(
+    Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize, Clone, Copy,
+)]
+pub enum DmKind {
+    Crypt,
+    Integrity,
+    Vdo,
+}
+
+impl DmKind {
+    /// Figures out the target kind from a `DM_UUID`.
+    ///
+    /// Returns `None` for dm devices modeled elsewhere (mpath, LVM)
+    /// or that we don't know about.
+    pub fn from_dm_uuid(uuid: &str) -> Option<Self> {
+        if uuid.starts_with("CRYPT-INTEGRITY-") || uuid.starts_with("CRYPT-SUBDEV-") {
+            Some(Self::Integrity)
+        } else if uuid.starts_with("CRYPT-") {
+            Some(Self::Crypt)
+        } else if uuid.starts_with("VDO-") {
+            Some(Self::Vdo)
+        } else {
+            None
+        }
+    }
+}
+
+/// A dm-crypt/LUKS, dm-integrity or VDO mapping.
+///
+/// Parents are the devices listed as the mapping's dm slaves.
+#[derive(
+    Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize, Clone,
+)]
+pub struct DmDevice {
+    pub kind: DmKind,
+    pub dm_name: String,
+    pub uuid: String,
+    pub major: String,
+    pub minor: String,
+    pub devpath: PathBuf,
+    pub size: u64,
+    pub filesystem_type: Option<String>,
+    pub fs_uuid: Option<String>,
+    pub fs_label: Option<String>,
+    pub paths: Paths,
+    pub mount: Option<mount::Mount>,
+    pub children: Children,
+}
+
 #[derive(
     Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize, Clone,
 )]
@@ -137,6 +226,22 @@ pub struct Zpool {
     pub props: Vec<libzfs_types::ZProp>,
     pub children: Children,
     pub mount: Option<mount::Mount>,
+    /// State and error counts of each leaf vdev
+    #[serde(default)]
+    pub vdevs: Vec<VdevHealth>,
+    /// The most recent scrub or resilver seen by ZED
+    #[serde(default)]
+    pub scan: Option<zpool::Scan>,
+}
+
+#[derive(
+    Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize, Clone,
+)]
+pub struct VdevHealth {
+    pub guid: u64,
+    pub path: PathBuf,
+    pub state: String,
+    pub errors: vdev::Errors,
 }
 
 #[derive(
@@ -156,16 +261,20 @@ pub struct Dataset {
 pub enum Device {
     Root(Root),
     ScsiDevice(ScsiDevice),
+    NvmeNamespace(NvmeNamespace),
     Partition(Partition),
     MdRaid(MdRaid),
     Mpath(Mpath),
+    DmDevice(DmDevice),
     VolumeGroup(VolumeGroup),
     LogicalVolume(LogicalVolume),
     Zpool(Zpool),
     Dataset(Dataset),
 }
 
-#[derive(Debug, serde::Serialize, Eq, PartialEq, Ord, PartialOrd, Clone, Hash)]
+#[derive(
+    Debug, serde::Serialize, serde::Deserialize, Eq, PartialEq, Ord, PartialOrd, Clone, Hash,
+)]
 pub struct DeviceId(pub String);
 
 impl Device {
@@ -184,6 +293,15 @@ impl Device {
                     .iter()
                     .find_map(|c| c.find_device_by_devpath(dev_path))
             }
+            Self::NvmeNamespace(x) => {
+                if x.paths.contains(dev_path) {
+                    return Some(self);
+                }
+
+                x.children
+                    .iter()
+                    .find_map(|c| c.find_device_by_devpath(dev_path))
+            }
             Self::Partition(x) => {
                 if x.paths.contains(dev_path) {
                     return Some(self);
@@ -211,6 +329,15 @@ impl Device {
                     .iter()
                     .find_map(|c| c.find_device_by_devpath(dev_path))
             }
+            Self::DmDevice(x) => {
+                if x.paths.contains(dev_path) {
+                    return Some(self);
+                }
+
+                x.children
+                    .iter()
+                    .find_map(|c| c.find_device_by_devpath(dev_path))
+            }
             Self::VolumeGroup(x) => x
                 .children
                 .iter()
@@ -241,9 +368,11 @@ impl Device {
         match self {
             Self::Root(_) => None,
             Self::ScsiDevice(x) => x.fs_uuid.as_deref(),
+            Self::NvmeNamespace(x) => x.fs_uuid.as_deref(),
             Self::Partition(x) => x.fs_uuid.as_deref(),
             Self::MdRaid(x) => x.fs_uuid.as_deref(),
             Self::Mpath(x) => x.fs_uuid.as_deref(),
+            Self::DmDevice(x) => x.fs_uuid.as_deref(),
             Self::VolumeGroup(_) => None,
             Self::LogicalVolume(x) => x.fs_uuid.as_deref(),
             Self::Zpool(_) => None,
@@ -254,6 +383,51 @@ impl Device {
             }
         }
     }
+    pub fn get_filesystem_type(&self) -> Option<&str> {
+        match self {
+            Self::Root(_) => None,
+            Self::ScsiDevice(x) => x.filesystem_type.as_deref(),
+            Self::NvmeNamespace(x) => x.filesystem_type.as_deref(),
+            Self::Partition(x) => x.filesystem_type.as_deref(),
+            Self::MdRaid(x) => x.filesystem_type.as_deref(),
+            Self::Mpath(x) => x.filesystem_type.as_deref(),
+            Self::DmDevice(x) => x.filesystem_type.as_deref(),
+            Self::VolumeGroup(_) => None,
+            Self::LogicalVolume(x) => x.filesystem_type.as_deref(),
+            Self::Zpool(_) => None,
+            Self::Dataset(x) => Some(x.kind.as_str()),
+        }
+    }
+    pub fn get_fs_label(&self) -> Option<&str> {
+        match self {
+            Self::Root(_) => None,
+            Self::ScsiDevice(x) => x.fs_label.as_deref(),
+            Self::NvmeNamespace(x) => x.fs_label.as_deref(),
+            Self::Partition(x) => x.fs_label.as_deref(),
+            Self::MdRaid(x) => x.fs_label.as_deref(),
+            Self::Mpath(x) => x.fs_label.as_deref(),
+            Self::DmDevice(x) => x.fs_label.as_deref(),
+            Self::VolumeGroup(_) => None,
+            Self::LogicalVolume(x) => x.fs_label.as_deref(),
+            Self::Zpool(_) => None,
+            Self::Dataset(_) => None,
+        }
+    }
+    pub fn get_mount(&self) -> Option<&mount::Mount> {
+        match self {
+            Self::Root(_) => None,
+            Self::ScsiDevice(x) => x.mount.as_ref(),
+            Self::NvmeNamespace(x) => x.mount.as_ref(),
+            Self::Partition(x) => x.mount.as_ref(),
+            Self::MdRaid(x) => x.mount.as_ref(),
+            Self::Mpath(x) => x.mount.as_ref(),
+            Self::DmDevice(x) => x.mount.as_ref(),
+            Self::VolumeGroup(_) => None,
+            Self::LogicalVolume(x) => x.mount.as_ref(),
+            Self::Zpool(x) => x.mount.as_ref(),
+            Self::Dataset(x) => x.mount.as_ref(),
+        }
+    }
     pub fn find_device_by_id(&self, id: &DeviceId) -> Option<&Device> {
         match self {
             Self::Root(x) => x.children.iter().find_map(|c| c.find_device_by_id(id)),
@@ -264,6 +438,13 @@ impl Device {
 
                 x.children.iter().find_map(|c| c.find_device_by_id(id))
             }
+            Self::NvmeNamespace(x) => {
+                if self.get_id().as_ref()? == id {
+                    return Some(self);
+                }
+
+                x.children.iter().find_map(|c| c.find_device_by_id(id))
+            }
             Self::Partition(x) => {
                 if self.get_id().as_ref()? == id {
                     return Some(self);
@@ -285,6 +466,13 @@ impl Device {
 
                 x.children.iter().find_map(|c| c.find_device_by_id(id))
             }
+            Self::DmDevice(x) => {
+                if self.get_id().as_ref()? == id {
+                    return Some(self);
+                }
+
+                x.children.iter().find_map(|c| c.find_device_by_id(id))
+            }
             Self::VolumeGroup(x) => {
                 if self.get_id().as_ref()? == id {
                     return Some(self);
@@ -319,6 +507,10 @@ impl Device {
         match self {
             Self::Root(_) => Some(DeviceId("root".into())),
             Self::ScsiDevice(x) => Some(DeviceId(format!("scsi_{}", x.serial.as_ref()?))),
+            Self::NvmeNamespace(x) => match &x.wwid {
+                Some(wwid) => Some(DeviceId(format!("nvme_{}", wwid))),
+                None => Some(DeviceId(format!("nvme_{}_{}", x.subsysnqn, x.nsid))),
+            },
             Self::Partition(x) => Some(DeviceId(format!(
                 "partition{}_{}",
                 x.partition_number,
@@ -326,6 +518,7 @@ impl Device {
             ))),
             Self::MdRaid(x) => Some(DeviceId(format!("mdraid_{}", x.uuid))),
             Self::Mpath(x) => Some(DeviceId(format!("mpath_{}", x.serial.as_ref()?))),
+            Self::DmDevice(x) => Some(DeviceId(format!("dm_{}", x.uuid))),
             Self::VolumeGroup(x) => Some(DeviceId(format!("vg_{}", x.uuid))),
             Self::LogicalVolume(x) => Some(DeviceId(format!("lv_{}", x.uuid))),
             Self::Zpool(x) => Some(DeviceId(format!("zpool_{}", x.guid))),
@@ -336,13 +529,70 @@ impl Device {
         match self {
             Self::Root(x) => Some(&x.children),
             Self::ScsiDevice(x) => Some(&x.children),
+            Self::NvmeNamespace(x) => Some(&x.children),
             Self::Partition(x) => Some(&x.children),
             Self::MdRaid(x) => Some(&x.children),
             Self::Mpath(x) => Some(&x.children),
+            Self::DmDevice(x) => Some(&x.children),
             Self::VolumeGroup(x) => Some(&x.children),
             Self::LogicalVolume(x) => Some(&x.children),
             Self::Zpool(x) => Some(&x.children),
             Self::Dataset(_) => None,
         }
     }
+    pub fn children_mut(&mut self) -> Option<&mut OrdSet<Device>> {
+        match self {
+            Self::Root(x) => Some(&mut x.children),
+            Self::ScsiDevice(x) => Some(&mut x.children),
+            Self::NvmeNamespace(x) => Some(&mut x.children),
+            Self::Partition(x) => Some(&mut x.children),
+            Self::MdRaid(x) => Some(&mut x.children),
+            Self::Mpath(x) => Some(&mut x.children),
+            Self::DmDevice(x) => Some(&mut x.children),
+            Self::VolumeGroup(x) => Some(&mut x.children),
+            Self::LogicalVolume(x) => Some(&mut x.children),
+            Self::Zpool(x) => Some(&mut x.children),
+            Self::Dataset(_) => None,
+        }
+    }
+    /// Returns a copy of this device with no children
+    pub fn without_children(&self) -> Device {
+        let mut x = self.clone();
+
+        if let Some(xs) = x.children_mut() {
+            xs.clear();
+        }
+
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DmKind;
+
+    #[test]
+    fn test_dm_kind_from_dm_uuid() {
+        assert_eq!(
+            DmKind::from_dm_uuid("CRYPT-LUKS2-8d7fb5b4c1b04d5e9f0e2a4c3d6b7a81-ost0"),
+            Some(DmKind::Crypt)
+        );
+        assert_eq!(
+            DmKind::from_dm_uuid("CRYPT-INTEGRITY-ost0_int"),
+            Some(DmKind::Integrity)
+        );
+        assert_eq!(
+            DmKind::from_dm_uuid("CRYPT-SUBDEV-8d7fb5b4c1b04d5e9f0e2a4c3d6b7a81-ost0_dif"),
+            Some(DmKind::Integrity)
+        );
+        assert_eq!(DmKind::from_dm_uuid("VDO-2a8e7c36-6f5b"), Some(DmKind::Vdo));
+        assert_eq!(
+            DmKind::from_dm_uuid("mpath-3600140550e41a841db244a992c31e7df"),
+            None
+        );
+        assert_eq!(
+            DmKind::from_dm_uuid("LVM-pV8TgNKMJVNrolJgMhVwg4CAeFFAIMC8"),
+            None
+        );
+    }
 }
@@ -5,12 +5,13 @@
 #![allow(clippy::large_enum_variant)]
 
 pub mod devices;
+pub mod stream;
 pub mod udev;
 pub mod uevent;
 
 use std::{
     cmp::Ordering,
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     hash::{Hash, Hasher},
     path::{Path, PathBuf},
 };
@@ -90,6 +91,47 @@ pub fn get_vdev_paths(vdev: &libzfs_types::VDev) -> BTreeSet<DevicePath> {
     }
 }
 
+/// Flattens a vdev tree into the health of each leaf vdev.
+///
+/// Error counts are not part of the vdev tree, so they are looked up by guid in `errors`.
+pub fn get_vdev_health(
+    vdev: &libzfs_types::VDev,
+    errors: &BTreeMap<u64, zed::vdev::Errors>,
+) -> Vec<devices::VdevHealth> {
+    match vdev {
+        libzfs_types::VDev::Disk {
+            guid, state, path, ..
+        }
+        | libzfs_types::VDev::File {
+            guid, state, path, ..
+        } => guid
+            .map(|guid| devices::VdevHealth {
+                guid,
+                path: path.clone(),
+                state: state.clone(),
+                errors: errors.get(&guid).copied().unwrap_or_default(),
+            })
+            .into_iter()
+            .collect(),
+        libzfs_types::VDev::Mirror { children, .. }
+        | libzfs_types::VDev::RaidZ { children, .. }
+        | libzfs_types::VDev::Replacing { children, .. } => children
+            .iter()
+            .flat_map(|x| get_vdev_health(x, errors))
+            .collect(),
+        libzfs_types::VDev::Root {
+            children,
+            spares,
+            cache,
+            ..
+        } => vec![children, spares, cache]
+            .into_iter()
+            .flatten()
+            .flat_map(|x| get_vdev_health(x, errors))
+            .collect(),
+    }
+}
+
 impl Ord for DevicePath {
     fn cmp(&self, other: &DevicePath) -> Ordering {
         let a_slot = find_sort_slot(self);
@@ -130,7 +172,7 @@ pub mod message {
 }
 
 pub mod state {
-    use crate::{mount, uevent};
+    use crate::{mount, uevent, zed::zpool};
     use im::{HashMap, HashSet};
     use std::path::PathBuf;
 
@@ -138,10 +180,15 @@ pub mod state {
 
     pub type ZedEvents = HashMap<u64, libzfs_types::Pool>;
 
+    /// Pool status that ZED reports as events, and is not part of a `libzfs_types::Pool`.
+    pub type ZpoolStatuses = HashMap<u64, zpool::Status>;
+
     #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
     pub struct State {
         pub uevents: UEvents,
         pub zed_events: ZedEvents,
+        #[serde(default)]
+        pub zpool_statuses: ZpoolStatuses,
         pub local_mounts: HashSet<mount::Mount>,
     }
 
@@ -150,6 +197,7 @@ pub mod state {
             State {
                 uevents: HashMap::new(),
                 zed_events: HashMap::new(),
+                zpool_statuses: HashMap::new(),
                 local_mounts: HashSet::new(),
             }
         }
@@ -224,13 +272,18 @@ pub mod zed {
         RemoveDataset(zpool::Guid, zfs::Name),
         SetZpoolProp(zpool::Guid, prop::Key, prop::Value),
         SetZfsProp(zpool::Guid, zfs::Name, prop::Key, prop::Value),
+        SetVdevErrors(zpool::Guid, vdev::Guid, vdev::Errors),
+        SetScan(zpool::Guid, zpool::Scan),
     }
 
     pub mod zpool {
+        use super::vdev;
+        use std::collections::BTreeMap;
+
         #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
         pub struct Name(pub String);
 
-        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
         pub struct Guid(pub String);
 
         impl From<u64> for Guid {
@@ -254,6 +307,93 @@ pub mod zed {
                 x
             }
         }
+
+        #[derive(
+            Debug,
+            Clone,
+            Copy,
+            PartialEq,
+            Eq,
+            PartialOrd,
+            Ord,
+            Hash,
+            serde::Serialize,
+            serde::Deserialize,
+        )]
+        pub enum ScanKind {
+            Scrub,
+            Resilver,
+        }
+
+        #[derive(
+            Debug,
+            Clone,
+            Copy,
+            PartialEq,
+            Eq,
+            PartialOrd,
+            Ord,
+            Hash,
+            serde::Serialize,
+            serde::Deserialize,
+        )]
+        pub enum ScanState {
+            Scanning,
+            Finished,
+            Canceled,
+        }
+
+        #[derive(
+            Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+        )]
+        pub struct Scan {
+            pub kind: ScanKind,
+            pub state: ScanState,
+            /// Seconds since the epoch when `state` was entered
+            pub time: i64,
+        }
+
+        #[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+        pub struct Status {
+            pub scan: Option<Scan>,
+            /// Error counts, keyed by vdev guid
+            pub vdev_errors: BTreeMap<u64, vdev::Errors>,
+        }
+    }
+
+    pub mod vdev {
+        #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+        pub struct Guid(pub String);
+
+        impl From<Guid> for Result<u64, std::num::ParseIntError> {
+            fn from(Guid(x): Guid) -> Self {
+                let without_prefix = x.trim_start_matches("0x");
+                u64::from_str_radix(without_prefix, 16)
+            }
+        }
+
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        pub struct State(pub String);
+
+        /// Cumulative error counts of a vdev
+        #[derive(
+            Debug,
+            Clone,
+            Copy,
+            Default,
+            PartialEq,
+            Eq,
+            PartialOrd,
+            Ord,
+            Hash,
+            serde::Serialize,
+            serde::Deserialize,
+        )]
+        pub struct Errors {
+            pub read: u64,
+            pub write: u64,
+            pub checksum: u64,
+        }
     }
 
     pub mod zfs {
@@ -281,12 +421,24 @@ pub mod zed {
         SetZpoolProp(zpool::Guid, prop::Key, prop::Value),
         SetZfsProp(zpool::Guid, zfs::Name, prop::Key, prop::Value),
         AddVdev(zpool::Name, zpool::Guid),
+        VdevStateChange(zpool::Name, zpool::Guid, vdev::Guid, vdev::State),
+        /// A checksum or I/O error was seen on a vdev
+        VdevErrors(zpool::Guid, vdev::Guid, vdev::Errors),
+        /// A scrub or resilver started at the given time
+        ScanStart(zpool::Guid, zpool::ScanKind, i64),
+        /// A scrub or resilver finished at the given time
+        ScanFinish(zpool::Guid, zpool::ScanKind, i64),
+        /// A scrub was stopped at the given time
+        ScrubAbort(zpool::Guid, i64),
     }
 }
 
 #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum Command {
     Stream,
+    /// Stream the device graph as a snapshot followed by patches.
+    /// See `stream::StreamMessage`.
+    StreamPatches,
     GetMounts,
     PoolCommand(zed::PoolCommand),
     UdevCommand(udev::UdevCommand),
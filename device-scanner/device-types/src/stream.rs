@@ -0,0 +1,495 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Incremental device-graph stream protocol.
+//!
+//! A client that sends `Command::StreamPatches` is first sent a `StreamMessage::Snapshot`
+//! of the whole device graph, followed by a `StreamMessage::Patches` each time the graph changes.
+//!
+//! Every message carries a sequence number. A client that sees a gap in the sequence
+//! has missed a change and should reconnect to get a fresh snapshot.
+
+use crate::{
+    devices::{Device, DeviceId, Root},
+    mount::Mount,
+};
+use im::{HashSet, OrdMap};
+use std::{error, fmt};
+
+/// The version of the stream protocol, sent with each snapshot.
+pub const VERSION: u32 = 1;
+
+/// A single device in a `DeviceIndex`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DeviceNode {
+    /// The device, with its children removed
+    pub device: Device,
+    pub children: Vec<DeviceId>,
+}
+
+/// A device graph flattened into nodes keyed by `DeviceId`.
+///
+/// Devices that appear under more than one parent (i.e. an mpath or a vg) are stored once.
+pub type DeviceIndex = OrdMap<DeviceId, DeviceNode>;
+
+/// A structural change to a device graph.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Patch {
+    Upsert(DeviceId, DeviceNode),
+    Remove(DeviceId),
+    Mounts(HashSet<Mount>),
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum StreamMessage {
+    Snapshot {
+        version: u32,
+        seq: u64,
+        devices: Device,
+        mounts: HashSet<Mount>,
+    },
+    Patches {
+        seq: u64,
+        patches: Vec<Patch>,
+    },
+}
+
+impl StreamMessage {
+    pub fn seq(&self) -> u64 {
+        match self {
+            Self::Snapshot { seq, .. } | Self::Patches { seq, .. } => *seq,
+        }
+    }
+}
+
+/// Flattens a device tree into a `DeviceIndex`.
+///
+/// Returns `None` if any device in the tree does not have an id,
+/// in which case the tree can only be sent as a snapshot.
+pub fn index_devices(root: &Device) -> Option<DeviceIndex> {
+    fn go(x: &Device, out: &mut DeviceIndex) -> Option<DeviceId> {
+        let id = x.get_id()?;
+
+        let mut children = vec![];
+
+        for c in x.children().into_iter().flatten() {
+            children.push(go(c, out)?);
+        }
+
+        out.insert(
+            id.clone(),
+            DeviceNode {
+                device: x.without_children(),
+                children,
+            },
+        );
+
+        Some(id)
+    }
+
+    let mut out = OrdMap::new();
+
+    go(root, &mut out)?;
+
+    Some(out)
+}
+
+/// Rebuilds a device tree from a `DeviceIndex`.
+///
+/// Returns `None` if the root or any referenced child is missing from the index.
+pub fn build_tree(index: &DeviceIndex) -> Option<Device> {
+    fn go(id: &DeviceId, index: &DeviceIndex) -> Option<Device> {
+        let node = index.get(id)?;
+
+        let mut x = node.device.clone();
+
+        if let Some(xs) = x.children_mut() {
+            for c in &node.children {
+                xs.insert(go(c, index)?);
+            }
+        }
+
+        Some(x)
+    }
+
+    go(&DeviceId("root".into()), index)
+}
+
+/// Computes the patches that turn `old` into `new`.
+pub fn diff(old: &DeviceIndex, new: &DeviceIndex) -> Vec<Patch> {
+    let removed = old
+        .keys()
+        .filter(|k| !new.contains_key(k))
+        .cloned()
+        .map(Patch::Remove);
+
+    let upserted = new
+        .iter()
+        .filter(|(k, v)| old.get(k) != Some(v))
+        .map(|(k, v)| Patch::Upsert(k.clone(), v.clone()));
+
+    removed.chain(upserted).collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResyncError {
+    /// A message was missed
+    SeqGap { expected: Option<u64>, got: u64 },
+    /// The patches reference devices that are not in the graph
+    IncompleteGraph(u64),
+}
+
+impl fmt::Display for ResyncError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::SeqGap {
+                expected: Some(expected),
+                got,
+            } => write!(f, "Expected seq {}, got {}", expected, got),
+            Self::SeqGap {
+                expected: None,
+                got,
+            } => write!(f, "Got seq {} before a snapshot", got),
+            Self::IncompleteGraph(seq) => {
+                write!(f, "Device graph is incomplete after applying seq {}", seq)
+            }
+        }
+    }
+}
+
+impl error::Error for ResyncError {}
+
+/// A device graph kept up to date from a stream of `StreamMessage`s.
+#[derive(Debug, Clone)]
+pub struct DeviceGraph {
+    seq: Option<u64>,
+    index: DeviceIndex,
+    devices: Device,
+    mounts: HashSet<Mount>,
+}
+
+impl Default for DeviceGraph {
+    fn default() -> Self {
+        Self {
+            seq: None,
+            index: OrdMap::new(),
+            devices: Device::Root(Root::default()),
+            mounts: HashSet::new(),
+        }
+    }
+}
+
+impl DeviceGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// The sequence number of the last applied message, if any.
+    pub fn seq(&self) -> Option<u64> {
+        self.seq
+    }
+    pub fn devices(&self) -> &Device {
+        &self.devices
+    }
+    pub fn mounts(&self) -> &HashSet<Mount> {
+        &self.mounts
+    }
+    /// Applies a message to the graph.
+    ///
+    /// On error the graph is left unchanged and the caller should resync from a new snapshot.
+    pub fn apply(&mut self, msg: StreamMessage) -> Result<(), ResyncError> {
+        match msg {
+            StreamMessage::Snapshot {
+                seq,
+                devices,
+                mounts,
+                ..
+            } => {
+                self.index = index_devices(&devices).unwrap_or_default();
+                self.devices = devices;
+                self.mounts = mounts;
+                self.seq = Some(seq);
+            }
+            StreamMessage::Patches { seq, patches } => {
+                let expected = self.seq.map(|x| x + 1);
+
+                if expected != Some(seq) {
+                    return Err(ResyncError::SeqGap { expected, got: seq });
+                }
+
+                let mut index = self.index.clone();
+                let mut mounts = None;
+
+                for p in patches {
+                    match p {
+                        Patch::Upsert(id, x) => {
+                            index.insert(id, x);
+                        }
+                        Patch::Remove(id) => {
+                            index.remove(&id);
+                        }
+                        Patch::Mounts(xs) => mounts = Some(xs),
+                    }
+                }
+
+                self.devices = build_tree(&index).ok_or(ResyncError::IncompleteGraph(seq))?;
+                self.index = index;
+                self.seq = Some(seq);
+
+                if let Some(xs) = mounts {
+                    self.mounts = xs;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Tracks the last published device graph and produces
+/// the `StreamMessage`s needed to bring clients up to date.
+#[derive(Debug, Clone)]
+pub struct Publisher {
+    seq: u64,
+    index: Option<DeviceIndex>,
+    devices: Device,
+    mounts: HashSet<Mount>,
+}
+
+impl Default for Publisher {
+    fn default() -> Self {
+        let devices = Device::Root(Root::default());
+
+        Self {
+            seq: 0,
+            index: index_devices(&devices),
+            devices,
+            mounts: HashSet::new(),
+        }
+    }
+}
+
+impl Publisher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// The whole graph at the current sequence number, for newly connected clients.
+    pub fn snapshot(&self) -> StreamMessage {
+        StreamMessage::Snapshot {
+            version: VERSION,
+            seq: self.seq,
+            devices: self.devices.clone(),
+            mounts: self.mounts.clone(),
+        }
+    }
+    /// Records a new device graph and returns the message to send to connected clients.
+    ///
+    /// Returns `None` if nothing changed. A snapshot is returned in place of patches
+    /// when either graph contains a device that cannot be identified.
+    pub fn update(&mut self, devices: Device, mounts: HashSet<Mount>) -> Option<StreamMessage> {
+        if devices == self.devices && mounts == self.mounts {
+            return None;
+        }
+
+        self.seq += 1;
+
+        let index = index_devices(&devices);
+
+        let msg = match (&self.index, &index) {
+            (Some(old), Some(new)) => {
+                let mut patches = diff(old, new);
+
+                if mounts != self.mounts {
+                    patches.push(Patch::Mounts(mounts.clone()));
+                }
+
+                StreamMessage::Patches {
+                    seq: self.seq,
+                    patches,
+                }
+            }
+            _ => StreamMessage::Snapshot {
+                version: VERSION,
+                seq: self.seq,
+                devices: devices.clone(),
+                mounts: mounts.clone(),
+            },
+        };
+
+        self.index = index;
+        self.devices = devices;
+        self.mounts = mounts;
+
+        Some(msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::{Partition, ScsiDevice, VolumeGroup};
+    use im::{ordset, OrdSet};
+
+    fn scsi(serial: &str, children: OrdSet<Device>) -> Device {
+        Device::ScsiDevice(ScsiDevice {
+            serial: Some(serial.into()),
+            scsi80: None,
+            major: "8".into(),
+            minor: "0".into(),
+            devpath: format!("/devices/{}", serial).into(),
+            size: 1024,
+            filesystem_type: None,
+            fs_uuid: None,
+            fs_label: None,
+            paths: ordset![format!("/dev/disk/by-id/{}", serial).into()],
+            mount: None,
+            children,
+        })
+    }
+
+    fn partition(serial: &str, partition_number: u64) -> Device {
+        Device::Partition(Partition {
+            serial: Some(serial.into()),
+            scsi80: None,
+            partition_number,
+            size: 512,
+            major: "8".into(),
+            minor: partition_number.to_string(),
+            devpath: format!("/devices/{}/{}", serial, partition_number).into(),
+            filesystem_type: None,
+            fs_uuid: None,
+            fs_label: None,
+            paths: ordset![format!("/dev/disk/by-id/{}-part{}", serial, partition_number).into()],
+            mount: None,
+            children: ordset![],
+        })
+    }
+
+    fn vg(uuid: &str) -> Device {
+        Device::VolumeGroup(VolumeGroup {
+            name: "vg".into(),
+            uuid: uuid.into(),
+            size: 1024,
+            children: ordset![],
+        })
+    }
+
+    fn root(children: OrdSet<Device>) -> Device {
+        Device::Root(Root { children })
+    }
+
+    #[test]
+    fn test_index_roundtrip() {
+        let x = root(ordset![
+            scsi("a", ordset![partition("a", 1), vg("vg1")]),
+            scsi("b", ordset![vg("vg1")])
+        ]);
+
+        let index = index_devices(&x).unwrap();
+
+        assert_eq!(index.len(), 5);
+        assert_eq!(build_tree(&index), Some(x));
+    }
+
+    #[test]
+    fn test_index_unidentified_device() {
+        let mut x = scsi("a", ordset![]);
+
+        if let Device::ScsiDevice(ref mut d) = x {
+            d.serial = None;
+        }
+
+        assert_eq!(index_devices(&root(ordset![x])), None);
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = index_devices(&root(ordset![scsi("a", ordset![partition("a", 1)])])).unwrap();
+        let new =
+            index_devices(&root(ordset![scsi("a", ordset![]), scsi("b", ordset![])])).unwrap();
+
+        let patches = diff(&old, &new);
+
+        assert_eq!(
+            patches
+                .iter()
+                .map(|x| match x {
+                    Patch::Upsert(id, _) => format!("upsert {}", id.0),
+                    Patch::Remove(id) => format!("remove {}", id.0),
+                    Patch::Mounts(_) => "mounts".to_string(),
+                })
+                .collect::<Vec<_>>(),
+            vec![
+                "remove partition1_a",
+                "upsert root",
+                "upsert scsi_a",
+                "upsert scsi_b"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_publish_and_apply() {
+        let mut publisher = Publisher::new();
+        let mut graph = DeviceGraph::new();
+
+        graph.apply(publisher.snapshot()).unwrap();
+
+        let x = root(ordset![scsi("a", ordset![partition("a", 1)])]);
+
+        let msg = publisher.update(x.clone(), HashSet::new()).unwrap();
+
+        assert!(matches!(msg, StreamMessage::Patches { seq: 1, .. }));
+
+        graph.apply(msg).unwrap();
+
+        assert_eq!(graph.devices(), &x);
+        assert_eq!(graph.seq(), Some(1));
+
+        assert_eq!(publisher.update(x, HashSet::new()), None);
+    }
+
+    #[test]
+    fn test_apply_seq_gap() {
+        let mut publisher = Publisher::new();
+        let mut graph = DeviceGraph::new();
+
+        graph.apply(publisher.snapshot()).unwrap();
+
+        publisher.update(root(ordset![scsi("a", ordset![])]), HashSet::new());
+
+        let msg = publisher
+            .update(root(ordset![scsi("b", ordset![])]), HashSet::new())
+            .unwrap();
+
+        assert_eq!(
+            graph.apply(msg),
+            Err(ResyncError::SeqGap {
+                expected: Some(1),
+                got: 2
+            })
+        );
+
+        graph.apply(publisher.snapshot()).unwrap();
+
+        assert_eq!(graph.devices(), &root(ordset![scsi("b", ordset![])]));
+    }
+
+    #[test]
+    fn test_apply_patches_before_snapshot() {
+        let mut publisher = Publisher::new();
+        let mut graph = DeviceGraph::new();
+
+        let msg = publisher
+            .update(root(ordset![scsi("a", ordset![])]), HashSet::new())
+            .unwrap();
+
+        assert_eq!(
+            graph.apply(msg),
+            Err(ResyncError::SeqGap {
+                expected: None,
+                got: 1
+            })
+        );
+    }
+}
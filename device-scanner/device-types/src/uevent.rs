@@ -2,7 +2,7 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
-use crate::DevicePath;
+use crate::{devices::NvmeController, DevicePath};
 use im::{OrdSet, Vector};
 use std::path::PathBuf;
 
@@ -38,9 +38,14 @@ pub struct UEvent {
     pub md_devs: OrdSet<DevicePath>,
     pub dm_multipath_devpath: Option<bool>,
     pub dm_name: Option<String>,
+    pub dm_uuid: Option<String>,
     pub dm_lv_name: Option<String>,
     pub lv_uuid: Option<String>,
     pub dm_vg_name: Option<String>,
     pub vg_uuid: Option<String>,
     pub md_uuid: Option<String>,
+    pub nvme_nsid: Option<u32>,
+    pub nvme_wwid: Option<String>,
+    pub nvme_subsysnqn: Option<String>,
+    pub nvme_controllers: OrdSet<NvmeController>,
 }
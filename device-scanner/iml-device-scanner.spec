@@ -1,7 +1,7 @@
 %define     base_name device-scanner
 
 Name:       iml-%{base_name}
-Version:    5.1.0
+Version:    5.2.0
 # Release Start
 Release:    1%{?dist}
 # Release End
@@ -36,6 +36,7 @@ cp device-scanner.{target,socket,service} %{buildroot}%{_unitdir}
 cp block-device-populator.service %{buildroot}%{_unitdir}
 cp 00-device-scanner.preset %{buildroot}%{_presetdir}
 cp device-scanner-daemon %{buildroot}%{_bindir}
+cp device-scanner-replay %{buildroot}%{_bindir}
 
 cp 99-iml-device-scanner.rules %{buildroot}%{_sysconfdir}/udev/rules.d
 cp uevent-listener %{buildroot}%{_bindir}
@@ -53,6 +54,14 @@ cp vdev_add-scanner %{buildroot}%{_libexecdir}/zfs/zed.d
 cp pool_destroy-scanner %{buildroot}%{_libexecdir}/zfs/zed.d
 cp history_event-scanner %{buildroot}%{_libexecdir}/zfs/zed.d
 cp pool_export-scanner %{buildroot}%{_libexecdir}/zfs/zed.d
+cp statechange-scanner %{buildroot}%{_libexecdir}/zfs/zed.d
+cp checksum-scanner %{buildroot}%{_libexecdir}/zfs/zed.d
+cp io-scanner %{buildroot}%{_libexecdir}/zfs/zed.d
+cp scrub_start-scanner %{buildroot}%{_libexecdir}/zfs/zed.d
+cp scrub_finish-scanner %{buildroot}%{_libexecdir}/zfs/zed.d
+cp scrub_abort-scanner %{buildroot}%{_libexecdir}/zfs/zed.d
+cp resilver_start-scanner %{buildroot}%{_libexecdir}/zfs/zed.d
+cp resilver_finish-scanner %{buildroot}%{_libexecdir}/zfs/zed.d
 
 mkdir -p %{buildroot}%{_sysconfdir}/zfs/zed.d
 ln -sf %{_libexecdir}/zfs/zed.d/pool_create-scanner %{buildroot}%{_sysconfdir}/zfs/zed.d
@@ -61,6 +70,14 @@ ln -sf %{_libexecdir}/zfs/zed.d/vdev_add-scanner %{buildroot}%{_sysconfdir}/zfs/
 ln -sf %{_libexecdir}/zfs/zed.d/pool_destroy-scanner %{buildroot}%{_sysconfdir}/zfs/zed.d
 ln -sf %{_libexecdir}/zfs/zed.d/history_event-scanner %{buildroot}%{_sysconfdir}/zfs/zed.d
 ln -sf %{_libexecdir}/zfs/zed.d/pool_export-scanner %{buildroot}%{_sysconfdir}/zfs/zed.d
+ln -sf %{_libexecdir}/zfs/zed.d/statechange-scanner %{buildroot}%{_sysconfdir}/zfs/zed.d
+ln -sf %{_libexecdir}/zfs/zed.d/checksum-scanner %{buildroot}%{_sysconfdir}/zfs/zed.d
+ln -sf %{_libexecdir}/zfs/zed.d/io-scanner %{buildroot}%{_sysconfdir}/zfs/zed.d
+ln -sf %{_libexecdir}/zfs/zed.d/scrub_start-scanner %{buildroot}%{_sysconfdir}/zfs/zed.d
+ln -sf %{_libexecdir}/zfs/zed.d/scrub_finish-scanner %{buildroot}%{_sysconfdir}/zfs/zed.d
+ln -sf %{_libexecdir}/zfs/zed.d/scrub_abort-scanner %{buildroot}%{_sysconfdir}/zfs/zed.d
+ln -sf %{_libexecdir}/zfs/zed.d/resilver_start-scanner %{buildroot}%{_sysconfdir}/zfs/zed.d
+ln -sf %{_libexecdir}/zfs/zed.d/resilver_finish-scanner %{buildroot}%{_sysconfdir}/zfs/zed.d
 
 cp zed-enhancer.{service,socket} %{buildroot}%{_unitdir}
 cp zed-populator.service %{buildroot}%{_unitdir}
@@ -84,6 +101,7 @@ cp 99-iml-zed-enhancer.rules %{buildroot}%{_sysconfdir}/udev/rules.d
 %attr(0644,root,root)%{_sysconfdir}/udev/rules.d/99-iml-device-scanner.rules
 %attr(0644,root,root)%{_sysconfdir}/udev/rules.d/99-iml-zed-enhancer.rules
 %attr(0755,root,root)%{_bindir}/device-scanner-daemon
+%attr(0755,root,root)%{_bindir}/device-scanner-replay
 %attr(0755,root,root)%{_bindir}/uevent-listener
 %attr(0755,root,root)%{_bindir}/mount-emitter
 %attr(0755,root,root)%{_bindir}/swap-emitter
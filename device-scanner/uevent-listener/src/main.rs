@@ -6,10 +6,18 @@
 #[macro_use]
 extern crate pretty_assertions;
 
-use device_types::{udev::UdevCommand, uevent::UEvent, Command, DevicePath};
-use im::{OrdSet, Vector};
+use device_types::{
+    devices::NvmeController, udev::UdevCommand, uevent::UEvent, Command, DevicePath,
+};
+use im::{ordset, OrdSet, Vector};
 use std::{
-    convert::Into, env, io::prelude::*, os::unix::net::UnixStream, process::exit, string::ToString,
+    convert::Into,
+    env, fs,
+    io::prelude::*,
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    process::exit,
+    string::ToString,
 };
 
 fn required_field(name: &str) -> String {
@@ -89,9 +97,92 @@ where
         .collect()
 }
 
+fn read_attr(dir: &Path, name: &str) -> Option<String> {
+    fs::read_to_string(dir.join(name))
+        .ok()
+        .map(|x| x.trim().to_string())
+        .and_then(empty_str_to_none)
+}
+
+/// Returns the sysfs dir of the NVMe namespace backing this device,
+/// or `None` if the device is not NVMe backed.
+fn nvme_namespace_dir(sys: &Path, devpath: &Path, devtype: &str) -> Option<PathBuf> {
+    let dir = sys.join(devpath.strip_prefix("/").unwrap_or(devpath));
+
+    let dir = if devtype == "partition" {
+        dir.parent()?.to_path_buf()
+    } else {
+        dir
+    };
+
+    if dir.join("nsid").exists() {
+        Some(dir)
+    } else {
+        None
+    }
+}
+
+fn nvme_controller(ctrl: &Path, ns: &Path) -> Option<NvmeController> {
+    Some(NvmeController {
+        name: ctrl.file_name()?.to_string_lossy().to_string(),
+        transport: read_attr(ctrl, "transport")?,
+        address: read_attr(ctrl, "address"),
+        state: read_attr(ctrl, "state"),
+        ana_state: read_attr(ns, "ana_state"),
+    })
+}
+
+/// Finds the controllers an NVMe namespace is reachable through.
+///
+/// Without native multipath the namespace's parent is its controller.
+/// With native multipath the parent is the subsystem, which links to each controller.
+/// Each of those controllers holds a path device for the namespace carrying its ANA state.
+fn nvme_controllers(ns: &Path) -> OrdSet<NvmeController> {
+    let parent = match fs::canonicalize(ns.join("device")) {
+        Ok(x) => x,
+        Err(_) => return ordset![],
+    };
+
+    if parent.join("transport").exists() {
+        return nvme_controller(&parent, ns).into_iter().collect();
+    }
+
+    let nsid = read_attr(ns, "nsid");
+
+    let entries = match fs::read_dir(&parent) {
+        Ok(x) => x,
+        Err(_) => return ordset![],
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .map(|x| x.path())
+        .filter(|x| x.join("transport").exists())
+        .filter_map(|ctrl| {
+            let path = fs::read_dir(&ctrl)
+                .ok()?
+                .filter_map(Result::ok)
+                .map(|x| x.path())
+                .find(|x| x.join("nsid").exists() && read_attr(x, "nsid") == nsid)?;
+
+            nvme_controller(&ctrl, &path)
+        })
+        .collect()
+}
+
 pub fn build_uevent() -> UEvent {
     let devname = required_field("DEVNAME").into();
-    let devpath = required_field("DEVPATH").into();
+    let devpath: PathBuf = required_field("DEVPATH").into();
+    let devtype = required_field("DEVTYPE");
+
+    let nvme_ns = nvme_namespace_dir(Path::new("/sys"), &devpath, &devtype);
+    let nvme_parent = nvme_ns
+        .as_ref()
+        .and_then(|x| fs::canonicalize(x.join("device")).ok());
+    let nvme_controllers = match nvme_ns {
+        Some(ref x) if devtype != "partition" => nvme_controllers(x),
+        _ => ordset![],
+    };
 
     UEvent {
         major: required_field("MAJOR"),
@@ -100,7 +191,7 @@ pub fn build_uevent() -> UEvent {
         paths: get_paths(),
         devname,
         devpath,
-        devtype: required_field("DEVTYPE"),
+        devtype,
         vendor: optional_field("ID_VENDOR"),
         model: optional_field("ID_MODEL"),
         serial: optional_field("ID_SERIAL"),
@@ -128,6 +219,7 @@ pub fn build_uevent() -> UEvent {
         md_devs: md_devs(env::vars()),
         dm_multipath_devpath: optional_field("DM_MULTIPATH_DEVICE_PATH").map(is_one),
         dm_name: optional_field("DM_NAME"),
+        dm_uuid: optional_field("DM_UUID"),
         dm_lv_name: optional_field("DM_LV_NAME"),
         vg_uuid: optional_field("DM_UUID")
             .and_then(lvm_uuids)
@@ -137,6 +229,13 @@ pub fn build_uevent() -> UEvent {
             .map(|(_, y)| y),
         dm_vg_name: optional_field("DM_VG_NAME"),
         md_uuid: optional_field("MD_UUID"),
+        nvme_nsid: nvme_ns
+            .as_ref()
+            .and_then(|x| read_attr(x, "nsid"))
+            .and_then(parse_to),
+        nvme_wwid: nvme_ns.as_ref().and_then(|x| read_attr(x, "wwid")),
+        nvme_subsysnqn: nvme_parent.and_then(|x| read_attr(&x, "subsysnqn")),
+        nvme_controllers,
     }
 }
 
@@ -164,7 +263,7 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use im::ordset;
+    use std::os::unix::fs::symlink;
 
     #[test]
     fn test_lvm_uuids() {
@@ -213,4 +312,70 @@ mod tests {
 
         assert_eq!(result, ordset!["/dev/sda".into(), "/dev/sdd".into()]);
     }
+
+    #[test]
+    fn test_nvme_native_multipath() -> std::io::Result<()> {
+        let sys = env::temp_dir().join(format!("uevent-listener-nvme-{}", std::process::id()));
+
+        let subsys = sys.join("devices/virtual/nvme-subsystem/nvme-subsys0");
+        let ns = subsys.join("nvme0n1");
+        let part = ns.join("nvme0n1p1");
+
+        fs::create_dir_all(&part)?;
+        fs::write(ns.join("nsid"), "1\n")?;
+        fs::write(ns.join("wwid"), "eui.0025388b91b1c8e1\n")?;
+        fs::write(
+            subsys.join("subsysnqn"),
+            "nqn.2014-08.org.nvmexpress:lustre\n",
+        )?;
+        symlink(&subsys, ns.join("device"))?;
+
+        for (ctrl, ana_state) in &[("nvme0", "optimized"), ("nvme1", "inaccessible")] {
+            let ctrl_dir = sys.join("devices/virtual/nvme-fabrics/ctl").join(ctrl);
+            let path = ctrl_dir.join(format!("nvme0c{}n1", &ctrl[4..]));
+
+            fs::create_dir_all(&path)?;
+            fs::write(ctrl_dir.join("transport"), "tcp\n")?;
+            fs::write(ctrl_dir.join("state"), "live\n")?;
+            fs::write(path.join("nsid"), "1\n")?;
+            fs::write(path.join("ana_state"), format!("{}\n", ana_state))?;
+            symlink(&ctrl_dir, subsys.join(ctrl))?;
+        }
+
+        let devpath = Path::new("/devices/virtual/nvme-subsystem/nvme-subsys0/nvme0n1/nvme0n1p1");
+
+        let found = nvme_namespace_dir(&sys, devpath, "partition");
+
+        assert_eq!(found, Some(ns.clone()));
+        assert_eq!(
+            read_attr(&ns, "wwid"),
+            Some("eui.0025388b91b1c8e1".to_string())
+        );
+
+        let result = nvme_controllers(&ns);
+
+        fs::remove_dir_all(&sys)?;
+
+        assert_eq!(
+            result,
+            ordset![
+                NvmeController {
+                    name: "nvme0".to_string(),
+                    transport: "tcp".to_string(),
+                    address: None,
+                    state: Some("live".to_string()),
+                    ana_state: Some("optimized".to_string()),
+                },
+                NvmeController {
+                    name: "nvme1".to_string(),
+                    transport: "tcp".to_string(),
+                    address: None,
+                    state: Some("live".to_string()),
+                    ana_state: Some("inaccessible".to_string()),
+                }
+            ]
+        );
+
+        Ok(())
+    }
 }
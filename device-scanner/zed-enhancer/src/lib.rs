@@ -2,7 +2,11 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
-use device_types::zed::{zfs, zpool, PoolCommand, ZedCommand};
+use device_types::zed::{
+    zfs,
+    zpool::{self, Scan, ScanKind, ScanState},
+    PoolCommand, ZedCommand,
+};
 use futures::TryStreamExt;
 use std::{io, num, result};
 use tokio::{
@@ -50,7 +54,8 @@ pub fn handle_zed_commands(cmd: ZedCommand) -> Result<PoolCommand> {
         }
 
         ZedCommand::ImportZpool(zpool::Name(name), guid, _)
-        | ZedCommand::AddVdev(zpool::Name(name), guid) => {
+        | ZedCommand::AddVdev(zpool::Name(name), guid)
+        | ZedCommand::VdevStateChange(zpool::Name(name), guid, _, _) => {
             let guid = guid_to_u64(guid)?;
             let pool = libzfs::get_pool_by_name_and_guid(&name, guid)?;
 
@@ -71,6 +76,33 @@ pub fn handle_zed_commands(cmd: ZedCommand) -> Result<PoolCommand> {
         ZedCommand::SetZfsProp(guid, name, key, value) => {
             Ok(PoolCommand::SetZfsProp(guid, name, key, value))
         }
+        ZedCommand::VdevErrors(guid, vdev_guid, errors) => {
+            Ok(PoolCommand::SetVdevErrors(guid, vdev_guid, errors))
+        }
+        ZedCommand::ScanStart(guid, kind, time) => Ok(PoolCommand::SetScan(
+            guid,
+            Scan {
+                kind,
+                state: ScanState::Scanning,
+                time,
+            },
+        )),
+        ZedCommand::ScanFinish(guid, kind, time) => Ok(PoolCommand::SetScan(
+            guid,
+            Scan {
+                kind,
+                state: ScanState::Finished,
+                time,
+            },
+        )),
+        ZedCommand::ScrubAbort(guid, time) => Ok(PoolCommand::SetScan(
+            guid,
+            Scan {
+                kind: ScanKind::Scrub,
+                state: ScanState::Canceled,
+                time,
+            },
+        )),
     }
 }
 
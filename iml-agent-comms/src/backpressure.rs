@@ -0,0 +1,130 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{metrics::Metrics, session::Sessions};
+use iml_wire_types::{Id, ManagerMessage, PluginName};
+use std::collections::HashMap;
+
+/// The last poll delay sent to each plugin session on a host.
+pub type PollDelays = HashMap<PluginName, (Id, u64)>;
+
+/// Computes the `ManagerMessage::Backpressure` messages that need to be sent to a host.
+///
+/// A message is only generated when the desired poll delay for a session
+/// differs from the one last sent, so agents are told once when a downstream
+/// queue goes over the limit and once again when it recovers.
+pub fn get_messages(
+    sessions: &Sessions,
+    sent: &mut PollDelays,
+    metrics: &Metrics,
+    delay: u64,
+) -> Vec<ManagerMessage> {
+    sent.retain(|plugin, _| sessions.contains_key(plugin));
+
+    sessions
+        .values()
+        .filter_map(|s| {
+            let desired = if metrics.is_congested(&s.plugin) {
+                delay
+            } else {
+                0
+            };
+
+            let last = sent
+                .get(&s.plugin)
+                .filter(|(id, _)| id == &s.id)
+                .map(|(_, x)| *x)
+                .unwrap_or(0);
+
+            if last == desired {
+                return None;
+            }
+
+            sent.insert(s.plugin.clone(), (s.id.clone(), desired));
+
+            Some(ManagerMessage::Backpressure {
+                fqdn: s.fqdn.clone(),
+                plugin: s.plugin.clone(),
+                session_id: s.id.clone(),
+                poll_delay: desired,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::Session;
+
+    fn create_sessions() -> (Sessions, Session) {
+        let s = Session::new("stats".into(), "oss1.local".into());
+
+        let mut sessions = Sessions::new();
+        sessions.insert(s.plugin.clone(), s.clone());
+
+        (sessions, s)
+    }
+
+    #[test]
+    fn test_no_messages_when_not_congested() {
+        let (sessions, _) = create_sessions();
+        let mut sent = PollDelays::new();
+
+        let xs = get_messages(&sessions, &mut sent, &Metrics::default(), 30);
+
+        assert!(xs.is_empty());
+    }
+
+    #[test]
+    fn test_messages_sent_on_change() {
+        let (sessions, s) = create_sessions();
+        let mut sent = PollDelays::new();
+        let mut metrics = Metrics::default();
+
+        metrics.set_queue_depth(&s.plugin, 100, Some(10));
+
+        let xs = get_messages(&sessions, &mut sent, &metrics, 30);
+
+        assert_eq!(xs.len(), 1);
+        assert!(matches!(
+            xs[0],
+            ManagerMessage::Backpressure { poll_delay: 30, .. }
+        ));
+
+        let xs = get_messages(&sessions, &mut sent, &metrics, 30);
+
+        assert!(xs.is_empty());
+
+        metrics.set_queue_depth(&s.plugin, 0, Some(10));
+
+        let xs = get_messages(&sessions, &mut sent, &metrics, 30);
+
+        assert_eq!(xs.len(), 1);
+        assert!(matches!(
+            xs[0],
+            ManagerMessage::Backpressure { poll_delay: 0, .. }
+        ));
+    }
+
+    #[test]
+    fn test_messages_resent_for_new_session() {
+        let (mut sessions, s) = create_sessions();
+        let mut sent = PollDelays::new();
+        let mut metrics = Metrics::default();
+
+        metrics.set_queue_depth(&s.plugin, 100, Some(10));
+
+        get_messages(&sessions, &mut sent, &metrics, 30);
+
+        sessions.insert(
+            s.plugin.clone(),
+            Session::new("stats".into(), "oss1.local".into()),
+        );
+
+        let xs = get_messages(&sessions, &mut sent, &metrics, 30);
+
+        assert_eq!(xs.len(), 1);
+    }
+}
@@ -2,7 +2,7 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
-use crate::session::SharedSessions;
+use crate::{backpressure::PollDelays, session::SharedSessions};
 use futures::{channel::oneshot, lock::Mutex};
 use iml_wire_types::Fqdn;
 use std::{
@@ -21,6 +21,7 @@ pub struct Host {
     pub stop_reading: Option<oneshot::Sender<Vec<Vec<u8>>>>,
     pub queue: Arc<Mutex<VecDeque<Vec<u8>>>>,
     pub sessions: SharedSessions,
+    pub poll_delays: PollDelays,
 }
 
 impl Host {
@@ -31,6 +32,7 @@ impl Host {
             stop_reading: None,
             queue: Arc::new(Mutex::new(VecDeque::new())),
             sessions: Arc::new(Mutex::new(HashMap::new())),
+            poll_delays: HashMap::new(),
         }
     }
     pub fn stop(&mut self) {
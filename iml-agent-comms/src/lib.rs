@@ -2,8 +2,10 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
+pub mod backpressure;
 pub mod error;
 pub mod flush_queue;
 pub mod host;
 pub mod messaging;
+pub mod metrics;
 pub mod session;
@@ -4,33 +4,47 @@
 
 use futures::{channel::oneshot, FutureExt, TryFutureExt, TryStreamExt};
 use iml_agent_comms::{
+    backpressure,
     error::ImlAgentCommsError,
     flush_queue,
     host::{self, SharedHosts},
     messaging::{consume_agent_tx_queue, AgentData, AGENT_TX_RUST},
+    metrics::{self, plugin_queue_name, SharedMetrics},
     session::{self, Session, Sessions},
 };
-use iml_rabbit::{self, create_connection_filter, send_message, Channel, Connection};
+use iml_rabbit::{
+    self, create_connection_filter, declare_transient_queue, send_message, Channel, Connection,
+    Pool,
+};
 use iml_wire_types::{
     Envelope, Fqdn, ManagerMessage, ManagerMessages, Message, PluginMessage, PluginName,
 };
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::time::interval;
 use warp::Filter;
 
 async fn data_handler(
     has_session: bool,
     ch: Channel,
+    metrics: &SharedMetrics,
     data: AgentData,
 ) -> Result<(), ImlAgentCommsError> {
     if has_session {
         tracing::debug!("Forwarding valid message {}", data);
 
-        let s = format!("rust_agent_{}_rx", data.plugin);
+        metrics.lock().await.inc_message(&data.fqdn, &data.plugin);
+
+        let s = plugin_queue_name(&data.plugin);
 
         send_message(&ch, "", s, PluginMessage::from(data)).await?;
     } else {
         tracing::warn!("Terminating session because unknown {}", data);
 
+        metrics.lock().await.inc_session_terminated(&data.plugin);
+
         send_message(
             &ch,
             "",
@@ -50,6 +64,7 @@ async fn data_handler(
 async fn session_create_req_handler(
     sessions: &mut Sessions,
     ch: Channel,
+    metrics: &SharedMetrics,
     fqdn: Fqdn,
     plugin: PluginName,
 ) -> Result<(), ImlAgentCommsError> {
@@ -57,12 +72,16 @@ async fn session_create_req_handler(
 
     tracing::info!("Creating session {}", session);
 
+    metrics.lock().await.inc_session_created(&plugin);
+
     let last_opt = sessions.insert(plugin.clone(), session.clone());
 
     if let Some(last) = last_opt {
         tracing::warn!("Destroying session {} to create new one", last);
 
-        let s = format!("rust_agent_{}_rx", plugin);
+        metrics.lock().await.inc_session_terminated(&plugin);
+
+        let s = plugin_queue_name(&plugin);
 
         send_message(
             &ch,
@@ -77,7 +96,7 @@ async fn session_create_req_handler(
         .await?;
     }
 
-    let s = format!("rust_agent_{}_rx", plugin.clone());
+    let s = plugin_queue_name(&plugin);
 
     send_message(
         &ch,
@@ -110,6 +129,10 @@ async fn session_create_req_handler(
 struct GetArgs {
     server_boot_time: String,
     client_start_time: String,
+    /// Set by agents that understand `ManagerMessage::Backpressure`.
+    /// Older agents fail to read messages they do not know, so they are never sent one.
+    #[serde(default)]
+    backpressure: bool,
 }
 
 #[derive(serde::Deserialize, Debug)]
@@ -117,6 +140,51 @@ struct MessageFqdn {
     pub fqdn: Fqdn,
 }
 
+/// Reads the depth of each known plugin queue
+/// and records it in the shared `Metrics`.
+async fn update_queue_depths(
+    pool: &Pool,
+    metrics: &SharedMetrics,
+    limit: Option<u32>,
+) -> Result<(), ImlAgentCommsError> {
+    let plugins = metrics.lock().await.plugins();
+
+    if plugins.is_empty() {
+        return Ok(());
+    }
+
+    let conn = iml_rabbit::get_conn(pool.clone()).await?;
+
+    let ch = iml_rabbit::create_channel(&conn).await?;
+
+    drop(conn);
+
+    for plugin in plugins {
+        let q = declare_transient_queue(&ch, plugin_queue_name(&plugin)).await?;
+
+        metrics
+            .lock()
+            .await
+            .set_queue_depth(&plugin, q.message_count(), limit);
+    }
+
+    iml_rabbit::close_channel(&ch).await?;
+
+    Ok(())
+}
+
+async fn poll_queue_depths(pool: Pool, metrics: SharedMetrics, limit: Option<u32>) {
+    let mut s = interval(Duration::from_secs(10));
+
+    loop {
+        s.tick().await;
+
+        if let Err(e) = update_queue_depths(&pool, &metrics, limit).await {
+            tracing::warn!("Could not read queue depths: {:?}", e);
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     iml_tracing::init();
@@ -128,7 +196,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let shared_hosts2 = Arc::clone(&shared_hosts);
     let shared_hosts3 = Arc::clone(&shared_hosts);
 
-    let pool = iml_rabbit::connect_to_rabbit(2);
+    let shared_metrics = metrics::shared_metrics();
+    let shared_metrics2 = Arc::clone(&shared_metrics);
+    let shared_metrics3 = Arc::clone(&shared_metrics);
+    let shared_metrics4 = Arc::clone(&shared_metrics);
+
+    let queue_limit = iml_manager_env::get_agent_comms_queue_limit();
+
+    let poll_delay = iml_manager_env::get_agent_comms_poll_delay();
+
+    let pool = iml_rabbit::connect_to_rabbit(3);
 
     let conn = iml_rabbit::get_conn(pool.clone()).await?;
 
@@ -171,16 +248,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }),
     );
 
+    tokio::spawn(poll_queue_depths(pool.clone(), shared_metrics, queue_limit));
+
     let hosts_filter = warp::any().map(move || Arc::clone(&shared_hosts2));
+    let metrics_filter = warp::any().map(move || Arc::clone(&shared_metrics2));
 
     let receiver = warp::post()
         .and(warp::header::<String>("x-ssl-client-name").map(Fqdn))
         .and(hosts_filter)
+        .and(metrics_filter)
         .and(create_connection_filter(pool))
         .and(warp::body::json())
         .and_then(
             |fqdn: Fqdn,
              hosts: SharedHosts,
+             metrics: SharedMetrics,
              conn: Connection,
              Envelope {
                  messages,
@@ -223,6 +305,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 data_handler(
                                     has_session,
                                     ch.clone(),
+                                    &metrics,
                                     AgentData {
                                         fqdn,
                                         plugin,
@@ -235,8 +318,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                             }
                             Message::SessionCreateRequest { plugin, fqdn } => {
                                 let mut lock = s2.lock().await;
-                                session_create_req_handler(&mut lock, ch.clone(), fqdn, plugin)
-                                    .await?;
+                                session_create_req_handler(
+                                    &mut lock,
+                                    ch.clone(),
+                                    &metrics,
+                                    fqdn,
+                                    plugin,
+                                )
+                                .await?;
                             }
                         }
                     }
@@ -253,81 +342,121 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         );
 
     let hosts_filter = warp::any().map(move || Arc::clone(&shared_hosts3));
+    let metrics_filter = warp::any().map(move || Arc::clone(&shared_metrics3));
 
     let sender = warp::get()
         .and(warp::header::<String>("x-ssl-client-name").map(Fqdn))
         .and(warp::query::<GetArgs>())
         .and(hosts_filter)
-        .and_then(|fqdn: Fqdn, args: GetArgs, hosts: SharedHosts| {
-            async move {
-                {
-                    let mut hosts = hosts.lock().await;
-                    let mut host = host::get_or_insert(
-                        &mut hosts,
-                        fqdn.clone(),
-                        args.client_start_time.clone(),
-                    );
+        .and(metrics_filter)
+        .and_then(
+            move |fqdn: Fqdn, args: GetArgs, hosts: SharedHosts, metrics: SharedMetrics| {
+                async move {
+                    {
+                        let mut hosts = hosts.lock().await;
+                        let mut host = host::get_or_insert(
+                            &mut hosts,
+                            fqdn.clone(),
+                            args.client_start_time.clone(),
+                        );
 
-                    host.stop();
+                        host.stop();
 
-                    // If we are not dealing with the same agent anymore, terminate all existing sessions.
-                    if host.client_start_time != args.client_start_time {
-                        tracing::info!(
-                            "Terminating all sessions on {:?} because start time has changed",
-                            fqdn
-                        );
+                        // If we are not dealing with the same agent anymore, terminate all existing sessions.
+                        if host.client_start_time != args.client_start_time {
+                            tracing::info!(
+                                "Terminating all sessions on {:?} because start time has changed",
+                                fqdn
+                            );
 
-                        host.client_start_time = args.client_start_time;
+                            host.client_start_time = args.client_start_time;
 
-                        return Ok::<_, ImlAgentCommsError>(ManagerMessages {
-                            messages: vec![ManagerMessage::SessionTerminateAll { fqdn }],
-                        });
+                            return Ok::<_, ImlAgentCommsError>(ManagerMessages {
+                                messages: vec![ManagerMessage::SessionTerminateAll { fqdn }],
+                            });
+                        }
                     }
-                }
 
-                let (tx, rx) = oneshot::channel();
+                    let (tx, rx) = oneshot::channel();
 
-                let (sessions, queue) = {
-                    let mut hosts = hosts.lock().await;
+                    let (sessions, queue) = {
+                        let mut hosts = hosts.lock().await;
 
-                    let host = host::get_or_insert(
-                        &mut hosts,
-                        fqdn.clone(),
-                        args.client_start_time.clone(),
-                    );
+                        let host = host::get_or_insert(
+                            &mut hosts,
+                            fqdn.clone(),
+                            args.client_start_time.clone(),
+                        );
+
+                        host.stop_reading = Some(tx);
 
-                    host.stop_reading = Some(tx);
+                        (Arc::clone(&host.sessions), Arc::clone(&host.queue))
+                    };
 
-                    (Arc::clone(&host.sessions), Arc::clone(&host.queue))
-                };
+                    let started = Instant::now();
 
-                let xs = flush_queue::flush(queue, Duration::from_secs(30), rx).await?;
+                    let xs = flush_queue::flush(queue, Duration::from_secs(30), rx).await?;
 
-                let mut xs: Vec<ManagerMessage> = xs
-                    .into_iter()
-                    .map(|x| serde_json::from_slice(&x).map_err(ImlAgentCommsError::from))
-                    .collect::<Result<Vec<_>, ImlAgentCommsError>>()?;
+                    metrics.lock().await.observe_flush(&fqdn, started.elapsed());
 
-                let guard = sessions.lock().await;
+                    let mut xs: Vec<ManagerMessage> = xs
+                        .into_iter()
+                        .map(|x| serde_json::from_slice(&x).map_err(ImlAgentCommsError::from))
+                        .collect::<Result<Vec<_>, ImlAgentCommsError>>()?;
 
-                xs.retain(|x| session::is_session_valid(x, &guard));
+                    let guard = sessions.lock().await;
 
-                tracing::debug!(
-                    "--> Delivery to agent {}({:?}): {:?}",
-                    &fqdn,
-                    &args.client_start_time,
-                    xs,
-                );
+                    xs.retain(|x| session::is_session_valid(x, &guard));
 
-                Ok::<_, ImlAgentCommsError>(ManagerMessages { messages: xs })
-            }
-            .map_err(warp::reject::custom)
-        })
+                    if args.backpressure {
+                        let metrics = metrics.lock().await;
+                        let mut hosts = hosts.lock().await;
+
+                        if let Some(host) = hosts.get_mut(&fqdn) {
+                            xs.extend(backpressure::get_messages(
+                                &guard,
+                                &mut host.poll_delays,
+                                &metrics,
+                                poll_delay,
+                            ));
+                        }
+                    }
+
+                    metrics.lock().await.inc_deliveries(&fqdn, xs.len());
+
+                    tracing::debug!(
+                        "--> Delivery to agent {}({:?}): {:?}",
+                        &fqdn,
+                        &args.client_start_time,
+                        xs,
+                    );
+
+                    Ok::<_, ImlAgentCommsError>(ManagerMessages { messages: xs })
+                }
+                .map_err(warp::reject::custom)
+            },
+        )
         .map(|envelope| warp::reply::json(&envelope));
 
+    let metrics_route = warp::get()
+        .and(warp::path("metrics"))
+        .and(warp::path::end())
+        .and(warp::any().map(move || Arc::clone(&shared_metrics4)))
+        .and_then(|metrics: SharedMetrics| async move {
+            let body = metrics.lock().await.to_prometheus();
+
+            Ok::<_, warp::Rejection>(warp::reply::with_header(
+                body,
+                "content-type",
+                "text/plain; version=0.0.4",
+            ))
+        });
+
     let log = warp::log("iml_agent_comms::api");
 
-    let routes = warp::path("message").and(receiver.or(sender).with(log));
+    let routes = warp::path("message")
+        .and(receiver.or(sender).with(log))
+        .or(metrics_route);
 
     let addr = iml_manager_env::get_http_agent2_addr();
 
@@ -0,0 +1,304 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use futures::lock::Mutex;
+use iml_wire_types::{Fqdn, PluginName};
+use std::{collections::BTreeMap, fmt::Write, sync::Arc, time::Duration};
+
+/// Upper bounds (in seconds) of the flush latency histogram buckets.
+static FLUSH_BUCKETS: [f64; 7] = [0.1, 0.5, 1.0, 5.0, 10.0, 20.0, 30.0];
+
+pub type SharedMetrics = Arc<Mutex<Metrics>>;
+
+pub fn shared_metrics() -> SharedMetrics {
+    Arc::new(Mutex::new(Metrics::default()))
+}
+
+/// Returns the name of the queue a plugin's data is forwarded to.
+pub fn plugin_queue_name(plugin: &PluginName) -> String {
+    format!("rust_agent_{}_rx", plugin)
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Histogram {
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    pub fn observe(&mut self, d: Duration) {
+        let secs = d.as_secs_f64();
+
+        if self.buckets.is_empty() {
+            self.buckets = vec![0; FLUSH_BUCKETS.len()];
+        }
+
+        for (bucket, le) in self.buckets.iter_mut().zip(FLUSH_BUCKETS.iter()) {
+            if secs <= *le {
+                *bucket += 1;
+            }
+        }
+
+        self.sum += secs;
+        self.count += 1;
+    }
+}
+
+/// Counters and gauges collected by agent-comms.
+///
+/// These are rendered in the Prometheus text exposition format
+/// by `Metrics::to_prometheus`.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// `Message::Data` received per host and plugin.
+    pub messages: BTreeMap<(Fqdn, PluginName), u64>,
+    /// `ManagerMessage`s delivered per host.
+    pub deliveries: BTreeMap<Fqdn, u64>,
+    pub sessions_created: BTreeMap<PluginName, u64>,
+    pub sessions_terminated: BTreeMap<PluginName, u64>,
+    /// Last known depth of each `rust_agent_{plugin}_rx` queue.
+    pub queue_depths: BTreeMap<PluginName, u32>,
+    pub flush_latency: BTreeMap<Fqdn, Histogram>,
+    /// Plugins currently under backpressure.
+    pub congested: BTreeMap<PluginName, bool>,
+}
+
+impl Metrics {
+    pub fn inc_message(&mut self, fqdn: &Fqdn, plugin: &PluginName) {
+        *self
+            .messages
+            .entry((fqdn.clone(), plugin.clone()))
+            .or_default() += 1;
+
+        self.queue_depths.entry(plugin.clone()).or_default();
+    }
+    pub fn inc_deliveries(&mut self, fqdn: &Fqdn, count: usize) {
+        *self.deliveries.entry(fqdn.clone()).or_default() += count as u64;
+    }
+    pub fn inc_session_created(&mut self, plugin: &PluginName) {
+        *self.sessions_created.entry(plugin.clone()).or_default() += 1;
+    }
+    pub fn inc_session_terminated(&mut self, plugin: &PluginName) {
+        *self.sessions_terminated.entry(plugin.clone()).or_default() += 1;
+    }
+    pub fn observe_flush(&mut self, fqdn: &Fqdn, d: Duration) {
+        self.flush_latency
+            .entry(fqdn.clone())
+            .or_default()
+            .observe(d);
+    }
+    /// Records the depth of a plugin queue and whether
+    /// it is over the given limit.
+    pub fn set_queue_depth(&mut self, plugin: &PluginName, depth: u32, limit: Option<u32>) {
+        self.queue_depths.insert(plugin.clone(), depth);
+
+        let congested = limit.map(|l| depth > l).unwrap_or(false);
+
+        let was_congested = self.congested.insert(plugin.clone(), congested);
+
+        if was_congested != Some(congested) && congested {
+            tracing::warn!(
+                "Queue {} is over limit ({} > {:?}), applying backpressure",
+                plugin_queue_name(plugin),
+                depth,
+                limit
+            );
+        } else if was_congested == Some(true) && !congested {
+            tracing::info!(
+                "Queue {} is back under limit, releasing backpressure",
+                plugin_queue_name(plugin)
+            );
+        }
+    }
+    pub fn is_congested(&self, plugin: &PluginName) -> bool {
+        self.congested.get(plugin).copied().unwrap_or(false)
+    }
+    /// The plugins with a known downstream queue.
+    pub fn plugins(&self) -> Vec<PluginName> {
+        self.queue_depths.keys().cloned().collect()
+    }
+    /// Renders all metrics using the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+
+        write_header(
+            &mut out,
+            "iml_agent_comms_messages_total",
+            "Data messages received from agents.",
+            "counter",
+        );
+        for ((fqdn, plugin), x) in &self.messages {
+            let _ = writeln!(
+                out,
+                "iml_agent_comms_messages_total{{host=\"{}\",plugin=\"{}\"}} {}",
+                fqdn, plugin, x
+            );
+        }
+
+        write_header(
+            &mut out,
+            "iml_agent_comms_deliveries_total",
+            "Messages delivered to agents.",
+            "counter",
+        );
+        for (fqdn, x) in &self.deliveries {
+            let _ = writeln!(
+                out,
+                "iml_agent_comms_deliveries_total{{host=\"{}\"}} {}",
+                fqdn, x
+            );
+        }
+
+        write_header(
+            &mut out,
+            "iml_agent_comms_sessions_created_total",
+            "Sessions created.",
+            "counter",
+        );
+        for (plugin, x) in &self.sessions_created {
+            let _ = writeln!(
+                out,
+                "iml_agent_comms_sessions_created_total{{plugin=\"{}\"}} {}",
+                plugin, x
+            );
+        }
+
+        write_header(
+            &mut out,
+            "iml_agent_comms_sessions_terminated_total",
+            "Sessions terminated.",
+            "counter",
+        );
+        for (plugin, x) in &self.sessions_terminated {
+            let _ = writeln!(
+                out,
+                "iml_agent_comms_sessions_terminated_total{{plugin=\"{}\"}} {}",
+                plugin, x
+            );
+        }
+
+        write_header(
+            &mut out,
+            "iml_agent_comms_queue_depth",
+            "Messages waiting in a plugin queue.",
+            "gauge",
+        );
+        for (plugin, x) in &self.queue_depths {
+            let _ = writeln!(
+                out,
+                "iml_agent_comms_queue_depth{{queue=\"{}\"}} {}",
+                plugin_queue_name(plugin),
+                x
+            );
+        }
+
+        write_header(
+            &mut out,
+            "iml_agent_comms_backpressure",
+            "Whether agents are being asked to slow down for a plugin.",
+            "gauge",
+        );
+        for (plugin, x) in &self.congested {
+            let _ = writeln!(
+                out,
+                "iml_agent_comms_backpressure{{plugin=\"{}\"}} {}",
+                plugin, *x as u8
+            );
+        }
+
+        write_header(
+            &mut out,
+            "iml_agent_comms_flush_seconds",
+            "Time spent waiting on a host queue before responding to an agent.",
+            "histogram",
+        );
+        for (fqdn, h) in &self.flush_latency {
+            for (le, x) in FLUSH_BUCKETS.iter().zip(h.buckets.iter()) {
+                let _ = writeln!(
+                    out,
+                    "iml_agent_comms_flush_seconds_bucket{{host=\"{}\",le=\"{}\"}} {}",
+                    fqdn, le, x
+                );
+            }
+            let _ = writeln!(
+                out,
+                "iml_agent_comms_flush_seconds_bucket{{host=\"{}\",le=\"+Inf\"}} {}",
+                fqdn, h.count
+            );
+            let _ = writeln!(
+                out,
+                "iml_agent_comms_flush_seconds_sum{{host=\"{}\"}} {}",
+                fqdn, h.sum
+            );
+            let _ = writeln!(
+                out,
+                "iml_agent_comms_flush_seconds_count{{host=\"{}\"}} {}",
+                fqdn, h.count
+            );
+        }
+
+        out
+    }
+}
+
+fn write_header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_observe() {
+        let mut h = Histogram::default();
+
+        h.observe(Duration::from_millis(50));
+        h.observe(Duration::from_secs(7));
+
+        assert_eq!(h.buckets, vec![1, 1, 1, 1, 2, 2, 2]);
+        assert_eq!(h.count, 2);
+    }
+
+    #[test]
+    fn test_set_queue_depth() {
+        let mut m = Metrics::default();
+        let plugin: PluginName = "stats".into();
+
+        m.set_queue_depth(&plugin, 10, Some(5));
+        assert!(m.is_congested(&plugin));
+
+        m.set_queue_depth(&plugin, 2, Some(5));
+        assert!(!m.is_congested(&plugin));
+
+        m.set_queue_depth(&plugin, 1_000, None);
+        assert!(!m.is_congested(&plugin));
+    }
+
+    #[test]
+    fn test_to_prometheus() {
+        let mut m = Metrics::default();
+        let fqdn: Fqdn = "oss1.local".into();
+        let plugin: PluginName = "stats".into();
+
+        m.inc_message(&fqdn, &plugin);
+        m.inc_message(&fqdn, &plugin);
+        m.inc_session_created(&plugin);
+        m.set_queue_depth(&plugin, 3, Some(100));
+        m.observe_flush(&fqdn, Duration::from_secs(1));
+
+        let out = m.to_prometheus();
+
+        assert!(out
+            .contains("iml_agent_comms_messages_total{host=\"oss1.local\",plugin=\"stats\"} 2\n"));
+        assert!(out.contains("iml_agent_comms_sessions_created_total{plugin=\"stats\"} 1\n"));
+        assert!(out.contains("iml_agent_comms_queue_depth{queue=\"rust_agent_stats_rx\"} 3\n"));
+        assert!(out.contains("iml_agent_comms_backpressure{plugin=\"stats\"} 0\n"));
+        assert!(out
+            .contains("iml_agent_comms_flush_seconds_bucket{host=\"oss1.local\",le=\"+Inf\"} 1\n"));
+        assert!(out.contains("# TYPE iml_agent_comms_flush_seconds histogram\n"));
+    }
+}
@@ -55,6 +55,9 @@ pub fn is_session_valid(msg: &ManagerMessage, sessions: &Sessions) -> bool {
         }
         | ManagerMessage::Data {
             session_id, plugin, ..
+        }
+        | ManagerMessage::Backpressure {
+            session_id, plugin, ..
         } => get_by_session_id(&plugin, &session_id, sessions).is_some(),
     };
 
@@ -5,12 +5,13 @@
 use crate::{
     action_plugins::{
         check_kernel, check_stonith, firewall_cmd, high_availability, kernel_module, lamigo, ldev,
-        lpurge, lustre,
+        lnet, lpurge, lustre,
         ntp::{action_configure, is_ntp_configured},
         ostpool, package, postoffice,
         stratagem::{
             action_cloudsync, action_filesync, action_mirror, action_purge, action_warning, server,
         },
+        tunable, zpool,
     },
     lustre::lctl,
 };
@@ -30,12 +31,18 @@ pub fn create_registry() -> action_plugins::Actions {
         .add_plugin("get_unit_run_state", iml_systemd::get_run_state)
         .add_plugin("kernel_module_loaded", kernel_module::loaded)
         .add_plugin("kernel_module_version", kernel_module::version)
+        .add_plugin("lustre_modules_reload", kernel_module::reload_lustre)
         .add_plugin("package_installed", package::installed)
         .add_plugin("package_version", package::version)
         .add_plugin("start_scan_stratagem", server::trigger_scan)
         .add_plugin("stream_fidlists_stratagem", server::stream_fidlists)
         .add_plugin("action_check_ha", high_availability::check_ha)
         .add_plugin("action_check_stonith", check_stonith::check_stonith)
+        .add_plugin(
+            "action_validate_stonith",
+            check_stonith::validate_stonith_nodes,
+        )
+        .add_plugin("action_test_stonith", check_stonith::test_stonith)
         .add_plugin("get_kernel", check_kernel::get_kernel)
         .add_plugin(
             "get_ha_resource_list",
@@ -50,6 +57,8 @@ pub fn create_registry() -> action_plugins::Actions {
         .add_plugin("ha_resource_start", high_availability::start_resource)
         .add_plugin("ha_resource_stop", high_availability::stop_resource)
         .add_plugin("ha_resource_move", high_availability::move_resource)
+        .add_plugin("ha_resource_ban", high_availability::ban_resource)
+        .add_plugin("ha_resource_clear", high_availability::clear_resource)
         .add_plugin(
             "ha_resource_create",
             high_availability::create_single_resource,
@@ -67,6 +76,15 @@ pub fn create_registry() -> action_plugins::Actions {
             "ha_cloned_client_destroy",
             high_availability::destroy_cloned_client,
         )
+        .add_plugin("ha_constraint_create", high_availability::create_constraint)
+        .add_plugin("ha_constraint_list", high_availability::list_constraints)
+        .add_plugin(
+            "ha_constraint_destroy",
+            high_availability::destroy_constraint,
+        )
+        .add_plugin("ha_group_create", high_availability::create_group)
+        .add_plugin("ha_group_list", high_availability::list_groups)
+        .add_plugin("ha_group_destroy", high_availability::destroy_group)
         .add_plugin("crm_attribute", high_availability::crm_attribute)
         .add_plugin(
             "change_mcast_port",
@@ -85,8 +103,33 @@ pub fn create_registry() -> action_plugins::Actions {
         .add_plugin("snapshot_destroy", lustre::snapshot::destroy)
         .add_plugin("snapshot_mount", lustre::snapshot::mount)
         .add_plugin("snapshot_unmount", lustre::snapshot::unmount)
+        .add_plugin("target_format", lustre::target::format)
+        .add_plugin(
+            "target_mountpoint_create",
+            lustre::target::create_mountpoint,
+        )
+        .add_plugin("quota_set", lustre::quota::set)
+        .add_plugin("quota_set_grace", lustre::quota::set_grace)
+        .add_plugin("quota_get", lustre::quota::get)
+        .add_plugin("layout_get", lustre::layout::get)
+        .add_plugin("layout_set", lustre::layout::set)
+        .add_plugin("changelog_register", lustre::changelog::register)
+        .add_plugin("changelog_deregister", lustre::changelog::deregister)
+        .add_plugin("nodemap_get", lustre::nodemap::get)
+        .add_plugin("nodemap_change", lustre::nodemap::change)
+        .add_plugin("tunable_set", tunable::set)
+        .add_plugin("tunable_watch", tunable::watch)
         .add_plugin("postoffice_add", postoffice::route_add)
         .add_plugin("postoffice_remove", postoffice::route_remove)
+        .add_plugin("zpool_scrub_start", zpool::scrub_start)
+        .add_plugin("zpool_scrub_stop", zpool::scrub_stop)
+        .add_plugin("lnet_net_add", lnet::net_add)
+        .add_plugin("lnet_net_del", lnet::net_del)
+        .add_plugin("lnet_route_add", lnet::route_add)
+        .add_plugin("lnet_route_del", lnet::route_del)
+        .add_plugin("lnet_import", lnet::import)
+        .add_plugin("lnet_export", lnet::export)
+        .add_plugin("lnet_persist", lnet::persist)
         .add_plugin(
             "configure_ntp",
             action_configure::update_and_write_new_config,
@@ -96,6 +139,9 @@ pub fn create_registry() -> action_plugins::Actions {
         // HotPools
         .add_plugin("create_lpurge_conf", lpurge::create_lpurge_conf)
         .add_plugin("create_lamigo_conf", lamigo::create_lamigo_conf)
+        // FLR Mirrors
+        .add_plugin("mirror_find", action_mirror::find)
+        .add_plugin("mirror_find_to_mailbox", action_mirror::find_to_mailbox)
         // Task Actions
         .add_plugin("action.mirror.extend", action_mirror::process_extend_fids)
         .add_plugin("action.mirror.resync", action_mirror::process_resync_fids)
@@ -2,11 +2,22 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
-use crate::agent_error::{CibError, ImlAgentError};
+use crate::{
+    agent_error::{CibError, ImlAgentError},
+    high_availability::{crm_mon_cmd, read_crm_output, CIB_NO_SUCH_OBJECT},
+};
 use elementtree::Element;
+use futures::lock::Mutex;
 use iml_cmd::{CheckedCommandExt, Command};
-use iml_wire_types::{ComponentState, ConfigState, RunState, ServiceState};
-use std::default::Default;
+use iml_wire_types::{
+    high_availability::{StonithDeviceStatus, StonithNode, StonithTarget},
+    ComponentState, ConfigState, RunState, ServiceState,
+};
+use lazy_static::lazy_static;
+use std::{
+    collections::{HashMap, HashSet},
+    default::Default,
+};
 
 /// This processes <instance_attributes> section of a stonith
 /// "<primitive>" type from the pacemaker cib.
@@ -180,11 +191,313 @@ pub async fn check_stonith(_: ()) -> Result<ComponentState<bool>, ImlAgentError>
     do_check_stonith(stonith.stdout.as_slice(), &String::from_utf8(node.stdout)?)
 }
 
+/// Parameters pacemaker fills in itself when invoking a fence agent.
+static PACEMAKER_PARAMS: [&str; 4] = ["action", "nodename", "plug", "port"];
+
+/// A parameter a fence agent requires to be set on its primitive.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RequiredParam {
+    pub(crate) name: String,
+    /// The deprecated name this parameter replaces, which may be set instead
+    pub(crate) obsoletes: Option<String>,
+}
+
+/// Parses the required parameters out of `stonith_admin --metadata` output.
+///
+/// Deprecated parameters, parameters pacemaker provides and parameters
+/// with a default value are skipped.
+pub(crate) fn parse_required_params(xml: &[u8]) -> Result<Vec<RequiredParam>, ImlAgentError> {
+    let elem = Element::from_reader(xml)?;
+
+    let params = match elem.find("parameters") {
+        Some(x) => x,
+        None => return Ok(vec![]),
+    };
+
+    let xs = params
+        .find_all("parameter")
+        .filter(|p| p.get_attr("required") == Some("1"))
+        .filter(|p| p.get_attr("deprecated") != Some("1"))
+        .filter(|p| {
+            p.find("content")
+                .and_then(|c| c.get_attr("default"))
+                .filter(|d| !d.is_empty())
+                .is_none()
+        })
+        .filter_map(|p| Some((p.get_attr("name")?, p.get_attr("obsoletes"))))
+        .filter(|(name, _)| !PACEMAKER_PARAMS.contains(name))
+        .map(|(name, obsoletes)| RequiredParam {
+            name: name.to_string(),
+            obsoletes: obsoletes.map(String::from),
+        })
+        .collect();
+
+    Ok(xs)
+}
+
+fn instance_attrs(elem: &Element) -> HashMap<&str, &str> {
+    elem.find("instance_attributes")
+        .map(|x| {
+            x.children()
+                .filter_map(|e| Some((e.get_attr("name")?, e.get_attr("value").unwrap_or(""))))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn check_required_params(elem: &Element, params: &[RequiredParam]) -> Result<(), String> {
+    let attrs = instance_attrs(elem);
+
+    let missing: Vec<&str> = params
+        .iter()
+        .filter(|p| {
+            !attrs.contains_key(p.name.as_str())
+                && p.obsoletes
+                    .as_deref()
+                    .filter(|x| attrs.contains_key(x))
+                    .is_none()
+        })
+        .map(|p| p.name.as_str())
+        .collect();
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "missing required parameters: {}",
+            missing.join(", ")
+        ))
+    }
+}
+
+/// Returns the stonith primitives of a cib query result, which is either
+/// a single `<primitive>` or an `<xpath-query>` wrapping several.
+fn stonith_primitives(elem: &Element) -> Result<Vec<&Element>, ImlAgentError> {
+    match elem.tag().name() {
+        "primitive" => Ok(vec![elem]),
+        "xpath-query" => Ok(elem.find_all("primitive").collect()),
+        tag => Err(ImlAgentError::CibError(CibError(format!(
+            "Unknown first tag {}",
+            tag
+        )))),
+    }
+}
+
+/// Checks whether a single stonith primitive is able to fence `node`.
+fn check_device(
+    elem: &Element,
+    node: &str,
+    params: &HashMap<String, Vec<RequiredParam>>,
+) -> Result<(), String> {
+    let t = elem.get_attr("type").ok_or("missing type attribute")?;
+
+    if t == "fence_chroma" {
+        return match elem.find("instance_attributes") {
+            None => Err(format!("{} - unconfigured", t)),
+            Some(_) => Ok(()),
+        };
+    }
+
+    check_meta_attr(elem.find("meta_attributes"))
+        .and_then(|_| check_instance_attr(elem.find("instance_attributes"), node))
+        .and_then(|_| {
+            params
+                .get(t)
+                .map(|xs| check_required_params(elem, xs))
+                .unwrap_or(Ok(()))
+        })
+        .map_err(|e| format!("{} - {}", t, e))
+}
+
+/// Validates that every node in `nodes` is covered by at least one fence device.
+///
+/// `params` maps a fence agent type to the parameters it requires.
+pub(crate) fn validate_stonith(
+    elem: &Element,
+    nodes: &[String],
+    params: &HashMap<String, Vec<RequiredParam>>,
+) -> Result<Vec<StonithNode>, ImlAgentError> {
+    let primitives = stonith_primitives(elem)?;
+
+    let xs = nodes
+        .iter()
+        .map(|node| {
+            let mut x = StonithNode {
+                node: node.to_string(),
+                ..Default::default()
+            };
+
+            if primitives.is_empty() {
+                x.errors.push("No fence devices configured".to_string());
+            }
+
+            for p in &primitives {
+                let id = p.get_attr("id").unwrap_or("<MISSING>");
+
+                match check_device(p, node, params) {
+                    Ok(_) => x.devices.push(id.to_string()),
+                    Err(e) => x.errors.push(format!("{}: {}", id, e)),
+                }
+            }
+
+            x
+        })
+        .collect();
+
+    Ok(xs)
+}
+
+lazy_static! {
+    static ref AGENT_PARAMS: Mutex<HashMap<String, Vec<RequiredParam>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Returns the required parameters of each fence agent type, caching
+/// `stonith_admin --metadata` output for the lifetime of the agent.
+async fn get_required_params(
+    types: HashSet<&str>,
+) -> Result<HashMap<String, Vec<RequiredParam>>, ImlAgentError> {
+    let mut cache = AGENT_PARAMS.lock().await;
+
+    for t in types {
+        if cache.contains_key(t) {
+            continue;
+        }
+
+        let x = Command::new("stonith_admin")
+            .kill_on_drop(true)
+            .args(&["--metadata", "--agent", t])
+            .output()
+            .await?;
+
+        // Agents without usable metadata are validated by the cib checks alone.
+        let params = if x.status.success() {
+            parse_required_params(&x.stdout).unwrap_or_else(|e| {
+                tracing::warn!("Could not parse metadata of fence agent {}: {}", t, e);
+                vec![]
+            })
+        } else {
+            tracing::debug!("No metadata for fence agent {}", t);
+            vec![]
+        };
+
+        cache.insert(t.to_string(), params);
+    }
+
+    Ok(cache.clone())
+}
+
+/// Validates the stonith configuration of the given cluster nodes.
+///
+/// Returns an empty list when pacemaker is not running.
+pub async fn get_stonith_nodes(nodes: &[String]) -> Result<Vec<StonithNode>, ImlAgentError> {
+    let stonith = Command::new("cibadmin")
+        .kill_on_drop(true)
+        .args(&["--query", "--xpath", "//primitive[@class='stonith']"])
+        .output()
+        .await?;
+
+    if matches!(stonith.status.code(), Some(x) if CIB_NO_SUCH_OBJECT.contains(&x)) {
+        return validate_stonith(&Element::new("xpath-query"), nodes, &HashMap::new());
+    }
+
+    if !stonith.status.success() {
+        return Ok(vec![]);
+    }
+
+    let elem = Element::from_reader(stonith.stdout.as_slice())?;
+
+    let types = stonith_primitives(&elem)?
+        .into_iter()
+        .filter_map(|x| x.get_attr("type"))
+        .filter(|x| x != &"fence_chroma")
+        .collect();
+
+    let params = get_required_params(types).await?;
+
+    validate_stonith(&elem, nodes, &params)
+}
+
+async fn get_cluster_nodes() -> Result<Vec<String>, ImlAgentError> {
+    let x = crm_mon_cmd().checked_output().await?;
+
+    let cluster = read_crm_output(&x.stdout)?;
+
+    Ok(cluster.nodes.into_iter().map(|x| x.name).collect())
+}
+
+/// Validates that every cluster node can be fenced by a correctly configured device.
+pub async fn validate_stonith_nodes(_: ()) -> Result<Vec<StonithNode>, ImlAgentError> {
+    let nodes = get_cluster_nodes().await?;
+
+    get_stonith_nodes(&nodes).await
+}
+
+/// Parses `stonith_admin --list` output into fence device ids.
+fn parse_stonith_list(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|x| !x.is_empty() && !x.ends_with("found"))
+        .map(String::from)
+        .collect()
+}
+
+/// Checks each node can be seen by at least one fence device, without fencing anything.
+///
+/// Runs `stonith_admin --list` for each cluster node, followed by
+/// `stonith_admin --query` for each device found.
+pub async fn test_stonith(_: ()) -> Result<Vec<StonithTarget>, ImlAgentError> {
+    let nodes = get_cluster_nodes().await?;
+
+    let mut xs = vec![];
+
+    for node in nodes {
+        let list = Command::new("stonith_admin")
+            .kill_on_drop(true)
+            .args(&["--list", &node])
+            .output()
+            .await?;
+
+        let mut devices = vec![];
+
+        for device in parse_stonith_list(&String::from_utf8_lossy(&list.stdout)) {
+            let query = Command::new("stonith_admin")
+                .kill_on_drop(true)
+                .args(&["--query", &device])
+                .output()
+                .await?;
+
+            let message = if query.status.success() {
+                String::from_utf8_lossy(&query.stdout)
+            } else {
+                String::from_utf8_lossy(&query.stderr)
+            };
+
+            devices.push(StonithDeviceStatus {
+                device,
+                reachable: query.status.success(),
+                message: message.trim().to_string(),
+            });
+        }
+
+        xs.push(StonithTarget { node, devices });
+    }
+
+    Ok(xs)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::do_check_stonith;
-    use iml_wire_types::{ComponentState, ConfigState, RunState, ServiceState};
-    use std::default::Default;
+    use super::{
+        do_check_stonith, parse_required_params, parse_stonith_list, validate_stonith,
+        RequiredParam,
+    };
+    use elementtree::Element;
+    use iml_wire_types::{
+        high_availability::StonithNode, ComponentState, ConfigState, RunState, ServiceState,
+    };
+    use std::{collections::HashMap, default::Default};
 
     #[test]
     fn test_stonith_unconfigured_fence_chroma() {
@@ -319,4 +632,116 @@ mod tests {
             }
         );
     }
+
+    fn ipmilan_params() -> HashMap<String, Vec<RequiredParam>> {
+        let xs = parse_required_params(include_bytes!("fixtures/check_stonith_test_metadata.xml"))
+            .unwrap();
+
+        vec![("fence_ipmilan".to_string(), xs)]
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_required_params() {
+        assert_eq!(
+            ipmilan_params().remove("fence_ipmilan").unwrap(),
+            vec![
+                RequiredParam {
+                    name: "ip".to_string(),
+                    obsoletes: Some("ipaddr".to_string()),
+                },
+                RequiredParam {
+                    name: "username".to_string(),
+                    obsoletes: Some("login".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_stonith_multihost_static() {
+        let elem = Element::from_reader(multihost_testxml_static()).unwrap();
+
+        let nodes = vec![
+            "host0".to_string(),
+            "host2".to_string(),
+            "host3".to_string(),
+        ];
+
+        assert_eq!(
+            validate_stonith(&elem, &nodes, &ipmilan_params()).unwrap(),
+            vec![
+                StonithNode {
+                    node: "host0".to_string(),
+                    devices: vec!["stonith-host0".to_string()],
+                    errors: vec![
+                        "stonith-host1: fence_ipmilan - host0 missing from host list".to_string()
+                    ],
+                },
+                StonithNode {
+                    node: "host2".to_string(),
+                    devices: vec!["stonith-host1".to_string()],
+                    errors: vec![
+                        "stonith-host0: fence_ipmilan - host2 missing from host list".to_string()
+                    ],
+                },
+                StonithNode {
+                    node: "host3".to_string(),
+                    devices: vec![],
+                    errors: vec![
+                        "stonith-host0: fence_ipmilan - host3 missing from host list".to_string(),
+                        "stonith-host1: fence_ipmilan - host3 missing from host list".to_string(),
+                    ],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_stonith_missing_params() {
+        let elem = Element::from_reader(
+            r#"<primitive class="stonith" id="ipmi" type="fence_ipmilan">
+    <instance_attributes id="ipmi-instance_attributes">
+      <nvpair id="ipmi-instance_attributes-ip" name="ip" value="10.0.1.10"/>
+    </instance_attributes>
+  </primitive>"#
+                .as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            validate_stonith(&elem, &["host0".to_string()], &ipmilan_params()).unwrap(),
+            vec![StonithNode {
+                node: "host0".to_string(),
+                devices: vec![],
+                errors: vec![
+                    "ipmi: fence_ipmilan - missing required parameters: username".to_string()
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate_stonith_no_devices() {
+        let elem = Element::from_reader("<xpath-query/>".as_bytes()).unwrap();
+
+        let xs = validate_stonith(&elem, &["host0".to_string()], &HashMap::new()).unwrap();
+
+        assert!(!xs[0].is_covered());
+        assert_eq!(
+            xs[0].errors,
+            vec!["No fence devices configured".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_parse_stonith_list() {
+        let output = "stonith-host0\nstonith-host1\n2 devices found\n";
+
+        assert_eq!(
+            parse_stonith_list(output),
+            vec!["stonith-host0".to_string(), "stonith-host1".to_string()]
+        );
+    }
 }
@@ -8,7 +8,8 @@ pub(crate) mod pacemaker;
 use crate::{
     agent_error::{ImlAgentError, RequiredError},
     high_availability::{
-        cibcreate, cibxpath, crm_mon_cmd, crm_resource, read_crm_output, set_resource_role,
+        cib_update_resources, cibcreate, cibxpath, cibxpath_exists, crm_mon_cmd, crm_resource,
+        get_constraints, get_groups, read_crm_output, set_resource_role,
     },
 };
 use elementtree::Element;
@@ -16,6 +17,7 @@ use futures::{future::try_join_all, try_join};
 use iml_cmd::{CheckedCommandExt, Command};
 use iml_fs::file_exists;
 use iml_wire_types::{
+    high_availability::{Resource, ResourceGroup},
     ComponentState, ConfigState, LossPolicy, PacemakerActions, PacemakerKindOrScore,
     PacemakerOperations, PacemakerScore, ResourceAgentInfo, ResourceAgentType, ResourceConstraint,
     ServiceState,
@@ -30,6 +32,9 @@ const NO_EXTRA: Vec<String> = vec![];
 const WAIT_SEC: u64 = 300;
 const WAIT_DELAY: u64 = 2;
 
+/// Number of consecutive polls a moved resource has to stay healthy for
+const HEALTHY_POLLS: u32 = 3;
+
 fn create(elem: &Element) -> ResourceAgentInfo {
     ResourceAgentInfo {
         agent: {
@@ -394,6 +399,87 @@ fn resource_xml_string(agent: &ResourceAgentInfo, cloned: bool) -> Result<String
     Ok(rc)
 }
 
+/// Builds the CIB element for a constraint.
+///
+/// `map_id` is applied to every resource id referenced by the constraint.
+fn constraint_element(
+    constraint: ResourceConstraint,
+    map_id: impl Fn(String) -> String,
+) -> Element {
+    match constraint {
+        ResourceConstraint::Order {
+            id,
+            first,
+            first_action,
+            then,
+            then_action,
+            kind,
+        } => {
+            let mut con = Element::new("rsc_order");
+            match kind {
+                Some(PacemakerKindOrScore::Kind(kind)) => {
+                    con.set_attr("kind", kind.to_string());
+                }
+                Some(PacemakerKindOrScore::Score(score)) => {
+                    con.set_attr("score", score.to_string());
+                }
+                None => (),
+            }
+            con.set_attr("id", id)
+                .set_attr("first", map_id(first))
+                .set_attr("then", map_id(then));
+            if let Some(action) = first_action {
+                con.set_attr("first-action", action.to_string());
+            }
+            if let Some(action) = then_action {
+                con.set_attr("then-action", action.to_string());
+            }
+            con
+        }
+        ResourceConstraint::Location {
+            id,
+            rsc,
+            node,
+            score,
+        } => {
+            let mut con = Element::new("rsc_location");
+            con.set_attr("id", id)
+                .set_attr("rsc", map_id(rsc))
+                .set_attr("node", node)
+                .set_attr("score", score.to_string());
+            con
+        }
+        ResourceConstraint::Colocation {
+            id,
+            rsc,
+            with_rsc,
+            score,
+        } => {
+            let mut con = Element::new("rsc_colocation");
+            con.set_attr("id", id)
+                .set_attr("rsc", map_id(rsc))
+                .set_attr("with-rsc", map_id(with_rsc))
+                .set_attr("score", score.to_string());
+            con
+        }
+        ResourceConstraint::Ticket {
+            id,
+            rsc,
+            ticket,
+            loss_policy,
+        } => {
+            let mut con = Element::new("rsc_ticket");
+            con.set_attr("id", id)
+                .set_attr("rsc", map_id(rsc))
+                .set_attr("ticket", ticket);
+            if let Some(policy) = loss_policy {
+                con.set_attr("loss-policy", policy.to_string());
+            }
+            con
+        }
+    }
+}
+
 async fn create_resource(
     agent: ResourceAgentInfo,
     cloned: bool,
@@ -404,82 +490,7 @@ async fn create_resource(
     cibcreate("resources", &xml).await?;
 
     for constraint in constraints {
-        let xml = {
-            let con = match constraint {
-                ResourceConstraint::Order {
-                    id,
-                    first,
-                    first_action,
-                    then,
-                    then_action,
-                    kind,
-                } => {
-                    let mut con = Element::new("rsc_order");
-                    match kind {
-                        Some(PacemakerKindOrScore::Kind(kind)) => {
-                            con.set_attr("kind", kind.to_string());
-                        }
-                        Some(PacemakerKindOrScore::Score(score)) => {
-                            con.set_attr("score", score.to_string());
-                        }
-                        None => (),
-                    }
-                    con.set_attr("id", id)
-                        .set_attr("first", check_id(cloned, &agent.id, first))
-                        .set_attr("then", check_id(cloned, &agent.id, then));
-                    if let Some(action) = first_action {
-                        con.set_attr("first-action", action.to_string());
-                    }
-                    if let Some(action) = then_action {
-                        con.set_attr("then-action", action.to_string());
-                    }
-                    con
-                }
-                ResourceConstraint::Location {
-                    id,
-                    rsc,
-                    node,
-                    score,
-                } => {
-                    let mut con = Element::new("rsc_location");
-                    con.set_attr("id", id)
-                        .set_attr("rsc", check_id(cloned, &agent.id, rsc))
-                        .set_attr("node", node)
-                        .set_attr("score", score.to_string());
-                    con
-                }
-                ResourceConstraint::Colocation {
-                    id,
-                    rsc,
-                    with_rsc,
-                    score,
-                } => {
-                    let mut con = Element::new("rsc_colocation");
-                    con.set_attr("id", id)
-                        .set_attr("rsc", check_id(cloned, &agent.id, rsc))
-                        .set_attr("with-rsc", check_id(cloned, &agent.id, with_rsc))
-                        .set_attr("score", score.to_string());
-                    con
-                }
-                ResourceConstraint::Ticket {
-                    id,
-                    rsc,
-                    ticket,
-                    loss_policy,
-                } => {
-                    let mut con = Element::new("rsc_ticket");
-                    con.set_attr("id", id)
-                        .set_attr("rsc", check_id(cloned, &agent.id, rsc))
-                        .set_attr("ticket", ticket);
-                    if let Some(policy) = loss_policy {
-                        con.set_attr("loss-policy", policy.to_string());
-                    }
-                    con
-                }
-            };
-
-            con.to_string()?
-        };
+        let xml = constraint_element(constraint, |x| check_id(cloned, &agent.id, x)).to_string()?;
         cibcreate("constraints", &xml).await?;
     }
 
@@ -623,9 +634,106 @@ pub async fn stop_resource(resource: String) -> Result<(), ImlAgentError> {
     Ok(())
 }
 
+async fn get_resource(resource: &str) -> Result<Option<Resource>, ImlAgentError> {
+    let output = crm_mon_cmd().checked_output().await?;
+    let cluster = read_crm_output(&output.stdout)?;
+
+    Ok(cluster.resources.into_iter().find(|r| r.id == resource))
+}
+
+async fn active_node(resource: &str) -> Result<Option<String>, ImlAgentError> {
+    Ok(get_resource(resource)
+        .await?
+        .filter(|r| r.active && r.role == "Started")
+        .and_then(|r| r.active_node_name))
+}
+
+/// Whether `r` is started on a single node, managed and without failed operations.
+fn is_healthy(r: &Resource) -> bool {
+    r.active
+        && r.role == "Started"
+        && r.managed
+        && (!r.failed || r.failure_ignored)
+        && r.nodes_running_on == 1
+}
+
+/// Waits for `resource` to stay healthy on a node accepted by `check`
+/// for `HEALTHY_POLLS` consecutive polls.
+/// Returns the node the resource is running on.
+async fn wait_resource_node(
+    resource: &str,
+    check: impl Fn(&str) -> bool,
+) -> Result<String, ImlAgentError> {
+    let delay_duration = Duration::new(WAIT_DELAY, 0);
+
+    let mut healthy = 0;
+
+    for _ in 0..(WAIT_SEC / WAIT_DELAY) {
+        let r = get_resource(resource).await?;
+
+        if let Some(r) = r.as_ref().filter(|r| r.failed && !r.failure_ignored) {
+            return Err(ImlAgentError::from(RequiredError(format!(
+                "Resource {} failed on {}",
+                resource,
+                r.active_node_name.as_deref().unwrap_or("an unknown node")
+            ))));
+        }
+
+        match r.filter(is_healthy).and_then(|r| r.active_node_name) {
+            Some(node) if check(&node) => {
+                healthy += 1;
+
+                if healthy >= HEALTHY_POLLS {
+                    return Ok(node);
+                }
+            }
+            _ => healthy = 0,
+        }
+
+        delay_for(delay_duration).await;
+    }
+
+    Err(ImlAgentError::from(RequiredError(format!(
+        "Waiting for resource {} to be healthy failed after {} sec",
+        resource, WAIT_SEC,
+    ))))
+}
+
+/// Ban a resource from a node, and wait for it to be healthy on another node.
+///
+/// The ban is left in place so the resource does not move back until
+/// `clear_resource` is called.
+pub async fn ban_resource((resource, node): (String, String)) -> Result<(), ImlAgentError> {
+    crm_resource(&["--resource", &resource, "--ban", "--node", &node]).await?;
+
+    let dest = wait_resource_node(&resource, |x| x != node).await?;
+
+    tracing::info!("Resource {} moved from {} to {}", resource, node, dest);
+
+    Ok(())
+}
+
+/// Remove any bans placed on a resource for a node.
+/// If the resource is running, wait for it to be healthy afterwards.
+pub async fn clear_resource((resource, node): (String, String)) -> Result<(), ImlAgentError> {
+    let running = active_node(&resource).await?.is_some();
+
+    crm_resource(&["--resource", &resource, "--clear", "--node", &node]).await?;
+
+    if running {
+        wait_resource_node(&resource, |_| true).await?;
+    }
+
+    Ok(())
+}
+
 pub async fn move_resource((resource, dest_host): (String, String)) -> Result<(), ImlAgentError> {
     let delay_duration = Duration::new(WAIT_DELAY, 0);
 
+    if active_node(&resource).await?.as_deref() == Some(dest_host.as_str()) {
+        return Ok(());
+    }
+
     crm_resource(&["--resource", &resource, "--move", "--node", &dest_host]).await?;
 
     let mut counter = 0;
@@ -651,12 +759,168 @@ pub async fn move_resource((resource, dest_host): (String, String)) -> Result<()
 
     crm_resource(&["--resource", &resource, "--un-move", "--node", &dest_host]).await?;
 
-    rc
+    rc?;
+
+    wait_resource_node(&resource, |x| x == dest_host).await?;
+
+    Ok(())
+}
+
+/// XPath of the constraint `id`.
+///
+/// Ids are XML ids, so one containing a `"` cannot name a constraint.
+fn constraint_xpath(id: &str) -> Result<String, ImlAgentError> {
+    if id.is_empty() || id.contains('"') {
+        return Err(RequiredError(format!("Invalid constraint id {:?}", id)).into());
+    }
+
+    Ok(format!("//constraints/*[@id=\"{}\"]", id))
+}
+
+/// Create a single constraint, doing nothing if a constraint with the same id exists
+pub async fn create_constraint(constraint: ResourceConstraint) -> Result<(), ImlAgentError> {
+    if cibxpath_exists(&constraint_xpath(constraint.id())?).await? {
+        return Ok(());
+    }
+
+    let xml = constraint_element(constraint, |x| x).to_string()?;
+
+    cibcreate("constraints", &xml).await
+}
+
+pub async fn list_constraints(_: ()) -> Result<Vec<ResourceConstraint>, ImlAgentError> {
+    get_constraints().await
+}
+
+/// Destroy a constraint by id, doing nothing if it does not exist
+pub async fn destroy_constraint(id: String) -> Result<(), ImlAgentError> {
+    let xpath = constraint_xpath(&id)?;
+
+    if !cibxpath_exists(&xpath).await? {
+        return Ok(());
+    }
+
+    cibxpath("delete", &xpath, NO_EXTRA).await?;
+
+    Ok(())
+}
+
+fn find_group<'a>(resources: &'a Element, id: &str) -> Option<&'a Element> {
+    resources
+        .find_all("group")
+        .find(|g| g.get_attr("id") == Some(id))
+}
+
+/// Rebuilds the resources section with the members of `group` moved
+/// into a new group element.
+///
+/// The group takes the place of its first member, so existing
+/// ordering of the resources section is preserved.
+fn group_resources(resources: &Element, group: &ResourceGroup) -> Result<Element, ImlAgentError> {
+    let mut members: HashMap<&str, &Element> = resources
+        .find_all("primitive")
+        .filter_map(|p| p.get_attr("id").map(|id| (id, p)))
+        .filter(|(id, _)| group.resources.iter().any(|r| r == id))
+        .collect();
+
+    if let Some(missing) = group
+        .resources
+        .iter()
+        .find(|r| !members.contains_key(r.as_str()))
+    {
+        return Err(RequiredError(format!(
+            "Resource {} is not an ungrouped primitive and cannot be added to group {}",
+            missing, group.id
+        ))
+        .into());
+    }
+
+    let mut g = Element::new("group");
+    g.set_attr("id", &group.id);
+
+    for r in &group.resources {
+        if let Some(p) = members.remove(r.as_str()) {
+            g.append_child(p.clone());
+        }
+    }
+
+    let mut out = Element::new("resources");
+    let mut g = Some(g);
+
+    for child in resources.children() {
+        let is_member = child.tag().name() == "primitive"
+            && child
+                .get_attr("id")
+                .map(|id| group.resources.iter().any(|r| r == id))
+                .unwrap_or(false);
+
+        if is_member {
+            if let Some(g) = g.take() {
+                out.append_child(g);
+            }
+        } else {
+            out.append_child(child.clone());
+        }
+    }
+
+    Ok(out)
+}
+
+/// Rebuilds the resources section with the group `id` replaced by its members.
+fn ungroup_resources(resources: &Element, id: &str) -> Element {
+    let mut out = Element::new("resources");
+
+    for child in resources.children() {
+        if child.tag().name() == "group" && child.get_attr("id") == Some(id) {
+            for p in child.children() {
+                out.append_child(p.clone());
+            }
+        } else {
+            out.append_child(child.clone());
+        }
+    }
+
+    out
+}
+
+/// Create a resource group out of existing primitives.
+///
+/// Does nothing if a group with the same id already exists.
+pub async fn create_group(group: ResourceGroup) -> Result<(), ImlAgentError> {
+    cib_update_resources(|resources| {
+        if find_group(resources, &group.id).is_some() {
+            return Ok(None);
+        }
+
+        group_resources(resources, &group).map(Some)
+    })
+    .await
+}
+
+pub async fn list_groups(_: ()) -> Result<Vec<ResourceGroup>, ImlAgentError> {
+    get_groups().await
+}
+
+/// Destroy a resource group, leaving its member primitives in place
+pub async fn destroy_group(id: String) -> Result<(), ImlAgentError> {
+    cib_update_resources(|resources| {
+        if find_group(resources, &id).is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(ungroup_resources(resources, &id)))
+    })
+    .await
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{process_resource, PacemakerOperations, ResourceAgentInfo, ResourceAgentType};
+    use super::{
+        constraint_xpath, find_group, group_resources, is_healthy, process_resource,
+        ungroup_resources, PacemakerOperations, Resource, ResourceAgentInfo, ResourceAgentType,
+        ResourceGroup,
+    };
+    use elementtree::Element;
     use std::collections::HashMap;
 
     #[test]
@@ -726,4 +990,115 @@ mod tests {
 
         assert_eq!(process_resource(testxml).unwrap(), r1);
     }
+
+    #[test]
+    fn test_is_healthy() {
+        let mut r = Resource {
+            id: "ost0001".into(),
+            resource_agent: "ocf::lustre:Lustre".into(),
+            role: "Started".into(),
+            active: true,
+            orphaned: false,
+            managed: true,
+            failed: false,
+            failure_ignored: false,
+            nodes_running_on: 1,
+            active_node_name: Some("oss1".into()),
+            active_node_id: Some("1".into()),
+        };
+
+        assert!(is_healthy(&r));
+
+        r.failed = true;
+        assert!(!is_healthy(&r));
+
+        r.failure_ignored = true;
+        assert!(is_healthy(&r));
+
+        r.nodes_running_on = 2;
+        assert!(!is_healthy(&r));
+
+        r.nodes_running_on = 1;
+        r.role = "Starting".into();
+        assert!(!is_healthy(&r));
+    }
+
+    #[test]
+    fn test_group_resources() {
+        let testxml = include_bytes!("../../fixtures/cibadmin_resources_fixture.xml");
+        let resources = Element::from_reader(&testxml[..]).unwrap();
+
+        let group = ResourceGroup {
+            id: "group-mgs".into(),
+            resources: vec!["MGS".into()],
+        };
+
+        let out = group_resources(&resources, &group).unwrap();
+
+        let ids: Vec<_> = out
+            .children()
+            .map(|c| (c.tag().name(), c.get_attr("id").unwrap_or_default()))
+            .collect();
+
+        assert_eq!(
+            ids,
+            vec![
+                ("primitive", "st-fencing"),
+                ("group", "group-mgs"),
+                ("group", "group-fs-ost"),
+                ("clone", "cl-fs-client"),
+            ]
+        );
+
+        let g = find_group(&out, "group-mgs").unwrap();
+        assert_eq!(g.find("primitive").unwrap().get_attr("id"), Some("MGS"));
+    }
+
+    #[test]
+    fn test_group_resources_missing() {
+        let testxml = include_bytes!("../../fixtures/cibadmin_resources_fixture.xml");
+        let resources = Element::from_reader(&testxml[..]).unwrap();
+
+        // ost0001 is already a member of group-fs-ost
+        let group = ResourceGroup {
+            id: "group-ost".into(),
+            resources: vec!["ost0001".into()],
+        };
+
+        assert!(group_resources(&resources, &group).is_err());
+    }
+
+    #[test]
+    fn test_ungroup_resources() {
+        let testxml = include_bytes!("../../fixtures/cibadmin_resources_fixture.xml");
+        let resources = Element::from_reader(&testxml[..]).unwrap();
+
+        let out = ungroup_resources(&resources, "group-fs-ost");
+
+        let ids: Vec<_> = out
+            .children()
+            .map(|c| c.get_attr("id").unwrap_or_default())
+            .collect();
+
+        assert_eq!(
+            ids,
+            vec![
+                "st-fencing",
+                "MGS",
+                "ost0001-zpool",
+                "ost0001",
+                "cl-fs-client"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_constraint_xpath() {
+        assert_eq!(
+            constraint_xpath("MGS-oss1").unwrap(),
+            "//constraints/*[@id=\"MGS-oss1\"]"
+        );
+        assert!(constraint_xpath("a\" or @id=\"b").is_err());
+        assert!(constraint_xpath("").is_err());
+    }
 }
@@ -33,3 +33,23 @@ pub async fn version(module: String) -> Result<String, ImlAgentError> {
 
     Ok(version)
 }
+
+/// Unloads the Lustre and LNet modules and loads them again,
+/// so the running modules match the installed Lustre packages.
+/// Fails if a target or client is still mounted.
+pub async fn reload_lustre(_: ()) -> Result<(), ImlAgentError> {
+    Command::new("lustre_rmmod")
+        .kill_on_drop(true)
+        .checked_output()
+        .await?;
+
+    iml_systemd::restart_unit("lnet.service".into()).await?;
+
+    Command::new("modprobe")
+        .arg("lustre")
+        .kill_on_drop(true)
+        .checked_output()
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,195 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::agent_error::ImlAgentError;
+use iml_cmd::{CheckedCommandExt, Command};
+use iml_fs::write_tempfile;
+use iml_wire_types::lnet::{NetAdd, NetDel, RouteAdd, RouteDel};
+use tokio::{fs, io::AsyncWriteExt};
+
+/// The file LNet loads its configuration from when the `lnet` service starts
+pub static LNET_CONF: &str = "/etc/lnet.conf";
+
+/// The configuration is written here and synced before being renamed over `LNET_CONF`,
+/// so a crash or a full disk never leaves a truncated configuration behind.
+static LNET_CONF_EDIT: &str = "/etc/lnet.conf.iml.edit";
+
+async fn lnetctl(args: Vec<String>) -> Result<String, ImlAgentError> {
+    let x = Command::new("lnetctl")
+        .args(args)
+        .kill_on_drop(true)
+        .checked_output()
+        .await?;
+
+    Ok(String::from_utf8_lossy(&x.stdout).to_string())
+}
+
+fn net_add_args(x: &NetAdd) -> Vec<String> {
+    let mut args = vec![
+        "net".into(),
+        "add".into(),
+        "--net".into(),
+        x.net.clone(),
+        "--if".into(),
+        x.interfaces.join(","),
+    ];
+
+    if let Some(peer_credits) = x.peer_credits {
+        args.extend(vec!["--peer-credits".into(), peer_credits.to_string()]);
+    }
+
+    if let Some(credits) = x.credits {
+        args.extend(vec!["--credits".into(), credits.to_string()]);
+    }
+
+    args
+}
+
+fn net_del_args(x: &NetDel) -> Vec<String> {
+    let mut args = vec!["net".into(), "del".into(), "--net".into(), x.net.clone()];
+
+    if !x.interfaces.is_empty() {
+        args.extend(vec!["--if".into(), x.interfaces.join(",")]);
+    }
+
+    args
+}
+
+fn route_add_args(x: &RouteAdd) -> Vec<String> {
+    let mut args = vec![
+        "route".into(),
+        "add".into(),
+        "--net".into(),
+        x.net.clone(),
+        "--gateway".into(),
+        x.gateway.clone(),
+    ];
+
+    if let Some(hop) = x.hop {
+        args.extend(vec!["--hop".into(), hop.to_string()]);
+    }
+
+    if let Some(priority) = x.priority {
+        args.extend(vec!["--priority".into(), priority.to_string()]);
+    }
+
+    args
+}
+
+fn route_del_args(x: &RouteDel) -> Vec<String> {
+    vec![
+        "route".into(),
+        "del".into(),
+        "--net".into(),
+        x.net.clone(),
+        "--gateway".into(),
+        x.gateway.clone(),
+    ]
+}
+
+/// Add a network on the given interfaces and persist the resulting configuration.
+pub async fn net_add(x: NetAdd) -> Result<(), ImlAgentError> {
+    lnetctl(net_add_args(&x)).await?;
+
+    persist(()).await
+}
+
+/// Remove a network, or some of its interfaces, and persist the resulting configuration.
+pub async fn net_del(x: NetDel) -> Result<(), ImlAgentError> {
+    lnetctl(net_del_args(&x)).await?;
+
+    persist(()).await
+}
+
+/// Add a route and persist the resulting configuration.
+pub async fn route_add(x: RouteAdd) -> Result<(), ImlAgentError> {
+    lnetctl(route_add_args(&x)).await?;
+
+    persist(()).await
+}
+
+/// Remove a route and persist the resulting configuration.
+pub async fn route_del(x: RouteDel) -> Result<(), ImlAgentError> {
+    lnetctl(route_del_args(&x)).await?;
+
+    persist(()).await
+}
+
+/// Apply a YAML configuration, as produced by `lnetctl export`,
+/// and persist the resulting configuration.
+pub async fn import(yaml: String) -> Result<(), ImlAgentError> {
+    let f = write_tempfile(yaml.into_bytes()).await?;
+
+    lnetctl(vec![
+        "import".into(),
+        f.path().to_string_lossy().to_string(),
+    ])
+    .await?;
+
+    persist(()).await
+}
+
+/// Returns the running LNet configuration as YAML.
+pub async fn export(_: ()) -> Result<String, ImlAgentError> {
+    lnetctl(vec!["export".into(), "--backup".into()]).await
+}
+
+/// Writes the running LNet configuration to `/etc/lnet.conf`,
+/// so it is restored when the `lnet` service starts.
+pub async fn persist(_: ()) -> Result<(), ImlAgentError> {
+    let x = export(()).await?;
+
+    let mut file = fs::File::create(LNET_CONF_EDIT).await?;
+    file.write_all(x.as_bytes()).await?;
+    file.sync_all().await?;
+
+    fs::rename(LNET_CONF_EDIT, LNET_CONF).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_net_add_args() {
+        let x = NetAdd {
+            net: "o2ib1".into(),
+            interfaces: vec!["ib0".into(), "ib1".into()],
+            peer_credits: Some(32),
+            credits: None,
+        };
+
+        assert_eq!(
+            net_add_args(&x).join(" "),
+            "net add --net o2ib1 --if ib0,ib1 --peer-credits 32"
+        );
+    }
+
+    #[test]
+    fn test_net_del_args() {
+        let x = NetDel {
+            net: "tcp0".into(),
+            interfaces: vec![],
+        };
+
+        assert_eq!(net_del_args(&x).join(" "), "net del --net tcp0");
+    }
+
+    #[test]
+    fn test_route_add_args() {
+        let x = RouteAdd {
+            net: "tcp1".into(),
+            gateway: "10.73.20.1@o2ib1".into(),
+            hop: Some(2),
+            priority: Some(1),
+        };
+
+        assert_eq!(
+            route_add_args(&x).join(" "),
+            "route add --net tcp1 --gateway 10.73.20.1@o2ib1 --hop 2 --priority 1"
+        );
+    }
+}
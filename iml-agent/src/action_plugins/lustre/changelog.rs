@@ -0,0 +1,31 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    agent_error::{ImlAgentError, RequiredError},
+    lustre::lctl,
+};
+use iml_wire_types::changelog::{ChangelogDeregister, ChangelogRegister};
+
+/// Registers a changelog user on an MDT. Returns the id of the new user, i.e. `cl1`.
+pub async fn register(x: ChangelogRegister) -> Result<String, ImlAgentError> {
+    let out = lctl(vec!["--device", &x.target, "changelog_register", "-n"]).await?;
+
+    let id = out.trim();
+
+    if id.is_empty() {
+        return Err(
+            RequiredError(format!("No changelog user id returned for {}", x.target)).into(),
+        );
+    }
+
+    Ok(id.to_string())
+}
+
+/// Deregisters a changelog user from an MDT.
+pub async fn deregister(x: ChangelogDeregister) -> Result<(), ImlAgentError> {
+    lctl(vec!["--device", &x.target, "changelog_deregister", &x.id])
+        .await
+        .map(drop)
+}
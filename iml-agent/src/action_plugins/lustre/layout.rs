@@ -0,0 +1,313 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::agent_error::{ImlAgentError, RequiredError};
+use iml_cmd::{CheckedCommandExt, Command};
+use iml_wire_types::layout::{GetLayout, Layout, LayoutComponent, SetLayout};
+use serde_yaml::{Mapping, Value};
+
+async fn lfs(args: Vec<String>) -> Result<String, ImlAgentError> {
+    let x = Command::new("/usr/bin/lfs")
+        .args(args)
+        .kill_on_drop(true)
+        .checked_output()
+        .await?;
+
+    Ok(String::from_utf8_lossy(&x.stdout).to_string())
+}
+
+fn component_args(x: &LayoutComponent) -> Vec<String> {
+    let mut args = vec![];
+
+    if x.dom {
+        args.extend(vec!["-L".to_string(), "mdt".to_string()]);
+    }
+
+    if let Some(count) = x.stripe_count {
+        args.extend(vec!["-c".to_string(), count.to_string()]);
+    }
+
+    if let Some(size) = x.stripe_size {
+        args.extend(vec!["-S".to_string(), size.to_string()]);
+    }
+
+    if let Some(pool) = &x.pool {
+        args.extend(vec!["-p".to_string(), pool.clone()]);
+    }
+
+    args
+}
+
+fn set_args(x: &SetLayout) -> Vec<String> {
+    let mut args = vec!["setstripe".to_string()];
+
+    match &x.layout {
+        None => args.push("-d".to_string()),
+        Some(layout) if layout.is_composite() => {
+            for c in &layout.components {
+                args.push("-E".to_string());
+                args.push(
+                    c.extent_end
+                        .map(|x| x.to_string())
+                        .unwrap_or_else(|| "eof".to_string()),
+                );
+                args.extend(component_args(c));
+            }
+        }
+        Some(layout) => {
+            args.extend(layout.components.iter().flat_map(component_args));
+        }
+    };
+
+    args.push(x.path.clone());
+
+    args
+}
+
+fn get_u64(x: &Mapping, key: &str) -> Option<u64> {
+    x.get(&Value::from(key)).and_then(Value::as_u64)
+}
+
+/// Converts a `sub_layout` or plain layout mapping.
+/// A stripe count or size of `0` means the filesystem default is used.
+fn parse_component(x: &Mapping, extent_end: Option<u64>) -> LayoutComponent {
+    let dom = x.get(&Value::from("pattern")).and_then(Value::as_str) == Some("mdt");
+
+    LayoutComponent {
+        extent_end,
+        dom,
+        stripe_count: x
+            .get(&Value::from("stripe_count"))
+            .and_then(Value::as_i64)
+            .filter(|x| *x != 0 && !dom),
+        stripe_size: get_u64(x, "stripe_size").filter(|x| *x != 0),
+        pool: x
+            .get(&Value::from("pool"))
+            .and_then(Value::as_str)
+            .filter(|x| !x.is_empty())
+            .map(String::from),
+    }
+}
+
+/// Parses the output of `lfs getstripe -d -y`.
+///
+/// Composite layouts list a `componentN` mapping per component,
+/// plain layouts list the stripe settings at the top level.
+/// Returns `None` if the directory has no default layout.
+fn parse_layout(output: &str) -> Result<Option<Layout>, ImlAgentError> {
+    fn is_yaml(x: &str) -> bool {
+        x.contains(": ") || x.trim_end().ends_with(':')
+    }
+
+    let mut lines = output.lines().filter(|x| !x.trim().is_empty()).peekable();
+
+    // Drop the directory name `lfs` may print ahead of the yaml
+    if lines.peek().filter(|x| !is_yaml(x)).is_some() {
+        lines.next();
+    }
+
+    let lines: Vec<_> = lines.collect();
+
+    if let Some(x) = lines.iter().find(|x| !is_yaml(x)) {
+        return Err(RequiredError(format!("Unexpected lfs getstripe output line: {}", x)).into());
+    }
+
+    if lines.is_empty() {
+        return Ok(None);
+    }
+
+    let output = lines.join("\n");
+
+    let x: Mapping = serde_yaml::from_str(&output)?;
+
+    let mut components: Vec<(usize, LayoutComponent)> = x
+        .iter()
+        .filter_map(|(k, v)| {
+            let idx = k.as_str()?.strip_prefix("component")?.parse().ok()?;
+            let v = v.as_mapping()?;
+
+            let extent_end = get_u64(v, "lcme_extent.e_end");
+            let sub_layout = v.get(&Value::from("sub_layout"))?.as_mapping()?;
+
+            Some((idx, parse_component(sub_layout, extent_end)))
+        })
+        .collect();
+
+    if !components.is_empty() {
+        components.sort_by_key(|(idx, _)| *idx);
+
+        return Ok(Some(Layout {
+            components: components.into_iter().map(|(_, x)| x).collect(),
+        }));
+    }
+
+    if !x.contains_key(&Value::from("stripe_count")) {
+        return Err(RequiredError(format!("Unexpected lfs getstripe output: {}", output)).into());
+    }
+
+    let component = parse_component(&x, None);
+
+    if component == LayoutComponent::default() {
+        return Ok(None);
+    }
+
+    Ok(Some(Layout {
+        components: vec![component],
+    }))
+}
+
+/// Returns the default layout of a directory, if it has one.
+pub async fn get(x: GetLayout) -> Result<Option<Layout>, ImlAgentError> {
+    let output = lfs(vec![
+        "getstripe".to_string(),
+        "-d".to_string(),
+        "-y".to_string(),
+        x.path,
+    ])
+    .await?;
+
+    parse_layout(&output)
+}
+
+/// Sets or removes the default layout of a directory.
+pub async fn set(x: SetLayout) -> Result<(), ImlAgentError> {
+    if let Some(layout) = &x.layout {
+        layout.validate().map_err(RequiredError)?;
+    }
+
+    lfs(set_args(&x)).await.map(drop)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(xs: &[&str]) -> Layout {
+        Layout {
+            components: xs.iter().map(|x| x.parse().unwrap()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_set_args() {
+        let x = SetLayout {
+            path: "/mnt/fs/project".into(),
+            layout: Some(layout(&[
+                "end=1M,dom",
+                "end=64M,count=1,pool=flash",
+                "end=eof,count=-1,size=4M",
+            ])),
+        };
+
+        assert_eq!(
+            set_args(&x).join(" "),
+            "setstripe -E 1048576 -L mdt -E 67108864 -c 1 -p flash -E eof -c -1 -S 4194304 /mnt/fs/project"
+        );
+
+        let x = SetLayout {
+            layout: Some(layout(&["count=4,size=1M"])),
+            ..x
+        };
+
+        assert_eq!(
+            set_args(&x).join(" "),
+            "setstripe -c 4 -S 1048576 /mnt/fs/project"
+        );
+
+        let x = SetLayout { layout: None, ..x };
+
+        assert_eq!(set_args(&x).join(" "), "setstripe -d /mnt/fs/project");
+    }
+
+    #[test]
+    fn test_parse_composite_layout() {
+        let output = r#"/mnt/fs/project
+  lcm_layout_gen:    0
+  lcm_mirror_count:  1
+  lcm_entry_count:   3
+  component0:
+    lcme_id:             N/A
+    lcme_mirror_id:      N/A
+    lcme_flags:          0
+    lcme_extent.e_start: 0
+    lcme_extent.e_end:   1048576
+    sub_layout:
+      stripe_count:  0
+      stripe_size:   1048576
+      pattern:       mdt
+      stripe_offset: -1
+  component1:
+    lcme_id:             N/A
+    lcme_mirror_id:      N/A
+    lcme_flags:          0
+    lcme_extent.e_start: 1048576
+    lcme_extent.e_end:   67108864
+    sub_layout:
+      stripe_count:  1
+      stripe_size:   0
+      pattern:       raid0
+      stripe_offset: -1
+      pool:          flash
+  component2:
+    lcme_id:             N/A
+    lcme_mirror_id:      N/A
+    lcme_flags:          0
+    lcme_extent.e_start: 67108864
+    lcme_extent.e_end:   EOF
+    sub_layout:
+      stripe_count:  -1
+      stripe_size:   4194304
+      pattern:       raid0
+      stripe_offset: -1
+"#;
+
+        assert_eq!(
+            parse_layout(output).unwrap(),
+            Some(Layout {
+                components: vec![
+                    LayoutComponent {
+                        extent_end: Some(1_048_576),
+                        dom: true,
+                        stripe_count: None,
+                        stripe_size: Some(1_048_576),
+                        pool: None,
+                    },
+                    "end=64M,count=1,pool=flash".parse().unwrap(),
+                    "end=eof,count=-1,size=4M".parse().unwrap(),
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_plain_layout() {
+        let output =
+            "stripe_count:  4\nstripe_size:   1048576\npattern:       raid0\nstripe_offset: -1\n";
+
+        assert_eq!(
+            parse_layout(output).unwrap(),
+            Some(layout(&["count=4,size=1M"]))
+        );
+
+        let output = "stripe_count:  0\nstripe_size:   0\npattern:       0\nstripe_offset: -1\n";
+
+        assert_eq!(parse_layout(output).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_unexpected_layout() {
+        assert_eq!(parse_layout("/mnt/fs/project\n").unwrap(), None);
+
+        let output = "/mnt/fs:project\nstripe_count:  4\nstripe_size:   1048576\n";
+
+        assert_eq!(
+            parse_layout(output).unwrap(),
+            Some(layout(&["count=4,size=1M"]))
+        );
+
+        let output = "/mnt/fs/project\nstripe_count:  4\nsome garbage\nstripe_size:   1048576\n";
+
+        assert!(parse_layout(output).is_err());
+    }
+}
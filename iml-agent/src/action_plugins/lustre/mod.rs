@@ -2,5 +2,10 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
+pub mod changelog;
 pub mod client;
+pub mod layout;
+pub mod nodemap;
+pub mod quota;
 pub mod snapshot;
+pub mod target;
@@ -0,0 +1,352 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    agent_error::{ImlAgentError, RequiredError},
+    lustre::lctl,
+};
+use iml_wire_types::nodemap::{
+    Nodemap, NodemapChange, NodemapIdKind, NodemapIdmap, NodemapRange, NodemapState,
+    DEFAULT_NODEMAP,
+};
+use std::collections::BTreeMap;
+
+/// Splits `lctl get_param` output into parameters and values.
+/// Values may span several lines, i.e. the `ranges` of a nodemap.
+fn parse_params(output: &str) -> Vec<(String, String)> {
+    output.lines().fold(vec![], |mut acc, line| {
+        let mut xs = line.splitn(2, '=');
+
+        match (xs.next(), xs.next()) {
+            (Some(k), Some(v)) if k.starts_with("nodemap.") && !k.contains(' ') => {
+                acc.push((k.to_string(), v.trim().to_string()))
+            }
+            _ => {
+                if let Some((_, v)) = acc.last_mut() {
+                    v.push_str(line.trim());
+                }
+            }
+        }
+
+        acc
+    })
+}
+
+/// Parses the `{ key: value, ... }` entries of a `ranges` or `idmap` value
+fn parse_entries(x: &str) -> Vec<BTreeMap<&str, &str>> {
+    x.split('{')
+        .skip(1)
+        .filter_map(|x| x.split('}').next())
+        .map(|x| {
+            x.split(',')
+                .filter_map(|x| {
+                    let mut xs = x.splitn(2, ':');
+
+                    Some((xs.next()?.trim(), xs.next()?.trim()))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn parse_ranges(x: &str) -> Vec<NodemapRange> {
+    parse_entries(x)
+        .into_iter()
+        .filter_map(|x| {
+            Some(NodemapRange {
+                start_nid: x.get("start_nid")?.to_string(),
+                end_nid: x.get("end_nid")?.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn parse_idmaps(x: &str) -> Vec<NodemapIdmap> {
+    parse_entries(x)
+        .into_iter()
+        .filter_map(|x| {
+            Some(NodemapIdmap {
+                kind: x.get("idtype")?.parse().ok()?,
+                client_id: x.get("client_id")?.parse().ok()?,
+                fs_id: x.get("fs_id")?.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+fn parse_state(output: &str) -> NodemapState {
+    let mut active = false;
+    let mut nodemaps: BTreeMap<String, Nodemap> = BTreeMap::new();
+
+    for (k, v) in parse_params(output) {
+        if k == "nodemap.active" {
+            active = v == "1";
+
+            continue;
+        }
+
+        let mut xs = k.rsplitn(2, '.');
+
+        let (prop, name) = match (
+            xs.next(),
+            xs.next().and_then(|x| x.strip_prefix("nodemap.")),
+        ) {
+            (Some(prop), Some(name)) => (prop, name),
+            _ => continue,
+        };
+
+        let x = nodemaps
+            .entry(name.to_string())
+            .or_insert_with(|| Nodemap::new(name));
+
+        match prop {
+            "admin_nodemap" => x.admin = v == "1",
+            "trusted_nodemap" => x.trusted = v == "1",
+            "squash_uid" => x.squash_uid = v.parse().unwrap_or(x.squash_uid),
+            "squash_gid" => x.squash_gid = v.parse().unwrap_or(x.squash_gid),
+            "ranges" => x.ranges = parse_ranges(&v),
+            "idmap" => x.idmaps = parse_idmaps(&v),
+            _ => {}
+        }
+    }
+
+    NodemapState {
+        active,
+        nodemaps: nodemaps.into_iter().map(|(_, x)| x).collect(),
+    }
+}
+
+/// Reads the nodemaps of this MGS
+pub async fn get(_: ()) -> Result<NodemapState, ImlAgentError> {
+    let out = lctl(vec![
+        "get_param",
+        "nodemap.active",
+        "nodemap.*.admin_nodemap",
+        "nodemap.*.trusted_nodemap",
+        "nodemap.*.squash_uid",
+        "nodemap.*.squash_gid",
+    ])
+    .await?;
+
+    let state = parse_state(&out);
+
+    // The default nodemap has no ranges or idmaps
+    let params: Vec<_> = state
+        .nodemaps
+        .iter()
+        .filter(|x| x.name != DEFAULT_NODEMAP)
+        .flat_map(|x| {
+            vec![
+                format!("nodemap.{}.ranges", x.name),
+                format!("nodemap.{}.idmap", x.name),
+            ]
+        })
+        .collect();
+
+    if params.is_empty() {
+        return Ok(state);
+    }
+
+    let mut args = vec!["get_param".to_string()];
+    args.extend(params);
+
+    let out2 = lctl(args).await?;
+
+    Ok(parse_state(&format!("{}\n{}", out, out2)))
+}
+
+fn change_args(x: &NodemapChange) -> Vec<String> {
+    let idmap_args = |cmd: &str, name: &str, x: &NodemapIdmap| {
+        vec![
+            cmd.to_string(),
+            "--name".to_string(),
+            name.to_string(),
+            "--idtype".to_string(),
+            x.kind.to_string(),
+            "--idmap".to_string(),
+            format!("{}:{}", x.client_id, x.fs_id),
+        ]
+    };
+
+    let range_args = |cmd: &str, name: &str, x: &NodemapRange| {
+        vec![
+            cmd.to_string(),
+            "--name".to_string(),
+            name.to_string(),
+            "--range".to_string(),
+            x.to_string(),
+        ]
+    };
+
+    match x {
+        NodemapChange::Add { name } => vec!["nodemap_add".to_string(), name.clone()],
+        NodemapChange::Del { name } => vec!["nodemap_del".to_string(), name.clone()],
+        NodemapChange::AddRange { name, range } => range_args("nodemap_add_range", name, range),
+        NodemapChange::DelRange { name, range } => range_args("nodemap_del_range", name, range),
+        NodemapChange::AddIdmap { name, idmap } => idmap_args("nodemap_add_idmap", name, idmap),
+        NodemapChange::DelIdmap { name, idmap } => idmap_args("nodemap_del_idmap", name, idmap),
+        NodemapChange::Modify {
+            name,
+            property,
+            value,
+        } => vec![
+            "nodemap_modify".to_string(),
+            "--name".to_string(),
+            name.clone(),
+            "--property".to_string(),
+            property.to_string(),
+            "--value".to_string(),
+            value.clone(),
+        ],
+        NodemapChange::Activate(x) => vec![
+            "nodemap_activate".to_string(),
+            if *x { "1" } else { "0" }.to_string(),
+        ],
+    }
+}
+
+/// Applies nodemap changes on this MGS, in order.
+/// Stops at the first change that fails.
+pub async fn change(xs: Vec<NodemapChange>) -> Result<(), ImlAgentError> {
+    for x in xs {
+        if let NodemapChange::Del { name } = &x {
+            if name == DEFAULT_NODEMAP {
+                return Err(RequiredError("The default nodemap cannot be removed".into()).into());
+            }
+        }
+
+        lctl(change_args(&x)).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iml_wire_types::nodemap::NodemapProperty;
+
+    #[test]
+    fn test_parse_state() {
+        let out = r#"nodemap.active=1
+nodemap.default.admin_nodemap=0
+nodemap.tenant1.admin_nodemap=1
+nodemap.default.trusted_nodemap=0
+nodemap.tenant1.trusted_nodemap=1
+nodemap.default.squash_uid=99
+nodemap.tenant1.squash_uid=65534
+nodemap.default.squash_gid=99
+nodemap.tenant1.squash_gid=65534
+nodemap.tenant1.ranges=
+[
+ { id: 1, start_nid: 192.168.1.1@tcp, end_nid: 192.168.1.100@tcp }
+]
+nodemap.tenant1.idmap=
+[
+ { idtype: uid, client_id: 500, fs_id: 1000 },
+ { idtype: gid, client_id: 500, fs_id: 1000 }
+]
+"#;
+
+        let x = parse_state(out);
+
+        assert!(x.active);
+        assert_eq!(x.nodemaps.len(), 2);
+        assert_eq!(x.nodemaps[0], Nodemap::new("default"));
+
+        assert_eq!(
+            x.nodemaps[1],
+            Nodemap {
+                admin: true,
+                trusted: true,
+                squash_uid: 65534,
+                squash_gid: 65534,
+                ranges: vec![NodemapRange {
+                    start_nid: "192.168.1.1@tcp".into(),
+                    end_nid: "192.168.1.100@tcp".into(),
+                }],
+                idmaps: vec![
+                    NodemapIdmap {
+                        kind: NodemapIdKind::Uid,
+                        client_id: 500,
+                        fs_id: 1000,
+                    },
+                    NodemapIdmap {
+                        kind: NodemapIdKind::Gid,
+                        client_id: 500,
+                        fs_id: 1000,
+                    },
+                ],
+                ..Nodemap::new("tenant1")
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_ranges() {
+        let x = parse_state("nodemap.active=0\nnodemap.t.ranges=\n[\n\n]\n");
+
+        assert!(!x.active);
+        assert_eq!(x.nodemaps[0].ranges, vec![]);
+    }
+
+    #[test]
+    fn test_change_args() {
+        assert_eq!(
+            change_args(&NodemapChange::AddRange {
+                name: "tenant1".into(),
+                range: "192.168.1.[1-100]@tcp".parse().unwrap(),
+            }),
+            vec![
+                "nodemap_add_range",
+                "--name",
+                "tenant1",
+                "--range",
+                "192.168.1.[1-100]@tcp"
+            ]
+        );
+
+        assert_eq!(
+            change_args(&NodemapChange::AddIdmap {
+                name: "tenant1".into(),
+                idmap: NodemapIdmap {
+                    kind: NodemapIdKind::Gid,
+                    client_id: 500,
+                    fs_id: 1000,
+                },
+            }),
+            vec![
+                "nodemap_add_idmap",
+                "--name",
+                "tenant1",
+                "--idtype",
+                "gid",
+                "--idmap",
+                "500:1000"
+            ]
+        );
+
+        assert_eq!(
+            change_args(&NodemapChange::Modify {
+                name: "tenant1".into(),
+                property: NodemapProperty::SquashUid,
+                value: "65534".into(),
+            }),
+            vec![
+                "nodemap_modify",
+                "--name",
+                "tenant1",
+                "--property",
+                "squash_uid",
+                "--value",
+                "65534"
+            ]
+        );
+
+        assert_eq!(
+            change_args(&NodemapChange::Activate(true)),
+            vec!["nodemap_activate", "1"]
+        );
+    }
+}
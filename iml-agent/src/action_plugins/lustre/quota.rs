@@ -0,0 +1,199 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::agent_error::{ImlAgentError, RequiredError};
+use iml_cmd::{CheckedCommandExt, Command};
+use iml_wire_types::quota::{GetQuota, Quota, SetQuota, SetQuotaGrace};
+
+async fn lfs(args: Vec<String>) -> Result<String, ImlAgentError> {
+    let x = Command::new("/usr/bin/lfs")
+        .args(args)
+        .kill_on_drop(true)
+        .checked_output()
+        .await?;
+
+    Ok(String::from_utf8_lossy(&x.stdout).to_string())
+}
+
+fn push_opt(args: &mut Vec<String>, flag: &str, x: &Option<String>) {
+    if let Some(x) = x {
+        args.extend(vec![flag.to_string(), x.clone()]);
+    }
+}
+
+fn set_args(x: &SetQuota) -> Vec<String> {
+    let mut args = vec!["setquota".to_string()];
+
+    match &x.id {
+        Some(id) => args.extend(vec![x.kind.flag().to_string(), id.clone()]),
+        None => args.push(x.kind.default_flag().to_string()),
+    }
+
+    push_opt(&mut args, "--block-softlimit", &x.block_soft);
+    push_opt(&mut args, "--block-hardlimit", &x.block_hard);
+    push_opt(&mut args, "--inode-softlimit", &x.inode_soft);
+    push_opt(&mut args, "--inode-hardlimit", &x.inode_hard);
+    push_opt(&mut args, "--pool", &x.pool);
+
+    args.push(x.mountpoint.clone());
+
+    args
+}
+
+fn set_grace_args(x: &SetQuotaGrace) -> Vec<String> {
+    let mut args = vec![
+        "setquota".to_string(),
+        "-t".to_string(),
+        x.kind.flag().to_string(),
+    ];
+
+    push_opt(&mut args, "--block-grace", &x.block_grace);
+    push_opt(&mut args, "--inode-grace", &x.inode_grace);
+    push_opt(&mut args, "--pool", &x.pool);
+
+    args.push(x.mountpoint.clone());
+
+    args
+}
+
+fn get_args(x: &GetQuota) -> Vec<String> {
+    let mut args = vec![
+        "quota".to_string(),
+        "-q".to_string(),
+        x.kind.flag().to_string(),
+        x.id.clone(),
+    ];
+
+    push_opt(&mut args, "--pool", &x.pool);
+
+    args.push(x.mountpoint.clone());
+
+    args
+}
+
+fn parse_limit(x: &str) -> Result<u64, ImlAgentError> {
+    // `lfs` marks a used value that exceeds its limit with a trailing `*`
+    x.trim_end_matches('*')
+        .parse()
+        .map_err(|_| RequiredError(format!("Could not parse quota value {}", x)).into())
+}
+
+fn parse_grace(x: &str) -> Option<String> {
+    match x {
+        "-" | "none" => None,
+        x => Some(x.to_string()),
+    }
+}
+
+/// Parses the output of `lfs quota -q`.
+///
+/// The filesystem is printed first, on its own line if it is long,
+/// followed by space usage, limits and grace, then inode usage, limits and grace.
+fn parse_quota(output: &str) -> Result<Quota, ImlAgentError> {
+    let xs: Vec<&str> = output.split_whitespace().collect();
+
+    match xs.as_slice() {
+        [_, kbytes_used, kbytes_soft, kbytes_hard, block_grace, inodes_used, inodes_soft, inodes_hard, inode_grace] => {
+            Ok(Quota {
+                kbytes_used: parse_limit(kbytes_used)?,
+                kbytes_soft: parse_limit(kbytes_soft)?,
+                kbytes_hard: parse_limit(kbytes_hard)?,
+                block_grace: parse_grace(block_grace),
+                inodes_used: parse_limit(inodes_used)?,
+                inodes_soft: parse_limit(inodes_soft)?,
+                inodes_hard: parse_limit(inodes_hard)?,
+                inode_grace: parse_grace(inode_grace),
+            })
+        }
+        _ => Err(RequiredError(format!("Unexpected lfs quota output: {}", output)).into()),
+    }
+}
+
+/// Set the limits of a user, group or project, or the default limits of a kind.
+pub async fn set(x: SetQuota) -> Result<(), ImlAgentError> {
+    lfs(set_args(&x)).await.map(drop)
+}
+
+/// Set the block and inode grace periods of a kind.
+pub async fn set_grace(x: SetQuotaGrace) -> Result<(), ImlAgentError> {
+    lfs(set_grace_args(&x)).await.map(drop)
+}
+
+/// Returns the usage and limits of a user, group or project.
+pub async fn get(x: GetQuota) -> Result<Quota, ImlAgentError> {
+    let output = lfs(get_args(&x)).await?;
+
+    parse_quota(&output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iml_wire_types::quota::QuotaKind;
+
+    #[test]
+    fn test_set_args() {
+        let x = SetQuota {
+            mountpoint: "/mnt/fs".into(),
+            kind: QuotaKind::Project,
+            id: Some("1000".into()),
+            pool: Some("flash".into()),
+            block_soft: Some("10G".into()),
+            block_hard: Some("12G".into()),
+            inode_soft: None,
+            inode_hard: None,
+        };
+
+        assert_eq!(
+            set_args(&x).join(" "),
+            "setquota -p 1000 --block-softlimit 10G --block-hardlimit 12G --pool flash /mnt/fs"
+        );
+
+        let x = SetQuota {
+            id: None,
+            pool: None,
+            kind: QuotaKind::User,
+            inode_hard: Some("100000".into()),
+            ..x
+        };
+
+        assert_eq!(
+            set_args(&x).join(" "),
+            "setquota -U --block-softlimit 10G --block-hardlimit 12G --inode-hardlimit 100000 /mnt/fs"
+        );
+    }
+
+    #[test]
+    fn test_parse_quota() {
+        let x = parse_quota(
+            "      /mnt/fs  10244*   10240   20480 6d23h59m58s       3       0       0       -\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            x,
+            Quota {
+                kbytes_used: 10244,
+                kbytes_soft: 10240,
+                kbytes_hard: 20480,
+                block_grace: Some("6d23h59m58s".into()),
+                inodes_used: 3,
+                inodes_soft: 0,
+                inodes_hard: 0,
+                inode_grace: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_quota_long_fs_name() {
+        let x = parse_quota(
+            "/mnt/a_filesystem_with_a_long_mountpoint\n                      4       0       0       -       1       0       0       -\n",
+        )
+        .unwrap();
+
+        assert_eq!(x.kbytes_used, 4);
+        assert_eq!(x.inodes_used, 1);
+    }
+}
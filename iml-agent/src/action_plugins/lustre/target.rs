@@ -0,0 +1,131 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::agent_error::ImlAgentError;
+use iml_cmd::{CheckedCommandExt, Command};
+use iml_wire_types::{
+    filesystem::{FormatTarget, TargetKind},
+    FsType,
+};
+use tokio::fs;
+
+fn mkfs_args(x: &FormatTarget) -> Vec<String> {
+    let mut args = vec![format!(
+        "--{}",
+        match x.kind {
+            TargetKind::Mgt => "mgs",
+            TargetKind::Mdt => "mdt",
+            TargetKind::Ost => "ost",
+        }
+    )];
+
+    if x.kind != TargetKind::Mgt {
+        args.push(format!("--fsname={}", x.fs_name));
+        args.push(format!("--index={}", x.index));
+
+        for nids in &x.mgs_nids {
+            args.push(format!("--mgsnode={}", nids.join(",")));
+        }
+    }
+
+    for nids in &x.service_nids {
+        args.push(format!("--servicenode={}", nids.join(",")));
+    }
+
+    args.push(format!("--backfstype={}", x.backfs));
+
+    if let Some(opts) = &x.mkfs_options {
+        args.push(format!("--mkfsoptions={}", opts));
+    }
+
+    if x.backfs == FsType::Zfs {
+        args.push(x.target());
+    }
+
+    args.push(x.device.clone());
+
+    args
+}
+
+/// Formats a device as a Lustre target with `mkfs.lustre`.
+/// ZFS backed targets are created in a new zpool on the device.
+pub async fn format(x: FormatTarget) -> Result<(), ImlAgentError> {
+    Command::new("mkfs.lustre")
+        .args(mkfs_args(&x))
+        .kill_on_drop(true)
+        .checked_output()
+        .await?;
+
+    Ok(())
+}
+
+/// Creates the directory a target is mounted on
+pub async fn create_mountpoint(path: String) -> Result<(), ImlAgentError> {
+    fs::create_dir_all(&path).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mkfs_args() {
+        let x = FormatTarget {
+            kind: TargetKind::Ost,
+            fs_name: "fs".into(),
+            index: 1,
+            backfs: FsType::Zfs,
+            device: "/dev/mapper/mpatha".into(),
+            mgs_nids: vec![vec!["10.0.0.1@tcp".into()], vec!["10.0.0.2@tcp".into()]],
+            service_nids: vec![
+                vec!["10.0.0.3@tcp".into(), "10.1.0.3@o2ib".into()],
+                vec!["10.0.0.4@tcp".into()],
+            ],
+            mkfs_options: Some("-o ashift=12".into()),
+        };
+
+        assert_eq!(
+            mkfs_args(&x),
+            vec![
+                "--ost",
+                "--fsname=fs",
+                "--index=1",
+                "--mgsnode=10.0.0.1@tcp",
+                "--mgsnode=10.0.0.2@tcp",
+                "--servicenode=10.0.0.3@tcp,10.1.0.3@o2ib",
+                "--servicenode=10.0.0.4@tcp",
+                "--backfstype=zfs",
+                "--mkfsoptions=-o ashift=12",
+                "fs-ost0001/ost",
+                "/dev/mapper/mpatha",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mkfs_args_mgt() {
+        let x = FormatTarget {
+            kind: TargetKind::Mgt,
+            fs_name: "fs".into(),
+            index: 0,
+            backfs: FsType::Ldiskfs,
+            device: "/dev/mapper/mpathb".into(),
+            mgs_nids: vec![],
+            service_nids: vec![vec!["10.0.0.1@tcp".into()]],
+            mkfs_options: None,
+        };
+
+        assert_eq!(
+            mkfs_args(&x),
+            vec![
+                "--mgs",
+                "--servicenode=10.0.0.1@tcp",
+                "--backfstype=ldiskfs",
+                "/dev/mapper/mpathb",
+            ]
+        );
+    }
+}
@@ -9,6 +9,7 @@ pub mod high_availability;
 pub mod kernel_module;
 pub mod lamigo;
 pub mod ldev;
+pub mod lnet;
 pub mod lpurge;
 pub mod lustre;
 pub mod ntp;
@@ -16,5 +17,7 @@ pub mod ostpool;
 pub mod package;
 pub mod postoffice;
 pub mod stratagem;
+pub mod tunable;
+pub mod zpool;
 pub use action_plugin::create_registry;
 pub(crate) mod firewall_cmd;
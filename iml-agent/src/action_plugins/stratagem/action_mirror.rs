@@ -2,11 +2,29 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
-use crate::{agent_error::ImlAgentError, lustre::search_rootpath};
-use futures::future::join_all;
-use iml_cmd::{CheckedCommandExt, CmdError, Command};
-use iml_wire_types::{FidError, FidItem};
-use std::{collections::HashMap, ffi::OsStr, process::Output};
+use crate::{
+    agent_error::{ImlAgentError, RequiredError},
+    http_comms::streaming_client,
+    lustre::search_rootpath,
+};
+use futures::{future::join_all, stream, StreamExt};
+use iml_cmd::{CheckedCommandExt, Child, CmdError, Command};
+use iml_wire_types::{
+    mirror::{MirrorFile, MirrorFilter, MirrorFind},
+    FidError, FidItem,
+};
+use liblustreapi::LlapiFid;
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    path::PathBuf,
+    process::{Output, Stdio},
+};
+use tokio::{process::ChildStdout, task::spawn_blocking};
+use tokio_util::codec::{FramedRead, LinesCodec};
+
+/// The number of files `lfs find` lists before their FIDs are read
+const FIND_BATCH: usize = 5000;
 
 async fn lfs_mirror<S: AsRef<OsStr>>(
     args: impl IntoIterator<Item = S>,
@@ -124,3 +142,193 @@ pub async fn process_split_fids(
 
     Ok(join_all(xs).await.into_iter().filter_map(|x| x).collect())
 }
+
+fn find_args(x: &MirrorFind) -> Vec<String> {
+    let mut args = vec![
+        "find".to_string(),
+        format!("{}{}", x.mountpoint.trim_end_matches('/'), x.path),
+        "--type".to_string(),
+        "f".to_string(),
+    ];
+
+    match &x.filter {
+        MirrorFilter::NotOnPool(pool) => {
+            args.extend(vec!["!".to_string(), "--pool".to_string(), pool.clone()])
+        }
+        MirrorFilter::Stale => args.extend(vec![
+            "--mirror-count".to_string(),
+            "+1".to_string(),
+            "--mirror-state".to_string(),
+            "^ro".to_string(),
+        ]),
+    };
+
+    args
+}
+
+/// A running `lfs find`, read in batches of `FIND_BATCH` files.
+/// `lfs find` is killed if this is dropped before it exits.
+struct FindFiles {
+    child: Child,
+    lines: FramedRead<ChildStdout, LinesCodec>,
+    llapi: LlapiFid,
+}
+
+impl FindFiles {
+    async fn spawn(x: &MirrorFind) -> Result<Self, ImlAgentError> {
+        let llapi = search_rootpath(x.mountpoint.clone()).await?;
+
+        let mut child = Command::new("/usr/bin/lfs")
+            .args(find_args(x))
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| RequiredError("Could not read the output of lfs find".to_string()))?;
+
+        Ok(FindFiles {
+            child,
+            lines: FramedRead::new(stdout, LinesCodec::new()),
+            llapi,
+        })
+    }
+    /// Returns the next batch of files, or `None` once `lfs find` has exited.
+    /// Files removed before their FID is read are skipped.
+    async fn next_batch(&mut self) -> Result<Option<Vec<MirrorFile>>, ImlAgentError> {
+        let mut paths = vec![];
+
+        while paths.len() < FIND_BATCH {
+            match self.lines.next().await {
+                Some(x) => {
+                    let x = x?;
+
+                    if !x.is_empty() {
+                        paths.push(x);
+                    }
+                }
+                None => break,
+            }
+        }
+
+        if paths.is_empty() {
+            let status = (&mut self.child).await?;
+
+            if !status.success() {
+                return Err(RequiredError(format!("lfs find failed: {}", status)).into());
+            }
+
+            return Ok(None);
+        }
+
+        let llapi = self.llapi.clone();
+
+        let xs = spawn_blocking(move || {
+            let mntpt = llapi.mntpt();
+
+            paths
+                .into_iter()
+                .filter_map(|path| match llapi.path2fid(&PathBuf::from(&path)) {
+                    Ok(fid) => Some(MirrorFile {
+                        path: path
+                            .strip_prefix(mntpt.trim_end_matches('/'))
+                            .unwrap_or(&path)
+                            .to_string(),
+                        fid,
+                    }),
+                    Err(e) => {
+                        tracing::debug!("Could not read the FID of {}: {}", path, e);
+
+                        None
+                    }
+                })
+                .collect()
+        })
+        .await?;
+
+        Ok(Some(xs))
+    }
+}
+
+/// Lists the FIDs of the files under a path matching a `MirrorFilter`.
+/// `lfs find` is stopped once `limit` files are listed.
+pub async fn find(x: MirrorFind) -> Result<Vec<MirrorFile>, ImlAgentError> {
+    let limit = x.limit.map(|x| x as usize).unwrap_or(usize::MAX);
+
+    let mut files = FindFiles::spawn(&x).await?;
+
+    let mut xs = vec![];
+
+    while xs.len() < limit {
+        match files.next_batch().await? {
+            Some(ys) => xs.extend(ys),
+            None => break,
+        }
+    }
+
+    xs.truncate(limit);
+
+    Ok(xs)
+}
+
+/// Streams the FIDs of the files under a path matching a `MirrorFilter` to `mailbox`,
+/// one batch at a time. Returns the number of FIDs sent.
+pub async fn find_to_mailbox((x, mailbox): (MirrorFind, String)) -> Result<u64, ImlAgentError> {
+    let mut files = FindFiles::spawn(&x).await?;
+
+    let mut count = 0;
+
+    while let Some(xs) = files.next_batch().await? {
+        if xs.is_empty() {
+            continue;
+        }
+
+        count += xs.len() as u64;
+
+        let xs = xs
+            .into_iter()
+            .map(|x| Ok(format!("{{ \"fid\": \"{}\" }}\n", x.fid).into()));
+
+        streaming_client::send("mailbox", mailbox.clone(), stream::iter(xs)).await?;
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_args() {
+        let mut x = MirrorFind {
+            mountpoint: "/mnt/fs/".into(),
+            path: "/data".into(),
+            filter: MirrorFilter::NotOnPool("cold".into()),
+            limit: None,
+        };
+
+        assert_eq!(
+            find_args(&x),
+            vec!["find", "/mnt/fs/data", "--type", "f", "!", "--pool", "cold"]
+        );
+
+        x.filter = MirrorFilter::Stale;
+
+        assert_eq!(
+            find_args(&x),
+            vec![
+                "find",
+                "/mnt/fs/data",
+                "--type",
+                "f",
+                "--mirror-count",
+                "+1",
+                "--mirror-state",
+                "^ro"
+            ]
+        );
+    }
+}
@@ -0,0 +1,69 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{agent_error::ImlAgentError, env, lustre::lctl};
+use iml_wire_types::tunable::{TunableMethod, TunableSet};
+use std::path::Path;
+use tokio::fs;
+
+fn set_args(x: &TunableSet) -> Vec<String> {
+    let kv = format!("{}={}", x.param, x.value);
+
+    match x.method {
+        TunableMethod::SetParamP => vec!["set_param".into(), "-P".into(), kv],
+        TunableMethod::ConfParam => vec!["conf_param".into(), kv],
+        TunableMethod::SetParam => vec!["set_param".into(), kv],
+    }
+}
+
+/// Sets each tunable in order, stopping at the first failure.
+///
+/// Persistent tunables must be set on the MGS.
+pub async fn set(xs: Vec<TunableSet>) -> Result<(), ImlAgentError> {
+    for x in xs {
+        lctl(set_args(&x)).await?;
+    }
+
+    Ok(())
+}
+
+/// Replaces the list of parameter patterns read back by the `tunables` daemon plugin.
+pub async fn watch(params: Vec<String>) -> Result<(), ImlAgentError> {
+    let path = env::get_tunables_conf();
+
+    if let Some(parent) = Path::new(&path).parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    fs::write(&path, params.join("\n")).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_args() {
+        let x = TunableSet {
+            method: TunableMethod::SetParamP,
+            param: "osc.*.max_rpcs_in_flight".into(),
+            value: "16".into(),
+        };
+
+        assert_eq!(
+            set_args(&x).join(" "),
+            "set_param -P osc.*.max_rpcs_in_flight=16"
+        );
+
+        let x = TunableSet {
+            method: TunableMethod::ConfParam,
+            param: "fs.sys.timeout".into(),
+            value: "40".into(),
+        };
+
+        assert_eq!(set_args(&x).join(" "), "conf_param fs.sys.timeout=40");
+    }
+}
@@ -0,0 +1,27 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::agent_error::ImlAgentError;
+use iml_cmd::{CheckedCommandExt, Command};
+
+async fn zpool_scrub(args: &[&str]) -> Result<(), ImlAgentError> {
+    Command::new("zpool")
+        .arg("scrub")
+        .args(args)
+        .kill_on_drop(true)
+        .checked_output()
+        .await?;
+
+    Ok(())
+}
+
+/// Start a scrub of the given zpool.
+pub async fn scrub_start(pool: String) -> Result<(), ImlAgentError> {
+    zpool_scrub(&[&pool]).await
+}
+
+/// Stop any scrub running on the given zpool.
+pub async fn scrub_stop(pool: String) -> Result<(), ImlAgentError> {
+    zpool_scrub(&["-s", &pool]).await
+}
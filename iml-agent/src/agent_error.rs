@@ -118,6 +118,8 @@ pub enum ImlAgentError {
     #[error(transparent)]
     RequiredError(#[from] RequiredError),
     #[error(transparent)]
+    ResyncError(#[from] device_types::stream::ResyncError),
+    #[error(transparent)]
     Reqwest(#[from] reqwest::Error),
     #[error("Rx went away")]
     SendError,
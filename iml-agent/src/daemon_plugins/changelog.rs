@@ -0,0 +1,221 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Reads the changelog users of each MDT on this server,
+//! so the manager can track how far behind each user is.
+
+use crate::{
+    agent_error::ImlAgentError,
+    daemon_plugins::{DaemonPlugin, Output},
+};
+use futures::{lock::Mutex, Future, FutureExt};
+use iml_cmd::Command;
+use iml_wire_types::changelog::{ChangelogStatus, ChangelogUserIndex};
+use std::{
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Resend unchanged changelog users every 5 min
+const DEFAULT_RESEND: Duration = Duration::from_secs(300);
+
+struct ChangelogSub {
+    last: Instant,
+    output: Output,
+}
+
+#[derive(Debug, Clone)]
+pub struct Changelog {
+    state: Arc<Mutex<ChangelogSub>>,
+}
+
+pub fn create() -> impl DaemonPlugin {
+    Changelog {
+        state: Arc::new(Mutex::new(ChangelogSub {
+            last: Instant::now(),
+            output: None,
+        })),
+    }
+}
+
+fn parse_user(line: &str) -> Option<ChangelogUserIndex> {
+    let mut xs = line.split_whitespace();
+
+    let id = xs.next()?.to_string();
+    let index = xs.next()?.parse().ok()?;
+    let idle_secs = xs
+        .next()
+        .and_then(|x| x.strip_prefix('('))
+        .and_then(|x| x.strip_suffix(')'))
+        .and_then(|x| x.parse().ok());
+
+    Some(ChangelogUserIndex {
+        id,
+        index,
+        idle_secs,
+    })
+}
+
+/// Parses the output of `lctl get_param mdd.*.changelog_users`, i.e.
+///
+/// ```text
+/// mdd.fs-MDT0000.changelog_users=
+/// current index: 1234
+/// ID    index (idle seconds)
+/// cl1   1200 (35)
+/// ```
+///
+/// Lustre before 2.12 does not list the idle seconds.
+fn parse_changelog_users(output: &str) -> Vec<ChangelogStatus> {
+    output
+        .lines()
+        .fold(vec![], |mut acc: Vec<ChangelogStatus>, line| {
+            let line = line.trim();
+
+            if let Some(target) = line
+                .strip_prefix("mdd.")
+                .and_then(|x| x.strip_suffix(".changelog_users="))
+            {
+                acc.push(ChangelogStatus {
+                    target: target.to_string(),
+                    current_index: 0,
+                    users: vec![],
+                });
+
+                return acc;
+            }
+
+            let status = match acc.last_mut() {
+                Some(x) => x,
+                None => return acc,
+            };
+
+            if let Some(x) = line
+                .strip_prefix("current index:")
+                .or_else(|| line.strip_prefix("current_index:"))
+            {
+                status.current_index = x.trim().parse().unwrap_or(0);
+            } else if !line.starts_with("ID") {
+                status.users.extend(parse_user(line));
+            }
+
+            acc
+        })
+}
+
+async fn get_changelog_users() -> Result<Output, ImlAgentError> {
+    // `lctl` exits non-zero if there are no MDTs on this server, which is expected
+    let x = Command::new("lctl")
+        .arg("get_param")
+        .arg("mdd.*.changelog_users")
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    let xs = parse_changelog_users(&String::from_utf8_lossy(&x.stdout));
+
+    Ok(serde_json::to_value(xs).map(Some)?)
+}
+
+impl DaemonPlugin for Changelog {
+    fn start_session(
+        &mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<Output, ImlAgentError>> + Send>> {
+        let state = Arc::clone(&self.state);
+
+        async move {
+            let output = get_changelog_users().await?;
+
+            let mut state = state.lock().await;
+
+            state.last = Instant::now();
+            state.output = output;
+
+            Ok(state.output.clone())
+        }
+        .boxed()
+    }
+
+    fn update_session(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<Output, ImlAgentError>> + Send>> {
+        let state = Arc::clone(&self.state);
+
+        async move {
+            let output = get_changelog_users().await?;
+
+            let mut state = state.lock().await;
+
+            let now = Instant::now();
+
+            if state.output != output || now >= state.last + DEFAULT_RESEND {
+                state.last = now;
+                state.output = output;
+
+                Ok(state.output.clone())
+            } else {
+                Ok(None)
+            }
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_changelog_users() {
+        let output = r#"mdd.fs-MDT0000.changelog_users=
+current index: 1234
+ID    index (idle seconds)
+cl1   1200 (35)
+cl2   17 (864000)
+mdd.fs-MDT0001.changelog_users=
+current index: 0
+ID    index
+"#;
+
+        assert_eq!(
+            parse_changelog_users(output),
+            vec![
+                ChangelogStatus {
+                    target: "fs-MDT0000".into(),
+                    current_index: 1234,
+                    users: vec![
+                        ChangelogUserIndex {
+                            id: "cl1".into(),
+                            index: 1200,
+                            idle_secs: Some(35),
+                        },
+                        ChangelogUserIndex {
+                            id: "cl2".into(),
+                            index: 17,
+                            idle_secs: Some(864_000),
+                        },
+                    ],
+                },
+                ChangelogStatus {
+                    target: "fs-MDT0001".into(),
+                    current_index: 0,
+                    users: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_user_without_idle() {
+        assert_eq!(
+            parse_user("cl3   42"),
+            Some(ChangelogUserIndex {
+                id: "cl3".into(),
+                index: 42,
+                idle_secs: None,
+            })
+        );
+    }
+}
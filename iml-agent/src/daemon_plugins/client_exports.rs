@@ -0,0 +1,212 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Lists the clients connected to each target on this server.
+
+use crate::{
+    agent_error::ImlAgentError,
+    daemon_plugins::{DaemonPlugin, Output},
+};
+use futures::{lock::Mutex, Future, FutureExt};
+use iml_cmd::Command;
+use iml_wire_types::client::ClientExport;
+use std::{
+    collections::BTreeSet,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+static PARAMS: [&str; 2] = ["obdfilter.*.exports.*.uuid", "mdt.*.exports.*.uuid"];
+
+/// Resend unchanged exports every 5 min
+const DEFAULT_RESEND: Duration = Duration::from_secs(300);
+
+struct ClientExportsSub {
+    last: Instant,
+    output: Output,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientExports {
+    state: Arc<Mutex<ClientExportsSub>>,
+}
+
+pub fn create() -> impl DaemonPlugin {
+    ClientExports {
+        state: Arc::new(Mutex::new(ClientExportsSub {
+            last: Instant::now(),
+            output: None,
+        })),
+    }
+}
+
+/// Returns the target and NID of a param, i.e. `obdfilter.fs-OST0000.exports.10.0.0.1@tcp.uuid`.
+/// NIDs may contain `.`, so the NID is everything between `exports.` and `.uuid`
+fn parse_param(x: &str) -> Option<(&str, &str)> {
+    let x = x.strip_suffix(".uuid")?;
+
+    let (target, nid) = match x.find(".exports.") {
+        Some(idx) => (&x[..idx], &x[idx + ".exports.".len()..]),
+        None => return None,
+    };
+
+    let target = match target.split('.').collect::<Vec<_>>().as_slice() {
+        ["obdfilter", target] | ["mdt", target] => *target,
+        _ => return None,
+    };
+
+    Some((target, nid))
+}
+
+/// Parses the output of `lctl get_param` for `PARAMS`.
+///
+/// Each export lists the uuid of the connected client, either after the `=` or on the next line.
+/// Targets connect to each other as well. Those exports have a uuid ending in `_UUID`, i.e.
+/// `fs-MDT0000-mdtlov_UUID`, and are skipped.
+fn parse_exports(output: &str) -> Vec<ClientExport> {
+    let mut xs = BTreeSet::new();
+    let mut pending = None;
+
+    for line in output.lines() {
+        let line = line.trim();
+
+        let mut parts = line.splitn(2, '=');
+
+        if let (Some(name), Some(uuid)) = (parts.next(), parts.next()) {
+            if let Some((target, nid)) = parse_param(name) {
+                if uuid.is_empty() {
+                    pending = Some((target, nid));
+                } else if !uuid.ends_with("_UUID") {
+                    xs.insert((target, nid));
+                }
+
+                continue;
+            }
+        }
+
+        if let Some((target, nid)) = pending.take() {
+            if !line.is_empty() && !line.ends_with("_UUID") {
+                xs.insert((target, nid));
+            }
+        }
+    }
+
+    xs.into_iter()
+        .map(|(target, nid)| ClientExport {
+            target: target.to_string(),
+            nid: nid.to_string(),
+        })
+        .collect()
+}
+
+async fn get_exports() -> Result<Output, ImlAgentError> {
+    // `lctl` exits non-zero if there are no targets of a kind on this server, which is expected
+    let x = Command::new("lctl")
+        .arg("get_param")
+        .args(&PARAMS)
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    let xs = parse_exports(&String::from_utf8_lossy(&x.stdout));
+
+    Ok(serde_json::to_value(xs).map(Some)?)
+}
+
+impl DaemonPlugin for ClientExports {
+    fn start_session(
+        &mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<Output, ImlAgentError>> + Send>> {
+        let state = Arc::clone(&self.state);
+
+        async move {
+            let output = get_exports().await?;
+
+            let mut state = state.lock().await;
+
+            state.last = Instant::now();
+            state.output = output;
+
+            Ok(state.output.clone())
+        }
+        .boxed()
+    }
+
+    fn update_session(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<Output, ImlAgentError>> + Send>> {
+        let state = Arc::clone(&self.state);
+
+        async move {
+            let output = get_exports().await?;
+
+            let mut state = state.lock().await;
+
+            let now = Instant::now();
+
+            if state.output != output || now >= state.last + DEFAULT_RESEND {
+                state.last = now;
+                state.output = output;
+
+                Ok(state.output.clone())
+            } else {
+                Ok(None)
+            }
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_param() {
+        assert_eq!(
+            parse_param("obdfilter.fs-OST0000.exports.10.0.0.1@tcp.uuid"),
+            Some(("fs-OST0000", "10.0.0.1@tcp"))
+        );
+        assert_eq!(
+            parse_param("mdt.fs-MDT0000.exports.0@lo.uuid"),
+            Some(("fs-MDT0000", "0@lo"))
+        );
+        assert_eq!(parse_param("obdfilter.fs-OST0000.exports.clear"), None);
+        assert_eq!(
+            parse_param("osd-ldiskfs.fs-OST0000.exports.10.0.0.1@tcp.uuid"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_exports() {
+        let output = r#"obdfilter.fs-OST0000.exports.10.0.0.1@tcp.uuid=
+2b9a0cd1-8c3d-8f7e-7a6e-3c1e5b0d1f2a
+obdfilter.fs-OST0000.exports.10.0.0.2@tcp.uuid=fs-MDT0000-mdtlov_UUID
+obdfilter.fs-OST0001.exports.10.0.0.1@tcp.uuid=2b9a0cd1-8c3d-8f7e-7a6e-3c1e5b0d1f2a
+mdt.fs-MDT0000.exports.10.0.0.3@o2ib.uuid=
+9f1c2e3d-4b5a-6c7d-8e9f-0a1b2c3d4e5f
+fs-OST0000-lwp-MDT0000_UUID
+"#;
+
+        assert_eq!(
+            parse_exports(output),
+            vec![
+                ClientExport {
+                    target: "fs-MDT0000".into(),
+                    nid: "10.0.0.3@o2ib".into(),
+                },
+                ClientExport {
+                    target: "fs-OST0000".into(),
+                    nid: "10.0.0.1@tcp".into(),
+                },
+                ClientExport {
+                    target: "fs-OST0001".into(),
+                    nid: "10.0.0.1@tcp".into(),
+                },
+            ]
+        );
+    }
+}
@@ -3,18 +3,25 @@
 // license that can be found in the LICENSE file.
 
 use crate::{
+    action_plugins::check_stonith::get_stonith_nodes,
     agent_error::ImlAgentError,
     daemon_plugins::{DaemonPlugin, Output},
-    high_availability::{get_crm_mon, get_local_nodeid},
+    high_availability::{
+        get_corosync_links, get_crm_mon, get_local_nodeid, get_quorum, ConstraintCache,
+    },
 };
-use futures::{Future, FutureExt};
-use std::{pin::Pin, time::Duration};
+use futures::{lock::Mutex, Future, FutureExt};
+use std::{pin::Pin, sync::Arc, time::Duration};
 
 #[derive(Debug, Clone)]
-struct Corosync;
+struct Corosync {
+    constraints: Arc<Mutex<ConstraintCache>>,
+}
 
 pub fn create() -> impl DaemonPlugin {
-    Corosync
+    Corosync {
+        constraints: Arc::new(Mutex::new(ConstraintCache::default())),
+    }
 }
 
 impl DaemonPlugin for Corosync {
@@ -30,10 +37,22 @@ impl DaemonPlugin for Corosync {
     fn update_session(
         &self,
     ) -> Pin<Box<dyn Future<Output = Result<Output, ImlAgentError>> + Send>> {
+        let constraints = Arc::clone(&self.constraints);
+
         async move {
             let node_id = get_local_nodeid().await?;
 
-            let cluster = get_crm_mon().await?;
+            let mut cluster = get_crm_mon(&mut *constraints.lock().await).await?;
+
+            if let Some(cluster) = cluster.as_mut() {
+                let nodes: Vec<_> = cluster.nodes.iter().map(|x| x.name.clone()).collect();
+
+                cluster.stonith = get_stonith_nodes(&nodes).await?;
+
+                cluster.links = get_corosync_links().await?;
+
+                cluster.quorum = get_quorum().await?;
+            }
 
             let x = match (node_id, cluster) {
                 (Some(x), Some(y)) => Some((x, y)),
@@ -5,8 +5,8 @@
 use crate::{
     agent_error::{NoPluginError, Result},
     daemon_plugins::{
-        action_runner, corosync, device, journal, network, ntp, ostpool, postoffice, snapshot,
-        stats,
+        action_runner, changelog, client_exports, corosync, device, disk_health, journal, network,
+        ntp, ostpool, postoffice, quota, snapshot, stats, tunables,
     },
 };
 use async_trait::async_trait;
@@ -84,6 +84,11 @@ pub fn plugin_registry() -> DaemonPlugins {
         ("corosync".into(), mk_callback(corosync::create)),
         ("snapshot".into(), mk_callback(snapshot::create)),
         ("network".into(), mk_callback(network::create)),
+        ("disk_health".into(), mk_callback(disk_health::create)),
+        ("quota".into(), mk_callback(quota::create)),
+        ("tunables".into(), mk_callback(tunables::create)),
+        ("client_exports".into(), mk_callback(client_exports::create)),
+        ("changelog".into(), mk_callback(changelog::create)),
     ]
     .into_iter()
     .collect();
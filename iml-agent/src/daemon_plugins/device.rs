@@ -8,7 +8,10 @@ use crate::{
     device_scanner_client,
 };
 use async_trait::async_trait;
-use futures::{future, lock::Mutex, Future, FutureExt, StreamExt, TryFutureExt, TryStreamExt};
+use device_types::stream::{ResyncError, StreamMessage};
+use futures::{
+    future, lock::Mutex, Future, FutureExt, Stream, StreamExt, TryFutureExt, TryStreamExt,
+};
 use iml_cmd::Command;
 use lustre_collector::{mgs::mgs_fs_parser, parse_mgs_fs_output, Record, TargetStats};
 use serde_json::Value;
@@ -18,14 +21,94 @@ use stream_cancel::{Trigger, Tripwire};
 #[derive(Debug, Clone)]
 pub struct Devices {
     trigger: Option<Arc<Trigger>>,
-    state: Arc<Mutex<Output>>,
+    state: Arc<Mutex<Vec<StreamMessage>>>,
 }
 
 pub fn create() -> impl DaemonPlugin {
     Devices {
         trigger: None,
-        state: Arc::new(Mutex::new(None)),
+        state: Arc::new(Mutex::new(vec![])),
+    }
+}
+
+/// Queues a message to be sent to the manager.
+///
+/// A snapshot replaces anything that has not been sent yet.
+fn push_message(xs: &mut Vec<StreamMessage>, x: StreamMessage) {
+    if let StreamMessage::Snapshot { .. } = x {
+        xs.clear();
+    }
+
+    xs.push(x);
+}
+
+fn check_seq(seq: u64, x: &StreamMessage) -> Result<(), ResyncError> {
+    match x {
+        StreamMessage::Snapshot { .. } => Ok(()),
+        StreamMessage::Patches { seq: got, .. } if *got == seq + 1 => Ok(()),
+        StreamMessage::Patches { seq: got, .. } => Err(ResyncError::SeqGap {
+            expected: Some(seq + 1),
+            got: *got,
+        }),
+    }
+}
+
+fn stream_messages() -> impl Stream<Item = Result<StreamMessage, ImlAgentError>> {
+    device_scanner_client::stream_lines(device_scanner_client::Cmd::StreamPatches)
+        .and_then(|x| future::ready(serde_json::from_str(&x)).err_into())
+}
+
+/// Queues incoming messages until the stream ends or a message is missed.
+async fn consume_messages(
+    state: Arc<Mutex<Vec<StreamMessage>>>,
+    s: impl Stream<Item = Result<StreamMessage, ImlAgentError>>,
+    mut seq: u64,
+) -> Result<(), ImlAgentError> {
+    let mut s = Box::pin(s);
+
+    while let Some(x) = s.try_next().await? {
+        check_seq(seq, &x)?;
+
+        seq = x.seq();
+
+        push_message(&mut *state.lock().await, x);
+    }
+
+    Ok(())
+}
+
+/// Follows the device-scanner stream, reconnecting for a new
+/// snapshot whenever a message is missed.
+async fn follow_stream(
+    state: Arc<Mutex<Vec<StreamMessage>>>,
+    s: impl Stream<Item = Result<StreamMessage, ImlAgentError>>,
+    seq: u64,
+    tripwire: Tripwire,
+) -> Result<(), ImlAgentError> {
+    let mut r = consume_messages(Arc::clone(&state), s.take_until(tripwire.clone()), seq).await;
+
+    while let Err(ImlAgentError::ResyncError(e)) = r {
+        tracing::warn!("Device stream out of sync ({}), resyncing", e);
+
+        let (x, s) = stream_messages()
+            .take_until(tripwire.clone())
+            .boxed()
+            .into_future()
+            .await;
+
+        let x = match x {
+            Some(x) => x?,
+            None => return Ok(()),
+        };
+
+        let seq = x.seq();
+
+        push_message(&mut *state.lock().await, x);
+
+        r = consume_messages(Arc::clone(&state), s, seq).await;
     }
+
+    r
 }
 
 async fn get_mgs_fses() -> Result<Vec<String>, ImlAgentError> {
@@ -71,17 +154,14 @@ impl DaemonPlugin for Devices {
 
         self.trigger = Some(Arc::new(trigger));
 
-        let fut = device_scanner_client::stream_lines(device_scanner_client::Cmd::Stream)
-            .boxed()
-            .and_then(|x| future::ready(serde_json::from_str(&x)).err_into())
-            .into_future();
+        let fut = stream_messages().boxed().into_future();
 
         let state = Arc::clone(&self.state);
 
         Box::pin(async move {
-            let (x, s): (Option<Result<Value, ImlAgentError>>, _) = fut.await;
+            let (x, s): (Option<Result<StreamMessage, ImlAgentError>>, _) = fut.await;
 
-            let x: Value = match x {
+            let x: StreamMessage = match x {
                 Some(x) => x?,
                 None => {
                     return Err(ImlAgentError::Io(io::Error::new(
@@ -91,32 +171,18 @@ impl DaemonPlugin for Devices {
                 }
             };
 
-            {
-                state.lock().await.replace(x.clone());
-            }
-
-            tokio::spawn(
-                s.take_until(tripwire)
-                    .try_for_each(move |x| {
-                        let state = Arc::clone(&state);
-
-                        async move {
-                            state.lock().await.replace(x);
+            state.lock().await.clear();
 
-                            Ok(())
-                        }
-                    })
-                    .map(|x| {
-                        if let Err(e) = x {
-                            tracing::error!("Error processing device output: {}", e);
-                        }
-                    }),
-            );
+            tokio::spawn(follow_stream(state, s, x.seq(), tripwire).map(|x| {
+                if let Err(e) = x {
+                    tracing::error!("Error processing device output: {}", e);
+                }
+            }));
 
             let fses = get_mgs_fses().await?;
             let fses = serde_json::to_value(fses)?;
 
-            let x = Some(Value::Array(vec![x, fses]));
+            let x = Some(Value::Array(vec![serde_json::to_value(vec![x])?, fses]));
 
             Ok(x)
         })
@@ -130,13 +196,13 @@ impl DaemonPlugin for Devices {
             let fses = get_mgs_fses().await?;
             let fses = serde_json::to_value(fses)?;
 
-            let x = state
-                .lock()
-                .await
-                .take()
-                .map(|x| Value::Array(vec![x, fses]));
+            let xs: Vec<StreamMessage> = state.lock().await.drain(..).collect();
 
-            Ok(x)
+            if xs.is_empty() {
+                return Ok(None);
+            }
+
+            Ok(Some(Value::Array(vec![serde_json::to_value(xs)?, fses])))
         }
         .boxed()
     }
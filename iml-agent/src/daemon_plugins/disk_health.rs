@@ -0,0 +1,531 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    agent_error::ImlAgentError,
+    daemon_plugins::{DaemonPlugin, Output},
+    device_scanner_client,
+};
+use device_types::devices::Device;
+use futures::{future::join_all, lock::Mutex, Future, FutureExt};
+use iml_cmd::Command;
+use iml_fs::file_exists;
+use iml_wire_types::disk_health::{DiskHealth, DiskHealthData};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Read SMART data every 5 min
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(300);
+
+static SMARTCTL_PATH: &str = "/usr/sbin/smartctl";
+static NVME_PATH: &str = "/usr/sbin/nvme";
+
+/// ATA SMART attributes we chart, by attribute id
+static ATA_ATTRIBUTES: &[(u64, &str)] = &[
+    (5, "reallocated_sectors"),
+    (187, "reported_uncorrectable"),
+    (197, "pending_sectors"),
+    (198, "offline_uncorrectable"),
+    (199, "crc_errors"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DriveKind {
+    Scsi,
+    Nvme,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Drive {
+    id: String,
+    path: String,
+    kind: DriveKind,
+}
+
+struct DiskHealthStateSub {
+    last: Instant,
+    disks: BTreeMap<String, DiskHealth>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiskHealthState {
+    state: Arc<Mutex<DiskHealthStateSub>>,
+}
+
+pub fn create() -> impl DaemonPlugin {
+    DiskHealthState {
+        state: Arc::new(Mutex::new(DiskHealthStateSub {
+            last: Instant::now(),
+            disks: BTreeMap::new(),
+        })),
+    }
+}
+
+/// Lists the physical drives device-scanner knows about.
+///
+/// The shortest path is used to address the drive, which will be the kernel name (i.e. `/dev/sda`).
+fn list_drives(root: &Device) -> Vec<Drive> {
+    root.children()
+        .into_iter()
+        .flatten()
+        .filter_map(|x| {
+            let (kind, paths) = match x {
+                Device::ScsiDevice(y) => (DriveKind::Scsi, &y.paths),
+                Device::NvmeNamespace(y) => (DriveKind::Nvme, &y.paths),
+                _ => return None,
+            };
+
+            let path = paths
+                .iter()
+                .map(|p| p.0.to_string_lossy().to_string())
+                .min_by_key(|p| p.len())?;
+
+            Some(Drive {
+                id: x.get_id()?.0,
+                path,
+                kind,
+            })
+        })
+        .collect()
+}
+
+/// Reads a counter that may be encoded as a number or a numeric string.
+fn as_i64(x: &Value) -> Option<i64> {
+    match x {
+        Value::Number(n) => n.as_i64().or_else(|| n.as_f64().map(|n| n as i64)),
+        Value::String(s) => s.replace(',', "").parse().ok(),
+        _ => None,
+    }
+}
+
+fn insert_attribute(attributes: &mut BTreeMap<String, i64>, name: &str, x: &Value) {
+    if let Some(x) = as_i64(x) {
+        attributes.insert(name.to_string(), x);
+    }
+}
+
+fn parse_smartctl_output(drive: &Drive, x: &[u8]) -> Result<DiskHealth, ImlAgentError> {
+    let v: Value = serde_json::from_slice(x)?;
+
+    let mut attributes = BTreeMap::new();
+
+    insert_attribute(&mut attributes, "temperature", &v["temperature"]["current"]);
+    insert_attribute(
+        &mut attributes,
+        "power_on_hours",
+        &v["power_on_time"]["hours"],
+    );
+
+    let passed = v["smart_status"]["passed"].as_bool();
+
+    let mut predictive_failure = passed == Some(false);
+
+    for x in v["ata_smart_attributes"]["table"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        // A threshold that is currently exceeded means the drive expects to fail
+        if x["when_failed"].as_str() == Some("now") {
+            predictive_failure = true;
+        }
+
+        let name = ATA_ATTRIBUTES
+            .iter()
+            .find(|(id, _)| x["id"].as_u64() == Some(*id))
+            .map(|(_, name)| name);
+
+        if let Some(name) = name {
+            insert_attribute(&mut attributes, name, &x["raw"]["value"]);
+        }
+    }
+
+    insert_attribute(
+        &mut attributes,
+        "grown_defects",
+        &v["scsi_grown_defect_list"],
+    );
+
+    let log = &v["scsi_error_counter_log"];
+
+    insert_attribute(
+        &mut attributes,
+        "read_uncorrected_errors",
+        &log["read"]["total_uncorrected_errors"],
+    );
+    insert_attribute(
+        &mut attributes,
+        "write_uncorrected_errors",
+        &log["write"]["total_uncorrected_errors"],
+    );
+
+    let model = v["model_name"]
+        .as_str()
+        .or_else(|| v["scsi_model_name"].as_str())
+        .map(|x| x.to_string());
+
+    Ok(DiskHealth {
+        device_id: drive.id.clone(),
+        path: drive.path.clone(),
+        model,
+        serial_number: v["serial_number"].as_str().map(|x| x.to_string()),
+        passed,
+        predictive_failure,
+        attributes,
+    })
+}
+
+fn parse_nvme_smart_log(drive: &Drive, x: &[u8]) -> Result<DiskHealth, ImlAgentError> {
+    let v: Value = serde_json::from_slice(x)?;
+
+    let mut attributes = BTreeMap::new();
+
+    for name in &[
+        "critical_warning",
+        "avail_spare",
+        "spare_thresh",
+        "media_errors",
+        "num_err_log_entries",
+        "power_on_hours",
+        "unsafe_shutdowns",
+    ] {
+        insert_attribute(&mut attributes, name, &v[name]);
+    }
+
+    // Older nvme-cli releases call this `percent_used`
+    match v.get("percentage_used") {
+        Some(x) => insert_attribute(&mut attributes, "percent_used", x),
+        None => insert_attribute(&mut attributes, "percent_used", &v["percent_used"]),
+    }
+
+    // Reported in Kelvin
+    if let Some(x) = as_i64(&v["temperature"]) {
+        attributes.insert("temperature".into(), x - 273);
+    }
+
+    let get = |k: &str| attributes.get(k).copied();
+
+    let predictive_failure = get("critical_warning").unwrap_or(0) != 0
+        || match (get("avail_spare"), get("spare_thresh")) {
+            (Some(spare), Some(thresh)) => spare < thresh,
+            _ => false,
+        }
+        || get("percent_used").unwrap_or(0) >= 100;
+
+    let passed = get("critical_warning").map(|x| x == 0);
+
+    Ok(DiskHealth {
+        device_id: drive.id.clone(),
+        path: drive.path.clone(),
+        model: None,
+        serial_number: None,
+        passed,
+        predictive_failure,
+        attributes,
+    })
+}
+
+async fn read_smartctl(drive: &Drive) -> Result<Option<DiskHealth>, ImlAgentError> {
+    let output = Command::new(SMARTCTL_PATH)
+        .kill_on_drop(true)
+        .args(&["--json", "-a", &drive.path])
+        .output()
+        .await?;
+
+    // The exit status is a bitmask; the low two bits mean the device could not be read at all.
+    // Other bits flag a failing drive, which is still reported in the output.
+    match output.status.code() {
+        Some(x) if x & 0b11 == 0 => {}
+        _ => return Ok(None),
+    }
+
+    parse_smartctl_output(drive, &output.stdout).map(Some)
+}
+
+async fn read_nvme_smart_log(drive: &Drive) -> Result<Option<DiskHealth>, ImlAgentError> {
+    let output = Command::new(NVME_PATH)
+        .kill_on_drop(true)
+        .args(&["smart-log", &drive.path, "-o", "json"])
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    parse_nvme_smart_log(drive, &output.stdout).map(Some)
+}
+
+async fn read_health(drive: Drive) -> Option<DiskHealth> {
+    let x = match drive.kind {
+        DriveKind::Scsi if file_exists(SMARTCTL_PATH).await => read_smartctl(&drive).await,
+        DriveKind::Nvme if file_exists(NVME_PATH).await => read_nvme_smart_log(&drive).await,
+        _ => Ok(None),
+    };
+
+    match x {
+        Ok(x) => x,
+        Err(e) => {
+            tracing::debug!("Could not read SMART data for {}: {}", drive.path, e);
+
+            None
+        }
+    }
+}
+
+async fn collect() -> Result<BTreeMap<String, DiskHealth>, ImlAgentError> {
+    let root = device_scanner_client::get_devices().await?;
+
+    let xs = join_all(list_drives(&root).into_iter().map(read_health))
+        .await
+        .into_iter()
+        .flatten()
+        .map(|x| (x.device_id.clone(), x))
+        .collect();
+
+    Ok(xs)
+}
+
+/// Computes what changed between two reads.
+///
+/// Drives that changed are sent with only the attributes that differ.
+fn diff(old: &BTreeMap<String, DiskHealth>, new: &BTreeMap<String, DiskHealth>) -> DiskHealthData {
+    let disks = new
+        .values()
+        .filter_map(|x| match old.get(&x.device_id) {
+            None => Some(x.clone()),
+            Some(y) if y == x => None,
+            Some(y) => Some(DiskHealth {
+                attributes: x
+                    .attributes
+                    .iter()
+                    .filter(|(k, v)| y.attributes.get(*k) != Some(v))
+                    .map(|(k, v)| (k.clone(), *v))
+                    .collect(),
+                ..x.clone()
+            }),
+        })
+        .collect();
+
+    let removed = old
+        .keys()
+        .filter(|k| !new.contains_key(*k))
+        .cloned()
+        .collect();
+
+    DiskHealthData {
+        full: false,
+        disks,
+        removed,
+    }
+}
+
+impl DaemonPlugin for DiskHealthState {
+    fn deadline(&self) -> Duration {
+        Duration::from_secs(60)
+    }
+    fn start_session(
+        &mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<Output, ImlAgentError>> + Send>> {
+        let state = Arc::clone(&self.state);
+
+        async move {
+            let mut state = state.lock().await;
+
+            let disks = collect().await?;
+
+            state.last = Instant::now();
+            state.disks = disks;
+
+            let x = DiskHealthData {
+                full: true,
+                disks: state.disks.values().cloned().collect(),
+                removed: vec![],
+            };
+
+            let x = serde_json::to_value(x).map(Some)?;
+
+            Ok(x)
+        }
+        .boxed()
+    }
+
+    fn update_session(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<Output, ImlAgentError>> + Send>> {
+        let state = Arc::clone(&self.state);
+
+        async move {
+            let mut state = state.lock().await;
+
+            let now = Instant::now();
+
+            if state.last + DEFAULT_INTERVAL > now {
+                return Ok(None);
+            }
+
+            let disks = collect().await?;
+
+            let x = diff(&state.disks, &disks);
+
+            state.last = now;
+            state.disks = disks;
+
+            if x.disks.is_empty() && x.removed.is_empty() {
+                return Ok(None);
+            }
+
+            let x = serde_json::to_value(x).map(Some)?;
+
+            Ok(x)
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drive(kind: DriveKind) -> Drive {
+        Drive {
+            id: "scsi_35000c500a1b2c3d4".into(),
+            path: "/dev/sda".into(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_parse_smartctl_output_ata() {
+        let x = br#"{
+            "model_name": "ST4000NM0035",
+            "serial_number": "ZC1ABCDE",
+            "smart_status": {"passed": true},
+            "temperature": {"current": 31},
+            "power_on_time": {"hours": 21034},
+            "ata_smart_attributes": {
+                "table": [
+                    {"id": 5, "name": "Reallocated_Sector_Ct", "when_failed": "", "raw": {"value": 8}},
+                    {"id": 9, "name": "Power_On_Hours", "when_failed": "", "raw": {"value": 21034}},
+                    {"id": 197, "name": "Current_Pending_Sector", "when_failed": "now", "raw": {"value": 72}}
+                ]
+            }
+        }"#;
+
+        let x = parse_smartctl_output(&drive(DriveKind::Scsi), x).unwrap();
+
+        assert_eq!(x.model.as_deref(), Some("ST4000NM0035"));
+        assert_eq!(x.passed, Some(true));
+        assert!(x.predictive_failure);
+        assert_eq!(
+            x.attributes,
+            vec![
+                ("pending_sectors".to_string(), 72),
+                ("power_on_hours".to_string(), 21034),
+                ("reallocated_sectors".to_string(), 8),
+                ("temperature".to_string(), 31),
+            ]
+            .into_iter()
+            .collect()
+        );
+    }
+
+    #[test]
+    fn test_parse_smartctl_output_scsi() {
+        let x = br#"{
+            "scsi_model_name": "AL14SEB060N",
+            "serial_number": "X8A0A0AAAAAA",
+            "smart_status": {"passed": false},
+            "temperature": {"current": 40},
+            "scsi_grown_defect_list": 12,
+            "scsi_error_counter_log": {
+                "read": {"total_uncorrected_errors": 3},
+                "write": {"total_uncorrected_errors": 0}
+            }
+        }"#;
+
+        let x = parse_smartctl_output(&drive(DriveKind::Scsi), x).unwrap();
+
+        assert_eq!(x.model.as_deref(), Some("AL14SEB060N"));
+        assert_eq!(x.passed, Some(false));
+        assert!(x.predictive_failure);
+        assert_eq!(x.attributes.get("grown_defects"), Some(&12));
+        assert_eq!(x.attributes.get("read_uncorrected_errors"), Some(&3));
+        assert_eq!(x.attributes.get("write_uncorrected_errors"), Some(&0));
+    }
+
+    #[test]
+    fn test_parse_nvme_smart_log() {
+        let x = br#"{
+            "critical_warning": 0,
+            "temperature": 308,
+            "avail_spare": 100,
+            "spare_thresh": 10,
+            "percent_used": 3,
+            "media_errors": 0,
+            "num_err_log_entries": 14,
+            "power_on_hours": 5021,
+            "unsafe_shutdowns": 9
+        }"#;
+
+        let x = parse_nvme_smart_log(&drive(DriveKind::Nvme), x).unwrap();
+
+        assert_eq!(x.passed, Some(true));
+        assert!(!x.predictive_failure);
+        assert_eq!(x.attributes.get("temperature"), Some(&35));
+        assert_eq!(x.attributes.get("percent_used"), Some(&3));
+
+        let x = br#"{"critical_warning": 0, "avail_spare": 5, "spare_thresh": 10}"#;
+
+        let x = parse_nvme_smart_log(&drive(DriveKind::Nvme), x).unwrap();
+
+        assert!(x.predictive_failure);
+    }
+
+    #[test]
+    fn test_diff() {
+        let a = parse_nvme_smart_log(
+            &drive(DriveKind::Nvme),
+            br#"{"critical_warning": 0, "media_errors": 0, "power_on_hours": 10}"#,
+        )
+        .unwrap();
+
+        let b = DiskHealth {
+            attributes: vec![
+                ("critical_warning".to_string(), 0),
+                ("media_errors".to_string(), 0),
+                ("power_on_hours".to_string(), 11),
+            ]
+            .into_iter()
+            .collect(),
+            ..a.clone()
+        };
+
+        let old = vec![(a.device_id.clone(), a.clone())].into_iter().collect();
+        let new = vec![(b.device_id.clone(), b)].into_iter().collect();
+
+        let x = diff(&old, &new);
+
+        assert_eq!(x.disks.len(), 1);
+        assert_eq!(
+            x.disks[0].attributes,
+            vec![("power_on_hours".to_string(), 11)]
+                .into_iter()
+                .collect()
+        );
+        assert!(x.removed.is_empty());
+
+        let x = diff(&old, &BTreeMap::new());
+
+        assert!(x.disks.is_empty());
+        assert_eq!(x.removed, vec![a.device_id]);
+
+        assert_eq!(diff(&old, &old), DiskHealthData::default());
+    }
+}
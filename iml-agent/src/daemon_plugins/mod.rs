@@ -10,16 +10,21 @@
 //! Each plugin is wrapped in a session which provides a connection guarantee with the IML manager.
 
 pub mod action_runner;
+pub mod changelog;
+pub mod client_exports;
 pub mod corosync;
 pub mod daemon_plugin;
 pub mod device;
+pub mod disk_health;
 pub mod journal;
 pub mod network;
 pub mod ntp;
 pub mod ostpool;
 pub mod postoffice;
+pub mod quota;
 pub mod snapshot;
 pub mod stats;
+pub mod tunables;
 
 pub use daemon_plugin::{
     get_plugin, plugin_registry, DaemonBox, DaemonPlugin, DaemonPlugins, Output, OutputValue,
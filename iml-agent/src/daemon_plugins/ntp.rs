@@ -21,6 +21,9 @@ use std::pin::Pin;
 
 static NTP_CONFIG_FILE: &str = "/etc/ntp.conf";
 
+/// Sync state, offset message and offset in seconds of a time sync client
+type SyncResult = (Option<time::Synced>, Option<time::Offset>, Option<f64>);
+
 async fn get_time_sync_services() -> Result<(RunState, RunState), ImlAgentError> {
     let ntpd = iml_systemd::get_run_state("ntpd.service".into()).err_into();
     let chronyd = iml_systemd::get_run_state("chronyd.service".into()).err_into();
@@ -62,6 +65,28 @@ fn parse_chrony_time_offset(output: impl ToString) -> Option<time::Offset> {
         .map(|x| x.as_str().into())
 }
 
+fn parse_chrony_offset_secs(output: impl ToString) -> Option<f64> {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"Offset          : ([+-][0-9.]+) seconds\n").unwrap();
+    }
+
+    RE.captures(&output.to_string())
+        .and_then(|caps| caps.get(1))
+        .and_then(|x| x.as_str().parse().ok())
+}
+
+/// Parses the output of `ntpq -c "rv 0 offset"`, which reports the offset in milliseconds.
+fn parse_ntpq_offset_secs(output: impl ToString) -> Option<f64> {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"offset=([+-]?[0-9.]+)").unwrap();
+    }
+
+    RE.captures(&output.to_string())
+        .and_then(|caps| caps.get(1))
+        .and_then(|x| x.as_str().parse::<f64>().ok())
+        .map(|x| x / 1000.0)
+}
+
 #[derive(Debug, Clone)]
 pub struct Ntp;
 
@@ -96,6 +121,16 @@ async fn get_chronyc_ntpdata_command() -> Result<String, ImlAgentError> {
     Ok(std::str::from_utf8(&p.stdout).unwrap_or("").to_string())
 }
 
+async fn get_ntpq_offset_command() -> Result<String, ImlAgentError> {
+    let p = Command::new("ntpq")
+        .args(vec!["-c", "rv 0 offset"])
+        .kill_on_drop(true)
+        .checked_output()
+        .await?;
+
+    Ok(std::str::from_utf8(&p.stdout).unwrap_or("").to_string())
+}
+
 async fn is_ntp_synced_command() -> Result<String, ImlAgentError> {
     let x = Command::new("busctl")
         .args(vec![
@@ -116,7 +151,7 @@ async fn is_ntp_synced_command() -> Result<String, ImlAgentError> {
 }
 
 /// Returns Ntp sync and offset.
-async fn ntpd_synced() -> Result<(Option<time::Synced>, Option<time::Offset>), ImlAgentError> {
+async fn ntpd_synced() -> Result<SyncResult, ImlAgentError> {
     let ntp_synced = is_ntp_synced_command().map_ok(parse_ntp_synced).map(|x| {
         x.or_else(|e| {
             tracing::warn!("Unable to get ntp sync state: {}", e);
@@ -125,12 +160,21 @@ async fn ntpd_synced() -> Result<(Option<time::Synced>, Option<time::Offset>), I
         })
     });
     let time_offset = get_ntpstat_command().map_ok(parse_ntp_time_offset);
+    let offset_secs = get_ntpq_offset_command()
+        .map_ok(parse_ntpq_offset_secs)
+        .map(|x| {
+            x.or_else(|e| {
+                tracing::warn!("Unable to get ntp offset: {}", e);
+
+                Ok(None)
+            })
+        });
 
-    future::try_join(ntp_synced, time_offset).await
+    future::try_join3(ntp_synced, time_offset, offset_secs).await
 }
 
 /// Returns Chrony sync and offset
-async fn chronyd_synced() -> Result<(Option<time::Synced>, Option<time::Offset>), ImlAgentError> {
+async fn chronyd_synced() -> Result<SyncResult, ImlAgentError> {
     let ntp_synced = is_ntp_synced_command().map_ok(parse_ntp_synced).map(|x| {
         x.or_else(|e| {
             tracing::warn!("Unable to get chrony sync state: {}", e);
@@ -138,16 +182,22 @@ async fn chronyd_synced() -> Result<(Option<time::Synced>, Option<time::Offset>)
             Ok(None)
         })
     });
-    let time_offset = get_chronyc_ntpdata_command().map_ok(parse_chrony_time_offset);
+    let ntpdata = get_chronyc_ntpdata_command();
 
-    future::try_join(ntp_synced, time_offset).await
+    let (synced, ntpdata) = future::try_join(ntp_synced, ntpdata).await?;
+
+    Ok((
+        synced,
+        parse_chrony_time_offset(&ntpdata),
+        parse_chrony_offset_secs(&ntpdata),
+    ))
 }
 
 async fn time_synced<
     F1: Future<Output = Result<bool, ImlAgentError>>,
     F2: Future<Output = Result<(RunState, RunState), ImlAgentError>>,
-    F3: Future<Output = Result<(Option<time::Synced>, Option<time::Offset>), ImlAgentError>>,
-    F4: Future<Output = Result<(Option<time::Synced>, Option<time::Offset>), ImlAgentError>>,
+    F3: Future<Output = Result<SyncResult, ImlAgentError>>,
+    F4: Future<Output = Result<SyncResult, ImlAgentError>>,
 >(
     is_ntp_configured_by_iml: fn() -> F1,
     get_time_sync_services: fn() -> F2,
@@ -166,31 +216,38 @@ async fn time_synced<
         chrony_runstate
     );
 
-    let state = if ntp_runstate >= RunState::Started && chrony_runstate >= RunState::Started {
+    let status = if ntp_runstate >= RunState::Started && chrony_runstate >= RunState::Started {
         // Both chronyd and ntpd should not be running at the same time.
-        time::State::Multiple
+        time::Status {
+            state: time::State::Multiple,
+            offset: None,
+        }
     } else if ntp_runstate < RunState::Started && chrony_runstate < RunState::Started {
         // Neither chronyd or ntpd are running
-        time::State::None
-    } else if ntp_runstate >= RunState::Started {
-        let (ntp_synced, ntp_offset) = ntpd_synced().await?;
-
-        match ntp_synced {
-            Some(time::Synced::Synced) => time::State::Synced,
-            Some(time::Synced::Unsynced) => time::State::Unsynced(ntp_offset),
-            None => time::State::Unknown,
+        time::Status {
+            state: time::State::None,
+            offset: None,
         }
     } else {
-        let (chrony_synced, chrony_offset) = chronyd_synced().await?;
+        let (synced, offset, offset_secs) = if ntp_runstate >= RunState::Started {
+            ntpd_synced().await?
+        } else {
+            chronyd_synced().await?
+        };
 
-        match chrony_synced {
+        let state = match synced {
             Some(time::Synced::Synced) => time::State::Synced,
-            Some(time::Synced::Unsynced) => time::State::Unsynced(chrony_offset),
+            Some(time::Synced::Unsynced) => time::State::Unsynced(offset),
             None => time::State::Unknown,
+        };
+
+        time::Status {
+            state,
+            offset: offset_secs,
         }
     };
 
-    let x = serde_json::to_value(state).map(Some)?;
+    let x = serde_json::to_value(status).map(Some)?;
 
     Ok(x)
 }
@@ -284,6 +341,16 @@ Total valid RX  : 1129"#;
             parse_chrony_time_offset(s),
             Some("0.000026767 seconds".to_string().into())
         );
+        assert_eq!(parse_chrony_offset_secs(s), Some(0.000_026_767));
+    }
+
+    #[test]
+    fn test_get_ntpq_offset() {
+        assert_eq!(parse_ntpq_offset_secs("offset=-1.250\n"), Some(-0.00125));
+        assert_eq!(
+            parse_ntpq_offset_secs("No association ID's returned\n"),
+            None
+        );
     }
 
     #[tokio::test]
@@ -300,30 +367,19 @@ Total valid RX  : 1129"#;
             future::ok((RunState::Setup, RunState::Stopped)).boxed()
         }
 
-        fn ntpd_synced() -> Pin<
-            Box<
-                dyn Future<
-                        Output = Result<
-                            (Option<time::Synced>, Option<time::Offset>),
-                            ImlAgentError,
-                        >,
-                    > + Send,
-            >,
-        > {
-            future::ok((Some(time::Synced::Synced), Some("949 ms".into()))).boxed()
+        fn ntpd_synced() -> Pin<Box<dyn Future<Output = Result<SyncResult, ImlAgentError>> + Send>>
+        {
+            future::ok((
+                Some(time::Synced::Synced),
+                Some("949 ms".into()),
+                Some(-0.000_125),
+            ))
+            .boxed()
         }
 
-        fn chronyd_synced() -> Pin<
-            Box<
-                dyn Future<
-                        Output = Result<
-                            (Option<time::Synced>, Option<time::Offset>),
-                            ImlAgentError,
-                        >,
-                    > + Send,
-            >,
-        > {
-            future::ok((Some(time::Synced::Unsynced), None)).boxed()
+        fn chronyd_synced(
+        ) -> Pin<Box<dyn Future<Output = Result<SyncResult, ImlAgentError>> + Send>> {
+            future::ok((Some(time::Synced::Unsynced), None, None)).boxed()
         }
 
         let r = time_synced(
@@ -0,0 +1,321 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Reads quota limits from the quota master and per target quota accounting.
+//!
+//! Limits are only available on the server running MDT0000 of a filesystem.
+//! Accounting is available on every server with mounted targets.
+
+use crate::{
+    agent_error::ImlAgentError,
+    daemon_plugins::{DaemonPlugin, Output},
+};
+use futures::{lock::Mutex, Future, FutureExt};
+use iml_cmd::Command;
+use iml_wire_types::quota::{QuotaAcct, QuotaData, QuotaKind, QuotaLimit, QuotaResource};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::{
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Resend unchanged quota data every 5 min
+const DEFAULT_RESEND: Duration = Duration::from_secs(300);
+
+static PARAMS: [&str; 6] = [
+    "qmt.*.*.glb-usr",
+    "qmt.*.*.glb-grp",
+    "qmt.*.*.glb-prj",
+    "osd-*.*.quota_slave.acct_user",
+    "osd-*.*.quota_slave.acct_group",
+    "osd-*.*.quota_slave.acct_project",
+];
+
+struct QuotaSub {
+    last: Instant,
+    output: Output,
+}
+
+#[derive(Debug, Clone)]
+pub struct Quota {
+    state: Arc<Mutex<QuotaSub>>,
+}
+
+pub fn create() -> impl DaemonPlugin {
+    Quota {
+        state: Arc::new(Mutex::new(QuotaSub {
+            last: Instant::now(),
+            output: None,
+        })),
+    }
+}
+
+/// The source of a block of `lctl get_param` output
+#[derive(Debug, PartialEq)]
+enum Param {
+    /// i.e. `qmt.fs-QMT0000.dt-0x0.glb-usr`
+    Limits {
+        fs_name: String,
+        pool: Option<String>,
+        resource: QuotaResource,
+        kind: QuotaKind,
+    },
+    /// i.e. `osd-ldiskfs.fs-MDT0000.quota_slave.acct_user`
+    Acct { target: String, kind: QuotaKind },
+}
+
+fn parse_param(x: &str) -> Option<Param> {
+    let xs: Vec<&str> = x.split('.').collect();
+
+    match xs.as_slice() {
+        ["qmt", qmt, pool, glb] => {
+            let fs_name = qmt.rsplitn(2, '-').nth(1)?.to_string();
+
+            let (resource, pool) = match pool.split_at(pool.find('-')?) {
+                ("dt", pool) => (QuotaResource::Block, &pool[1..]),
+                ("md", pool) => (QuotaResource::Inode, &pool[1..]),
+                _ => return None,
+            };
+
+            let pool = match pool {
+                "0x0" => None,
+                x => Some(x.to_string()),
+            };
+
+            let kind = glb.strip_prefix("glb-")?.parse().ok()?;
+
+            Some(Param::Limits {
+                fs_name,
+                pool,
+                resource,
+                kind,
+            })
+        }
+        [osd, target, "quota_slave", acct] if osd.starts_with("osd-") => {
+            let kind = acct.strip_prefix("acct_")?.parse().ok()?;
+
+            Some(Param::Acct {
+                target: target.to_string(),
+                kind,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn parse_quota_output(output: &str) -> QuotaData {
+    lazy_static! {
+        static ref ID: Regex = Regex::new(r"^- id:\s+(\d+)").unwrap();
+        static ref USAGE: Regex =
+            Regex::new(r"usage:\s+\{ inodes:\s+(\d+), kbytes:\s+(\d+) \}").unwrap();
+        static ref LIMITS: Regex = Regex::new(
+            r"limits:\s+\{ hard:\s+(\d+), soft:\s+(\d+), granted:\s+(\d+), time:\s+(\d+) \}"
+        )
+        .unwrap();
+    }
+
+    let mut data = QuotaData::default();
+
+    let mut param = None;
+    let mut id = None;
+
+    for line in output.lines() {
+        if let Some(x) = line.strip_suffix('=') {
+            param = parse_param(x);
+            id = None;
+
+            continue;
+        }
+
+        if let Some(caps) = ID.captures(line) {
+            id = caps[1].parse().ok();
+        }
+
+        match (&param, id) {
+            (
+                Some(Param::Limits {
+                    fs_name,
+                    pool,
+                    resource,
+                    kind,
+                }),
+                Some(id),
+            ) => {
+                if let Some(caps) = LIMITS.captures(line) {
+                    data.limits.push(QuotaLimit {
+                        fs_name: fs_name.clone(),
+                        pool: pool.clone(),
+                        kind: *kind,
+                        resource: *resource,
+                        id,
+                        hard: caps[1].parse().unwrap_or_default(),
+                        soft: caps[2].parse().unwrap_or_default(),
+                        granted: caps[3].parse().unwrap_or_default(),
+                        time: caps[4].parse().unwrap_or_default(),
+                    });
+                }
+            }
+            (Some(Param::Acct { target, kind }), Some(id)) => {
+                if let Some(caps) = USAGE.captures(line) {
+                    data.usage.push(QuotaAcct {
+                        target: target.clone(),
+                        kind: *kind,
+                        id,
+                        inodes: caps[1].parse().unwrap_or_default(),
+                        kbytes: caps[2].parse().unwrap_or_default(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    data
+}
+
+async fn get_quota_data() -> Result<Output, ImlAgentError> {
+    // `lctl` exits non-zero when a param does not match, which is expected
+    // for the quota master on all but one server.
+    let x = Command::new("lctl")
+        .arg("get_param")
+        .args(&PARAMS)
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    let data = parse_quota_output(&String::from_utf8_lossy(&x.stdout));
+
+    Ok(serde_json::to_value(data).map(Some)?)
+}
+
+impl DaemonPlugin for Quota {
+    fn start_session(
+        &mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<Output, ImlAgentError>> + Send>> {
+        let state = Arc::clone(&self.state);
+
+        async move {
+            let output = get_quota_data().await?;
+
+            let mut state = state.lock().await;
+
+            state.last = Instant::now();
+            state.output = output;
+
+            Ok(state.output.clone())
+        }
+        .boxed()
+    }
+
+    fn update_session(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<Output, ImlAgentError>> + Send>> {
+        let state = Arc::clone(&self.state);
+
+        async move {
+            let output = get_quota_data().await?;
+
+            let mut state = state.lock().await;
+
+            let now = Instant::now();
+
+            if state.output != output || now >= state.last + DEFAULT_RESEND {
+                state.last = now;
+                state.output = output;
+
+                Ok(state.output.clone())
+            } else {
+                Ok(None)
+            }
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_param() {
+        assert_eq!(
+            parse_param("qmt.fs-QMT0000.dt-flash.glb-prj"),
+            Some(Param::Limits {
+                fs_name: "fs".into(),
+                pool: Some("flash".into()),
+                resource: QuotaResource::Block,
+                kind: QuotaKind::Project,
+            })
+        );
+        assert_eq!(
+            parse_param("osd-zfs.fs-OST0001.quota_slave.acct_group"),
+            Some(Param::Acct {
+                target: "fs-OST0001".into(),
+                kind: QuotaKind::Group,
+            })
+        );
+        assert_eq!(parse_param("osd-zfs.fs-OST0001.quota_slave.info"), None);
+    }
+
+    #[test]
+    fn test_parse_quota_output() {
+        let output = r#"qmt.fs-QMT0000.dt-0x0.glb-usr=
+global_pool0_dt_usr
+- id:      0
+  limits:  { hard:                    0, soft:                    0, granted:                    0, time:               604800 }
+- id:      1000
+  limits:  { hard:                20480, soft:                10240, granted:                12288, time:           1609459200 }
+qmt.fs-QMT0000.md-0x0.glb-usr=
+global_pool0_md_usr
+- id:      1000
+  limits:  { hard:                  200, soft:                  100, granted:                    5, time:                    0 }
+osd-ldiskfs.fs-MDT0000.quota_slave.acct_user=
+usr_accounting:
+- id:      0
+  usage:   { inodes:                  267, kbytes:                 1224 }
+- id:      1000
+  usage:   { inodes:                    5, kbytes:                   20 }
+"#;
+
+        let x = parse_quota_output(output);
+
+        assert_eq!(x.limits.len(), 3);
+        assert_eq!(
+            x.limits[1],
+            QuotaLimit {
+                fs_name: "fs".into(),
+                pool: None,
+                kind: QuotaKind::User,
+                resource: QuotaResource::Block,
+                id: 1000,
+                hard: 20480,
+                soft: 10240,
+                granted: 12288,
+                time: 1_609_459_200,
+            }
+        );
+        assert_eq!(x.limits[2].resource, QuotaResource::Inode);
+        assert_eq!(
+            x.usage,
+            vec![
+                QuotaAcct {
+                    target: "fs-MDT0000".into(),
+                    kind: QuotaKind::User,
+                    id: 0,
+                    kbytes: 1224,
+                    inodes: 267,
+                },
+                QuotaAcct {
+                    target: "fs-MDT0000".into(),
+                    kind: QuotaKind::User,
+                    id: 1000,
+                    kbytes: 20,
+                    inodes: 5,
+                }
+            ]
+        );
+    }
+}
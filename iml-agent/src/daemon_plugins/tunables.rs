@@ -0,0 +1,177 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Reads back the tunables listed by the `tunable_watch` action,
+//! so the manager can compare them with their desired values.
+
+use crate::{
+    agent_error::ImlAgentError,
+    daemon_plugins::{DaemonPlugin, Output},
+    env,
+};
+use futures::{lock::Mutex, Future, FutureExt};
+use iml_cmd::Command;
+use iml_wire_types::tunable::TunableValue;
+use std::{
+    io,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::fs;
+
+/// Resend unchanged values every 5 min
+const DEFAULT_RESEND: Duration = Duration::from_secs(300);
+
+struct TunablesSub {
+    last: Instant,
+    output: Output,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tunables {
+    state: Arc<Mutex<TunablesSub>>,
+}
+
+pub fn create() -> impl DaemonPlugin {
+    Tunables {
+        state: Arc::new(Mutex::new(TunablesSub {
+            last: Instant::now(),
+            output: None,
+        })),
+    }
+}
+
+async fn read_params() -> Result<Vec<String>, ImlAgentError> {
+    match fs::read_to_string(env::get_tunables_conf()).await {
+        Ok(x) => Ok(x
+            .lines()
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(String::from)
+            .collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Parses `name=value` lines of `lctl get_param` output.
+/// Continuation lines of multi-line values are skipped.
+fn parse_params(output: &str) -> Vec<TunableValue> {
+    output
+        .lines()
+        .filter_map(|x| {
+            let mut xs = x.splitn(2, '=');
+
+            let name = xs.next()?;
+            let value = xs.next()?;
+
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                return None;
+            }
+
+            Some(TunableValue {
+                name: name.to_string(),
+                value: value.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+async fn get_values() -> Result<Output, ImlAgentError> {
+    let params = read_params().await?;
+
+    if params.is_empty() {
+        return Ok(serde_json::to_value(Vec::<TunableValue>::new()).map(Some)?);
+    }
+
+    // `lctl` exits non-zero if any param does not exist on this host, which is expected
+    let x = Command::new("lctl")
+        .arg("get_param")
+        .args(&params)
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    let xs = parse_params(&String::from_utf8_lossy(&x.stdout));
+
+    Ok(serde_json::to_value(xs).map(Some)?)
+}
+
+impl DaemonPlugin for Tunables {
+    fn start_session(
+        &mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<Output, ImlAgentError>> + Send>> {
+        let state = Arc::clone(&self.state);
+
+        async move {
+            let output = get_values().await?;
+
+            let mut state = state.lock().await;
+
+            state.last = Instant::now();
+            state.output = output;
+
+            Ok(state.output.clone())
+        }
+        .boxed()
+    }
+
+    fn update_session(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<Output, ImlAgentError>> + Send>> {
+        let state = Arc::clone(&self.state);
+
+        async move {
+            let output = get_values().await?;
+
+            let mut state = state.lock().await;
+
+            let now = Instant::now();
+
+            if state.output != output || now >= state.last + DEFAULT_RESEND {
+                state.last = now;
+                state.output = output;
+
+                Ok(state.output.clone())
+            } else {
+                Ok(None)
+            }
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_params() {
+        let output = r#"osc.fs-OST0000-osc-ffff8800.max_rpcs_in_flight=8
+osc.fs-OST0001-osc-ffff8800.max_rpcs_in_flight=32
+osd-ldiskfs.fs-OST0000.brw_stats=
+snapshot_time:         1609407247.512931 (secs.nsecs)
+read      | write
+"#;
+
+        assert_eq!(
+            parse_params(output),
+            vec![
+                TunableValue {
+                    name: "osc.fs-OST0000-osc-ffff8800.max_rpcs_in_flight".into(),
+                    value: "8".into(),
+                },
+                TunableValue {
+                    name: "osc.fs-OST0001-osc-ffff8800.max_rpcs_in_flight".into(),
+                    value: "32".into(),
+                },
+                TunableValue {
+                    name: "osd-ldiskfs.fs-OST0000.brw_stats".into(),
+                    value: "".into(),
+                },
+            ]
+        );
+    }
+}
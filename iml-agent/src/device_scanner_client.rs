@@ -3,7 +3,10 @@
 // license that can be found in the LICENSE file.
 
 use crate::agent_error::ImlAgentError;
-use device_types::mount::Mount;
+use device_types::{
+    devices::{Device, Root},
+    mount::Mount,
+};
 use futures::{Stream, StreamExt, TryFutureExt, TryStreamExt};
 use tokio::{io::AsyncWriteExt, net::UnixStream};
 use tokio_util::codec::{FramedRead, LinesCodec};
@@ -16,6 +19,7 @@ pub async fn connect() -> Result<UnixStream, ImlAgentError> {
 
 pub enum Cmd {
     Stream,
+    StreamPatches,
     GetMounts,
 }
 
@@ -23,6 +27,7 @@ impl From<Cmd> for &[u8] {
     fn from(cmd: Cmd) -> Self {
         match cmd {
             Cmd::Stream => b"\"Stream\"\n",
+            Cmd::StreamPatches => b"\"StreamPatches\"\n",
             Cmd::GetMounts => b"\"GetMounts\"\n",
         }
     }
@@ -54,6 +59,22 @@ pub async fn get_mounts() -> Result<Vec<Mount>, ImlAgentError> {
     Ok(x)
 }
 
+/// Returns the current device tree from device-scanner
+pub async fn get_devices() -> Result<Device, ImlAgentError> {
+    let (x, _) = stream_lines(Cmd::Stream).boxed().into_future().await;
+
+    let x = match x {
+        Some(x) => {
+            let (x, _): (Device, serde_json::Value) = serde_json::from_str(x?.as_str())?;
+
+            x
+        }
+        None => Device::Root(Root::default()),
+    };
+
+    Ok(x)
+}
+
 pub async fn get_snapshot_mounts() -> Result<Vec<Mount>, ImlAgentError> {
     let xs = get_mounts()
         .await?
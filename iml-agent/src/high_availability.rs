@@ -2,19 +2,35 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
-use crate::agent_error::ImlAgentError;
+use crate::agent_error::{CibError, ImlAgentError};
+use elementtree::Element;
 use futures::TryFutureExt;
 use iml_cmd::{CheckedCommandExt, Command};
 use iml_fs::file_exists;
-use iml_wire_types::high_availability::{Ban, Cluster, Node, Resource};
+use iml_wire_types::{
+    high_availability::{Ban, Cluster, CorosyncLink, Node, Quorum, Resource, ResourceGroup},
+    LossPolicy, OrderingKind, PacemakerActions, PacemakerKindOrScore, PacemakerScore,
+    ResourceConstraint,
+};
 use quick_xml::{
     events::{attributes::Attributes, Event},
     Reader,
 };
-use std::{collections::HashMap, convert::TryInto, ffi::OsStr, process::Output};
+use std::{
+    collections::HashMap,
+    convert::{TryFrom, TryInto},
+    ffi::OsStr,
+    process::Output,
+};
 
 static CIBADMIN_PATH: &str = "/usr/sbin/cibadmin";
 
+/// cibadmin exit codes for an xpath that matches nothing,
+/// `ENXIO` on older pacemaker releases and `CRM_EX_NOSUCH` on newer ones.
+pub(crate) const CIB_NO_SUCH_OBJECT: [i32; 2] = [6, 105];
+
+const CIB_UPDATE_ATTEMPTS: usize = 5;
+
 fn cibadmin_cmd() -> Command {
     let mut cmd = Command::new(CIBADMIN_PATH);
 
@@ -100,6 +116,132 @@ fn parse_local_nodeid_output(x: &[u8]) -> Result<Option<String>, ImlAgentError>
     Ok(x)
 }
 
+/// Reads the status of each corosync ring or knet link on the local node.
+///
+/// `corosync-cfgtool -s` exits non-zero when a ring is faulty,
+/// so the output is parsed regardless of exit status.
+pub async fn get_corosync_links() -> Result<Vec<CorosyncLink>, ImlAgentError> {
+    if !file_exists(COROSYNC_CFGTOOL_PATH).await {
+        return Ok(vec![]);
+    }
+
+    let output = corosync_cfgtool().arg("-s").output().await?;
+
+    parse_link_status_output(&output.stdout)
+}
+
+fn parse_link_status_output(x: &[u8]) -> Result<Vec<CorosyncLink>, ImlAgentError> {
+    let x = std::str::from_utf8(x)?;
+
+    let mut xs: Vec<CorosyncLink> = vec![];
+
+    for line in x.lines().map(str::trim) {
+        if let Some(id) = line
+            .strip_prefix("RING ID ")
+            .or_else(|| line.strip_prefix("LINK ID "))
+        {
+            xs.push(CorosyncLink {
+                id: id.trim().parse()?,
+                address: "".to_string(),
+                faulty: false,
+                status: "".to_string(),
+            });
+
+            continue;
+        }
+
+        let link = match xs.last_mut() {
+            Some(x) => x,
+            None => continue,
+        };
+
+        if let Some((k, v)) = split_once(line, '=') {
+            match k {
+                "id" | "addr" => link.address = v.to_string(),
+                "status" => {
+                    link.faulty = v.contains("FAULTY");
+                    link.status = v.to_string();
+                }
+                _ => {}
+            }
+        } else if line.starts_with("nodeid") {
+            // knet reports the state of the link to each node
+            if let Some((node, state)) = split_once(line, ':') {
+                let node = node.split_whitespace().collect::<Vec<_>>().join(" ");
+                let state = state.split_whitespace().collect::<Vec<_>>().join(" ");
+
+                if state.contains("disconnected") || state.contains("connected:0") {
+                    link.faulty = true;
+                }
+
+                if !link.status.is_empty() {
+                    link.status.push_str(", ");
+                }
+
+                link.status.push_str(&format!("{}: {}", node, state));
+            }
+        }
+    }
+
+    Ok(xs)
+}
+
+fn split_once(x: &str, sep: char) -> Option<(&str, &str)> {
+    let mut it = x.splitn(2, sep);
+
+    Some((it.next()?.trim(), it.next()?.trim()))
+}
+
+static COROSYNC_QUORUMTOOL_PATH: &str = "/usr/sbin/corosync-quorumtool";
+
+/// Reads the votequorum state of the local node.
+///
+/// `corosync-quorumtool -s` exits non-zero when the partition is not quorate,
+/// so the output is parsed regardless of exit status.
+pub async fn get_quorum() -> Result<Option<Quorum>, ImlAgentError> {
+    if !file_exists(COROSYNC_QUORUMTOOL_PATH).await {
+        return Ok(None);
+    }
+
+    let output = Command::new(COROSYNC_QUORUMTOOL_PATH)
+        .kill_on_drop(true)
+        .arg("-s")
+        .output()
+        .await?;
+
+    parse_quorumtool_output(&output.stdout)
+}
+
+fn parse_quorumtool_output(x: &[u8]) -> Result<Option<Quorum>, ImlAgentError> {
+    let x = std::str::from_utf8(x)?;
+
+    let fields: HashMap<&str, &str> = x.lines().filter_map(|x| split_once(x, ':')).collect();
+
+    let quorate = match fields.get("Quorate") {
+        Some(x) => *x == "Yes",
+        None => return Ok(None),
+    };
+
+    // Values may be followed by extra information, i.e. `Quorum: 2 Activity blocked`
+    let num = |k: &str| -> Result<u32, ImlAgentError> {
+        let x = fields
+            .get(k)
+            .and_then(|x| x.split_whitespace().next())
+            .unwrap_or("0")
+            .parse()?;
+
+        Ok(x)
+    };
+
+    Ok(Some(Quorum {
+        quorate,
+        nodes: num("Nodes")?,
+        expected_votes: num("Expected votes")?,
+        total_votes: num("Total votes")?,
+        quorum: num("Quorum")?,
+    }))
+}
+
 async fn get_lustre_resource_mounts(
     xs: Vec<String>,
 ) -> Result<HashMap<String, String>, ImlAgentError> {
@@ -151,6 +293,15 @@ pub async fn cibcreate(scope: &str, xml: &str) -> Result<(), ImlAgentError> {
         .await
 }
 
+pub async fn cibquery(scope: &str) -> Result<Vec<u8>, ImlAgentError> {
+    let o = cibadmin_cmd()
+        .args(&["--query", "--scope", scope])
+        .checked_output()
+        .await?;
+
+    Ok(o.stdout)
+}
+
 pub async fn cibxpath<I, S>(op: &str, xpath: &str, extra: I) -> Result<String, ImlAgentError>
 where
     I: IntoIterator<Item = S>,
@@ -167,6 +318,81 @@ where
     Ok(String::from_utf8_lossy(&o.stdout).to_string())
 }
 
+/// Returns whether any CIB element matches `xpath`.
+pub async fn cibxpath_exists(xpath: &str) -> Result<bool, ImlAgentError> {
+    let o = cibadmin_cmd()
+        .args(&["--query", "--xpath", xpath])
+        .output()
+        .await?;
+
+    match o.status.code() {
+        _ if o.status.success() => Ok(true),
+        Some(x) if CIB_NO_SUCH_OBJECT.contains(&x) => Ok(false),
+        _ => Err(o.into()),
+    }
+}
+
+/// XPath of the resources section, matching only while the CIB configuration
+/// is still at the version of `cib`.
+fn versioned_resources_xpath(cib: &Element) -> String {
+    format!(
+        "/cib[@admin_epoch=\"{}\" and @epoch=\"{}\"]/configuration/resources",
+        cib.get_attr("admin_epoch").unwrap_or("0"),
+        cib.get_attr("epoch").unwrap_or("0")
+    )
+}
+
+/// Replaces the resources section of the CIB with the result of `f`,
+/// or does nothing if `f` returns `None`.
+///
+/// Only the resources section is replaced, and only if the configuration
+/// has not changed since it was queried. Otherwise the update is retried
+/// against the current resources.
+pub async fn cib_update_resources<F>(f: F) -> Result<(), ImlAgentError>
+where
+    F: Fn(&Element) -> Result<Option<Element>, ImlAgentError>,
+{
+    for _ in 0..CIB_UPDATE_ATTEMPTS {
+        // Read the version first, so a change made before the resources are read fails the replace
+        let o = cibadmin_cmd()
+            .args(&["--query", "--no-children"])
+            .checked_output()
+            .await?;
+
+        let xpath = versioned_resources_xpath(&Element::from_reader(o.stdout.as_slice())?);
+
+        let resources = Element::from_reader(cibquery("resources").await?.as_slice())?;
+
+        let xml = match f(&resources)? {
+            Some(x) => x.to_string()?,
+            None => return Ok(()),
+        };
+
+        let o = cibadmin_cmd()
+            .args(&["--replace", "--xpath", &xpath, "--xml-text", &xml])
+            .output()
+            .await?;
+
+        match o.status.code() {
+            _ if o.status.success() => return Ok(()),
+            Some(x) if CIB_NO_SUCH_OBJECT.contains(&x) => {
+                tracing::debug!("CIB changed while updating resources, retrying");
+            }
+            _ => {
+                tracing::error!("Failed to replace resources: {}", xml);
+
+                return Err(o.into());
+            }
+        }
+    }
+
+    Err(CibError(format!(
+        "CIB kept changing, gave up updating resources after {} attempts",
+        CIB_UPDATE_ATTEMPTS
+    ))
+    .into())
+}
+
 pub async fn crm_resource<I, S>(args: I) -> Result<String, ImlAgentError>
 where
     I: IntoIterator<Item = S>,
@@ -193,7 +419,18 @@ pub async fn get_crm_mon_output() -> Result<Output, iml_cmd::CmdError> {
     crm_mon_cmd().checked_output().await
 }
 
-pub async fn get_crm_mon() -> Result<Option<Cluster>, ImlAgentError> {
+/// Constraints last read from the CIB, along with the configuration change they were read after.
+#[derive(Debug, Default)]
+pub struct ConstraintCache {
+    last_change: Option<String>,
+    constraints: Vec<ResourceConstraint>,
+}
+
+/// Reads the cluster state from `crm_mon`.
+///
+/// Constraints are only queried from the CIB when `crm_mon` reports
+/// a configuration change since they were last read into `cache`.
+pub async fn get_crm_mon(cache: &mut ConstraintCache) -> Result<Option<Cluster>, ImlAgentError> {
     if !file_exists(CRM_MON_PATH).await {
         return Ok(None);
     }
@@ -225,9 +462,162 @@ pub async fn get_crm_mon() -> Result<Option<Cluster>, ImlAgentError> {
 
     read_banned_output(&ban_output.stdout, &mut x)?;
 
+    let last_change = read_last_change(&crm_output.stdout)?;
+
+    if last_change.is_none() || last_change != cache.last_change {
+        cache.constraints = get_constraints().await?;
+        cache.last_change = last_change;
+    }
+
+    x.constraints = cache.constraints.clone();
+
     Ok(Some(x))
 }
 
+pub async fn get_constraints() -> Result<Vec<ResourceConstraint>, ImlAgentError> {
+    let xml = cibquery("constraints").await?;
+
+    parse_constraints(&xml)
+}
+
+pub async fn get_groups() -> Result<Vec<ResourceGroup>, ImlAgentError> {
+    let xml = cibquery("resources").await?;
+
+    parse_groups(&xml)
+}
+
+fn required_attr<'a>(elem: &'a Element, name: &str) -> Result<&'a str, ImlAgentError> {
+    elem.get_attr(name).ok_or_else(|| {
+        ImlAgentError::CibError(CibError(format!(
+            "{} {} is missing {} attribute",
+            elem.tag(),
+            elem.get_attr("id").unwrap_or_default(),
+            name
+        )))
+    })
+}
+
+/// Converts a CIB constraint element into a `ResourceConstraint`.
+///
+/// Constraints using rules or resource sets cannot be represented
+/// and are skipped.
+fn constraint_from_element(elem: &Element) -> Result<Option<ResourceConstraint>, ImlAgentError> {
+    let id = required_attr(elem, "id")?.to_string();
+
+    let x = match (elem.tag().name(), elem.get_attr("rsc")) {
+        ("rsc_location", Some(rsc)) => {
+            let (node, score) = match (elem.get_attr("node"), elem.get_attr("score")) {
+                (Some(node), Some(score)) => (node, score),
+                _ => {
+                    tracing::debug!("Skipping rule based location constraint {}", id);
+
+                    return Ok(None);
+                }
+            };
+
+            ResourceConstraint::Location {
+                id,
+                rsc: rsc.to_string(),
+                node: node.to_string(),
+                score: PacemakerScore::try_from(score)?,
+            }
+        }
+        ("rsc_colocation", Some(rsc)) => ResourceConstraint::Colocation {
+            rsc: rsc.to_string(),
+            with_rsc: required_attr(elem, "with-rsc")?.to_string(),
+            score: PacemakerScore::try_from(required_attr(elem, "score")?)?,
+            id,
+        },
+        ("rsc_ticket", Some(rsc)) => ResourceConstraint::Ticket {
+            rsc: rsc.to_string(),
+            ticket: required_attr(elem, "ticket")?.to_string(),
+            loss_policy: elem
+                .get_attr("loss-policy")
+                .map(LossPolicy::try_from)
+                .transpose()?,
+            id,
+        },
+        ("rsc_order", _) => {
+            let (first, then) = match (elem.get_attr("first"), elem.get_attr("then")) {
+                (Some(first), Some(then)) => (first, then),
+                _ => {
+                    tracing::debug!("Skipping resource set order constraint {}", id);
+
+                    return Ok(None);
+                }
+            };
+
+            let kind = match (elem.get_attr("kind"), elem.get_attr("score")) {
+                (Some(kind), _) => Some(PacemakerKindOrScore::Kind(OrderingKind::try_from(kind)?)),
+                (None, Some(score)) => Some(PacemakerKindOrScore::Score(PacemakerScore::try_from(
+                    score,
+                )?)),
+                (None, None) => None,
+            };
+
+            ResourceConstraint::Order {
+                first: first.to_string(),
+                first_action: elem
+                    .get_attr("first-action")
+                    .map(PacemakerActions::try_from)
+                    .transpose()?,
+                then: then.to_string(),
+                then_action: elem
+                    .get_attr("then-action")
+                    .map(PacemakerActions::try_from)
+                    .transpose()?,
+                kind,
+                id,
+            }
+        }
+        (name, _) => {
+            tracing::debug!("Skipping unsupported constraint {} {}", name, id);
+
+            return Ok(None);
+        }
+    };
+
+    Ok(Some(x))
+}
+
+pub(crate) fn parse_constraints(xml: &[u8]) -> Result<Vec<ResourceConstraint>, ImlAgentError> {
+    let elem = Element::from_reader(xml)?;
+
+    let xs = elem
+        .children()
+        .filter_map(|x| match constraint_from_element(x) {
+            Ok(x) => x,
+            Err(e) => {
+                tracing::warn!(
+                    "Skipping constraint {} that could not be parsed: {}",
+                    x.get_attr("id").unwrap_or_default(),
+                    e
+                );
+
+                None
+            }
+        })
+        .collect();
+
+    Ok(xs)
+}
+
+pub(crate) fn parse_groups(xml: &[u8]) -> Result<Vec<ResourceGroup>, ImlAgentError> {
+    let elem = Element::from_reader(xml)?;
+
+    elem.find_all("group")
+        .map(|g| {
+            Ok(ResourceGroup {
+                id: required_attr(g, "id")?.to_string(),
+                resources: g
+                    .find_all("primitive")
+                    .map(|p| required_attr(p, "id").map(str::to_string))
+                    .collect::<Result<Vec<_>, _>>()?,
+            })
+        })
+        .collect()
+}
+
 fn required_arg<'a>(arg: &str, x: &'a HashMap<&str, String>) -> Result<&'a str, ImlAgentError> {
     x.get(arg)
         .map(|x| x.as_str())
@@ -528,6 +918,39 @@ pub(crate) fn read_crm_output(crm_output: &[u8]) -> Result<Cluster, ImlAgentErro
     Ok(cluster)
 }
 
+/// The last configuration change `crm_mon` reports, as its `last_change` attributes.
+fn read_last_change(crm_output: &[u8]) -> Result<Option<String>, ImlAgentError> {
+    let x = std::str::from_utf8(crm_output)?;
+
+    let mut reader = Reader::from_str(x);
+    reader.trim_text(true);
+
+    let mut buf = vec![];
+
+    loop {
+        match reader.read_event(&mut buf)? {
+            Event::Empty(ref x) | Event::Start(ref x) if x.name() == b"last_change" => {
+                let mut xs: Vec<_> = attrs_to_hashmap(x.attributes(), &reader)?
+                    .into_iter()
+                    .collect();
+                xs.sort();
+
+                let x = xs
+                    .into_iter()
+                    .map(|(k, v)| format!("{}={}", k, v))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+
+                return Ok(Some(x));
+            }
+            Event::Eof => return Ok(None),
+            _ => {}
+        };
+
+        buf.clear();
+    }
+}
+
 fn read_banned_output(crm_output: &[u8], cluster: &mut Cluster) -> Result<(), ImlAgentError> {
     let x = std::str::from_utf8(crm_output)?;
 
@@ -564,6 +987,18 @@ mod tests {
         include_bytes!("./fixtures/vagrant_stopped_fixture.xml");
     static COROSYNC_CFGTOOL_FIXTURE: &'static [u8] =
         include_bytes!("./fixtures/corosync_cfgtool_fixture.txt");
+    static COROSYNC_CFGTOOL_FAULTY_FIXTURE: &'static [u8] =
+        include_bytes!("./fixtures/corosync_cfgtool_faulty_fixture.txt");
+    static COROSYNC_CFGTOOL_KNET_FIXTURE: &'static [u8] =
+        include_bytes!("./fixtures/corosync_cfgtool_knet_fixture.txt");
+    static COROSYNC_QUORUMTOOL_FIXTURE: &'static [u8] =
+        include_bytes!("./fixtures/corosync_quorumtool_fixture.txt");
+    static COROSYNC_QUORUMTOOL_LOST_FIXTURE: &'static [u8] =
+        include_bytes!("./fixtures/corosync_quorumtool_lost_fixture.txt");
+    static CIBADMIN_CONSTRAINTS_FIXTURE: &'static [u8] =
+        include_bytes!("./fixtures/cibadmin_constraints_fixture.xml");
+    static CIBADMIN_RESOURCES_FIXTURE: &'static [u8] =
+        include_bytes!("./fixtures/cibadmin_resources_fixture.xml");
 
     #[test]
     fn test_read_es() {
@@ -588,6 +1023,18 @@ mod tests {
         insta::assert_debug_snapshot!(cluster);
     }
 
+    #[test]
+    fn test_read_last_change() {
+        assert_eq!(
+            read_last_change(VAGRANT_FIXTURE).unwrap().as_deref(),
+            Some("client=crm_resource origin=oss1.local time=Fri Aug 28 16:17:18 2020 user=root")
+        );
+        assert_ne!(
+            read_last_change(VAGRANT_FIXTURE).unwrap(),
+            read_last_change(VAGRANT_STOPPED_FIXTURE).unwrap()
+        );
+    }
+
     #[test]
     fn test_lustre_resource_mounts_output() {
         let x = parse_resource_xml(ES_LUSTRE_RESOURCE_MOUNTS_FIXTURE).unwrap();
@@ -601,4 +1048,158 @@ mod tests {
 
         insta::assert_debug_snapshot!(x);
     }
+
+    #[test]
+    fn test_link_status_output() {
+        assert_eq!(
+            parse_link_status_output(COROSYNC_CFGTOOL_FIXTURE).unwrap(),
+            vec![CorosyncLink {
+                id: 0,
+                address: "10.73.10.11".to_string(),
+                faulty: false,
+                status: "ring 0 active with no faults".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_link_status_output_faulty() {
+        let xs = parse_link_status_output(COROSYNC_CFGTOOL_FAULTY_FIXTURE).unwrap();
+
+        assert_eq!(
+            xs.iter().map(|x| (x.id, x.faulty)).collect::<Vec<_>>(),
+            vec![(0, false), (1, true)]
+        );
+    }
+
+    #[test]
+    fn test_link_status_output_knet() {
+        assert_eq!(
+            parse_link_status_output(COROSYNC_CFGTOOL_KNET_FIXTURE).unwrap(),
+            vec![
+                CorosyncLink {
+                    id: 0,
+                    address: "10.73.10.11".to_string(),
+                    faulty: false,
+                    status: "nodeid 1: localhost, nodeid 2: connected".to_string(),
+                },
+                CorosyncLink {
+                    id: 1,
+                    address: "10.73.20.11".to_string(),
+                    faulty: true,
+                    status: "nodeid 1: localhost, nodeid 2: disconnected".to_string(),
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quorumtool_output() {
+        assert_eq!(
+            parse_quorumtool_output(COROSYNC_QUORUMTOOL_FIXTURE).unwrap(),
+            Some(Quorum {
+                quorate: true,
+                nodes: 2,
+                expected_votes: 2,
+                total_votes: 2,
+                quorum: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_quorumtool_output_lost() {
+        assert_eq!(
+            parse_quorumtool_output(COROSYNC_QUORUMTOOL_LOST_FIXTURE).unwrap(),
+            Some(Quorum {
+                quorate: false,
+                nodes: 1,
+                expected_votes: 3,
+                total_votes: 1,
+                quorum: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_constraints() {
+        let xs = parse_constraints(CIBADMIN_CONSTRAINTS_FIXTURE).unwrap();
+
+        assert_eq!(
+            xs,
+            vec![
+                ResourceConstraint::Location {
+                    id: "MGS-oss1".into(),
+                    rsc: "MGS".into(),
+                    node: "mds1.local".into(),
+                    score: PacemakerScore::Value(20),
+                },
+                ResourceConstraint::Location {
+                    id: "MGS-oss2".into(),
+                    rsc: "MGS".into(),
+                    node: "mds2.local".into(),
+                    score: PacemakerScore::Value(10),
+                },
+                ResourceConstraint::Location {
+                    id: "cli-ban-ost0001-on-oss1.local".into(),
+                    rsc: "ost0001".into(),
+                    node: "oss1.local".into(),
+                    score: PacemakerScore::NegInfinity,
+                },
+                ResourceConstraint::Colocation {
+                    id: "ost0001-with-ost0002".into(),
+                    rsc: "ost0001".into(),
+                    with_rsc: "ost0002".into(),
+                    score: PacemakerScore::NegInfinity,
+                },
+                ResourceConstraint::Order {
+                    id: "fs-client-after-mgs".into(),
+                    first: "MGS".into(),
+                    first_action: Some(PacemakerActions::Start),
+                    then: "fs-client".into(),
+                    then_action: Some(PacemakerActions::Start),
+                    kind: Some(PacemakerKindOrScore::Score(PacemakerScore::Value(0))),
+                },
+                ResourceConstraint::Order {
+                    id: "mdt-after-mgs".into(),
+                    first: "MGS".into(),
+                    first_action: None,
+                    then: "MDT0000".into(),
+                    then_action: None,
+                    kind: Some(PacemakerKindOrScore::Kind(OrderingKind::Optional)),
+                },
+                ResourceConstraint::Ticket {
+                    id: "ticket-fs-allocated-client".into(),
+                    rsc: "fs-client".into(),
+                    ticket: "fs-allocated".into(),
+                    loss_policy: Some(LossPolicy::Stop),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_versioned_resources_xpath() {
+        let cib =
+            Element::from_reader(&br#"<cib admin_epoch="0" epoch="42" num_updates="7"/>"#[..])
+                .unwrap();
+
+        assert_eq!(
+            versioned_resources_xpath(&cib),
+            r#"/cib[@admin_epoch="0" and @epoch="42"]/configuration/resources"#
+        );
+    }
+
+    #[test]
+    fn test_parse_groups() {
+        let xs = parse_groups(CIBADMIN_RESOURCES_FIXTURE).unwrap();
+
+        assert_eq!(
+            xs,
+            vec![ResourceGroup {
+                id: "group-fs-ost".into(),
+                resources: vec!["ost0001-zpool".into(), "ost0001".into()],
+            }]
+        );
+    }
 }
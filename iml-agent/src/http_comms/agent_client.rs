@@ -7,7 +7,7 @@ use futures::{Future, TryFutureExt};
 use iml_wire_types::{Id, PluginName};
 use reqwest::Client;
 use std::convert::Into;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 /// A wrapper around `CryptoClient`.
 ///
@@ -107,11 +107,59 @@ impl AgentClient {
                 server_properties::BOOT_TIME.to_string(),
             ),
             ("client_start_time".into(), self.start_time.clone()),
+            ("backpressure".into(), "true".into()),
         ];
 
         debug!("Sending get {:?}", get_params);
 
         crypto_client::get_buffered(&self.client, self.message_endpoint.clone(), &get_params)
-            .and_then(|x| async move { serde_json::from_str(&x).map_err(Into::into) })
+            .and_then(|x| async move { parse_manager_messages(&x) })
+    }
+}
+
+/// Reads each of the delivered messages on its own,
+/// skipping any this agent does not know, i.e. ones added by a newer manager.
+fn parse_manager_messages(x: &str) -> Result<iml_wire_types::ManagerMessages, ImlAgentError> {
+    #[derive(serde::Deserialize)]
+    struct RawMessages {
+        messages: Vec<serde_json::Value>,
+    }
+
+    let RawMessages { messages } = serde_json::from_str(x)?;
+
+    let messages = messages
+        .into_iter()
+        .filter_map(|x| match serde_json::from_value(x.clone()) {
+            Ok(m) => Some(m),
+            Err(e) => {
+                warn!("Skipping unknown manager message {}: {}", x, e);
+
+                None
+            }
+        })
+        .collect();
+
+    Ok(iml_wire_types::ManagerMessages { messages })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iml_wire_types::ManagerMessage;
+
+    #[test]
+    fn test_parse_manager_messages() {
+        let x = r#"{"messages": [
+            {"type": "SESSION_TERMINATE_ALL", "fqdn": "oss1.local"},
+            {"type": "SOMETHING_NEW", "fqdn": "oss1.local"}
+        ]}"#;
+
+        let xs = parse_manager_messages(x).unwrap();
+
+        assert_eq!(xs.messages.len(), 1);
+        assert!(matches!(
+            xs.messages[0],
+            ManagerMessage::SessionTerminateAll { .. }
+        ));
     }
 }
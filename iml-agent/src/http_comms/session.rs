@@ -38,6 +38,8 @@ pub struct Active {
     pub session: Session,
     pub in_flight: Option<oneshot::Receiver<()>>,
     pub instant: Instant,
+    /// Extra time to wait between polls, as requested by the manager.
+    pub poll_delay: Duration,
 }
 
 #[derive(Debug)]
@@ -65,10 +67,17 @@ impl State {
     }
     pub fn reset_active(&mut self) {
         if let State::Active(a) = self {
-            a.instant = Instant::now() + WAIT_TIME;
+            a.instant = Instant::now() + WAIT_TIME + a.poll_delay;
             a.in_flight = None;
         }
     }
+    pub fn set_poll_delay(&mut self, id: &Id, poll_delay: Duration) {
+        if let State::Active(a) = self {
+            if &a.session.id == id {
+                a.poll_delay = poll_delay;
+            }
+        }
+    }
     pub fn create_active(&mut self, session: Session, in_flight: oneshot::Receiver<()>) {
         let _ = std::mem::replace(
             self,
@@ -76,6 +85,7 @@ impl State {
                 session,
                 in_flight: Some(in_flight),
                 instant: Instant::now() + WAIT_TIME,
+                poll_delay: Duration::from_secs(0),
             }),
         );
     }
@@ -120,6 +130,13 @@ impl Sessions {
             x.write().await.reset_empty()
         }
     }
+    pub async fn set_poll_delay(&self, name: &PluginName, id: &Id, poll_delay: Duration) {
+        if let Some(x) = self.0.get(name) {
+            x.write().await.set_poll_delay(id, poll_delay);
+
+            tracing::debug!("Set poll delay for {:?} to {:?}", name, poll_delay);
+        }
+    }
     pub async fn convert_to_pending(&self, name: &PluginName) {
         if let Some(x) = self.0.get(name) {
             x.write().await.convert_to_pending()
@@ -350,7 +367,10 @@ mod tests {
     };
     use futures::channel::oneshot;
     use serde_json::json;
-    use std::{ops::Deref, time::Instant};
+    use std::{
+        ops::Deref,
+        time::{Duration, Instant},
+    };
 
     fn create_session() -> Session {
         Session::new(
@@ -478,6 +498,37 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_sessions_set_poll_delay() -> Result<()> {
+        let sessions = Sessions::new(&["test_plugin".into()]);
+
+        let session = create_session();
+
+        let (_tx, rx) = oneshot::channel();
+
+        sessions
+            .insert_session("test_plugin".into(), session, rx)
+            .await?;
+
+        sessions
+            .set_poll_delay(
+                &"test_plugin".into(),
+                &"1234".into(),
+                Duration::from_secs(30),
+            )
+            .await;
+
+        let state = sessions.0.get(&"test_plugin".into()).cloned().unwrap();
+        let state = state.read().await;
+
+        match state.deref() {
+            State::Active(a) => assert_eq!(a.poll_delay, Duration::from_secs(30)),
+            _ => panic!("State was not Active"),
+        };
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_sessions_session_message() -> Result<()> {
         let sessions = Sessions::new(&["test_plugin".into()]);
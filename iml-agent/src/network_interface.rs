@@ -45,8 +45,9 @@ impl SetNetworkProperty for NetworkInterface {
 
     fn set_prop(mut self, prop: InterfaceProperties) -> Result<NetworkInterface, ImlAgentError> {
         match prop {
-            InterfaceProperties::InterfaceFlagsAndAttributes((interface, flags, _attributes)) => {
+            InterfaceProperties::InterfaceFlagsAndAttributes((interface, flags, attributes)) => {
                 self.interface = interface;
+                self.mtu = attributes.get("mtu").and_then(|x| x.parse().ok());
 
                 if flags.iter().any(|x| x == &"UP".to_string()) {
                     self.is_up = true;
@@ -9,6 +9,7 @@ use crate::{
 use iml_cmd::{CheckedCommandExt, CmdError, Command};
 use iml_wire_types::{LNet, NetworkInterface};
 use std::io;
+use tokio::fs;
 
 fn ip_addr_cmd() -> Command {
     let mut cmd = Command::new("ip");
@@ -37,6 +38,27 @@ fn get_lnet_data_cmd() -> Command {
     cmd
 }
 
+/// Reads the link speed of `interface` in Mb/s.
+///
+/// The kernel reports -1, or fails the read, when the link is down or the driver does not know its speed.
+async fn get_speed(interface: &str) -> Option<u64> {
+    let name = interface.split('@').next().unwrap_or(interface);
+
+    let x = fs::read_to_string(format!("/sys/class/net/{}/speed", name))
+        .await
+        .ok()?;
+
+    parse_speed(&x)
+}
+
+fn parse_speed(x: &str) -> Option<u64> {
+    x.trim()
+        .parse::<i64>()
+        .ok()
+        .filter(|x| *x > 0)
+        .map(|x| x as u64)
+}
+
 pub async fn get_interfaces() -> Result<Vec<NetworkInterface>, ImlAgentError> {
     let net_stats = get_net_stats_cmd().checked_output().await?;
 
@@ -48,7 +70,13 @@ pub async fn get_interfaces() -> Result<Vec<NetworkInterface>, ImlAgentError> {
 
     let network_interfaces = std::str::from_utf8(&network_interfaces.stdout)?;
 
-    parse_interfaces(network_interfaces, net_stats)
+    let mut xs = parse_interfaces(network_interfaces, net_stats)?;
+
+    for x in xs.iter_mut() {
+        x.speed = get_speed(&x.interface).await;
+    }
+
+    Ok(xs)
 }
 
 pub async fn get_lnet_data() -> Result<LNet, ImlAgentError> {
@@ -113,4 +141,11 @@ mod tests {
             insta::assert_json_snapshot!(yaml)
         });
     }
+
+    #[test]
+    fn test_parse_speed() {
+        assert_eq!(parse_speed("10000\n"), Some(10_000));
+        assert_eq!(parse_speed("-1\n"), None);
+        assert_eq!(parse_speed(""), None);
+    }
 }
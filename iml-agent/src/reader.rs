@@ -88,6 +88,16 @@ async fn get_delivery(
                 plugin, session_id, ..
             } => sessions.terminate_session(&plugin, &session_id).await?,
             ManagerMessage::SessionTerminateAll { .. } => sessions.terminate_all_sessions().await?,
+            ManagerMessage::Backpressure {
+                plugin,
+                session_id,
+                poll_delay,
+                ..
+            } => {
+                sessions
+                    .set_poll_delay(&plugin, &session_id, Duration::from_secs(poll_delay))
+                    .await
+            }
         }
     }
 
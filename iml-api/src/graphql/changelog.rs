@@ -0,0 +1,148 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::graphql::Context;
+use iml_postgres::{sqlx, PgPool};
+use iml_wire_types::changelog::{ChangelogDeregister, ChangelogRegister, ChangelogUser};
+use juniper::{FieldError, Value};
+
+pub(crate) struct ChangelogQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl ChangelogQuery {
+    #[graphql(arguments(fs_name(description = "Only list changelog users of this filesystem")))]
+    /// List the changelog users of each MDT, as last reported by the servers
+    async fn users(
+        context: &Context,
+        fs_name: Option<String>,
+    ) -> juniper::FieldResult<Vec<ChangelogUser>> {
+        let xs = sqlx::query!(
+            r#"
+                SELECT
+                    fs_name,
+                    target,
+                    user_id,
+                    idx,
+                    current_index,
+                    index_updated_at,
+                    updated_at
+                FROM changelog_user
+                WHERE ($1::text IS NULL OR fs_name = $1)
+                ORDER BY fs_name, target, user_id
+            "#,
+            fs_name
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        Ok(xs
+            .into_iter()
+            .map(|x| ChangelogUser {
+                fs_name: x.fs_name,
+                target: x.target,
+                id: x.user_id,
+                index: x.idx as f64,
+                current_index: x.current_index as f64,
+                lag: (x.current_index - x.idx) as f64,
+                index_updated_at: x.index_updated_at,
+                updated_at: x.updated_at,
+            })
+            .collect())
+    }
+}
+
+pub(crate) struct ChangelogMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl ChangelogMutation {
+    #[graphql(arguments(target(
+        description = "The MDT to register the user on, i.e. `fs-MDT0000`"
+    )))]
+    /// Register a changelog user on an MDT.
+    /// Returns the id of the new user, i.e. `cl1`.
+    async fn register(context: &Context, target: String) -> juniper::FieldResult<String> {
+        let fqdn = mdt_host(&context.pg_pool, &target).await?;
+
+        let x = context
+            .action_client
+            .invoke_rust_agent_expect_result(
+                fqdn,
+                "changelog_register",
+                ChangelogRegister { target },
+                None,
+            )
+            .await?
+            .map_err(|e| FieldError::new(e, Value::null()))?;
+
+        let id: String = serde_json::from_value(x)?;
+
+        Ok(id)
+    }
+    #[graphql(arguments(
+        target(description = "The MDT the user is registered on, i.e. `fs-MDT0000`"),
+        id(description = "The id of the user, i.e. `cl1`"),
+    ))]
+    /// Deregister a changelog user from an MDT.
+    /// Records the user has not cleared are purged.
+    async fn deregister(
+        context: &Context,
+        target: String,
+        id: String,
+    ) -> juniper::FieldResult<bool> {
+        let fqdn = mdt_host(&context.pg_pool, &target).await?;
+
+        context
+            .action_client
+            .invoke_rust_agent_expect_result(
+                fqdn,
+                "changelog_deregister",
+                ChangelogDeregister {
+                    target: target.clone(),
+                    id: id.clone(),
+                },
+                None,
+            )
+            .await?
+            .map_err(|e| FieldError::new(e, Value::null()))?;
+
+        sqlx::query!(
+            "DELETE FROM changelog_user WHERE target = $1 AND user_id = $2",
+            target,
+            id
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        Ok(true)
+    }
+}
+
+/// Returns the host currently running an MDT
+async fn mdt_host(pool: &PgPool, target: &str) -> juniper::FieldResult<String> {
+    if !target.contains("-MDT") {
+        return Err(FieldError::new(
+            format!("{} is not an MDT", target),
+            Value::null(),
+        ));
+    }
+
+    let x = sqlx::query!(
+        r#"
+            SELECT h.fqdn
+            FROM target t
+            INNER JOIN chroma_core_managedhost h ON h.id = t.active_host_id
+            WHERE t.name = $1 AND h.not_deleted = 't'
+        "#,
+        target
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    x.map(|x| x.fqdn).ok_or_else(|| {
+        FieldError::new(
+            format!("MDT {} is not mounted on any server", target),
+            Value::null(),
+        )
+    })
+}
@@ -0,0 +1,141 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::graphql::Context;
+use iml_postgres::sqlx;
+use iml_wire_types::client::ConnectedClient;
+use juniper::{FieldError, Value};
+
+pub(crate) struct ClientQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl ClientQuery {
+    #[graphql(arguments(
+        fs_name(description = "Only list clients of this filesystem"),
+        nid(description = "Only list the client with this NID"),
+    ))]
+    /// List the clients connected to targets, as last reported by the servers
+    async fn connected(
+        context: &Context,
+        fs_name: Option<String>,
+        nid: Option<String>,
+    ) -> juniper::FieldResult<Vec<ConnectedClient>> {
+        let xs = sqlx::query_as!(
+            ConnectedClient,
+            r#"
+                SELECT
+                    nid,
+                    fs_name,
+                    array_agg(target ORDER BY target) AS "targets!",
+                    max(last_seen) AS "last_seen!"
+                FROM client_export
+                WHERE ($1::text IS NULL OR fs_name = $1)
+                AND ($2::text IS NULL OR nid = $2)
+                GROUP BY nid, fs_name
+                ORDER BY fs_name, nid
+            "#,
+            fs_name,
+            nid
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        Ok(xs)
+    }
+}
+
+pub(crate) struct ClientMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl ClientMutation {
+    #[graphql(arguments(
+        nid(description = "The NID of the client, i.e. `10.0.0.1@tcp`"),
+        fs_name(description = "Only evict the client from targets of this filesystem"),
+    ))]
+    /// Evict a client from each target it is connected to.
+    /// Returns the targets the client was evicted from.
+    async fn evict(
+        context: &Context,
+        nid: String,
+        fs_name: Option<String>,
+    ) -> juniper::FieldResult<Vec<String>> {
+        if !nid.contains('@') {
+            return Err(FieldError::new(
+                format!("Invalid NID {}", nid),
+                Value::null(),
+            ));
+        }
+
+        let xs = sqlx::query!(
+            r#"
+                SELECT e.target, h.fqdn
+                FROM client_export e
+                INNER JOIN target t ON t.name = e.target AND e.fs_name = ANY(t.filesystems)
+                INNER JOIN chroma_core_managedhost h ON h.id = t.active_host_id
+                WHERE e.nid = $1
+                AND ($2::text IS NULL OR e.fs_name = $2)
+                AND h.not_deleted = 't'
+                ORDER BY e.target
+            "#,
+            nid,
+            fs_name
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        if xs.is_empty() {
+            return Err(FieldError::new(
+                format!("Client {} is not connected to any target", nid),
+                Value::null(),
+            ));
+        }
+
+        let mut evicted = vec![];
+
+        for x in xs {
+            context
+                .action_client
+                .invoke_rust_agent_expect_result(
+                    x.fqdn,
+                    "lctl",
+                    vec!["set_param".to_string(), evict_param(&x.target, &nid)],
+                    None,
+                )
+                .await?
+                .map_err(|e| FieldError::new(e, Value::null()))?;
+
+            evicted.push(x.target);
+        }
+
+        Ok(evicted)
+    }
+}
+
+/// Returns the `lctl set_param` argument to evict `nid` from `target`
+fn evict_param(target: &str, nid: &str) -> String {
+    let obd = if target.contains("-MDT") {
+        "mdt"
+    } else {
+        "obdfilter"
+    };
+
+    format!("{}.{}.evict_client=nid:{}", obd, target, nid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evict_param() {
+        assert_eq!(
+            evict_param("fs-OST0001", "10.0.0.1@tcp"),
+            "obdfilter.fs-OST0001.evict_client=nid:10.0.0.1@tcp"
+        );
+        assert_eq!(
+            evict_param("fs-MDT0000", "10.0.0.1@o2ib"),
+            "mdt.fs-MDT0000.evict_client=nid:10.0.0.1@o2ib"
+        );
+    }
+}
@@ -3,15 +3,34 @@
 // license that can be found in the LICENSE file.
 
 use crate::{
+    command::get_command,
     error::ImlApiError,
-    graphql::{get_fs_target_resources, Context, TargetResource},
+    graphql::{dependent_job, get_fs_target_resources, run_jobs, Context, TargetResource},
+};
+use device_types::{
+    devices::{Device, Paths},
+    DevicePath,
 };
 use futures::TryStreamExt;
-use iml_postgres::sqlx::{self, Postgres, Transaction};
+use iml_postgres::{
+    sqlx::{self, Postgres, Transaction},
+    PgPool,
+};
+use iml_wire_types::{
+    filesystem::{FormatTarget, TargetDevice, TargetKind},
+    quota::{QuotaId, QuotaKind, QuotaUsage, SetQuota, SetQuotaGrace},
+    Command, FsType, OrderingKind, PacemakerKindOrScore, PacemakerOperations, PacemakerScore,
+    ResourceAgentInfo, ResourceAgentType, ResourceConstraint,
+};
 use juniper::{FieldError, Value};
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::collections::{HashMap, HashSet};
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    convert::TryFrom,
+    num::TryFromIntError,
+};
 
 #[derive(Default)]
 struct FsParts<'a> {
@@ -28,6 +47,75 @@ impl<'a> FsParts<'a> {
 
 type Filesystems<'a> = HashMap<String, FsParts<'a>>;
 
+pub(crate) struct FilesystemQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl FilesystemQuery {
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to list quotas of"),
+        kind(description = "Only list quotas of this kind"),
+        pool(
+            description = "Only list quotas of this OST pool. An empty string lists filesystem wide quotas"
+        ),
+    ))]
+    /// List the quota limits of `fs_name` along with the usage they apply to.
+    /// Quota id `0` holds the default limits of each kind.
+    async fn quotas(
+        context: &Context,
+        fs_name: String,
+        kind: Option<QuotaKind>,
+        pool: Option<String>,
+    ) -> juniper::FieldResult<Vec<QuotaUsage>> {
+        let xs = sqlx::query!(
+            r#"
+                SELECT
+                    fs_name AS "fs_name!",
+                    pool AS "pool!",
+                    kind AS "kind!: QuotaKind",
+                    quota_id AS "quota_id!",
+                    kbytes_used AS "kbytes_used!",
+                    kbytes_soft AS "kbytes_soft!",
+                    kbytes_hard AS "kbytes_hard!",
+                    block_grace_expires,
+                    inodes_used AS "inodes_used!",
+                    inodes_soft AS "inodes_soft!",
+                    inodes_hard AS "inodes_hard!",
+                    inode_grace_expires
+                FROM quota
+                WHERE fs_name = $1
+                AND ($2::text IS NULL OR kind::text = $2)
+                AND ($3::text IS NULL OR pool = $3)
+                ORDER BY kind, quota_id, pool
+            "#,
+            fs_name,
+            kind.map(|x| x.to_string()),
+            pool
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| {
+            Ok(QuotaUsage {
+                fs_name: x.fs_name,
+                pool: Some(x.pool).filter(|x| !x.is_empty()),
+                kind: x.kind,
+                quota_id: QuotaId(u32::try_from(x.quota_id)?),
+                kbytes_used: x.kbytes_used as f64,
+                kbytes_soft: x.kbytes_soft as f64,
+                kbytes_hard: x.kbytes_hard as f64,
+                block_grace_expires: x.block_grace_expires,
+                inodes_used: x.inodes_used as f64,
+                inodes_soft: x.inodes_soft as f64,
+                inodes_hard: x.inodes_hard as f64,
+                inode_grace_expires: x.inode_grace_expires,
+            })
+        })
+        .collect::<Result<_, TryFromIntError>>()?;
+
+        Ok(xs)
+    }
+}
+
 pub(crate) struct FilesystemMutation;
 
 #[juniper::graphql_object(Context = Context)]
@@ -57,6 +145,7 @@ impl FilesystemMutation {
                 resource_id: "".to_string(),
                 state: x.state,
                 cluster_hosts: vec![],
+                preferred_hosts: vec![],
             })
             .try_collect()
             .await?;
@@ -285,6 +374,761 @@ impl FilesystemMutation {
 
         Ok(true)
     }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to set the quota on"),
+        kind(description = "The kind of quota"),
+        id(
+            description = "The user, group or project to set limits for. If not set, the default limits of `kind` are set"
+        ),
+        pool(description = "Only apply the block limits to the OSTs of this pool"),
+        block_soft(description = "Block soft limit, i.e. `10G`. Values without a unit are kbytes"),
+        block_hard(description = "Block hard limit, i.e. `12G`. Values without a unit are kbytes"),
+        inode_soft(description = "Inode soft limit"),
+        inode_hard(description = "Inode hard limit"),
+    ))]
+    /// Sets quota limits with `lfs setquota` on a client that has `fs_name` mounted.
+    /// Limits that are not passed are left unchanged, `0` removes a limit.
+    async fn set_quota(
+        context: &Context,
+        fs_name: String,
+        kind: QuotaKind,
+        id: Option<String>,
+        pool: Option<String>,
+        block_soft: Option<String>,
+        block_hard: Option<String>,
+        inode_soft: Option<String>,
+        inode_hard: Option<String>,
+    ) -> juniper::FieldResult<bool> {
+        for x in [&block_soft, &block_hard, &inode_soft, &inode_hard].iter() {
+            validate_quota_value(x, &LIMIT_RE)?;
+        }
+
+        if let Some(pool) = &pool {
+            check_pool(&context.pg_pool, &fs_name, pool).await?;
+        }
+
+        let (fqdn, mountpoint) = get_client_mount(&context.pg_pool, &fs_name).await?;
+
+        context
+            .action_client
+            .invoke_rust_agent_expect_result(
+                fqdn,
+                "quota_set",
+                SetQuota {
+                    mountpoint,
+                    kind,
+                    id,
+                    pool,
+                    block_soft,
+                    block_hard,
+                    inode_soft,
+                    inode_hard,
+                },
+                None,
+            )
+            .await?
+            .map_err(|e| FieldError::new(e, Value::null()))?;
+
+        Ok(true)
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to set grace periods on"),
+        kind(description = "The kind of quota"),
+        pool(description = "Only apply the block grace period to this pool"),
+        block_grace(description = "Block grace period, i.e. `1w2d` or seconds"),
+        inode_grace(description = "Inode grace period, i.e. `1w2d` or seconds"),
+    ))]
+    /// Sets the grace periods of a kind of quota with `lfs setquota -t`
+    /// on a client that has `fs_name` mounted.
+    async fn set_quota_grace(
+        context: &Context,
+        fs_name: String,
+        kind: QuotaKind,
+        pool: Option<String>,
+        block_grace: Option<String>,
+        inode_grace: Option<String>,
+    ) -> juniper::FieldResult<bool> {
+        validate_quota_value(&block_grace, &GRACE_RE)?;
+        validate_quota_value(&inode_grace, &GRACE_RE)?;
+
+        if let Some(pool) = &pool {
+            check_pool(&context.pg_pool, &fs_name, pool).await?;
+        }
+
+        let (fqdn, mountpoint) = get_client_mount(&context.pg_pool, &fs_name).await?;
+
+        context
+            .action_client
+            .invoke_rust_agent_expect_result(
+                fqdn,
+                "quota_set_grace",
+                SetQuotaGrace {
+                    mountpoint,
+                    kind,
+                    pool,
+                    block_grace,
+                    inode_grace,
+                },
+                None,
+            )
+            .await?
+            .map_err(|e| FieldError::new(e, Value::null()))?;
+
+        Ok(true)
+    }
+    #[graphql(arguments(
+        fs_name(description = "The name of the new filesystem, up to 8 characters"),
+        mgs(description = "The MGT device. A device already formatted as an MGT is reused"),
+        mdts(description = "The MDT devices, in index order"),
+        osts(description = "The OST devices, in index order"),
+        backfs(description = "The backing filesystem of the targets. Defaults to ldiskfs"),
+        mkfs_options(description = "Passed to `mkfs.lustre` as `--mkfsoptions`"),
+    ))]
+    /// Creates a new filesystem by formatting the given devices as targets
+    /// and creating a HA resource for each of them.
+    /// Each device must be unused and shared by at least two hosts of the same cluster.
+    /// The filesystem is detected once all of its new targets have started.
+    async fn create(
+        context: &Context,
+        fs_name: String,
+        mgs: TargetDevice,
+        mdts: Vec<TargetDevice>,
+        osts: Vec<TargetDevice>,
+        backfs: Option<FsType>,
+        mkfs_options: Option<String>,
+    ) -> juniper::FieldResult<Command> {
+        let pool = &context.pg_pool;
+
+        validate_fs_name(&fs_name)?;
+
+        let mut transaction = pool.begin().await?;
+
+        let exists = find_managed_fs_id_by_name(&fs_name, &mut transaction)
+            .await?
+            .is_some();
+
+        transaction.rollback().await?;
+
+        if exists
+            || !get_fs_target_resources(pool, Some(fs_name.clone()))
+                .await?
+                .is_empty()
+        {
+            return Err(FieldError::new(
+                format!("Filesystem {} already exists", fs_name),
+                Value::null(),
+            ));
+        }
+
+        if mdts.is_empty() || osts.is_empty() {
+            return Err(FieldError::new(
+                "At least one MDT and one OST are required",
+                Value::null(),
+            ));
+        }
+
+        let backfs = backfs.unwrap_or(FsType::Ldiskfs);
+
+        let trees = get_device_trees(pool).await?;
+
+        let selected = std::iter::once((TargetKind::Mgt, 0, &mgs))
+            .chain(
+                mdts.iter()
+                    .enumerate()
+                    .map(|(i, x)| (TargetKind::Mdt, i, x)),
+            )
+            .chain(
+                osts.iter()
+                    .enumerate()
+                    .map(|(i, x)| (TargetKind::Ost, i, x)),
+            );
+
+        let mut seen = HashSet::new();
+        let mut targets = vec![];
+
+        for (kind, index, x) in selected {
+            let shared = find_shared_device(&trees, kind, x)?;
+
+            if !seen.insert(shared.id.clone()) {
+                return Err(FieldError::new(
+                    format!("Device {} on {} is selected more than once", x.path, x.host),
+                    Value::null(),
+                ));
+            }
+
+            targets.push((kind, index as u32, x.path.clone(), shared));
+        }
+
+        let mut fqdns: Vec<_> = targets
+            .iter()
+            .flat_map(|(_, _, _, x)| x.hosts.iter().cloned())
+            .collect();
+        fqdns.sort();
+        fqdns.dedup();
+
+        let ha_hosts = get_ha_hosts(pool, &fqdns).await?;
+
+        let mgs_nids: Vec<_> = targets[0]
+            .3
+            .hosts
+            .iter()
+            .map(|x| ha_hosts[x].nids.clone())
+            .collect();
+
+        let mut new_targets = vec![];
+
+        for (kind, index, selected, shared) in targets {
+            let hosts: Vec<_> = shared.hosts.iter().map(|x| &ha_hosts[x]).collect();
+
+            if hosts.iter().any(|x| x.cluster_id != hosts[0].cluster_id) {
+                return Err(FieldError::new(
+                    format!(
+                        "The hosts sharing {} on {} are not in the same cluster",
+                        selected, hosts[0].fqdn
+                    ),
+                    Value::null(),
+                ));
+            }
+
+            if shared.formatted {
+                continue;
+            }
+
+            let config = FormatTarget {
+                kind,
+                fs_name: fs_name.clone(),
+                index,
+                backfs: backfs.clone(),
+                device: shared.path,
+                mgs_nids: mgs_nids.clone(),
+                service_nids: hosts.iter().map(|x| x.nids.clone()).collect(),
+                mkfs_options: mkfs_options.clone(),
+            };
+
+            new_targets.push((config, hosts));
+        }
+
+        check_resources_unused(pool, &new_targets).await?;
+
+        let mut jobs = vec![];
+
+        for (config, hosts) in &new_targets {
+            jobs.push(dependent_job(
+                "FormatLustreTargetJob",
+                vec![
+                    ("fqdn", json!(hosts[0].fqdn)),
+                    ("label", json!(config.label())),
+                    ("config", serde_json::to_value(config)?),
+                ],
+                &[],
+            ));
+        }
+
+        // Targets are started in MGS, MDT, OST order once every target is formatted
+        for kind in [TargetKind::Mgt, TargetKind::Mdt, TargetKind::Ost].iter() {
+            let deps: Vec<_> = (0..jobs.len()).collect();
+
+            for (config, hosts) in new_targets.iter().filter(|(x, _)| x.kind == *kind) {
+                let node_names: Vec<_> = hosts.iter().map(|x| x.node_name.as_str()).collect();
+
+                jobs.push(dependent_job(
+                    "CreateTargetResourceJob",
+                    vec![
+                        ("fqdn", json!(hosts[0].fqdn)),
+                        ("ha_label", json!(config.ha_label())),
+                        ("mountpoint", json!(config.mountpoint())),
+                        (
+                            "hosts",
+                            json!(hosts.iter().map(|x| &x.fqdn).collect::<Vec<_>>()),
+                        ),
+                        (
+                            "resources",
+                            serde_json::to_value(target_resources(config, &node_names))?,
+                        ),
+                    ],
+                    &deps,
+                ));
+            }
+        }
+
+        let deps: Vec<_> = (0..jobs.len()).collect();
+
+        jobs.push(dependent_job(
+            "DetectFilesystemJob",
+            vec![
+                ("fs_name", json!(fs_name)),
+                (
+                    "targets",
+                    json!(new_targets
+                        .iter()
+                        .map(|(x, _)| x.label())
+                        .collect::<Vec<_>>()),
+                ),
+            ],
+            &deps,
+        ));
+
+        let cmd_id = run_jobs(
+            format!("Creating filesystem {}", fs_name),
+            jobs,
+            &context.rabbit_pool,
+        )
+        .await?;
+
+        let command = get_command(pool, cmd_id).await?;
+
+        Ok(command)
+    }
+}
+
+lazy_static! {
+    static ref LIMIT_RE: Regex = Regex::new(r"^[0-9]+[kKmMgGtTpP]?$").unwrap();
+    static ref GRACE_RE: Regex = Regex::new(r"^([0-9]+[wdhms]?)+$").unwrap();
+}
+
+fn validate_quota_value(x: &Option<String>, re: &Regex) -> juniper::FieldResult<()> {
+    match x {
+        Some(x) if !re.is_match(x) => Err(FieldError::new(
+            format!("Invalid quota value {}", x),
+            Value::null(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Returns an error if `pool` is not an OST pool of `fs_name`.
+pub(super) async fn check_pool(
+    pool: &PgPool,
+    fs_name: &str,
+    pool_name: &str,
+) -> juniper::FieldResult<()> {
+    let x = sqlx::query!(
+        r#"
+            SELECT p.id FROM chroma_core_ostpool p
+            INNER JOIN chroma_core_managedfilesystem f ON f.id = p.filesystem_id
+            WHERE f.name = $1 AND p.name = $2
+            AND p.not_deleted = 't' AND f.not_deleted = 't'
+        "#,
+        fs_name,
+        pool_name
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    match x {
+        Some(_) => Ok(()),
+        None => Err(FieldError::new(
+            format!("OST pool {} not found on filesystem {}", pool_name, fs_name),
+            Value::null(),
+        )),
+    }
+}
+
+/// Returns a host and mountpoint where `fs_name` is mounted as a client.
+pub(crate) async fn get_client_mount(
+    pool: &PgPool,
+    fs_name: &str,
+) -> juniper::FieldResult<(String, String)> {
+    let x = sqlx::query!(
+        r#"
+            SELECT h.fqdn, cm.mountpoints[1] AS "mountpoint!"
+            FROM chroma_core_lustreclientmount cm
+            INNER JOIN chroma_core_managedhost h ON h.id = cm.host_id
+            WHERE cm.filesystem = $1
+            AND cm.state = 'mounted'
+            AND cm.not_deleted = 't'
+            AND h.not_deleted = 't'
+            AND CARDINALITY(cm.mountpoints) > 0
+            ORDER BY h.fqdn
+            LIMIT 1
+        "#,
+        fs_name
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    x.map(|x| (x.fqdn, x.mountpoint)).ok_or_else(|| {
+        FieldError::new(
+            format!("No client mount of filesystem {} found", fs_name),
+            Value::null(),
+        )
+    })
+}
+
+fn validate_fs_name(fs_name: &str) -> juniper::FieldResult<()> {
+    if fs_name.is_empty()
+        || fs_name.len() > 8
+        || !fs_name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err(FieldError::new(
+            format!(
+                "Invalid filesystem name {}. Use up to 8 letters, digits or underscores",
+                fs_name
+            ),
+            Value::null(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns the device tree of each host
+async fn get_device_trees(pool: &PgPool) -> juniper::FieldResult<BTreeMap<String, Device>> {
+    let xs = sqlx::query!("select * from chroma_core_device")
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|x| -> juniper::FieldResult<(String, Device)> {
+            Ok((x.fqdn, serde_json::from_value(x.devices)?))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(xs)
+}
+
+/// A selected device and the hosts that can see it
+struct SharedDevice {
+    /// Identifies the device across hosts and paths
+    id: String,
+    /// The hosts that can see the device, with the selected host first
+    hosts: Vec<String>,
+    /// A path of the device that is the same on all of `hosts`
+    path: String,
+    /// Whether the device can be used as is, which is only the case for an existing MGT
+    formatted: bool,
+}
+
+/// Whether `x` is formatted as an MGT, either with ldiskfs or as a ZFS dataset.
+/// A device holding a zpool is an MGT if the zpool holds an MGT dataset.
+fn is_mgt(x: &Device) -> bool {
+    match x {
+        Device::Dataset(x) => x
+            .props
+            .iter()
+            .any(|p| p.name == "lustre:svname" && p.value == "MGS"),
+        Device::Zpool(x) => x.children.iter().any(is_mgt),
+        x if x.get_filesystem_type() == Some("lustre") => x.get_fs_label() == Some("MGS"),
+        x => x
+            .children()
+            .map(|xs| {
+                xs.iter()
+                    .any(|c| matches!(c, Device::Zpool(_)) && is_mgt(c))
+            })
+            .unwrap_or(false),
+    }
+}
+
+/// Finds the selected device and the hosts that can see it.
+/// Devices are matched across hosts by id, or by path if they have no id.
+fn find_shared_device(
+    trees: &BTreeMap<String, Device>,
+    kind: TargetKind,
+    x: &TargetDevice,
+) -> juniper::FieldResult<SharedDevice> {
+    let dev_path = DevicePath::from(x.path.as_str());
+
+    let device = trees
+        .get(&x.host)
+        .and_then(|tree| tree.find_device_by_devpath(&dev_path))
+        .ok_or_else(|| {
+            FieldError::new(
+                format!("Device {} not found on {}", x.path, x.host),
+                Value::null(),
+            )
+        })?;
+
+    let formatted = kind == TargetKind::Mgt && is_mgt(device);
+
+    let in_use = device.get_filesystem_type().is_some()
+        || device.get_mount().is_some()
+        || device.children().map(|xs| !xs.is_empty()).unwrap_or(false);
+
+    if in_use && !formatted {
+        return Err(FieldError::new(
+            format!("Device {} on {} is in use", x.path, x.host),
+            Value::null(),
+        ));
+    }
+
+    let device_id = device.get_id();
+
+    let peers: Vec<_> = trees
+        .iter()
+        .filter(|(host, _)| *host != &x.host)
+        .filter_map(|(host, tree)| {
+            let peer = match &device_id {
+                Some(id) => tree.find_device_by_id(id),
+                None => tree.find_device_by_devpath(&dev_path),
+            }?;
+
+            Some((host, peer))
+        })
+        .collect();
+
+    let hosts: Vec<_> = std::iter::once(x.host.clone())
+        .chain(peers.iter().map(|(host, _)| (*host).clone()))
+        .collect();
+
+    // An existing MGT already has its HA resource, so only new targets need a peer
+    if hosts.len() < 2 && !formatted {
+        return Err(FieldError::new(
+            format!(
+                "Device {} on {} is not shared with another host",
+                x.path, x.host
+            ),
+            Value::null(),
+        ));
+    }
+
+    let path = if formatted {
+        x.path.clone()
+    } else {
+        let peers: Vec<_> = peers.iter().map(|(_, x)| *x).collect();
+
+        shared_path(device, &dev_path, &peers).ok_or_else(|| {
+            FieldError::new(
+                format!(
+                    "Device {} on {} has no stable path common to the hosts sharing it",
+                    x.path, x.host
+                ),
+                Value::null(),
+            )
+        })?
+    };
+
+    let id = match device_id {
+        Some(id) => id.0,
+        None => format!("{}:{}", x.host, x.path),
+    };
+
+    Ok(SharedDevice {
+        id,
+        hosts,
+        path,
+        formatted,
+    })
+}
+
+/// Path prefixes that name a device the same way on every host that can see it
+const STABLE_PATH_PREFIXES: [&str; 3] = ["/dev/disk/by-id/", "/dev/disk/by-uuid/", "/dev/mapper/"];
+
+fn device_paths(x: &Device) -> Option<&Paths> {
+    match x {
+        Device::ScsiDevice(x) => Some(&x.paths),
+        Device::NvmeNamespace(x) => Some(&x.paths),
+        Device::Partition(x) => Some(&x.paths),
+        Device::MdRaid(x) => Some(&x.paths),
+        Device::Mpath(x) => Some(&x.paths),
+        Device::DmDevice(x) => Some(&x.paths),
+        Device::LogicalVolume(x) => Some(&x.paths),
+        Device::Root(_) | Device::VolumeGroup(_) | Device::Zpool(_) | Device::Dataset(_) => None,
+    }
+}
+
+/// Returns a stable path of `x` that all of `peers` have as well,
+/// preferring `selected` if it is one.
+fn shared_path(x: &Device, selected: &DevicePath, peers: &[&Device]) -> Option<String> {
+    let is_shared = |p: &DevicePath| {
+        let s = p.0.to_string_lossy();
+
+        STABLE_PATH_PREFIXES.iter().any(|x| s.starts_with(x))
+            && peers
+                .iter()
+                .all(|x| device_paths(x).map(|xs| xs.contains(p)).unwrap_or(false))
+    };
+
+    std::iter::once(selected)
+        .chain(device_paths(x)?.iter())
+        .find(|p| is_shared(p))
+        .map(|p| p.0.to_string_lossy().into_owned())
+}
+
+/// Returns an error if a resource of a new target already exists in its cluster
+async fn check_resources_unused(
+    pool: &PgPool,
+    xs: &[(FormatTarget, Vec<&HaHost>)],
+) -> juniper::FieldResult<()> {
+    let names: Vec<_> = xs.iter().map(|(x, _)| x.ha_label()).collect();
+    let cluster_ids: Vec<_> = xs.iter().map(|(_, hosts)| hosts[0].cluster_id).collect();
+
+    let existing = sqlx::query!(
+        r#"
+            SELECT r.name FROM corosync_resource r
+            INNER JOIN UNNEST($1::text[], $2::int[]) AS n(name, cluster_id)
+            ON n.name = r.name AND n.cluster_id = r.cluster_id
+        "#,
+        &names,
+        &cluster_ids
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    match existing {
+        Some(x) => Err(FieldError::new(
+            format!("Resource {} already exists in the cluster", x.name),
+            Value::null(),
+        )),
+        None => Ok(()),
+    }
+}
+
+struct HaHost {
+    fqdn: String,
+    cluster_id: i32,
+    node_name: String,
+    nids: Vec<String>,
+}
+
+/// Returns the cluster, corosync node name and NIDs of each host.
+/// Returns an error if a host is not a cluster node or has no NIDs.
+async fn get_ha_hosts(
+    pool: &PgPool,
+    fqdns: &[String],
+) -> juniper::FieldResult<HashMap<String, HaHost>> {
+    let mut xs: HashMap<_, _> = sqlx::query!(
+        r#"
+            SELECT h.id, h.fqdn, nh.cluster_id, (nh.corosync_node_id).name AS "node_name!"
+            FROM chroma_core_managedhost h
+            INNER JOIN corosync_node_managed_host nh ON nh.host_id = h.id
+            WHERE h.fqdn = ANY($1::text[])
+            AND h.not_deleted = 't'
+        "#,
+        fqdns
+    )
+    .fetch(pool)
+    .map_ok(|x| {
+        (
+            x.id,
+            HaHost {
+                fqdn: x.fqdn,
+                cluster_id: x.cluster_id,
+                node_name: x.node_name,
+                nids: vec![],
+            },
+        )
+    })
+    .try_collect()
+    .await?;
+
+    let ids: Vec<_> = xs.keys().copied().collect();
+
+    let nids: Vec<_> = sqlx::query!(
+        r#"
+            SELECT n.host_id, n.nid FROM nid n
+            INNER JOIN lnet l ON l.host_id = n.host_id AND n.id = ANY(l.nids)
+            WHERE n.host_id = ANY($1)
+            AND n.net_type != 'lo'
+            ORDER BY n.host_id, n.nid
+        "#,
+        &ids
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for x in nids {
+        if let Some(host) = xs.get_mut(&x.host_id) {
+            host.nids.push(x.nid);
+        }
+    }
+
+    let xs: HashMap<_, _> = xs.into_iter().map(|(_, x)| (x.fqdn.clone(), x)).collect();
+
+    for fqdn in fqdns {
+        match xs.get(fqdn) {
+            None => {
+                return Err(FieldError::new(
+                    format!("Host {} is not a cluster node", fqdn),
+                    Value::null(),
+                ))
+            }
+            Some(x) if x.nids.is_empty() => {
+                return Err(FieldError::new(
+                    format!("Host {} has no NIDs", fqdn),
+                    Value::null(),
+                ))
+            }
+            Some(_) => {}
+        }
+    }
+
+    Ok(xs)
+}
+
+/// The resources of a new target with their constraints, in creation order.
+/// The target prefers to run on the first node in `node_names`.
+fn target_resources(
+    x: &FormatTarget,
+    node_names: &[&str],
+) -> Vec<(ResourceAgentInfo, Vec<ResourceConstraint>)> {
+    let label = x.ha_label();
+
+    let locations = |rsc: &str| -> Vec<ResourceConstraint> {
+        node_names
+            .iter()
+            .enumerate()
+            .map(|(i, node)| ResourceConstraint::Location {
+                id: format!("{}-{}", rsc, node),
+                rsc: rsc.to_string(),
+                node: node.to_string(),
+                score: PacemakerScore::Value(if i == 0 { 20 } else { 10 }),
+            })
+            .collect()
+    };
+
+    let mut xs = vec![];
+    let mut constraints = locations(&label);
+
+    if x.backfs == FsType::Zfs {
+        let zpool_id = format!("{}-zpool", label);
+
+        xs.push((
+            ResourceAgentInfo {
+                agent: ResourceAgentType::new("ocf", "heartbeat", "ZFS"),
+                id: zpool_id.clone(),
+                args: vec![("pool".to_string(), x.zpool())].into_iter().collect(),
+                ops: PacemakerOperations::new(None, None, None),
+            },
+            locations(&zpool_id),
+        ));
+
+        constraints.push(ResourceConstraint::Order {
+            id: format!("{}-after-zpool", label),
+            first: zpool_id.clone(),
+            first_action: None,
+            then: label.clone(),
+            then_action: None,
+            kind: Some(PacemakerKindOrScore::Kind(OrderingKind::Mandatory)),
+        });
+        constraints.push(ResourceConstraint::Colocation {
+            id: format!("{}-with-zpool", label),
+            rsc: label.clone(),
+            with_rsc: zpool_id,
+            score: PacemakerScore::Infinity,
+        });
+    }
+
+    xs.push((
+        ResourceAgentInfo {
+            agent: ResourceAgentType::new("ocf", "lustre", "Lustre"),
+            id: label,
+            args: vec![
+                ("target".to_string(), x.target()),
+                ("mountpoint".to_string(), x.mountpoint()),
+            ]
+            .into_iter()
+            .collect(),
+            ops: PacemakerOperations::new(
+                "300s".to_string(),
+                "20s".to_string(),
+                "300s".to_string(),
+            ),
+        },
+        constraints,
+    ));
+
+    xs
 }
 
 async fn find_managed_fs_id_by_name(
@@ -546,3 +1390,198 @@ fn get_content_type(x: &HashMap<String, i32>, name: &str) -> juniper::FieldResul
 
     Ok(*x)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use device_types::devices::{Root, ScsiDevice};
+
+    fn scsi(serial: &str, paths: &[&str], fs: Option<(&str, &str)>) -> Device {
+        Device::ScsiDevice(ScsiDevice {
+            serial: Some(serial.into()),
+            scsi80: None,
+            major: "8".into(),
+            minor: "0".into(),
+            devpath: "/devices/sda".into(),
+            size: 1024,
+            filesystem_type: fs.map(|(x, _)| x.into()),
+            fs_uuid: None,
+            fs_label: fs.map(|(_, x)| x.into()),
+            paths: paths.iter().map(|x| DevicePath::from(*x)).collect(),
+            mount: None,
+            children: Default::default(),
+        })
+    }
+
+    fn trees(xs: Vec<(&str, Vec<Device>)>) -> BTreeMap<String, Device> {
+        xs.into_iter()
+            .map(|(host, xs)| {
+                (
+                    host.to_string(),
+                    Device::Root(Root {
+                        children: xs.into_iter().collect(),
+                    }),
+                )
+            })
+            .collect()
+    }
+
+    fn target_device(host: &str, path: &str) -> TargetDevice {
+        TargetDevice {
+            host: host.into(),
+            path: path.into(),
+        }
+    }
+
+    fn format_target(backfs: FsType) -> FormatTarget {
+        FormatTarget {
+            kind: TargetKind::Ost,
+            fs_name: "fs".into(),
+            index: 10,
+            backfs,
+            device: "/dev/disk/by-id/scsi-1".into(),
+            mgs_nids: vec![],
+            service_nids: vec![],
+            mkfs_options: None,
+        }
+    }
+
+    #[test]
+    fn test_find_shared_device() {
+        let trees = trees(vec![
+            (
+                "oss1",
+                vec![scsi("1", &["/dev/sda", "/dev/disk/by-id/scsi-1"], None)],
+            ),
+            (
+                "oss2",
+                vec![scsi("1", &["/dev/sdc", "/dev/disk/by-id/scsi-1"], None)],
+            ),
+            (
+                "oss3",
+                vec![scsi("2", &["/dev/sda", "/dev/disk/by-id/scsi-2"], None)],
+            ),
+        ]);
+
+        let x = find_shared_device(&trees, TargetKind::Ost, &target_device("oss1", "/dev/sda"))
+            .unwrap();
+
+        assert_eq!(x.id, "scsi_1");
+        assert_eq!(x.hosts, vec!["oss1", "oss2"]);
+        assert_eq!(x.path, "/dev/disk/by-id/scsi-1");
+        assert!(!x.formatted);
+
+        let x = find_shared_device(&trees, TargetKind::Ost, &target_device("oss2", "/dev/sdc"))
+            .unwrap();
+
+        assert_eq!(x.hosts, vec!["oss2", "oss1"]);
+        assert_eq!(x.path, "/dev/disk/by-id/scsi-1");
+    }
+
+    #[test]
+    fn test_find_shared_device_errors() {
+        let trees = trees(vec![
+            (
+                "oss1",
+                vec![
+                    scsi("1", &["/dev/sda", "/dev/disk/by-id/scsi-1"], None),
+                    scsi("2", &["/dev/sdb"], None),
+                    scsi(
+                        "3",
+                        &["/dev/sdc", "/dev/disk/by-id/scsi-3"],
+                        Some(("ext4", "data")),
+                    ),
+                ],
+            ),
+            (
+                "oss2",
+                vec![
+                    scsi("2", &["/dev/sdd"], None),
+                    scsi(
+                        "3",
+                        &["/dev/sde", "/dev/disk/by-id/scsi-3"],
+                        Some(("ext4", "data")),
+                    ),
+                ],
+            ),
+        ]);
+
+        let find = |path| find_shared_device(&trees, TargetKind::Ost, &target_device("oss1", path));
+
+        // Not found
+        assert!(find("/dev/sdz").is_err());
+        // Only seen by oss1
+        assert!(find("/dev/sda").is_err());
+        // Shared, but named differently on each host
+        assert!(find("/dev/sdb").is_err());
+        // In use
+        assert!(find("/dev/sdc").is_err());
+    }
+
+    #[test]
+    fn test_find_shared_device_reuses_mgt() {
+        let mgt = scsi("1", &["/dev/sda"], Some(("lustre", "MGS")));
+        let trees = trees(vec![("mds1", vec![mgt])]);
+        let x = target_device("mds1", "/dev/sda");
+
+        let shared = find_shared_device(&trees, TargetKind::Mgt, &x).unwrap();
+
+        assert!(shared.formatted);
+        assert_eq!(shared.hosts, vec!["mds1"]);
+        assert_eq!(shared.path, "/dev/sda");
+
+        assert!(find_shared_device(&trees, TargetKind::Mdt, &x).is_err());
+    }
+
+    #[test]
+    fn test_is_mgt() {
+        assert!(is_mgt(&scsi("1", &[], Some(("lustre", "MGS")))));
+        assert!(!is_mgt(&scsi("1", &[], Some(("lustre", "fs-OST0000")))));
+        assert!(!is_mgt(&scsi("1", &[], Some(("ext4", "MGS")))));
+        assert!(!is_mgt(&scsi("1", &[], None)));
+    }
+
+    #[test]
+    fn test_target_resources_ldiskfs() {
+        let xs = target_resources(&format_target(FsType::Ldiskfs), &["oss1", "oss2"]);
+
+        assert_eq!(xs.len(), 1);
+
+        let (rsc, constraints) = &xs[0];
+
+        assert_eq!(rsc.id, "fs-OST000a");
+        assert_eq!(rsc.agent, ResourceAgentType::new("ocf", "lustre", "Lustre"));
+        assert_eq!(rsc.args["target"], "/dev/disk/by-id/scsi-1");
+        assert_eq!(rsc.args["mountpoint"], "/mnt/fs-OST000a");
+        assert_eq!(
+            constraints,
+            &vec![
+                ResourceConstraint::Location {
+                    id: "fs-OST000a-oss1".into(),
+                    rsc: "fs-OST000a".into(),
+                    node: "oss1".into(),
+                    score: PacemakerScore::Value(20),
+                },
+                ResourceConstraint::Location {
+                    id: "fs-OST000a-oss2".into(),
+                    rsc: "fs-OST000a".into(),
+                    node: "oss2".into(),
+                    score: PacemakerScore::Value(10),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_target_resources_zfs() {
+        let xs = target_resources(&format_target(FsType::Zfs), &["oss1", "oss2"]);
+
+        let ids: Vec<_> = xs.iter().map(|(x, _)| x.id.as_str()).collect();
+
+        assert_eq!(ids, vec!["fs-OST000a-zpool", "fs-OST000a"]);
+        assert_eq!(xs[0].0.args["pool"], "fs-ost000a");
+        assert_eq!(xs[1].0.args["target"], "fs-ost000a/ost");
+        assert_eq!(xs[0].1.len(), 2);
+        assert_eq!(xs[1].1.len(), 4);
+    }
+}
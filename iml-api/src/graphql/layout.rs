@@ -0,0 +1,378 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::graphql::{filesystem::get_client_mount, Context};
+use iml_postgres::{sqlx, PgPool};
+use iml_wire_types::layout::{
+    AppliedLayout, GetLayout, Layout, LayoutComponent, LayoutComponentInput, LayoutComponentOut,
+    LayoutTemplate, SetLayout,
+};
+use juniper::{FieldError, Value};
+use std::convert::TryFrom;
+
+pub(crate) struct LayoutQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl LayoutQuery {
+    /// List all layout templates
+    async fn templates(context: &Context) -> juniper::FieldResult<Vec<LayoutTemplate>> {
+        let xs = sqlx::query!(
+            "SELECT id, name, description, components FROM layout_template ORDER BY name"
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        xs.into_iter()
+            .map(|x| {
+                Ok(LayoutTemplate {
+                    id: x.id,
+                    name: x.name,
+                    description: x.description,
+                    components: to_components(x.components)?,
+                })
+            })
+            .collect()
+    }
+    #[graphql(arguments(fs_name(description = "Only list layouts set on this filesystem")))]
+    /// List the default layouts that were set on directories through the manager
+    async fn applied(
+        context: &Context,
+        fs_name: Option<String>,
+    ) -> juniper::FieldResult<Vec<AppliedLayout>> {
+        let xs = sqlx::query!(
+            r#"
+                SELECT a.fs_name, a.path, t.name AS "template?", a.components, a.applied_at
+                FROM layout_applied a
+                LEFT JOIN layout_template t ON t.id = a.template_id
+                WHERE ($1::text IS NULL OR a.fs_name = $1)
+                ORDER BY a.fs_name, a.path
+            "#,
+            fs_name
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        xs.into_iter()
+            .map(|x| {
+                Ok(AppliedLayout {
+                    fs_name: x.fs_name,
+                    path: x.path,
+                    template: x.template,
+                    components: to_components(x.components)?,
+                    applied_at: x.applied_at,
+                })
+            })
+            .collect()
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem the directory is on"),
+        path(description = "The directory, relative to the filesystem root"),
+    ))]
+    /// Read the default layout of a directory with `lfs getstripe`
+    /// on a client that has `fs_name` mounted.
+    /// Returns `null` if the directory has no default layout.
+    async fn directory(
+        context: &Context,
+        fs_name: String,
+        path: String,
+    ) -> juniper::FieldResult<Option<Vec<LayoutComponentOut>>> {
+        let path = normalize_path(&path)?;
+
+        let (fqdn, mountpoint) = get_client_mount(&context.pg_pool, &fs_name).await?;
+
+        let x = context
+            .action_client
+            .invoke_rust_agent_expect_result(
+                fqdn,
+                "layout_get",
+                GetLayout {
+                    path: client_path(&mountpoint, &path),
+                },
+                None,
+            )
+            .await?
+            .map_err(|e| FieldError::new(e, Value::null()))?;
+
+        let x: Option<Layout> = serde_json::from_value(x)?;
+
+        Ok(x.map(|x| x.components.into_iter().map(Into::into).collect()))
+    }
+}
+
+pub(crate) struct LayoutMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl LayoutMutation {
+    #[graphql(arguments(
+        name(description = "The unique name of the template"),
+        description(description = "What the layout is meant for"),
+        components(description = "The components of the layout, in extent order"),
+    ))]
+    /// Create a layout template
+    async fn create_template(
+        context: &Context,
+        name: String,
+        description: Option<String>,
+        components: Vec<LayoutComponentInput>,
+    ) -> juniper::FieldResult<LayoutTemplate> {
+        let layout = to_layout(components)?;
+
+        let id = sqlx::query!(
+            r#"
+                INSERT INTO layout_template (name, description, components)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (name) DO NOTHING
+                RETURNING id
+            "#,
+            name,
+            description.unwrap_or_default(),
+            serde_json::to_value(&layout.components)?
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .ok_or_else(|| {
+            FieldError::new(
+                format!("Layout template {} already exists", name),
+                Value::null(),
+            )
+        })?
+        .id;
+
+        let x = sqlx::query!(
+            "SELECT id, name, description, components FROM layout_template WHERE id = $1",
+            id
+        )
+        .fetch_one(&context.pg_pool)
+        .await?;
+
+        Ok(LayoutTemplate {
+            id: x.id,
+            name: x.name,
+            description: x.description,
+            components: to_components(x.components)?,
+        })
+    }
+    #[graphql(arguments(name(description = "The name of the template to remove")))]
+    /// Remove a layout template.
+    /// Directories the template was applied to keep their layout.
+    async fn remove_template(context: &Context, name: String) -> juniper::FieldResult<bool> {
+        sqlx::query!(
+            "DELETE FROM layout_template WHERE name = $1 RETURNING id",
+            name
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .ok_or_else(|| {
+            FieldError::new(format!("Layout template {} not found", name), Value::null())
+        })?;
+
+        Ok(true)
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem the directory is on"),
+        path(description = "The directory, relative to the filesystem root"),
+        name(description = "The template to apply"),
+    ))]
+    /// Set the default layout of a directory from a template
+    async fn apply_template(
+        context: &Context,
+        fs_name: String,
+        path: String,
+        name: String,
+    ) -> juniper::FieldResult<bool> {
+        let x = sqlx::query!(
+            "SELECT id, components FROM layout_template WHERE name = $1",
+            name
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .ok_or_else(|| {
+            FieldError::new(format!("Layout template {} not found", name), Value::null())
+        })?;
+
+        let layout = Layout {
+            components: serde_json::from_value(x.components)?,
+        };
+
+        set_layout(context, &fs_name, &path, Some(x.id), layout).await?;
+
+        Ok(true)
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem the directory is on"),
+        path(description = "The directory, relative to the filesystem root"),
+        components(description = "The components of the layout, in extent order"),
+    ))]
+    /// Set the default layout of a directory
+    async fn set_directory(
+        context: &Context,
+        fs_name: String,
+        path: String,
+        components: Vec<LayoutComponentInput>,
+    ) -> juniper::FieldResult<bool> {
+        let layout = to_layout(components)?;
+
+        set_layout(context, &fs_name, &path, None, layout).await?;
+
+        Ok(true)
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem the directory is on"),
+        path(description = "The directory, relative to the filesystem root"),
+    ))]
+    /// Remove the default layout of a directory.
+    /// New files in the directory then use the filesystem default layout.
+    async fn clear_directory(
+        context: &Context,
+        fs_name: String,
+        path: String,
+    ) -> juniper::FieldResult<bool> {
+        let path = normalize_path(&path)?;
+
+        let (fqdn, mountpoint) = get_client_mount(&context.pg_pool, &fs_name).await?;
+
+        context
+            .action_client
+            .invoke_rust_agent_expect_result(
+                fqdn,
+                "layout_set",
+                SetLayout {
+                    path: client_path(&mountpoint, &path),
+                    layout: None,
+                },
+                None,
+            )
+            .await?
+            .map_err(|e| FieldError::new(e, Value::null()))?;
+
+        sqlx::query!(
+            "DELETE FROM layout_applied WHERE fs_name = $1 AND path = $2",
+            fs_name,
+            path
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        Ok(true)
+    }
+}
+
+fn to_components(x: serde_json::Value) -> Result<Vec<LayoutComponentOut>, serde_json::Error> {
+    let xs: Vec<LayoutComponent> = serde_json::from_value(x)?;
+
+    Ok(xs.into_iter().map(Into::into).collect())
+}
+
+fn to_layout(xs: Vec<LayoutComponentInput>) -> juniper::FieldResult<Layout> {
+    let layout = Layout {
+        components: xs
+            .into_iter()
+            .map(LayoutComponent::try_from)
+            .collect::<Result<_, _>>()
+            .map_err(|e| FieldError::new(e, Value::null()))?,
+    };
+
+    layout
+        .validate()
+        .map_err(|e| FieldError::new(e, Value::null()))?;
+
+    Ok(layout)
+}
+
+/// Checks `path` is absolute and does not leave the filesystem,
+/// and strips any trailing `/`.
+pub(super) fn normalize_path(path: &str) -> juniper::FieldResult<String> {
+    if !path.starts_with('/') || path.split('/').any(|x| x == "..") {
+        return Err(FieldError::new(
+            format!(
+                "Invalid path {}. Paths must be absolute from the filesystem root",
+                path
+            ),
+            Value::null(),
+        ));
+    }
+
+    match path.trim_end_matches('/') {
+        "" => Ok("/".to_string()),
+        x => Ok(x.to_string()),
+    }
+}
+
+fn client_path(mountpoint: &str, path: &str) -> String {
+    format!("{}{}", mountpoint.trim_end_matches('/'), path)
+}
+
+async fn set_layout(
+    context: &Context,
+    fs_name: &str,
+    path: &str,
+    template_id: Option<i32>,
+    layout: Layout,
+) -> juniper::FieldResult<()> {
+    let path = normalize_path(path)?;
+
+    let (fqdn, mountpoint) = get_client_mount(&context.pg_pool, fs_name).await?;
+
+    let components = serde_json::to_value(&layout.components)?;
+
+    context
+        .action_client
+        .invoke_rust_agent_expect_result(
+            fqdn,
+            "layout_set",
+            SetLayout {
+                path: client_path(&mountpoint, &path),
+                layout: Some(layout),
+            },
+            None,
+        )
+        .await?
+        .map_err(|e| FieldError::new(e, Value::null()))?;
+
+    record_applied(&context.pg_pool, fs_name, &path, template_id, components).await?;
+
+    Ok(())
+}
+
+async fn record_applied(
+    pool: &PgPool,
+    fs_name: &str,
+    path: &str,
+    template_id: Option<i32>,
+    components: serde_json::Value,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+            INSERT INTO layout_applied (fs_name, path, template_id, components)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (fs_name, path) DO UPDATE
+            SET template_id = EXCLUDED.template_id,
+                components = EXCLUDED.components,
+                applied_at = now()
+        "#,
+        fs_name,
+        path,
+        template_id,
+        components
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("/").unwrap(), "/");
+        assert_eq!(normalize_path("/project/a/").unwrap(), "/project/a");
+        assert!(normalize_path("project").is_err());
+        assert!(normalize_path("/project/../..").is_err());
+
+        assert_eq!(client_path("/mnt/fs/", "/project"), "/mnt/fs/project");
+    }
+}
@@ -0,0 +1,59 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    command::get_command,
+    graphql::{run_jobs, server::get_host_id, Context, SendJob},
+};
+use iml_wire_types::Command;
+use juniper::{FieldError, Value};
+use serde_json::json;
+use std::collections::HashMap;
+
+/// Rejects a negative value for the optional argument `name`,
+/// as the agent expects an unsigned value.
+pub(crate) fn check_non_negative(name: &str, x: Option<i32>) -> juniper::FieldResult<()> {
+    match x {
+        Some(x) if x < 0 => Err(FieldError::new(
+            format!("{} must not be negative, got {}", name, x),
+            Value::null(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+pub(crate) async fn lnet_command(
+    context: &Context,
+    class_name: &str,
+    msg: String,
+    fqdn: String,
+    args: Vec<(&str, serde_json::Value)>,
+) -> juniper::FieldResult<Command> {
+    get_host_id(&context.pg_pool, &fqdn).await?;
+
+    let job = SendJob {
+        class_name,
+        args: std::iter::once(("fqdn".to_string(), json!(fqdn)))
+            .chain(args.into_iter().map(|(k, v)| (k.to_string(), v)))
+            .collect::<HashMap<String, serde_json::Value>>(),
+    };
+
+    let command_id = run_jobs(msg, vec![job], &context.rabbit_pool).await?;
+
+    let command = get_command(&context.pg_pool, command_id).await?;
+
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_non_negative() {
+        assert!(check_non_negative("hop", None).is_ok());
+        assert!(check_non_negative("hop", Some(0)).is_ok());
+        assert!(check_non_negative("hop", Some(-1)).is_err());
+    }
+}
@@ -0,0 +1,337 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    command::get_command,
+    graphql::{
+        create_task_job,
+        filesystem::{check_pool, get_client_mount},
+        fs_id_by_name, insert_task,
+        layout::normalize_path,
+        run_jobs,
+        stratagem::scan_task,
+        Context,
+    },
+    timer::{configure_mirror_resync_timer, remove_mirror_resync_timer},
+};
+use futures::TryStreamExt;
+use iml_postgres::{sqlx, sqlx::postgres::types::PgInterval};
+use iml_wire_types::{
+    graphql_duration::GraphQLDuration,
+    mirror::{MirrorFile, MirrorFilter, MirrorFind, MirrorResyncInterval},
+    Command,
+};
+use juniper::{FieldError, Value};
+use std::{collections::HashMap, convert::TryFrom};
+use uuid::Uuid;
+
+/// The number of stale files listed if no limit is given
+const STALE_LIMIT: i32 = 100;
+
+pub(crate) struct MirrorQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl MirrorQuery {
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to search"),
+        path(description = "The directory to search, from the filesystem root"),
+        limit(description = "The maximum number of files to list. Defaults to 100"),
+    ))]
+    /// List the files under `path` with mirrors that are out of sync
+    async fn stale(
+        context: &Context,
+        fs_name: String,
+        path: String,
+        limit: Option<i32>,
+    ) -> juniper::FieldResult<Vec<MirrorFile>> {
+        let limit = limit.unwrap_or(STALE_LIMIT).max(0) as u32;
+
+        find_files(context, &fs_name, &path, MirrorFilter::Stale, limit).await
+    }
+    /// List all mirror resync intervals
+    async fn resync_intervals(
+        context: &Context,
+    ) -> juniper::FieldResult<Vec<MirrorResyncInterval>> {
+        let xs = sqlx::query!(
+            r#"
+                SELECT id, fs_name, path, interval FROM mirror_resync_interval
+                ORDER BY fs_name, path
+            "#
+        )
+        .fetch(&context.pg_pool)
+        .map_ok(|x| MirrorResyncInterval {
+            id: x.id,
+            fs_name: x.fs_name,
+            path: x.path,
+            interval: x.interval.into(),
+        })
+        .try_collect()
+        .await?;
+
+        Ok(xs)
+    }
+}
+
+pub(crate) struct MirrorMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl MirrorMutation {
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to mirror files of"),
+        pool(description = "The OST pool to add mirrors on"),
+        path(description = "Mirror the files under this directory, from the filesystem root"),
+        expression(description = "Mirror the files matching this Stratagem expression"),
+        striping(description = "Space separated `lfs mirror extend` striping options"),
+    ))]
+    /// Adds a mirror on `pool` to files of a filesystem.
+    /// Either `path` or `expression` selects the files.
+    /// Files under `path` that already have a component on `pool` are skipped.
+    async fn extend(
+        context: &Context,
+        fs_name: String,
+        pool: String,
+        path: Option<String>,
+        expression: Option<String>,
+        striping: Option<String>,
+    ) -> juniper::FieldResult<Command> {
+        check_pool(&context.pg_pool, &fs_name, &pool).await?;
+
+        let mut args = vec![("pool".to_string(), pool.clone())];
+
+        if let Some(x) = striping {
+            args.push(("striping".to_string(), x));
+        }
+
+        let args: HashMap<String, String> = args.into_iter().collect();
+
+        match (path, expression) {
+            (Some(path), None) => find_task(
+                context,
+                &fs_name,
+                &path,
+                MirrorFilter::NotOnPool(pool.clone()),
+                "mirror.extend",
+                serde_json::to_value(&args)?,
+                format!("Mirroring files under {} to {}", path, pool),
+            )
+            .await?
+            .ok_or_else(|| {
+                FieldError::new(
+                    format!("No files under {} need a mirror on {}", path, pool),
+                    Value::null(),
+                )
+            }),
+            (None, Some(expression)) => {
+                scan_task(
+                    context,
+                    &fs_name,
+                    "mirror",
+                    "mirror.extend",
+                    serde_json::to_value(&args)?,
+                    &expression,
+                    &format!("Stratagem: Mirroring to {}", pool),
+                )
+                .await
+            }
+            _ => Err(FieldError::new(
+                "Exactly one of path or expression must be given",
+                Value::null(),
+            )),
+        }
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to resync files of"),
+        path(description = "Resync the files under this directory, from the filesystem root"),
+    ))]
+    /// Resyncs the stale mirrors of the files under `path`.
+    /// Returns `null` if no mirror is stale.
+    async fn resync(
+        context: &Context,
+        fs_name: String,
+        path: String,
+    ) -> juniper::FieldResult<Option<Command>> {
+        let command = find_task(
+            context,
+            &fs_name,
+            &path,
+            MirrorFilter::Stale,
+            "mirror.resync",
+            serde_json::json!({}),
+            format!("Resyncing mirrors under {}", path),
+        )
+        .await?;
+
+        Ok(command)
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to resync files of"),
+        path(description = "Resync the files under this directory, from the filesystem root"),
+        interval(description = "How often stale mirrors should be resynced"),
+    ))]
+    /// Creates a new mirror resync interval, or replaces the interval of an existing one.
+    /// The stale mirrors under `path` will be resynced each time `interval` expires.
+    async fn create_resync_interval(
+        context: &Context,
+        fs_name: String,
+        path: String,
+        interval: GraphQLDuration,
+    ) -> juniper::FieldResult<bool> {
+        fs_id_by_name(&context.pg_pool, &fs_name).await?;
+
+        let path = normalize_path(&path)?;
+
+        if path
+            .chars()
+            .any(|c| c.is_whitespace() || c == '%' || c == '"' || c == '\\')
+        {
+            return Err(FieldError::new(
+                format!("Path {} cannot be resynced on an interval", path),
+                Value::null(),
+            ));
+        }
+
+        let id = sqlx::query!(
+            r#"
+                INSERT INTO mirror_resync_interval (fs_name, path, interval)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (fs_name, path)
+                DO UPDATE SET interval = EXCLUDED.interval
+                RETURNING id
+            "#,
+            fs_name,
+            path,
+            PgInterval::try_from(interval.0)?,
+        )
+        .fetch_one(&context.pg_pool)
+        .await?
+        .id;
+
+        configure_mirror_resync_timer(id, &fs_name, &path, interval.0).await?;
+
+        Ok(true)
+    }
+    /// Removes an existing mirror resync interval.
+    #[graphql(arguments(id(description = "The mirror resync interval id"),))]
+    async fn remove_resync_interval(context: &Context, id: i32) -> juniper::FieldResult<bool> {
+        sqlx::query!("DELETE FROM mirror_resync_interval WHERE id=$1", id)
+            .execute(&context.pg_pool)
+            .await?;
+
+        remove_mirror_resync_timer(id).await?;
+
+        Ok(true)
+    }
+}
+
+/// Lists up to `limit` files under `path` matching `filter` on a client of `fs_name`
+async fn find_files(
+    context: &Context,
+    fs_name: &str,
+    path: &str,
+    filter: MirrorFilter,
+    limit: u32,
+) -> juniper::FieldResult<Vec<MirrorFile>> {
+    let path = normalize_path(path)?;
+
+    let (fqdn, mountpoint) = get_client_mount(&context.pg_pool, fs_name).await?;
+
+    let xs = context
+        .action_client
+        .invoke_rust_agent_expect_result(
+            fqdn,
+            "mirror_find",
+            MirrorFind {
+                mountpoint,
+                path,
+                filter,
+                limit: Some(limit),
+            },
+            None,
+        )
+        .await?
+        .map_err(|e| FieldError::new(e, Value::null()))?;
+
+    Ok(xs)
+}
+
+/// Creates a task running `action` on the files under `path` matching `filter`.
+/// A client of `fs_name` finds the files and streams their FIDs to the task mailbox in batches.
+/// Returns `None` if no file matches.
+async fn find_task(
+    context: &Context,
+    fs_name: &str,
+    path: &str,
+    filter: MirrorFilter,
+    action: &str,
+    args: serde_json::Value,
+    msg: String,
+) -> juniper::FieldResult<Option<Command>> {
+    let path = normalize_path(path)?;
+
+    let (fqdn, mountpoint) = get_client_mount(&context.pg_pool, fs_name).await?;
+
+    let uuid = Uuid::new_v4().to_hyphenated().to_string();
+
+    let fs_id = fs_id_by_name(&context.pg_pool, fs_name).await?;
+
+    let task = insert_task(
+        &format!("{}-{}", uuid, action.replace('.', "-")),
+        "created",
+        false,
+        false,
+        &[action.to_string()],
+        args,
+        fs_id,
+        &context.pg_pool,
+    )
+    .await?;
+
+    let count: Result<u64, _> = context
+        .action_client
+        .invoke_rust_agent_expect_result(
+            fqdn,
+            "mirror_find_to_mailbox",
+            (
+                MirrorFind {
+                    mountpoint,
+                    path,
+                    filter,
+                    limit: None,
+                },
+                &task.name,
+            ),
+            None,
+        )
+        .await?
+        .map_err(|e| FieldError::new(e, Value::null()));
+
+    // Do not leave a task behind that has nothing to run on
+    match count {
+        Ok(x) if x > 0 => {}
+        x => {
+            let names = vec![task.name.clone()];
+
+            sqlx::query!(
+                r#"
+                    UPDATE chroma_core_task
+                    SET state = 'removed', finish = now()
+                    WHERE name = ANY($1::text[])
+                "#,
+                &names
+            )
+            .execute(&context.pg_pool)
+            .await?;
+
+            x?;
+
+            return Ok(None);
+        }
+    };
+
+    let cmd_id = run_jobs(msg, vec![create_task_job(task.id)], &context.rabbit_pool).await?;
+
+    let command = get_command(&context.pg_pool, cmd_id).await?;
+
+    Ok(Some(command))
+}
@@ -2,9 +2,18 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
+mod changelog;
+mod client;
 mod filesystem;
+mod layout;
+mod lnet;
+mod mirror;
+mod nodemap;
+mod server;
 mod stratagem;
 mod task;
+mod tiering;
+mod tunable;
 
 use crate::{
     command::get_command,
@@ -82,6 +91,15 @@ struct TargetResource {
     state: String,
     /// The list of host ids this target could possibly run on
     cluster_hosts: Vec<i32>,
+    /// The host ids this target prefers to run on, as set by
+    /// location constraints. Ordered from most to least preferred.
+    preferred_hosts: Vec<i32>,
+}
+
+struct PreferredResourceHost {
+    resource: String,
+    cluster_id: i32,
+    host_id: i32,
 }
 
 struct BannedTargetResource {
@@ -120,12 +138,39 @@ pub(crate) struct QueryRoot;
 
 #[juniper::graphql_object(Context = Context)]
 impl QueryRoot {
+    fn changelog(&self) -> changelog::ChangelogQuery {
+        changelog::ChangelogQuery
+    }
+    fn client(&self) -> client::ClientQuery {
+        client::ClientQuery
+    }
+    fn filesystem(&self) -> filesystem::FilesystemQuery {
+        filesystem::FilesystemQuery
+    }
+    fn layout(&self) -> layout::LayoutQuery {
+        layout::LayoutQuery
+    }
+    fn mirror(&self) -> mirror::MirrorQuery {
+        mirror::MirrorQuery
+    }
+    fn nodemap(&self) -> nodemap::NodemapQuery {
+        nodemap::NodemapQuery
+    }
+    fn server(&self) -> server::ServerQuery {
+        server::ServerQuery
+    }
     fn stratagem(&self) -> stratagem::StratagemQuery {
         stratagem::StratagemQuery
     }
     fn task(&self) -> task::TaskQuery {
         task::TaskQuery
     }
+    fn tiering(&self) -> tiering::TieringQuery {
+        tiering::TieringQuery
+    }
+    fn tunable(&self) -> tunable::TunableQuery {
+        tunable::TunableQuery
+    }
     /// Given a host id, try to find the matching corosync node name
     #[graphql(arguments(host_id(description = "The id to search on")))]
     async fn corosync_node_name_by_host(
@@ -670,15 +715,39 @@ pub(crate) struct MutationRoot;
 
 #[juniper::graphql_object(Context = Context)]
 impl MutationRoot {
+    fn changelog(&self) -> changelog::ChangelogMutation {
+        changelog::ChangelogMutation
+    }
+    fn client(&self) -> client::ClientMutation {
+        client::ClientMutation
+    }
     fn filesystem(&self) -> filesystem::FilesystemMutation {
         filesystem::FilesystemMutation
     }
+    fn layout(&self) -> layout::LayoutMutation {
+        layout::LayoutMutation
+    }
+    fn mirror(&self) -> mirror::MirrorMutation {
+        mirror::MirrorMutation
+    }
+    fn nodemap(&self) -> nodemap::NodemapMutation {
+        nodemap::NodemapMutation
+    }
+    fn server(&self) -> server::ServerMutation {
+        server::ServerMutation
+    }
     fn stratagem(&self) -> stratagem::StratagemMutation {
         stratagem::StratagemMutation
     }
     fn task(&self) -> task::TaskMutation {
         task::TaskMutation
     }
+    fn tiering(&self) -> tiering::TieringMutation {
+        tiering::TieringMutation
+    }
+    fn tunable(&self) -> tunable::TunableMutation {
+        tunable::TunableMutation
+    }
     #[graphql(arguments(
         fsname(description = "Filesystem to snapshot"),
         name(description = "Name of the snapshot"),
@@ -1178,6 +1247,7 @@ pub(crate) type Schema = RootNode<'static, QueryRoot, MutationRoot, EmptySubscri
 pub(crate) struct Context {
     pub(crate) pg_pool: PgPool,
     pub(crate) rabbit_pool: Pool,
+    pub(crate) action_client: iml_action_client::Client,
 }
 
 impl juniper::Context for Context {}
@@ -1222,6 +1292,8 @@ async fn get_fs_target_resources(
 ) -> Result<Vec<TargetResource>, ImlApiError> {
     let banned_resources = get_banned_targets(pool).await?;
 
+    let preferred_hosts = get_preferred_resource_hosts(pool).await?;
+
     let xs = sqlx::query!(r#"
             SELECT
                 rh.cluster_id,
@@ -1261,6 +1333,12 @@ async fn get_fs_target_resources(
                 x
             })
             .map_ok(|x| {
+                let preferred_hosts = preferred_hosts
+                    .iter()
+                    .filter(|y| y.cluster_id == x.cluster_id && y.resource == x.id && x.cluster_hosts.contains(&y.host_id))
+                    .map(|y| y.host_id)
+                    .collect();
+
                 TargetResource {
                     cluster_id: x.cluster_id,
                     fs_names: x.filesystems,
@@ -1268,7 +1346,8 @@ async fn get_fs_target_resources(
                     name: x.name,
                     resource_id: x.id,
                     state: x.state,
-                    cluster_hosts: x.cluster_hosts
+                    cluster_hosts: x.cluster_hosts,
+                    preferred_hosts,
                 }
             }).try_collect()
             .await?;
@@ -1330,6 +1409,29 @@ async fn get_banned_targets(pool: &PgPool) -> Result<Vec<BannedTargetResource>,
     Ok(xs)
 }
 
+/// Hosts resources have a positive location constraint for,
+/// ordered by descending score.
+async fn get_preferred_resource_hosts(
+    pool: &PgPool,
+) -> Result<Vec<PreferredResourceHost>, ImlApiError> {
+    let xs = sqlx::query_as!(
+        PreferredResourceHost,
+        r#"
+            SELECT c.resource, c.cluster_id, nh.host_id
+            FROM corosync_resource_constraint c
+            INNER JOIN corosync_node_managed_host nh ON (nh.corosync_node_id).name = c.node
+            AND nh.cluster_id = c.cluster_id
+            WHERE c.constraint_type = 'location'
+            AND c.score > 0
+            ORDER BY c.score DESC
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(xs)
+}
+
 async fn get_banned_resources(pool: &PgPool) -> Result<Vec<BannedResource>, ImlApiError> {
     let xs = sqlx::query_as!(
         BannedResource,
@@ -1510,6 +1612,22 @@ fn create_task_job<'a>(task_id: i32) -> SendJob<'a, HashMap<String, serde_json::
     }
 }
 
+/// A job that starts once the jobs at the `deps` indices have completed
+fn dependent_job<'a>(
+    class_name: &'a str,
+    args: Vec<(&str, serde_json::Value)>,
+    deps: &[usize],
+) -> SendJob<'a, HashMap<String, serde_json::Value>> {
+    let mut args: HashMap<String, serde_json::Value> =
+        args.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+
+    if !deps.is_empty() {
+        args.insert("depends_on_job_range".into(), serde_json::json!(deps));
+    }
+
+    SendJob { class_name, args }
+}
+
 async fn client_mount_source(pg_pool: &PgPool, fs_name: &str) -> Result<String, ImlApiError> {
     let nids = sqlx::query!(
         r#"
@@ -0,0 +1,628 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::graphql::Context;
+use iml_postgres::{active_mgs_host_fqdn, sqlx, PgPool};
+use iml_wire_types::nodemap::{
+    nodemap_changes, unmanaged_nodemaps, Nodemap, NodemapChange, NodemapIdKind, NodemapIdmap,
+    NodemapRange, NodemapState, NodemapStatus, DEFAULT_NODEMAP,
+};
+use juniper::{FieldError, Value};
+
+pub(crate) struct NodemapQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl NodemapQuery {
+    #[graphql(arguments(fs_name(description = "The filesystem to list nodemaps of")))]
+    /// List the desired nodemaps on the MGS of a filesystem.
+    /// Nodemaps are global to an MGS, so they are shared by all filesystems using it.
+    async fn list(context: &Context, fs_name: String) -> juniper::FieldResult<Vec<Nodemap>> {
+        let mgs_id = fs_mgs_id(&context.pg_pool, &fs_name).await?;
+
+        let xs = get_nodemaps(&context.pg_pool, mgs_id).await?;
+
+        Ok(xs)
+    }
+    #[graphql(arguments(fs_name(description = "The filesystem to compare nodemaps of")))]
+    /// Compare the desired nodemaps on the MGS of a filesystem with those set on it
+    async fn status(context: &Context, fs_name: String) -> juniper::FieldResult<NodemapStatus> {
+        let mgs_id = fs_mgs_id(&context.pg_pool, &fs_name).await?;
+        let fqdn = mgs_fqdn(&context.pg_pool, &fs_name).await?;
+
+        let desired = get_nodemaps(&context.pg_pool, mgs_id).await?;
+        let desired_active = get_active(&context.pg_pool, mgs_id).await?;
+        let actual = get_state(context, fqdn).await?;
+
+        Ok(NodemapStatus {
+            active: actual.active,
+            desired_active,
+            pending: nodemap_changes(&desired, desired_active, &actual)
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            unmanaged: unmanaged_nodemaps(&desired, &actual)
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        })
+    }
+}
+
+pub(crate) struct NodemapMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl NodemapMutation {
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to create the nodemap on"),
+        name(description = "The name of the nodemap"),
+        admin(description = "Do not squash root on clients of the nodemap. Defaults to false"),
+        trusted(description = "Use client ids as is, without mapping. Defaults to false"),
+        squash_uid(description = "The uid unmapped users are squashed to. Defaults to 99"),
+        squash_gid(description = "The gid unmapped groups are squashed to. Defaults to 99"),
+        ranges(description = "Client NID ranges, i.e. `192.168.1.[1-100]@tcp`"),
+    ))]
+    /// Create a nodemap and set it on the MGS
+    async fn create(
+        context: &Context,
+        fs_name: String,
+        name: String,
+        admin: Option<bool>,
+        trusted: Option<bool>,
+        squash_uid: Option<i32>,
+        squash_gid: Option<i32>,
+        ranges: Option<Vec<String>>,
+    ) -> juniper::FieldResult<Nodemap> {
+        validate_name(&name)?;
+
+        let mgs_id = fs_mgs_id(&context.pg_pool, &fs_name).await?;
+
+        let ranges = ranges
+            .unwrap_or_default()
+            .iter()
+            .map(|x| parse_range(&name, x))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut transaction = context.pg_pool.begin().await?;
+
+        let id = sqlx::query!(
+            r#"
+                INSERT INTO nodemap (mgs_id, name, admin, trusted, squash_uid, squash_gid)
+                VALUES (
+                    $1,
+                    $2,
+                    COALESCE($3, 'f'),
+                    COALESCE($4, 'f'),
+                    COALESCE($5, 99),
+                    COALESCE($6, 99)
+                )
+                ON CONFLICT (mgs_id, name) DO NOTHING
+                RETURNING id
+            "#,
+            mgs_id,
+            name,
+            admin,
+            trusted,
+            squash_uid,
+            squash_gid
+        )
+        .fetch_optional(&mut transaction)
+        .await?
+        .ok_or_else(|| {
+            FieldError::new(
+                format!("Nodemap {} already exists on the MGS of {}", name, fs_name),
+                Value::null(),
+            )
+        })?
+        .id;
+
+        for x in ranges {
+            sqlx::query!(
+                r#"
+                    INSERT INTO nodemap_range (nodemap_id, start_nid, end_nid)
+                    VALUES ($1, $2, $3)
+                    ON CONFLICT DO NOTHING
+                "#,
+                id,
+                x.start_nid,
+                x.end_nid
+            )
+            .execute(&mut transaction)
+            .await?;
+        }
+
+        transaction.commit().await?;
+
+        // Do not keep a nodemap that could not be set on the MGS.
+        // Anything `lctl` set before failing shows up as unmanaged.
+        if let Err(e) = reconcile_fs(context, &fs_name).await {
+            sqlx::query!("DELETE FROM nodemap WHERE id = $1", id)
+                .execute(&context.pg_pool)
+                .await?;
+
+            return Err(e);
+        }
+
+        get_nodemap(&context.pg_pool, &fs_name, &name).await
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem of the nodemap"),
+        name(description = "The name of the nodemap"),
+        admin(description = "Do not squash root on clients of the nodemap"),
+        trusted(description = "Use client ids as is, without mapping"),
+        squash_uid(description = "The uid unmapped users are squashed to"),
+        squash_gid(description = "The gid unmapped groups are squashed to"),
+    ))]
+    /// Change the properties of a nodemap and set them on the MGS.
+    /// Properties that are not given are left as is.
+    async fn edit(
+        context: &Context,
+        fs_name: String,
+        name: String,
+        admin: Option<bool>,
+        trusted: Option<bool>,
+        squash_uid: Option<i32>,
+        squash_gid: Option<i32>,
+    ) -> juniper::FieldResult<Nodemap> {
+        let id = nodemap_id(&context.pg_pool, &fs_name, &name).await?;
+
+        sqlx::query!(
+            r#"
+                UPDATE nodemap
+                SET admin = COALESCE($2, admin),
+                    trusted = COALESCE($3, trusted),
+                    squash_uid = COALESCE($4, squash_uid),
+                    squash_gid = COALESCE($5, squash_gid)
+                WHERE id = $1
+            "#,
+            id,
+            admin,
+            trusted,
+            squash_uid,
+            squash_gid
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        reconcile_fs(context, &fs_name).await?;
+
+        get_nodemap(&context.pg_pool, &fs_name, &name).await
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem of the nodemap"),
+        name(description = "The name of the nodemap"),
+        range(description = "The client NID range, i.e. `192.168.1.[1-100]@tcp`"),
+    ))]
+    /// Add a client NID range to a nodemap and set it on the MGS
+    async fn add_range(
+        context: &Context,
+        fs_name: String,
+        name: String,
+        range: String,
+    ) -> juniper::FieldResult<Nodemap> {
+        let range = parse_range(&name, &range)?;
+        let id = nodemap_id(&context.pg_pool, &fs_name, &name).await?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO nodemap_range (nodemap_id, start_nid, end_nid)
+                VALUES ($1, $2, $3)
+                ON CONFLICT DO NOTHING
+            "#,
+            id,
+            range.start_nid,
+            range.end_nid
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        reconcile_fs(context, &fs_name).await?;
+
+        get_nodemap(&context.pg_pool, &fs_name, &name).await
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem of the nodemap"),
+        name(description = "The name of the nodemap"),
+        range(description = "The client NID range, i.e. `192.168.1.[1-100]@tcp`"),
+    ))]
+    /// Remove a client NID range from a nodemap and from the MGS
+    async fn remove_range(
+        context: &Context,
+        fs_name: String,
+        name: String,
+        range: String,
+    ) -> juniper::FieldResult<Nodemap> {
+        let range = parse_range(&name, &range)?;
+        let id = nodemap_id(&context.pg_pool, &fs_name, &name).await?;
+
+        sqlx::query!(
+            r#"
+                DELETE FROM nodemap_range
+                WHERE nodemap_id = $1 AND start_nid = $2 AND end_nid = $3
+                RETURNING id
+            "#,
+            id,
+            range.start_nid,
+            range.end_nid
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .ok_or_else(|| {
+            FieldError::new(
+                format!("Nodemap {} has no range {}", name, range),
+                Value::null(),
+            )
+        })?;
+
+        reconcile_fs(context, &fs_name).await?;
+
+        get_nodemap(&context.pg_pool, &fs_name, &name).await
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem of the nodemap"),
+        name(description = "The name of the nodemap"),
+        kind(description = "Whether to map a user or group id"),
+        client_id(description = "The id on the clients"),
+        fs_id(description = "The id on the filesystem"),
+    ))]
+    /// Map an id on the clients of a nodemap to an id on the filesystem.
+    /// An existing mapping of the client id is replaced.
+    async fn add_idmap(
+        context: &Context,
+        fs_name: String,
+        name: String,
+        kind: NodemapIdKind,
+        client_id: i32,
+        fs_id: i32,
+    ) -> juniper::FieldResult<Nodemap> {
+        let id = nodemap_id(&context.pg_pool, &fs_name, &name).await?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO nodemap_idmap (nodemap_id, kind, client_id, fs_id)
+                VALUES ($1, $2::text::nodemap_id_kind, $3, $4)
+                ON CONFLICT (nodemap_id, kind, client_id) DO UPDATE
+                SET fs_id = EXCLUDED.fs_id
+            "#,
+            id,
+            kind.to_string(),
+            client_id,
+            fs_id
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        reconcile_fs(context, &fs_name).await?;
+
+        get_nodemap(&context.pg_pool, &fs_name, &name).await
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem of the nodemap"),
+        name(description = "The name of the nodemap"),
+        kind(description = "Whether the mapping is of a user or group id"),
+        client_id(description = "The id on the clients"),
+    ))]
+    /// Remove an id mapping from a nodemap and from the MGS
+    async fn remove_idmap(
+        context: &Context,
+        fs_name: String,
+        name: String,
+        kind: NodemapIdKind,
+        client_id: i32,
+    ) -> juniper::FieldResult<Nodemap> {
+        let id = nodemap_id(&context.pg_pool, &fs_name, &name).await?;
+
+        sqlx::query!(
+            r#"
+                DELETE FROM nodemap_idmap
+                WHERE nodemap_id = $1 AND kind = $2::text::nodemap_id_kind AND client_id = $3
+                RETURNING id
+            "#,
+            id,
+            kind.to_string(),
+            client_id
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .ok_or_else(|| {
+            FieldError::new(
+                format!("Nodemap {} has no {} map of {}", name, kind, client_id),
+                Value::null(),
+            )
+        })?;
+
+        reconcile_fs(context, &fs_name).await?;
+
+        get_nodemap(&context.pg_pool, &fs_name, &name).await
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem of the nodemap"),
+        name(description = "The name of the nodemap"),
+    ))]
+    /// Remove a nodemap and delete it from the MGS.
+    /// The default nodemap is only unmanaged, as it cannot be deleted.
+    async fn remove(
+        context: &Context,
+        fs_name: String,
+        name: String,
+    ) -> juniper::FieldResult<bool> {
+        let id = nodemap_id(&context.pg_pool, &fs_name, &name).await?;
+
+        if name != DEFAULT_NODEMAP {
+            let fqdn = mgs_fqdn(&context.pg_pool, &fs_name).await?;
+            let actual = get_state(context, fqdn.clone()).await?;
+
+            if actual.nodemaps.iter().any(|x| x.name == name) {
+                change(context, fqdn, vec![NodemapChange::Del { name }]).await?;
+            }
+        }
+
+        sqlx::query!("DELETE FROM nodemap WHERE id = $1", id)
+            .execute(&context.pg_pool)
+            .await?;
+
+        Ok(true)
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem to activate nodemaps on"),
+        active(description = "Whether nodemaps should be enforced"),
+    ))]
+    /// Activate or deactivate nodemaps on the MGS of a filesystem.
+    /// This applies to all filesystems using the MGS.
+    async fn activate(
+        context: &Context,
+        fs_name: String,
+        active: bool,
+    ) -> juniper::FieldResult<bool> {
+        let mgs_id = fs_mgs_id(&context.pg_pool, &fs_name).await?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO nodemap_config (mgs_id, active)
+                VALUES ($1, $2)
+                ON CONFLICT (mgs_id) DO UPDATE
+                SET active = EXCLUDED.active
+            "#,
+            mgs_id,
+            active
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        reconcile_fs(context, &fs_name).await?;
+
+        Ok(true)
+    }
+    #[graphql(arguments(fs_name(description = "The filesystem to reconcile nodemaps of")))]
+    /// Make the nodemaps on the MGS of a filesystem match the desired nodemaps.
+    /// Returns the changes made.
+    async fn reconcile(context: &Context, fs_name: String) -> juniper::FieldResult<Vec<String>> {
+        let xs = reconcile_fs(context, &fs_name).await?;
+
+        Ok(xs.iter().map(|x| x.to_string()).collect())
+    }
+}
+
+/// Checks `name` can be used as a nodemap name
+fn validate_name(name: &str) -> juniper::FieldResult<()> {
+    if name.is_empty()
+        || name.len() > 16
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(FieldError::new(
+            format!("Invalid nodemap name {}", name),
+            Value::null(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn parse_range(name: &str, range: &str) -> juniper::FieldResult<NodemapRange> {
+    if name == DEFAULT_NODEMAP {
+        return Err(FieldError::new(
+            "The default nodemap cannot have ranges",
+            Value::null(),
+        ));
+    }
+
+    range.parse().map_err(|e| FieldError::new(e, Value::null()))
+}
+
+fn not_found(fs_name: &str, name: &str) -> FieldError {
+    FieldError::new(
+        format!("Nodemap {} not found on the MGS of {}", name, fs_name),
+        Value::null(),
+    )
+}
+
+/// Returns the id of the MGS used by `fs_name`
+async fn fs_mgs_id(pool: &PgPool, fs_name: &str) -> juniper::FieldResult<i32> {
+    let x = sqlx::query!(
+        "SELECT mgs_id FROM chroma_core_managedfilesystem WHERE name = $1 AND not_deleted = 't'",
+        fs_name
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| FieldError::new(format!("Filesystem {} not found", fs_name), Value::null()))?;
+
+    Ok(x.mgs_id)
+}
+
+async fn nodemap_id(pool: &PgPool, fs_name: &str, name: &str) -> juniper::FieldResult<i32> {
+    let mgs_id = fs_mgs_id(pool, fs_name).await?;
+
+    let x = sqlx::query!(
+        "SELECT id FROM nodemap WHERE mgs_id = $1 AND name = $2",
+        mgs_id,
+        name
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| not_found(fs_name, name))?;
+
+    Ok(x.id)
+}
+
+async fn get_nodemaps(pool: &PgPool, mgs_id: i32) -> Result<Vec<Nodemap>, sqlx::Error> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT id, name, admin, trusted, squash_uid, squash_gid
+            FROM nodemap
+            WHERE mgs_id = $1
+            ORDER BY name
+        "#,
+        mgs_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let ranges = sqlx::query!(
+        r#"
+            SELECT r.nodemap_id, r.start_nid, r.end_nid
+            FROM nodemap_range r
+            INNER JOIN nodemap n ON n.id = r.nodemap_id
+            WHERE n.mgs_id = $1
+            ORDER BY r.start_nid, r.end_nid
+        "#,
+        mgs_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let idmaps = sqlx::query!(
+        r#"
+            SELECT i.nodemap_id, i.kind AS "kind: NodemapIdKind", i.client_id, i.fs_id
+            FROM nodemap_idmap i
+            INNER JOIN nodemap n ON n.id = i.nodemap_id
+            WHERE n.mgs_id = $1
+            ORDER BY i.kind, i.client_id
+        "#,
+        mgs_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(xs
+        .into_iter()
+        .map(|x| Nodemap {
+            ranges: ranges
+                .iter()
+                .filter(|r| r.nodemap_id == x.id)
+                .map(|r| NodemapRange {
+                    start_nid: r.start_nid.clone(),
+                    end_nid: r.end_nid.clone(),
+                })
+                .collect(),
+            idmaps: idmaps
+                .iter()
+                .filter(|i| i.nodemap_id == x.id)
+                .map(|i| NodemapIdmap {
+                    kind: i.kind,
+                    client_id: i.client_id,
+                    fs_id: i.fs_id,
+                })
+                .collect(),
+            name: x.name,
+            admin: x.admin,
+            trusted: x.trusted,
+            squash_uid: x.squash_uid,
+            squash_gid: x.squash_gid,
+        })
+        .collect())
+}
+
+async fn get_nodemap(pool: &PgPool, fs_name: &str, name: &str) -> juniper::FieldResult<Nodemap> {
+    let mgs_id = fs_mgs_id(pool, fs_name).await?;
+
+    get_nodemaps(pool, mgs_id)
+        .await?
+        .into_iter()
+        .find(|x| x.name == name)
+        .ok_or_else(|| not_found(fs_name, name))
+}
+
+/// Returns whether nodemaps should be active on the MGS, if that is managed
+async fn get_active(pool: &PgPool, mgs_id: i32) -> Result<Option<bool>, sqlx::Error> {
+    let x = sqlx::query!(
+        "SELECT active FROM nodemap_config WHERE mgs_id = $1",
+        mgs_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(x.map(|x| x.active))
+}
+
+async fn mgs_fqdn(pool: &PgPool, fs_name: &str) -> juniper::FieldResult<String> {
+    active_mgs_host_fqdn(fs_name, pool).await?.ok_or_else(|| {
+        FieldError::new(
+            format!("No active MGS found for filesystem {}", fs_name),
+            Value::null(),
+        )
+    })
+}
+
+async fn get_state(context: &Context, fqdn: String) -> juniper::FieldResult<NodemapState> {
+    let x = context
+        .action_client
+        .invoke_rust_agent_expect_result(fqdn, "nodemap_get", (), None)
+        .await?
+        .map_err(|e| FieldError::new(e, Value::null()))?;
+
+    let x = serde_json::from_value(x)?;
+
+    Ok(x)
+}
+
+async fn change(
+    context: &Context,
+    fqdn: String,
+    xs: Vec<NodemapChange>,
+) -> juniper::FieldResult<()> {
+    context
+        .action_client
+        .invoke_rust_agent_expect_result(fqdn, "nodemap_change", xs, None)
+        .await?
+        .map_err(|e| FieldError::new(e, Value::null()))?;
+
+    Ok(())
+}
+
+/// Makes the nodemaps on the MGS of `fs_name` match the desired nodemaps of that MGS.
+/// Returns the changes made.
+async fn reconcile_fs(
+    context: &Context,
+    fs_name: &str,
+) -> juniper::FieldResult<Vec<NodemapChange>> {
+    let mgs_id = fs_mgs_id(&context.pg_pool, fs_name).await?;
+    let fqdn = mgs_fqdn(&context.pg_pool, fs_name).await?;
+
+    let desired = get_nodemaps(&context.pg_pool, mgs_id).await?;
+    let desired_active = get_active(&context.pg_pool, mgs_id).await?;
+    let actual = get_state(context, fqdn.clone()).await?;
+
+    let xs = nodemap_changes(&desired, desired_active, &actual);
+
+    if !xs.is_empty() {
+        change(context, fqdn, xs.clone()).await?;
+    }
+
+    Ok(xs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("tenant1").is_ok());
+        assert!(validate_name("lab-admins").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("tenant.1").is_err());
+        assert!(validate_name("a_very_long_nodemap_name").is_err());
+    }
+}
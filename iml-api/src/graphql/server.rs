@@ -0,0 +1,939 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    command::get_command,
+    error::ImlApiError,
+    graphql::{
+        dependent_job, get_banned_targets, get_fs_target_resources,
+        lnet::{check_non_negative, lnet_command},
+        run_jobs, Context, SendJob, TargetResource,
+    },
+    timer::{configure_scrub_timer, remove_scrub_timer},
+};
+use futures::TryStreamExt;
+use iml_manager_client::{get_client, get_influx};
+use iml_manager_env::get_influxdb_metrics_db;
+use iml_postgres::{
+    fqdn_by_host_id, host_id_by_fqdn, sqlx, sqlx::postgres::types::PgInterval, PgPool,
+};
+use iml_wire_types::{
+    device_history::{DeviceChangeKind, DeviceHistory},
+    graphql_duration::GraphQLDuration,
+    time::{ClusterSkew, HostOffset},
+    zpool::ScrubInterval,
+    Command, Fqdn, NetworkInterfaceRates,
+};
+use juniper::{FieldError, Value};
+use serde_json::json;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom as _,
+};
+
+pub(crate) struct ServerQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl ServerQuery {
+    /// List all zpool scrub intervals
+    async fn scrub_intervals(context: &Context) -> juniper::FieldResult<Vec<ScrubInterval>> {
+        let xs: Vec<ScrubInterval> = sqlx::query!(
+            r#"
+                SELECT si.id, h.fqdn, si.zpool, si.interval FROM zpool_scrub_interval si
+                INNER JOIN chroma_core_managedhost h ON h.id = si.host_id
+                ORDER BY h.fqdn, si.zpool
+            "#
+        )
+        .fetch(&context.pg_pool)
+        .map_ok(|x| ScrubInterval {
+            id: x.id,
+            fqdn: x.fqdn,
+            zpool: x.zpool,
+            interval: x.interval.into(),
+        })
+        .try_collect()
+        .await?;
+
+        Ok(xs)
+    }
+    #[graphql(arguments(
+        fqdn(description = "The host to list device changes for"),
+        device_id(description = "Only list changes to the device with this id"),
+        limit(description = "The maximum number of changes to return, newest first"),
+    ))]
+    /// List the recorded changes to devices on `fqdn`, newest first.
+    async fn device_history(
+        context: &Context,
+        fqdn: String,
+        device_id: Option<String>,
+        limit: Option<i32>,
+    ) -> juniper::FieldResult<Vec<DeviceHistory>> {
+        let xs = sqlx::query_as!(
+            DeviceHistory,
+            r#"
+                SELECT
+                    id,
+                    fqdn,
+                    device_id,
+                    device_type,
+                    change AS "change: DeviceChangeKind",
+                    old_value,
+                    new_value,
+                    time
+                FROM device_history
+                WHERE fqdn = $1
+                AND ($2::text IS NULL OR device_id = $2)
+                ORDER BY time DESC, id DESC
+                LIMIT $3
+            "#,
+            fqdn,
+            device_id,
+            limit.map(i64::from),
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        Ok(xs)
+    }
+    #[graphql(arguments(fqdn(description = "The host to export the LNet configuration of")))]
+    /// Exports the running LNet configuration of `fqdn` as `lnetctl` YAML.
+    async fn lnet_config(context: &Context, fqdn: String) -> juniper::FieldResult<String> {
+        get_host_id(&context.pg_pool, &fqdn).await?;
+
+        let x = context
+            .action_client
+            .invoke_rust_agent_expect_result(fqdn, "lnet_export", (), None)
+            .await?
+            .map_err(|e| FieldError::new(e, Value::null()))?;
+
+        let x = serde_json::from_value(x)?;
+
+        Ok(x)
+    }
+
+    #[graphql(arguments(fqdn(description = "The host to list network interface rates for")))]
+    /// List the network interfaces of `fqdn` with their latest rx / tx rates per second.
+    async fn network_interface_rates(
+        context: &Context,
+        fqdn: String,
+    ) -> juniper::FieldResult<Vec<NetworkInterfaceRates>> {
+        let host_id = get_host_id(&context.pg_pool, &fqdn).await?;
+
+        let query = iml_influx::network::query(host_id, "5m");
+
+        let resp = get_influx::<iml_influx::network::InfluxResponse>(
+            get_client()?,
+            &get_influxdb_metrics_db(),
+            &query,
+        )
+        .await?;
+        let mut rates = iml_influx::network::Response::from(resp);
+
+        let xs = sqlx::query!(
+            "SELECT name, mtu, speed FROM network_interface WHERE host_id = $1 ORDER BY name",
+            host_id
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| {
+            let r = rates.remove(&x.name).unwrap_or_default();
+
+            NetworkInterfaceRates {
+                interface: x.name,
+                mtu: x.mtu,
+                speed: x.speed.and_then(|x| i32::try_from(x).ok()),
+                rx_bytes: r.rx_bytes,
+                tx_bytes: r.tx_bytes,
+                rx_packets: r.rx_packets,
+                tx_packets: r.tx_packets,
+                rx_errors: r.rx_errors,
+                tx_errors: r.tx_errors,
+                rx_drops: r.rx_drops,
+                tx_drops: r.tx_drops,
+            }
+        })
+        .collect();
+
+        Ok(xs)
+    }
+
+    /// The latest clock offset of each Lustre server and the largest skew between any two of them.
+    /// Servers that have not reported an offset in the last 5 minutes are left out.
+    async fn time_skew(context: &Context) -> juniper::FieldResult<ClusterSkew> {
+        let resp = get_influx::<iml_influx::ntp::InfluxResponse>(
+            get_client()?,
+            &get_influxdb_metrics_db(),
+            &iml_influx::ntp::query("5m"),
+        )
+        .await?;
+        let offsets = iml_influx::ntp::Response::from(resp);
+
+        let xs = sqlx::query!(
+            r#"
+                SELECT h.id, h.fqdn FROM chroma_core_managedhost h
+                INNER JOIN chroma_core_serverprofile sp ON sp.name = h.server_profile_id
+                WHERE h.not_deleted = 't' AND sp.worker = 'f'
+                ORDER BY h.fqdn
+            "#
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .filter_map(|x| {
+            offsets.get(&x.id).map(|offset| HostOffset {
+                fqdn: x.fqdn,
+                offset: *offset,
+            })
+        })
+        .collect();
+
+        Ok(ClusterSkew::new(xs))
+    }
+
+    #[graphql(arguments(fqdn(description = "The host to list target resources of")))]
+    /// List the target resources running on `fqdn`, along with the host each would be failed over to
+    async fn failover_resources(
+        context: &Context,
+        fqdn: String,
+    ) -> juniper::FieldResult<Vec<MovedResource>> {
+        let (host_id, _) = get_host_node(&context.pg_pool, &fqdn).await?;
+
+        let xs = failover_resources(&context.pg_pool, host_id).await?;
+
+        Ok(xs)
+    }
+}
+
+pub(crate) struct ServerMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl ServerMutation {
+    #[graphql(arguments(fqdn(description = "The host to move target resources away from")))]
+    /// Moves every target resource running on `fqdn` to a failover host in parallel.
+    /// Each resource is banned from `fqdn` until the host is failed back.
+    /// Returns a `Command` to track progress.
+    async fn failover(context: &Context, fqdn: String) -> juniper::FieldResult<Command> {
+        let pool = &context.pg_pool;
+
+        let (host_id, node_name) = get_host_node(pool, &fqdn).await?;
+
+        let jobs: Vec<_> = failover_resources(pool, host_id)
+            .await?
+            .into_iter()
+            .map(|x| resource_job("FailoverHostResourceJob", x.peer, x.ha_label, &node_name))
+            .collect();
+
+        if jobs.is_empty() {
+            return Err(FieldError::new(
+                format!("No target resources are running on {}", fqdn),
+                Value::null(),
+            ));
+        }
+
+        let command_id = run_jobs(
+            format!("Failing over host {}", fqdn),
+            jobs,
+            &context.rabbit_pool,
+        )
+        .await?;
+
+        let command = get_command(pool, command_id).await?;
+
+        Ok(command)
+    }
+    #[graphql(arguments(fqdn(description = "The host to move target resources back to")))]
+    /// Clears bans placed on `fqdn` and moves target resources that prefer `fqdn` back to it.
+    /// Only resources running on another host are moved,
+    /// resources that are stopped or disabled only have their ban cleared.
+    /// Returns a `Command` to track progress.
+    async fn failback(context: &Context, fqdn: String) -> juniper::FieldResult<Command> {
+        let pool = &context.pg_pool;
+
+        let (host_id, node_name) = get_host_node(pool, &fqdn).await?;
+
+        let running = get_running_resources(pool).await?;
+
+        let banned: HashSet<(String, i32)> = get_banned_targets(pool)
+            .await?
+            .into_iter()
+            .filter(|x| x.host_id == host_id)
+            .map(|x| (x.resource, x.cluster_id))
+            .collect();
+
+        let resources = get_fs_target_resources(pool, None).await?;
+
+        let mut seen = HashSet::new();
+        let mut jobs = vec![];
+
+        for r in resources {
+            let key = (r.resource_id.clone(), r.cluster_id);
+
+            let is_banned = banned.contains(&key);
+
+            let prefers_host = r.preferred_hosts.first() == Some(&host_id);
+
+            let running_elsewhere = matches!(running.get(&key), Some(id) if *id != host_id);
+
+            let class_name = if running_elsewhere && (is_banned || prefers_host) {
+                "FailbackHostResourceJob"
+            } else if is_banned {
+                "ClearHostResourceBanJob"
+            } else {
+                continue;
+            };
+
+            if !seen.insert(key) {
+                continue;
+            }
+
+            jobs.push(resource_job(
+                class_name,
+                fqdn.clone(),
+                r.resource_id,
+                &node_name,
+            ));
+        }
+
+        if jobs.is_empty() {
+            return Err(FieldError::new(
+                format!("No target resources need to be failed back to {}", fqdn),
+                Value::null(),
+            ));
+        }
+
+        let command_id = run_jobs(
+            format!("Failing back host {}", fqdn),
+            jobs,
+            &context.rabbit_pool,
+        )
+        .await?;
+
+        let command = get_command(pool, command_id).await?;
+
+        Ok(command)
+    }
+    #[graphql(arguments(
+        hosts(description = "The hosts to upgrade, one at a time in the given order"),
+        reboot(
+            description = "Reboot each host instead of reloading the Lustre modules. Defaults to false"
+        ),
+    ))]
+    /// Upgrades Lustre on `hosts` one HA pair at a time, and one host at a time within a pair,
+    /// keeping targets available on the HA peers.
+    /// For each host, target resources are failed over, packages are updated,
+    /// Lustre is restarted and its version verified, then the resources are failed back.
+    /// The resources to move are found when each host is failed over.
+    /// The upgrade pauses, cancelling the remaining hosts, if an alert raised
+    /// on the pair of a host since the upgrade started does not clear.
+    /// Returns a `Command` to track progress.
+    async fn upgrade(
+        context: &Context,
+        hosts: Vec<String>,
+        reboot: Option<bool>,
+    ) -> juniper::FieldResult<Command> {
+        let pool = &context.pg_pool;
+
+        let reboot = reboot.unwrap_or(false);
+
+        let mut seen = HashSet::new();
+        let hosts: Vec<_> = hosts
+            .into_iter()
+            .filter(|x| seen.insert(x.clone()))
+            .collect();
+
+        if hosts.is_empty() {
+            return Err(FieldError::new(
+                "At least one host is required",
+                Value::null(),
+            ));
+        }
+
+        let resources = get_fs_target_resources(pool, None).await?;
+
+        let mut xs = vec![];
+
+        for fqdn in &hosts {
+            let (host_id, node_name) = get_host_node(pool, fqdn).await?;
+
+            xs.push(UpgradeHost {
+                fqdn: fqdn.clone(),
+                host_id,
+                node_name,
+            });
+        }
+
+        let jobs = upgrade_jobs(&ha_pairs(xs, &resources), reboot);
+
+        let command_id = run_jobs(
+            format!("Upgrading Lustre on {}", hosts.join(", ")),
+            jobs,
+            &context.rabbit_pool,
+        )
+        .await?;
+
+        let command = get_command(pool, command_id).await?;
+
+        Ok(command)
+    }
+    #[graphql(arguments(
+        fqdn(description = "The host the zpool is imported on"),
+        zpool(description = "The zpool to scrub"),
+    ))]
+    /// Starts a scrub of `zpool` on `fqdn`.
+    /// Returns a `Command` to track progress.
+    async fn start_scrub(
+        context: &Context,
+        fqdn: String,
+        zpool: String,
+    ) -> juniper::FieldResult<Command> {
+        scrub_command(
+            context,
+            "StartZpoolScrubJob",
+            format!("Starting scrub of zpool {} on {}", zpool, fqdn),
+            fqdn,
+            zpool,
+        )
+        .await
+    }
+    #[graphql(arguments(
+        fqdn(description = "The host the zpool is imported on"),
+        zpool(description = "The zpool to stop scrubbing"),
+    ))]
+    /// Stops a running scrub of `zpool` on `fqdn`.
+    /// Returns a `Command` to track progress.
+    async fn stop_scrub(
+        context: &Context,
+        fqdn: String,
+        zpool: String,
+    ) -> juniper::FieldResult<Command> {
+        scrub_command(
+            context,
+            "StopZpoolScrubJob",
+            format!("Stopping scrub of zpool {} on {}", zpool, fqdn),
+            fqdn,
+            zpool,
+        )
+        .await
+    }
+    #[graphql(arguments(
+        fqdn(description = "The host the zpool is imported on"),
+        zpool(description = "The zpool to scrub"),
+        interval(description = "How often the zpool should be scrubbed"),
+    ))]
+    /// Creates a new scrub interval, or replaces the interval of an existing one.
+    /// A scrub of `zpool` will be started each time `interval` expires.
+    async fn create_scrub_interval(
+        context: &Context,
+        fqdn: String,
+        zpool: String,
+        interval: GraphQLDuration,
+    ) -> juniper::FieldResult<bool> {
+        let host_id = host_id_by_fqdn(&Fqdn(fqdn.clone()), &context.pg_pool)
+            .await?
+            .ok_or_else(|| FieldError::new(format!("Host {} not found", fqdn), Value::null()))?;
+
+        let id = sqlx::query!(
+            r#"
+                INSERT INTO zpool_scrub_interval (host_id, zpool, interval)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (host_id, zpool)
+                DO UPDATE SET interval = EXCLUDED.interval
+                RETURNING id
+            "#,
+            host_id,
+            zpool,
+            PgInterval::try_from(interval.0)?,
+        )
+        .fetch_one(&context.pg_pool)
+        .await?
+        .id;
+
+        configure_scrub_timer(id, &fqdn, &zpool, interval.0).await?;
+
+        Ok(true)
+    }
+    /// Removes an existing scrub interval.
+    /// This will also cancel any outstanding scrubs scheduled by this rule.
+    #[graphql(arguments(id(description = "The scrub interval id"),))]
+    async fn remove_scrub_interval(context: &Context, id: i32) -> juniper::FieldResult<bool> {
+        sqlx::query!("DELETE FROM zpool_scrub_interval WHERE id=$1", id)
+            .execute(&context.pg_pool)
+            .await?;
+
+        remove_scrub_timer(id).await?;
+
+        Ok(true)
+    }
+    #[graphql(arguments(
+        fqdn(description = "The host to configure"),
+        net(description = "The LNet network, i.e. `tcp0` or `o2ib1`"),
+        interfaces(description = "The interfaces to configure `net` on"),
+        peer_credits(description = "The peer credits of the network"),
+        credits(description = "The credits of the network"),
+    ))]
+    /// Adds LNet network `net` on `interfaces` of `fqdn` and persists it to `/etc/lnet.conf`.
+    /// Returns a `Command` to track progress.
+    async fn add_lnet_network(
+        context: &Context,
+        fqdn: String,
+        net: String,
+        interfaces: Vec<String>,
+        peer_credits: Option<i32>,
+        credits: Option<i32>,
+    ) -> juniper::FieldResult<Command> {
+        if interfaces.is_empty() {
+            return Err(FieldError::new(
+                "At least one interface is required",
+                Value::null(),
+            ));
+        }
+
+        check_non_negative("peer_credits", peer_credits)?;
+        check_non_negative("credits", credits)?;
+
+        lnet_command(
+            context,
+            "AddLNetNetworkJob",
+            format!("Adding LNet network {} on {}", net, fqdn),
+            fqdn,
+            vec![
+                ("net", json!(net)),
+                ("interfaces", json!(interfaces.join(","))),
+                ("peer_credits", json!(peer_credits)),
+                ("credits", json!(credits)),
+            ],
+        )
+        .await
+    }
+    #[graphql(arguments(
+        fqdn(description = "The host to configure"),
+        net(description = "The LNet network to remove"),
+        interfaces(description = "Only remove `net` from these interfaces"),
+    ))]
+    /// Removes LNet network `net` from `fqdn` and persists the change to `/etc/lnet.conf`.
+    /// Returns a `Command` to track progress.
+    async fn remove_lnet_network(
+        context: &Context,
+        fqdn: String,
+        net: String,
+        interfaces: Option<Vec<String>>,
+    ) -> juniper::FieldResult<Command> {
+        lnet_command(
+            context,
+            "RemoveLNetNetworkJob",
+            format!("Removing LNet network {} from {}", net, fqdn),
+            fqdn,
+            vec![
+                ("net", json!(net)),
+                (
+                    "interfaces",
+                    json!(interfaces.unwrap_or_default().join(",")),
+                ),
+            ],
+        )
+        .await
+    }
+    #[graphql(arguments(
+        fqdn(description = "The host to configure"),
+        net(description = "The remote LNet network"),
+        gateway(description = "The NID of the router, i.e. `10.0.0.1@tcp0`"),
+        hop(description = "The number of hops to the remote network"),
+        priority(description = "The priority of the route"),
+    ))]
+    /// Adds a route to `net` via `gateway` on `fqdn` and persists it to `/etc/lnet.conf`.
+    /// Returns a `Command` to track progress.
+    async fn add_lnet_route(
+        context: &Context,
+        fqdn: String,
+        net: String,
+        gateway: String,
+        hop: Option<i32>,
+        priority: Option<i32>,
+    ) -> juniper::FieldResult<Command> {
+        check_non_negative("hop", hop)?;
+        check_non_negative("priority", priority)?;
+
+        lnet_command(
+            context,
+            "AddLNetRouteJob",
+            format!("Adding LNet route to {} via {} on {}", net, gateway, fqdn),
+            fqdn,
+            vec![
+                ("net", json!(net)),
+                ("gateway", json!(gateway)),
+                ("hop", json!(hop)),
+                ("priority", json!(priority)),
+            ],
+        )
+        .await
+    }
+    #[graphql(arguments(
+        fqdn(description = "The host to configure"),
+        net(description = "The remote LNet network"),
+        gateway(description = "The NID of the router"),
+    ))]
+    /// Removes the route to `net` via `gateway` from `fqdn` and persists the change to `/etc/lnet.conf`.
+    /// Returns a `Command` to track progress.
+    async fn remove_lnet_route(
+        context: &Context,
+        fqdn: String,
+        net: String,
+        gateway: String,
+    ) -> juniper::FieldResult<Command> {
+        lnet_command(
+            context,
+            "RemoveLNetRouteJob",
+            format!(
+                "Removing LNet route to {} via {} from {}",
+                net, gateway, fqdn
+            ),
+            fqdn,
+            vec![("net", json!(net)), ("gateway", json!(gateway))],
+        )
+        .await
+    }
+    #[graphql(arguments(
+        fqdn(description = "The host to configure"),
+        config(description = "The LNet configuration, as produced by `lnetctl export`"),
+    ))]
+    /// Applies an LNet YAML configuration on `fqdn` and persists it to `/etc/lnet.conf`.
+    /// Returns a `Command` to track progress.
+    async fn import_lnet_config(
+        context: &Context,
+        fqdn: String,
+        config: String,
+    ) -> juniper::FieldResult<Command> {
+        lnet_command(
+            context,
+            "ImportLNetConfigJob",
+            format!("Importing LNet configuration on {}", fqdn),
+            fqdn,
+            vec![("config", json!(config))],
+        )
+        .await
+    }
+}
+
+pub(crate) async fn get_host_id(pool: &PgPool, fqdn: &str) -> Result<i32, FieldError> {
+    host_id_by_fqdn(&Fqdn(fqdn.to_string()), pool)
+        .await?
+        .ok_or_else(|| FieldError::new(format!("Host {} not found", fqdn), Value::null()))
+}
+
+async fn scrub_command(
+    context: &Context,
+    class_name: &str,
+    msg: String,
+    fqdn: String,
+    zpool: String,
+) -> juniper::FieldResult<Command> {
+    host_id_by_fqdn(&Fqdn(fqdn.clone()), &context.pg_pool)
+        .await?
+        .ok_or_else(|| FieldError::new(format!("Host {} not found", fqdn), Value::null()))?;
+
+    let job = SendJob {
+        class_name,
+        args: vec![("fqdn".to_string(), fqdn), ("zpool".to_string(), zpool)]
+            .into_iter()
+            .collect::<HashMap<String, String>>(),
+    };
+
+    let command_id = run_jobs(msg, vec![job], &context.rabbit_pool).await?;
+
+    let command = get_command(&context.pg_pool, command_id).await?;
+
+    Ok(command)
+}
+
+fn resource_job<'a>(
+    class_name: &'a str,
+    fqdn: String,
+    ha_label: String,
+    node_name: &str,
+) -> SendJob<'a, HashMap<String, String>> {
+    SendJob {
+        class_name,
+        args: vec![
+            ("fqdn".into(), fqdn),
+            ("ha_label".into(), ha_label),
+            ("node_name".into(), node_name.to_string()),
+        ]
+        .into_iter()
+        .collect(),
+    }
+}
+
+/// A target resource running on a host, and the host it is failed over to
+#[derive(Debug, juniper::GraphQLObject)]
+struct MovedResource {
+    ha_label: String,
+    /// The host the resource is moved to
+    peer: String,
+    /// Whether the resource prefers the host it is moved away from,
+    /// so it is failed back once that host is upgraded
+    failback: bool,
+}
+
+#[derive(Debug)]
+struct UpgradeHost {
+    fqdn: String,
+    host_id: i32,
+    node_name: String,
+}
+
+/// Groups hosts into the HA pairs they form through the target resources they share.
+/// Pairs and the hosts within them keep the order the hosts were given in.
+fn ha_pairs(xs: Vec<UpgradeHost>, resources: &[TargetResource]) -> Vec<Vec<UpgradeHost>> {
+    let mut pairs: Vec<Vec<UpgradeHost>> = vec![];
+
+    for x in xs {
+        let peers: HashSet<_> = resources
+            .iter()
+            .filter(|r| r.cluster_hosts.contains(&x.host_id))
+            .flat_map(|r| r.cluster_hosts.iter().copied())
+            .collect();
+
+        match pairs
+            .iter_mut()
+            .find(|pair| pair.iter().any(|y| peers.contains(&y.host_id)))
+        {
+            Some(pair) => pair.push(x),
+            None => pairs.push(vec![x]),
+        }
+    }
+
+    pairs
+}
+
+/// The jobs upgrading `pairs` one host at a time.
+/// Each job starts once all jobs before it have completed,
+/// and the health of the pair is checked after each host.
+fn upgrade_jobs(
+    pairs: &[Vec<UpgradeHost>],
+    reboot: bool,
+) -> Vec<SendJob<'static, HashMap<String, serde_json::Value>>> {
+    let mut jobs = vec![];
+
+    for pair in pairs {
+        let hosts: Vec<_> = pair.iter().map(|x| &x.fqdn).collect();
+
+        for x in pair {
+            for (class_name, args) in vec![
+                (
+                    "UpgradeFailoverJob",
+                    vec![("fqdn", json!(x.fqdn)), ("node_name", json!(x.node_name))],
+                ),
+                ("UpdateJob", vec![("host_id", json!(x.host_id))]),
+                (
+                    "RestartLustreJob",
+                    vec![("fqdn", json!(x.fqdn)), ("reboot", json!(reboot))],
+                ),
+                ("VerifyLustreVersionJob", vec![("fqdn", json!(x.fqdn))]),
+                (
+                    "UpgradeFailbackJob",
+                    vec![("fqdn", json!(x.fqdn)), ("node_name", json!(x.node_name))],
+                ),
+                ("CheckUpgradeHealthJob", vec![("hosts", json!(hosts))]),
+            ] {
+                let deps: Vec<_> = (0..jobs.len()).collect();
+
+                jobs.push(dependent_job(class_name, args, &deps));
+            }
+        }
+    }
+
+    jobs
+}
+
+/// The target resources running on a host, along with the host each is failed over to
+async fn failover_resources(pool: &PgPool, host_id: i32) -> Result<Vec<MovedResource>, FieldError> {
+    let active = get_active_resources(pool, host_id).await?;
+
+    let resources = get_fs_target_resources(pool, None).await?;
+
+    let mut seen = HashSet::new();
+    let mut xs = vec![];
+
+    for r in resources {
+        let key = (r.resource_id.clone(), r.cluster_id);
+
+        if !active.contains(&key) || !seen.insert(key) {
+            continue;
+        }
+
+        let peer = r
+            .preferred_hosts
+            .iter()
+            .chain(r.cluster_hosts.iter())
+            .find(|id| **id != host_id)
+            .ok_or_else(|| {
+                FieldError::new(
+                    format!("Target {} has no failover host available", r.name),
+                    Value::null(),
+                )
+            })?;
+
+        xs.push(MovedResource {
+            peer: fqdn_by_host_id(pool, *peer).await?,
+            failback: r.preferred_hosts.first() == Some(&host_id),
+            ha_label: r.resource_id,
+        });
+    }
+
+    Ok(xs)
+}
+
+/// Given a host fqdn, find its id and corosync node name
+async fn get_host_node(pool: &PgPool, fqdn: &str) -> Result<(i32, String), FieldError> {
+    let host_id = host_id_by_fqdn(&Fqdn(fqdn.to_string()), pool)
+        .await?
+        .ok_or_else(|| FieldError::new(format!("Host {} not found", fqdn), Value::null()))?;
+
+    let node_name = sqlx::query!(
+        r#"
+            SELECT (nmh.corosync_node_id).name AS "name!" FROM corosync_node_managed_host nmh
+            WHERE host_id = $1
+        "#,
+        host_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|x| x.name)
+    .ok_or_else(|| {
+        FieldError::new(
+            format!("Host {} is not a member of a corosync cluster", fqdn),
+            Value::null(),
+        )
+    })?;
+
+    Ok((host_id, node_name))
+}
+
+/// The target resources currently active on the given host
+async fn get_active_resources(
+    pool: &PgPool,
+    host_id: i32,
+) -> Result<HashSet<(String, i32)>, ImlApiError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT r.name, r.cluster_id FROM corosync_resource r
+            INNER JOIN corosync_node_managed_host nh ON nh.cluster_id = r.cluster_id
+            AND (nh.corosync_node_id).name = (r.active_node).name
+            WHERE nh.host_id = $1
+            AND r.mount_point IS NOT NULL
+        "#,
+        host_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| (x.name, x.cluster_id))
+    .collect();
+
+    Ok(xs)
+}
+
+/// Target resources that are running, along with the host each is running on.
+async fn get_running_resources(pool: &PgPool) -> Result<HashMap<(String, i32), i32>, ImlApiError> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT r.name, r.cluster_id, nh.host_id FROM corosync_resource r
+            INNER JOIN corosync_node_managed_host nh ON nh.cluster_id = r.cluster_id
+            AND (nh.corosync_node_id).name = (r.active_node).name
+            WHERE r.active = true
+            AND r.mount_point IS NOT NULL
+        "#
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| ((x.name, x.cluster_id), x.host_id))
+    .collect();
+
+    Ok(xs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resource(id: &str, cluster_hosts: Vec<i32>) -> TargetResource {
+        TargetResource {
+            cluster_id: 1,
+            fs_names: vec!["fs".into()],
+            uuid: id.into(),
+            name: id.into(),
+            resource_id: id.into(),
+            state: "mounted".into(),
+            preferred_hosts: cluster_hosts.clone(),
+            cluster_hosts,
+        }
+    }
+
+    fn host(host_id: i32) -> UpgradeHost {
+        UpgradeHost {
+            fqdn: format!("oss{}", host_id),
+            host_id,
+            node_name: format!("oss{}", host_id),
+        }
+    }
+
+    fn deps(job: &SendJob<HashMap<String, serde_json::Value>>) -> Vec<usize> {
+        job.args
+            .get("depends_on_job_range")
+            .map(|x| serde_json::from_value(x.clone()).unwrap())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_ha_pairs() {
+        let resources = vec![
+            resource("fs-OST0000", vec![1, 2]),
+            resource("fs-OST0001", vec![2, 1]),
+            resource("fs-OST0002", vec![3, 4]),
+        ];
+
+        let xs = vec![host(3), host(1), host(5), host(4), host(2)];
+
+        let pairs: Vec<Vec<_>> = ha_pairs(xs, &resources)
+            .iter()
+            .map(|pair| pair.iter().map(|x| x.host_id).collect())
+            .collect();
+
+        assert_eq!(pairs, vec![vec![3, 4], vec![1, 2], vec![5]]);
+    }
+
+    #[test]
+    fn test_upgrade_jobs() {
+        let pairs = vec![vec![host(1), host(2)], vec![host(3)]];
+
+        let jobs = upgrade_jobs(&pairs, false);
+
+        let class_names: Vec<_> = jobs.iter().map(|x| x.class_name).collect();
+
+        let host_jobs = vec![
+            "UpgradeFailoverJob",
+            "UpdateJob",
+            "RestartLustreJob",
+            "VerifyLustreVersionJob",
+            "UpgradeFailbackJob",
+            "CheckUpgradeHealthJob",
+        ];
+
+        assert_eq!(class_names, host_jobs.repeat(3));
+
+        for (i, job) in jobs.iter().enumerate() {
+            assert_eq!(deps(job), (0..i).collect::<Vec<_>>());
+        }
+
+        assert_eq!(jobs[0].args["fqdn"], json!("oss1"));
+        assert_eq!(jobs[0].args["node_name"], json!("oss1"));
+        assert_eq!(jobs[5].args["hosts"], json!(["oss1", "oss2"]));
+        assert_eq!(jobs[6].args["fqdn"], json!("oss2"));
+        assert_eq!(jobs[10].args["fqdn"], json!("oss2"));
+        assert_eq!(jobs[11].args["hosts"], json!(["oss1", "oss2"]));
+        assert_eq!(jobs[17].args["hosts"], json!(["oss3"]));
+    }
+}
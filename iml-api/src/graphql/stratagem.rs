@@ -5,7 +5,9 @@
 use crate::{
     command::get_command,
     error::ImlApiError,
-    graphql::{fs_id_by_name, insert_fidlist, insert_task, Context, SendJob},
+    graphql::{
+        create_task_job, fs_id_by_name, insert_fidlist, insert_task, run_jobs, Context, SendJob,
+    },
 };
 use futures::{
     future::{self, try_join_all},
@@ -92,95 +94,22 @@ impl StratagemMutation {
         expression: String,
         action: String,
     ) -> juniper::FieldResult<Command> {
-        let uuid = Uuid::new_v4().to_hyphenated().to_string();
-
-        let fs_id = fs_id_by_name(&context.pg_pool, &fsname).await?;
-
-        let task = insert_task(
-            &format!("{}-filesync-filesync", uuid),
-            "created",
-            false,
-            false,
-            &["stratagem.filesync".into()],
-            serde_json::json!({
-                "remote": remote,
-                "expression": expression,
-                "action": action,
-            }),
-            fs_id,
-            &context.pg_pool,
-        )
-        .await?;
-
-        let mut jobs: Vec<SendJob<HashMap<String, serde_json::Value>>> = vec![SendJob {
-            class_name: "CreateTaskJob",
-            args: vec![("task_id".into(), serde_json::json!(task.id))]
-                .into_iter()
-                .collect(),
-        }];
-
-        let job_range: Vec<_> = (0..jobs.len()).collect();
-
-        let xs = get_target_hosts_by_fsname(&fsname, &context.pg_pool).await?;
-
-        for x in xs {
-            let path = match x.dev_path {
-                Some(x) => x,
-                None => continue,
-            };
-
-            let cfg = stratagem::StratagemConfig {
-                flist_type: "none".into(),
-                summarize_size: true,
-                device: stratagem::StratagemDevice {
-                    path,
-                    groups: vec!["filesync".into()],
-                },
-                groups: vec![stratagem::StratagemGroup {
-                    name: "filesync".into(),
-                    rules: vec![stratagem::StratagemRule {
-                        action: "LAT_SHELL_CMD_FID".into(),
-                        expression: expression.clone(),
-                        argument: "filesync".into(),
-                        counter_name: Some("filesync".into()),
-                    }],
-                }],
-            };
-
-            jobs.push(SendJob {
-                class_name: "ScanMdtJob",
-                args: vec![
-                    ("fqdn".into(), serde_json::to_value(&x.fqdn)?),
-                    ("uuid".into(), serde_json::to_value(&uuid)?),
-                    ("fsname".into(), serde_json::to_value(&fsname)?),
-                    ("config".into(), serde_json::to_value(cfg)?),
-                    (
-                        "depends_on_job_range".into(),
-                        serde_json::to_value(&job_range)?,
-                    ),
-                ]
-                .into_iter()
-                .collect(),
-            })
-        }
-
-        let kwargs: HashMap<String, String> =
-            vec![("message".into(), "Stratagem: Filesync".into())]
-                .into_iter()
-                .collect();
+        let args = serde_json::json!({
+            "remote": remote,
+            "expression": expression,
+            "action": action,
+        });
 
-        let command_id: i32 = iml_job_scheduler_rpc::call(
-            &context.rabbit_pool.get().await?,
-            "run_jobs",
-            vec![jobs],
-            Some(kwargs),
+        scan_task(
+            context,
+            &fsname,
+            "filesync",
+            "stratagem.filesync",
+            args,
+            &expression,
+            "Stratagem: Filesync",
         )
-        .map_err(ImlApiError::ImlJobSchedulerRpcError)
-        .await?;
-
-        let command = get_command(&context.pg_pool, command_id).await?;
-
-        Ok(command)
+        .await
     }
     async fn run_cloudsync(
         context: &Context,
@@ -189,95 +118,22 @@ impl StratagemMutation {
         expression: String,
         action: String,
     ) -> juniper::FieldResult<Command> {
-        let uuid = Uuid::new_v4().to_hyphenated().to_string();
-
-        let fs_id = fs_id_by_name(&context.pg_pool, &fsname).await?;
-
-        let task = insert_task(
-            &format!("{}-cloudsync-cloudsync", uuid),
-            "created",
-            false,
-            false,
-            &["stratagem.cloudsync".into()],
-            serde_json::json!({
-                "remote": remote,
-                "expression": expression,
-                "action": action,
-            }),
-            fs_id,
-            &context.pg_pool,
-        )
-        .await?;
-
-        let mut jobs: Vec<SendJob<HashMap<String, serde_json::Value>>> = vec![SendJob {
-            class_name: "CreateTaskJob",
-            args: vec![("task_id".into(), serde_json::json!(task.id))]
-                .into_iter()
-                .collect(),
-        }];
-
-        let job_range: Vec<_> = (0..jobs.len()).collect();
-
-        let xs = get_target_hosts_by_fsname(&fsname, &context.pg_pool).await?;
-
-        for x in xs {
-            let path = match x.dev_path {
-                Some(x) => x,
-                None => continue,
-            };
-
-            let cfg = stratagem::StratagemConfig {
-                flist_type: "none".into(),
-                summarize_size: true,
-                device: stratagem::StratagemDevice {
-                    path,
-                    groups: vec!["cloudsync".into()],
-                },
-                groups: vec![stratagem::StratagemGroup {
-                    name: "cloudsync".into(),
-                    rules: vec![stratagem::StratagemRule {
-                        action: "LAT_SHELL_CMD_FID".into(),
-                        expression: expression.clone(),
-                        argument: "cloudsync".into(),
-                        counter_name: Some("cloudsync".into()),
-                    }],
-                }],
-            };
-
-            jobs.push(SendJob {
-                class_name: "ScanMdtJob",
-                args: vec![
-                    ("fqdn".into(), serde_json::to_value(&x.fqdn)?),
-                    ("uuid".into(), serde_json::to_value(&uuid)?),
-                    ("fsname".into(), serde_json::to_value(&fsname)?),
-                    ("config".into(), serde_json::to_value(cfg)?),
-                    (
-                        "depends_on_job_range".into(),
-                        serde_json::to_value(&job_range)?,
-                    ),
-                ]
-                .into_iter()
-                .collect(),
-            })
-        }
-
-        let kwargs: HashMap<String, String> =
-            vec![("message".into(), "Stratagem: Cloudsync".into())]
-                .into_iter()
-                .collect();
+        let args = serde_json::json!({
+            "remote": remote,
+            "expression": expression,
+            "action": action,
+        });
 
-        let command_id: i32 = iml_job_scheduler_rpc::call(
-            &context.rabbit_pool.get().await?,
-            "run_jobs",
-            vec![jobs],
-            Some(kwargs),
+        scan_task(
+            context,
+            &fsname,
+            "cloudsync",
+            "stratagem.cloudsync",
+            args,
+            &expression,
+            "Stratagem: Cloudsync",
         )
-        .map_err(ImlApiError::ImlJobSchedulerRpcError)
-        .await?;
-
-        let command = get_command(&context.pg_pool, command_id).await?;
-
-        Ok(command)
+        .await
     }
     async fn run_fast_file_scan(
         context: &Context,
@@ -667,6 +523,88 @@ async fn get_stratagem_files(
     })
 }
 
+/// Creates a task running `action` and scans the MDTs of `fsname` for the files matching
+/// `expression`, which are added to the task.
+/// `name` names the task and the Stratagem group of the scan.
+pub(super) async fn scan_task(
+    context: &Context,
+    fsname: &str,
+    name: &str,
+    action: &str,
+    args: serde_json::Value,
+    expression: &str,
+    msg: &str,
+) -> juniper::FieldResult<Command> {
+    let uuid = Uuid::new_v4().to_hyphenated().to_string();
+
+    let fs_id = fs_id_by_name(&context.pg_pool, fsname).await?;
+
+    let task = insert_task(
+        &format!("{}-{}-{}", uuid, name, name),
+        "created",
+        false,
+        false,
+        &[action.into()],
+        args,
+        fs_id,
+        &context.pg_pool,
+    )
+    .await?;
+
+    let mut jobs: Vec<SendJob<HashMap<String, serde_json::Value>>> = vec![create_task_job(task.id)];
+
+    let job_range: Vec<_> = (0..jobs.len()).collect();
+
+    let xs = get_target_hosts_by_fsname(fsname, &context.pg_pool).await?;
+
+    for x in xs {
+        let path = match x.dev_path {
+            Some(x) => x,
+            None => continue,
+        };
+
+        let cfg = stratagem::StratagemConfig {
+            flist_type: "none".into(),
+            summarize_size: true,
+            device: stratagem::StratagemDevice {
+                path,
+                groups: vec![name.into()],
+            },
+            groups: vec![stratagem::StratagemGroup {
+                name: name.into(),
+                rules: vec![stratagem::StratagemRule {
+                    action: "LAT_SHELL_CMD_FID".into(),
+                    expression: expression.into(),
+                    argument: name.into(),
+                    counter_name: Some(name.into()),
+                }],
+            }],
+        };
+
+        jobs.push(SendJob {
+            class_name: "ScanMdtJob",
+            args: vec![
+                ("fqdn".into(), serde_json::to_value(&x.fqdn)?),
+                ("uuid".into(), serde_json::to_value(&uuid)?),
+                ("fsname".into(), serde_json::to_value(fsname)?),
+                ("config".into(), serde_json::to_value(cfg)?),
+                (
+                    "depends_on_job_range".into(),
+                    serde_json::to_value(&job_range)?,
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        })
+    }
+
+    let command_id = run_jobs(msg, jobs, &context.rabbit_pool).await?;
+
+    let command = get_command(&context.pg_pool, command_id).await?;
+
+    Ok(command)
+}
+
 #[derive(Debug)]
 struct TargetHost {
     name: String,
@@ -0,0 +1,800 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::graphql::{
+    filesystem::check_pool, fs_id_by_name, insert_task, tunable::check_fs, Context,
+};
+use iml_postgres::{sqlx, PgPool};
+use iml_wire_types::{
+    changelog::{ChangelogDeregister, ChangelogRegister},
+    tiering::{
+        target_index, TieringMailbox, TieringPolicy, TieringStatus, TieringUnitKind,
+        TieringUnitStatus,
+    },
+    RunState,
+};
+use juniper::{FieldError, Value};
+use std::collections::BTreeSet;
+
+pub(crate) struct TieringQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl TieringQuery {
+    #[graphql(arguments(fs_name(description = "Only list policies of this filesystem")))]
+    /// List tiering policies
+    async fn policies(
+        context: &Context,
+        fs_name: Option<String>,
+    ) -> juniper::FieldResult<Vec<TieringPolicy>> {
+        let xs = sqlx::query_as!(
+            TieringPolicy,
+            r#"
+                SELECT
+                    id,
+                    fs_name,
+                    name,
+                    hot_pool,
+                    cold_pool,
+                    min_age,
+                    heatfn,
+                    freehi,
+                    freelo,
+                    mountpoint,
+                    applied_at
+                FROM tiering_policy
+                WHERE ($1::text IS NULL OR fs_name = $1)
+                ORDER BY fs_name, name
+            "#,
+            fs_name
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        Ok(xs)
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem of the policy"),
+        name(description = "The name of the policy"),
+    ))]
+    /// Read the run state of the units of a tiering policy from their hosts,
+    /// along with the FID counts of its mailboxes
+    async fn status(
+        context: &Context,
+        fs_name: String,
+        name: String,
+    ) -> juniper::FieldResult<TieringStatus> {
+        let x = get_policy(&context.pg_pool, &fs_name, &name).await?;
+
+        let mut units = vec![];
+
+        for u in get_units(&context.pg_pool, x.id).await? {
+            let state = invoke(context, &u.fqdn, "get_unit_run_state", &u.unit)
+                .await
+                .and_then(|x| Ok(serde_json::from_value::<RunState>(x)?));
+
+            let (state, healthy) = match state {
+                Ok(x) => (
+                    format!("{:?}", x),
+                    x == RunState::Started || x == RunState::Setup,
+                ),
+                Err(e) => (e.message().to_string(), false),
+            };
+
+            units.push(TieringUnitStatus {
+                kind: u.kind,
+                target: u.target,
+                host: u.fqdn,
+                unit: u.unit,
+                state,
+                healthy,
+            });
+        }
+
+        let names = vec![x.extend_mailbox(), x.resync_mailbox(), x.purge_mailbox()];
+
+        let mailboxes = sqlx::query!(
+            r#"
+                SELECT name, state, fids_total, fids_completed, fids_failed
+                FROM chroma_core_task
+                WHERE name = ANY($1::text[])
+                ORDER BY name
+            "#,
+            &names
+        )
+        .fetch_all(&context.pg_pool)
+        .await?
+        .into_iter()
+        .map(|x| TieringMailbox {
+            name: x.name,
+            state: x.state,
+            fids_total: x.fids_total as f64,
+            fids_completed: x.fids_completed as f64,
+            fids_failed: x.fids_failed as f64,
+        })
+        .collect();
+
+        Ok(TieringStatus { units, mailboxes })
+    }
+}
+
+pub(crate) struct TieringMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl TieringMutation {
+    #[graphql(arguments(
+        fs_name(description = "The filesystem of the policy"),
+        name(description = "The name of the policy"),
+        hot_pool(description = "The OST pool new files are written to"),
+        cold_pool(description = "The OST pool files are mirrored to"),
+        min_age(description = "Seconds to wait before mirroring a closed file"),
+        heatfn(description = "The lamigo heat function"),
+        freehi(description = "Stop purging hot mirrors once an OST has this percentage free"),
+        freelo(
+            description = "Start purging hot mirrors once an OST has less than this percentage free"
+        ),
+        mountpoint(description = "The Lustre client mountpoint lamigo uses on the MDS"),
+    ))]
+    /// Create a tiering policy.
+    /// The policy is not started until `apply` is called.
+    async fn create(
+        context: &Context,
+        fs_name: String,
+        name: String,
+        hot_pool: String,
+        cold_pool: String,
+        min_age: i32,
+        heatfn: Option<i32>,
+        freehi: i32,
+        freelo: i32,
+        mountpoint: String,
+    ) -> juniper::FieldResult<TieringPolicy> {
+        validate(&name, &hot_pool, &cold_pool, min_age, freehi, freelo)?;
+
+        check_fs(&context.pg_pool, &fs_name).await?;
+        check_pool(&context.pg_pool, &fs_name, &hot_pool).await?;
+        check_pool(&context.pg_pool, &fs_name, &cold_pool).await?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO tiering_policy
+                    (fs_name, name, hot_pool, cold_pool, min_age, heatfn, freehi, freelo, mountpoint)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                ON CONFLICT (fs_name, name) DO NOTHING
+                RETURNING id
+            "#,
+            fs_name,
+            name,
+            hot_pool,
+            cold_pool,
+            min_age,
+            heatfn,
+            freehi,
+            freelo,
+            mountpoint
+        )
+        .fetch_optional(&context.pg_pool)
+        .await?
+        .ok_or_else(|| {
+            FieldError::new(
+                format!("Tiering policy {} already exists on {}", name, fs_name),
+                Value::null(),
+            )
+        })?;
+
+        get_policy(&context.pg_pool, &fs_name, &name).await
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem of the policy"),
+        name(description = "The name of the policy"),
+        min_age(description = "Seconds to wait before mirroring a closed file"),
+        heatfn(description = "The lamigo heat function"),
+        freehi(description = "Stop purging hot mirrors once an OST has this percentage free"),
+        freelo(
+            description = "Start purging hot mirrors once an OST has less than this percentage free"
+        ),
+        mountpoint(description = "The Lustre client mountpoint lamigo uses on the MDS"),
+    ))]
+    /// Change the settings of a tiering policy. Settings that are not given are left as is.
+    /// If the policy is applied, its configs are rewritten and its units restarted.
+    async fn update(
+        context: &Context,
+        fs_name: String,
+        name: String,
+        min_age: Option<i32>,
+        heatfn: Option<i32>,
+        freehi: Option<i32>,
+        freelo: Option<i32>,
+        mountpoint: Option<String>,
+    ) -> juniper::FieldResult<TieringPolicy> {
+        let x = get_policy(&context.pg_pool, &fs_name, &name).await?;
+
+        let min_age = min_age.unwrap_or(x.min_age);
+        let freehi = freehi.unwrap_or(x.freehi);
+        let freelo = freelo.unwrap_or(x.freelo);
+
+        validate(&name, &x.hot_pool, &x.cold_pool, min_age, freehi, freelo)?;
+
+        sqlx::query!(
+            r#"
+                UPDATE tiering_policy
+                SET min_age = $2,
+                    heatfn = COALESCE($3, heatfn),
+                    freehi = $4,
+                    freelo = $5,
+                    mountpoint = COALESCE($6, mountpoint)
+                WHERE id = $1
+            "#,
+            x.id,
+            min_age,
+            heatfn,
+            freehi,
+            freelo,
+            mountpoint
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        let x = get_policy(&context.pg_pool, &fs_name, &name).await?;
+
+        if x.applied_at.is_some() {
+            apply_policy(context, &x).await?;
+        }
+
+        get_policy(&context.pg_pool, &fs_name, &name).await
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem of the policy"),
+        name(description = "The name of the policy"),
+    ))]
+    /// Start a tiering policy.
+    /// Creates its mailboxes, writes the lamigo config of each MDT and the lpurge config
+    /// of each OST in the hot pool, then (re)starts their units.
+    /// Only one policy of a filesystem can be applied at a time.
+    async fn apply(
+        context: &Context,
+        fs_name: String,
+        name: String,
+    ) -> juniper::FieldResult<TieringPolicy> {
+        let x = get_policy(&context.pg_pool, &fs_name, &name).await?;
+
+        apply_policy(context, &x).await?;
+
+        get_policy(&context.pg_pool, &fs_name, &name).await
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem of the policy"),
+        name(description = "The name of the policy"),
+    ))]
+    /// Stop the units of a tiering policy and deregister their changelog users.
+    /// The policy is kept and can be applied again.
+    async fn stop(
+        context: &Context,
+        fs_name: String,
+        name: String,
+    ) -> juniper::FieldResult<TieringPolicy> {
+        let x = get_policy(&context.pg_pool, &fs_name, &name).await?;
+
+        stop_policy(context, &x).await?;
+
+        get_policy(&context.pg_pool, &fs_name, &name).await
+    }
+    #[graphql(arguments(
+        fs_name(description = "The filesystem of the policy"),
+        name(description = "The name of the policy"),
+    ))]
+    /// Stop and remove a tiering policy. Its mailboxes are marked removed.
+    async fn remove(
+        context: &Context,
+        fs_name: String,
+        name: String,
+    ) -> juniper::FieldResult<bool> {
+        let x = get_policy(&context.pg_pool, &fs_name, &name).await?;
+
+        stop_policy(context, &x).await?;
+
+        let names = vec![x.extend_mailbox(), x.resync_mailbox(), x.purge_mailbox()];
+
+        sqlx::query!(
+            r#"
+                UPDATE chroma_core_task
+                SET state = 'removed', finish = now()
+                WHERE name = ANY($1::text[])
+            "#,
+            &names
+        )
+        .execute(&context.pg_pool)
+        .await?;
+
+        sqlx::query!("DELETE FROM tiering_policy WHERE id = $1", x.id)
+            .execute(&context.pg_pool)
+            .await?;
+
+        Ok(true)
+    }
+}
+
+/// Checks the settings of a policy are consistent
+fn validate(
+    name: &str,
+    hot_pool: &str,
+    cold_pool: &str,
+    min_age: i32,
+    freehi: i32,
+    freelo: i32,
+) -> juniper::FieldResult<()> {
+    let err = |x: String| Err(FieldError::new(x, Value::null()));
+
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return err(format!("Invalid tiering policy name {}", name));
+    }
+
+    if hot_pool == cold_pool {
+        return err("The hot and cold pools must differ".into());
+    }
+
+    if min_age < 0 {
+        return err(format!("Invalid minimum age {}", min_age));
+    }
+
+    if freehi < 1 || freehi > 100 || freelo < 0 || freelo >= freehi {
+        return err(format!(
+            "freelo ({}) must be less than freehi ({}), and both between 0 and 100",
+            freelo, freehi
+        ));
+    }
+
+    Ok(())
+}
+
+async fn get_policy(
+    pool: &PgPool,
+    fs_name: &str,
+    name: &str,
+) -> juniper::FieldResult<TieringPolicy> {
+    let x = sqlx::query_as!(
+        TieringPolicy,
+        r#"
+            SELECT
+                id,
+                fs_name,
+                name,
+                hot_pool,
+                cold_pool,
+                min_age,
+                heatfn,
+                freehi,
+                freelo,
+                mountpoint,
+                applied_at
+            FROM tiering_policy
+            WHERE fs_name = $1 AND name = $2
+        "#,
+        fs_name,
+        name
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| {
+        FieldError::new(
+            format!("Tiering policy {} not found on {}", name, fs_name),
+            Value::null(),
+        )
+    })?;
+
+    Ok(x)
+}
+
+struct UnitRow {
+    id: i32,
+    kind: TieringUnitKind,
+    target: String,
+    fqdn: String,
+    unit: String,
+    changelog_user: Option<String>,
+}
+
+async fn get_units(pool: &PgPool, policy_id: i32) -> Result<Vec<UnitRow>, sqlx::Error> {
+    sqlx::query_as!(
+        UnitRow,
+        r#"
+            SELECT
+                u.id,
+                u.kind AS "kind: TieringUnitKind",
+                u.target,
+                h.fqdn,
+                u.unit,
+                u.changelog_user
+            FROM tiering_unit u
+            INNER JOIN chroma_core_managedhost h ON h.id = u.host_id
+            WHERE u.policy_id = $1
+            ORDER BY u.kind, u.target
+        "#,
+        policy_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// A target and the host currently running it
+struct TargetHost {
+    name: String,
+    host_id: i32,
+    fqdn: String,
+}
+
+async fn mdt_hosts(pool: &PgPool, fs_name: &str) -> Result<Vec<TargetHost>, sqlx::Error> {
+    sqlx::query_as!(
+        TargetHost,
+        r#"
+            SELECT t.name, h.id AS host_id, h.fqdn
+            FROM target t
+            INNER JOIN chroma_core_managedhost h ON h.id = t.active_host_id
+            WHERE $1 = ANY(t.filesystems) AND t.name LIKE '%-MDT%' AND h.not_deleted = 't'
+            ORDER BY t.name
+        "#,
+        fs_name
+    )
+    .fetch_all(pool)
+    .await
+}
+
+async fn pool_ost_hosts(
+    pool: &PgPool,
+    fs_name: &str,
+    pool_name: &str,
+) -> Result<Vec<TargetHost>, sqlx::Error> {
+    sqlx::query_as!(
+        TargetHost,
+        r#"
+            SELECT t.name, h.id AS host_id, h.fqdn
+            FROM chroma_core_ostpool p
+            INNER JOIN chroma_core_managedfilesystem f ON f.id = p.filesystem_id
+            INNER JOIN chroma_core_ostpool_osts po ON po.ostpool_id = p.id
+            INNER JOIN chroma_core_managedtarget mt ON mt.id = po.managedost_id
+            INNER JOIN target t ON t.name = mt.name AND f.name = ANY(t.filesystems)
+            INNER JOIN chroma_core_managedhost h ON h.id = t.active_host_id
+            WHERE f.name = $1 AND p.name = $2
+            AND p.not_deleted = 't' AND f.not_deleted = 't' AND h.not_deleted = 't'
+            ORDER BY t.name
+        "#,
+        fs_name,
+        pool_name
+    )
+    .fetch_all(pool)
+    .await
+}
+
+async fn invoke(
+    context: &Context,
+    fqdn: &str,
+    action: &str,
+    args: impl serde::Serialize + Send,
+) -> juniper::FieldResult<serde_json::Value> {
+    let x = context
+        .action_client
+        .invoke_rust_agent_expect_result(fqdn, action, args, None)
+        .await?
+        .map_err(|e| FieldError::new(e, Value::null()))?;
+
+    Ok(x)
+}
+
+/// Creates the task backing a mailbox, or reopens it if it was removed
+async fn ensure_task(
+    pool: &PgPool,
+    name: &str,
+    action: &str,
+    fs_id: i32,
+) -> juniper::FieldResult<()> {
+    let x = sqlx::query!(
+        r#"
+            UPDATE chroma_core_task
+            SET state = 'created', finish = NULL
+            WHERE name = $1
+            RETURNING id
+        "#,
+        name
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    if x.is_none() {
+        insert_task(
+            name,
+            "created",
+            false,
+            true,
+            &[action.to_string()],
+            serde_json::json!({}),
+            fs_id,
+            pool,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn upsert_unit(
+    pool: &PgPool,
+    policy_id: i32,
+    kind: TieringUnitKind,
+    x: &TargetHost,
+    changelog_user: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+            INSERT INTO tiering_unit (policy_id, kind, target, host_id, unit, changelog_user)
+            VALUES ($1, $2::text::tiering_unit_kind, $3, $4, $5, $6)
+            ON CONFLICT (policy_id, target) DO UPDATE
+            SET host_id = EXCLUDED.host_id,
+                changelog_user = EXCLUDED.changelog_user
+        "#,
+        policy_id,
+        kind.to_string(),
+        x.name,
+        x.host_id,
+        kind.unit_name(&x.name),
+        changelog_user
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Stops a unit and deregisters its changelog user.
+/// Failures are logged, as the host may be down or the target moved.
+async fn teardown_unit(context: &Context, u: &UnitRow) {
+    if let Err(e) = invoke(context, &u.fqdn, "stop_unit", &u.unit).await {
+        tracing::warn!("Could not stop {} on {}: {}", u.unit, u.fqdn, e.message());
+    }
+
+    if let Some(id) = &u.changelog_user {
+        let x = ChangelogDeregister {
+            target: u.target.clone(),
+            id: id.clone(),
+        };
+
+        if let Err(e) = invoke(context, &u.fqdn, "changelog_deregister", x).await {
+            tracing::warn!(
+                "Could not deregister changelog user {} of {}: {}",
+                id,
+                u.target,
+                e.message()
+            );
+        }
+    }
+}
+
+async fn apply_policy(context: &Context, x: &TieringPolicy) -> juniper::FieldResult<()> {
+    let pool = &context.pg_pool;
+
+    let other = sqlx::query!(
+        r#"
+            SELECT name FROM tiering_policy
+            WHERE fs_name = $1 AND id <> $2 AND applied_at IS NOT NULL
+        "#,
+        x.fs_name,
+        x.id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    if let Some(other) = other {
+        return Err(FieldError::new(
+            format!(
+                "Tiering policy {} is already applied on {}",
+                other.name, x.fs_name
+            ),
+            Value::null(),
+        ));
+    }
+
+    check_pool(pool, &x.fs_name, &x.hot_pool).await?;
+    check_pool(pool, &x.fs_name, &x.cold_pool).await?;
+
+    let mdts = mdt_hosts(pool, &x.fs_name).await?;
+
+    if mdts.is_empty() {
+        return Err(FieldError::new(
+            format!("No MDTs of {} are mounted", x.fs_name),
+            Value::null(),
+        ));
+    }
+
+    let osts = pool_ost_hosts(pool, &x.fs_name, &x.hot_pool).await?;
+
+    if osts.is_empty() {
+        return Err(FieldError::new(
+            format!("OST pool {} has no mounted OSTs", x.hot_pool),
+            Value::null(),
+        ));
+    }
+
+    let fs_id = fs_id_by_name(pool, &x.fs_name).await?;
+
+    ensure_task(pool, &x.extend_mailbox(), "mirror.extend", fs_id).await?;
+    ensure_task(pool, &x.resync_mailbox(), "mirror.resync", fs_id).await?;
+    ensure_task(pool, &x.purge_mailbox(), "mirror.split", fs_id).await?;
+
+    let existing = get_units(pool, x.id).await?;
+
+    for mdt in &mdts {
+        let prev = existing.iter().find(|u| u.target == mdt.name);
+
+        // The MDT failed over, so stop lamigo where it ran before
+        if let Some(u) = prev.filter(|u| u.fqdn != mdt.fqdn) {
+            if let Err(e) = invoke(context, &u.fqdn, "stop_unit", &u.unit).await {
+                tracing::warn!("Could not stop {} on {}: {}", u.unit, u.fqdn, e.message());
+            }
+        }
+
+        let user = match prev.and_then(|u| u.changelog_user.clone()) {
+            Some(x) => x,
+            None => {
+                let x = invoke(
+                    context,
+                    &mdt.fqdn,
+                    "changelog_register",
+                    ChangelogRegister {
+                        target: mdt.name.clone(),
+                    },
+                )
+                .await?;
+
+                serde_json::from_value(x)?
+            }
+        };
+
+        // Record the unit before starting it, so the changelog user is not lost on failure
+        upsert_unit(pool, x.id, TieringUnitKind::Lamigo, mdt, Some(&user)).await?;
+
+        for mailbox in &[x.extend_mailbox(), x.resync_mailbox()] {
+            invoke(context, &mdt.fqdn, "postoffice_add", mailbox).await?;
+        }
+
+        invoke(
+            context,
+            &mdt.fqdn,
+            "create_lamigo_conf",
+            serde_json::json!({
+                "fs": x.fs_name,
+                "mdt": target_index(&mdt.name).unwrap_or_default(),
+                "user": user,
+                "hot_pool": x.hot_pool,
+                "cold_pool": x.cold_pool,
+                "min_age": x.min_age,
+                "mountpoint": x.mountpoint,
+                "mailbox_extend": x.extend_mailbox(),
+                "mailbox_resync": x.resync_mailbox(),
+                "heatfn": x.heatfn,
+            }),
+        )
+        .await?;
+
+        invoke(
+            context,
+            &mdt.fqdn,
+            "restart_unit",
+            TieringUnitKind::Lamigo.unit_name(&mdt.name),
+        )
+        .await?;
+    }
+
+    for ost in &osts {
+        if let Some(u) = existing
+            .iter()
+            .find(|u| u.target == ost.name && u.fqdn != ost.fqdn)
+        {
+            if let Err(e) = invoke(context, &u.fqdn, "stop_unit", &u.unit).await {
+                tracing::warn!("Could not stop {} on {}: {}", u.unit, u.fqdn, e.message());
+            }
+        }
+
+        upsert_unit(pool, x.id, TieringUnitKind::Lpurge, ost, None).await?;
+
+        invoke(context, &ost.fqdn, "postoffice_add", x.purge_mailbox()).await?;
+
+        invoke(
+            context,
+            &ost.fqdn,
+            "create_lpurge_conf",
+            serde_json::json!({
+                "fs": x.fs_name,
+                "ost": target_index(&ost.name).unwrap_or_default(),
+                "pool": x.hot_pool,
+                "freehi": x.freehi,
+                "freelo": x.freelo,
+                "mailbox": x.purge_mailbox(),
+            }),
+        )
+        .await?;
+
+        invoke(
+            context,
+            &ost.fqdn,
+            "restart_unit",
+            TieringUnitKind::Lpurge.unit_name(&ost.name),
+        )
+        .await?;
+    }
+
+    // Stop units of targets that left the hot pool or are no longer mounted
+    let current: BTreeSet<_> = mdts.iter().chain(osts.iter()).map(|x| &x.name).collect();
+
+    for u in existing.iter().filter(|u| !current.contains(&u.target)) {
+        teardown_unit(context, u).await;
+
+        sqlx::query!("DELETE FROM tiering_unit WHERE id = $1", u.id)
+            .execute(pool)
+            .await?;
+    }
+
+    sqlx::query!(
+        "UPDATE tiering_policy SET applied_at = now() WHERE id = $1",
+        x.id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn stop_policy(context: &Context, x: &TieringPolicy) -> juniper::FieldResult<()> {
+    let pool = &context.pg_pool;
+
+    let units = get_units(pool, x.id).await?;
+
+    for u in &units {
+        teardown_unit(context, u).await;
+    }
+
+    let hosts: BTreeSet<_> = units.iter().map(|u| &u.fqdn).collect();
+
+    for fqdn in hosts {
+        for mailbox in &[x.extend_mailbox(), x.resync_mailbox(), x.purge_mailbox()] {
+            if let Err(e) = invoke(context, fqdn, "postoffice_remove", mailbox).await {
+                tracing::warn!(
+                    "Could not remove mailbox {} from {}: {}",
+                    mailbox,
+                    fqdn,
+                    e.message()
+                );
+            }
+        }
+    }
+
+    sqlx::query!("DELETE FROM tiering_unit WHERE policy_id = $1", x.id)
+        .execute(pool)
+        .await?;
+
+    sqlx::query!(
+        "UPDATE tiering_policy SET applied_at = NULL WHERE id = $1",
+        x.id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("tier1", "ssd", "hdd", 600, 90, 70).is_ok());
+        assert!(validate("tier1", "ssd", "ssd", 600, 90, 70).is_err());
+        assert!(validate("tier1", "ssd", "hdd", 600, 70, 90).is_err());
+        assert!(validate("tier1", "ssd", "hdd", 600, 101, 70).is_err());
+        assert!(validate("tier1", "ssd", "hdd", -1, 90, 70).is_err());
+        assert!(validate("tier 1", "ssd", "hdd", 600, 90, 70).is_err());
+    }
+}
@@ -0,0 +1,419 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::graphql::Context;
+use iml_postgres::{active_mgs_host_fqdn, host_id_by_fqdn, sqlx, PgPool};
+use iml_wire_types::{
+    db::TargetKind,
+    tunable::{Tunable, TunableDrift, TunableMethod, TunableSet},
+    Fqdn,
+};
+use juniper::{FieldError, Value};
+use std::collections::BTreeMap;
+
+struct TunableRow {
+    id: i32,
+    fs_name: String,
+    param: String,
+    value: String,
+    method: TunableMethod,
+    target_kind: Option<String>,
+    host: Option<String>,
+}
+
+impl From<TunableRow> for Tunable {
+    fn from(x: TunableRow) -> Self {
+        Tunable {
+            id: x.id,
+            fs_name: x.fs_name,
+            param: x.param,
+            value: x.value,
+            method: x.method,
+            target_kind: x.target_kind.and_then(|x| x.parse().ok()),
+            host: x.host,
+        }
+    }
+}
+
+pub(crate) struct TunableQuery;
+
+#[juniper::graphql_object(Context = Context)]
+impl TunableQuery {
+    #[graphql(arguments(fs_name(description = "The filesystem to list tunables of")))]
+    /// List the desired tunables of a filesystem
+    async fn tunables(context: &Context, fs_name: String) -> juniper::FieldResult<Vec<Tunable>> {
+        let xs = sqlx::query_as!(
+            TunableRow,
+            r#"
+                SELECT
+                    t.id,
+                    t.fs_name,
+                    t.param,
+                    t.value,
+                    t.method AS "method: TunableMethod",
+                    t.target_kind,
+                    h.fqdn AS "host?"
+                FROM tunable t
+                LEFT JOIN chroma_core_managedhost h ON h.id = t.host_id
+                WHERE t.fs_name = $1
+                ORDER BY t.param, h.fqdn NULLS FIRST
+            "#,
+            fs_name
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        Ok(xs.into_iter().map(Tunable::from).collect())
+    }
+    #[graphql(arguments(fs_name(description = "Only list drift of tunables on this filesystem")))]
+    /// List tunables whose value, as last read back from a server,
+    /// differs from the desired value
+    async fn drift(
+        context: &Context,
+        fs_name: Option<String>,
+    ) -> juniper::FieldResult<Vec<TunableDrift>> {
+        let xs = sqlx::query_as!(
+            TunableDrift,
+            r#"
+                SELECT
+                    t.fs_name,
+                    h.fqdn AS host,
+                    t.param,
+                    d.name,
+                    t.value AS desired,
+                    d.actual,
+                    d.detected_at
+                FROM tunable_drift d
+                INNER JOIN tunable t ON t.id = d.tunable_id
+                INNER JOIN chroma_core_managedhost h ON h.id = d.host_id
+                WHERE ($1::text IS NULL OR t.fs_name = $1)
+                ORDER BY t.fs_name, h.fqdn, d.name
+            "#,
+            fs_name
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        Ok(xs)
+    }
+}
+
+pub(crate) struct TunableMutation;
+
+#[juniper::graphql_object(Context = Context)]
+impl TunableMutation {
+    #[graphql(arguments(
+        fs_name(description = "The filesystem the tunable belongs to"),
+        param(
+            description = "The parameter pattern, i.e. `osc.fs-*.max_rpcs_in_flight`. Filesystem tunables must name the filesystem"
+        ),
+        value(description = "The desired value"),
+        method(
+            description = "How the tunable is set. Defaults to `set_param` for host tunables, `set_param_p` otherwise"
+        ),
+        target_kind(description = "Only check the tunable on servers with targets of this kind"),
+        host(description = "Only set the tunable on this host"),
+    ))]
+    /// Add or update a desired tunable.
+    /// The tunable is not set on the servers until `applyTunables` is called.
+    async fn set_tunable(
+        context: &Context,
+        fs_name: String,
+        param: String,
+        value: String,
+        method: Option<TunableMethod>,
+        target_kind: Option<TargetKind>,
+        host: Option<String>,
+    ) -> juniper::FieldResult<Tunable> {
+        validate_param(&param)?;
+
+        check_fs(&context.pg_pool, &fs_name).await?;
+
+        let host_id = match &host {
+            Some(fqdn) => Some(
+                host_id_by_fqdn(&Fqdn(fqdn.clone()), &context.pg_pool)
+                    .await?
+                    .ok_or_else(|| {
+                        FieldError::new(format!("Host {} not found", fqdn), Value::null())
+                    })?,
+            ),
+            None => None,
+        };
+
+        let method = match (host_id, method) {
+            (Some(_), None) => TunableMethod::SetParam,
+            (None, None) => TunableMethod::SetParamP,
+            (Some(_), Some(x)) if x.is_persistent() => {
+                return Err(FieldError::new(
+                    format!("Host tunables must use set_param, not {}", x),
+                    Value::null(),
+                ))
+            }
+            (None, Some(x)) if !x.is_persistent() => {
+                return Err(FieldError::new(
+                    "Filesystem tunables must use set_param_p or conf_param",
+                    Value::null(),
+                ))
+            }
+            (_, Some(x)) => x,
+        };
+
+        if method.is_persistent() {
+            check_fs_scoped(&fs_name, &param)?;
+        }
+
+        if host_id.is_some() && target_kind.is_some() {
+            return Err(FieldError::new(
+                "A tunable cannot have both a host and a target kind",
+                Value::null(),
+            ));
+        }
+
+        let id = sqlx::query!(
+            r#"
+                INSERT INTO tunable (fs_name, param, value, method, target_kind, host_id)
+                VALUES ($1, $2, $3, $4::text::tunable_method, $5, $6)
+                ON CONFLICT (fs_name, param, COALESCE(host_id, 0)) DO UPDATE
+                SET value = EXCLUDED.value,
+                    method = EXCLUDED.method,
+                    target_kind = EXCLUDED.target_kind
+                RETURNING id
+            "#,
+            fs_name,
+            param,
+            value,
+            method.to_string(),
+            target_kind.map(|x| x.to_string()),
+            host_id
+        )
+        .fetch_one(&context.pg_pool)
+        .await?
+        .id;
+
+        let x = sqlx::query_as!(
+            TunableRow,
+            r#"
+                SELECT
+                    t.id,
+                    t.fs_name,
+                    t.param,
+                    t.value,
+                    t.method AS "method: TunableMethod",
+                    t.target_kind,
+                    h.fqdn AS "host?"
+                FROM tunable t
+                LEFT JOIN chroma_core_managedhost h ON h.id = t.host_id
+                WHERE t.id = $1
+            "#,
+            id
+        )
+        .fetch_one(&context.pg_pool)
+        .await?;
+
+        Ok(x.into())
+    }
+    #[graphql(arguments(id(description = "The id of the tunable to remove")))]
+    /// Remove a desired tunable.
+    /// The value currently set on the servers is left as is.
+    async fn remove_tunable(context: &Context, id: i32) -> juniper::FieldResult<bool> {
+        sqlx::query!("DELETE FROM tunable WHERE id = $1 RETURNING id", id)
+            .fetch_optional(&context.pg_pool)
+            .await?
+            .ok_or_else(|| FieldError::new(format!("Tunable {} not found", id), Value::null()))?;
+
+        Ok(true)
+    }
+    #[graphql(arguments(fs_name(description = "The filesystem to apply tunables of")))]
+    /// Set the desired tunables of a filesystem on its servers.
+    /// Filesystem tunables are set on the MGS, host tunables on their host.
+    /// Each server is then told which tunables to read back for drift detection.
+    async fn apply_tunables(context: &Context, fs_name: String) -> juniper::FieldResult<bool> {
+        check_fs(&context.pg_pool, &fs_name).await?;
+
+        let xs = sqlx::query_as!(
+            TunableSet,
+            r#"
+                SELECT method AS "method: TunableMethod", param, value
+                FROM tunable
+                WHERE fs_name = $1 AND host_id IS NULL
+                ORDER BY param
+            "#,
+            fs_name
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        if !xs.is_empty() {
+            let fqdn = active_mgs_host_fqdn(&fs_name, &context.pg_pool)
+                .await?
+                .ok_or_else(|| {
+                    FieldError::new(
+                        format!("No active MGS found for filesystem {}", fs_name),
+                        Value::null(),
+                    )
+                })?;
+
+            set_tunables(context, fqdn, xs).await?;
+        }
+
+        let xs = sqlx::query!(
+            r#"
+                SELECT h.fqdn, t.param, t.value
+                FROM tunable t
+                INNER JOIN chroma_core_managedhost h ON h.id = t.host_id
+                WHERE t.fs_name = $1 AND h.not_deleted = 't'
+                ORDER BY h.fqdn, t.param
+            "#,
+            fs_name
+        )
+        .fetch_all(&context.pg_pool)
+        .await?;
+
+        let by_host = xs.into_iter().fold(BTreeMap::new(), |mut acc, x| {
+            acc.entry(x.fqdn).or_insert_with(Vec::new).push(TunableSet {
+                method: TunableMethod::SetParam,
+                param: x.param,
+                value: x.value,
+            });
+
+            acc
+        });
+
+        for (fqdn, xs) in by_host {
+            set_tunables(context, fqdn, xs).await?;
+        }
+
+        for (fqdn, params) in watched_params(&context.pg_pool, &fs_name).await? {
+            context
+                .action_client
+                .invoke_rust_agent_expect_result(fqdn, "tunable_watch", params, None)
+                .await?
+                .map_err(|e| FieldError::new(e, Value::null()))?;
+        }
+
+        Ok(true)
+    }
+}
+
+/// Checks `param` is a single `lctl` parameter pattern without a value.
+fn validate_param(param: &str) -> juniper::FieldResult<()> {
+    if param.is_empty() || param.contains(|c: char| c.is_whitespace() || c == '=') {
+        return Err(FieldError::new(
+            format!("Invalid tunable parameter {}", param),
+            Value::null(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks a persistent tunable only applies to `fs_name`.
+///
+/// Persistent tunables are set on the MGS, which may serve several filesystems,
+/// so a pattern such as `osc.*.max_rpcs_in_flight` would apply to all of them.
+/// A component of the pattern must name the filesystem, i.e. `osc.fs-*.max_rpcs_in_flight`.
+fn check_fs_scoped(fs_name: &str, param: &str) -> juniper::FieldResult<()> {
+    let prefix = format!("{}-", fs_name);
+
+    let scoped = param
+        .split(|c| c == '.' || c == '/')
+        .any(|x| x == fs_name || x.starts_with(&prefix));
+
+    if !scoped {
+        return Err(FieldError::new(
+            format!(
+                "Tunable parameter {} is not scoped to filesystem {}, i.e. osc.{}-*.max_rpcs_in_flight",
+                param, fs_name, fs_name
+            ),
+            Value::null(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub(super) async fn check_fs(pool: &PgPool, fs_name: &str) -> juniper::FieldResult<()> {
+    sqlx::query!(
+        "SELECT id FROM chroma_core_managedfilesystem WHERE name = $1 AND not_deleted = 't'",
+        fs_name
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| FieldError::new(format!("Filesystem {} not found", fs_name), Value::null()))?;
+
+    Ok(())
+}
+
+async fn set_tunables(
+    context: &Context,
+    fqdn: String,
+    xs: Vec<TunableSet>,
+) -> juniper::FieldResult<()> {
+    context
+        .action_client
+        .invoke_rust_agent_expect_result(fqdn, "tunable_set", xs, None)
+        .await?
+        .map_err(|e| FieldError::new(e, Value::null()))?;
+
+    Ok(())
+}
+
+/// Returns the parameters each server of `fs_name` should read back.
+/// A server may serve several filesystems, so its parameters
+/// cover the tunables of every filesystem that applies to it.
+///
+/// `conf_param` names are not `get_param` paths, so those tunables are not read back.
+async fn watched_params(
+    pool: &PgPool,
+    fs_name: &str,
+) -> Result<Vec<(String, Vec<String>)>, sqlx::Error> {
+    let xs = sqlx::query!(
+        r#"
+            SELECT
+                h.fqdn,
+                COALESCE(
+                    array_agg(DISTINCT ht.param) FILTER (WHERE ht.param IS NOT NULL),
+                    '{}'
+                ) AS "params!"
+            FROM chroma_core_managedhost h
+            LEFT JOIN host_tunable ht ON ht.host_id = h.id AND ht.method != 'conf_param'
+            WHERE h.not_deleted = 't'
+            AND h.id IN (
+                SELECT UNNEST(host_ids) FROM target WHERE $1 = ANY(filesystems)
+                UNION
+                SELECT host_id FROM tunable WHERE fs_name = $1 AND host_id IS NOT NULL
+            )
+            GROUP BY h.fqdn
+            ORDER BY h.fqdn
+        "#,
+        fs_name
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(xs.into_iter().map(|x| (x.fqdn, x.params)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_param() {
+        assert!(validate_param("osc.*.max_rpcs_in_flight").is_ok());
+        assert!(validate_param("").is_err());
+        assert!(validate_param("osc.*.max_rpcs_in_flight=16").is_err());
+        assert!(validate_param("osc.* max_rpcs_in_flight").is_err());
+    }
+
+    #[test]
+    fn test_check_fs_scoped() {
+        assert!(check_fs_scoped("fs", "osc.fs-*.max_rpcs_in_flight").is_ok());
+        assert!(check_fs_scoped("fs", "obdfilter/fs-OST0000/readcache_max_filesize").is_ok());
+        assert!(check_fs_scoped("fs", "fs.sys.timeout").is_ok());
+        assert!(check_fs_scoped("fs", "osc.*.max_rpcs_in_flight").is_err());
+        assert!(check_fs_scoped("fs", "osc.fs2-*.max_rpcs_in_flight").is_err());
+        assert!(check_fs_scoped("fs", "osc.fs*.max_rpcs_in_flight").is_err());
+    }
+}
@@ -52,6 +52,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let ctx = Arc::new(graphql::Context {
         pg_pool,
         rabbit_pool,
+        action_client: iml_action_client::Client::default(),
     });
     let ctx_filter = warp::any().map(move || Arc::clone(&ctx));
 
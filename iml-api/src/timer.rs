@@ -103,3 +103,181 @@ pub async fn remove_snapshot_timer(config_id: i32) -> Result<(), ImlApiError> {
 
     Ok(())
 }
+
+pub async fn configure_scrub_timer(
+    config_id: i32,
+    fqdn: &str,
+    zpool: &str,
+    interval: Duration,
+) -> Result<(), ImlApiError> {
+    let iml_cmd = format!("/usr/bin/iml server scrub start {} {}", fqdn, zpool);
+
+    let timer_config = format!(
+        r#"# Automatically created by IML
+
+[Unit]
+Description=Scrub zpool {} on {}
+
+[Timer]
+OnActiveSec={}
+OnUnitActiveSec={}
+AccuracySec=1us
+Persistent=true
+
+[Install]
+WantedBy=timers.target
+"#,
+        zpool,
+        fqdn,
+        interval.as_secs(),
+        interval.as_secs()
+    );
+
+    let service_config = format!(
+        r#"# Automatically created by IML
+
+[Unit]
+Description=Scrub zpool {} on {}
+{}
+
+[Service]
+Type=oneshot
+EnvironmentFile=/var/lib/chroma/iml-settings.conf
+ExecStart={}
+"#,
+        zpool,
+        fqdn,
+        if !running_in_docker() {
+            "After=iml-manager.target"
+        } else {
+            ""
+        },
+        iml_cmd,
+    );
+
+    let config = TimerConfig {
+        config_id: config_id.to_string(),
+        file_prefix: "iml-zpool-scrub".to_string(),
+        timer_config,
+        service_config,
+    };
+
+    let client = get_client()?;
+
+    let url = format!("http://{}/configure/", get_timer_addr());
+    tracing::debug!(
+        "Sending scrub interval config to timer service: {:?} {:?}",
+        url,
+        config
+    );
+    put(client, url.as_str(), config).await?;
+
+    Ok(())
+}
+
+pub async fn remove_scrub_timer(config_id: i32) -> Result<(), ImlApiError> {
+    let client = get_client()?;
+
+    delete(
+        client,
+        format!(
+            "http://{}/unconfigure/iml-zpool-scrub/{}",
+            get_timer_addr(),
+            config_id
+        )
+        .as_str(),
+        serde_json::json!("{}"),
+    )
+    .await?;
+
+    Ok(())
+}
+
+pub async fn configure_mirror_resync_timer(
+    config_id: i32,
+    fs_name: &str,
+    path: &str,
+    interval: Duration,
+) -> Result<(), ImlApiError> {
+    let iml_cmd = format!("/usr/bin/iml filesystem mirror resync {} {}", fs_name, path);
+
+    let timer_config = format!(
+        r#"# Automatically created by IML
+
+[Unit]
+Description=Resync stale mirrors under {} on filesystem {}
+
+[Timer]
+OnActiveSec={}
+OnUnitActiveSec={}
+AccuracySec=1us
+Persistent=true
+
+[Install]
+WantedBy=timers.target
+"#,
+        path,
+        fs_name,
+        interval.as_secs(),
+        interval.as_secs()
+    );
+
+    let service_config = format!(
+        r#"# Automatically created by IML
+
+[Unit]
+Description=Resync stale mirrors under {} on filesystem {}
+{}
+
+[Service]
+Type=oneshot
+EnvironmentFile=/var/lib/chroma/iml-settings.conf
+ExecStart={}
+"#,
+        path,
+        fs_name,
+        if !running_in_docker() {
+            "After=iml-manager.target"
+        } else {
+            ""
+        },
+        iml_cmd,
+    );
+
+    let config = TimerConfig {
+        config_id: config_id.to_string(),
+        file_prefix: "iml-mirror-resync".to_string(),
+        timer_config,
+        service_config,
+    };
+
+    let client = get_client()?;
+
+    let url = format!("http://{}/configure/", get_timer_addr());
+    tracing::debug!(
+        "Sending mirror resync interval config to timer service: {:?} {:?}",
+        url,
+        config
+    );
+    put(client, url.as_str(), config).await?;
+
+    Ok(())
+}
+
+pub async fn remove_mirror_resync_timer(config_id: i32) -> Result<(), ImlApiError> {
+    let client = get_client()?;
+
+    delete(
+        client,
+        format!(
+            "http://{}/unconfigure/iml-mirror-resync/{}",
+            get_timer_addr(),
+            config_id
+        )
+        .as_str(),
+        serde_json::json!("{}"),
+    )
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Resp<T> {
+    pub changelog: T,
+}
+
+pub mod users {
+    use crate::Query;
+    use iml_wire_types::changelog::ChangelogUser;
+
+    pub static QUERY: &str = r#"
+        query ChangelogUsers($fsName: String) {
+          changelog {
+            users(fsName: $fsName) {
+              fs_name: fsName
+              target
+              id
+              index
+              current_index: currentIndex
+              lag
+              index_updated_at: indexUpdatedAt
+              updated_at: updatedAt
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: Option<String>,
+    }
+
+    pub fn build(fs_name: Option<impl ToString>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.map(|x| x.to_string()),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Users {
+        pub users: Vec<ChangelogUser>,
+    }
+
+    pub type Resp = super::Resp<Users>;
+}
+
+pub mod register {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation RegisterChangelogUser($target: String!) {
+          changelog {
+            register(target: $target)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        target: String,
+    }
+
+    pub fn build(target: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                target: target.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Register {
+        pub register: String,
+    }
+
+    pub type Resp = super::Resp<Register>;
+}
+
+pub mod deregister {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation DeregisterChangelogUser($target: String!, $id: String!) {
+          changelog {
+            deregister(target: $target, id: $id)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        target: String,
+        id: String,
+    }
+
+    pub fn build(target: impl ToString, id: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                target: target.to_string(),
+                id: id.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Deregister {
+        pub deregister: bool,
+    }
+
+    pub type Resp = super::Resp<Deregister>;
+}
@@ -0,0 +1,86 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Resp<T> {
+    pub client: T,
+}
+
+pub mod connected {
+    use crate::Query;
+    use iml_wire_types::client::ConnectedClient;
+
+    pub static QUERY: &str = r#"
+        query ConnectedClients($fsName: String, $nid: String) {
+          client {
+            connected(fsName: $fsName, nid: $nid) {
+              nid
+              fs_name: fsName
+              targets
+              last_seen: lastSeen
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: Option<String>,
+        nid: Option<String>,
+    }
+
+    pub fn build(fs_name: Option<impl ToString>, nid: Option<impl ToString>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.map(|x| x.to_string()),
+                nid: nid.map(|x| x.to_string()),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Connected {
+        pub connected: Vec<ConnectedClient>,
+    }
+
+    pub type Resp = super::Resp<Connected>;
+}
+
+pub mod evict {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation EvictClient($nid: String!, $fsName: String) {
+          client {
+            evict(nid: $nid, fsName: $fsName)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        nid: String,
+        fs_name: Option<String>,
+    }
+
+    pub fn build(nid: impl ToString, fs_name: Option<impl ToString>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                nid: nid.to_string(),
+                fs_name: fs_name.map(|x| x.to_string()),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Evict {
+        pub evict: Vec<String>,
+    }
+
+    pub type Resp = super::Resp<Evict>;
+}
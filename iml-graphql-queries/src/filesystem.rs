@@ -32,3 +32,234 @@ pub mod detect {
 
     pub type Resp = super::Resp<Detect>;
 }
+
+pub mod create {
+    use crate::Query;
+    use iml_wire_types::{filesystem::TargetDevice, Command, FsType};
+
+    pub static QUERY: &str = r#"
+        mutation CreateFilesystem($fsName: String!, $mgs: TargetDevice!, $mdts: [TargetDevice!]!, $osts: [TargetDevice!]!, $backfs: FsType, $mkfsOptions: String) {
+          filesystem {
+            create(fsName: $fsName, mgs: $mgs, mdts: $mdts, osts: $osts, backfs: $backfs, mkfsOptions: $mkfsOptions) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+        mgs: TargetDevice,
+        mdts: Vec<TargetDevice>,
+        osts: Vec<TargetDevice>,
+        backfs: Option<FsType>,
+        #[serde(rename = "mkfsOptions")]
+        mkfs_options: Option<String>,
+    }
+
+    pub fn build(
+        fs_name: impl ToString,
+        mgs: TargetDevice,
+        mdts: Vec<TargetDevice>,
+        osts: Vec<TargetDevice>,
+        backfs: Option<FsType>,
+        mkfs_options: Option<impl ToString>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                mgs,
+                mdts,
+                osts,
+                backfs,
+                mkfs_options: mkfs_options.map(|x| x.to_string()),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Create {
+        pub create: Command,
+    }
+
+    pub type Resp = super::Resp<Create>;
+}
+
+pub mod quotas {
+    use crate::Query;
+    use iml_wire_types::quota::{QuotaKind, QuotaUsage};
+
+    pub static QUERY: &str = r#"
+        query Quotas($fsName: String!, $kind: QuotaKind, $pool: String) {
+          filesystem {
+            quotas(fsName: $fsName, kind: $kind, pool: $pool) {
+              fs_name: fsName
+              pool
+              kind
+              quota_id: quotaId
+              kbytes_used: kbytesUsed
+              kbytes_soft: kbytesSoft
+              kbytes_hard: kbytesHard
+              block_grace_expires: blockGraceExpires
+              inodes_used: inodesUsed
+              inodes_soft: inodesSoft
+              inodes_hard: inodesHard
+              inode_grace_expires: inodeGraceExpires
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+        kind: Option<QuotaKind>,
+        pool: Option<String>,
+    }
+
+    pub fn build(
+        fs_name: impl ToString,
+        kind: Option<QuotaKind>,
+        pool: Option<impl ToString>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                kind,
+                pool: pool.map(|x| x.to_string()),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Quotas {
+        pub quotas: Vec<QuotaUsage>,
+    }
+
+    pub type Resp = super::Resp<Quotas>;
+}
+
+pub mod set_quota {
+    use crate::Query;
+    use iml_wire_types::quota::QuotaKind;
+
+    pub static QUERY: &str = r#"
+        mutation SetQuota($fsName: String!, $kind: QuotaKind!, $id: String, $pool: String,
+                          $blockSoft: String, $blockHard: String, $inodeSoft: String, $inodeHard: String) {
+          filesystem {
+            setQuota(fsName: $fsName, kind: $kind, id: $id, pool: $pool,
+                     blockSoft: $blockSoft, blockHard: $blockHard, inodeSoft: $inodeSoft, inodeHard: $inodeHard)
+          }
+        }
+    "#;
+
+    #[derive(Debug, Default, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Limits {
+        pub block_soft: Option<String>,
+        pub block_hard: Option<String>,
+        pub inode_soft: Option<String>,
+        pub inode_hard: Option<String>,
+    }
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        kind: QuotaKind,
+        id: Option<String>,
+        pool: Option<String>,
+        #[serde(flatten)]
+        limits: Limits,
+    }
+
+    pub fn build(
+        fs_name: impl ToString,
+        kind: QuotaKind,
+        id: Option<impl ToString>,
+        pool: Option<impl ToString>,
+        limits: Limits,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                kind,
+                id: id.map(|x| x.to_string()),
+                pool: pool.map(|x| x.to_string()),
+                limits,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct SetQuota {
+        #[serde(rename(deserialize = "setQuota"))]
+        pub set_quota: bool,
+    }
+
+    pub type Resp = super::Resp<SetQuota>;
+}
+
+pub mod set_quota_grace {
+    use crate::Query;
+    use iml_wire_types::quota::QuotaKind;
+
+    pub static QUERY: &str = r#"
+        mutation SetQuotaGrace($fsName: String!, $kind: QuotaKind!, $pool: String, $blockGrace: String, $inodeGrace: String) {
+          filesystem {
+            setQuotaGrace(fsName: $fsName, kind: $kind, pool: $pool, blockGrace: $blockGrace, inodeGrace: $inodeGrace)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        kind: QuotaKind,
+        pool: Option<String>,
+        block_grace: Option<String>,
+        inode_grace: Option<String>,
+    }
+
+    pub fn build(
+        fs_name: impl ToString,
+        kind: QuotaKind,
+        pool: Option<impl ToString>,
+        block_grace: Option<impl ToString>,
+        inode_grace: Option<impl ToString>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                kind,
+                pool: pool.map(|x| x.to_string()),
+                block_grace: block_grace.map(|x| x.to_string()),
+                inode_grace: inode_grace.map(|x| x.to_string()),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct SetQuotaGrace {
+        #[serde(rename(deserialize = "setQuotaGrace"))]
+        pub set_quota_grace: bool,
+    }
+
+    pub type Resp = super::Resp<SetQuotaGrace>;
+}
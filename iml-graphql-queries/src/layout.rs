@@ -0,0 +1,344 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Resp<T> {
+    pub layout: T,
+}
+
+pub mod templates {
+    use crate::Query;
+    use iml_wire_types::layout::LayoutTemplate;
+
+    pub static QUERY: &str = r#"
+        query LayoutTemplates {
+          layout {
+            templates {
+              id
+              name
+              description
+              components {
+                extent_end: extentEnd
+                dom
+                stripe_count: stripeCount
+                stripe_size: stripeSize
+                pool
+              }
+            }
+          }
+        }
+    "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Templates {
+        pub templates: Vec<LayoutTemplate>,
+    }
+
+    pub type Resp = super::Resp<Templates>;
+}
+
+pub mod applied {
+    use crate::Query;
+    use iml_wire_types::layout::AppliedLayout;
+
+    pub static QUERY: &str = r#"
+        query AppliedLayouts($fsName: String) {
+          layout {
+            applied(fsName: $fsName) {
+              fs_name: fsName
+              path
+              template
+              components {
+                extent_end: extentEnd
+                dom
+                stripe_count: stripeCount
+                stripe_size: stripeSize
+                pool
+              }
+              applied_at: appliedAt
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: Option<String>,
+    }
+
+    pub fn build(fs_name: Option<impl ToString>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.map(|x| x.to_string()),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Applied {
+        pub applied: Vec<AppliedLayout>,
+    }
+
+    pub type Resp = super::Resp<Applied>;
+}
+
+pub mod directory {
+    use crate::Query;
+    use iml_wire_types::layout::LayoutComponentOut;
+
+    pub static QUERY: &str = r#"
+        query DirectoryLayout($fsName: String!, $path: String!) {
+          layout {
+            directory(fsName: $fsName, path: $path) {
+              extent_end: extentEnd
+              dom
+              stripe_count: stripeCount
+              stripe_size: stripeSize
+              pool
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        path: String,
+    }
+
+    pub fn build(fs_name: impl ToString, path: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                path: path.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Directory {
+        pub directory: Option<Vec<LayoutComponentOut>>,
+    }
+
+    pub type Resp = super::Resp<Directory>;
+}
+
+pub mod create_template {
+    use crate::Query;
+    use iml_wire_types::layout::{LayoutComponentInput, LayoutTemplate};
+
+    pub static QUERY: &str = r#"
+        mutation CreateLayoutTemplate($name: String!, $description: String, $components: [LayoutComponentInput!]!) {
+          layout {
+            createTemplate(name: $name, description: $description, components: $components) {
+              id
+              name
+              description
+              components {
+                extent_end: extentEnd
+                dom
+                stripe_count: stripeCount
+                stripe_size: stripeSize
+                pool
+              }
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        name: String,
+        description: Option<String>,
+        components: Vec<LayoutComponentInput>,
+    }
+
+    pub fn build(
+        name: impl ToString,
+        description: Option<impl ToString>,
+        components: Vec<LayoutComponentInput>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                name: name.to_string(),
+                description: description.map(|x| x.to_string()),
+                components,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct CreateTemplate {
+        #[serde(rename(deserialize = "createTemplate"))]
+        pub create_template: LayoutTemplate,
+    }
+
+    pub type Resp = super::Resp<CreateTemplate>;
+}
+
+pub mod remove_template {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation RemoveLayoutTemplate($name: String!) {
+          layout {
+            removeTemplate(name: $name)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        name: String,
+    }
+
+    pub fn build(name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct RemoveTemplate {
+        #[serde(rename(deserialize = "removeTemplate"))]
+        pub remove_template: bool,
+    }
+
+    pub type Resp = super::Resp<RemoveTemplate>;
+}
+
+pub mod apply_template {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation ApplyLayoutTemplate($fsName: String!, $path: String!, $name: String!) {
+          layout {
+            applyTemplate(fsName: $fsName, path: $path, name: $name)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        path: String,
+        name: String,
+    }
+
+    pub fn build(fs_name: impl ToString, path: impl ToString, name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                path: path.to_string(),
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct ApplyTemplate {
+        #[serde(rename(deserialize = "applyTemplate"))]
+        pub apply_template: bool,
+    }
+
+    pub type Resp = super::Resp<ApplyTemplate>;
+}
+
+pub mod set_directory {
+    use crate::Query;
+    use iml_wire_types::layout::LayoutComponentInput;
+
+    pub static QUERY: &str = r#"
+        mutation SetDirectoryLayout($fsName: String!, $path: String!, $components: [LayoutComponentInput!]!) {
+          layout {
+            setDirectory(fsName: $fsName, path: $path, components: $components)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        path: String,
+        components: Vec<LayoutComponentInput>,
+    }
+
+    pub fn build(
+        fs_name: impl ToString,
+        path: impl ToString,
+        components: Vec<LayoutComponentInput>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                path: path.to_string(),
+                components,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct SetDirectory {
+        #[serde(rename(deserialize = "setDirectory"))]
+        pub set_directory: bool,
+    }
+
+    pub type Resp = super::Resp<SetDirectory>;
+}
+
+pub mod clear_directory {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation ClearDirectoryLayout($fsName: String!, $path: String!) {
+          layout {
+            clearDirectory(fsName: $fsName, path: $path)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        path: String,
+    }
+
+    pub fn build(fs_name: impl ToString, path: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                path: path.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct ClearDirectory {
+        #[serde(rename(deserialize = "clearDirectory"))]
+        pub clear_directory: bool,
+    }
+
+    pub type Resp = super::Resp<ClearDirectory>;
+}
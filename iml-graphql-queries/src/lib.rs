@@ -2,14 +2,22 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
+pub mod changelog;
+pub mod client;
 pub mod client_mount;
 pub mod filesystem;
+pub mod layout;
 pub mod log;
+pub mod mirror;
+pub mod nodemap;
+pub mod server;
 pub mod server_profile;
 pub mod snapshot;
 pub mod stratagem;
 pub mod target;
 pub mod task;
+pub mod tiering;
+pub mod tunable;
 
 use std::fmt;
 
@@ -0,0 +1,260 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Resp<T> {
+    pub mirror: T,
+}
+
+pub mod stale {
+    use crate::Query;
+    use iml_wire_types::mirror::MirrorFile;
+
+    pub static QUERY: &str = r#"
+        query StaleMirrors($fsName: String!, $path: String!, $limit: Int) {
+          mirror {
+            stale(fsName: $fsName, path: $path, limit: $limit) {
+              path
+              fid
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        path: String,
+        limit: Option<i32>,
+    }
+
+    pub fn build(fs_name: impl ToString, path: impl ToString, limit: Option<i32>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                path: path.to_string(),
+                limit,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Stale {
+        pub stale: Vec<MirrorFile>,
+    }
+
+    pub type Resp = super::Resp<Stale>;
+}
+
+pub mod resync_intervals {
+    use crate::Query;
+    use iml_wire_types::mirror::MirrorResyncInterval;
+
+    pub static QUERY: &str = r#"
+        query MirrorResyncIntervals {
+          mirror {
+            resyncIntervals {
+              id
+              fs_name: fsName
+              path
+              interval
+            }
+          }
+        }
+    "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct ResyncIntervals {
+        #[serde(rename(deserialize = "resyncIntervals"))]
+        pub resync_intervals: Vec<MirrorResyncInterval>,
+    }
+
+    pub type Resp = super::Resp<ResyncIntervals>;
+}
+
+pub mod extend {
+    use crate::Query;
+    use iml_wire_types::Command;
+
+    pub static QUERY: &str = r#"
+        mutation ExtendMirrors($fsName: String!, $pool: String!, $path: String, $expression: String, $striping: String) {
+          mirror {
+            extend(fsName: $fsName, pool: $pool, path: $path, expression: $expression, striping: $striping) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        pool: String,
+        path: Option<String>,
+        expression: Option<String>,
+        striping: Option<String>,
+    }
+
+    pub fn build(
+        fs_name: impl ToString,
+        pool: impl ToString,
+        path: Option<String>,
+        expression: Option<String>,
+        striping: Option<String>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                pool: pool.to_string(),
+                path,
+                expression,
+                striping,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Extend {
+        pub extend: Command,
+    }
+
+    pub type Resp = super::Resp<Extend>;
+}
+
+pub mod resync {
+    use crate::Query;
+    use iml_wire_types::Command;
+
+    pub static QUERY: &str = r#"
+        mutation ResyncMirrors($fsName: String!, $path: String!) {
+          mirror {
+            resync(fsName: $fsName, path: $path) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        path: String,
+    }
+
+    pub fn build(fs_name: impl ToString, path: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                path: path.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Resync {
+        pub resync: Option<Command>,
+    }
+
+    pub type Resp = super::Resp<Resync>;
+}
+
+pub mod create_resync_interval {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation CreateMirrorResyncInterval($fsName: String!, $path: String!, $interval: Duration!) {
+          mirror {
+            createResyncInterval(fsName: $fsName, path: $path, interval: $interval)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        path: String,
+        interval: String,
+    }
+
+    pub fn build(fs_name: impl ToString, path: impl ToString, interval: String) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                path: path.to_string(),
+                interval,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct CreateResyncInterval {
+        #[serde(rename(deserialize = "createResyncInterval"))]
+        pub create_resync_interval: bool,
+    }
+
+    pub type Resp = super::Resp<CreateResyncInterval>;
+}
+
+pub mod remove_resync_interval {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation RemoveMirrorResyncInterval($id: Int!) {
+          mirror {
+            removeResyncInterval(id: $id)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        id: i32,
+    }
+
+    pub fn build(id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct RemoveResyncInterval {
+        #[serde(rename(deserialize = "removeResyncInterval"))]
+        pub remove_resync_interval: bool,
+    }
+
+    pub type Resp = super::Resp<RemoveResyncInterval>;
+}
@@ -0,0 +1,578 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Resp<T> {
+    pub nodemap: T,
+}
+
+pub mod list {
+    use crate::Query;
+    use iml_wire_types::nodemap::Nodemap;
+
+    pub static QUERY: &str = r#"
+        query Nodemaps($fsName: String!) {
+          nodemap {
+            list(fsName: $fsName) {
+              name
+              admin
+              trusted
+              squash_uid: squashUid
+              squash_gid: squashGid
+              ranges {
+                start_nid: startNid
+                end_nid: endNid
+              }
+              idmaps {
+                kind
+                client_id: clientId
+                fs_id: fsId
+              }
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+    }
+
+    pub fn build(fs_name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Nodemaps {
+        pub list: Vec<Nodemap>,
+    }
+
+    pub type Resp = super::Resp<Nodemaps>;
+}
+
+pub mod status {
+    use crate::Query;
+    use iml_wire_types::nodemap::NodemapStatus;
+
+    pub static QUERY: &str = r#"
+        query NodemapStatus($fsName: String!) {
+          nodemap {
+            status(fsName: $fsName) {
+              active
+              desired_active: desiredActive
+              pending
+              unmanaged
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+    }
+
+    pub fn build(fs_name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Status {
+        pub status: NodemapStatus,
+    }
+
+    pub type Resp = super::Resp<Status>;
+}
+
+pub mod create {
+    use crate::Query;
+    use iml_wire_types::nodemap::Nodemap;
+
+    pub static QUERY: &str = r#"
+        mutation CreateNodemap($fsName: String!, $name: String!, $admin: Boolean, $trusted: Boolean, $squashUid: Int, $squashGid: Int, $ranges: [String!]) {
+          nodemap {
+            create(fsName: $fsName, name: $name, admin: $admin, trusted: $trusted, squashUid: $squashUid, squashGid: $squashGid, ranges: $ranges) {
+              name
+              admin
+              trusted
+              squash_uid: squashUid
+              squash_gid: squashGid
+              ranges {
+                start_nid: startNid
+                end_nid: endNid
+              }
+              idmaps {
+                kind
+                client_id: clientId
+                fs_id: fsId
+              }
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        name: String,
+        admin: Option<bool>,
+        trusted: Option<bool>,
+        squash_uid: Option<i32>,
+        squash_gid: Option<i32>,
+        ranges: Option<Vec<String>>,
+    }
+
+    pub fn build(
+        fs_name: impl ToString,
+        name: impl ToString,
+        admin: Option<bool>,
+        trusted: Option<bool>,
+        squash_uid: Option<i32>,
+        squash_gid: Option<i32>,
+        ranges: Option<Vec<String>>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                name: name.to_string(),
+                admin,
+                trusted,
+                squash_uid,
+                squash_gid,
+                ranges,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Create {
+        pub create: Nodemap,
+    }
+
+    pub type Resp = super::Resp<Create>;
+}
+
+pub mod edit {
+    use crate::Query;
+    use iml_wire_types::nodemap::Nodemap;
+
+    pub static QUERY: &str = r#"
+        mutation EditNodemap($fsName: String!, $name: String!, $admin: Boolean, $trusted: Boolean, $squashUid: Int, $squashGid: Int) {
+          nodemap {
+            edit(fsName: $fsName, name: $name, admin: $admin, trusted: $trusted, squashUid: $squashUid, squashGid: $squashGid) {
+              name
+              admin
+              trusted
+              squash_uid: squashUid
+              squash_gid: squashGid
+              ranges {
+                start_nid: startNid
+                end_nid: endNid
+              }
+              idmaps {
+                kind
+                client_id: clientId
+                fs_id: fsId
+              }
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        name: String,
+        admin: Option<bool>,
+        trusted: Option<bool>,
+        squash_uid: Option<i32>,
+        squash_gid: Option<i32>,
+    }
+
+    pub fn build(
+        fs_name: impl ToString,
+        name: impl ToString,
+        admin: Option<bool>,
+        trusted: Option<bool>,
+        squash_uid: Option<i32>,
+        squash_gid: Option<i32>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                name: name.to_string(),
+                admin,
+                trusted,
+                squash_uid,
+                squash_gid,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Edit {
+        pub edit: Nodemap,
+    }
+
+    pub type Resp = super::Resp<Edit>;
+}
+
+pub mod add_range {
+    use crate::Query;
+    use iml_wire_types::nodemap::Nodemap;
+
+    pub static QUERY: &str = r#"
+        mutation AddNodemapRange($fsName: String!, $name: String!, $range: String!) {
+          nodemap {
+            addRange(fsName: $fsName, name: $name, range: $range) {
+              name
+              admin
+              trusted
+              squash_uid: squashUid
+              squash_gid: squashGid
+              ranges {
+                start_nid: startNid
+                end_nid: endNid
+              }
+              idmaps {
+                kind
+                client_id: clientId
+                fs_id: fsId
+              }
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        name: String,
+        range: String,
+    }
+
+    pub fn build(fs_name: impl ToString, name: impl ToString, range: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                name: name.to_string(),
+                range: range.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct AddRange {
+        #[serde(rename(deserialize = "addRange"))]
+        pub add_range: Nodemap,
+    }
+
+    pub type Resp = super::Resp<AddRange>;
+}
+
+pub mod remove_range {
+    use crate::Query;
+    use iml_wire_types::nodemap::Nodemap;
+
+    pub static QUERY: &str = r#"
+        mutation RemoveNodemapRange($fsName: String!, $name: String!, $range: String!) {
+          nodemap {
+            removeRange(fsName: $fsName, name: $name, range: $range) {
+              name
+              admin
+              trusted
+              squash_uid: squashUid
+              squash_gid: squashGid
+              ranges {
+                start_nid: startNid
+                end_nid: endNid
+              }
+              idmaps {
+                kind
+                client_id: clientId
+                fs_id: fsId
+              }
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        name: String,
+        range: String,
+    }
+
+    pub fn build(fs_name: impl ToString, name: impl ToString, range: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                name: name.to_string(),
+                range: range.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct RemoveRange {
+        #[serde(rename(deserialize = "removeRange"))]
+        pub remove_range: Nodemap,
+    }
+
+    pub type Resp = super::Resp<RemoveRange>;
+}
+
+pub mod add_idmap {
+    use crate::Query;
+    use iml_wire_types::nodemap::{Nodemap, NodemapIdKind};
+
+    pub static QUERY: &str = r#"
+        mutation AddNodemapIdmap($fsName: String!, $name: String!, $kind: NodemapIdKind!, $clientId: Int!, $fsId: Int!) {
+          nodemap {
+            addIdmap(fsName: $fsName, name: $name, kind: $kind, clientId: $clientId, fsId: $fsId) {
+              name
+              admin
+              trusted
+              squash_uid: squashUid
+              squash_gid: squashGid
+              ranges {
+                start_nid: startNid
+                end_nid: endNid
+              }
+              idmaps {
+                kind
+                client_id: clientId
+                fs_id: fsId
+              }
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        name: String,
+        kind: NodemapIdKind,
+        client_id: i32,
+        fs_id: i32,
+    }
+
+    pub fn build(
+        fs_name: impl ToString,
+        name: impl ToString,
+        kind: NodemapIdKind,
+        client_id: i32,
+        fs_id: i32,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                name: name.to_string(),
+                kind,
+                client_id,
+                fs_id,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct AddIdmap {
+        #[serde(rename(deserialize = "addIdmap"))]
+        pub add_idmap: Nodemap,
+    }
+
+    pub type Resp = super::Resp<AddIdmap>;
+}
+
+pub mod remove_idmap {
+    use crate::Query;
+    use iml_wire_types::nodemap::{Nodemap, NodemapIdKind};
+
+    pub static QUERY: &str = r#"
+        mutation RemoveNodemapIdmap($fsName: String!, $name: String!, $kind: NodemapIdKind!, $clientId: Int!) {
+          nodemap {
+            removeIdmap(fsName: $fsName, name: $name, kind: $kind, clientId: $clientId) {
+              name
+              admin
+              trusted
+              squash_uid: squashUid
+              squash_gid: squashGid
+              ranges {
+                start_nid: startNid
+                end_nid: endNid
+              }
+              idmaps {
+                kind
+                client_id: clientId
+                fs_id: fsId
+              }
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        name: String,
+        kind: NodemapIdKind,
+        client_id: i32,
+    }
+
+    pub fn build(
+        fs_name: impl ToString,
+        name: impl ToString,
+        kind: NodemapIdKind,
+        client_id: i32,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                name: name.to_string(),
+                kind,
+                client_id,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct RemoveIdmap {
+        #[serde(rename(deserialize = "removeIdmap"))]
+        pub remove_idmap: Nodemap,
+    }
+
+    pub type Resp = super::Resp<RemoveIdmap>;
+}
+
+pub mod remove {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation RemoveNodemap($fsName: String!, $name: String!) {
+          nodemap {
+            remove(fsName: $fsName, name: $name)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        name: String,
+    }
+
+    pub fn build(fs_name: impl ToString, name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Remove {
+        pub remove: bool,
+    }
+
+    pub type Resp = super::Resp<Remove>;
+}
+
+pub mod activate {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation ActivateNodemaps($fsName: String!, $active: Boolean!) {
+          nodemap {
+            activate(fsName: $fsName, active: $active)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        active: bool,
+    }
+
+    pub fn build(fs_name: impl ToString, active: bool) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                active,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Activate {
+        pub activate: bool,
+    }
+
+    pub type Resp = super::Resp<Activate>;
+}
+
+pub mod reconcile {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation ReconcileNodemaps($fsName: String!) {
+          nodemap {
+            reconcile(fsName: $fsName)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+    }
+
+    pub fn build(fs_name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Reconcile {
+        pub reconcile: Vec<String>,
+    }
+
+    pub type Resp = super::Resp<Reconcile>;
+}
@@ -0,0 +1,723 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Resp<T> {
+    pub server: T,
+}
+
+pub mod failover {
+    use crate::Query;
+    use iml_wire_types::Command;
+
+    pub static QUERY: &str = r#"
+        mutation Failover($fqdn: String!) {
+          server {
+            failover(fqdn: $fqdn) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fqdn: String,
+    }
+
+    pub fn build(fqdn: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fqdn: fqdn.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Failover {
+        pub failover: Command,
+    }
+
+    pub type Resp = super::Resp<Failover>;
+}
+
+pub mod failback {
+    use crate::Query;
+    use iml_wire_types::Command;
+
+    pub static QUERY: &str = r#"
+        mutation Failback($fqdn: String!) {
+          server {
+            failback(fqdn: $fqdn) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fqdn: String,
+    }
+
+    pub fn build(fqdn: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fqdn: fqdn.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Failback {
+        pub failback: Command,
+    }
+
+    pub type Resp = super::Resp<Failback>;
+}
+
+pub mod upgrade {
+    use crate::Query;
+    use iml_wire_types::Command;
+
+    pub static QUERY: &str = r#"
+        mutation Upgrade($hosts: [String!]!, $reboot: Boolean) {
+          server {
+            upgrade(hosts: $hosts, reboot: $reboot) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        hosts: Vec<String>,
+        reboot: Option<bool>,
+    }
+
+    pub fn build(hosts: Vec<String>, reboot: Option<bool>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { hosts, reboot }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Upgrade {
+        pub upgrade: Command,
+    }
+
+    pub type Resp = super::Resp<Upgrade>;
+}
+
+pub mod start_scrub {
+    use crate::Query;
+    use iml_wire_types::Command;
+
+    pub static QUERY: &str = r#"
+        mutation StartScrub($fqdn: String!, $zpool: String!) {
+          server {
+            startScrub(fqdn: $fqdn, zpool: $zpool) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fqdn: String,
+        zpool: String,
+    }
+
+    pub fn build(fqdn: impl ToString, zpool: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fqdn: fqdn.to_string(),
+                zpool: zpool.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct StartScrub {
+        #[serde(rename(deserialize = "startScrub"))]
+        pub start_scrub: Command,
+    }
+
+    pub type Resp = super::Resp<StartScrub>;
+}
+
+pub mod stop_scrub {
+    use crate::Query;
+    use iml_wire_types::Command;
+
+    pub static QUERY: &str = r#"
+        mutation StopScrub($fqdn: String!, $zpool: String!) {
+          server {
+            stopScrub(fqdn: $fqdn, zpool: $zpool) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fqdn: String,
+        zpool: String,
+    }
+
+    pub fn build(fqdn: impl ToString, zpool: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fqdn: fqdn.to_string(),
+                zpool: zpool.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct StopScrub {
+        #[serde(rename(deserialize = "stopScrub"))]
+        pub stop_scrub: Command,
+    }
+
+    pub type Resp = super::Resp<StopScrub>;
+}
+
+pub mod create_scrub_interval {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation CreateScrubInterval($fqdn: String!, $zpool: String!, $interval: Duration!) {
+          server {
+            createScrubInterval(fqdn: $fqdn, zpool: $zpool, interval: $interval)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fqdn: String,
+        zpool: String,
+        interval: String,
+    }
+
+    pub fn build(fqdn: impl ToString, zpool: impl ToString, interval: String) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fqdn: fqdn.to_string(),
+                zpool: zpool.to_string(),
+                interval,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct CreateScrubInterval {
+        #[serde(rename(deserialize = "createScrubInterval"))]
+        pub create_scrub_interval: bool,
+    }
+
+    pub type Resp = super::Resp<CreateScrubInterval>;
+}
+
+pub mod remove_scrub_interval {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation RemoveScrubInterval($id: Int!) {
+          server {
+            removeScrubInterval(id: $id)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        id: i32,
+    }
+
+    pub fn build(id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct RemoveScrubInterval {
+        #[serde(rename(deserialize = "removeScrubInterval"))]
+        pub remove_scrub_interval: bool,
+    }
+
+    pub type Resp = super::Resp<RemoveScrubInterval>;
+}
+
+pub mod list_scrub_intervals {
+    use crate::Query;
+    use iml_wire_types::zpool::ScrubInterval;
+
+    pub static QUERY: &str = r#"
+        query ScrubIntervals {
+          server {
+            scrubIntervals {
+              id
+              fqdn
+              zpool
+              interval
+            }
+          }
+        }
+    "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct ScrubIntervals {
+        #[serde(rename(deserialize = "scrubIntervals"))]
+        pub scrub_intervals: Vec<ScrubInterval>,
+    }
+
+    pub type Resp = super::Resp<ScrubIntervals>;
+}
+
+pub mod device_history {
+    use crate::Query;
+    use iml_wire_types::device_history::DeviceHistory;
+
+    pub static QUERY: &str = r#"
+        query DeviceHistory($fqdn: String!, $device_id: String, $limit: Int) {
+          server {
+            deviceHistory(fqdn: $fqdn, deviceId: $device_id, limit: $limit) {
+              id
+              fqdn
+              device_id: deviceId
+              device_type: deviceType
+              change
+              old_value: oldValue
+              new_value: newValue
+              time
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fqdn: String,
+        device_id: Option<String>,
+        limit: Option<i32>,
+    }
+
+    pub fn build(
+        fqdn: impl ToString,
+        device_id: Option<impl ToString>,
+        limit: Option<i32>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fqdn: fqdn.to_string(),
+                device_id: device_id.map(|x| x.to_string()),
+                limit,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct History {
+        #[serde(rename(deserialize = "deviceHistory"))]
+        pub device_history: Vec<DeviceHistory>,
+    }
+
+    pub type Resp = super::Resp<History>;
+}
+
+pub mod add_lnet_network {
+    use crate::Query;
+    use iml_wire_types::Command;
+
+    pub static QUERY: &str = r#"
+        mutation AddLnetNetwork($fqdn: String!, $net: String!, $interfaces: [String!]!, $peer_credits: Int, $credits: Int) {
+          server {
+            addLnetNetwork(fqdn: $fqdn, net: $net, interfaces: $interfaces, peerCredits: $peer_credits, credits: $credits) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fqdn: String,
+        net: String,
+        interfaces: Vec<String>,
+        peer_credits: Option<i32>,
+        credits: Option<i32>,
+    }
+
+    pub fn build(
+        fqdn: impl ToString,
+        net: impl ToString,
+        interfaces: Vec<String>,
+        peer_credits: Option<i32>,
+        credits: Option<i32>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fqdn: fqdn.to_string(),
+                net: net.to_string(),
+                interfaces,
+                peer_credits,
+                credits,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct AddLnetNetwork {
+        #[serde(rename(deserialize = "addLnetNetwork"))]
+        pub add_lnet_network: Command,
+    }
+
+    pub type Resp = super::Resp<AddLnetNetwork>;
+}
+
+pub mod remove_lnet_network {
+    use crate::Query;
+    use iml_wire_types::Command;
+
+    pub static QUERY: &str = r#"
+        mutation RemoveLnetNetwork($fqdn: String!, $net: String!, $interfaces: [String!]) {
+          server {
+            removeLnetNetwork(fqdn: $fqdn, net: $net, interfaces: $interfaces) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fqdn: String,
+        net: String,
+        interfaces: Option<Vec<String>>,
+    }
+
+    pub fn build(
+        fqdn: impl ToString,
+        net: impl ToString,
+        interfaces: Option<Vec<String>>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fqdn: fqdn.to_string(),
+                net: net.to_string(),
+                interfaces,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct RemoveLnetNetwork {
+        #[serde(rename(deserialize = "removeLnetNetwork"))]
+        pub remove_lnet_network: Command,
+    }
+
+    pub type Resp = super::Resp<RemoveLnetNetwork>;
+}
+
+pub mod add_lnet_route {
+    use crate::Query;
+    use iml_wire_types::Command;
+
+    pub static QUERY: &str = r#"
+        mutation AddLnetRoute($fqdn: String!, $net: String!, $gateway: String!, $hop: Int, $priority: Int) {
+          server {
+            addLnetRoute(fqdn: $fqdn, net: $net, gateway: $gateway, hop: $hop, priority: $priority) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fqdn: String,
+        net: String,
+        gateway: String,
+        hop: Option<i32>,
+        priority: Option<i32>,
+    }
+
+    pub fn build(
+        fqdn: impl ToString,
+        net: impl ToString,
+        gateway: impl ToString,
+        hop: Option<i32>,
+        priority: Option<i32>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fqdn: fqdn.to_string(),
+                net: net.to_string(),
+                gateway: gateway.to_string(),
+                hop,
+                priority,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct AddLnetRoute {
+        #[serde(rename(deserialize = "addLnetRoute"))]
+        pub add_lnet_route: Command,
+    }
+
+    pub type Resp = super::Resp<AddLnetRoute>;
+}
+
+pub mod remove_lnet_route {
+    use crate::Query;
+    use iml_wire_types::Command;
+
+    pub static QUERY: &str = r#"
+        mutation RemoveLnetRoute($fqdn: String!, $net: String!, $gateway: String!) {
+          server {
+            removeLnetRoute(fqdn: $fqdn, net: $net, gateway: $gateway) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fqdn: String,
+        net: String,
+        gateway: String,
+    }
+
+    pub fn build(fqdn: impl ToString, net: impl ToString, gateway: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fqdn: fqdn.to_string(),
+                net: net.to_string(),
+                gateway: gateway.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct RemoveLnetRoute {
+        #[serde(rename(deserialize = "removeLnetRoute"))]
+        pub remove_lnet_route: Command,
+    }
+
+    pub type Resp = super::Resp<RemoveLnetRoute>;
+}
+
+pub mod import_lnet_config {
+    use crate::Query;
+    use iml_wire_types::Command;
+
+    pub static QUERY: &str = r#"
+        mutation ImportLnetConfig($fqdn: String!, $config: String!) {
+          server {
+            importLnetConfig(fqdn: $fqdn, config: $config) {
+              cancelled
+              complete
+              created_at: createdAt
+              errored
+              id
+              jobs
+              logs
+              message
+              resource_uri: resourceUri
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fqdn: String,
+        config: String,
+    }
+
+    pub fn build(fqdn: impl ToString, config: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fqdn: fqdn.to_string(),
+                config: config.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct ImportLnetConfig {
+        #[serde(rename(deserialize = "importLnetConfig"))]
+        pub import_lnet_config: Command,
+    }
+
+    pub type Resp = super::Resp<ImportLnetConfig>;
+}
+
+pub mod lnet_config {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        query LnetConfig($fqdn: String!) {
+          server {
+            lnetConfig(fqdn: $fqdn)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        fqdn: String,
+    }
+
+    pub fn build(fqdn: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fqdn: fqdn.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct LnetConfig {
+        #[serde(rename(deserialize = "lnetConfig"))]
+        pub lnet_config: String,
+    }
+
+    pub type Resp = super::Resp<LnetConfig>;
+}
+
+pub mod time_skew {
+    use crate::Query;
+    use iml_wire_types::time::ClusterSkew;
+
+    pub static QUERY: &str = r#"
+        query TimeSkew {
+          server {
+            timeSkew {
+              maxSkew
+              offsets {
+                fqdn
+                offset
+              }
+            }
+          }
+        }
+    "#;
+
+    pub fn build() -> Query<()> {
+        Query {
+            query: QUERY.to_string(),
+            variables: None,
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct TimeSkew {
+        #[serde(rename(deserialize = "timeSkew"))]
+        pub time_skew: ClusterSkew,
+    }
+
+    pub type Resp = super::Resp<TimeSkew>;
+}
@@ -0,0 +1,382 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Resp<T> {
+    pub tiering: T,
+}
+
+pub mod list {
+    use crate::Query;
+    use iml_wire_types::tiering::TieringPolicy;
+
+    pub static QUERY: &str = r#"
+        query TieringPolicies($fsName: String) {
+          tiering {
+            policies(fsName: $fsName) {
+              id
+              fs_name: fsName
+              name
+              hot_pool: hotPool
+              cold_pool: coldPool
+              min_age: minAge
+              heatfn
+              freehi
+              freelo
+              mountpoint
+              applied_at: appliedAt
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: Option<String>,
+    }
+
+    pub fn build(fs_name: Option<impl ToString>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.map(|x| x.to_string()),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Policies {
+        pub policies: Vec<TieringPolicy>,
+    }
+
+    pub type Resp = super::Resp<Policies>;
+}
+
+pub mod status {
+    use crate::Query;
+    use iml_wire_types::tiering::TieringStatus;
+
+    pub static QUERY: &str = r#"
+        query TieringStatus($fsName: String!, $name: String!) {
+          tiering {
+            status(fsName: $fsName, name: $name) {
+              units {
+                kind
+                target
+                host
+                unit
+                state
+                healthy
+              }
+              mailboxes {
+                name
+                state
+                fids_total: fidsTotal
+                fids_completed: fidsCompleted
+                fids_failed: fidsFailed
+              }
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        name: String,
+    }
+
+    pub fn build(fs_name: impl ToString, name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Status {
+        pub status: TieringStatus,
+    }
+
+    pub type Resp = super::Resp<Status>;
+}
+
+pub mod create {
+    use crate::Query;
+    use iml_wire_types::tiering::TieringPolicy;
+
+    pub static QUERY: &str = r#"
+        mutation CreateTieringPolicy($fsName: String!, $name: String!, $hotPool: String!, $coldPool: String!, $minAge: Int!, $heatfn: Int, $freehi: Int!, $freelo: Int!, $mountpoint: String!) {
+          tiering {
+            create(fsName: $fsName, name: $name, hotPool: $hotPool, coldPool: $coldPool, minAge: $minAge, heatfn: $heatfn, freehi: $freehi, freelo: $freelo, mountpoint: $mountpoint) {
+              id
+              fs_name: fsName
+              name
+              hot_pool: hotPool
+              cold_pool: coldPool
+              min_age: minAge
+              heatfn
+              freehi
+              freelo
+              mountpoint
+              applied_at: appliedAt
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        name: String,
+        hot_pool: String,
+        cold_pool: String,
+        min_age: i32,
+        heatfn: Option<i32>,
+        freehi: i32,
+        freelo: i32,
+        mountpoint: String,
+    }
+
+    pub fn build(
+        fs_name: impl ToString,
+        name: impl ToString,
+        hot_pool: impl ToString,
+        cold_pool: impl ToString,
+        min_age: i32,
+        heatfn: Option<i32>,
+        freehi: i32,
+        freelo: i32,
+        mountpoint: impl ToString,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                name: name.to_string(),
+                hot_pool: hot_pool.to_string(),
+                cold_pool: cold_pool.to_string(),
+                min_age,
+                heatfn,
+                freehi,
+                freelo,
+                mountpoint: mountpoint.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Create {
+        pub create: TieringPolicy,
+    }
+
+    pub type Resp = super::Resp<Create>;
+}
+
+pub mod update {
+    use crate::Query;
+    use iml_wire_types::tiering::TieringPolicy;
+
+    pub static QUERY: &str = r#"
+        mutation UpdateTieringPolicy($fsName: String!, $name: String!, $minAge: Int, $heatfn: Int, $freehi: Int, $freelo: Int, $mountpoint: String) {
+          tiering {
+            update(fsName: $fsName, name: $name, minAge: $minAge, heatfn: $heatfn, freehi: $freehi, freelo: $freelo, mountpoint: $mountpoint) {
+              id
+              fs_name: fsName
+              name
+              hot_pool: hotPool
+              cold_pool: coldPool
+              min_age: minAge
+              heatfn
+              freehi
+              freelo
+              mountpoint
+              applied_at: appliedAt
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        name: String,
+        min_age: Option<i32>,
+        heatfn: Option<i32>,
+        freehi: Option<i32>,
+        freelo: Option<i32>,
+        mountpoint: Option<String>,
+    }
+
+    pub fn build(
+        fs_name: impl ToString,
+        name: impl ToString,
+        min_age: Option<i32>,
+        heatfn: Option<i32>,
+        freehi: Option<i32>,
+        freelo: Option<i32>,
+        mountpoint: Option<impl ToString>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                name: name.to_string(),
+                min_age,
+                heatfn,
+                freehi,
+                freelo,
+                mountpoint: mountpoint.map(|x| x.to_string()),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Update {
+        pub update: TieringPolicy,
+    }
+
+    pub type Resp = super::Resp<Update>;
+}
+
+pub mod apply {
+    use crate::Query;
+    use iml_wire_types::tiering::TieringPolicy;
+
+    pub static QUERY: &str = r#"
+        mutation ApplyTieringPolicy($fsName: String!, $name: String!) {
+          tiering {
+            apply(fsName: $fsName, name: $name) {
+              id
+              fs_name: fsName
+              name
+              hot_pool: hotPool
+              cold_pool: coldPool
+              min_age: minAge
+              heatfn
+              freehi
+              freelo
+              mountpoint
+              applied_at: appliedAt
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        name: String,
+    }
+
+    pub fn build(fs_name: impl ToString, name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Apply {
+        pub apply: TieringPolicy,
+    }
+
+    pub type Resp = super::Resp<Apply>;
+}
+
+pub mod stop {
+    use crate::Query;
+    use iml_wire_types::tiering::TieringPolicy;
+
+    pub static QUERY: &str = r#"
+        mutation StopTieringPolicy($fsName: String!, $name: String!) {
+          tiering {
+            stop(fsName: $fsName, name: $name) {
+              id
+              fs_name: fsName
+              name
+              hot_pool: hotPool
+              cold_pool: coldPool
+              min_age: minAge
+              heatfn
+              freehi
+              freelo
+              mountpoint
+              applied_at: appliedAt
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        name: String,
+    }
+
+    pub fn build(fs_name: impl ToString, name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Stop {
+        pub stop: TieringPolicy,
+    }
+
+    pub type Resp = super::Resp<Stop>;
+}
+
+pub mod remove {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation RemoveTieringPolicy($fsName: String!, $name: String!) {
+          tiering {
+            remove(fsName: $fsName, name: $name)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        name: String,
+    }
+
+    pub fn build(fs_name: impl ToString, name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Remove {
+        pub remove: bool,
+    }
+
+    pub type Resp = super::Resp<Remove>;
+}
@@ -0,0 +1,225 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Resp<T> {
+    pub tunable: T,
+}
+
+pub mod list {
+    use crate::Query;
+    use iml_wire_types::tunable::Tunable;
+
+    pub static QUERY: &str = r#"
+        query Tunables($fsName: String!) {
+          tunable {
+            tunables(fsName: $fsName) {
+              id
+              fs_name: fsName
+              param
+              value
+              method
+              target_kind: targetKind
+              host
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+    }
+
+    pub fn build(fs_name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Tunables {
+        pub tunables: Vec<Tunable>,
+    }
+
+    pub type Resp = super::Resp<Tunables>;
+}
+
+pub mod drift {
+    use crate::Query;
+    use iml_wire_types::tunable::TunableDrift;
+
+    pub static QUERY: &str = r#"
+        query TunableDrift($fsName: String) {
+          tunable {
+            drift(fsName: $fsName) {
+              fs_name: fsName
+              host
+              param
+              name
+              desired
+              actual
+              detected_at: detectedAt
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: Option<String>,
+    }
+
+    pub fn build(fs_name: Option<impl ToString>) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.map(|x| x.to_string()),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct Drift {
+        pub drift: Vec<TunableDrift>,
+    }
+
+    pub type Resp = super::Resp<Drift>;
+}
+
+pub mod set {
+    use crate::Query;
+    use iml_wire_types::{
+        db::TargetKind,
+        tunable::{Tunable, TunableMethod},
+    };
+
+    pub static QUERY: &str = r#"
+        mutation SetTunable($fsName: String!, $param: String!, $value: String!, $method: TunableMethod, $targetKind: TargetKind, $host: String) {
+          tunable {
+            setTunable(fsName: $fsName, param: $param, value: $value, method: $method, targetKind: $targetKind, host: $host) {
+              id
+              fs_name: fsName
+              param
+              value
+              method
+              target_kind: targetKind
+              host
+            }
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vars {
+        fs_name: String,
+        param: String,
+        value: String,
+        method: Option<TunableMethod>,
+        target_kind: Option<TargetKind>,
+        host: Option<String>,
+    }
+
+    pub fn build(
+        fs_name: impl ToString,
+        param: impl ToString,
+        value: impl ToString,
+        method: Option<TunableMethod>,
+        target_kind: Option<TargetKind>,
+        host: Option<impl ToString>,
+    ) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+                param: param.to_string(),
+                value: value.to_string(),
+                method,
+                target_kind,
+                host: host.map(|x| x.to_string()),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct SetTunable {
+        #[serde(rename(deserialize = "setTunable"))]
+        pub set_tunable: Tunable,
+    }
+
+    pub type Resp = super::Resp<SetTunable>;
+}
+
+pub mod remove {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation RemoveTunable($id: Int!) {
+          tunable {
+            removeTunable(id: $id)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        id: i32,
+    }
+
+    pub fn build(id: i32) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars { id }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct RemoveTunable {
+        #[serde(rename(deserialize = "removeTunable"))]
+        pub remove_tunable: bool,
+    }
+
+    pub type Resp = super::Resp<RemoveTunable>;
+}
+
+pub mod apply {
+    use crate::Query;
+
+    pub static QUERY: &str = r#"
+        mutation ApplyTunables($fsName: String!) {
+          tunable {
+            applyTunables(fsName: $fsName)
+          }
+        }
+    "#;
+
+    #[derive(Debug, serde::Serialize)]
+    pub struct Vars {
+        #[serde(rename = "fsName")]
+        fs_name: String,
+    }
+
+    pub fn build(fs_name: impl ToString) -> Query<Vars> {
+        Query {
+            query: QUERY.to_string(),
+            variables: Some(Vars {
+                fs_name: fs_name.to_string(),
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, serde::Deserialize)]
+    pub struct ApplyTunables {
+        #[serde(rename(deserialize = "applyTunables"))]
+        pub apply_tunables: bool,
+    }
+
+    pub type Resp = super::Resp<ApplyTunables>;
+}
@@ -4,6 +4,8 @@
 
 pub mod filesystem;
 pub mod filesystems;
+pub mod network;
+pub mod ntp;
 
 #[cfg(feature = "with-db-client")]
 use futures::{future::BoxFuture, FutureExt};
@@ -0,0 +1,141 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use std::collections::BTreeMap;
+
+pub type InfluxResponse = crate::InfluxResponse<ResponseTuple>;
+type ResponseTuple = (
+    String,
+    Option<f64>,
+    Option<f64>,
+    Option<f64>,
+    Option<f64>,
+    Option<f64>,
+    Option<f64>,
+    Option<f64>,
+    Option<f64>,
+);
+
+/// Per second rates of an interface
+#[derive(Default, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct Rates {
+    pub rx_bytes: Option<f64>,
+    pub tx_bytes: Option<f64>,
+    pub rx_packets: Option<f64>,
+    pub tx_packets: Option<f64>,
+    pub rx_errors: Option<f64>,
+    pub tx_errors: Option<f64>,
+    pub rx_drops: Option<f64>,
+    pub tx_drops: Option<f64>,
+}
+
+/// Rates keyed by interface name
+pub type Response = BTreeMap<String, Rates>;
+
+impl From<InfluxResponse> for Response {
+    fn from(response: InfluxResponse) -> Self {
+        response
+            .results
+            .into_iter()
+            .take(1)
+            .filter_map(|result| result.series)
+            .flatten()
+            .filter_map(|s| {
+                let interface = s
+                    .tags
+                    .and_then(|h| h.get("interface").map(|x| x.to_string()))?;
+
+                s.values.into_iter().next().map(
+                    |(
+                        _,
+                        rx_bytes,
+                        tx_bytes,
+                        rx_packets,
+                        tx_packets,
+                        rx_errors,
+                        tx_errors,
+                        rx_drops,
+                        tx_drops,
+                    )| {
+                        (
+                            interface,
+                            Rates {
+                                rx_bytes,
+                                tx_bytes,
+                                rx_packets,
+                                tx_packets,
+                                rx_errors,
+                                tx_errors,
+                                rx_drops,
+                                tx_drops,
+                            },
+                        )
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+/// The latest per second rates of each interface on the host,
+/// derived from the counters written in the last `window`, i.e. `5m`.
+pub fn query(host_id: i32, window: &str) -> String {
+    format!(
+        r#"SELECT LAST(rx_bytes), LAST(tx_bytes), LAST(rx_packets), LAST(tx_packets),
+                  LAST(rx_errors), LAST(tx_errors), LAST(rx_drops), LAST(tx_drops)
+           FROM (
+            SELECT NON_NEGATIVE_DERIVATIVE(rx_bytes, 1s) AS rx_bytes
+                 , NON_NEGATIVE_DERIVATIVE(tx_bytes, 1s) AS tx_bytes
+                 , NON_NEGATIVE_DERIVATIVE(rx_packets, 1s) AS rx_packets
+                 , NON_NEGATIVE_DERIVATIVE(tx_packets, 1s) AS tx_packets
+                 , NON_NEGATIVE_DERIVATIVE(rx_errors, 1s) AS rx_errors
+                 , NON_NEGATIVE_DERIVATIVE(tx_errors, 1s) AS tx_errors
+                 , NON_NEGATIVE_DERIVATIVE(rx_drops, 1s) AS rx_drops
+                 , NON_NEGATIVE_DERIVATIVE(tx_drops, 1s) AS tx_drops
+            FROM net
+            WHERE "host_id" = '{}' AND time > now() - {}
+            GROUP BY interface)
+           GROUP BY interface"#,
+        host_id, window
+    )
+    .split_whitespace()
+    .collect::<Vec<&str>>()
+    .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_from_influx() {
+        let x: InfluxResponse = serde_json::from_value(serde_json::json!({
+            "results": [{
+                "series": [{
+                    "name": "net",
+                    "tags": { "interface": "ib0" },
+                    "columns": ["time", "last", "last_1", "last_2", "last_3", "last_4", "last_5", "last_6", "last_7"],
+                    "values": [["2020-12-23T14:20:00Z", 1048576.0, 524288.0, 100.0, 50.0, 0.0, 0.0, 0.5, null]]
+                }]
+            }]
+        }))
+        .unwrap();
+
+        let x = Response::from(x);
+
+        assert_eq!(
+            x.get("ib0"),
+            Some(&Rates {
+                rx_bytes: Some(1_048_576.0),
+                tx_bytes: Some(524_288.0),
+                rx_packets: Some(100.0),
+                tx_packets: Some(50.0),
+                rx_errors: Some(0.0),
+                tx_errors: Some(0.0),
+                rx_drops: Some(0.5),
+                tx_drops: None,
+            })
+        );
+    }
+}
@@ -0,0 +1,76 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use std::collections::BTreeMap;
+
+pub type InfluxResponse = crate::InfluxResponse<ResponseTuple>;
+type ResponseTuple = (String, Option<f64>);
+
+/// Latest clock offset in seconds keyed by host id
+pub type Response = BTreeMap<i32, f64>;
+
+impl From<InfluxResponse> for Response {
+    fn from(response: InfluxResponse) -> Self {
+        response
+            .results
+            .into_iter()
+            .take(1)
+            .filter_map(|result| result.series)
+            .flatten()
+            .filter_map(|s| {
+                let host_id = s
+                    .tags
+                    .and_then(|h| h.get("host_id").and_then(|x| x.parse().ok()))?;
+
+                let (_, offset) = s.values.into_iter().next()?;
+
+                offset.map(|x| (host_id, x))
+            })
+            .collect()
+    }
+}
+
+/// The latest offset of each host written in the last `window`, i.e. `5m`.
+pub fn query(window: &str) -> String {
+    format!(
+        r#"SELECT LAST(offset)
+           FROM ntp
+           WHERE time > now() - {}
+           GROUP BY host_id"#,
+        window
+    )
+    .split_whitespace()
+    .collect::<Vec<&str>>()
+    .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_from_influx() {
+        let x: InfluxResponse = serde_json::from_value(serde_json::json!({
+            "results": [{
+                "series": [{
+                    "name": "ntp",
+                    "tags": { "host_id": "1" },
+                    "columns": ["time", "last"],
+                    "values": [["2020-12-28T10:00:00Z", -0.000125]]
+                }, {
+                    "name": "ntp",
+                    "tags": { "host_id": "2" },
+                    "columns": ["time", "last"],
+                    "values": [["2020-12-28T10:00:00Z", 0.75]]
+                }]
+            }]
+        }))
+        .unwrap();
+
+        let x = Response::from(x);
+
+        assert_eq!(x.get(&1), Some(&-0.000_125));
+        assert_eq!(x.get(&2), Some(&0.75));
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    api_utils::graphql,
+    display_utils::{display_success, wrap_fut, DisplayType, IntoDisplayType as _},
+    error::ImlManagerCliError,
+};
+use console::Term;
+use iml_graphql_queries::client as client_queries;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum ClientCommand {
+    /// List the clients connected to targets
+    #[structopt(name = "list")]
+    List {
+        /// Only list clients of this filesystem
+        #[structopt(long = "fs")]
+        fsname: Option<String>,
+        /// Only list the client with this NID
+        #[structopt(long = "nid")]
+        nid: Option<String>,
+        /// Display type: json, yaml, tabular
+        #[structopt(short = "d", long = "display", default_value = "tabular")]
+        display_type: DisplayType,
+    },
+    /// Evict a client from each target it is connected to
+    #[structopt(name = "evict")]
+    Evict {
+        /// The NID of the client, i.e. 10.0.0.1@tcp
+        #[structopt(name = "NID")]
+        nid: String,
+        /// Only evict the client from targets of this filesystem
+        #[structopt(long = "fs")]
+        fsname: Option<String>,
+    },
+}
+
+pub async fn client_cli(command: ClientCommand) -> Result<(), ImlManagerCliError> {
+    match command {
+        ClientCommand::List {
+            fsname,
+            nid,
+            display_type,
+        } => {
+            let query = client_queries::connected::build(fsname, nid);
+
+            let resp: iml_graphql_queries::Response<client_queries::connected::Resp> =
+                wrap_fut("Fetching clients...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.client.connected;
+
+            let x = xs.into_display_type(display_type);
+
+            let term = Term::stdout();
+            term.write_line(&x).unwrap();
+        }
+        ClientCommand::Evict { nid, fsname } => {
+            let query = client_queries::evict::build(&nid, fsname);
+
+            let resp: iml_graphql_queries::Response<client_queries::evict::Resp> =
+                wrap_fut("Evicting client...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.client.evict;
+
+            display_success(format!("Evicted {} from {}", nid, xs.join(", ")));
+        }
+    };
+
+    Ok(())
+}
@@ -6,9 +6,20 @@ use chrono_humanize::{Accuracy, HumanTime, Tense};
 use console::style;
 use futures::{Future, FutureExt};
 use iml_wire_types::{
+    changelog::ChangelogUser,
+    client::ConnectedClient,
     db::TargetRecord,
+    device_history::DeviceHistory,
     graphql::ServerProfile,
+    layout::{AppliedLayout, LayoutComponent, LayoutComponentOut, LayoutTemplate},
+    mirror::{MirrorFile, MirrorResyncInterval},
+    nodemap::Nodemap,
+    quota::QuotaUsage,
     snapshot::{ReserveUnit, Snapshot, SnapshotInterval, SnapshotRetention},
+    tiering::{TieringMailbox, TieringPolicy, TieringStatus, TieringUnitStatus},
+    time::ClusterSkew,
+    tunable::{Tunable, TunableDrift},
+    zpool::ScrubInterval,
     Command, Filesystem, Host, OstPool, StratagemConfiguration, StratagemReport,
 };
 use indicatif::ProgressBar;
@@ -160,6 +171,390 @@ impl IntoTable for Vec<SnapshotInterval> {
     }
 }
 
+impl IntoTable for Vec<ScrubInterval> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["Id", "Server", "Zpool", "Interval"],
+            self.into_iter().map(|i| {
+                vec![
+                    i.id.to_string(),
+                    i.fqdn,
+                    i.zpool,
+                    chrono::Duration::from_std(i.interval.0)
+                        .map(HumanTime::from)
+                        .map(|x| x.to_text_en(Accuracy::Precise, Tense::Present))
+                        .unwrap_or_else(|_| "---".to_string()),
+                ]
+            }),
+        )
+    }
+}
+
+impl IntoTable for Vec<MirrorResyncInterval> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["Id", "Filesystem", "Path", "Interval"],
+            self.into_iter().map(|i| {
+                vec![
+                    i.id.to_string(),
+                    i.fs_name,
+                    i.path,
+                    chrono::Duration::from_std(i.interval.0)
+                        .map(HumanTime::from)
+                        .map(|x| x.to_text_en(Accuracy::Precise, Tense::Present))
+                        .unwrap_or_else(|_| "---".to_string()),
+                ]
+            }),
+        )
+    }
+}
+
+impl IntoTable for Vec<MirrorFile> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["Path", "FID"],
+            self.into_iter().map(|x| vec![x.path, x.fid]),
+        )
+    }
+}
+
+impl IntoTable for Vec<DeviceHistory> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["Time", "Device", "Type", "Change", "Old", "New"],
+            self.into_iter().map(|x| {
+                vec![
+                    x.time.to_rfc2822(),
+                    x.device_id,
+                    x.device_type,
+                    x.change.to_string(),
+                    x.old_value.unwrap_or_else(|| "---".to_string()),
+                    x.new_value.unwrap_or_else(|| "---".to_string()),
+                ]
+            }),
+        )
+    }
+}
+
+fn format_layout(xs: Vec<LayoutComponentOut>) -> String {
+    xs.into_iter()
+        .map(|x| LayoutComponent::from(x).to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl IntoTable for Vec<LayoutTemplate> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["Name", "Description", "Components"],
+            self.into_iter()
+                .map(|x| vec![x.name, x.description, format_layout(x.components)]),
+        )
+    }
+}
+
+impl IntoTable for Vec<AppliedLayout> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["Filesystem", "Path", "Template", "Components", "Applied"],
+            self.into_iter().map(|x| {
+                vec![
+                    x.fs_name,
+                    x.path,
+                    x.template.unwrap_or_else(|| "---".to_string()),
+                    format_layout(x.components),
+                    x.applied_at.to_rfc2822(),
+                ]
+            }),
+        )
+    }
+}
+
+fn format_quota_limit(x: f64, formatter: fn(f64, Option<usize>) -> String) -> String {
+    if x > 0.0 {
+        formatter(x, Some(0))
+    } else {
+        "---".to_string()
+    }
+}
+
+impl IntoTable for Vec<QuotaUsage> {
+    fn into_table(self) -> Table {
+        let grace = |x: Option<chrono::DateTime<chrono::Utc>>| {
+            x.map(|x| x.to_rfc2822())
+                .unwrap_or_else(|| "---".to_string())
+        };
+
+        generate_table(
+            &[
+                "Kind",
+                "Id",
+                "Pool",
+                "Space Used",
+                "Soft",
+                "Hard",
+                "Grace Expires",
+                "Inodes Used",
+                "Soft",
+                "Hard",
+                "Grace Expires",
+            ],
+            self.into_iter().map(|x| {
+                let kbytes = |x: f64| format_quota_limit(x * 1024.0, format_bytes);
+
+                vec![
+                    x.kind.to_string(),
+                    match x.quota_id.0 {
+                        0 => "default".to_string(),
+                        id => id.to_string(),
+                    },
+                    x.pool.unwrap_or_else(|| "---".to_string()),
+                    format_bytes(x.kbytes_used * 1024.0, Some(0)),
+                    kbytes(x.kbytes_soft),
+                    kbytes(x.kbytes_hard),
+                    grace(x.block_grace_expires),
+                    format_number(x.inodes_used, Some(0)),
+                    format_quota_limit(x.inodes_soft, format_number),
+                    format_quota_limit(x.inodes_hard, format_number),
+                    grace(x.inode_grace_expires),
+                ]
+            }),
+        )
+    }
+}
+
+impl IntoTable for Vec<ChangelogUser> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &[
+                "Target",
+                "Id",
+                "Index",
+                "Current Index",
+                "Records Behind",
+                "Last Cleared",
+            ],
+            self.into_iter().map(|x| {
+                vec![
+                    x.target,
+                    x.id,
+                    format_number(x.index, Some(0)),
+                    format_number(x.current_index, Some(0)),
+                    format_number(x.lag, Some(0)),
+                    x.index_updated_at.to_rfc2822(),
+                ]
+            }),
+        )
+    }
+}
+
+impl IntoTable for Vec<ConnectedClient> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["NID", "Filesystem", "Targets", "Last Seen"],
+            self.into_iter().map(|x| {
+                vec![
+                    x.nid,
+                    x.fs_name,
+                    x.targets.join(", "),
+                    x.last_seen.to_rfc2822(),
+                ]
+            }),
+        )
+    }
+}
+
+impl IntoTable for Vec<Nodemap> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["Name", "Admin", "Trusted", "Squash", "Ranges", "Id Maps"],
+            self.into_iter().map(|x| {
+                vec![
+                    x.name,
+                    x.admin.to_string(),
+                    x.trusted.to_string(),
+                    format!("{}:{}", x.squash_uid, x.squash_gid),
+                    x.ranges
+                        .iter()
+                        .map(|x| x.to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    x.idmaps
+                        .iter()
+                        .map(|x| format!("{} {}:{}", x.kind, x.client_id, x.fs_id))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                ]
+            }),
+        )
+    }
+}
+
+impl IntoTable for Vec<TieringPolicy> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &[
+                "Filesystem",
+                "Name",
+                "Hot Pool",
+                "Cold Pool",
+                "Min Age",
+                "Heat Fn",
+                "Free Hi",
+                "Free Lo",
+                "Mountpoint",
+                "Applied",
+            ],
+            self.into_iter().map(|x| {
+                vec![
+                    x.fs_name,
+                    x.name,
+                    x.hot_pool,
+                    x.cold_pool,
+                    x.min_age.to_string(),
+                    x.heatfn
+                        .map(|x| x.to_string())
+                        .unwrap_or_else(|| "---".to_string()),
+                    format!("{}%", x.freehi),
+                    format!("{}%", x.freelo),
+                    x.mountpoint,
+                    x.applied_at
+                        .map(|x| x.to_rfc2822())
+                        .unwrap_or_else(|| "---".to_string()),
+                ]
+            }),
+        )
+    }
+}
+
+impl IntoTable for Vec<TieringUnitStatus> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["Kind", "Target", "Host", "Unit", "State"],
+            self.into_iter().map(|x| {
+                let state = if x.healthy {
+                    style(x.state).green()
+                } else {
+                    style(x.state).red()
+                };
+
+                vec![
+                    x.kind.to_string(),
+                    x.target,
+                    x.host,
+                    x.unit,
+                    state.to_string(),
+                ]
+            }),
+        )
+    }
+}
+
+impl IntoTable for Vec<TieringMailbox> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["Mailbox", "State", "FIDs", "Completed", "Failed"],
+            self.into_iter().map(|x| {
+                vec![
+                    x.name,
+                    x.state,
+                    format_number(x.fids_total, Some(0)),
+                    format_number(x.fids_completed, Some(0)),
+                    format_number(x.fids_failed, Some(0)),
+                ]
+            }),
+        )
+    }
+}
+
+impl IntoTable for TieringStatus {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["Units", "Mailboxes"],
+            vec![vec![
+                self.units.into_table().to_string(),
+                self.mailboxes.into_table().to_string(),
+            ]],
+        )
+    }
+}
+
+impl IsEmpty for TieringStatus {
+    fn is_empty(&self) -> bool {
+        self.units.is_empty() && self.mailboxes.is_empty()
+    }
+}
+
+impl IntoTable for Vec<Tunable> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &["Id", "Param", "Value", "Method", "Target Kind", "Host"],
+            self.into_iter().map(|x| {
+                vec![
+                    x.id.to_string(),
+                    x.param,
+                    x.value,
+                    x.method.to_string(),
+                    x.target_kind
+                        .map(|x| x.to_string())
+                        .unwrap_or_else(|| "---".to_string()),
+                    x.host.unwrap_or_else(|| "---".to_string()),
+                ]
+            }),
+        )
+    }
+}
+
+impl IntoTable for Vec<TunableDrift> {
+    fn into_table(self) -> Table {
+        generate_table(
+            &[
+                "Filesystem",
+                "Host",
+                "Param",
+                "Name",
+                "Desired",
+                "Actual",
+                "Detected",
+            ],
+            self.into_iter().map(|x| {
+                vec![
+                    x.fs_name,
+                    x.host,
+                    x.param,
+                    x.name,
+                    x.desired,
+                    x.actual,
+                    x.detected_at.to_rfc2822(),
+                ]
+            }),
+        )
+    }
+}
+
+impl IsEmpty for ClusterSkew {
+    fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+}
+
+impl IntoTable for ClusterSkew {
+    fn into_table(self) -> Table {
+        let max_skew = self
+            .max_skew
+            .map(|x| format!("{}s", x))
+            .unwrap_or_else(|| "---".to_string());
+
+        generate_table(
+            &["Server", "Offset"],
+            self.offsets
+                .into_iter()
+                .map(|x| vec![x.fqdn, format!("{}s", x.offset)])
+                .chain(std::iter::once(vec!["Max skew".to_string(), max_skew])),
+        )
+    }
+}
+
 impl IntoTable for Vec<SnapshotRetention> {
     fn into_table(self) -> Table {
         generate_table(
@@ -6,14 +6,21 @@ use crate::{
     api_utils::{get_all, get_hosts, get_influx, get_one, graphql, put, wait_for_cmds_success},
     display_utils::{usage, wrap_fut, DisplayType, IntoDisplayType as _},
     error::ImlManagerCliError,
+    layout::{layout_cli, LayoutCommand},
+    mirror::{mirror_cli, MirrorCommand},
+    nodemap::{nodemap_cli, NodemapCommand},
     ostpool::{ostpool_cli, OstPoolCommand},
+    quota::{quota_cli, QuotaCommand},
+    tiering::{tiering_cli, TieringCommand},
+    tunable::{tunable_cli, TunableCommand},
 };
 use console::Term;
 use futures::future::{try_join, try_join5};
 use iml_graphql_queries::{client_mount, filesystem as fs_queries, target as target_queries};
-use iml_wire_types::{db::TargetKind, CmdWrapper, Filesystem};
+use iml_wire_types::{db::TargetKind, filesystem::TargetDevice, CmdWrapper, Filesystem, FsType};
 use number_formatter::{format_bytes, format_number};
 use prettytable::{Row, Table};
+use std::convert::TryFrom;
 use structopt::StructOpt;
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -42,6 +49,79 @@ pub enum FilesystemCommand {
         #[structopt(subcommand)]
         command: OstPoolCommand,
     },
+    /// User, group and project quotas
+    #[structopt(name = "quota")]
+    Quota {
+        #[structopt(subcommand)]
+        command: QuotaCommand,
+    },
+    /// Directory default layouts
+    #[structopt(name = "layout")]
+    Layout {
+        #[structopt(subcommand)]
+        command: LayoutCommand,
+    },
+    /// FLR mirrors
+    #[structopt(name = "mirror")]
+    Mirror {
+        #[structopt(subcommand)]
+        command: MirrorCommand,
+    },
+    /// Nodemaps and client access control
+    #[structopt(name = "nodemap")]
+    Nodemap {
+        #[structopt(subcommand)]
+        command: NodemapCommand,
+    },
+    /// Hot / cold pool tiering policies
+    #[structopt(name = "tiering")]
+    Tiering {
+        #[structopt(subcommand)]
+        command: TieringCommand,
+    },
+    /// Persistent Lustre tunables
+    #[structopt(name = "tunable")]
+    Tunable {
+        #[structopt(subcommand)]
+        command: TunableCommand,
+    },
+    /// Create a new filesystem.
+    /// Devices are given as `host:path`, where `path` is a path of the device on `host`.
+    /// Each device must be unused and shared with another host of the same cluster
+    #[structopt(name = "create")]
+    Create {
+        #[structopt(name = "FSNAME")]
+        fs_name: String,
+        /// The MGT device. A device already formatted as an MGT is reused
+        #[structopt(long = "mgs", parse(try_from_str = parse_target_device))]
+        mgs: TargetDevice,
+        /// An MDT device. Repeat for each MDT, in index order
+        #[structopt(
+            long = "mdt",
+            required = true,
+            number_of_values = 1,
+            parse(try_from_str = parse_target_device)
+        )]
+        mdts: Vec<TargetDevice>,
+        /// An OST device. Repeat for each OST, in index order
+        #[structopt(
+            long = "ost",
+            required = true,
+            number_of_values = 1,
+            parse(try_from_str = parse_target_device)
+        )]
+        osts: Vec<TargetDevice>,
+        /// The backing filesystem of the targets: ldiskfs, zfs
+        #[structopt(
+            long = "backfs",
+            default_value = "ldiskfs",
+            parse(try_from_str = parse_backfs)
+        )]
+        backfs: FsType,
+        /// Passed to `mkfs.lustre` as `--mkfsoptions`
+        #[structopt(long = "mkfs-options")]
+        mkfs_options: Option<String>,
+    },
     /// Detect existing filesystem
     #[structopt(name = "detect")]
     Detect,
@@ -65,6 +145,43 @@ fn option_sub(a: Option<u64>, b: Option<u64>) -> Option<u64> {
     Some(a?.saturating_sub(b?))
 }
 
+fn parse_target_device(src: &str) -> Result<TargetDevice, ImlManagerCliError> {
+    match src.find(':') {
+        Some(i) if i > 0 && i + 1 < src.len() => Ok(TargetDevice {
+            host: src[..i].to_string(),
+            path: src[i + 1..].to_string(),
+        }),
+        _ => Err(ImlManagerCliError::ApiError(format!(
+            "Invalid device {}. Expected host:path",
+            src
+        ))),
+    }
+}
+
+fn parse_backfs(src: &str) -> Result<FsType, &'static str> {
+    FsType::try_from(src)
+}
+
+async fn create_filesystem(
+    fs_name: String,
+    mgs: TargetDevice,
+    mdts: Vec<TargetDevice>,
+    osts: Vec<TargetDevice>,
+    backfs: FsType,
+    mkfs_options: Option<String>,
+) -> Result<(), ImlManagerCliError> {
+    let query = fs_queries::create::build(&fs_name, mgs, mdts, osts, Some(backfs), mkfs_options);
+
+    let resp: iml_graphql_queries::Response<fs_queries::create::Resp> =
+        wrap_fut("Creating Filesystem...", graphql(query)).await?;
+
+    let x = Result::from(resp)?.data.filesystem.create;
+
+    wait_for_cmds_success(&[x]).await?;
+
+    detect_filesystem().await
+}
+
 async fn detect_filesystem() -> Result<(), ImlManagerCliError> {
     let query = fs_queries::detect::build();
 
@@ -234,6 +351,20 @@ pub async fn filesystem_cli(command: FilesystemCommand) -> Result<(), ImlManager
             term.write_line(&cmd).unwrap();
         }
         FilesystemCommand::Pool { command } => ostpool_cli(command).await?,
+        FilesystemCommand::Quota { command } => quota_cli(command).await?,
+        FilesystemCommand::Layout { command } => layout_cli(command).await?,
+        FilesystemCommand::Mirror { command } => mirror_cli(command).await?,
+        FilesystemCommand::Nodemap { command } => nodemap_cli(command).await?,
+        FilesystemCommand::Tiering { command } => tiering_cli(command).await?,
+        FilesystemCommand::Tunable { command } => tunable_cli(command).await?,
+        FilesystemCommand::Create {
+            fs_name,
+            mgs,
+            mdts,
+            osts,
+            backfs,
+            mkfs_options,
+        } => create_filesystem(fs_name, mgs, mdts, osts, backfs, mkfs_options).await?,
         FilesystemCommand::Detect => detect_filesystem().await?,
         FilesystemCommand::Forget { fs_name } => forget_filesystem(fs_name).await?,
     };
@@ -0,0 +1,240 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    api_utils::graphql,
+    display_utils::{display_success, wrap_fut, DisplayType, IntoDisplayType as _},
+    error::ImlManagerCliError,
+};
+use console::Term;
+use iml_graphql_queries::layout as layout_queries;
+use iml_wire_types::layout::{LayoutComponent, LayoutComponentInput};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum LayoutCommand {
+    /// List the default layouts set on directories
+    #[structopt(name = "list")]
+    List {
+        #[structopt(name = "FSNAME")]
+        fsname: Option<String>,
+        /// Display type: json, yaml, tabular
+        #[structopt(short = "d", long = "display", default_value = "tabular")]
+        display_type: DisplayType,
+    },
+    /// Show the current default layout of a directory
+    #[structopt(name = "show")]
+    Show {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        /// The directory, relative to the filesystem root
+        #[structopt(name = "PATH")]
+        path: String,
+    },
+    /// Set the default layout of a directory.
+    /// Components are given in extent order as key=value lists, i.e.
+    /// `end=1M,dom end=64M,count=1,pool=flash end=eof,count=-1,size=4M`
+    #[structopt(name = "set")]
+    Set {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        /// The directory, relative to the filesystem root
+        #[structopt(name = "PATH")]
+        path: String,
+        #[structopt(name = "COMPONENT", required = true, min_values = 1)]
+        components: Vec<LayoutComponent>,
+    },
+    /// Remove the default layout of a directory
+    #[structopt(name = "clear")]
+    Clear {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        /// The directory, relative to the filesystem root
+        #[structopt(name = "PATH")]
+        path: String,
+    },
+    /// Set the default layout of a directory from a template
+    #[structopt(name = "apply")]
+    Apply {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        /// The directory, relative to the filesystem root
+        #[structopt(name = "PATH")]
+        path: String,
+        #[structopt(name = "TEMPLATE")]
+        template: String,
+    },
+    /// Layout templates
+    #[structopt(name = "template")]
+    Template {
+        #[structopt(subcommand)]
+        command: TemplateCommand,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum TemplateCommand {
+    /// List layout templates
+    #[structopt(name = "list")]
+    List {
+        /// Display type: json, yaml, tabular
+        #[structopt(short = "d", long = "display", default_value = "tabular")]
+        display_type: DisplayType,
+    },
+    /// Create a layout template.
+    /// Components are given in extent order as key=value lists, i.e.
+    /// `end=1M,dom end=64M,count=1,pool=flash end=eof,count=-1,size=4M`
+    #[structopt(name = "create")]
+    Create {
+        #[structopt(name = "NAME")]
+        name: String,
+        /// What the layout is meant for
+        #[structopt(long = "description")]
+        description: Option<String>,
+        #[structopt(name = "COMPONENT", required = true, min_values = 1)]
+        components: Vec<LayoutComponent>,
+    },
+    /// Remove a layout template
+    #[structopt(name = "remove")]
+    Remove {
+        #[structopt(name = "NAME")]
+        name: String,
+    },
+}
+
+fn to_input(xs: Vec<LayoutComponent>) -> Vec<LayoutComponentInput> {
+    xs.into_iter().map(Into::into).collect()
+}
+
+async fn template_cli(command: TemplateCommand) -> Result<(), ImlManagerCliError> {
+    match command {
+        TemplateCommand::List { display_type } => {
+            let query = layout_queries::templates::build();
+
+            let resp: iml_graphql_queries::Response<layout_queries::templates::Resp> =
+                wrap_fut("Fetching layout templates...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.layout.templates;
+
+            let x = xs.into_display_type(display_type);
+
+            let term = Term::stdout();
+            term.write_line(&x).unwrap();
+        }
+        TemplateCommand::Create {
+            name,
+            description,
+            components,
+        } => {
+            let query =
+                layout_queries::create_template::build(&name, description, to_input(components));
+
+            let resp: iml_graphql_queries::Response<layout_queries::create_template::Resp> =
+                wrap_fut("Creating layout template...", graphql(query)).await?;
+
+            let _ = Result::from(resp)?;
+
+            display_success(format!("Created layout template {}", name));
+        }
+        TemplateCommand::Remove { name } => {
+            let query = layout_queries::remove_template::build(&name);
+
+            let resp: iml_graphql_queries::Response<layout_queries::remove_template::Resp> =
+                wrap_fut("Removing layout template...", graphql(query)).await?;
+
+            let _ = Result::from(resp)?;
+
+            display_success(format!("Removed layout template {}", name));
+        }
+    };
+
+    Ok(())
+}
+
+pub async fn layout_cli(command: LayoutCommand) -> Result<(), ImlManagerCliError> {
+    match command {
+        LayoutCommand::List {
+            fsname,
+            display_type,
+        } => {
+            let query = layout_queries::applied::build(fsname);
+
+            let resp: iml_graphql_queries::Response<layout_queries::applied::Resp> =
+                wrap_fut("Fetching layouts...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.layout.applied;
+
+            let x = xs.into_display_type(display_type);
+
+            let term = Term::stdout();
+            term.write_line(&x).unwrap();
+        }
+        LayoutCommand::Show { fsname, path } => {
+            let query = layout_queries::directory::build(&fsname, &path);
+
+            let resp: iml_graphql_queries::Response<layout_queries::directory::Resp> =
+                wrap_fut("Fetching layout...", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.layout.directory;
+
+            let term = Term::stdout();
+
+            match x {
+                Some(xs) => {
+                    for x in xs {
+                        term.write_line(&LayoutComponent::from(x).to_string())
+                            .unwrap();
+                    }
+                }
+                None => term
+                    .write_line(&format!("{} has no default layout", path))
+                    .unwrap(),
+            }
+        }
+        LayoutCommand::Set {
+            fsname,
+            path,
+            components,
+        } => {
+            let query = layout_queries::set_directory::build(&fsname, &path, to_input(components));
+
+            let resp: iml_graphql_queries::Response<layout_queries::set_directory::Resp> =
+                wrap_fut("Setting layout...", graphql(query)).await?;
+
+            let _ = Result::from(resp)?;
+
+            display_success(format!("Set layout of {} on {}", path, fsname));
+        }
+        LayoutCommand::Clear { fsname, path } => {
+            let query = layout_queries::clear_directory::build(&fsname, &path);
+
+            let resp: iml_graphql_queries::Response<layout_queries::clear_directory::Resp> =
+                wrap_fut("Clearing layout...", graphql(query)).await?;
+
+            let _ = Result::from(resp)?;
+
+            display_success(format!("Cleared layout of {} on {}", path, fsname));
+        }
+        LayoutCommand::Apply {
+            fsname,
+            path,
+            template,
+        } => {
+            let query = layout_queries::apply_template::build(&fsname, &path, &template);
+
+            let resp: iml_graphql_queries::Response<layout_queries::apply_template::Resp> =
+                wrap_fut("Applying layout template...", graphql(query)).await?;
+
+            let _ = Result::from(resp)?;
+
+            display_success(format!(
+                "Applied layout template {} to {} on {}",
+                template, path, fsname
+            ));
+        }
+        LayoutCommand::Template { command } => template_cli(command).await?,
+    };
+
+    Ok(())
+}
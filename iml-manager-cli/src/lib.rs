@@ -4,16 +4,23 @@
 
 pub mod api;
 pub mod api_utils;
+pub mod client;
 pub mod display_utils;
 pub mod error;
 pub mod filesystem;
+pub mod layout;
+pub mod mirror;
 pub mod nginx;
+pub mod nodemap;
 pub mod ostpool;
 pub mod profile;
+pub mod quota;
 pub mod server;
 pub mod snapshot;
 pub mod stratagem;
 pub mod target;
+pub mod tiering;
+pub mod tunable;
 pub mod update_repo_file;
 
 use std::{collections::BTreeSet, env};
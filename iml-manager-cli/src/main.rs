@@ -4,6 +4,7 @@
 
 use iml_manager_cli::{
     api::{self, api_cli, graphql_cli},
+    client::{self, client_cli},
     display_utils::display_error,
     filesystem::{self, filesystem_cli},
     selfname,
@@ -27,6 +28,12 @@ pub enum App {
         #[structopt(subcommand)]
         command: stratagem::StratagemCommand,
     },
+    #[structopt(name = "client")]
+    /// Work with Lustre clients
+    Client {
+        #[structopt(subcommand)]
+        command: client::ClientCommand,
+    },
     #[structopt(name = "server")]
     /// Work with Storage Servers
     Server {
@@ -95,6 +102,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let r = match matches {
         App::DebugApi(command) => api_cli(command).await,
         App::DebugQl(command) => graphql_cli(command).await,
+        App::Client { command } => client_cli(command).await,
         App::Filesystem { command } => filesystem_cli(command).await,
         App::Server { command } => server_cli(command).await,
         App::Snapshot { command } => snapshot_cli(command).await,
@@ -0,0 +1,178 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    api_utils::{graphql, wait_for_cmds_success},
+    display_utils::{display_success, wrap_fut, DisplayType, IntoDisplayType as _},
+    error::ImlManagerCliError,
+};
+use console::Term;
+use iml_graphql_queries::mirror as mirror_queries;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum MirrorCommand {
+    /// Add a mirror on an OST pool to the files under a directory
+    /// or matching a Stratagem expression
+    #[structopt(name = "extend")]
+    Extend {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        /// The OST pool to add mirrors on
+        #[structopt(name = "POOL")]
+        pool: String,
+        /// Mirror the files under this directory, from the filesystem root
+        #[structopt(long = "path", required_unless = "expression")]
+        path: Option<String>,
+        /// Mirror the files matching this Stratagem expression
+        #[structopt(long = "expression", conflicts_with = "path")]
+        expression: Option<String>,
+        /// Striping options of the new mirrors, i.e. "-c 2 -S 4M"
+        #[structopt(long = "striping")]
+        striping: Option<String>,
+    },
+    /// Resync the stale mirrors of the files under a directory
+    #[structopt(name = "resync")]
+    Resync {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        /// The directory, from the filesystem root
+        #[structopt(name = "PATH")]
+        path: String,
+    },
+    /// List the files under a directory with stale mirrors
+    #[structopt(name = "stale")]
+    Stale {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        /// The directory, from the filesystem root
+        #[structopt(name = "PATH")]
+        path: String,
+        /// The maximum number of files to list
+        #[structopt(long = "limit")]
+        limit: Option<i32>,
+        /// Display type: json, yaml, tabular
+        #[structopt(short = "d", long = "display", default_value = "tabular")]
+        display_type: DisplayType,
+    },
+    /// Resync the stale mirrors under a directory on a recurring interval
+    #[structopt(name = "schedule")]
+    Schedule {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        /// The directory, from the filesystem root
+        #[structopt(name = "PATH")]
+        path: String,
+        /// Resync interval in human form, e. g. 1day
+        #[structopt(required = true, min_values = 1)]
+        interval: Vec<String>,
+    },
+    /// Remove mirror resync intervals
+    #[structopt(name = "unschedule")]
+    Unschedule {
+        /// The ids of the resync intervals to remove
+        #[structopt(required = true, min_values = 1)]
+        ids: Vec<i32>,
+    },
+    /// List mirror resync intervals
+    #[structopt(name = "intervals")]
+    Intervals {
+        /// Display type: json, yaml, tabular
+        #[structopt(short = "d", long = "display", default_value = "tabular")]
+        display_type: DisplayType,
+    },
+}
+
+pub async fn mirror_cli(command: MirrorCommand) -> Result<(), ImlManagerCliError> {
+    match command {
+        MirrorCommand::Extend {
+            fsname,
+            pool,
+            path,
+            expression,
+            striping,
+        } => {
+            let query = mirror_queries::extend::build(&fsname, &pool, path, expression, striping);
+
+            let resp: iml_graphql_queries::Response<mirror_queries::extend::Resp> =
+                wrap_fut("Finding files to mirror...", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.mirror.extend;
+
+            wait_for_cmds_success(&[x]).await?;
+        }
+        MirrorCommand::Resync { fsname, path } => {
+            let query = mirror_queries::resync::build(&fsname, &path);
+
+            let resp: iml_graphql_queries::Response<mirror_queries::resync::Resp> =
+                wrap_fut("Finding stale mirrors...", graphql(query)).await?;
+
+            match Result::from(resp)?.data.mirror.resync {
+                Some(x) => {
+                    wait_for_cmds_success(&[x]).await?;
+                }
+                None => display_success(format!("No stale mirrors under {}", path)),
+            }
+        }
+        MirrorCommand::Stale {
+            fsname,
+            path,
+            limit,
+            display_type,
+        } => {
+            let query = mirror_queries::stale::build(&fsname, &path, limit);
+
+            let resp: iml_graphql_queries::Response<mirror_queries::stale::Resp> =
+                wrap_fut("Finding stale mirrors...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.mirror.stale;
+
+            let x = xs.into_display_type(display_type);
+
+            let term = Term::stdout();
+            term.write_line(&x).unwrap();
+        }
+        MirrorCommand::Schedule {
+            fsname,
+            path,
+            interval,
+        } => {
+            let query =
+                mirror_queries::create_resync_interval::build(&fsname, &path, interval.join(" "));
+
+            let resp: iml_graphql_queries::Response<mirror_queries::create_resync_interval::Resp> =
+                graphql(query).await?;
+
+            let _ = Result::from(resp)?;
+
+            display_success(format!("Scheduled resync of mirrors under {}", path));
+        }
+        MirrorCommand::Unschedule { ids } => {
+            for id in ids {
+                let query = mirror_queries::remove_resync_interval::build(id);
+
+                let resp: iml_graphql_queries::Response<
+                    mirror_queries::remove_resync_interval::Resp,
+                > = graphql(query).await?;
+
+                let _ = Result::from(resp)?;
+            }
+        }
+        MirrorCommand::Intervals { display_type } => {
+            let query = mirror_queries::resync_intervals::build();
+
+            let resp: iml_graphql_queries::Response<mirror_queries::resync_intervals::Resp> =
+                graphql(query).await?;
+
+            let xs = Result::from(resp)?.data.mirror.resync_intervals;
+
+            let x = xs.into_display_type(display_type);
+
+            let term = Term::stdout();
+            term.write_line(&x).unwrap();
+        }
+    };
+
+    Ok(())
+}
@@ -0,0 +1,371 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    api_utils::graphql,
+    display_utils::{display_success, wrap_fut, DisplayType, IntoDisplayType as _},
+    error::ImlManagerCliError,
+};
+use console::Term;
+use iml_graphql_queries::nodemap as nodemap_queries;
+use iml_wire_types::nodemap::NodemapIdKind;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum NodemapCommand {
+    /// List the nodemaps on the MGS of a filesystem, shared by all filesystems using it
+    #[structopt(name = "list")]
+    List {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        /// Display type: json, yaml, tabular
+        #[structopt(short = "d", long = "display", default_value = "tabular")]
+        display_type: DisplayType,
+    },
+    /// Compare the desired nodemaps on the MGS of a filesystem with those set on it
+    #[structopt(name = "status")]
+    Status {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+    },
+    /// Create a nodemap
+    #[structopt(name = "create")]
+    Create {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        #[structopt(name = "NAME")]
+        name: String,
+        /// Do not squash root on clients of the nodemap
+        #[structopt(long = "admin")]
+        admin: bool,
+        /// Use client ids as is, without mapping
+        #[structopt(long = "trusted")]
+        trusted: bool,
+        /// The uid unmapped users are squashed to
+        #[structopt(long = "squash-uid")]
+        squash_uid: Option<i32>,
+        /// The gid unmapped groups are squashed to
+        #[structopt(long = "squash-gid")]
+        squash_gid: Option<i32>,
+        /// Client NID range, i.e. 192.168.1.[1-100]@tcp. May be given more than once
+        #[structopt(short = "r", long = "range", number_of_values = 1)]
+        ranges: Vec<String>,
+    },
+    /// Change the properties of a nodemap
+    #[structopt(name = "edit")]
+    Edit {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        #[structopt(name = "NAME")]
+        name: String,
+        /// Do not squash root on clients of the nodemap: true, false
+        #[structopt(long = "admin")]
+        admin: Option<bool>,
+        /// Use client ids as is, without mapping: true, false
+        #[structopt(long = "trusted")]
+        trusted: Option<bool>,
+        /// The uid unmapped users are squashed to
+        #[structopt(long = "squash-uid")]
+        squash_uid: Option<i32>,
+        /// The gid unmapped groups are squashed to
+        #[structopt(long = "squash-gid")]
+        squash_gid: Option<i32>,
+    },
+    /// Add a client NID range to a nodemap
+    #[structopt(name = "add-range")]
+    AddRange {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        #[structopt(name = "NAME")]
+        name: String,
+        /// The range, i.e. 192.168.1.[1-100]@tcp
+        #[structopt(name = "RANGE")]
+        range: String,
+    },
+    /// Remove a client NID range from a nodemap
+    #[structopt(name = "remove-range")]
+    RemoveRange {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        #[structopt(name = "NAME")]
+        name: String,
+        #[structopt(name = "RANGE")]
+        range: String,
+    },
+    /// Map a client id to a filesystem id
+    #[structopt(name = "add-idmap")]
+    AddIdmap {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        #[structopt(name = "NAME")]
+        name: String,
+        /// The kind of id: uid, gid
+        #[structopt(name = "KIND")]
+        kind: NodemapIdKind,
+        #[structopt(name = "CLIENT_ID")]
+        client_id: i32,
+        #[structopt(name = "FS_ID")]
+        fs_id: i32,
+    },
+    /// Remove the mapping of a client id
+    #[structopt(name = "remove-idmap")]
+    RemoveIdmap {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        #[structopt(name = "NAME")]
+        name: String,
+        /// The kind of id: uid, gid
+        #[structopt(name = "KIND")]
+        kind: NodemapIdKind,
+        #[structopt(name = "CLIENT_ID")]
+        client_id: i32,
+    },
+    /// Remove a nodemap
+    #[structopt(name = "remove")]
+    Remove {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        #[structopt(name = "NAME")]
+        name: String,
+    },
+    /// Enforce nodemaps on the MGS
+    #[structopt(name = "activate")]
+    Activate {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+    },
+    /// Stop enforcing nodemaps on the MGS
+    #[structopt(name = "deactivate")]
+    Deactivate {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+    },
+    /// Make the nodemaps on the MGS of a filesystem match the desired nodemaps
+    #[structopt(name = "reconcile")]
+    Reconcile {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+    },
+}
+
+async fn activate(fsname: String, active: bool) -> Result<(), ImlManagerCliError> {
+    let query = nodemap_queries::activate::build(&fsname, active);
+
+    let resp: iml_graphql_queries::Response<nodemap_queries::activate::Resp> =
+        wrap_fut("Updating nodemaps...", graphql(query)).await?;
+
+    let _ = Result::from(resp)?;
+
+    let x = if active { "Activated" } else { "Deactivated" };
+
+    display_success(format!("{} nodemaps on {}", x, fsname));
+
+    Ok(())
+}
+
+pub async fn nodemap_cli(command: NodemapCommand) -> Result<(), ImlManagerCliError> {
+    match command {
+        NodemapCommand::List {
+            fsname,
+            display_type,
+        } => {
+            let query = nodemap_queries::list::build(&fsname);
+
+            let resp: iml_graphql_queries::Response<nodemap_queries::list::Resp> =
+                wrap_fut("Fetching nodemaps...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.nodemap.list;
+
+            let x = xs.into_display_type(display_type);
+
+            let term = Term::stdout();
+            term.write_line(&x).unwrap();
+        }
+        NodemapCommand::Status { fsname } => {
+            let query = nodemap_queries::status::build(&fsname);
+
+            let resp: iml_graphql_queries::Response<nodemap_queries::status::Resp> =
+                wrap_fut("Fetching nodemap status...", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.nodemap.status;
+
+            let term = Term::stdout();
+
+            let desired = match x.desired_active {
+                Some(true) => "active",
+                Some(false) => "inactive",
+                None => "unmanaged",
+            };
+
+            term.write_line(&format!(
+                "Active on MGS: {} (desired: {})",
+                x.active, desired
+            ))
+            .unwrap();
+
+            if x.pending.is_empty() {
+                term.write_line("No pending changes").unwrap();
+            } else {
+                term.write_line("Pending changes:").unwrap();
+
+                for p in x.pending {
+                    term.write_line(&format!("  {}", p)).unwrap();
+                }
+            }
+
+            if !x.unmanaged.is_empty() {
+                term.write_line(&format!("Unmanaged nodemaps: {}", x.unmanaged.join(", ")))
+                    .unwrap();
+            }
+        }
+        NodemapCommand::Create {
+            fsname,
+            name,
+            admin,
+            trusted,
+            squash_uid,
+            squash_gid,
+            ranges,
+        } => {
+            let query = nodemap_queries::create::build(
+                &fsname,
+                &name,
+                Some(admin),
+                Some(trusted),
+                squash_uid,
+                squash_gid,
+                Some(ranges),
+            );
+
+            let resp: iml_graphql_queries::Response<nodemap_queries::create::Resp> =
+                wrap_fut("Creating nodemap...", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.nodemap.create;
+
+            display_success(format!("Created nodemap {} on {}", x.name, fsname));
+        }
+        NodemapCommand::Edit {
+            fsname,
+            name,
+            admin,
+            trusted,
+            squash_uid,
+            squash_gid,
+        } => {
+            let query = nodemap_queries::edit::build(
+                &fsname, &name, admin, trusted, squash_uid, squash_gid,
+            );
+
+            let resp: iml_graphql_queries::Response<nodemap_queries::edit::Resp> =
+                wrap_fut("Updating nodemap...", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.nodemap.edit;
+
+            display_success(format!("Updated nodemap {} on {}", x.name, fsname));
+        }
+        NodemapCommand::AddRange {
+            fsname,
+            name,
+            range,
+        } => {
+            let query = nodemap_queries::add_range::build(&fsname, &name, &range);
+
+            let resp: iml_graphql_queries::Response<nodemap_queries::add_range::Resp> =
+                wrap_fut("Adding range...", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.nodemap.add_range;
+
+            display_success(format!("Added range {} to nodemap {}", range, x.name));
+        }
+        NodemapCommand::RemoveRange {
+            fsname,
+            name,
+            range,
+        } => {
+            let query = nodemap_queries::remove_range::build(&fsname, &name, &range);
+
+            let resp: iml_graphql_queries::Response<nodemap_queries::remove_range::Resp> =
+                wrap_fut("Removing range...", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.nodemap.remove_range;
+
+            display_success(format!("Removed range {} from nodemap {}", range, x.name));
+        }
+        NodemapCommand::AddIdmap {
+            fsname,
+            name,
+            kind,
+            client_id,
+            fs_id,
+        } => {
+            let query = nodemap_queries::add_idmap::build(&fsname, &name, kind, client_id, fs_id);
+
+            let resp: iml_graphql_queries::Response<nodemap_queries::add_idmap::Resp> =
+                wrap_fut("Adding id map...", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.nodemap.add_idmap;
+
+            display_success(format!(
+                "Mapped {} {} to {} on nodemap {}",
+                kind, client_id, fs_id, x.name
+            ));
+        }
+        NodemapCommand::RemoveIdmap {
+            fsname,
+            name,
+            kind,
+            client_id,
+        } => {
+            let query = nodemap_queries::remove_idmap::build(&fsname, &name, kind, client_id);
+
+            let resp: iml_graphql_queries::Response<nodemap_queries::remove_idmap::Resp> =
+                wrap_fut("Removing id map...", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.nodemap.remove_idmap;
+
+            display_success(format!(
+                "Removed {} map of {} from nodemap {}",
+                kind, client_id, x.name
+            ));
+        }
+        NodemapCommand::Remove { fsname, name } => {
+            let query = nodemap_queries::remove::build(&fsname, &name);
+
+            let resp: iml_graphql_queries::Response<nodemap_queries::remove::Resp> =
+                wrap_fut("Removing nodemap...", graphql(query)).await?;
+
+            let _ = Result::from(resp)?;
+
+            display_success(format!("Removed nodemap {} from {}", name, fsname));
+        }
+        NodemapCommand::Activate { fsname } => activate(fsname, true).await?,
+        NodemapCommand::Deactivate { fsname } => activate(fsname, false).await?,
+        NodemapCommand::Reconcile { fsname } => {
+            let query = nodemap_queries::reconcile::build(&fsname);
+
+            let resp: iml_graphql_queries::Response<nodemap_queries::reconcile::Resp> =
+                wrap_fut("Reconciling nodemaps...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.nodemap.reconcile;
+
+            if xs.is_empty() {
+                display_success(format!("Nodemaps of {} are up to date", fsname));
+            } else {
+                let term = Term::stdout();
+
+                for x in &xs {
+                    term.write_line(x).unwrap();
+                }
+
+                display_success(format!(
+                    "Applied {} nodemap changes on {}",
+                    xs.len(),
+                    fsname
+                ));
+            }
+        }
+    };
+
+    Ok(())
+}
@@ -0,0 +1,150 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    api_utils::graphql,
+    display_utils::{display_success, wrap_fut, DisplayType, IntoDisplayType as _},
+    error::ImlManagerCliError,
+};
+use console::Term;
+use iml_graphql_queries::filesystem as fs_queries;
+use iml_wire_types::quota::QuotaKind;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum QuotaCommand {
+    /// List quota limits and usage of a filesystem
+    #[structopt(name = "list")]
+    List {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        /// Only list quotas of this kind: user, group, project
+        #[structopt(short = "k", long = "kind")]
+        kind: Option<QuotaKind>,
+        /// Only list quotas of this OST pool
+        #[structopt(short = "p", long = "pool")]
+        pool: Option<String>,
+        /// Display type: json, yaml, tabular
+        #[structopt(short = "d", long = "display", default_value = "tabular")]
+        display_type: DisplayType,
+    },
+    /// Set the quota limits of a user, group or project.
+    /// If no id is given the default limits of the kind are set.
+    /// Limits that are not passed are left unchanged, 0 removes a limit
+    #[structopt(name = "set")]
+    Set {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        /// The kind of quota: user, group, project
+        #[structopt(name = "KIND")]
+        kind: QuotaKind,
+        /// The user, group or project name or id
+        #[structopt(name = "ID")]
+        id: Option<String>,
+        /// Only apply the block limits to this OST pool
+        #[structopt(short = "p", long = "pool")]
+        pool: Option<String>,
+        /// Block soft limit, i.e. 10G. Values without a unit are kbytes
+        #[structopt(short = "b", long = "block-soft")]
+        block_soft: Option<String>,
+        /// Block hard limit, i.e. 12G. Values without a unit are kbytes
+        #[structopt(short = "B", long = "block-hard")]
+        block_hard: Option<String>,
+        /// Inode soft limit
+        #[structopt(short = "i", long = "inode-soft")]
+        inode_soft: Option<String>,
+        /// Inode hard limit
+        #[structopt(short = "I", long = "inode-hard")]
+        inode_hard: Option<String>,
+    },
+    /// Set the grace periods of a kind of quota
+    #[structopt(name = "grace")]
+    Grace {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        /// The kind of quota: user, group, project
+        #[structopt(name = "KIND")]
+        kind: QuotaKind,
+        /// Only apply the block grace period to this OST pool
+        #[structopt(short = "p", long = "pool")]
+        pool: Option<String>,
+        /// Block grace period, i.e. 1w2d or seconds
+        #[structopt(short = "b", long = "block")]
+        block: Option<String>,
+        /// Inode grace period, i.e. 1w2d or seconds
+        #[structopt(short = "i", long = "inode")]
+        inode: Option<String>,
+    },
+}
+
+pub async fn quota_cli(command: QuotaCommand) -> Result<(), ImlManagerCliError> {
+    match command {
+        QuotaCommand::List {
+            fsname,
+            kind,
+            pool,
+            display_type,
+        } => {
+            let query = fs_queries::quotas::build(&fsname, kind, pool);
+
+            let resp: iml_graphql_queries::Response<fs_queries::quotas::Resp> =
+                wrap_fut("Fetching quotas...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.filesystem.quotas;
+
+            let x = xs.into_display_type(display_type);
+
+            let term = Term::stdout();
+            term.write_line(&x).unwrap();
+        }
+        QuotaCommand::Set {
+            fsname,
+            kind,
+            id,
+            pool,
+            block_soft,
+            block_hard,
+            inode_soft,
+            inode_hard,
+        } => {
+            let query = fs_queries::set_quota::build(
+                &fsname,
+                kind,
+                id,
+                pool,
+                fs_queries::set_quota::Limits {
+                    block_soft,
+                    block_hard,
+                    inode_soft,
+                    inode_hard,
+                },
+            );
+
+            let resp: iml_graphql_queries::Response<fs_queries::set_quota::Resp> =
+                wrap_fut("Setting quota...", graphql(query)).await?;
+
+            let _ = Result::from(resp)?;
+
+            display_success(format!("Set {} quota on {}", kind, fsname));
+        }
+        QuotaCommand::Grace {
+            fsname,
+            kind,
+            pool,
+            block,
+            inode,
+        } => {
+            let query = fs_queries::set_quota_grace::build(&fsname, kind, pool, block, inode);
+
+            let resp: iml_graphql_queries::Response<fs_queries::set_quota_grace::Resp> =
+                wrap_fut("Setting quota grace periods...", graphql(query)).await?;
+
+            let _ = Result::from(resp)?;
+
+            display_success(format!("Set {} quota grace periods on {}", kind, fsname));
+        }
+    };
+
+    Ok(())
+}
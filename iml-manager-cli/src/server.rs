@@ -4,7 +4,8 @@
 
 use crate::{
     api_utils::{
-        get, get_all, get_hosts, post, put, wait_for_cmds, wait_for_cmds_success, SendCmd, SendJob,
+        get, get_all, get_hosts, graphql, post, put, wait_for_cmds, wait_for_cmds_success, SendCmd,
+        SendJob,
     },
     display_utils::{
         display_cancelled, display_error, format_error, format_success, generate_table, wrap_fut,
@@ -16,6 +17,7 @@ use crate::{
 use console::{style, Term};
 use dialoguer::Confirm;
 use futures::future;
+use iml_graphql_queries::server as server_queries;
 use iml_wire_types::{
     ApiList, AvailableAction, CmdWrapper, Command, EndpointName, Host, ProfileTest, ServerProfile,
     TestHostJob, ToCompositeId,
@@ -86,6 +88,179 @@ pub enum ServerCommand {
         #[structopt(subcommand)]
         cmd: Option<profile::Cmd>,
     },
+    /// Move all targets running on a server to their failover servers
+    #[structopt(name = "failover")]
+    Failover {
+        /// The server to move targets away from
+        host: String,
+    },
+    /// Move targets back to a server that was failed over
+    #[structopt(name = "failback")]
+    Failback {
+        /// The server to move targets back to
+        host: String,
+    },
+    /// Upgrade Lustre on servers one at a time, failing their targets over to HA peers
+    #[structopt(name = "upgrade")]
+    Upgrade {
+        /// Hostlist expressions, e. g. oss[1-4].local. Servers are upgraded in sorted order
+        #[structopt(required = true, min_values = 1)]
+        hosts: Vec<String>,
+        /// Reboot each server instead of reloading the Lustre modules
+        #[structopt(long = "reboot")]
+        reboot: bool,
+    },
+    /// Scrub zpools on a server
+    #[structopt(name = "scrub")]
+    Scrub(ScrubCommand),
+    /// Work with devices on a server
+    #[structopt(name = "devices")]
+    Devices(DevicesCommand),
+    /// Configure LNet on a server
+    #[structopt(name = "lnet")]
+    Lnet(LnetCommand),
+    /// Show the clock offset of each server and the largest skew between them
+    #[structopt(name = "time")]
+    Time {
+        /// Display type: json, yaml, tabular
+        #[structopt(short = "d", long = "display", default_value = "tabular")]
+        display_type: DisplayType,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum LnetCommand {
+    /// Add an LNet network on one or more interfaces
+    #[structopt(name = "net-add")]
+    NetAdd {
+        /// The server to configure
+        host: String,
+        /// The LNet network, e. g. tcp0 or o2ib1
+        net: String,
+        /// The interfaces to add the network on
+        #[structopt(required = true, min_values = 1)]
+        interfaces: Vec<String>,
+        /// Peer credits of the network
+        #[structopt(long = "peer-credits")]
+        peer_credits: Option<i32>,
+        /// Credits of the network
+        #[structopt(long)]
+        credits: Option<i32>,
+    },
+    /// Remove an LNet network
+    #[structopt(name = "net-del")]
+    NetDel {
+        /// The server to configure
+        host: String,
+        /// The LNet network to remove
+        net: String,
+        /// Only remove the network from these interfaces
+        interfaces: Vec<String>,
+    },
+    /// Add a route to a remote LNet network
+    #[structopt(name = "route-add")]
+    RouteAdd {
+        /// The server to configure
+        host: String,
+        /// The remote LNet network
+        net: String,
+        /// The NID of the router, e. g. 10.0.0.1@tcp0
+        gateway: String,
+        /// Number of hops to the remote network
+        #[structopt(long)]
+        hop: Option<i32>,
+        /// Priority of the route
+        #[structopt(long)]
+        priority: Option<i32>,
+    },
+    /// Remove a route to a remote LNet network
+    #[structopt(name = "route-del")]
+    RouteDel {
+        /// The server to configure
+        host: String,
+        /// The remote LNet network
+        net: String,
+        /// The NID of the router
+        gateway: String,
+    },
+    /// Apply an LNet YAML configuration, as produced by `export`
+    #[structopt(name = "import")]
+    Import {
+        /// The server to configure
+        host: String,
+        /// The YAML file to import. Read from stdin if omitted
+        file: Option<String>,
+    },
+    /// Print the running LNet configuration as YAML
+    #[structopt(name = "export")]
+    Export {
+        /// The server to export the configuration of
+        host: String,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum DevicesCommand {
+    /// Show a timeline of device changes on a server
+    #[structopt(name = "history")]
+    History {
+        /// The server to show device changes for
+        host: String,
+        /// Only show changes to the device with this id
+        #[structopt(short = "i", long = "device")]
+        device_id: Option<String>,
+        /// The maximum number of changes to show, newest first
+        #[structopt(short = "l", long = "limit")]
+        limit: Option<i32>,
+        /// Display type: json, yaml, tabular
+        #[structopt(short = "d", long = "display", default_value = "tabular")]
+        display_type: DisplayType,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum ScrubCommand {
+    /// Start a scrub of a zpool
+    #[structopt(name = "start")]
+    Start {
+        /// The server the zpool is imported on
+        host: String,
+        /// The zpool to scrub
+        zpool: String,
+    },
+    /// Stop a running scrub of a zpool
+    #[structopt(name = "stop")]
+    Stop {
+        /// The server the zpool is imported on
+        host: String,
+        /// The zpool to stop scrubbing
+        zpool: String,
+    },
+    /// Scrub a zpool on a recurring interval
+    #[structopt(name = "schedule")]
+    Schedule {
+        /// The server the zpool is imported on
+        host: String,
+        /// The zpool to scrub
+        zpool: String,
+        /// Scrub interval in human form, e. g. 1week
+        #[structopt(required = true, min_values = 1)]
+        interval: Vec<String>,
+    },
+    /// Remove scrub intervals
+    #[structopt(name = "unschedule")]
+    Unschedule {
+        /// The ids of the scrub intervals to remove
+        #[structopt(required = true, min_values = 1)]
+        ids: Vec<i32>,
+    },
+    /// List scrub intervals
+    #[structopt(name = "list")]
+    List {
+        /// Display type: json, yaml, tabular
+        #[structopt(short = "d", long = "display", default_value = "tabular")]
+        display_type: DisplayType,
+    },
 }
 
 #[derive(Debug)]
@@ -677,6 +852,224 @@ async fn server(command: ServerCommand) -> Result<(), ImlManagerCliError> {
             wait_for_cmds_success(&commands).await?;
         }
         ServerCommand::Profile { cmd } => profile::cmd(cmd).await?,
+        ServerCommand::Failover { host } => {
+            let query = server_queries::failover::build(host);
+
+            let resp: iml_graphql_queries::Response<server_queries::failover::Resp> =
+                graphql(query).await?;
+            let x = Result::from(resp)?.data.server.failover;
+
+            wait_for_cmds_success(&[x]).await?;
+        }
+        ServerCommand::Failback { host } => {
+            let query = server_queries::failback::build(host);
+
+            let resp: iml_graphql_queries::Response<server_queries::failback::Resp> =
+                graphql(query).await?;
+            let x = Result::from(resp)?.data.server.failback;
+
+            wait_for_cmds_success(&[x]).await?;
+        }
+        ServerCommand::Upgrade { hosts, reboot } => {
+            let hosts = parse_hosts(&hosts)?;
+
+            tracing::debug!("Parsed hosts {:?}", hosts);
+
+            let query = server_queries::upgrade::build(hosts.into_iter().collect(), Some(reboot));
+
+            let resp: iml_graphql_queries::Response<server_queries::upgrade::Resp> =
+                graphql(query).await?;
+            let x = Result::from(resp)?.data.server.upgrade;
+
+            wait_for_cmds_success(&[x]).await?;
+        }
+        ServerCommand::Scrub(cmd) => scrub_cli(cmd).await?,
+        ServerCommand::Lnet(cmd) => lnet_cli(cmd).await?,
+        ServerCommand::Time { display_type } => {
+            let query = server_queries::time_skew::build();
+
+            let resp: iml_graphql_queries::Response<server_queries::time_skew::Resp> =
+                graphql(query).await?;
+            let x = Result::from(resp)?.data.server.time_skew;
+
+            let x = x.into_display_type(display_type);
+
+            let term = Term::stdout();
+            term.write_line(&x).unwrap();
+        }
+        ServerCommand::Devices(DevicesCommand::History {
+            host,
+            device_id,
+            limit,
+            display_type,
+        }) => {
+            let query = server_queries::device_history::build(host, device_id, limit);
+
+            let resp: iml_graphql_queries::Response<server_queries::device_history::Resp> =
+                graphql(query).await?;
+            let mut xs = Result::from(resp)?.data.server.device_history;
+
+            // Group the changes into a timeline per device
+            xs.sort_by(|a, b| (&a.device_id, a.time).cmp(&(&b.device_id, b.time)));
+
+            let x = xs.into_display_type(display_type);
+
+            let term = Term::stdout();
+            term.write_line(&x).unwrap();
+        }
+    };
+
+    Ok(())
+}
+
+async fn scrub_cli(cmd: ScrubCommand) -> Result<(), ImlManagerCliError> {
+    match cmd {
+        ScrubCommand::Start { host, zpool } => {
+            let query = server_queries::start_scrub::build(host, zpool);
+
+            let resp: iml_graphql_queries::Response<server_queries::start_scrub::Resp> =
+                graphql(query).await?;
+            let x = Result::from(resp)?.data.server.start_scrub;
+
+            wait_for_cmds_success(&[x]).await?;
+        }
+        ScrubCommand::Stop { host, zpool } => {
+            let query = server_queries::stop_scrub::build(host, zpool);
+
+            let resp: iml_graphql_queries::Response<server_queries::stop_scrub::Resp> =
+                graphql(query).await?;
+            let x = Result::from(resp)?.data.server.stop_scrub;
+
+            wait_for_cmds_success(&[x]).await?;
+        }
+        ScrubCommand::Schedule {
+            host,
+            zpool,
+            interval,
+        } => {
+            let query =
+                server_queries::create_scrub_interval::build(host, zpool, interval.join(" "));
+
+            let _resp: iml_graphql_queries::Response<server_queries::create_scrub_interval::Resp> =
+                graphql(query).await?;
+        }
+        ScrubCommand::Unschedule { ids } => {
+            for id in ids {
+                let query = server_queries::remove_scrub_interval::build(id);
+
+                let _resp: iml_graphql_queries::Response<
+                    server_queries::remove_scrub_interval::Resp,
+                > = graphql(query).await?;
+            }
+        }
+        ScrubCommand::List { display_type } => {
+            let query = server_queries::list_scrub_intervals::build();
+
+            let resp: iml_graphql_queries::Response<server_queries::list_scrub_intervals::Resp> =
+                graphql(query).await?;
+            let intervals = Result::from(resp)?.data.server.scrub_intervals;
+
+            let x = intervals.into_display_type(display_type);
+
+            let term = Term::stdout();
+            term.write_line(&x).unwrap();
+        }
+    };
+
+    Ok(())
+}
+
+async fn lnet_cli(cmd: LnetCommand) -> Result<(), ImlManagerCliError> {
+    match cmd {
+        LnetCommand::NetAdd {
+            host,
+            net,
+            interfaces,
+            peer_credits,
+            credits,
+        } => {
+            let query = server_queries::add_lnet_network::build(
+                host,
+                net,
+                interfaces,
+                peer_credits,
+                credits,
+            );
+
+            let resp: iml_graphql_queries::Response<server_queries::add_lnet_network::Resp> =
+                graphql(query).await?;
+            let x = Result::from(resp)?.data.server.add_lnet_network;
+
+            wait_for_cmds_success(&[x]).await?;
+        }
+        LnetCommand::NetDel {
+            host,
+            net,
+            interfaces,
+        } => {
+            let interfaces = Some(interfaces).filter(|xs| !xs.is_empty());
+
+            let query = server_queries::remove_lnet_network::build(host, net, interfaces);
+
+            let resp: iml_graphql_queries::Response<server_queries::remove_lnet_network::Resp> =
+                graphql(query).await?;
+            let x = Result::from(resp)?.data.server.remove_lnet_network;
+
+            wait_for_cmds_success(&[x]).await?;
+        }
+        LnetCommand::RouteAdd {
+            host,
+            net,
+            gateway,
+            hop,
+            priority,
+        } => {
+            let query = server_queries::add_lnet_route::build(host, net, gateway, hop, priority);
+
+            let resp: iml_graphql_queries::Response<server_queries::add_lnet_route::Resp> =
+                graphql(query).await?;
+            let x = Result::from(resp)?.data.server.add_lnet_route;
+
+            wait_for_cmds_success(&[x]).await?;
+        }
+        LnetCommand::RouteDel { host, net, gateway } => {
+            let query = server_queries::remove_lnet_route::build(host, net, gateway);
+
+            let resp: iml_graphql_queries::Response<server_queries::remove_lnet_route::Resp> =
+                graphql(query).await?;
+            let x = Result::from(resp)?.data.server.remove_lnet_route;
+
+            wait_for_cmds_success(&[x]).await?;
+        }
+        LnetCommand::Import { host, file } => {
+            let config = match file {
+                Some(file) => tokio::fs::read_to_string(file).await?,
+                None => {
+                    let mut config = String::new();
+                    stdin().read_to_string(&mut config).await?;
+
+                    config
+                }
+            };
+
+            let query = server_queries::import_lnet_config::build(host, config);
+
+            let resp: iml_graphql_queries::Response<server_queries::import_lnet_config::Resp> =
+                graphql(query).await?;
+            let x = Result::from(resp)?.data.server.import_lnet_config;
+
+            wait_for_cmds_success(&[x]).await?;
+        }
+        LnetCommand::Export { host } => {
+            let query = server_queries::lnet_config::build(host);
+
+            let resp: iml_graphql_queries::Response<server_queries::lnet_config::Resp> =
+                graphql(query).await?;
+            let x = Result::from(resp)?.data.server.lnet_config;
+
+            let term = Term::stdout();
+            term.write_str(&x).unwrap();
+        }
     };
 
     Ok(())
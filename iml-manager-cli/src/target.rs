@@ -4,11 +4,11 @@
 
 use crate::{
     api_utils::{get_hosts, graphql},
-    display_utils::{wrap_fut, DisplayType, IntoDisplayType as _},
+    display_utils::{display_success, wrap_fut, DisplayType, IntoDisplayType as _},
     error::ImlManagerCliError,
 };
 use console::Term;
-use iml_graphql_queries::target as target_queries;
+use iml_graphql_queries::{changelog as changelog_queries, target as target_queries};
 use iml_wire_types::{ApiList, Host};
 use structopt::StructOpt;
 
@@ -22,6 +22,89 @@ pub enum TargetCommand {
         /// Optionally filter by filesystem name
         fsname: Option<String>,
     },
+    /// MDT changelog users
+    #[structopt(name = "changelog")]
+    Changelog {
+        #[structopt(subcommand)]
+        command: ChangelogCommand,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum ChangelogCommand {
+    /// List the changelog users of each MDT and how far behind they are
+    #[structopt(name = "list")]
+    List {
+        /// Display type: json, yaml, tabular
+        #[structopt(short = "d", long = "display", default_value = "tabular")]
+        display_type: DisplayType,
+        /// Optionally filter by filesystem name
+        fsname: Option<String>,
+    },
+    /// Register a changelog user on an MDT
+    #[structopt(name = "register")]
+    Register {
+        /// The MDT, i.e. fs-MDT0000
+        #[structopt(name = "MDT")]
+        target: String,
+    },
+    /// Deregister a changelog user from an MDT.
+    /// Records the user has not cleared are purged
+    #[structopt(name = "deregister")]
+    Deregister {
+        /// The MDT, i.e. fs-MDT0000
+        #[structopt(name = "MDT")]
+        target: String,
+        /// The id of the user, i.e. cl1
+        #[structopt(name = "ID")]
+        id: String,
+    },
+}
+
+async fn changelog_cli(command: ChangelogCommand) -> Result<(), ImlManagerCliError> {
+    match command {
+        ChangelogCommand::List {
+            display_type,
+            fsname,
+        } => {
+            let query = changelog_queries::users::build(fsname);
+
+            let resp: iml_graphql_queries::Response<changelog_queries::users::Resp> =
+                wrap_fut("Fetching changelog users...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.changelog.users;
+
+            let x = xs.into_display_type(display_type);
+
+            let term = Term::stdout();
+            term.write_line(&x).unwrap();
+        }
+        ChangelogCommand::Register { target } => {
+            let query = changelog_queries::register::build(&target);
+
+            let resp: iml_graphql_queries::Response<changelog_queries::register::Resp> =
+                wrap_fut("Registering changelog user...", graphql(query)).await?;
+
+            let id = Result::from(resp)?.data.changelog.register;
+
+            display_success(format!("Registered changelog user {} on {}", id, target));
+        }
+        ChangelogCommand::Deregister { target, id } => {
+            let query = changelog_queries::deregister::build(&target, &id);
+
+            let resp: iml_graphql_queries::Response<changelog_queries::deregister::Resp> =
+                wrap_fut("Deregistering changelog user...", graphql(query)).await?;
+
+            let _ = Result::from(resp)?;
+
+            display_success(format!(
+                "Deregistered changelog user {} from {}",
+                id, target
+            ));
+        }
+    };
+
+    Ok(())
 }
 
 pub async fn target_cli(command: TargetCommand) -> Result<(), ImlManagerCliError> {
@@ -45,5 +128,6 @@ pub async fn target_cli(command: TargetCommand) -> Result<(), ImlManagerCliError
 
             Ok(())
         }
+        TargetCommand::Changelog { command } => changelog_cli(command).await,
     }
 }
@@ -0,0 +1,246 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    api_utils::graphql,
+    display_utils::{display_success, wrap_fut, DisplayType, IntoDisplayType as _},
+    error::ImlManagerCliError,
+};
+use console::Term;
+use iml_graphql_queries::tiering as tiering_queries;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum TieringCommand {
+    /// List tiering policies
+    #[structopt(name = "list")]
+    List {
+        #[structopt(name = "FSNAME")]
+        fsname: Option<String>,
+        /// Display type: json, yaml, tabular
+        #[structopt(short = "d", long = "display", default_value = "tabular")]
+        display_type: DisplayType,
+    },
+    /// Show the units and mailboxes of a tiering policy
+    #[structopt(name = "status")]
+    Status {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        #[structopt(name = "NAME")]
+        name: String,
+        /// Display type: json, yaml, tabular
+        #[structopt(short = "d", long = "display", default_value = "tabular")]
+        display_type: DisplayType,
+    },
+    /// Create a tiering policy.
+    /// Run `apply` to start it
+    #[structopt(name = "create")]
+    Create {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        #[structopt(name = "NAME")]
+        name: String,
+        /// The OST pool new files are written to
+        #[structopt(long = "hot-pool")]
+        hot_pool: String,
+        /// The OST pool files are mirrored to
+        #[structopt(long = "cold-pool")]
+        cold_pool: String,
+        /// Seconds to wait before mirroring a closed file
+        #[structopt(long = "min-age", default_value = "600")]
+        min_age: i32,
+        /// The lamigo heat function
+        #[structopt(long = "heatfn")]
+        heatfn: Option<i32>,
+        /// Stop purging hot mirrors once an OST has this percentage free
+        #[structopt(long = "freehi", default_value = "90")]
+        freehi: i32,
+        /// Start purging hot mirrors once an OST has less than this percentage free
+        #[structopt(long = "freelo", default_value = "70")]
+        freelo: i32,
+        /// The Lustre client mountpoint lamigo uses on the MDS
+        #[structopt(long = "mountpoint")]
+        mountpoint: String,
+    },
+    /// Change the settings of a tiering policy.
+    /// Applied policies are restarted with the new settings
+    #[structopt(name = "update")]
+    Update {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        #[structopt(name = "NAME")]
+        name: String,
+        /// Seconds to wait before mirroring a closed file
+        #[structopt(long = "min-age")]
+        min_age: Option<i32>,
+        /// The lamigo heat function
+        #[structopt(long = "heatfn")]
+        heatfn: Option<i32>,
+        /// Stop purging hot mirrors once an OST has this percentage free
+        #[structopt(long = "freehi")]
+        freehi: Option<i32>,
+        /// Start purging hot mirrors once an OST has less than this percentage free
+        #[structopt(long = "freelo")]
+        freelo: Option<i32>,
+        /// The Lustre client mountpoint lamigo uses on the MDS
+        #[structopt(long = "mountpoint")]
+        mountpoint: Option<String>,
+    },
+    /// Write the lamigo and lpurge configs of a tiering policy and start their units
+    #[structopt(name = "apply")]
+    Apply {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        #[structopt(name = "NAME")]
+        name: String,
+    },
+    /// Stop the units of a tiering policy
+    #[structopt(name = "stop")]
+    Stop {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        #[structopt(name = "NAME")]
+        name: String,
+    },
+    /// Stop and remove a tiering policy
+    #[structopt(name = "remove")]
+    Remove {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        #[structopt(name = "NAME")]
+        name: String,
+    },
+}
+
+pub async fn tiering_cli(command: TieringCommand) -> Result<(), ImlManagerCliError> {
+    match command {
+        TieringCommand::List {
+            fsname,
+            display_type,
+        } => {
+            let query = tiering_queries::list::build(fsname);
+
+            let resp: iml_graphql_queries::Response<tiering_queries::list::Resp> =
+                wrap_fut("Fetching tiering policies...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.tiering.policies;
+
+            let x = xs.into_display_type(display_type);
+
+            let term = Term::stdout();
+            term.write_line(&x).unwrap();
+        }
+        TieringCommand::Status {
+            fsname,
+            name,
+            display_type,
+        } => {
+            let query = tiering_queries::status::build(&fsname, &name);
+
+            let resp: iml_graphql_queries::Response<tiering_queries::status::Resp> =
+                wrap_fut("Fetching tiering status...", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.tiering.status;
+
+            let x = x.into_display_type(display_type);
+
+            let term = Term::stdout();
+            term.write_line(&x).unwrap();
+        }
+        TieringCommand::Create {
+            fsname,
+            name,
+            hot_pool,
+            cold_pool,
+            min_age,
+            heatfn,
+            freehi,
+            freelo,
+            mountpoint,
+        } => {
+            let query = tiering_queries::create::build(
+                &fsname,
+                &name,
+                &hot_pool,
+                &cold_pool,
+                min_age,
+                heatfn,
+                freehi,
+                freelo,
+                &mountpoint,
+            );
+
+            let resp: iml_graphql_queries::Response<tiering_queries::create::Resp> =
+                wrap_fut("Creating tiering policy...", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.tiering.create;
+
+            display_success(format!(
+                "Created tiering policy {} ({} -> {}) on {}",
+                x.name, x.hot_pool, x.cold_pool, x.fs_name
+            ));
+        }
+        TieringCommand::Update {
+            fsname,
+            name,
+            min_age,
+            heatfn,
+            freehi,
+            freelo,
+            mountpoint,
+        } => {
+            let query = tiering_queries::update::build(
+                &fsname, &name, min_age, heatfn, freehi, freelo, mountpoint,
+            );
+
+            let resp: iml_graphql_queries::Response<tiering_queries::update::Resp> =
+                wrap_fut("Updating tiering policy...", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.tiering.update;
+
+            display_success(format!(
+                "Updated tiering policy {} on {}",
+                x.name, x.fs_name
+            ));
+        }
+        TieringCommand::Apply { fsname, name } => {
+            let query = tiering_queries::apply::build(&fsname, &name);
+
+            let resp: iml_graphql_queries::Response<tiering_queries::apply::Resp> =
+                wrap_fut("Applying tiering policy...", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.tiering.apply;
+
+            display_success(format!(
+                "Applied tiering policy {} on {}",
+                x.name, x.fs_name
+            ));
+        }
+        TieringCommand::Stop { fsname, name } => {
+            let query = tiering_queries::stop::build(&fsname, &name);
+
+            let resp: iml_graphql_queries::Response<tiering_queries::stop::Resp> =
+                wrap_fut("Stopping tiering policy...", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.tiering.stop;
+
+            display_success(format!(
+                "Stopped tiering policy {} on {}",
+                x.name, x.fs_name
+            ));
+        }
+        TieringCommand::Remove { fsname, name } => {
+            let query = tiering_queries::remove::build(&fsname, &name);
+
+            let resp: iml_graphql_queries::Response<tiering_queries::remove::Resp> =
+                wrap_fut("Removing tiering policy...", graphql(query)).await?;
+
+            let _ = Result::from(resp)?;
+
+            display_success(format!("Removed tiering policy {} from {}", name, fsname));
+        }
+    };
+
+    Ok(())
+}
@@ -0,0 +1,149 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use crate::{
+    api_utils::graphql,
+    display_utils::{display_success, wrap_fut, DisplayType, IntoDisplayType as _},
+    error::ImlManagerCliError,
+};
+use console::Term;
+use iml_graphql_queries::tunable as tunable_queries;
+use iml_wire_types::{db::TargetKind, tunable::TunableMethod};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum TunableCommand {
+    /// List the desired tunables of a filesystem
+    #[structopt(name = "list")]
+    List {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        /// Display type: json, yaml, tabular
+        #[structopt(short = "d", long = "display", default_value = "tabular")]
+        display_type: DisplayType,
+    },
+    /// Add or update a desired tunable.
+    /// Run `apply` to set it on the servers
+    #[structopt(name = "set")]
+    Set {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+        /// The parameter pattern, i.e. osc.fs-*.max_rpcs_in_flight. Filesystem tunables must name the filesystem
+        #[structopt(name = "PARAM")]
+        param: String,
+        #[structopt(name = "VALUE")]
+        value: String,
+        /// How the tunable is set: set_param_p, conf_param, set_param
+        #[structopt(short = "m", long = "method")]
+        method: Option<TunableMethod>,
+        /// Only check the tunable on servers with targets of this kind: mgt, mdt, ost
+        #[structopt(short = "k", long = "target-kind")]
+        target_kind: Option<TargetKind>,
+        /// Only set the tunable on this host
+        #[structopt(long = "host")]
+        host: Option<String>,
+    },
+    /// Remove a desired tunable
+    #[structopt(name = "remove")]
+    Remove {
+        /// The id of the tunable, as shown by `list`
+        #[structopt(name = "ID")]
+        id: i32,
+    },
+    /// Set the desired tunables of a filesystem on its servers
+    #[structopt(name = "apply")]
+    Apply {
+        #[structopt(name = "FSNAME")]
+        fsname: String,
+    },
+    /// List tunables whose value on a server differs from the desired value
+    #[structopt(name = "drift")]
+    Drift {
+        #[structopt(name = "FSNAME")]
+        fsname: Option<String>,
+        /// Display type: json, yaml, tabular
+        #[structopt(short = "d", long = "display", default_value = "tabular")]
+        display_type: DisplayType,
+    },
+}
+
+pub async fn tunable_cli(command: TunableCommand) -> Result<(), ImlManagerCliError> {
+    match command {
+        TunableCommand::List {
+            fsname,
+            display_type,
+        } => {
+            let query = tunable_queries::list::build(&fsname);
+
+            let resp: iml_graphql_queries::Response<tunable_queries::list::Resp> =
+                wrap_fut("Fetching tunables...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.tunable.tunables;
+
+            let x = xs.into_display_type(display_type);
+
+            let term = Term::stdout();
+            term.write_line(&x).unwrap();
+        }
+        TunableCommand::Set {
+            fsname,
+            param,
+            value,
+            method,
+            target_kind,
+            host,
+        } => {
+            let query =
+                tunable_queries::set::build(&fsname, &param, &value, method, target_kind, host);
+
+            let resp: iml_graphql_queries::Response<tunable_queries::set::Resp> =
+                wrap_fut("Setting tunable...", graphql(query)).await?;
+
+            let x = Result::from(resp)?.data.tunable.set_tunable;
+
+            display_success(format!(
+                "Set tunable {}={} ({}) on {}",
+                x.param, x.value, x.id, x.fs_name
+            ));
+        }
+        TunableCommand::Remove { id } => {
+            let query = tunable_queries::remove::build(id);
+
+            let resp: iml_graphql_queries::Response<tunable_queries::remove::Resp> =
+                wrap_fut("Removing tunable...", graphql(query)).await?;
+
+            let _ = Result::from(resp)?;
+
+            display_success(format!("Removed tunable {}", id));
+        }
+        TunableCommand::Apply { fsname } => {
+            let query = tunable_queries::apply::build(&fsname);
+
+            let resp: iml_graphql_queries::Response<tunable_queries::apply::Resp> =
+                wrap_fut("Applying tunables...", graphql(query)).await?;
+
+            let _ = Result::from(resp)?;
+
+            display_success(format!("Applied tunables of {}", fsname));
+        }
+        TunableCommand::Drift {
+            fsname,
+            display_type,
+        } => {
+            let query = tunable_queries::drift::build(fsname);
+
+            let resp: iml_graphql_queries::Response<tunable_queries::drift::Resp> =
+                wrap_fut("Fetching tunable drift...", graphql(query)).await?;
+
+            let xs = Result::from(resp)?.data.tunable.drift;
+
+            let x = xs.into_display_type(display_type);
+
+            let term = Term::stdout();
+            term.write_line(&x).unwrap();
+        }
+    };
+
+    Ok(())
+}
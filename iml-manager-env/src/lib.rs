@@ -146,6 +146,64 @@ pub fn get_http_agent2_addr() -> SocketAddr {
     to_socket_addr(&get_server_host(), &get_http_agent2_port())
 }
 
+/// Get the depth a `rust_agent_*_rx` queue may reach before agent-comms applies backpressure.
+/// Backpressure is disabled if this is not set.
+pub fn get_agent_comms_queue_limit() -> Option<u32> {
+    env::var("AGENT_COMMS_QUEUE_LIMIT")
+        .ok()
+        .map(|l| l.parse().ok())
+        .flatten()
+}
+
+/// Get the number of seconds agents are asked to add between polls while under backpressure
+pub fn get_agent_comms_poll_delay() -> u64 {
+    env::var("AGENT_COMMS_POLL_DELAY")
+        .ok()
+        .map(|l| l.parse().ok())
+        .flatten()
+        .unwrap_or(30)
+}
+
+/// Get the allowed clock offset, in seconds, before a server is considered out of sync.
+/// Defaults to half a second if not set.
+pub fn get_ntp_drift_threshold() -> f64 {
+    env::var("NTP_DRIFT_THRESHOLD")
+        .ok()
+        .map(|l| l.parse().ok())
+        .flatten()
+        .unwrap_or(0.5)
+}
+
+/// Get the number of uncleared changelog records before a changelog user is considered behind.
+/// Defaults to 10 million records if not set.
+pub fn get_changelog_lag_threshold() -> i64 {
+    env::var("CHANGELOG_LAG_THRESHOLD")
+        .ok()
+        .map(|l| l.parse().ok())
+        .flatten()
+        .unwrap_or(10_000_000)
+}
+
+/// Get the time, in seconds, a changelog user with uncleared records may go without clearing any
+/// before it is considered abandoned. Defaults to a day if not set.
+pub fn get_changelog_idle_threshold() -> i64 {
+    env::var("CHANGELOG_IDLE_THRESHOLD")
+        .ok()
+        .map(|l| l.parse().ok())
+        .flatten()
+        .unwrap_or(86_400)
+}
+
+/// Get the time, in seconds, device history is kept for before it is pruned.
+/// Defaults to 30 days if not set.
+pub fn get_device_history_retention() -> i64 {
+    env::var("DEVICE_HISTORY_RETENTION")
+        .ok()
+        .map(|l| l.parse().ok())
+        .flatten()
+        .unwrap_or(2_592_000)
+}
+
 /// Get the server host from the env or panic
 pub fn get_server_host() -> String {
     get_var("PROXY_HOST")
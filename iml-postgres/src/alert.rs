@@ -2,8 +2,10 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
+use futures::TryStreamExt;
 use iml_wire_types::{AlertRecordType, AlertSeverity};
 use sqlx::PgPool;
+use std::collections::HashMap;
 
 pub async fn lower(
     pool: &PgPool,
@@ -29,6 +31,53 @@ pub async fn lower(
     Ok(())
 }
 
+/// Returns the message of each active `record_type` alert, keyed by the id of the alerted item.
+pub async fn active_messages(
+    pool: &PgPool,
+    record_type: AlertRecordType,
+) -> Result<HashMap<i32, String>, sqlx::Error> {
+    sqlx::query!(
+        r#"SELECT alert_item_id AS "alert_item_id!", COALESCE(message, '') AS "message!"
+            FROM chroma_core_alertstate
+            WHERE
+                active = true
+                AND alert_item_id IS NOT NULL
+                AND record_type = $1
+        "#,
+        record_type.to_string()
+    )
+    .fetch(pool)
+    .map_ok(|x| (x.alert_item_id, x.message))
+    .try_collect()
+    .await
+}
+
+/// Updates the message of an active alert in place.
+pub async fn update_message(
+    pool: &PgPool,
+    record_type: AlertRecordType,
+    msg: String,
+    item_id: i32,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"UPDATE chroma_core_alertstate
+            SET message = $3
+            WHERE
+                active = true
+                AND alert_item_id = $1
+                AND record_type = $2
+                AND message != $3
+        "#,
+        item_id,
+        record_type.to_string(),
+        msg
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
 pub async fn raise(
     pool: &PgPool,
     record_type: AlertRecordType,
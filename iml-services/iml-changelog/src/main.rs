@@ -0,0 +1,292 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use futures::TryStreamExt;
+use iml_manager_env::{get_changelog_idle_threshold, get_changelog_lag_threshold, get_pool_limit};
+use iml_postgres::{alert, get_db_pool, sqlx, PgPool};
+use iml_service_queue::service_queue::consume_data;
+use iml_tracing::tracing;
+use iml_wire_types::{
+    changelog::ChangelogStatus, db::ManagedHostRecord, AlertRecordType, AlertSeverity,
+};
+use std::collections::HashMap;
+
+// Default pool limit if not overridden by POOL_LIMIT
+const DEFAULT_POOL_LIMIT: u32 = 2;
+
+/// Returns the filesystem name of a target, i.e. `fs` for `fs-MDT0000`
+fn fs_name(target: &str) -> Option<&str> {
+    target.rsplitn(2, '-').nth(1)
+}
+
+/// Replaces the changelog users reported by a host.
+async fn update_users(
+    pool: &PgPool,
+    host_id: i32,
+    xs: &[ChangelogStatus],
+) -> Result<(), sqlx::Error> {
+    let x = xs
+        .iter()
+        .filter_map(|x| fs_name(&x.target).map(|fs_name| (fs_name, x)))
+        .flat_map(|(fs_name, x)| x.users.iter().map(move |u| (fs_name, x, u)))
+        .fold(
+            (vec![], vec![], vec![], vec![], vec![], vec![]),
+            |mut acc, (fs_name, x, u)| {
+                acc.0.push(fs_name.to_string());
+                acc.1.push(x.target.clone());
+                acc.2.push(u.id.clone());
+                acc.3.push(u.index as i64);
+                acc.4.push(x.current_index as i64);
+                acc.5.push(u.idle_secs.map(|x| x as i64));
+
+                acc
+            },
+        );
+
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM changelog_user c
+        WHERE c.host_id = $1
+        AND NOT EXISTS (
+            SELECT 1 FROM UNNEST($2::text[], $3::text[]) AS x(target, user_id)
+            WHERE x.target = c.target AND x.user_id = c.user_id
+        )
+    "#,
+        host_id,
+        &x.1,
+        &x.2
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    // When the idle time of a user is not reported,
+    // the user is considered idle from the first report with an unchanged index
+    sqlx::query!(
+        r#"
+        INSERT INTO changelog_user
+        (host_id, fs_name, target, user_id, idx, current_index, index_updated_at)
+        SELECT $1, fs_name, target, user_id, idx, current_index,
+            now() - COALESCE(idle_secs, 0) * interval '1 second'
+        FROM UNNEST(
+            $2::text[],
+            $3::text[],
+            $4::text[],
+            $5::bigint[],
+            $6::bigint[],
+            $7::bigint[]
+        ) AS x(fs_name, target, user_id, idx, current_index, idle_secs)
+        ON CONFLICT (target, user_id) DO UPDATE
+        SET host_id = EXCLUDED.host_id,
+            idx = EXCLUDED.idx,
+            current_index = EXCLUDED.current_index,
+            index_updated_at = CASE
+                WHEN EXCLUDED.index_updated_at < now() THEN EXCLUDED.index_updated_at
+                WHEN changelog_user.idx = EXCLUDED.idx THEN changelog_user.index_updated_at
+                ELSE now()
+            END,
+            updated_at = now()
+    "#,
+        host_id,
+        &x.0,
+        &x.1,
+        &x.2,
+        &x.3,
+        &x.4,
+        &x.5 as &[Option<i64>],
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+/// Returns a description of each changelog user on the host that is too far behind
+/// and of each user that looks abandoned
+async fn offenders(
+    pool: &PgPool,
+    host_id: i32,
+    lag_threshold: i64,
+    idle_threshold: i64,
+) -> Result<(Vec<String>, Vec<String>), sqlx::Error> {
+    let xs = sqlx::query!(
+        r#"
+        SELECT
+            target,
+            user_id,
+            current_index - idx AS "lag!",
+            (
+                current_index > idx
+                AND index_updated_at < now() - $2 * interval '1 second'
+            ) AS "abandoned!"
+        FROM changelog_user
+        WHERE host_id = $1
+        ORDER BY target, user_id
+    "#,
+        host_id,
+        idle_threshold as f64
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let lagging = xs
+        .iter()
+        .filter(|x| x.lag > lag_threshold)
+        .map(|x| format!("{} {} ({} records behind)", x.target, x.user_id, x.lag))
+        .collect();
+
+    let abandoned = xs
+        .iter()
+        .filter(|x| x.abandoned)
+        .map(|x| format!("{} {}", x.target, x.user_id))
+        .collect();
+
+    Ok((lagging, abandoned))
+}
+
+/// Raises `record_type` on the host listing `xs`, or lowers it if `xs` is empty.
+/// The alert is re-raised whenever its message changes.
+///
+/// `raised` holds the message of each active alert.
+async fn update_alert(
+    pool: &PgPool,
+    raised: &mut HashMap<(i32, AlertRecordType), String>,
+    host: &ManagedHostRecord,
+    record_type: AlertRecordType,
+    severity: AlertSeverity,
+    msg: String,
+    xs: Vec<String>,
+) -> Result<(), sqlx::Error> {
+    let key = (host.id, record_type);
+
+    if xs.is_empty() {
+        if raised.remove(&key).is_some() {
+            alert::lower(pool, vec![record_type], host.id).await?;
+        }
+
+        return Ok(());
+    }
+
+    let msg = format!("{}: {}", msg, xs.join(", "));
+
+    match raised.get(&key) {
+        Some(x) if x == &msg => return Ok(()),
+        Some(_) => alert::lower(pool, vec![record_type], host.id).await?,
+        None => {}
+    };
+
+    alert::raise(
+        pool,
+        record_type,
+        msg.clone(),
+        host.content_type_id.expect("Host has no content_type_id"),
+        None,
+        severity,
+        host.id,
+    )
+    .await?;
+
+    raised.insert(key, msg);
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    iml_tracing::init();
+
+    let pool = get_db_pool(get_pool_limit().unwrap_or(DEFAULT_POOL_LIMIT)).await?;
+
+    let rabbit_pool = iml_rabbit::connect_to_rabbit(1);
+
+    let conn = iml_rabbit::get_conn(rabbit_pool).await?;
+
+    let ch = iml_rabbit::create_channel(&conn).await?;
+
+    let lag_threshold = get_changelog_lag_threshold();
+    let idle_threshold = get_changelog_idle_threshold();
+
+    let mut s = consume_data::<Vec<ChangelogStatus>>(&ch, "rust_agent_changelog_rx");
+
+    sqlx::migrate!("../../migrations").run(&pool).await?;
+
+    let mut raised = HashMap::new();
+
+    for record_type in [
+        AlertRecordType::ChangelogLagAlert,
+        AlertRecordType::ChangelogUserAbandonedAlert,
+    ]
+    .iter()
+    {
+        for (host_id, msg) in alert::active_messages(&pool, *record_type).await? {
+            raised.insert((host_id, *record_type), msg);
+        }
+    }
+
+    while let Some((fqdn, xs)) = s.try_next().await? {
+        tracing::debug!("fqdn: {:?} mdts: {}", fqdn, xs.len());
+
+        let host: Option<ManagedHostRecord> = sqlx::query_as!(
+            ManagedHostRecord,
+            "select * from chroma_core_managedhost where fqdn = $1 and not_deleted = 't'",
+            fqdn.to_string()
+        )
+        .fetch_optional(&pool)
+        .await?;
+
+        let host = match host {
+            Some(x) => x,
+            None => {
+                tracing::warn!("Host '{}' is unknown", fqdn);
+
+                continue;
+            }
+        };
+
+        update_users(&pool, host.id, &xs).await?;
+
+        let (lagging, abandoned) = offenders(&pool, host.id, lag_threshold, idle_threshold).await?;
+
+        update_alert(
+            &pool,
+            &mut raised,
+            &host,
+            AlertRecordType::ChangelogLagAlert,
+            AlertSeverity::WARNING,
+            format!("Changelog users are falling behind on server {}", host.fqdn),
+            lagging,
+        )
+        .await?;
+
+        update_alert(
+            &pool,
+            &mut raised,
+            &host,
+            AlertRecordType::ChangelogUserAbandonedAlert,
+            AlertSeverity::ERROR,
+            format!(
+                "Changelog users have stopped clearing records on server {}",
+                host.fqdn
+            ),
+            abandoned,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fs_name() {
+        assert_eq!(fs_name("fs-MDT0000"), Some("fs"));
+        assert_eq!(fs_name("my-fs-MDT0001"), Some("my-fs"));
+    }
+}
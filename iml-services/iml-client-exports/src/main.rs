@@ -0,0 +1,118 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use futures::TryStreamExt;
+use iml_manager_env::get_pool_limit;
+use iml_postgres::{get_db_pool, host_id_by_fqdn, sqlx, PgPool};
+use iml_service_queue::service_queue::consume_data;
+use iml_tracing::tracing;
+use iml_wire_types::client::ClientExport;
+
+// Default pool limit if not overridden by POOL_LIMIT
+const DEFAULT_POOL_LIMIT: u32 = 2;
+
+/// Returns the filesystem name of a target, i.e. `fs` for `fs-OST0000`
+fn fs_name(target: &str) -> Option<&str> {
+    target.rsplitn(2, '-').nth(1)
+}
+
+/// Replaces the exports reported by a host.
+///
+/// A target that failed over is reported by its new host,
+/// so existing exports of the target move to that host.
+async fn update_exports(
+    pool: &PgPool,
+    host_id: i32,
+    xs: &[ClientExport],
+) -> Result<(), sqlx::Error> {
+    let (fs_names, (targets, nids)): (Vec<String>, (Vec<String>, Vec<String>)) = xs
+        .iter()
+        .filter_map(|x| fs_name(&x.target).map(|fs_name| (fs_name, x)))
+        .map(|(fs_name, x)| (fs_name.to_string(), (x.target.clone(), x.nid.clone())))
+        .unzip();
+
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM client_export e
+        WHERE e.host_id = $1
+        AND NOT EXISTS (
+            SELECT 1 FROM UNNEST($2::text[], $3::text[]) AS x(target, nid)
+            WHERE x.target = e.target AND x.nid = e.nid
+        )
+    "#,
+        host_id,
+        &targets,
+        &nids
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO client_export (host_id, fs_name, target, nid)
+        SELECT $1, fs_name, target, nid
+        FROM UNNEST($2::text[], $3::text[], $4::text[]) AS x(fs_name, target, nid)
+        ON CONFLICT (target, nid) DO UPDATE
+        SET host_id = EXCLUDED.host_id, last_seen = now()
+    "#,
+        host_id,
+        &fs_names,
+        &targets,
+        &nids
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    iml_tracing::init();
+
+    let pool = get_db_pool(get_pool_limit().unwrap_or(DEFAULT_POOL_LIMIT)).await?;
+
+    let rabbit_pool = iml_rabbit::connect_to_rabbit(1);
+
+    let conn = iml_rabbit::get_conn(rabbit_pool).await?;
+
+    let ch = iml_rabbit::create_channel(&conn).await?;
+
+    let mut s = consume_data::<Vec<ClientExport>>(&ch, "rust_agent_client_exports_rx");
+
+    sqlx::migrate!("../../migrations").run(&pool).await?;
+
+    while let Some((fqdn, xs)) = s.try_next().await? {
+        tracing::debug!("fqdn: {:?} exports: {}", fqdn, xs.len());
+
+        let host_id = match host_id_by_fqdn(&fqdn, &pool).await? {
+            Some(x) => x,
+            None => {
+                tracing::warn!("Host '{}' is unknown", fqdn);
+
+                continue;
+            }
+        };
+
+        update_exports(&pool, host_id, &xs).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fs_name() {
+        assert_eq!(fs_name("fs-OST0000"), Some("fs"));
+        assert_eq!(fs_name("my-fs-MDT0001"), Some("my-fs"));
+        assert_eq!(fs_name("MGS"), None);
+    }
+}
@@ -4,7 +4,10 @@
 
 use iml_postgres::{sqlx, PgPool};
 use iml_service_queue::service_queue::ImlServiceQueueError;
-use iml_wire_types::high_availability::{Ban, Node, Resource};
+use iml_wire_types::{
+    high_availability::{Ban, CorosyncLink, Node, Quorum, Resource, StonithNode},
+    PacemakerKindOrScore, PacemakerScore, ResourceConstraint,
+};
 use std::{collections::HashMap, fmt};
 use thiserror::Error;
 
@@ -118,6 +121,339 @@ pub async fn delete_corosync_resource_bans(
     Ok(())
 }
 
+pub async fn delete_resource_constraints(
+    cluster_id: i32,
+    names: &[String],
+    pool: &PgPool,
+) -> Result<(), ImlCorosyncError> {
+    sqlx::query!(
+        r#"
+            DELETE FROM corosync_resource_constraint
+            WHERE cluster_id = $1
+            AND name != ALL($2)
+        "#,
+        cluster_id,
+        names
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Pacemaker treats any score of 1,000,000 or more as INFINITY
+const PACEMAKER_INFINITY: i32 = 1_000_000;
+
+fn score_value(x: &PacemakerScore) -> i32 {
+    match x {
+        PacemakerScore::Infinity => PACEMAKER_INFINITY,
+        PacemakerScore::NegInfinity => -PACEMAKER_INFINITY,
+        PacemakerScore::Value(x) => *x,
+    }
+}
+
+pub async fn upsert_resource_constraints(
+    cluster_id: i32,
+    constraints: Vec<ResourceConstraint>,
+    pool: &PgPool,
+) -> Result<(), ImlCorosyncError> {
+    let x = constraints.into_iter().fold(
+        (
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+        ),
+        |mut acc, x| {
+            let (name, constraint_type, resource, with_resource, node, ticket, score, kind) =
+                match x {
+                    ResourceConstraint::Location {
+                        id,
+                        rsc,
+                        node,
+                        score,
+                    } => (
+                        id,
+                        "location",
+                        rsc,
+                        None,
+                        Some(node),
+                        None,
+                        Some(score_value(&score)),
+                        None,
+                    ),
+                    ResourceConstraint::Colocation {
+                        id,
+                        rsc,
+                        with_rsc,
+                        score,
+                    } => (
+                        id,
+                        "colocation",
+                        rsc,
+                        Some(with_rsc),
+                        None,
+                        None,
+                        Some(score_value(&score)),
+                        None,
+                    ),
+                    ResourceConstraint::Order {
+                        id,
+                        first,
+                        then,
+                        kind,
+                        ..
+                    } => {
+                        let (score, kind) = match kind {
+                            Some(PacemakerKindOrScore::Score(x)) => (Some(score_value(&x)), None),
+                            Some(PacemakerKindOrScore::Kind(x)) => (None, Some(x.to_string())),
+                            None => (None, None),
+                        };
+
+                        (id, "order", first, Some(then), None, None, score, kind)
+                    }
+                    ResourceConstraint::Ticket {
+                        id, rsc, ticket, ..
+                    } => (id, "ticket", rsc, None, None, Some(ticket), None, None),
+                };
+
+            acc.0.push(name);
+            acc.1.push(constraint_type.to_string());
+            acc.2.push(resource);
+            acc.3.push(with_resource);
+            acc.4.push(node);
+            acc.5.push(ticket);
+            acc.6.push(score);
+            acc.7.push(kind);
+
+            acc
+        },
+    );
+
+    sqlx::query!(
+        r#"
+            INSERT INTO corosync_resource_constraint (
+                name,
+                cluster_id,
+                constraint_type,
+                resource,
+                with_resource,
+                node,
+                ticket,
+                score,
+                kind
+            )
+            SELECT
+                name,
+                $9,
+                constraint_type,
+                resource,
+                with_resource,
+                node,
+                ticket,
+                score,
+                kind
+            FROM UNNEST(
+                $1::text[],
+                $2::text[],
+                $3::text[],
+                $4::text[],
+                $5::text[],
+                $6::text[],
+                $7::int[],
+                $8::text[]
+            )
+            AS t(
+                name,
+                constraint_type,
+                resource,
+                with_resource,
+                node,
+                ticket,
+                score,
+                kind
+            )
+            ON CONFLICT (name, cluster_id) DO UPDATE
+            SET
+                constraint_type = excluded.constraint_type,
+                resource = excluded.resource,
+                with_resource = excluded.with_resource,
+                node = excluded.node,
+                ticket = excluded.ticket,
+                score = excluded.score,
+                kind = excluded.kind
+        "#,
+        &x.0,
+        &x.1,
+        &x.2,
+        &x.3 as &[Option<String>],
+        &x.4 as &[Option<String>],
+        &x.5 as &[Option<String>],
+        &x.6 as &[Option<i32>],
+        &x.7 as &[Option<String>],
+        cluster_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_stonith_nodes(
+    cluster_id: i32,
+    nodes: &[String],
+    pool: &PgPool,
+) -> Result<(), ImlCorosyncError> {
+    sqlx::query!(
+        r#"
+            DELETE FROM corosync_stonith_node
+            WHERE cluster_id = $1
+            AND node != ALL($2)
+        "#,
+        cluster_id,
+        nodes
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn upsert_stonith_nodes(
+    cluster_id: i32,
+    xs: Vec<StonithNode>,
+    pool: &PgPool,
+) -> Result<(), ImlCorosyncError> {
+    for x in xs {
+        sqlx::query!(
+            r#"
+                INSERT INTO corosync_stonith_node (cluster_id, node, devices, errors)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (node, cluster_id) DO UPDATE
+                SET
+                    devices = excluded.devices,
+                    errors = excluded.errors
+            "#,
+            cluster_id,
+            x.node,
+            &x.devices,
+            &x.errors
+        )
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn delete_links(
+    host_id: i32,
+    link_ids: &[i32],
+    pool: &PgPool,
+) -> Result<(), ImlCorosyncError> {
+    sqlx::query!(
+        r#"
+            DELETE FROM corosync_link
+            WHERE host_id = $1
+            AND link_id != ALL($2)
+        "#,
+        host_id,
+        link_ids
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn upsert_links(
+    host_id: i32,
+    cluster_id: i32,
+    links: &[CorosyncLink],
+    pool: &PgPool,
+) -> Result<(), ImlCorosyncError> {
+    let x = links
+        .iter()
+        .fold((vec![], vec![], vec![], vec![]), |mut acc, x| {
+            acc.0.push(x.id as i32);
+            acc.1.push(x.address.to_string());
+            acc.2.push(x.faulty);
+            acc.3.push(x.status.to_string());
+
+            acc
+        });
+
+    sqlx::query!(
+        r#"
+            INSERT INTO corosync_link (host_id, cluster_id, link_id, address, faulty, status)
+            SELECT $5, $6, link_id, address, faulty, status
+            FROM UNNEST($1::int[], $2::text[], $3::bool[], $4::text[])
+            AS t(link_id, address, faulty, status)
+            ON CONFLICT (host_id, link_id) DO UPDATE
+            SET
+                cluster_id = excluded.cluster_id,
+                address = excluded.address,
+                faulty = excluded.faulty,
+                status = excluded.status
+        "#,
+        &x.0,
+        &x.1,
+        &x.2,
+        &x.3,
+        host_id,
+        cluster_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_quorum(host_id: i32, pool: &PgPool) -> Result<(), ImlCorosyncError> {
+    sqlx::query!("DELETE FROM corosync_quorum WHERE host_id = $1", host_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn upsert_quorum(
+    host_id: i32,
+    cluster_id: i32,
+    x: &Quorum,
+    pool: &PgPool,
+) -> Result<(), ImlCorosyncError> {
+    sqlx::query!(
+        r#"
+            INSERT INTO corosync_quorum (host_id, cluster_id, quorate, nodes, expected_votes, total_votes, quorum)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (host_id) DO UPDATE
+            SET
+                cluster_id = excluded.cluster_id,
+                quorate = excluded.quorate,
+                nodes = excluded.nodes,
+                expected_votes = excluded.expected_votes,
+                total_votes = excluded.total_votes,
+                quorum = excluded.quorum
+        "#,
+        host_id,
+        cluster_id,
+        x.quorate,
+        x.nodes as i32,
+        x.expected_votes as i32,
+        x.total_votes as i32,
+        x.quorum as i32
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
 pub async fn delete_nodes(
     host_id: i32,
     xs: &[String],
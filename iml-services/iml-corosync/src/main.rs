@@ -4,17 +4,21 @@
 
 use futures::TryStreamExt;
 use iml_corosync::{
-    delete_cluster, delete_corosync_resource_bans, delete_nodes, delete_target_resources,
+    delete_cluster, delete_corosync_resource_bans, delete_links, delete_nodes, delete_quorum,
+    delete_resource_constraints, delete_stonith_nodes, delete_target_resources,
     fetch_corosync_cluster_by_nodes, update_chroma_ticket, upsert_cluster_nodes,
-    upsert_corosync_cluster, upsert_node_managed_host, upsert_resource_bans,
+    upsert_corosync_cluster, upsert_links, upsert_node_managed_host, upsert_quorum,
+    upsert_resource_bans, upsert_resource_constraints, upsert_stonith_nodes,
     upsert_target_resource_managed_host, upsert_target_resources, CorosyncNodeKey,
     ImlCorosyncError,
 };
 use iml_manager_env::get_pool_limit;
-use iml_postgres::{get_db_pool, host_id_by_fqdn, sqlx};
+use iml_postgres::{alert, get_db_pool, sqlx};
 use iml_service_queue::service_queue::consume_data;
 use iml_tracing::tracing;
-use iml_wire_types::high_availability::Cluster;
+use iml_wire_types::{
+    db::ManagedHostRecord, high_availability::Cluster, AlertRecordType, AlertSeverity,
+};
 use std::collections::BTreeSet;
 
 // Default pool limit if not overridden by POOL_LIMIT
@@ -37,10 +41,16 @@ async fn main() -> Result<(), ImlCorosyncError> {
     sqlx::migrate!("../../migrations").run(&pool).await?;
 
     while let Some((fqdn, (local_node_id, cluster))) = s.try_next().await? {
-        let host_id = host_id_by_fqdn(&fqdn, &pool).await?;
+        let host: Option<ManagedHostRecord> = sqlx::query_as!(
+            ManagedHostRecord,
+            "select * from chroma_core_managedhost where fqdn = $1 and not_deleted = 't'",
+            fqdn.to_string()
+        )
+        .fetch_optional(&pool)
+        .await?;
 
-        let host_id = match host_id {
-            Some(id) => id,
+        let host = match host {
+            Some(x) => x,
             None => {
                 tracing::warn!("Host '{}' is unknown, discarding incoming data", fqdn);
 
@@ -48,6 +58,8 @@ async fn main() -> Result<(), ImlCorosyncError> {
             }
         };
 
+        let host_id = host.id;
+
         let node_keys: BTreeSet<CorosyncNodeKey> = cluster
             .nodes
             .iter()
@@ -75,6 +87,16 @@ async fn main() -> Result<(), ImlCorosyncError> {
 
         let ban_ids: Vec<String> = cluster.bans.iter().map(|x| x.id.to_string()).collect();
 
+        let constraint_ids: Vec<String> = cluster
+            .constraints
+            .iter()
+            .map(|x| x.id().to_string())
+            .collect();
+
+        let stonith_nodes: Vec<String> = cluster.stonith.iter().map(|x| x.node.clone()).collect();
+
+        let link_ids: Vec<i32> = cluster.links.iter().map(|x| x.id as i32).collect();
+
         upsert_corosync_cluster(&node_keys_db, &pool).await?;
 
         let cluster_id = fetch_corosync_cluster_by_nodes(&node_keys_db, &pool).await?;
@@ -84,6 +106,12 @@ async fn main() -> Result<(), ImlCorosyncError> {
 
         delete_corosync_resource_bans(host_id, &ban_ids, &pool).await?;
 
+        delete_resource_constraints(cluster_id, &constraint_ids, &pool).await?;
+
+        delete_stonith_nodes(cluster_id, &stonith_nodes, &pool).await?;
+
+        delete_links(host_id, &link_ids, &pool).await?;
+
         delete_nodes(host_id, &node_keys_db, &pool).await?;
 
         delete_target_resources(host_id, &resource_ids, &pool).await?;
@@ -103,9 +131,73 @@ async fn main() -> Result<(), ImlCorosyncError> {
 
         upsert_resource_bans(cluster_id, cluster.bans, &pool).await?;
 
+        upsert_resource_constraints(cluster_id, cluster.constraints, &pool).await?;
+
+        upsert_stonith_nodes(cluster_id, cluster.stonith, &pool).await?;
+
+        upsert_links(host_id, cluster_id, &cluster.links, &pool).await?;
+
+        match cluster.quorum.as_ref() {
+            Some(x) => upsert_quorum(host_id, cluster_id, x, &pool).await?,
+            None => delete_quorum(host_id, &pool).await?,
+        }
+
         upsert_node_managed_host(host_id, cluster_id, local_node_key, &pool).await?;
 
         upsert_target_resource_managed_host(host_id, cluster_id, &resource_ids, &pool).await?;
+
+        let faulty: Vec<String> = cluster
+            .links
+            .iter()
+            .filter(|x| x.faulty)
+            .map(|x| format!("{} ({})", x.id, x.address))
+            .collect();
+
+        if faulty.is_empty() || !host.is_setup() {
+            alert::lower(
+                &pool,
+                vec![AlertRecordType::CorosyncRingFaultyAlert],
+                host_id,
+            )
+            .await?;
+        } else {
+            alert::raise(
+                &pool,
+                AlertRecordType::CorosyncRingFaultyAlert,
+                format!("Corosync ring {} faulty on {}", faulty.join(", "), fqdn),
+                host.content_type_id.expect("Host has no content_type_id"),
+                None,
+                AlertSeverity::ERROR,
+                host_id,
+            )
+            .await?;
+        }
+
+        match cluster.quorum {
+            Some(x) if !x.quorate && host.is_setup() => {
+                alert::raise(
+                    &pool,
+                    AlertRecordType::CorosyncQuorumLostAlert,
+                    format!(
+                        "Corosync quorum lost on {} ({} of {} expected votes, {} needed)",
+                        fqdn, x.total_votes, x.expected_votes, x.quorum
+                    ),
+                    host.content_type_id.expect("Host has no content_type_id"),
+                    None,
+                    AlertSeverity::CRITICAL,
+                    host_id,
+                )
+                .await?;
+            }
+            _ => {
+                alert::lower(
+                    &pool,
+                    vec![AlertRecordType::CorosyncQuorumLostAlert],
+                    host_id,
+                )
+                .await?;
+            }
+        }
     }
 
     Ok(())
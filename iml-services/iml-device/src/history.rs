@@ -0,0 +1,308 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Detects changes between two device trees of a host, so they can be recorded in `device_history`.
+
+use crate::ImlDeviceError;
+use device_types::{
+    devices::{Device, DeviceId},
+    mount::Mount,
+    DevicePath,
+};
+use im::OrdSet;
+use iml_change::{Deletions, GetChanges as _, Identifiable, Upserts};
+use iml_postgres::{sqlx, PgPool};
+use iml_tracing::tracing;
+use iml_wire_types::{device_history::DeviceChangeKind, Fqdn};
+use std::collections::BTreeMap;
+
+/// The parts of a device that are tracked over time.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DeviceSummary {
+    pub id: DeviceId,
+    pub device_type: &'static str,
+    pub paths: Vec<String>,
+    pub size: Option<u64>,
+    pub mount: Option<String>,
+}
+
+impl Identifiable for DeviceSummary {
+    type Id = DeviceId;
+
+    fn id(&self) -> Self::Id {
+        self.id.clone()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceChange {
+    pub device_id: String,
+    pub device_type: &'static str,
+    pub change: DeviceChangeKind,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+fn format_paths(xs: &OrdSet<DevicePath>) -> Vec<String> {
+    xs.iter()
+        .map(|x| x.0.to_string_lossy().to_string())
+        .collect()
+}
+
+fn format_mount(x: &Option<Mount>) -> Option<String> {
+    x.as_ref().map(|x| {
+        format!(
+            "{} ({}, {})",
+            x.target.0.to_string_lossy(),
+            x.fs_type.0,
+            x.opts.0
+        )
+    })
+}
+
+fn summarize(device: &Device) -> Option<DeviceSummary> {
+    let id = device.get_id()?;
+
+    let (device_type, paths, size, mount) = match device {
+        Device::Root(_) => return None,
+        Device::ScsiDevice(x) => ("ScsiDevice", format_paths(&x.paths), Some(x.size), &x.mount),
+        Device::NvmeNamespace(x) => (
+            "NvmeNamespace",
+            format_paths(&x.paths),
+            Some(x.size),
+            &x.mount,
+        ),
+        Device::Partition(x) => ("Partition", format_paths(&x.paths), Some(x.size), &x.mount),
+        Device::MdRaid(x) => ("MdRaid", format_paths(&x.paths), Some(x.size), &x.mount),
+        Device::Mpath(x) => ("Mpath", format_paths(&x.paths), Some(x.size), &x.mount),
+        Device::DmDevice(x) => ("DmDevice", format_paths(&x.paths), Some(x.size), &x.mount),
+        Device::VolumeGroup(x) => ("VolumeGroup", vec![], Some(x.size), &None),
+        Device::LogicalVolume(x) => (
+            "LogicalVolume",
+            format_paths(&x.paths),
+            Some(x.size),
+            &x.mount,
+        ),
+        Device::Zpool(x) => ("Zpool", vec![], Some(x.size), &x.mount),
+        Device::Dataset(x) => ("Dataset", vec![], None, &x.mount),
+    };
+
+    Some(DeviceSummary {
+        id,
+        device_type,
+        paths,
+        size,
+        mount: format_mount(mount),
+    })
+}
+
+/// Collects a summary of every device in the tree, keyed by id.
+///
+/// Devices can appear more than once in the tree (i.e. a volume group under each of its PVs),
+/// so only the first is kept.
+pub fn summarize_devices(device: &Device, xs: &mut BTreeMap<DeviceId, DeviceSummary>) {
+    if let Some(x) = summarize(device) {
+        xs.entry(x.id.clone()).or_insert(x);
+    }
+
+    if let Some(children) = device.children() {
+        for c in children {
+            summarize_devices(c, xs);
+        }
+    }
+}
+
+fn diff_summary(old: &DeviceSummary, new: &DeviceSummary) -> Vec<DeviceChange> {
+    let change = |change, old_value, new_value| DeviceChange {
+        device_id: new.id.0.clone(),
+        device_type: new.device_type,
+        change,
+        old_value,
+        new_value,
+    };
+
+    let mut xs = vec![];
+
+    if old.paths != new.paths {
+        xs.push(change(
+            DeviceChangeKind::Paths,
+            Some(old.paths.join(", ")),
+            Some(new.paths.join(", ")),
+        ));
+    }
+
+    if old.size != new.size {
+        xs.push(change(
+            DeviceChangeKind::Size,
+            old.size.map(|x| x.to_string()),
+            new.size.map(|x| x.to_string()),
+        ));
+    }
+
+    if old.mount != new.mount {
+        xs.push(change(
+            DeviceChangeKind::Mount,
+            old.mount.clone(),
+            new.mount.clone(),
+        ));
+    }
+
+    xs
+}
+
+/// Compares the previous device tree of a host with the new one and returns what changed.
+pub fn get_device_changes(old: Option<&Device>, new: &Device) -> Vec<DeviceChange> {
+    let mut old_summaries = BTreeMap::new();
+
+    if let Some(old) = old {
+        summarize_devices(old, &mut old_summaries);
+    }
+
+    let mut new_summaries = BTreeMap::new();
+    summarize_devices(new, &mut new_summaries);
+
+    let olds: Vec<DeviceSummary> = old_summaries.values().cloned().collect();
+    let news: Vec<DeviceSummary> = new_summaries.values().cloned().collect();
+
+    let (upserts, deletions) = news.get_changes(&olds);
+
+    let mut xs = vec![];
+
+    if let Some(Upserts(ys)) = upserts {
+        for y in ys {
+            match old_summaries.get(&y.id) {
+                Some(old) => xs.extend(diff_summary(old, y)),
+                None => xs.push(DeviceChange {
+                    device_id: y.id.0.clone(),
+                    device_type: y.device_type,
+                    change: DeviceChangeKind::Added,
+                    old_value: None,
+                    new_value: Some(y.paths.join(", ")).filter(|x| !x.is_empty()),
+                }),
+            }
+        }
+    }
+
+    if let Some(Deletions(ys)) = deletions {
+        for y in ys {
+            xs.push(DeviceChange {
+                device_id: y.id.0.clone(),
+                device_type: y.device_type,
+                change: DeviceChangeKind::Removed,
+                old_value: Some(y.paths.join(", ")).filter(|x| !x.is_empty()),
+                new_value: None,
+            });
+        }
+    }
+
+    xs
+}
+
+pub async fn insert_device_changes(
+    pool: &PgPool,
+    host: &Fqdn,
+    changes: Vec<DeviceChange>,
+) -> Result<(), ImlDeviceError> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    tracing::debug!("Recording {} device changes for {}", changes.len(), host);
+
+    let x = changes
+        .into_iter()
+        .fold((vec![], vec![], vec![], vec![], vec![]), |mut acc, x| {
+            acc.0.push(x.device_id);
+            acc.1.push(x.device_type.to_string());
+            acc.2.push(x.change.to_string());
+            acc.3.push(x.old_value);
+            acc.4.push(x.new_value);
+
+            acc
+        });
+
+    sqlx::query!(
+        r#"
+        INSERT INTO device_history
+        (fqdn, device_id, device_type, change, old_value, new_value)
+        SELECT $1, device_id, device_type, change, old_value, new_value
+        FROM UNNEST($2::text[], $3::text[], $4::device_change_kind[], $5::text[], $6::text[])
+        AS t(device_id, device_type, change, old_value, new_value)
+    "#,
+        host.to_string(),
+        &x.0,
+        &x.1,
+        &x.2 as &[String],
+        &x.3 as &[Option<String>],
+        &x.4 as &[Option<String>],
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Removes device history older than `retention` seconds.
+pub async fn prune_device_history(pool: &PgPool, retention: i64) -> Result<u64, ImlDeviceError> {
+    let x = sqlx::query!(
+        "DELETE FROM device_history WHERE time < now() - $1 * interval '1 second'",
+        retention as f64
+    )
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    Ok(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_device_changes() {
+        let old: Device =
+            serde_json::from_slice(include_bytes!("../fixtures/devtree.json")).unwrap();
+
+        assert_eq!(get_device_changes(Some(&old), &old), vec![]);
+
+        let added = get_device_changes(None, &old);
+
+        assert!(!added.is_empty());
+        assert!(added.iter().all(|x| x.change == DeviceChangeKind::Added));
+
+        let removed = get_device_changes(Some(&old), &Device::Root(Default::default()));
+
+        assert_eq!(removed.len(), added.len());
+        assert!(removed
+            .iter()
+            .all(|x| x.change == DeviceChangeKind::Removed));
+    }
+
+    #[test]
+    fn test_diff_summary() {
+        let old = DeviceSummary {
+            id: DeviceId("scsi_36001405943dd5f394fb4b5ba71ec818f".into()),
+            device_type: "ScsiDevice",
+            paths: vec!["/dev/sda".into()],
+            size: Some(5_368_709_120),
+            mount: None,
+        };
+
+        let new = DeviceSummary {
+            paths: vec!["/dev/sdb".into()],
+            size: Some(10_737_418_240),
+            ..old.clone()
+        };
+
+        let xs = diff_summary(&old, &new);
+
+        assert_eq!(
+            xs.iter().map(|x| x.change).collect::<Vec<_>>(),
+            vec![DeviceChangeKind::Paths, DeviceChangeKind::Size]
+        );
+        assert_eq!(xs[0].old_value.as_deref(), Some("/dev/sda"));
+        assert_eq!(xs[0].new_value.as_deref(), Some("/dev/sdb"));
+    }
+}
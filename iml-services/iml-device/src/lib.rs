@@ -3,6 +3,7 @@
 // license that can be found in the LICENSE file.
 
 pub mod error;
+pub mod history;
 pub mod linux_plugin_transforms;
 
 use device_types::{
@@ -271,6 +272,7 @@ fn add_shared_storage<'a>(
             device_id.0.starts_with("lv_")
                 || device_id.0.starts_with("dataset_")
                 || device_id.0.starts_with("mdraid_")
+                || device_id.0.starts_with("dm_")
         })
         .collect();
 
@@ -339,6 +341,23 @@ fn build_device_map(device: &Device, map: &mut DeviceMap, path: &[DeviceId]) {
         .for_each(|d| build_device_map(d, map, &parent_path));
 }
 
+/// Collects the names of any zpools in the device tree with the given health.
+///
+/// A pool is found under each of its vdevs, so the names are deduplicated.
+pub fn find_zpools_by_health<'a>(device: &'a Device, health: &str, xs: &mut BTreeSet<&'a str>) {
+    if let Device::Zpool(x) = device {
+        if x.health == health {
+            xs.insert(&x.name);
+        }
+    }
+
+    if let Some(children) = device.children() {
+        children
+            .iter()
+            .for_each(|d| find_zpools_by_health(d, health, xs));
+    }
+}
+
 pub fn find_targets<'a>(
     x: &'a HashMap<Fqdn, Device>,
     mounts: &HashMap<Fqdn, HashSet<Mount>>,
@@ -553,6 +572,22 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_find_zpools_by_health() {
+        let device: Device =
+            serde_json::from_slice(include_bytes!("../fixtures/devtree_zpool.json")).unwrap();
+
+        let mut xs = BTreeSet::new();
+        find_zpools_by_health(&device, "ONLINE", &mut xs);
+
+        assert_eq!(xs.into_iter().collect::<Vec<_>>(), vec!["mgs"]);
+
+        let mut xs = BTreeSet::new();
+        find_zpools_by_health(&device, "DEGRADED", &mut xs);
+
+        assert!(xs.is_empty());
+    }
+
     #[test]
     fn test_upserts_only() {
         let ups = vec![
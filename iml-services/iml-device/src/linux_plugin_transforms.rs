@@ -4,8 +4,8 @@
 
 use device_types::{
     devices::{
-        Dataset, Device, LogicalVolume, MdRaid, Mpath, Partition, Root, ScsiDevice, VolumeGroup,
-        Zpool,
+        Dataset, Device, DmDevice, DmKind, LogicalVolume, MdRaid, Mpath, NvmeNamespace, Partition,
+        Root, ScsiDevice, VolumeGroup, Zpool,
     },
     get_vdev_paths,
     mount::{FsType, Mount, MountPoint},
@@ -60,6 +60,14 @@ pub struct LinuxPluginMpathDevice<'a> {
     nodes: BTreeSet<LinuxPluginDevice<'a>>,
 }
 
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LinuxPluginDmDevice<'a> {
+    name: &'a str,
+    kind: DmKind,
+    block_device: MajorMinor,
+    parents: BTreeSet<MajorMinor>,
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct LinuxPluginVgDevice<'a> {
     name: &'a str,
@@ -127,6 +135,7 @@ pub struct LinuxPluginData<'a> {
     pub lvs: BTreeMap<&'a str, BTreeMap<&'a str, LinuxPluginLvDevice<'a>>>,
     pub zfspools: BTreeMap<u64, LinuxPluginZpool<'a>>,
     pub zfsdatasets: BTreeMap<u64, LinuxPluginZpool<'a>>,
+    pub dms: BTreeMap<&'a str, LinuxPluginDmDevice<'a>>,
 }
 
 impl<'a> Default for LinuxPluginData<'a> {
@@ -139,6 +148,7 @@ impl<'a> Default for LinuxPluginData<'a> {
             lvs: BTreeMap::new(),
             zfspools: BTreeMap::new(),
             zfsdatasets: BTreeMap::new(),
+            dms: BTreeMap::new(),
         }
     }
 }
@@ -159,6 +169,25 @@ impl<'a> From<&'a ScsiDevice> for LinuxPluginDevice<'a> {
     }
 }
 
+/// With native multipath an NVMe namespace is a single block device
+/// no matter how many controllers it is reachable through,
+/// so it is reported like a SCSI device keyed on its wwid.
+impl<'a> From<&'a NvmeNamespace> for LinuxPluginDevice<'a> {
+    fn from(s: &NvmeNamespace) -> LinuxPluginDevice {
+        LinuxPluginDevice {
+            major_minor: (&s.major, &s.minor).into(),
+            parent: None,
+            path: s.paths.get_min().unwrap(),
+            paths: s.paths.iter().collect(),
+            partition_number: None,
+            serial_83: &s.wwid,
+            serial_80: &None,
+            size: Some(s.size),
+            filesystem_type: &s.filesystem_type,
+        }
+    }
+}
+
 impl<'a> From<(&'a Partition, Option<&LinuxPluginDevice<'a>>)> for LinuxPluginDevice<'a> {
     fn from((x, p): (&'a Partition, Option<&LinuxPluginDevice>)) -> LinuxPluginDevice<'a> {
         LinuxPluginDevice {
@@ -197,6 +226,25 @@ impl<'a> From<(&'a Mpath, Option<&LinuxPluginDevice<'a>>)> for LinuxPluginDevice
     }
 }
 
+impl<'a> From<(&'a DmDevice, Option<&LinuxPluginDevice<'a>>)> for LinuxPluginDevice<'a> {
+    fn from((x, _p): (&'a DmDevice, Option<&LinuxPluginDevice>)) -> LinuxPluginDevice<'a> {
+        LinuxPluginDevice {
+            major_minor: (&x.major, &x.minor).into(),
+            // @FIXME
+            // See the note on `Mpath`, linux.py
+            // only allows partitions to have a parent.
+            parent: None,
+            path: x.paths.get_min().unwrap(),
+            paths: x.paths.iter().collect(),
+            partition_number: None,
+            serial_83: &None,
+            serial_80: &None,
+            size: Some(x.size),
+            filesystem_type: &x.filesystem_type,
+        }
+    }
+}
+
 impl<'a> From<(&'a LogicalVolume, Option<&LinuxPluginDevice<'a>>)> for LinuxPluginDevice<'a> {
     fn from((x, _p): (&'a LogicalVolume, Option<&LinuxPluginDevice>)) -> LinuxPluginDevice<'a> {
         LinuxPluginDevice {
@@ -334,6 +382,21 @@ pub fn devtree2linuxoutput<'a>(
                 .devs
                 .insert(d.major_minor.clone(), LinuxPluginItem::LinuxPluginDevice(d));
         }
+        Device::NvmeNamespace(x) => {
+            let d: LinuxPluginDevice = x.into();
+
+            if let Some(mount) = &x.mount {
+                add_mount(mount, &d, linux_plugin_data);
+            }
+
+            for c in &x.children {
+                devtree2linuxoutput(&c, Some(&d), &mut linux_plugin_data);
+            }
+
+            linux_plugin_data
+                .devs
+                .insert(d.major_minor.clone(), LinuxPluginItem::LinuxPluginDevice(d));
+        }
         Device::Partition(x) => {
             let d: LinuxPluginDevice = (x, parent).into();
 
@@ -380,6 +443,38 @@ pub fn devtree2linuxoutput<'a>(
                 mpath_device.nodes.insert(parent.clone());
             }
         }
+        Device::DmDevice(x) => {
+            let d: LinuxPluginDevice = (x, parent).into();
+
+            if let Some(mount) = &x.mount {
+                add_mount(mount, &d, linux_plugin_data);
+            }
+
+            for c in &x.children {
+                devtree2linuxoutput(c, Some(&d), &mut linux_plugin_data);
+            }
+
+            let block_device = d.major_minor.clone();
+
+            linux_plugin_data
+                .devs
+                .insert(d.major_minor.clone(), LinuxPluginItem::LinuxPluginDevice(d));
+
+            let dm_device =
+                linux_plugin_data
+                    .dms
+                    .entry(&x.dm_name)
+                    .or_insert(LinuxPluginDmDevice {
+                        name: &x.dm_name,
+                        kind: x.kind,
+                        block_device,
+                        parents: BTreeSet::new(),
+                    });
+
+            if let Some(parent) = parent {
+                dm_device.parents.insert(parent.major_minor.clone());
+            }
+        }
         Device::VolumeGroup(x) => {
             let vg_device = linux_plugin_data
                 .vgs
@@ -458,6 +553,13 @@ pub fn build_device_lookup<'a>(
             minor,
             ..
         })
+        | Device::NvmeNamespace(NvmeNamespace {
+            children,
+            paths,
+            major,
+            minor,
+            ..
+        })
         | Device::Partition(Partition {
             children,
             paths,
@@ -478,6 +580,13 @@ pub fn build_device_lookup<'a>(
             major,
             minor,
             ..
+        })
+        | Device::DmDevice(DmDevice {
+            children,
+            paths,
+            major,
+            minor,
+            ..
         }) => {
             for p in paths {
                 path_map.insert(p, (major, minor).into());
@@ -648,8 +757,11 @@ pub fn update_vgs<'a>(
 
 #[cfg(test)]
 mod tests {
-    use super::{devtree2linuxoutput, LinuxPluginData};
-    use device_types::devices::Device;
+    use super::{devtree2linuxoutput, LinuxPluginData, LinuxPluginItem, MajorMinor};
+    use device_types::devices::{
+        Device, DmDevice, DmKind, NvmeController, NvmeNamespace, Partition, Root, ScsiDevice,
+    };
+    use im::ordset;
     use insta::assert_json_snapshot;
 
     #[test]
@@ -688,4 +800,131 @@ mod tests {
 
         assert_json_snapshot!(data);
     }
+
+    #[test]
+    fn test_devtree2linuxoutput_nvme() {
+        let wwid = Some("eui.0025388b91b1c8e1".to_string());
+
+        let partition = Device::Partition(Partition {
+            serial: wwid.clone(),
+            scsi80: None,
+            partition_number: 1,
+            size: 1_048_576,
+            major: "259".into(),
+            minor: "1".into(),
+            devpath: "/devices/virtual/nvme-subsystem/nvme-subsys0/nvme0n1/nvme0n1p1".into(),
+            filesystem_type: Some("ext4".into()),
+            fs_uuid: None,
+            fs_label: None,
+            paths: ordset!["/dev/nvme0n1p1".into()],
+            mount: None,
+            children: ordset![],
+        });
+
+        let controller = |name: &str, ana_state: &str| NvmeController {
+            name: name.into(),
+            transport: "tcp".into(),
+            address: None,
+            state: Some("live".into()),
+            ana_state: Some(ana_state.into()),
+        };
+
+        let device = Device::Root(Root {
+            children: ordset![Device::NvmeNamespace(NvmeNamespace {
+                wwid: wwid.clone(),
+                subsysnqn: "nqn.2014-08.org.nvmexpress:lustre".into(),
+                nsid: 1,
+                controllers: ordset![
+                    controller("nvme0", "optimized"),
+                    controller("nvme1", "inaccessible")
+                ],
+                major: "259".into(),
+                minor: "0".into(),
+                devpath: "/devices/virtual/nvme-subsystem/nvme-subsys0/nvme0n1".into(),
+                size: 2_097_152,
+                filesystem_type: None,
+                fs_uuid: None,
+                fs_label: None,
+                paths: ordset!["/dev/nvme0n1".into()],
+                mount: None,
+                children: ordset![partition],
+            })],
+        });
+
+        let mut data = LinuxPluginData::default();
+
+        devtree2linuxoutput(&device, None, &mut data);
+
+        assert!(data.mpath.is_empty());
+
+        match &data.devs[&MajorMinor("259:0".into())] {
+            LinuxPluginItem::LinuxPluginDevice(d) => {
+                assert_eq!(d.serial_83, &wwid);
+                assert_eq!(d.parent, None);
+            }
+            x => panic!("Expected a device, got {:?}", x),
+        }
+
+        match &data.devs[&MajorMinor("259:1".into())] {
+            LinuxPluginItem::LinuxPluginDevice(d) => {
+                assert_eq!(d.parent, Some(MajorMinor("259:0".into())));
+                assert_eq!(d.partition_number, Some(1));
+            }
+            x => panic!("Expected a device, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_devtree2linuxoutput_dm_multiple_parents() {
+        let vdo = Device::DmDevice(DmDevice {
+            kind: DmKind::Vdo,
+            dm_name: "vdo0".into(),
+            uuid: "VDO-4ae9f4dc-8b4e-4a5c-9e42-3c2a3b0a1d7e".into(),
+            major: "253".into(),
+            minor: "3".into(),
+            devpath: "/devices/virtual/block/dm-3".into(),
+            size: 4_194_304,
+            filesystem_type: None,
+            fs_uuid: None,
+            fs_label: None,
+            paths: ordset!["/dev/mapper/vdo0".into(), "/dev/dm-3".into()],
+            mount: None,
+            children: ordset![],
+        });
+
+        let scsi = |name: &str, minor: &str| {
+            Device::ScsiDevice(ScsiDevice {
+                serial: Some(format!("serial-{}", name)),
+                scsi80: None,
+                major: "8".into(),
+                minor: minor.into(),
+                devpath: format!("/devices/pci0000:00/block/{}", name).into(),
+                size: 2_097_152,
+                filesystem_type: None,
+                fs_uuid: None,
+                fs_label: None,
+                paths: ordset![format!("/dev/{}", name).into()],
+                mount: None,
+                children: ordset![vdo.clone()],
+            })
+        };
+
+        let device = Device::Root(Root {
+            children: ordset![scsi("sda", "0"), scsi("sdb", "16")],
+        });
+
+        let mut data = LinuxPluginData::default();
+
+        devtree2linuxoutput(&device, None, &mut data);
+
+        assert_eq!(data.dms.len(), 1);
+
+        let dm = &data.dms["vdo0"];
+
+        assert_eq!(dm.block_device, MajorMinor("253:3".into()));
+        assert_eq!(
+            dm.parents.iter().collect::<Vec<_>>(),
+            vec![&MajorMinor("8:0".into()), &MajorMinor("8:16".into())]
+        );
+    }
 }
@@ -2,12 +2,16 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
-use device_types::{devices::Device, mount::Mount};
+use device_types::{
+    devices::Device,
+    stream::{DeviceGraph, StreamMessage},
+};
 use futures::{TryFutureExt, TryStreamExt};
-use im::HashSet;
 use iml_change::GetChanges as _;
 use iml_device::{
     build_device_index, client_mount_content_id, create_cache, create_target_cache, find_targets,
+    find_zpools_by_health,
+    history::{get_device_changes, insert_device_changes, prune_device_history},
     linux_plugin_transforms::{
         build_device_lookup, devtree2linuxoutput, get_shared_pools, populate_zpool, update_vgs,
         LinuxPluginData,
@@ -15,20 +19,39 @@ use iml_device::{
     update_client_mounts, update_devices, Cache, ImlDeviceError,
 };
 use iml_manager_env::get_pool_limit;
-use iml_postgres::{get_db_pool, sqlx, PgPool};
-use iml_service_queue::service_queue::consume_data;
+use iml_postgres::{alert, get_db_pool, sqlx, PgPool};
+use iml_service_queue::service_queue::consume_service_queue;
 use iml_tracing::tracing;
-use iml_wire_types::Fqdn;
+use iml_wire_types::{
+    db::ManagedHostRecord, AlertRecordType, AlertSeverity, Fqdn, ManagerMessage, PluginMessage,
+};
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
     iter::FromIterator,
     sync::Arc,
 };
+use tokio::time::{interval, Duration};
 use warp::Filter;
 
 // Default pool limit if not overridden by POOL_LIMIT
 const DEFAULT_POOL_LIMIT: u32 = 2;
 
+static AGENT_TX_RUST: &str = "agent_tx_rust";
+
+/// Zpool health values that raise an alert, along with the alert they raise.
+static ZPOOL_ALERTS: [(&str, AlertRecordType, AlertSeverity); 2] = [
+    (
+        "DEGRADED",
+        AlertRecordType::ZpoolDegradedAlert,
+        AlertSeverity::WARNING,
+    ),
+    (
+        "FAULTED",
+        AlertRecordType::ZpoolFaultedAlert,
+        AlertSeverity::ERROR,
+    ),
+];
+
 #[tokio::main]
 async fn main() -> Result<(), ImlDeviceError> {
     iml_tracing::init();
@@ -93,28 +116,97 @@ async fn main() -> Result<(), ImlDeviceError> {
 
     tokio::spawn(server);
 
+    let pool_2 = pool.clone();
+
+    tokio::spawn(async move {
+        let retention = iml_manager_env::get_device_history_retention();
+        let mut interval = interval(Duration::from_secs(3600));
+
+        loop {
+            interval.tick().await;
+
+            match prune_device_history(&pool_2, retention).await {
+                Ok(0) => {}
+                Ok(x) => tracing::info!("Pruned {} device history rows", x),
+                Err(e) => tracing::error!("Could not prune device history: {}", e),
+            }
+        }
+    });
+
     let rabbit_pool = iml_rabbit::connect_to_rabbit(1);
 
     let conn = iml_rabbit::get_conn(rabbit_pool).await?;
 
     let ch = iml_rabbit::create_channel(&conn).await?;
 
-    let mut s =
-        consume_data::<((Device, HashSet<Mount>), Vec<String>)>(&ch, "rust_agent_device_rx");
+    let mut s = consume_service_queue(&ch, "rust_agent_device_rx").await?;
 
     let lustreclientmount_ct_id = client_mount_content_id(&pool).await?;
 
+    let mut graphs: HashMap<Fqdn, DeviceGraph> = HashMap::new();
+
     let mut mount_cache = HashMap::new();
 
     let mut mgs_fs_cache = HashMap::new();
 
-    while let Some((host, ((devices, mounts), mgs_fses))) = s.try_next().await? {
+    let mut zpool_alerts = HashMap::new();
+
+    while let Some(msg) = s.try_next().await? {
+        let (host, plugin, session_id, body) = match msg {
+            PluginMessage::Data {
+                fqdn,
+                plugin,
+                session_id,
+                body,
+                ..
+            } => (fqdn, plugin, session_id, body),
+            _ => continue,
+        };
+
+        let (messages, mgs_fses): (Vec<StreamMessage>, Vec<String>) = serde_json::from_value(body)?;
+
+        let graph = graphs.entry(host.clone()).or_default();
+
+        let applied = messages.into_iter().try_for_each(|x| graph.apply(x));
+
+        if let Err(e) = applied {
+            tracing::warn!(
+                "Device graph for {} is out of sync ({}), requesting a new snapshot",
+                host,
+                e
+            );
+
+            graphs.remove(&host);
+
+            iml_rabbit::send_message(
+                &ch,
+                "",
+                AGENT_TX_RUST,
+                ManagerMessage::SessionTerminate {
+                    fqdn: host,
+                    plugin,
+                    session_id,
+                },
+            )
+            .await?;
+
+            continue;
+        }
+
+        let devices = graph.devices().clone();
+        let mounts = graph.mounts().clone();
+
         update_devices(&pool, &host, &devices).await?;
         update_client_mounts(&pool, lustreclientmount_ct_id, &host, &mounts).await?;
+        update_zpool_alerts(&pool, &host, &devices, &mut zpool_alerts).await?;
 
         let target_cache = create_target_cache(&pool).await?;
 
         let mut device_cache = cache2.lock().await;
+
+        let changes = get_device_changes(device_cache.get(&host), &devices);
+        insert_device_changes(&pool, &host, changes).await?;
+
         device_cache.insert(host.clone(), devices);
         mount_cache.insert(host.clone(), mounts);
         mgs_fs_cache.insert(host, mgs_fses);
@@ -221,6 +313,81 @@ async fn main() -> Result<(), ImlDeviceError> {
     Ok(())
 }
 
+/// Raise or lower alerts for any unhealthy zpools on the host.
+///
+/// `raised` holds the unhealthy pools last seen for each host and health,
+/// so the database is only touched when they change.
+async fn update_zpool_alerts(
+    pool: &PgPool,
+    fqdn: &Fqdn,
+    device: &Device,
+    raised: &mut HashMap<Fqdn, HashMap<&'static str, BTreeSet<String>>>,
+) -> Result<(), ImlDeviceError> {
+    let current: HashMap<&'static str, BTreeSet<String>> = ZPOOL_ALERTS
+        .iter()
+        .map(|(health, _, _)| {
+            let mut xs = BTreeSet::new();
+            find_zpools_by_health(device, health, &mut xs);
+
+            (*health, xs.into_iter().map(ToString::to_string).collect())
+        })
+        .collect();
+
+    let previous = raised.get(fqdn);
+
+    if previous == Some(&current) {
+        return Ok(());
+    }
+
+    let host: Option<ManagedHostRecord> = sqlx::query_as!(
+        ManagedHostRecord,
+        "select * from chroma_core_managedhost where fqdn = $1 and not_deleted = 't'",
+        fqdn.to_string()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let host = match host {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+
+    for (health, record_type, severity) in ZPOOL_ALERTS.iter() {
+        let xs = &current[health];
+
+        // With no previous report, any alert raised before a restart is reconciled here.
+        if previous.and_then(|x| x.get(health)) == Some(xs) {
+            continue;
+        }
+
+        alert::lower(pool, vec![*record_type], host.id).await?;
+
+        if xs.is_empty() {
+            continue;
+        }
+
+        alert::raise(
+            pool,
+            *record_type,
+            format!(
+                "Zpool {} on {}: {}",
+                health,
+                fqdn,
+                xs.iter().cloned().collect::<Vec<_>>().join(", ")
+            ),
+            host.content_type_id.expect("Host has no content_type_id"),
+            None,
+            *severity,
+            host.id,
+        )
+        .await?;
+    }
+
+    raised.insert(fqdn.clone(), current);
+
+    Ok(())
+}
+
 /// Update the state of any `ManagedTarget` records as we learn about state changes.
 /// This should hopefully be removed in short order.
 async fn update_managed_targets(
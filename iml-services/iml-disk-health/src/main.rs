@@ -0,0 +1,307 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use futures::TryStreamExt;
+use iml_influx::{Client, Point, Points, Precision, Value};
+use iml_manager_env::{get_influxdb_addr, get_influxdb_metrics_db, get_pool_limit};
+use iml_postgres::{alert, get_db_pool, sqlx, PgPool};
+use iml_service_queue::service_queue::consume_data;
+use iml_tracing::tracing;
+use iml_wire_types::{
+    db::ManagedHostRecord,
+    disk_health::{DiskHealth, DiskHealthData},
+    AlertRecordType, AlertSeverity,
+};
+use std::collections::HashMap;
+use url::Url;
+
+// Default pool limit if not overridden by POOL_LIMIT
+const DEFAULT_POOL_LIMIT: u32 = 2;
+
+async fn upsert_disks(
+    pool: &PgPool,
+    host_id: i32,
+    disks: &[DiskHealth],
+) -> Result<(), sqlx::Error> {
+    for x in disks {
+        let attributes = serde_json::to_string(&x.attributes)
+            .expect("Could not serialize disk health attributes");
+
+        sqlx::query!(
+            r#"
+            INSERT INTO disk_health
+            (host_id, device_id, path, model, serial_number, passed, predictive_failure, attributes)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8::text::jsonb)
+            ON CONFLICT (host_id, device_id) DO UPDATE
+            SET
+                path = excluded.path,
+                model = excluded.model,
+                serial_number = excluded.serial_number,
+                passed = excluded.passed,
+                predictive_failure = excluded.predictive_failure,
+                attributes = disk_health.attributes || excluded.attributes,
+                updated_at = now()
+        "#,
+            host_id,
+            x.device_id,
+            x.path,
+            x.model,
+            x.serial_number,
+            x.passed,
+            x.predictive_failure,
+            attributes
+        )
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn delete_disks(
+    pool: &PgPool,
+    host_id: i32,
+    device_ids: &[String],
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM disk_health WHERE host_id = $1 AND device_id = ANY($2)",
+        host_id,
+        device_ids
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Removes any drives not part of a full listing
+async fn delete_stale_disks(
+    pool: &PgPool,
+    host_id: i32,
+    device_ids: &[String],
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM disk_health WHERE host_id = $1 AND device_id != ALL($2)",
+        host_id,
+        device_ids
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Returns a description of each drive on the host that expects to fail
+async fn failing_disks(pool: &PgPool, host_id: i32) -> Result<Vec<String>, sqlx::Error> {
+    let xs = sqlx::query!(
+        r#"
+        SELECT path, serial_number FROM disk_health
+        WHERE host_id = $1 AND predictive_failure = true
+        ORDER BY path
+    "#,
+        host_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| describe_disk(&x.path, x.serial_number.as_deref()))
+    .collect();
+
+    Ok(xs)
+}
+
+fn describe_disk(path: &str, serial_number: Option<&str>) -> String {
+    match serial_number {
+        Some(serial_number) => format!("{} ({})", path, serial_number),
+        None => path.to_string(),
+    }
+}
+
+fn to_points(host_id: i32, disks: &[DiskHealth]) -> Vec<Point> {
+    disks
+        .iter()
+        .filter(|x| !x.attributes.is_empty())
+        .map(|x| {
+            x.attributes.iter().fold(
+                Point::new("disk_health")
+                    .add_tag("host_id", Value::Integer(host_id as i64))
+                    .add_tag("device_id", Value::String(x.device_id.clone()))
+                    .add_tag("path", Value::String(x.path.clone())),
+                |p, (k, v)| p.add_field(k, Value::Integer(*v)),
+            )
+        })
+        .collect()
+}
+
+async fn update_disk_stats(
+    influx_client: &Client,
+    host_id: i32,
+    disks: &[DiskHealth],
+) -> Result<(), iml_influx::Error> {
+    let points = to_points(host_id, disks);
+
+    if points.is_empty() {
+        return Ok(());
+    }
+
+    tracing::debug!("Writing disk health stats to influx.");
+
+    influx_client
+        .write_points(
+            Points::create_new(points),
+            Some(Precision::Nanoseconds),
+            None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    iml_tracing::init();
+
+    let pool = get_db_pool(get_pool_limit().unwrap_or(DEFAULT_POOL_LIMIT)).await?;
+
+    let rabbit_pool = iml_rabbit::connect_to_rabbit(1);
+
+    let conn = iml_rabbit::get_conn(rabbit_pool).await?;
+
+    let ch = iml_rabbit::create_channel(&conn).await?;
+
+    let mut s = consume_data::<DiskHealthData>(&ch, "rust_agent_disk_health_rx");
+
+    sqlx::migrate!("../../migrations").run(&pool).await?;
+
+    let influx_url: String = format!("http://{}", get_influxdb_addr());
+    let influx_client = Client::new(
+        Url::parse(&influx_url).expect("Influx URL is invalid."),
+        get_influxdb_metrics_db(),
+    );
+
+    // The failing drives each alert was raised with, so the alert can be re-raised when they change.
+    let mut raised: HashMap<i32, Vec<String>> = HashMap::new();
+
+    while let Some((fqdn, x)) = s.try_next().await? {
+        tracing::debug!("fqdn: {:?} disk health: {:?}", fqdn, x);
+
+        let host: Option<ManagedHostRecord> = sqlx::query_as!(
+            ManagedHostRecord,
+            "select * from chroma_core_managedhost where fqdn = $1 and not_deleted = 't'",
+            fqdn.to_string()
+        )
+        .fetch_optional(&pool)
+        .await?;
+
+        let host = match host {
+            Some(x) => x,
+            None => {
+                tracing::warn!("Host '{}' is unknown, discarding incoming data", fqdn);
+
+                continue;
+            }
+        };
+
+        if x.full {
+            let device_ids: Vec<String> = x.disks.iter().map(|x| x.device_id.clone()).collect();
+
+            delete_stale_disks(&pool, host.id, &device_ids).await?;
+        }
+
+        delete_disks(&pool, host.id, &x.removed).await?;
+
+        upsert_disks(&pool, host.id, &x.disks).await?;
+
+        if let Err(e) = update_disk_stats(&influx_client, host.id, &x.disks).await {
+            tracing::warn!("Could not write disk health stats to influx: {}", e);
+        }
+
+        let failing = failing_disks(&pool, host.id).await?;
+
+        if failing.is_empty() {
+            alert::lower(
+                &pool,
+                vec![AlertRecordType::DiskPredictiveFailureAlert],
+                host.id,
+            )
+            .await?;
+
+            raised.remove(&host.id);
+
+            continue;
+        }
+
+        match raised.get(&host.id) {
+            Some(xs) if xs == &failing => continue,
+            Some(_) => {
+                alert::lower(
+                    &pool,
+                    vec![AlertRecordType::DiskPredictiveFailureAlert],
+                    host.id,
+                )
+                .await?
+            }
+            None => {}
+        };
+
+        alert::raise(
+            &pool,
+            AlertRecordType::DiskPredictiveFailureAlert,
+            format!(
+                "Predictive drive failure on {}: {}",
+                fqdn,
+                failing.join(", ")
+            ),
+            host.content_type_id.expect("Host has no content_type_id"),
+            None,
+            AlertSeverity::ERROR,
+            host.id,
+        )
+        .await?;
+
+        raised.insert(host.id, failing);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_disk() {
+        assert_eq!(
+            describe_disk("/dev/sda", Some("ZC1ABCDE")),
+            "/dev/sda (ZC1ABCDE)"
+        );
+        assert_eq!(describe_disk("/dev/nvme0n1", None), "/dev/nvme0n1");
+    }
+
+    #[test]
+    fn test_to_points_skips_unchanged_disks() {
+        let disks = vec![
+            DiskHealth {
+                device_id: "scsi_35000c500a1b2c3d4".into(),
+                path: "/dev/sda".into(),
+                model: None,
+                serial_number: None,
+                passed: Some(true),
+                predictive_failure: false,
+                attributes: vec![("temperature".to_string(), 31)].into_iter().collect(),
+            },
+            DiskHealth {
+                device_id: "scsi_35000c500a1b2c3d5".into(),
+                path: "/dev/sdb".into(),
+                model: None,
+                serial_number: None,
+                passed: Some(false),
+                predictive_failure: true,
+                attributes: Default::default(),
+            },
+        ];
+
+        assert_eq!(to_points(1, &disks).len(), 1);
+    }
+}
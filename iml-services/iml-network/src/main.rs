@@ -7,7 +7,7 @@ use iml_influx::{Client, Error as InfluxError, Point, Points, Precision, Value};
 use iml_manager_env::{get_influxdb_addr, get_influxdb_metrics_db, get_pool_limit};
 use iml_postgres::{get_db_pool, host_id_by_fqdn, sqlx, PgPool};
 use iml_service_queue::service_queue::consume_data;
-use iml_wire_types::{LNet, LNetState as _, NetworkData, NetworkInterface};
+use iml_wire_types::{InterfaceStats, LNet, LNetState as _, NetworkData, NetworkInterface};
 use url::Url;
 
 // Default pool limit if not overridden by POOL_LIMIT
@@ -18,62 +18,77 @@ async fn update_interfaces(
     host_id: i32,
     interfaces: &[NetworkInterface],
 ) -> Result<(), sqlx::Error> {
-    let xs =
-        interfaces
-            .iter()
-            .cloned()
-            .filter_map(|x| {
-                if let Some(mac_address) = x.mac_address {
-                    Some((
-                        mac_address,
-                        x.interface,
-                        x.inet4_address
-                            .into_iter()
-                            .map(|x| x.to_string())
-                            .collect::<Vec<String>>()
-                            .join(","),
-                        x.inet6_address
-                            .into_iter()
-                            .map(|x| x.to_string())
-                            .collect::<Vec<String>>()
-                            .join(","),
-                        x.interface_type.map(|x| x.to_string()),
-                        x.is_up,
-                    ))
-                } else {
-                    None
-                }
-            })
-            .fold(
-                (vec![], vec![], vec![], vec![], vec![], vec![], vec![]),
-                |mut acc,
-                 (
+    let xs = interfaces
+        .iter()
+        .cloned()
+        .filter_map(|x| {
+            if let Some(mac_address) = x.mac_address {
+                Some((
                     mac_address,
-                    interface,
-                    inet4_addresses,
-                    inet6_addresses,
-                    interface_type,
-                    is_up,
-                )| {
-                    acc.0.push(mac_address);
-                    acc.1.push(interface);
-                    acc.2.push(inet4_addresses);
-                    acc.3.push(inet6_addresses);
-                    acc.4.push(interface_type);
-                    acc.5.push(is_up);
-                    acc.6.push(host_id);
-
-                    acc
-                },
-            );
+                    x.interface,
+                    x.inet4_address
+                        .into_iter()
+                        .map(|x| x.to_string())
+                        .collect::<Vec<String>>()
+                        .join(","),
+                    x.inet6_address
+                        .into_iter()
+                        .map(|x| x.to_string())
+                        .collect::<Vec<String>>()
+                        .join(","),
+                    x.interface_type.map(|x| x.to_string()),
+                    x.is_up,
+                    x.mtu.map(|x| x as i32),
+                    x.speed.map(|x| x as i64),
+                ))
+            } else {
+                None
+            }
+        })
+        .fold(
+            (
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+            ),
+            |mut acc,
+             (
+                mac_address,
+                interface,
+                inet4_addresses,
+                inet6_addresses,
+                interface_type,
+                is_up,
+                mtu,
+                speed,
+            )| {
+                acc.0.push(mac_address);
+                acc.1.push(interface);
+                acc.2.push(inet4_addresses);
+                acc.3.push(inet6_addresses);
+                acc.4.push(interface_type);
+                acc.5.push(is_up);
+                acc.6.push(host_id);
+                acc.7.push(mtu);
+                acc.8.push(speed);
+
+                acc
+            },
+        );
 
     sqlx::query!(
         r#"
             INSERT INTO network_interface 
-            (mac_address, name, inet4_address, inet6_address, lnd_type, state_up, host_id)
-            SELECT mac_address, name, string_to_array(inet4_address, ',')::inet[], string_to_array(inet6_address, ',')::inet[], lnd_type, state_up, host_id
-            FROM UNNEST($1::text[], $2::text[], $3::text[], $4::text[], $5::lnd_network_type[], $6::bool[], $7::int[])
-            AS t(mac_address, name, inet4_address, inet6_address, lnd_type, state_up, host_id)
+            (mac_address, name, inet4_address, inet6_address, lnd_type, state_up, host_id, mtu, speed)
+            SELECT mac_address, name, string_to_array(inet4_address, ',')::inet[], string_to_array(inet6_address, ',')::inet[], lnd_type, state_up, host_id, mtu, speed
+            FROM UNNEST($1::text[], $2::text[], $3::text[], $4::text[], $5::lnd_network_type[], $6::bool[], $7::int[], $8::int[], $9::bigint[])
+            AS t(mac_address, name, inet4_address, inet6_address, lnd_type, state_up, host_id, mtu, speed)
             ON CONFLICT (mac_address)
                 DO
                 UPDATE SET  name          = EXCLUDED.name,
@@ -81,7 +96,9 @@ async fn update_interfaces(
                             inet6_address = EXCLUDED.inet6_address,
                             lnd_type      = EXCLUDED.lnd_type,
                             state_up      = EXCLUDED.state_up,
-                            host_id       = EXCLUDED.host_id"#,
+                            host_id       = EXCLUDED.host_id,
+                            mtu           = EXCLUDED.mtu,
+                            speed         = EXCLUDED.speed"#,
             &xs.0,
             &xs.1,
             &xs.2,
@@ -89,6 +106,8 @@ async fn update_interfaces(
             &xs.4 as &[Option<String>],
             &xs.5,
             &xs.6,
+            &xs.7 as &[Option<i32>],
+            &xs.8 as &[Option<i64>],
     )
     .execute(pool)
     .await?;
@@ -96,27 +115,36 @@ async fn update_interfaces(
     Ok(())
 }
 
+fn to_points(host_id: i32, interfaces: &[NetworkInterface]) -> Vec<Point> {
+    interfaces
+        .iter()
+        .filter_map(|x| x.stats.as_ref().map(|stats| (&x.interface, stats)))
+        .map(|(interface, InterfaceStats { rx, tx })| {
+            Point::new("net")
+                .add_tag("interface", Value::String(interface.to_string()))
+                .add_tag("host_id", Value::Integer(host_id as i64))
+                .add_field("rx_bytes", Value::Integer(rx.bytes as i64))
+                .add_field("tx_bytes", Value::Integer(tx.bytes as i64))
+                .add_field("rx_packets", Value::Integer(rx.packets as i64))
+                .add_field("tx_packets", Value::Integer(tx.packets as i64))
+                .add_field("rx_errors", Value::Integer(rx.errs as i64))
+                .add_field("tx_errors", Value::Integer(tx.errs as i64))
+                .add_field("rx_drops", Value::Integer(rx.drop as i64))
+                .add_field("tx_drops", Value::Integer(tx.drop as i64))
+        })
+        .collect()
+}
+
 async fn update_network_stats(
     influx_client: &Client,
     host_id: i32,
     interfaces: &[NetworkInterface],
 ) -> Result<(), InfluxError> {
-    let points = interfaces
-        .iter()
-        .cloned()
-        .filter_map(|x: NetworkInterface| {
-            x.stats
-                .clone()
-                .map(|stat| (x.interface, stat.rx.bytes, stat.tx.bytes))
-        })
-        .map(|(interface, rx_bytes, tx_bytes)| {
-            Point::new("net")
-                .add_tag("interface", Value::String(interface))
-                .add_tag("host_id", Value::Integer(host_id as i64))
-                .add_field("rx_bytes", Value::Integer(rx_bytes as i64))
-                .add_field("tx_bytes", Value::Integer(tx_bytes as i64))
-        })
-        .collect::<Vec<Point>>();
+    let points = to_points(host_id, interfaces);
+
+    if points.is_empty() {
+        return Ok(());
+    }
 
     let points = Points::create_new(points);
 
@@ -282,7 +310,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use iml_wire_types::{Net, Nid};
+    use iml_wire_types::{Net, Nid, RxStats, TxStats};
     use std::collections::BTreeMap;
 
     #[test]
@@ -345,6 +373,48 @@ mod tests {
         insta::assert_debug_snapshot!(parsed_data)
     }
 
+    #[test]
+    fn test_to_points() {
+        let interfaces = vec![
+            NetworkInterface {
+                interface: "ib0".into(),
+                stats: Some(InterfaceStats {
+                    rx: RxStats {
+                        bytes: 449_364,
+                        packets: 3919,
+                        errs: 0,
+                        drop: 2,
+                        fifo: 0,
+                        frame: 0,
+                        compressed: 0,
+                        multicast: 0,
+                    },
+                    tx: TxStats {
+                        bytes: 20174,
+                        packets: 173,
+                        errs: 0,
+                        drop: 0,
+                        fifo: 0,
+                        colls: 0,
+                        carrier: 0,
+                        compressed: 0,
+                    },
+                }),
+                ..Default::default()
+            },
+            NetworkInterface {
+                interface: "eth0".into(),
+                stats: None,
+                ..Default::default()
+            },
+        ];
+
+        let points = to_points(2, &interfaces);
+
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].fields.len(), 8);
+    }
+
     #[test]
     fn test_parse_empty_lnetctl_data() {
         let data = LNet { net: vec![] };
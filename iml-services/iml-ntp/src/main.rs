@@ -3,14 +3,55 @@
 // license that can be found in the LICENSE file.
 
 use futures::TryStreamExt;
-use iml_manager_env::get_pool_limit;
+use iml_influx::{Client, Point, Points, Precision, Value};
+use iml_manager_env::{
+    get_influxdb_addr, get_influxdb_metrics_db, get_ntp_drift_threshold, get_pool_limit,
+};
 use iml_postgres::{alert, get_db_pool, sqlx};
 use iml_service_queue::service_queue::consume_data;
-use iml_wire_types::{db::ManagedHostRecord, time::State, AlertRecordType, AlertSeverity};
+use iml_wire_types::{
+    db::ManagedHostRecord,
+    time::{Report, State, Status},
+    AlertRecordType, AlertSeverity, Fqdn,
+};
+use std::collections::HashMap;
+use url::Url;
 
 // Default pool limit if not overridden by POOL_LIMIT
 const DEFAULT_POOL_LIMIT: u32 = 2;
 
+fn to_point(host_id: i32, offset: f64) -> Point {
+    Point::new("ntp")
+        .add_tag("host_id", Value::Integer(host_id as i64))
+        .add_field("offset", Value::Float(offset))
+}
+
+/// Returns the `TimeOutOfSyncAlert` message if the host is out of sync,
+/// either as reported by its time sync client or because its offset exceeds `threshold`.
+fn out_of_sync_message(
+    fqdn: &Fqdn,
+    state: &State,
+    offset: Option<f64>,
+    threshold: f64,
+) -> Option<String> {
+    match (state, offset) {
+        (State::Unsynced(_), Some(offset)) => Some(format!(
+            "Time is out of sync on server {}. Offset is {}s",
+            fqdn, offset
+        )),
+        (State::Unsynced(Some(offset)), None) => Some(format!(
+            "Time is out of sync on server {}. Offset is {}",
+            fqdn, offset
+        )),
+        (State::Unsynced(None), None) => Some(format!("Time is out of sync on server {}", fqdn)),
+        (State::Synced, Some(offset)) if offset.abs() > threshold => Some(format!(
+            "Time is out of sync on server {}. Offset is {}s, exceeding the threshold of {}s",
+            fqdn, offset, threshold
+        )),
+        _ => None,
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     iml_tracing::init();
@@ -23,10 +64,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let ch = iml_rabbit::create_channel(&conn).await?;
 
-    let mut s = consume_data::<State>(&ch, "rust_agent_ntp_rx");
+    let influx_url: String = format!("http://{}", get_influxdb_addr());
+    let influx_client = Client::new(
+        Url::parse(&influx_url).expect("Influx URL is invalid."),
+        get_influxdb_metrics_db(),
+    );
+
+    let threshold = get_ntp_drift_threshold();
+
+    let mut s = consume_data::<Report>(&ch, "rust_agent_ntp_rx");
 
-    while let Some((fqdn, state)) = s.try_next().await? {
-        tracing::debug!("fqdn: {:?} state: {:?}", fqdn, state);
+    // The last `TimeOutOfSyncAlert` message written for each host.
+    let mut messages: HashMap<i32, String> = HashMap::new();
+
+    while let Some((fqdn, report)) = s.try_next().await? {
+        let Status { state, offset } = report.into();
+
+        tracing::debug!("fqdn: {:?} state: {:?} offset: {:?}", fqdn, state, offset);
 
         let host: Option<ManagedHostRecord> = sqlx::query_as!(
             ManagedHostRecord,
@@ -45,19 +99,75 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         };
 
+        if let Some(offset) = offset {
+            let r = influx_client
+                .write_points(
+                    Points::create_new(vec![to_point(host.id, offset)]),
+                    Some(Precision::Nanoseconds),
+                    None,
+                )
+                .await;
+
+            if let Err(e) = r {
+                tracing::warn!("Unable to write ntp offset of {} to influx: {:?}", fqdn, e);
+            }
+        }
+
+        let msg = match state {
+            State::Synced | State::Unsynced(_) => {
+                out_of_sync_message(&fqdn, &state, offset, threshold).filter(|_| host.is_setup())
+            }
+            _ => None,
+        };
+
+        if msg.is_none() {
+            messages.remove(&host.id);
+        }
+
         match state {
-            State::Synced => {
+            State::Synced | State::Unsynced(_) => {
                 alert::lower(
                     &pool,
                     vec![
-                        AlertRecordType::TimeOutOfSyncAlert,
-                        AlertRecordType::NoTimeSyncAlert,
                         AlertRecordType::MultipleTimeSyncAlert,
+                        AlertRecordType::NoTimeSyncAlert,
                         AlertRecordType::UnknownTimeSyncAlert,
                     ],
                     host.id,
                 )
                 .await?;
+
+                match msg {
+                    Some(msg) if messages.get(&host.id) == Some(&msg) => {}
+                    Some(msg) => {
+                        alert::raise(
+                            &pool,
+                            AlertRecordType::TimeOutOfSyncAlert,
+                            msg.clone(),
+                            host.content_type_id.expect("Host has no content_type_id"),
+                            None,
+                            AlertSeverity::ERROR,
+                            host.id,
+                        )
+                        .await?;
+
+                        // The offset changes between reports,
+                        // keep the message of an already active alert current.
+                        alert::update_message(
+                            &pool,
+                            AlertRecordType::TimeOutOfSyncAlert,
+                            msg.clone(),
+                            host.id,
+                        )
+                        .await?;
+
+                        messages.insert(host.id, msg);
+                    }
+                    None => {
+                        alert::lower(&pool, vec![AlertRecordType::TimeOutOfSyncAlert], host.id)
+                            .await?;
+                    }
+                }
             }
             State::None => {
                 alert::lower(
@@ -109,31 +219,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     .await?;
                 }
             }
-            State::Unsynced(_) => {
-                alert::lower(
-                    &pool,
-                    vec![
-                        AlertRecordType::MultipleTimeSyncAlert,
-                        AlertRecordType::NoTimeSyncAlert,
-                        AlertRecordType::UnknownTimeSyncAlert,
-                    ],
-                    host.id,
-                )
-                .await?;
-
-                if host.is_setup() {
-                    alert::raise(
-                        &pool,
-                        AlertRecordType::TimeOutOfSyncAlert,
-                        format!("Time is out of sync on server {}", fqdn),
-                        host.content_type_id.expect("Host has no content_type_id"),
-                        None,
-                        AlertSeverity::ERROR,
-                        host.id,
-                    )
-                    .await?;
-                }
-            }
             State::Unknown => {
                 alert::lower(
                     &pool,
@@ -164,3 +249,30 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_out_of_sync_message() {
+        let fqdn = Fqdn("oss1.local".into());
+
+        assert_eq!(
+            out_of_sync_message(&fqdn, &State::Synced, Some(0.2), 0.5),
+            None
+        );
+        assert_eq!(out_of_sync_message(&fqdn, &State::Synced, None, 0.5), None);
+        assert_eq!(
+            out_of_sync_message(&fqdn, &State::Synced, Some(-0.75), 0.5),
+            Some(
+                "Time is out of sync on server oss1.local. Offset is -0.75s, exceeding the threshold of 0.5s"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            out_of_sync_message(&fqdn, &State::Unsynced(None), Some(1.5), 0.5),
+            Some("Time is out of sync on server oss1.local. Offset is 1.5s".to_string())
+        );
+    }
+}
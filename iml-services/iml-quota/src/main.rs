@@ -0,0 +1,575 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use chrono::{offset::Utc, DateTime, NaiveDateTime};
+use futures::TryStreamExt;
+use iml_manager_env::get_pool_limit;
+use iml_postgres::{alert, get_db_pool, host_id_by_fqdn, sqlx, PgPool};
+use iml_service_queue::service_queue::consume_data;
+use iml_tracing::tracing;
+use iml_wire_types::{
+    quota::{QuotaAcct, QuotaData, QuotaKind, QuotaLimit, QuotaResource},
+    AlertRecordType, AlertSeverity,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+// Default pool limit if not overridden by POOL_LIMIT
+const DEFAULT_POOL_LIMIT: u32 = 2;
+
+/// The block and inode limits of a quota id, merged from the quota master.
+#[derive(Debug, Default, PartialEq)]
+struct LimitRow {
+    kbytes_soft: i64,
+    kbytes_hard: i64,
+    inodes_soft: i64,
+    inodes_hard: i64,
+    block_grace_expires: Option<DateTime<Utc>>,
+    inode_grace_expires: Option<DateTime<Utc>>,
+}
+
+type LimitKey = (String, String, QuotaKind, u32);
+
+/// Returns the filesystem name of a target, i.e. `fs` for `fs-OST0000`
+fn fs_name(target: &str) -> Option<&str> {
+    target.rsplitn(2, '-').nth(1)
+}
+
+fn grace_expires(x: &QuotaLimit) -> Option<DateTime<Utc>> {
+    // For id 0 `time` is the grace period itself
+    if x.id == 0 || x.time == 0 {
+        return None;
+    }
+
+    Some(DateTime::from_utc(
+        NaiveDateTime::from_timestamp(x.time as i64, 0),
+        Utc,
+    ))
+}
+
+/// Merges the block and inode limits of each quota id into a single row.
+fn merge_limits(xs: &[QuotaLimit]) -> BTreeMap<LimitKey, LimitRow> {
+    xs.iter().fold(BTreeMap::new(), |mut acc, x| {
+        let row = acc
+            .entry((
+                x.fs_name.clone(),
+                x.pool.clone().unwrap_or_default(),
+                x.kind,
+                x.id,
+            ))
+            .or_insert_with(LimitRow::default);
+
+        match x.resource {
+            QuotaResource::Block => {
+                row.kbytes_soft = x.soft as i64;
+                row.kbytes_hard = x.hard as i64;
+                row.block_grace_expires = grace_expires(x);
+            }
+            QuotaResource::Inode => {
+                row.inodes_soft = x.soft as i64;
+                row.inodes_hard = x.hard as i64;
+                row.inode_grace_expires = grace_expires(x);
+            }
+        }
+
+        acc
+    })
+}
+
+/// Replaces the limits reported by a host.
+///
+/// The quota master of a filesystem may fail over,
+/// so existing limits of a reported filesystem move to the reporting host.
+///
+/// Returns the filesystems that had limits removed.
+async fn update_limits(
+    pool: &PgPool,
+    host_id: i32,
+    xs: &[QuotaLimit],
+) -> Result<Vec<String>, sqlx::Error> {
+    let x = merge_limits(xs).into_iter().fold(
+        (
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+        ),
+        |mut acc, ((fs_name, pool, kind, id), row)| {
+            acc.0.push(fs_name);
+            acc.1.push(pool);
+            acc.2.push(kind.to_string());
+            acc.3.push(id as i64);
+            acc.4.push(row.kbytes_soft);
+            acc.5.push(row.kbytes_hard);
+            acc.6.push(row.inodes_soft);
+            acc.7.push(row.inodes_hard);
+            acc.8.push(row.block_grace_expires);
+            acc.9.push(row.inode_grace_expires);
+
+            acc
+        },
+    );
+
+    let mut transaction = pool.begin().await?;
+
+    let removed = sqlx::query!(
+        r#"
+        DELETE FROM quota_limit l
+        WHERE (l.host_id = $1 OR l.fs_name = ANY($2))
+        AND NOT EXISTS (
+            SELECT 1 FROM UNNEST($2::text[], $3::text[], $4::quota_kind[], $5::bigint[])
+            AS x(fs_name, pool, kind, quota_id)
+            WHERE x.fs_name = l.fs_name AND x.pool = l.pool AND x.kind = l.kind AND x.quota_id = l.quota_id
+        )
+        RETURNING l.fs_name
+    "#,
+        host_id,
+        &x.0,
+        &x.1,
+        &x.2 as &[String],
+        &x.3,
+    )
+    .fetch_all(&mut transaction)
+    .await?
+    .into_iter()
+    .map(|x| x.fs_name)
+    .collect();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO quota_limit
+        (host_id, fs_name, pool, kind, quota_id, kbytes_soft, kbytes_hard, inodes_soft, inodes_hard, block_grace_expires, inode_grace_expires)
+        SELECT $1, * FROM
+        UNNEST (
+            $2::text[],
+            $3::text[],
+            $4::quota_kind[],
+            $5::bigint[],
+            $6::bigint[],
+            $7::bigint[],
+            $8::bigint[],
+            $9::bigint[],
+            $10::timestamptz[],
+            $11::timestamptz[]
+        )
+        ON CONFLICT (fs_name, pool, kind, quota_id) DO UPDATE
+        SET
+            host_id = EXCLUDED.host_id,
+            kbytes_soft = EXCLUDED.kbytes_soft,
+            kbytes_hard = EXCLUDED.kbytes_hard,
+            inodes_soft = EXCLUDED.inodes_soft,
+            inodes_hard = EXCLUDED.inodes_hard,
+            block_grace_expires = EXCLUDED.block_grace_expires,
+            inode_grace_expires = EXCLUDED.inode_grace_expires
+        WHERE (
+            quota_limit.host_id,
+            quota_limit.kbytes_soft,
+            quota_limit.kbytes_hard,
+            quota_limit.inodes_soft,
+            quota_limit.inodes_hard,
+            quota_limit.block_grace_expires,
+            quota_limit.inode_grace_expires
+        ) IS DISTINCT FROM (
+            EXCLUDED.host_id,
+            EXCLUDED.kbytes_soft,
+            EXCLUDED.kbytes_hard,
+            EXCLUDED.inodes_soft,
+            EXCLUDED.inodes_hard,
+            EXCLUDED.block_grace_expires,
+            EXCLUDED.inode_grace_expires
+        )
+    "#,
+        host_id,
+        &x.0,
+        &x.1,
+        &x.2 as &[String],
+        &x.3,
+        &x.4,
+        &x.5,
+        &x.6,
+        &x.7,
+        &x.8 as &[Option<DateTime<Utc>>],
+        &x.9 as &[Option<DateTime<Utc>>],
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(removed)
+}
+
+/// Replaces the accounting reported by a host.
+///
+/// A target that failed over is reported by its new host,
+/// so existing accounting of the target moves to that host.
+///
+/// Returns the filesystems that had accounting removed.
+async fn update_usage(
+    pool: &PgPool,
+    host_id: i32,
+    xs: &[QuotaAcct],
+) -> Result<Vec<String>, sqlx::Error> {
+    let x = xs
+        .iter()
+        .filter_map(|x| fs_name(&x.target).map(|fs_name| (fs_name, x)))
+        .fold(
+            (vec![], vec![], vec![], vec![], vec![], vec![]),
+            |mut acc, (fs_name, x)| {
+                acc.0.push(fs_name.to_string());
+                acc.1.push(x.target.clone());
+                acc.2.push(x.kind.to_string());
+                acc.3.push(x.id as i64);
+                acc.4.push(x.kbytes as i64);
+                acc.5.push(x.inodes as i64);
+
+                acc
+            },
+        );
+
+    let mut transaction = pool.begin().await?;
+
+    let removed = sqlx::query!(
+        r#"
+        DELETE FROM quota_usage u
+        WHERE (u.host_id = $1 OR u.target = ANY($2))
+        AND NOT EXISTS (
+            SELECT 1 FROM UNNEST($2::text[], $3::quota_kind[], $4::bigint[])
+            AS x(target, kind, quota_id)
+            WHERE x.target = u.target AND x.kind = u.kind AND x.quota_id = u.quota_id
+        )
+        RETURNING u.fs_name
+    "#,
+        host_id,
+        &x.1,
+        &x.2 as &[String],
+        &x.3,
+    )
+    .fetch_all(&mut transaction)
+    .await?
+    .into_iter()
+    .map(|x| x.fs_name)
+    .collect();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO quota_usage
+        (host_id, fs_name, target, kind, quota_id, kbytes, inodes)
+        SELECT $1, * FROM
+        UNNEST (
+            $2::text[],
+            $3::text[],
+            $4::quota_kind[],
+            $5::bigint[],
+            $6::bigint[],
+            $7::bigint[]
+        )
+        ON CONFLICT (target, kind, quota_id) DO UPDATE
+        SET
+            host_id = EXCLUDED.host_id,
+            kbytes = EXCLUDED.kbytes,
+            inodes = EXCLUDED.inodes
+        WHERE (quota_usage.host_id, quota_usage.kbytes, quota_usage.inodes)
+            IS DISTINCT FROM (EXCLUDED.host_id, EXCLUDED.kbytes, EXCLUDED.inodes)
+    "#,
+        host_id,
+        &x.0,
+        &x.1,
+        &x.2 as &[String],
+        &x.3,
+        &x.4,
+        &x.5,
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(removed)
+}
+
+fn describe_id(kind: QuotaKind, quota_id: i64, pool: &str) -> String {
+    if pool.is_empty() {
+        format!("{} {}", kind, quota_id)
+    } else {
+        format!("{} {} (pool {})", kind, quota_id, pool)
+    }
+}
+
+/// Returns a description of each quota id over its soft limit
+/// and of each quota id whose grace period has run out
+async fn offenders(
+    pool: &PgPool,
+    fs_name: &str,
+) -> Result<(Vec<String>, Vec<String>), sqlx::Error> {
+    let xs = sqlx::query!(
+        r#"
+        SELECT
+            pool AS "pool!",
+            kind AS "kind!: QuotaKind",
+            quota_id AS "quota_id!",
+            (
+                (kbytes_soft > 0 AND kbytes_used > kbytes_soft)
+                OR (inodes_soft > 0 AND inodes_used > inodes_soft)
+            ) AS "over_soft!",
+            COALESCE(
+                block_grace_expires < now() OR inode_grace_expires < now(),
+                false
+            ) AS "grace_expired!"
+        FROM quota
+        WHERE fs_name = $1 AND quota_id != 0
+        ORDER BY kind, quota_id, pool
+    "#,
+        fs_name
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let over_soft = xs
+        .iter()
+        .filter(|x| x.over_soft)
+        .map(|x| describe_id(x.kind, x.quota_id, &x.pool))
+        .collect();
+
+    let grace_expired = xs
+        .iter()
+        .filter(|x| x.grace_expired)
+        .map(|x| describe_id(x.kind, x.quota_id, &x.pool))
+        .collect();
+
+    Ok((over_soft, grace_expired))
+}
+
+/// Raises `record_type` on the filesystem listing `xs`, or lowers it if `xs` is empty.
+/// The alert is re-raised whenever its message changes.
+///
+/// `raised` holds the message of each active alert.
+async fn update_alert(
+    pool: &PgPool,
+    raised: &mut HashMap<(i32, AlertRecordType), String>,
+    fs: (i32, i32),
+    record_type: AlertRecordType,
+    severity: AlertSeverity,
+    msg: String,
+    xs: Vec<String>,
+) -> Result<(), sqlx::Error> {
+    let (fs_id, content_type_id) = fs;
+
+    let key = (fs_id, record_type);
+
+    if xs.is_empty() {
+        if raised.remove(&key).is_some() {
+            alert::lower(pool, vec![record_type], fs_id).await?;
+        }
+
+        return Ok(());
+    }
+
+    let msg = format!("{}: {}", msg, xs.join(", "));
+
+    match raised.get(&key) {
+        Some(x) if x == &msg => return Ok(()),
+        Some(_) => alert::lower(pool, vec![record_type], fs_id).await?,
+        None => {}
+    };
+
+    alert::raise(
+        pool,
+        record_type,
+        msg.clone(),
+        content_type_id,
+        None,
+        severity,
+        fs_id,
+    )
+    .await?;
+
+    raised.insert(key, msg);
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    iml_tracing::init();
+
+    let pool = get_db_pool(get_pool_limit().unwrap_or(DEFAULT_POOL_LIMIT)).await?;
+
+    let rabbit_pool = iml_rabbit::connect_to_rabbit(1);
+
+    let conn = iml_rabbit::get_conn(rabbit_pool).await?;
+
+    let ch = iml_rabbit::create_channel(&conn).await?;
+
+    let mut s = consume_data::<QuotaData>(&ch, "rust_agent_quota_rx");
+
+    sqlx::migrate!("../../migrations").run(&pool).await?;
+
+    let mut raised = HashMap::new();
+
+    for record_type in [
+        AlertRecordType::QuotaSoftLimitAlert,
+        AlertRecordType::QuotaGraceExpiredAlert,
+    ]
+    .iter()
+    {
+        for (fs_id, msg) in alert::active_messages(&pool, *record_type).await? {
+            raised.insert((fs_id, *record_type), msg);
+        }
+    }
+
+    while let Some((fqdn, x)) = s.try_next().await? {
+        tracing::debug!(
+            "fqdn: {:?} limits: {} usage: {}",
+            fqdn,
+            x.limits.len(),
+            x.usage.len()
+        );
+
+        let host_id = match host_id_by_fqdn(&fqdn, &pool).await? {
+            Some(x) => x,
+            None => {
+                tracing::warn!("Host '{}' is unknown", fqdn);
+
+                continue;
+            }
+        };
+
+        let mut removed = update_limits(&pool, host_id, &x.limits).await?;
+
+        removed.extend(update_usage(&pool, host_id, &x.usage).await?);
+
+        let fs_names: BTreeSet<&str> = x
+            .limits
+            .iter()
+            .map(|x| x.fs_name.as_str())
+            .chain(x.usage.iter().filter_map(|x| fs_name(&x.target)))
+            .chain(removed.iter().map(|x| x.as_str()))
+            .collect();
+
+        for fs_name in fs_names {
+            let fs = sqlx::query!(
+                r#"
+                SELECT id, content_type_id FROM chroma_core_managedfilesystem
+                WHERE name = $1 AND not_deleted = 't'
+            "#,
+                fs_name
+            )
+            .fetch_optional(&pool)
+            .await?;
+
+            let fs = match fs {
+                Some(x) => (
+                    x.id,
+                    x.content_type_id
+                        .expect("Filesystem has no content_type_id"),
+                ),
+                None => {
+                    tracing::debug!("Filesystem '{}' is unknown, skipping alerts", fs_name);
+
+                    continue;
+                }
+            };
+
+            let (over_soft, grace_expired) = offenders(&pool, fs_name).await?;
+
+            update_alert(
+                &pool,
+                &mut raised,
+                fs,
+                AlertRecordType::QuotaSoftLimitAlert,
+                AlertSeverity::WARNING,
+                format!("Quota soft limit exceeded on filesystem {}", fs_name),
+                over_soft,
+            )
+            .await?;
+
+            update_alert(
+                &pool,
+                &mut raised,
+                fs,
+                AlertRecordType::QuotaGraceExpiredAlert,
+                AlertSeverity::ERROR,
+                format!("Quota grace period expired on filesystem {}", fs_name),
+                grace_expired,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fs_name() {
+        assert_eq!(fs_name("fs-OST0000"), Some("fs"));
+        assert_eq!(fs_name("my-fs-MDT0001"), Some("my-fs"));
+        assert_eq!(fs_name("MGS"), None);
+    }
+
+    #[test]
+    fn test_merge_limits() {
+        let block = QuotaLimit {
+            fs_name: "fs".into(),
+            pool: None,
+            kind: QuotaKind::User,
+            resource: QuotaResource::Block,
+            id: 1000,
+            hard: 20480,
+            soft: 10240,
+            granted: 12288,
+            time: 1_609_459_200,
+        };
+
+        let inode = QuotaLimit {
+            resource: QuotaResource::Inode,
+            hard: 200,
+            soft: 100,
+            time: 0,
+            ..block.clone()
+        };
+
+        let default = QuotaLimit {
+            id: 0,
+            time: 604_800,
+            ..block.clone()
+        };
+
+        let xs = merge_limits(&[block, inode, default]);
+
+        assert_eq!(xs.len(), 2);
+
+        let row = &xs[&("fs".to_string(), "".to_string(), QuotaKind::User, 1000)];
+
+        assert_eq!(row.kbytes_soft, 10240);
+        assert_eq!(row.inodes_hard, 200);
+        assert_eq!(
+            row.block_grace_expires.map(|x| x.timestamp()),
+            Some(1_609_459_200)
+        );
+        assert_eq!(row.inode_grace_expires, None);
+
+        let row = &xs[&("fs".to_string(), "".to_string(), QuotaKind::User, 0)];
+
+        assert_eq!(row.block_grace_expires, None);
+    }
+
+    #[test]
+    fn test_describe_id() {
+        assert_eq!(describe_id(QuotaKind::User, 1000, ""), "user 1000");
+        assert_eq!(
+            describe_id(QuotaKind::Project, 7, "flash"),
+            "project 7 (pool flash)"
+        );
+    }
+}
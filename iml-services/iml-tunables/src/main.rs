@@ -0,0 +1,294 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+use futures::TryStreamExt;
+use iml_manager_env::get_pool_limit;
+use iml_postgres::{alert, get_db_pool, sqlx, PgPool};
+use iml_service_queue::service_queue::consume_data;
+use iml_tracing::tracing;
+use iml_wire_types::{
+    db::ManagedHostRecord,
+    tunable::{param_matches, TunableValue},
+    AlertRecordType, AlertSeverity,
+};
+use std::collections::HashMap;
+
+// Default pool limit if not overridden by POOL_LIMIT
+const DEFAULT_POOL_LIMIT: u32 = 2;
+
+/// A tunable that applies to a host.
+#[derive(Debug)]
+struct Desired {
+    tunable_id: i32,
+    param: String,
+    value: String,
+}
+
+/// A read back value that differs from the desired value of a tunable.
+#[derive(Debug, PartialEq)]
+struct Drift<'a> {
+    tunable_id: i32,
+    name: &'a str,
+    actual: &'a str,
+    desired: &'a str,
+}
+
+/// Returns each value in `xs` that matches a desired tunable but has a different value.
+fn find_drift<'a>(desired: &'a [Desired], xs: &'a [TunableValue]) -> Vec<Drift<'a>> {
+    desired
+        .iter()
+        .flat_map(|d| {
+            xs.iter()
+                .filter(move |x| param_matches(&d.param, &x.name) && x.value != d.value)
+                .map(move |x| Drift {
+                    tunable_id: d.tunable_id,
+                    name: &x.name,
+                    actual: &x.value,
+                    desired: &d.value,
+                })
+        })
+        .collect()
+}
+
+/// Replaces the read back values of a host.
+/// Only rows that were removed or changed are written.
+async fn update_values(
+    pool: &PgPool,
+    host_id: i32,
+    xs: &[TunableValue],
+) -> Result<(), sqlx::Error> {
+    let (names, values): (Vec<String>, Vec<String>) =
+        xs.iter().map(|x| (x.name.clone(), x.value.clone())).unzip();
+
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        "DELETE FROM tunable_value WHERE host_id = $1 AND NOT name = ANY($2)",
+        host_id,
+        &names
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO tunable_value (host_id, name, value)
+        SELECT $1, name, value FROM UNNEST($2::text[], $3::text[]) AS x(name, value)
+        ON CONFLICT (host_id, name) DO UPDATE SET value = EXCLUDED.value
+        WHERE tunable_value.value != EXCLUDED.value
+    "#,
+        host_id,
+        &names,
+        &values
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+/// Replaces the drift of a host.
+async fn update_drift(pool: &PgPool, host_id: i32, xs: &[Drift<'_>]) -> Result<(), sqlx::Error> {
+    let (ids, (names, actuals)): (Vec<i32>, (Vec<String>, Vec<String>)) = xs
+        .iter()
+        .map(|x| (x.tunable_id, (x.name.to_string(), x.actual.to_string())))
+        .unzip();
+
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM tunable_drift d
+        WHERE d.host_id = $1
+        AND NOT EXISTS (
+            SELECT 1 FROM UNNEST($2::int[], $3::text[]) AS x(tunable_id, name)
+            WHERE x.tunable_id = d.tunable_id AND x.name = d.name
+        )
+    "#,
+        host_id,
+        &ids,
+        &names
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO tunable_drift (tunable_id, host_id, name, actual)
+        SELECT tunable_id, $1, name, actual
+        FROM UNNEST($2::int[], $3::text[], $4::text[]) AS x(tunable_id, name, actual)
+        ON CONFLICT (tunable_id, host_id, name) DO UPDATE SET actual = EXCLUDED.actual
+    "#,
+        host_id,
+        &ids,
+        &names,
+        &actuals
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+/// Raises `TunableDriftAlert` on the host listing `xs`, or lowers it if `xs` is empty.
+/// The alert is re-raised whenever `xs` changes.
+async fn update_alert(
+    pool: &PgPool,
+    raised: &mut HashMap<i32, Vec<String>>,
+    host: &ManagedHostRecord,
+    xs: Vec<String>,
+) -> Result<(), sqlx::Error> {
+    let record_type = AlertRecordType::TunableDriftAlert;
+
+    if xs.is_empty() {
+        alert::lower(pool, vec![record_type], host.id).await?;
+
+        raised.remove(&host.id);
+
+        return Ok(());
+    }
+
+    match raised.get(&host.id) {
+        Some(ys) if ys == &xs => return Ok(()),
+        Some(_) => alert::lower(pool, vec![record_type], host.id).await?,
+        None => {}
+    };
+
+    alert::raise(
+        pool,
+        record_type,
+        format!(
+            "Tunables differ from their desired values on server {}: {}",
+            host.fqdn,
+            xs.join(", ")
+        ),
+        host.content_type_id.expect("Host has no content_type_id"),
+        None,
+        AlertSeverity::WARNING,
+        host.id,
+    )
+    .await?;
+
+    raised.insert(host.id, xs);
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    iml_tracing::init();
+
+    let pool = get_db_pool(get_pool_limit().unwrap_or(DEFAULT_POOL_LIMIT)).await?;
+
+    let rabbit_pool = iml_rabbit::connect_to_rabbit(1);
+
+    let conn = iml_rabbit::get_conn(rabbit_pool).await?;
+
+    let ch = iml_rabbit::create_channel(&conn).await?;
+
+    let mut s = consume_data::<Vec<TunableValue>>(&ch, "rust_agent_tunables_rx");
+
+    sqlx::migrate!("../../migrations").run(&pool).await?;
+
+    let mut raised = HashMap::new();
+
+    while let Some((fqdn, xs)) = s.try_next().await? {
+        tracing::debug!("fqdn: {:?} values: {}", fqdn, xs.len());
+
+        let host: Option<ManagedHostRecord> = sqlx::query_as!(
+            ManagedHostRecord,
+            "select * from chroma_core_managedhost where fqdn = $1 and not_deleted = 't'",
+            fqdn.to_string()
+        )
+        .fetch_optional(&pool)
+        .await?;
+
+        let host = match host {
+            Some(x) => x,
+            None => {
+                tracing::warn!("Host '{}' is unknown", fqdn);
+
+                continue;
+            }
+        };
+
+        update_values(&pool, host.id, &xs).await?;
+
+        let desired = sqlx::query_as!(
+            Desired,
+            r#"
+            SELECT tunable_id AS "tunable_id!", param AS "param!", value AS "value!"
+            FROM host_tunable
+            WHERE host_id = $1 AND method != 'conf_param'
+            ORDER BY param
+        "#,
+            host.id
+        )
+        .fetch_all(&pool)
+        .await?;
+
+        let drift = find_drift(&desired, &xs);
+
+        update_drift(&pool, host.id, &drift).await?;
+
+        let msgs = drift
+            .iter()
+            .map(|x| format!("{} ({}, expected {})", x.name, x.actual, x.desired))
+            .collect();
+
+        update_alert(&pool, &mut raised, &host, msgs).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_drift() {
+        let desired = vec![
+            Desired {
+                tunable_id: 1,
+                param: "osc.*.max_rpcs_in_flight".into(),
+                value: "16".into(),
+            },
+            Desired {
+                tunable_id: 2,
+                param: "osd-ldiskfs.fs-MDT0000.lma_self_repair".into(),
+                value: "1".into(),
+            },
+        ];
+
+        let xs = vec![
+            TunableValue {
+                name: "osc.fs-OST0000-osc-MDT0000.max_rpcs_in_flight".into(),
+                value: "16".into(),
+            },
+            TunableValue {
+                name: "osc.fs-OST0001-osc-MDT0000.max_rpcs_in_flight".into(),
+                value: "8".into(),
+            },
+            TunableValue {
+                name: "osd-ldiskfs.fs-MDT0000.lma_self_repair".into(),
+                value: "1".into(),
+            },
+        ];
+
+        assert_eq!(
+            find_drift(&desired, &xs),
+            vec![Drift {
+                tunable_id: 1,
+                name: "osc.fs-OST0001-osc-MDT0000.max_rpcs_in_flight",
+                actual: "8",
+                desired: "16",
+            }]
+        );
+    }
+}
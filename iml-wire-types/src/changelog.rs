@@ -0,0 +1,58 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Data structures for Lustre changelog users.
+
+use chrono::{offset::Utc, DateTime};
+
+/// A changelog user of an MDT, as listed by `mdd.*.changelog_users`
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct ChangelogUserIndex {
+    /// The user id, i.e. `cl1`
+    pub id: String,
+    /// The last record the user cleared
+    pub index: u64,
+    /// Seconds since the user last cleared records. Only reported by Lustre 2.12 and later
+    pub idle_secs: Option<u64>,
+}
+
+/// The changelog users of an MDT
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct ChangelogStatus {
+    pub target: String,
+    /// The index of the latest changelog record
+    pub current_index: u64,
+    pub users: Vec<ChangelogUserIndex>,
+}
+
+/// Register a changelog user on an MDT
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct ChangelogRegister {
+    pub target: String,
+}
+
+/// Deregister a changelog user from an MDT.
+/// Records not yet cleared by the user are purged.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct ChangelogDeregister {
+    pub target: String,
+    pub id: String,
+}
+
+/// A changelog user as tracked by the manager
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct ChangelogUser {
+    pub fs_name: String,
+    pub target: String,
+    pub id: String,
+    pub index: f64,
+    pub current_index: f64,
+    /// The number of records the user has not cleared yet
+    pub lag: f64,
+    /// When the user last cleared records
+    pub index_updated_at: DateTime<Utc>,
+    /// When the MDT last reported the user
+    pub updated_at: DateTime<Utc>,
+}
@@ -2,8 +2,9 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
-//! Data structures for communicating with the agent regarding lustre client mounts.
+//! Data structures for communicating with the agent regarding lustre clients.
 
+use chrono::{offset::Utc, DateTime};
 #[cfg(feature = "cli")]
 use structopt::StructOpt;
 
@@ -29,3 +30,24 @@ pub struct Unmount {
     /// mountpoint
     pub mountpoint: String,
 }
+
+/// A client export of a target, as listed under `obdfilter.*.exports` or `mdt.*.exports`
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct ClientExport {
+    /// The target the client is connected to, i.e. `fs-OST0000`
+    pub target: String,
+    /// The NID of the client, i.e. `10.0.0.1@tcp`
+    pub nid: String,
+}
+
+/// A client connected to targets of a filesystem
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct ConnectedClient {
+    pub nid: String,
+    pub fs_name: String,
+    /// The targets the client is connected to
+    pub targets: Vec<String>,
+    /// When a server last reported the client as connected
+    pub last_seen: DateTime<Utc>,
+}
@@ -304,6 +304,7 @@ impl ManagedTargetRecord {
     }
 }
 
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
 #[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Eq, PartialEq)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum TargetKind {
@@ -312,6 +313,31 @@ pub enum TargetKind {
     Ost,
 }
 
+impl fmt::Display for TargetKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let x = match self {
+            Self::Mgt => "MGT",
+            Self::Mdt => "MDT",
+            Self::Ost => "OST",
+        };
+
+        write!(f, "{}", x)
+    }
+}
+
+impl FromStr for TargetKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "MGT" | "MGS" => Ok(Self::Mgt),
+            "MDT" => Ok(Self::Mdt),
+            "OST" => Ok(Self::Ost),
+            x => Err(format!("Unknown target kind {}", x)),
+        }
+    }
+}
+
 fn get_kind(x: &str) -> TargetKind {
     match x {
         "MGS" => TargetKind::Mgt,
@@ -0,0 +1,69 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Data structures for the recorded history of device tree changes.
+
+use chrono::{offset::Utc, DateTime};
+use std::fmt;
+
+/// The kind of change seen on a device between two device trees.
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+#[cfg_attr(feature = "postgres-interop", derive(sqlx::Type))]
+#[cfg_attr(feature = "postgres-interop", sqlx(rename = "device_change_kind"))]
+#[cfg_attr(feature = "postgres-interop", sqlx(rename_all = "lowercase"))]
+#[derive(
+    serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceChangeKind {
+    /// The device appeared
+    #[cfg_attr(feature = "graphql", graphql(name = "added"))]
+    Added,
+    /// The device went away
+    #[cfg_attr(feature = "graphql", graphql(name = "removed"))]
+    Removed,
+    /// The set of paths to the device changed
+    #[cfg_attr(feature = "graphql", graphql(name = "paths"))]
+    Paths,
+    /// The size of the device changed
+    #[cfg_attr(feature = "graphql", graphql(name = "size"))]
+    Size,
+    /// The device was mounted, unmounted or remounted
+    #[cfg_attr(feature = "graphql", graphql(name = "mount"))]
+    Mount,
+}
+
+impl fmt::Display for DeviceChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let x = match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Paths => "paths",
+            Self::Size => "size",
+            Self::Mount => "mount",
+        };
+
+        write!(f, "{}", x)
+    }
+}
+
+/// A single recorded change to a device on a host.
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct DeviceHistory {
+    pub id: i32,
+    /// The host the device was seen on
+    pub fqdn: String,
+    /// The device-scanner id of the device
+    pub device_id: String,
+    /// The kind of device, i.e. `ScsiDevice`, `Zpool`
+    pub device_type: String,
+    pub change: DeviceChangeKind,
+    /// The value before the change, if the change has one
+    pub old_value: Option<String>,
+    /// The value after the change, if the change has one
+    pub new_value: Option<String>,
+    /// When the change was seen by the manager
+    pub time: DateTime<Utc>,
+}
@@ -0,0 +1,38 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Data structures for communicating drive SMART health between the agent and manager.
+
+use std::collections::BTreeMap;
+
+/// SMART health of a single drive.
+///
+/// `attributes` holds the counters that were read for this drive,
+/// keyed by a normalized name (i.e. `temperature`, `media_errors`).
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct DiskHealth {
+    /// The device-scanner id of the drive
+    pub device_id: String,
+    pub path: String,
+    pub model: Option<String>,
+    pub serial_number: Option<String>,
+    /// The overall SMART self-assessment, if the drive reports one
+    pub passed: Option<bool>,
+    /// The drive has flagged itself as likely to fail soon
+    pub predictive_failure: bool,
+    pub attributes: BTreeMap<String, i64>,
+}
+
+/// The payload sent by the `disk_health` daemon plugin.
+///
+/// When `full` is set, `disks` is a listing of every drive on the host.
+/// Otherwise `disks` only holds drives that changed since the last send,
+/// with only their changed `attributes`.
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug, Default)]
+pub struct DiskHealthData {
+    pub full: bool,
+    pub disks: Vec<DiskHealth>,
+    /// device ids of drives that are no longer present
+    pub removed: Vec<String>,
+}
@@ -0,0 +1,155 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Data structures for creating Lustre filesystems.
+
+use crate::FsType;
+use std::fmt;
+
+/// The kind of a Lustre target
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetKind {
+    Mgt,
+    Mdt,
+    Ost,
+}
+
+impl fmt::Display for TargetKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let x = match self {
+            Self::Mgt => "mgt",
+            Self::Mdt => "mdt",
+            Self::Ost => "ost",
+        };
+
+        write!(f, "{}", x)
+    }
+}
+
+/// A device to format as a target.
+/// `path` must be one of the paths of the device in the device tree of `host`
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLInputObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct TargetDevice {
+    /// The host the target runs on by default
+    pub host: String,
+    pub path: String,
+}
+
+/// The arguments of the `target_format` action
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct FormatTarget {
+    pub kind: TargetKind,
+    pub fs_name: String,
+    pub index: u32,
+    pub backfs: FsType,
+    /// The device to format, by a path that is the same on every host the target runs on
+    pub device: String,
+    /// The NIDs of each host the MGS runs on
+    pub mgs_nids: Vec<Vec<String>>,
+    /// The NIDs of each host the target runs on
+    pub service_nids: Vec<Vec<String>>,
+    /// Passed to `mkfs.lustre` as `--mkfsoptions`
+    pub mkfs_options: Option<String>,
+}
+
+impl FormatTarget {
+    /// The name of the target, i.e. `fs-OST000a`
+    pub fn label(&self) -> String {
+        match self.kind {
+            TargetKind::Mgt => "MGS".into(),
+            TargetKind::Mdt => format!("{}-MDT{:04x}", self.fs_name, self.index),
+            TargetKind::Ost => format!("{}-OST{:04x}", self.fs_name, self.index),
+        }
+    }
+    /// The id of the HA resource of the target.
+    /// MGTs are named after their filesystem, so they don't collide with other MGSs in the cluster
+    pub fn ha_label(&self) -> String {
+        match self.kind {
+            TargetKind::Mgt => format!("{}-MGS", self.fs_name),
+            _ => self.label(),
+        }
+    }
+    /// The zpool a ZFS backed target is created in, i.e. `fs-ost000a`
+    pub fn zpool(&self) -> String {
+        match self.kind {
+            TargetKind::Mgt => format!("{}-mgs", self.fs_name),
+            _ => self.label().to_lowercase(),
+        }
+    }
+    /// What the target is mounted from.
+    /// This is the device for ldiskfs and the dataset for ZFS, i.e. `fs-ost000a/ost`
+    pub fn target(&self) -> String {
+        match self.backfs {
+            FsType::Ldiskfs => self.device.clone(),
+            FsType::Zfs => format!("{}/{}", self.zpool(), self.kind),
+        }
+    }
+    /// Where the target is mounted, i.e. `/mnt/fs-OST000a`
+    pub fn mountpoint(&self) -> String {
+        match self.kind {
+            TargetKind::Mgt => format!("/mnt/{}-MGS", self.fs_name),
+            _ => format!("/mnt/{}", self.label()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format_target(kind: TargetKind, backfs: FsType) -> FormatTarget {
+        FormatTarget {
+            kind,
+            fs_name: "fs".into(),
+            index: 10,
+            backfs,
+            device: "/dev/mapper/mpatha".into(),
+            mgs_nids: vec![],
+            service_nids: vec![],
+            mkfs_options: None,
+        }
+    }
+
+    #[test]
+    fn test_label() {
+        assert_eq!(
+            format_target(TargetKind::Ost, FsType::Ldiskfs).label(),
+            "fs-OST000a"
+        );
+        assert_eq!(
+            format_target(TargetKind::Mgt, FsType::Ldiskfs).label(),
+            "MGS"
+        );
+    }
+
+    #[test]
+    fn test_ha_label() {
+        assert_eq!(
+            format_target(TargetKind::Mgt, FsType::Zfs).ha_label(),
+            "fs-MGS"
+        );
+        assert_eq!(
+            format_target(TargetKind::Mdt, FsType::Zfs).ha_label(),
+            "fs-MDT000a"
+        );
+    }
+
+    #[test]
+    fn test_target() {
+        assert_eq!(
+            format_target(TargetKind::Mdt, FsType::Ldiskfs).target(),
+            "/dev/mapper/mpatha"
+        );
+        assert_eq!(
+            format_target(TargetKind::Mdt, FsType::Zfs).target(),
+            "fs-mdt000a/mdt"
+        );
+        assert_eq!(
+            format_target(TargetKind::Mgt, FsType::Zfs).target(),
+            "fs-mgs/mgt"
+        );
+    }
+}
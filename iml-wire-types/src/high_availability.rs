@@ -2,6 +2,7 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
+use crate::ResourceConstraint;
 use std::{
     collections::HashMap,
     convert::TryFrom,
@@ -88,10 +89,77 @@ pub struct Ban {
     pub master_only: bool,
 }
 
+/// A pacemaker resource group and the ids of its member primitives, in order.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ResourceGroup {
+    pub id: String,
+    pub resources: Vec<String>,
+}
+
+/// Fencing coverage of a single cluster node.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StonithNode {
+    pub node: String,
+    /// Ids of the fence devices able to fence this node
+    pub devices: Vec<String>,
+    /// Why the remaining fence devices cannot fence this node
+    pub errors: Vec<String>,
+}
+
+impl StonithNode {
+    /// Is at least one fence device able to fence this node
+    pub fn is_covered(&self) -> bool {
+        !self.devices.is_empty()
+    }
+}
+
+/// Status of a fence device as reported by `stonith_admin --query`
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StonithDeviceStatus {
+    pub device: String,
+    pub reachable: bool,
+    pub message: String,
+}
+
+/// The fence devices `stonith_admin --list` reports can fence a node
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StonithTarget {
+    pub node: String,
+    pub devices: Vec<StonithDeviceStatus>,
+}
+
+/// Status of a corosync ring (corosync 2) or knet link (corosync 3)
+/// as seen by the local node.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CorosyncLink {
+    pub id: u32,
+    pub address: String,
+    pub faulty: bool,
+    pub status: String,
+}
+
+/// Votequorum state as reported by `corosync-quorumtool`
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Quorum {
+    pub quorate: bool,
+    pub nodes: u32,
+    pub expected_votes: u32,
+    pub total_votes: u32,
+    pub quorum: u32,
+}
+
 #[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
 pub struct Cluster {
     pub nodes: Vec<Node>,
     pub resources: Vec<Resource>,
     pub bans: Vec<Ban>,
     pub resource_mounts: HashMap<String, String>,
+    #[serde(default)]
+    pub constraints: Vec<ResourceConstraint>,
+    #[serde(default)]
+    pub stonith: Vec<StonithNode>,
+    #[serde(default)]
+    pub links: Vec<CorosyncLink>,
+    #[serde(default)]
+    pub quorum: Option<Quorum>,
 }
@@ -0,0 +1,400 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Data structures for Lustre directory default layouts.
+//!
+//! A layout is a list of components. A plain layout has a single component
+//! spanning the whole file, a progressive file layout (PFL) has one component per extent.
+//! The first component of a PFL may be stored on the MDT (Data-on-MDT).
+
+use chrono::{offset::Utc, DateTime};
+use std::{convert::TryFrom, fmt, str::FromStr};
+
+/// Stripe sizes must be a multiple of 64KiB
+const STRIPE_ALIGN: u64 = 64 * 1024;
+
+/// One component of a layout.
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug, Default)]
+pub struct LayoutComponent {
+    /// Where the component ends, in bytes. `None` extends it to the end of the file
+    pub extent_end: Option<u64>,
+    /// Store the component on the MDT. Only the first component may do this
+    pub dom: bool,
+    /// `-1` stripes over all OSTs. `None` uses the filesystem default
+    pub stripe_count: Option<i64>,
+    /// In bytes. `None` uses the filesystem default
+    pub stripe_size: Option<u64>,
+    /// Only place stripes on the OSTs of this pool
+    pub pool: Option<String>,
+}
+
+/// Parses a size with an optional binary unit suffix, i.e. `64K`, `1M`, `4G`.
+pub fn parse_size(x: &str) -> Result<u64, String> {
+    let x = x.trim();
+    let (num, unit) = match x.find(|c: char| !c.is_ascii_digit()) {
+        Some(idx) => x.split_at(idx),
+        None => (x, ""),
+    };
+
+    let shift = match unit.to_lowercase().as_str() {
+        "" => 0,
+        "k" => 10,
+        "m" => 20,
+        "g" => 30,
+        "t" => 40,
+        "p" => 50,
+        _ => return Err(format!("Invalid size {}", x)),
+    };
+
+    let num: u64 = num.parse().map_err(|_| format!("Invalid size {}", x))?;
+
+    num.checked_mul(1 << shift)
+        .ok_or_else(|| format!("Size {} is too large", x))
+}
+
+fn format_size(x: u64) -> String {
+    if x == 0 {
+        return x.to_string();
+    }
+
+    ["P", "T", "G", "M", "K"]
+        .iter()
+        .zip((1..=5).rev())
+        .find(|(_, shift)| x % (1 << (shift * 10)) == 0)
+        .map(|(unit, shift)| format!("{}{}", x >> (shift * 10), unit))
+        .unwrap_or_else(|| x.to_string())
+}
+
+/// Parses the `key=value,...` form of a component,
+/// i.e. `end=1M,dom` or `end=eof,count=-1,size=4M,pool=flash`
+impl FromStr for LayoutComponent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut x = LayoutComponent::default();
+
+        for part in s.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+            let mut kv = part.splitn(2, '=');
+
+            match (kv.next(), kv.next()) {
+                (Some("dom"), None) => x.dom = true,
+                (Some("end"), Some(v)) if v.eq_ignore_ascii_case("eof") => x.extent_end = None,
+                (Some("end"), Some(v)) => x.extent_end = Some(parse_size(v)?),
+                (Some("count"), Some(v)) => {
+                    x.stripe_count = Some(
+                        v.parse()
+                            .map_err(|_| format!("Invalid stripe count {}", v))?,
+                    )
+                }
+                (Some("size"), Some(v)) => x.stripe_size = Some(parse_size(v)?),
+                (Some("pool"), Some(v)) => x.pool = Some(v.to_string()),
+                _ => return Err(format!("Invalid layout component option {}", part)),
+            }
+        }
+
+        Ok(x)
+    }
+}
+
+impl fmt::Display for LayoutComponent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut xs = vec![format!(
+            "end={}",
+            self.extent_end
+                .map(format_size)
+                .unwrap_or_else(|| "eof".to_string())
+        )];
+
+        if self.dom {
+            xs.push("dom".to_string());
+        }
+
+        if let Some(count) = self.stripe_count {
+            xs.push(format!("count={}", count));
+        }
+
+        if let Some(size) = self.stripe_size {
+            xs.push(format!("size={}", format_size(size)));
+        }
+
+        if let Some(pool) = &self.pool {
+            xs.push(format!("pool={}", pool));
+        }
+
+        write!(f, "{}", xs.join(","))
+    }
+}
+
+/// A directory default layout.
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct Layout {
+    pub components: Vec<LayoutComponent>,
+}
+
+impl Layout {
+    /// A layout is composite unless it is a single OST component spanning the whole file.
+    pub fn is_composite(&self) -> bool {
+        match self.components.as_slice() {
+            [x] => x.dom || x.extent_end.is_some(),
+            _ => true,
+        }
+    }
+    /// Checks the layout can be set with `lfs setstripe`.
+    pub fn validate(&self) -> Result<(), String> {
+        let last = self
+            .components
+            .last()
+            .ok_or_else(|| "A layout needs at least one component".to_string())?;
+
+        if last.extent_end.is_some() {
+            return Err("The last layout component must extend to eof".into());
+        }
+
+        let mut start = 0;
+
+        for (idx, x) in self.components.iter().enumerate() {
+            if x.dom {
+                if idx != 0 {
+                    return Err("Only the first layout component can be on the MDT".into());
+                }
+
+                if x.extent_end.is_none() || self.components.len() == 1 {
+                    return Err("A Data-on-MDT component must be followed by OST components".into());
+                }
+
+                if x.stripe_count.is_some() || x.pool.is_some() {
+                    return Err(
+                        "A Data-on-MDT component can not have a stripe count or pool".into(),
+                    );
+                }
+            }
+
+            if let Some(end) = x.extent_end {
+                if end <= start {
+                    return Err(format!(
+                        "Layout component {} must end after {}",
+                        idx,
+                        format_size(start)
+                    ));
+                }
+
+                if end % STRIPE_ALIGN != 0 {
+                    return Err(format!(
+                        "Layout component {} must end on a 64K boundary",
+                        idx
+                    ));
+                }
+
+                start = end;
+            } else if idx != self.components.len() - 1 {
+                return Err("Only the last layout component can extend to eof".into());
+            }
+
+            match x.stripe_count {
+                Some(count) if count < -1 => {
+                    return Err(format!("Invalid stripe count {}", count));
+                }
+                _ => {}
+            }
+
+            match x.stripe_size {
+                Some(size) if size == 0 || size % STRIPE_ALIGN != 0 => {
+                    return Err(format!(
+                        "Stripe size {} is not a multiple of 64K",
+                        format_size(size)
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Read the default layout of a directory via `lfs getstripe -d`.
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct GetLayout {
+    /// The directory, on a client mount
+    pub path: String,
+}
+
+/// Set the default layout of a directory via `lfs setstripe`.
+/// A `layout` of `None` removes the default layout of the directory.
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct SetLayout {
+    /// The directory, on a client mount
+    pub path: String,
+    pub layout: Option<Layout>,
+}
+
+/// GraphQL representation of a `LayoutComponent`. Sizes are in bytes.
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct LayoutComponentOut {
+    /// Where the component ends. Not set if it extends to the end of the file
+    pub extent_end: Option<f64>,
+    /// Whether the component is stored on the MDT
+    pub dom: bool,
+    pub stripe_count: Option<i32>,
+    pub stripe_size: Option<f64>,
+    pub pool: Option<String>,
+}
+
+impl From<LayoutComponent> for LayoutComponentOut {
+    fn from(x: LayoutComponent) -> Self {
+        Self {
+            extent_end: x.extent_end.map(|x| x as f64),
+            dom: x.dom,
+            stripe_count: x.stripe_count.map(|x| x as i32),
+            stripe_size: x.stripe_size.map(|x| x as f64),
+            pool: x.pool,
+        }
+    }
+}
+
+impl From<LayoutComponentOut> for LayoutComponent {
+    fn from(x: LayoutComponentOut) -> Self {
+        Self {
+            extent_end: x.extent_end.map(|x| x as u64),
+            dom: x.dom,
+            stripe_count: x.stripe_count.map(i64::from),
+            stripe_size: x.stripe_size.map(|x| x as u64),
+            pool: x.pool,
+        }
+    }
+}
+
+/// GraphQL input of a `LayoutComponent`. Sizes are in bytes.
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLInputObject))]
+#[derive(serde::Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutComponentInput {
+    /// Where the component ends. If not set it extends to the end of the file
+    pub extent_end: Option<f64>,
+    /// Store the component on the MDT
+    pub dom: bool,
+    /// `-1` stripes over all OSTs. If not set the filesystem default is used
+    pub stripe_count: Option<i32>,
+    /// If not set the filesystem default is used
+    pub stripe_size: Option<f64>,
+    /// Only place stripes on the OSTs of this pool
+    pub pool: Option<String>,
+}
+
+impl From<LayoutComponent> for LayoutComponentInput {
+    fn from(x: LayoutComponent) -> Self {
+        Self {
+            extent_end: x.extent_end.map(|x| x as f64),
+            dom: x.dom,
+            stripe_count: x.stripe_count.map(|x| x as i32),
+            stripe_size: x.stripe_size.map(|x| x as f64),
+            pool: x.pool,
+        }
+    }
+}
+
+fn to_bytes(x: f64) -> Result<u64, String> {
+    if x < 0.0 || x.fract() != 0.0 {
+        Err(format!("Invalid size {}", x))
+    } else {
+        Ok(x as u64)
+    }
+}
+
+impl TryFrom<LayoutComponentInput> for LayoutComponent {
+    type Error = String;
+
+    fn try_from(x: LayoutComponentInput) -> Result<Self, Self::Error> {
+        Ok(Self {
+            extent_end: x.extent_end.map(to_bytes).transpose()?,
+            dom: x.dom,
+            stripe_count: x.stripe_count.map(i64::from),
+            stripe_size: x.stripe_size.map(to_bytes).transpose()?,
+            pool: x.pool,
+        })
+    }
+}
+
+/// A named layout that can be applied to directories.
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct LayoutTemplate {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    pub components: Vec<LayoutComponentOut>,
+}
+
+/// A default layout the manager has set on a directory.
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct AppliedLayout {
+    pub fs_name: String,
+    /// The directory, relative to the filesystem root
+    pub path: String,
+    /// The template the layout was set from, if any
+    pub template: Option<String>,
+    pub components: Vec<LayoutComponentOut>,
+    pub applied_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_component() {
+        assert_eq!(
+            "end=1M,dom".parse::<LayoutComponent>().unwrap(),
+            LayoutComponent {
+                extent_end: Some(1024 * 1024),
+                dom: true,
+                ..LayoutComponent::default()
+            }
+        );
+
+        let x: LayoutComponent = "end=eof,count=-1,size=4M,pool=flash".parse().unwrap();
+
+        assert_eq!(
+            x,
+            LayoutComponent {
+                extent_end: None,
+                dom: false,
+                stripe_count: Some(-1),
+                stripe_size: Some(4 * 1024 * 1024),
+                pool: Some("flash".into()),
+            }
+        );
+        assert_eq!(x.to_string(), "end=eof,count=-1,size=4M,pool=flash");
+
+        assert!("end=1X".parse::<LayoutComponent>().is_err());
+        assert!("stripes=4".parse::<LayoutComponent>().is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let layout = |xs: &[&str]| Layout {
+            components: xs.iter().map(|x| x.parse().unwrap()).collect(),
+        };
+
+        assert_eq!(
+            layout(&["end=1M,dom", "end=64M,count=1", "end=eof,count=-1"]).validate(),
+            Ok(())
+        );
+        assert_eq!(layout(&["count=4,size=1M"]).validate(), Ok(()));
+        assert!(!layout(&["count=4,size=1M"]).is_composite());
+
+        assert!(layout(&["end=1M,dom"]).validate().is_err());
+        assert!(layout(&["end=64M", "end=1M,dom", "end=eof"])
+            .validate()
+            .is_err());
+        assert!(layout(&["end=64M", "end=64M", "end=eof"])
+            .validate()
+            .is_err());
+        assert!(layout(&["end=64M", "end=eof,size=100K"])
+            .validate()
+            .is_err());
+    }
+}
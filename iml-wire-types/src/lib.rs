@@ -2,15 +2,27 @@
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
 
+pub mod changelog;
 pub mod client;
 pub mod db;
+pub mod device_history;
+pub mod disk_health;
+pub mod filesystem;
 pub mod graphql_duration;
 pub mod high_availability;
+pub mod layout;
+pub mod lnet;
+pub mod mirror;
+pub mod nodemap;
+pub mod quota;
 pub mod sfa;
 pub mod snapshot;
 pub mod stratagem;
 pub mod task;
+pub mod tiering;
+pub mod tunable;
 pub mod warp_drive;
+pub mod zpool;
 
 use chrono::{DateTime, Utc};
 use db::LogMessageRecord;
@@ -26,7 +38,9 @@ use std::{
     sync::Arc,
 };
 
-#[derive(Eq, PartialEq, Hash, Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(
+    Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Clone, serde::Serialize, serde::Deserialize,
+)]
 #[serde(transparent)]
 pub struct PluginName(pub String);
 
@@ -164,6 +178,15 @@ pub enum ManagerMessage {
     SessionTerminateAll {
         fqdn: Fqdn,
     },
+    /// Asks the agent to wait an additional `poll_delay` seconds
+    /// between polls of a session, because the manager is falling behind.
+    /// A `poll_delay` of 0 restores the normal rate.
+    Backpressure {
+        fqdn: Fqdn,
+        plugin: PluginName,
+        session_id: Id,
+        poll_delay: u64,
+    },
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
@@ -1094,7 +1117,7 @@ pub struct FilesystemShort {
     pub name: String,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Copy, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub enum AlertRecordType {
     AlertState,
     LearnEvent,
@@ -1109,6 +1132,11 @@ pub enum AlertRecordType {
     CorosyncToManyPeersAlert,
     CorosyncNoPeersAlert,
     CorosyncStoppedAlert,
+    CorosyncRingFaultyAlert,
+    CorosyncQuorumLostAlert,
+    DiskPredictiveFailureAlert,
+    ZpoolDegradedAlert,
+    ZpoolFaultedAlert,
     StonithNotEnabledAlert,
     PacemakerStoppedAlert,
     HostContactAlert,
@@ -1124,6 +1152,11 @@ pub enum AlertRecordType {
     IpmiBmcUnavailableAlert,
     LNetOfflineAlert,
     LNetNidsChangedAlert,
+    QuotaSoftLimitAlert,
+    QuotaGraceExpiredAlert,
+    TunableDriftAlert,
+    ChangelogLagAlert,
+    ChangelogUserAbandonedAlert,
     StratagemUnconfiguredAlert,
     TimeOutOfSyncAlert,
     NoTimeSyncAlert,
@@ -1523,6 +1556,75 @@ pub mod time {
             Self(s.to_string())
         }
     }
+
+    impl std::fmt::Display for Offset {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    /// The time sync `State` of a host, along with
+    /// the measured offset between its clock and its time source.
+    #[derive(Debug, serde::Serialize, serde::Deserialize)]
+    pub struct Status {
+        pub state: State,
+        /// Offset in seconds, as reported by the running time sync client
+        pub offset: Option<f64>,
+    }
+
+    /// A time sync report as sent by an agent.
+    /// Agents that predate offset reporting send a bare `State`.
+    #[derive(Debug, serde::Deserialize)]
+    #[serde(untagged)]
+    pub enum Report {
+        Current(Status),
+        Legacy(State),
+    }
+
+    impl From<Report> for Status {
+        fn from(x: Report) -> Self {
+            match x {
+                Report::Current(x) => x,
+                Report::Legacy(state) => Status {
+                    state,
+                    offset: None,
+                },
+            }
+        }
+    }
+
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    /// The latest clock offset of a server
+    pub struct HostOffset {
+        pub fqdn: String,
+        /// Offset in seconds
+        pub offset: f64,
+    }
+
+    #[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+    #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    /// Clock offsets across the Lustre servers of the cluster
+    pub struct ClusterSkew {
+        /// The largest difference in seconds between the clocks of any two servers
+        pub max_skew: Option<f64>,
+        pub offsets: Vec<HostOffset>,
+    }
+
+    impl ClusterSkew {
+        pub fn new(offsets: Vec<HostOffset>) -> Self {
+            let max_skew = offsets
+                .iter()
+                .map(|x| x.offset)
+                .fold(None, |acc: Option<(f64, f64)>, x| match acc {
+                    Some((lo, hi)) => Some((lo.min(x), hi.max(x))),
+                    None => Some((x, x)),
+                })
+                .map(|(lo, hi)| hi - lo);
+
+            Self { max_skew, offsets }
+        }
+    }
 }
 
 /// Types used for component checks
@@ -1640,6 +1742,22 @@ impl fmt::Display for OrderingKind {
     }
 }
 
+impl TryFrom<&str> for OrderingKind {
+    type Error = io::Error;
+
+    fn try_from(x: &str) -> Result<Self, Self::Error> {
+        match x {
+            "Mandatory" => Ok(Self::Mandatory),
+            "Optional" => Ok(Self::Optional),
+            "Serialize" => Ok(Self::Serialize),
+            x => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid ordering kind {}", x),
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum PacemakerScore {
@@ -1658,6 +1776,18 @@ impl fmt::Display for PacemakerScore {
     }
 }
 
+impl TryFrom<&str> for PacemakerScore {
+    type Error = ParseIntError;
+
+    fn try_from(x: &str) -> Result<Self, Self::Error> {
+        match x {
+            "INFINITY" | "+INFINITY" => Ok(Self::Infinity),
+            "-INFINITY" => Ok(Self::NegInfinity),
+            x => Ok(Self::Value(x.parse()?)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum PacemakerKindOrScore {
@@ -1713,6 +1843,23 @@ impl fmt::Display for LossPolicy {
     }
 }
 
+impl TryFrom<&str> for LossPolicy {
+    type Error = io::Error;
+
+    fn try_from(x: &str) -> Result<Self, Self::Error> {
+        match x {
+            "stop" => Ok(Self::Stop),
+            "demote" => Ok(Self::Demote),
+            "fence" => Ok(Self::Fence),
+            "freeze" => Ok(Self::Freeze),
+            x => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid loss policy {}", x),
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum PacemakerActions {
@@ -1733,6 +1880,23 @@ impl fmt::Display for PacemakerActions {
     }
 }
 
+impl TryFrom<&str> for PacemakerActions {
+    type Error = io::Error;
+
+    fn try_from(x: &str) -> Result<Self, Self::Error> {
+        match x {
+            "start" => Ok(Self::Start),
+            "promote" => Ok(Self::Promote),
+            "demote" => Ok(Self::Demote),
+            "stop" => Ok(Self::Stop),
+            x => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid pacemaker action {}", x),
+            )),
+        }
+    }
+}
+
 /// Information about pacemaker resource agents
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -1767,6 +1931,18 @@ pub enum ResourceConstraint {
     },
 }
 
+impl ResourceConstraint {
+    /// The id of this constraint in the CIB
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Order { id, .. }
+            | Self::Location { id, .. }
+            | Self::Colocation { id, .. }
+            | Self::Ticket { id, .. } => id,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum ConfigState {
     Unknown,
@@ -2365,6 +2541,11 @@ pub struct NetworkInterface {
     pub stats: Option<InterfaceStats>,
     pub is_up: bool,
     pub is_slave: bool,
+    #[serde(default)]
+    pub mtu: Option<u32>,
+    /// Link speed in Mb/s
+    #[serde(default)]
+    pub speed: Option<u64>,
 }
 
 #[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
@@ -2416,3 +2597,21 @@ pub struct NetworkData {
     pub network_interfaces: Vec<NetworkInterface>,
     pub lnet_data: LNet,
 }
+
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+/// The recent throughput and error rates of a network interface, per second
+pub struct NetworkInterfaceRates {
+    pub interface: String,
+    pub mtu: Option<i32>,
+    /// Link speed in Mb/s
+    pub speed: Option<i32>,
+    pub rx_bytes: Option<f64>,
+    pub tx_bytes: Option<f64>,
+    pub rx_packets: Option<f64>,
+    pub tx_packets: Option<f64>,
+    pub rx_errors: Option<f64>,
+    pub tx_errors: Option<f64>,
+    pub rx_drops: Option<f64>,
+    pub tx_drops: Option<f64>,
+}
@@ -0,0 +1,43 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Arguments for the agent actions that configure LNet via `lnetctl`.
+
+/// Configure an LNet network on one or more interfaces
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct NetAdd {
+    /// The LNet network, i.e. `tcp0` or `o2ib1`
+    pub net: String,
+    /// The interfaces to configure `net` on
+    pub interfaces: Vec<String>,
+    pub peer_credits: Option<u32>,
+    pub credits: Option<u32>,
+}
+
+/// Remove an LNet network, or only some of its interfaces
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct NetDel {
+    pub net: String,
+    /// The interfaces to remove. If empty, the whole network is removed
+    #[serde(default)]
+    pub interfaces: Vec<String>,
+}
+
+/// Add a route to a remote LNet network
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct RouteAdd {
+    /// The remote network
+    pub net: String,
+    /// The NID of the router, i.e. `10.0.0.1@tcp0`
+    pub gateway: String,
+    pub hop: Option<u32>,
+    pub priority: Option<u32>,
+}
+
+/// Remove a route to a remote LNet network
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct RouteDel {
+    pub net: String,
+    pub gateway: String,
+}
@@ -0,0 +1,50 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Data structures for managing FLR mirrors.
+
+use crate::graphql_duration::GraphQLDuration;
+
+/// Which files under a path `mirror_find` lists
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub enum MirrorFilter {
+    /// Regular files without a component on the pool
+    NotOnPool(String),
+    /// Mirrored files with mirrors that are out of sync
+    Stale,
+}
+
+/// Arguments of the `mirror_find` and `mirror_find_to_mailbox` actions
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct MirrorFind {
+    /// Where the filesystem is mounted on the client
+    pub mountpoint: String,
+    /// The path to search, from the filesystem root
+    pub path: String,
+    pub filter: MirrorFilter,
+    /// The maximum number of files to list. `mirror_find_to_mailbox` sends all of them
+    pub limit: Option<u32>,
+}
+
+/// A file listed by `mirror_find`
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct MirrorFile {
+    /// The path of the file, from the filesystem root
+    pub path: String,
+    pub fid: String,
+}
+
+/// A recurring resync of the stale mirrors under a path
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct MirrorResyncInterval {
+    /// The configuration id
+    pub id: i32,
+    pub fs_name: String,
+    /// The path to resync, from the filesystem root
+    pub path: String,
+    /// How often stale mirrors are resynced
+    pub interval: GraphQLDuration,
+}
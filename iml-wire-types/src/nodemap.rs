@@ -0,0 +1,506 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Data structures for Lustre nodemaps.
+
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+/// The uid and gid unmapped users are squashed to by default
+pub const NODEMAP_NOBODY_ID: i32 = 99;
+
+/// The nodemap clients outside any range belong to. It always exists and cannot have ranges
+pub const DEFAULT_NODEMAP: &str = "default";
+
+/// A range of client NIDs
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct NodemapRange {
+    pub start_nid: String,
+    pub end_nid: String,
+}
+
+/// Splits a NID into its address, the last address component and the network,
+/// i.e. `("192.168.1", "10", "tcp")` for `192.168.1.10@tcp`
+fn split_nid(nid: &str) -> Option<(&str, &str, &str)> {
+    let mut xs = nid.splitn(2, '@');
+
+    let addr = xs.next()?;
+    let net = xs.next()?;
+
+    let idx = addr.rfind('.')?;
+
+    Some((&addr[..idx], &addr[idx + 1..], net))
+}
+
+impl FromStr for NodemapRange {
+    type Err = String;
+
+    /// Parses a single NID, i.e. `192.168.1.10@tcp`,
+    /// or a range of the last address component, i.e. `192.168.1.[1-100]@tcp`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid NID range {}", s);
+
+        let (prefix, last, net) = split_nid(s).ok_or_else(invalid)?;
+
+        if net.is_empty() || prefix.is_empty() {
+            return Err(invalid());
+        }
+
+        let (start, end) = match last.strip_prefix('[').and_then(|x| x.strip_suffix(']')) {
+            Some(x) => {
+                let mut xs = x.splitn(2, '-');
+
+                (
+                    xs.next().ok_or_else(invalid)?,
+                    xs.next().ok_or_else(invalid)?,
+                )
+            }
+            None => (last, last),
+        };
+
+        let start: u8 = start.parse().map_err(|_| invalid())?;
+        let end: u8 = end.parse().map_err(|_| invalid())?;
+
+        if start > end {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            start_nid: format!("{}.{}@{}", prefix, start, net),
+            end_nid: format!("{}.{}@{}", prefix, end, net),
+        })
+    }
+}
+
+impl fmt::Display for NodemapRange {
+    /// Formats the range as `lctl` accepts it, if possible
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.start_nid == self.end_nid {
+            return write!(f, "{}", self.start_nid);
+        }
+
+        match (split_nid(&self.start_nid), split_nid(&self.end_nid)) {
+            (Some((p1, start, n1)), Some((p2, end, n2))) if p1 == p2 && n1 == n2 => {
+                write!(f, "{}.[{}-{}]@{}", p1, start, end, n1)
+            }
+            _ => write!(f, "{}:{}", self.start_nid, self.end_nid),
+        }
+    }
+}
+
+/// Whether an idmap maps user or group ids
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+#[cfg_attr(feature = "postgres-interop", derive(sqlx::Type))]
+#[cfg_attr(feature = "postgres-interop", sqlx(rename = "nodemap_id_kind"))]
+#[cfg_attr(feature = "postgres-interop", sqlx(rename_all = "lowercase"))]
+#[derive(
+    serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum NodemapIdKind {
+    #[cfg_attr(feature = "graphql", graphql(name = "uid"))]
+    Uid,
+    #[cfg_attr(feature = "graphql", graphql(name = "gid"))]
+    Gid,
+}
+
+impl fmt::Display for NodemapIdKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let x = match self {
+            Self::Uid => "uid",
+            Self::Gid => "gid",
+        };
+
+        write!(f, "{}", x)
+    }
+}
+
+impl FromStr for NodemapIdKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "uid" => Ok(Self::Uid),
+            "gid" => Ok(Self::Gid),
+            x => Err(format!("Unknown id kind {}", x)),
+        }
+    }
+}
+
+/// Maps an id on the client to an id on the filesystem
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct NodemapIdmap {
+    pub kind: NodemapIdKind,
+    pub client_id: i32,
+    pub fs_id: i32,
+}
+
+/// A nodemap, either as desired or as reported by the MGS
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct Nodemap {
+    pub name: String,
+    /// Whether root on the clients is not squashed
+    pub admin: bool,
+    /// Whether client ids are used as is, without mapping
+    pub trusted: bool,
+    pub squash_uid: i32,
+    pub squash_gid: i32,
+    pub ranges: Vec<NodemapRange>,
+    pub idmaps: Vec<NodemapIdmap>,
+}
+
+impl Nodemap {
+    /// A nodemap as `nodemap_add` creates it
+    pub fn new(name: impl ToString) -> Self {
+        Self {
+            name: name.to_string(),
+            admin: false,
+            trusted: false,
+            squash_uid: NODEMAP_NOBODY_ID,
+            squash_gid: NODEMAP_NOBODY_ID,
+            ranges: vec![],
+            idmaps: vec![],
+        }
+    }
+}
+
+/// The nodemaps of an MGS
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct NodemapState {
+    /// Whether nodemaps are enforced
+    pub active: bool,
+    pub nodemaps: Vec<Nodemap>,
+}
+
+/// A settable nodemap property
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum NodemapProperty {
+    Admin,
+    Trusted,
+    SquashUid,
+    SquashGid,
+}
+
+impl fmt::Display for NodemapProperty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let x = match self {
+            Self::Admin => "admin",
+            Self::Trusted => "trusted",
+            Self::SquashUid => "squash_uid",
+            Self::SquashGid => "squash_gid",
+        };
+
+        write!(f, "{}", x)
+    }
+}
+
+/// A single `lctl nodemap_*` change on the MGS
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum NodemapChange {
+    Add {
+        name: String,
+    },
+    Del {
+        name: String,
+    },
+    AddRange {
+        name: String,
+        range: NodemapRange,
+    },
+    DelRange {
+        name: String,
+        range: NodemapRange,
+    },
+    AddIdmap {
+        name: String,
+        idmap: NodemapIdmap,
+    },
+    DelIdmap {
+        name: String,
+        idmap: NodemapIdmap,
+    },
+    Modify {
+        name: String,
+        property: NodemapProperty,
+        value: String,
+    },
+    Activate(bool),
+}
+
+impl fmt::Display for NodemapChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Add { name } => write!(f, "add nodemap {}", name),
+            Self::Del { name } => write!(f, "remove nodemap {}", name),
+            Self::AddRange { name, range } => write!(f, "add range {} to {}", range, name),
+            Self::DelRange { name, range } => write!(f, "remove range {} from {}", range, name),
+            Self::AddIdmap { name, idmap } => write!(
+                f,
+                "add {} map {}:{} to {}",
+                idmap.kind, idmap.client_id, idmap.fs_id, name
+            ),
+            Self::DelIdmap { name, idmap } => write!(
+                f,
+                "remove {} map {}:{} from {}",
+                idmap.kind, idmap.client_id, idmap.fs_id, name
+            ),
+            Self::Modify {
+                name,
+                property,
+                value,
+            } => write!(f, "set {} of {} to {}", property, name, value),
+            Self::Activate(true) => write!(f, "activate nodemaps"),
+            Self::Activate(false) => write!(f, "deactivate nodemaps"),
+        }
+    }
+}
+
+fn bool_value(x: bool) -> String {
+    if x { "1" } else { "0" }.to_string()
+}
+
+/// Returns the changes that make the nodemaps of the MGS in `actual` match `desired`.
+///
+/// Ranges and idmaps are removed before any are added, as ranges may not overlap
+/// across nodemaps. Nodemaps on the MGS that are not in `desired` are left as is,
+/// as is activation if `desired_active` is `None`.
+pub fn nodemap_changes(
+    desired: &[Nodemap],
+    desired_active: Option<bool>,
+    actual: &NodemapState,
+) -> Vec<NodemapChange> {
+    let mut adds = vec![];
+    let mut dels = vec![];
+    let mut updates = vec![];
+
+    for d in desired {
+        let a = match actual.nodemaps.iter().find(|x| x.name == d.name) {
+            Some(x) => x.clone(),
+            None => {
+                adds.push(NodemapChange::Add {
+                    name: d.name.clone(),
+                });
+
+                Nodemap::new(&d.name)
+            }
+        };
+
+        let name = || d.name.clone();
+
+        let desired_ranges: BTreeSet<_> = d.ranges.iter().collect();
+        let actual_ranges: BTreeSet<_> = a.ranges.iter().collect();
+
+        dels.extend(
+            actual_ranges
+                .difference(&desired_ranges)
+                .map(|x| NodemapChange::DelRange {
+                    name: name(),
+                    range: (*x).clone(),
+                }),
+        );
+        updates.extend(desired_ranges.difference(&actual_ranges).map(|x| {
+            NodemapChange::AddRange {
+                name: name(),
+                range: (*x).clone(),
+            }
+        }));
+
+        let desired_idmaps: BTreeSet<_> = d.idmaps.iter().collect();
+        let actual_idmaps: BTreeSet<_> = a.idmaps.iter().collect();
+
+        dels.extend(
+            actual_idmaps
+                .difference(&desired_idmaps)
+                .map(|x| NodemapChange::DelIdmap {
+                    name: name(),
+                    idmap: (*x).clone(),
+                }),
+        );
+        updates.extend(desired_idmaps.difference(&actual_idmaps).map(|x| {
+            NodemapChange::AddIdmap {
+                name: name(),
+                idmap: (*x).clone(),
+            }
+        }));
+
+        let properties = vec![
+            (
+                NodemapProperty::Admin,
+                d.admin != a.admin,
+                bool_value(d.admin),
+            ),
+            (
+                NodemapProperty::Trusted,
+                d.trusted != a.trusted,
+                bool_value(d.trusted),
+            ),
+            (
+                NodemapProperty::SquashUid,
+                d.squash_uid != a.squash_uid,
+                d.squash_uid.to_string(),
+            ),
+            (
+                NodemapProperty::SquashGid,
+                d.squash_gid != a.squash_gid,
+                d.squash_gid.to_string(),
+            ),
+        ];
+
+        updates.extend(
+            properties
+                .into_iter()
+                .filter(|(_, changed, _)| *changed)
+                .map(|(property, _, value)| NodemapChange::Modify {
+                    name: name(),
+                    property,
+                    value,
+                }),
+        );
+    }
+
+    let mut xs: Vec<_> = adds.into_iter().chain(dels).chain(updates).collect();
+
+    match desired_active {
+        Some(x) if x != actual.active => xs.push(NodemapChange::Activate(x)),
+        _ => {}
+    }
+
+    xs
+}
+
+/// Returns the names of nodemaps on the MGS that are not in `desired`
+pub fn unmanaged_nodemaps<'a>(desired: &[Nodemap], actual: &'a NodemapState) -> Vec<&'a str> {
+    actual
+        .nodemaps
+        .iter()
+        .map(|x| x.name.as_str())
+        .filter(|x| *x != DEFAULT_NODEMAP && !desired.iter().any(|d| d.name == *x))
+        .collect()
+}
+
+/// The nodemaps of a filesystem compared with its MGS
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct NodemapStatus {
+    /// Whether nodemaps are active on the MGS
+    pub active: bool,
+    /// Whether nodemaps should be active, if managed
+    pub desired_active: Option<bool>,
+    /// The changes needed to make the MGS match the desired nodemaps
+    pub pending: Vec<String>,
+    /// Nodemaps on the MGS that are not managed
+    pub unmanaged: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range() {
+        let x: NodemapRange = "192.168.1.[1-100]@tcp".parse().unwrap();
+
+        assert_eq!(
+            x,
+            NodemapRange {
+                start_nid: "192.168.1.1@tcp".into(),
+                end_nid: "192.168.1.100@tcp".into(),
+            }
+        );
+        assert_eq!(x.to_string(), "192.168.1.[1-100]@tcp");
+
+        let x: NodemapRange = "10.0.0.5@o2ib1".parse().unwrap();
+
+        assert_eq!(x.start_nid, x.end_nid);
+        assert_eq!(x.to_string(), "10.0.0.5@o2ib1");
+
+        assert!("192.168.1.[100-1]@tcp".parse::<NodemapRange>().is_err());
+        assert!("192.168.1.1".parse::<NodemapRange>().is_err());
+        assert!("192.168.1.[1-300]@tcp".parse::<NodemapRange>().is_err());
+    }
+
+    #[test]
+    fn test_nodemap_changes() {
+        let tenant = Nodemap {
+            ranges: vec!["192.168.1.[1-100]@tcp".parse().unwrap()],
+            idmaps: vec![NodemapIdmap {
+                kind: NodemapIdKind::Uid,
+                client_id: 500,
+                fs_id: 1000,
+            }],
+            trusted: true,
+            ..Nodemap::new("tenant1")
+        };
+
+        let actual = NodemapState {
+            active: false,
+            nodemaps: vec![
+                Nodemap::new("default"),
+                Nodemap {
+                    ranges: vec!["192.168.1.[1-50]@tcp".parse().unwrap()],
+                    ..Nodemap::new("tenant1")
+                },
+                Nodemap::new("old"),
+            ],
+        };
+
+        assert_eq!(
+            nodemap_changes(&[tenant.clone()], Some(true), &actual)
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "remove range 192.168.1.[1-50]@tcp from tenant1",
+                "add range 192.168.1.[1-100]@tcp to tenant1",
+                "add uid map 500:1000 to tenant1",
+                "set trusted of tenant1 to 1",
+                "activate nodemaps",
+            ]
+        );
+
+        assert_eq!(unmanaged_nodemaps(&[tenant.clone()], &actual), vec!["old"]);
+
+        let actual = NodemapState {
+            active: true,
+            nodemaps: vec![tenant.clone()],
+        };
+
+        assert_eq!(
+            nodemap_changes(&[tenant.clone()], Some(true), &actual),
+            vec![]
+        );
+        assert_eq!(nodemap_changes(&[tenant], None, &actual), vec![]);
+    }
+
+    #[test]
+    fn test_new_nodemap_changes() {
+        let x = Nodemap {
+            admin: true,
+            ..Nodemap::new("admins")
+        };
+
+        let actual = NodemapState {
+            active: true,
+            nodemaps: vec![],
+        };
+
+        assert_eq!(
+            nodemap_changes(&[x], None, &actual),
+            vec![
+                NodemapChange::Add {
+                    name: "admins".into()
+                },
+                NodemapChange::Modify {
+                    name: "admins".into(),
+                    property: NodemapProperty::Admin,
+                    value: "1".into(),
+                },
+            ]
+        );
+    }
+}
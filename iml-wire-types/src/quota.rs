@@ -0,0 +1,250 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Data structures for Lustre user, group and project quotas.
+
+use chrono::{offset::Utc, DateTime};
+use std::{convert::TryFrom, fmt, num::ParseIntError};
+
+/// What a quota id refers to.
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+#[cfg_attr(feature = "postgres-interop", derive(sqlx::Type))]
+#[cfg_attr(feature = "postgres-interop", sqlx(rename = "quota_kind"))]
+#[cfg_attr(feature = "postgres-interop", sqlx(rename_all = "lowercase"))]
+#[derive(
+    serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum QuotaKind {
+    #[cfg_attr(feature = "graphql", graphql(name = "user"))]
+    User,
+    #[cfg_attr(feature = "graphql", graphql(name = "group"))]
+    Group,
+    #[cfg_attr(feature = "graphql", graphql(name = "project"))]
+    Project,
+}
+
+impl QuotaKind {
+    /// The `lfs` flag selecting an id of this kind
+    pub fn flag(self) -> &'static str {
+        match self {
+            Self::User => "-u",
+            Self::Group => "-g",
+            Self::Project => "-p",
+        }
+    }
+    /// The `lfs` flag selecting the default quota of this kind
+    pub fn default_flag(self) -> &'static str {
+        match self {
+            Self::User => "-U",
+            Self::Group => "-G",
+            Self::Project => "-P",
+        }
+    }
+}
+
+impl fmt::Display for QuotaKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let x = match self {
+            Self::User => "user",
+            Self::Group => "group",
+            Self::Project => "project",
+        };
+
+        write!(f, "{}", x)
+    }
+}
+
+impl std::str::FromStr for QuotaKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "u" | "usr" | "user" => Ok(Self::User),
+            "g" | "grp" | "group" => Ok(Self::Group),
+            "p" | "prj" | "project" => Ok(Self::Project),
+            x => Err(format!("Unknown quota kind {}", x)),
+        }
+    }
+}
+
+/// Set the limits of a quota id, or the default limits of a kind, via `lfs setquota`.
+///
+/// Limits are passed through to `lfs`, so block limits take an optional unit suffix, i.e. `10G`.
+/// Limits that are not set are left unchanged, `0` removes a limit.
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct SetQuota {
+    /// A client mount of the filesystem
+    pub mountpoint: String,
+    pub kind: QuotaKind,
+    /// The user, group or project. If not set, the default quota of `kind` is set
+    pub id: Option<String>,
+    /// Only apply the block limits to the OSTs of this pool
+    pub pool: Option<String>,
+    pub block_soft: Option<String>,
+    pub block_hard: Option<String>,
+    pub inode_soft: Option<String>,
+    pub inode_hard: Option<String>,
+}
+
+/// Set the grace periods of a kind via `lfs setquota -t`.
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct SetQuotaGrace {
+    pub mountpoint: String,
+    pub kind: QuotaKind,
+    pub pool: Option<String>,
+    /// Grace period in `lfs` form, i.e. `1w2d` or seconds
+    pub block_grace: Option<String>,
+    pub inode_grace: Option<String>,
+}
+
+/// Read the quota of a single id via `lfs quota`.
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct GetQuota {
+    pub mountpoint: String,
+    pub kind: QuotaKind,
+    pub id: String,
+    pub pool: Option<String>,
+}
+
+/// Usage and limits of a single id as reported by `lfs quota`.
+/// Space is in kbytes. Grace is `None` unless a soft limit is exceeded.
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug, Default)]
+pub struct Quota {
+    pub kbytes_used: u64,
+    pub kbytes_soft: u64,
+    pub kbytes_hard: u64,
+    pub block_grace: Option<String>,
+    pub inodes_used: u64,
+    pub inodes_soft: u64,
+    pub inodes_hard: u64,
+    pub inode_grace: Option<String>,
+}
+
+/// Whether a quota master limit is on space or on inodes
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum QuotaResource {
+    /// Space, in kbytes
+    Block,
+    Inode,
+}
+
+/// A limit read from the quota master of a filesystem (`qmt.*.*.glb-*`).
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct QuotaLimit {
+    pub fs_name: String,
+    /// The pool the limit applies to, if not the whole filesystem
+    pub pool: Option<String>,
+    pub kind: QuotaKind,
+    pub resource: QuotaResource,
+    /// id `0` holds the default limits and grace period of `kind`
+    pub id: u32,
+    pub hard: u64,
+    pub soft: u64,
+    pub granted: u64,
+    /// For id `0` the grace period in seconds. Otherwise when the grace
+    /// period ends in seconds since the epoch, or `0` if the soft limit is not exceeded
+    pub time: u64,
+}
+
+/// Space and inode accounting of a quota id on a single target (`osd-*.*.quota_slave.acct_*`).
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct QuotaAcct {
+    pub target: String,
+    pub kind: QuotaKind,
+    pub id: u32,
+    pub kbytes: u64,
+    pub inodes: u64,
+}
+
+/// The payload sent by the `quota` daemon plugin.
+///
+/// `limits` is only populated on the server running the quota master.
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug, Default)]
+pub struct QuotaData {
+    pub limits: Vec<QuotaLimit>,
+    pub usage: Vec<QuotaAcct>,
+}
+
+/// A user, group or project id.
+///
+/// Ids are unsigned 32 bit values, which do not fit in a GraphQL `Int`,
+/// so they are passed as strings.
+#[derive(
+    serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug,
+)]
+#[serde(try_from = "String", into = "String")]
+pub struct QuotaId(pub u32);
+
+#[cfg(feature = "graphql")]
+#[juniper::graphql_scalar(
+    name = "QuotaId",
+    description = "A user, group or project id, as a string"
+)]
+impl<S> GraphQLScalar for QuotaId
+where
+    S: juniper::ScalarValue,
+{
+    fn resolve(&self) -> juniper::Value {
+        juniper::Value::scalar(self.0.to_string())
+    }
+
+    fn from_input_value(value: &juniper::InputValue) -> Option<QuotaId> {
+        value.as_string_value()?.parse().ok().map(QuotaId)
+    }
+
+    fn from_str<'a>(value: juniper::ScalarToken<'a>) -> juniper::ParseScalarResult<'a, S> {
+        <String as juniper::ParseScalarValue<S>>::from_str(value)
+    }
+}
+
+impl TryFrom<String> for QuotaId {
+    type Error = ParseIntError;
+
+    fn try_from(x: String) -> Result<Self, Self::Error> {
+        x.parse().map(QuotaId)
+    }
+}
+
+impl From<QuotaId> for String {
+    fn from(x: QuotaId) -> Self {
+        x.to_string()
+    }
+}
+
+impl fmt::Display for QuotaId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Limits and usage of a quota id on a filesystem.
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct QuotaUsage {
+    pub fs_name: String,
+    /// The OST pool the block limits apply to, if not the whole filesystem
+    pub pool: Option<String>,
+    pub kind: QuotaKind,
+    /// The user, group or project id. `0` holds the default limits of `kind`
+    pub quota_id: QuotaId,
+    pub kbytes_used: f64,
+    pub kbytes_soft: f64,
+    pub kbytes_hard: f64,
+    /// When the block grace period ends, if the block soft limit is exceeded
+    pub block_grace_expires: Option<DateTime<Utc>>,
+    pub inodes_used: f64,
+    pub inodes_soft: f64,
+    pub inodes_hard: f64,
+    /// When the inode grace period ends, if the inode soft limit is exceeded
+    pub inode_grace_expires: Option<DateTime<Utc>>,
+}
+
+impl QuotaUsage {
+    pub fn over_soft_limit(&self) -> bool {
+        (self.kbytes_soft > 0.0 && self.kbytes_used > self.kbytes_soft)
+            || (self.inodes_soft > 0.0 && self.inodes_used > self.inodes_soft)
+    }
+}
@@ -0,0 +1,150 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Data structures for hot / cold pool tiering with `lamigo` and `lpurge`.
+
+use chrono::{offset::Utc, DateTime};
+use std::fmt;
+
+/// A hot / cold pool tiering policy of a filesystem.
+///
+/// `lamigo` runs for each MDT and mirrors files from the hot pool to the cold pool.
+/// `lpurge` runs for each OST of the hot pool and removes hot mirrors
+/// once the OST is fuller than `freelo`.
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct TieringPolicy {
+    pub id: i32,
+    pub fs_name: String,
+    pub name: String,
+    pub hot_pool: String,
+    pub cold_pool: String,
+    /// Seconds `lamigo` waits before mirroring a closed file
+    pub min_age: i32,
+    /// The `lamigo` heat function, if any
+    pub heatfn: Option<i32>,
+    /// `lpurge` stops purging once an OST has this percentage free
+    pub freehi: i32,
+    /// `lpurge` starts purging once an OST has less than this percentage free
+    pub freelo: i32,
+    /// The Lustre client mountpoint `lamigo` uses on the MDS
+    pub mountpoint: String,
+    /// When the policy was last applied. Unset if the policy is not running
+    pub applied_at: Option<DateTime<Utc>>,
+}
+
+impl TieringPolicy {
+    /// The mailbox `lamigo` writes FIDs to mirror to
+    pub fn extend_mailbox(&self) -> String {
+        format!("tiering-{}-{}-extend", self.fs_name, self.name)
+    }
+    /// The mailbox `lamigo` writes FIDs to resync to
+    pub fn resync_mailbox(&self) -> String {
+        format!("tiering-{}-{}-resync", self.fs_name, self.name)
+    }
+    /// The mailbox `lpurge` writes FIDs to purge hot mirrors of to
+    pub fn purge_mailbox(&self) -> String {
+        format!("tiering-{}-{}-purge", self.fs_name, self.name)
+    }
+}
+
+/// The service a tiering unit runs
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+#[cfg_attr(feature = "postgres-interop", derive(sqlx::Type))]
+#[cfg_attr(feature = "postgres-interop", sqlx(rename = "tiering_unit_kind"))]
+#[cfg_attr(feature = "postgres-interop", sqlx(rename_all = "lowercase"))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum TieringUnitKind {
+    #[cfg_attr(feature = "graphql", graphql(name = "lamigo"))]
+    Lamigo,
+    #[cfg_attr(feature = "graphql", graphql(name = "lpurge"))]
+    Lpurge,
+}
+
+impl fmt::Display for TieringUnitKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let x = match self {
+            Self::Lamigo => "lamigo",
+            Self::Lpurge => "lpurge",
+        };
+
+        write!(f, "{}", x)
+    }
+}
+
+impl TieringUnitKind {
+    /// The systemd unit running this kind of service for `target`, i.e. `lamigo@fs-MDT0000.service`.
+    /// The instance matches the default `LAMIGO_CONF_PATH` and `LPURGE_CONF_PATH` of the agent.
+    pub fn unit_name(&self, target: &str) -> String {
+        format!("{}@{}.service", self, target)
+    }
+}
+
+/// Returns the index of a target, i.e. `16` for `fs-OST0010`
+pub fn target_index(target: &str) -> Option<u32> {
+    let idx = target.rfind(|c| c == '-')?;
+    let x = &target[idx + 1..];
+
+    if !(x.starts_with("MDT") || x.starts_with("OST")) {
+        return None;
+    }
+
+    u32::from_str_radix(&x[3..], 16).ok()
+}
+
+/// The state of a tiering unit, as reported by its host
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct TieringUnitStatus {
+    pub kind: TieringUnitKind,
+    pub target: String,
+    pub host: String,
+    pub unit: String,
+    /// The run state of the unit, or why it could not be read
+    pub state: String,
+    /// Whether the unit is running
+    pub healthy: bool,
+}
+
+/// The FID counts of a tiering mailbox
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct TieringMailbox {
+    pub name: String,
+    pub state: String,
+    pub fids_total: f64,
+    pub fids_completed: f64,
+    pub fids_failed: f64,
+}
+
+/// The units and mailboxes of an applied tiering policy
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct TieringStatus {
+    pub units: Vec<TieringUnitStatus>,
+    pub mailboxes: Vec<TieringMailbox>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_index() {
+        assert_eq!(target_index("fs-OST0010"), Some(16));
+        assert_eq!(target_index("fs-MDT0000"), Some(0));
+        assert_eq!(target_index("my-fs-OST000a"), Some(10));
+        assert_eq!(target_index("MGS"), None);
+        assert_eq!(target_index("fs-OSTxyz"), None);
+    }
+
+    #[test]
+    fn test_unit_name() {
+        assert_eq!(
+            TieringUnitKind::Lpurge.unit_name("fs-OST0010"),
+            "lpurge@fs-OST0010.service"
+        );
+    }
+}
@@ -0,0 +1,159 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Data structures for managed Lustre tunables.
+
+use crate::db::TargetKind;
+use chrono::{offset::Utc, DateTime};
+use std::fmt;
+
+/// How a tunable is set.
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLEnum))]
+#[cfg_attr(feature = "postgres-interop", derive(sqlx::Type))]
+#[cfg_attr(feature = "postgres-interop", sqlx(rename = "tunable_method"))]
+#[cfg_attr(feature = "postgres-interop", sqlx(rename_all = "snake_case"))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TunableMethod {
+    /// `lctl set_param -P` on the MGS. Persistent for the whole filesystem
+    #[cfg_attr(feature = "graphql", graphql(name = "set_param_p"))]
+    SetParamP,
+    /// `lctl conf_param` on the MGS. Persistent for the whole filesystem.
+    /// `conf_param` names are not `get_param` paths, so these are not checked for drift
+    #[cfg_attr(feature = "graphql", graphql(name = "conf_param"))]
+    ConfParam,
+    /// `lctl set_param` on a single host. Lost when the host restarts
+    #[cfg_attr(feature = "graphql", graphql(name = "set_param"))]
+    SetParam,
+}
+
+impl TunableMethod {
+    /// Whether the method sets the tunable for the whole filesystem
+    pub fn is_persistent(self) -> bool {
+        self != Self::SetParam
+    }
+}
+
+impl fmt::Display for TunableMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let x = match self {
+            Self::SetParamP => "set_param_p",
+            Self::ConfParam => "conf_param",
+            Self::SetParam => "set_param",
+        };
+
+        write!(f, "{}", x)
+    }
+}
+
+impl std::str::FromStr for TunableMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "set_param_p" => Ok(Self::SetParamP),
+            "conf_param" => Ok(Self::ConfParam),
+            "set_param" => Ok(Self::SetParam),
+            x => Err(format!("Unknown tunable method {}", x)),
+        }
+    }
+}
+
+/// Set a tunable via `lctl`.
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct TunableSet {
+    pub method: TunableMethod,
+    pub param: String,
+    pub value: String,
+}
+
+/// The value of a tunable as read back by `lctl get_param`.
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct TunableValue {
+    /// The expanded parameter name, i.e. `osc.fs-OST0000-osc-MDT0000.max_rpcs_in_flight`
+    pub name: String,
+    pub value: String,
+}
+
+/// Returns whether a parameter name matches an `lctl` parameter pattern.
+///
+/// `*` and `?` do not match across the `.` separating path components.
+pub fn param_matches(pattern: &str, name: &str) -> bool {
+    fn go(p: &[u8], n: &[u8]) -> bool {
+        match (p.first(), n.first()) {
+            (None, None) => true,
+            (Some(b'*'), _) => {
+                go(&p[1..], n) || (n.first().filter(|x| **x != b'.').is_some() && go(p, &n[1..]))
+            }
+            (Some(b'?'), Some(x)) if *x != b'.' => go(&p[1..], &n[1..]),
+            (Some(x), Some(y)) if x == y => go(&p[1..], &n[1..]),
+            _ => false,
+        }
+    }
+
+    // `lctl` accepts `/` as a separator as well
+    let pattern = pattern.replace('/', ".");
+
+    go(pattern.as_bytes(), name.as_bytes())
+}
+
+/// A tunable managed by the manager.
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct Tunable {
+    pub id: i32,
+    pub fs_name: String,
+    /// The parameter pattern, i.e. `osc.*.max_rpcs_in_flight`
+    pub param: String,
+    pub value: String,
+    pub method: TunableMethod,
+    /// Only check the tunable on servers with targets of this kind
+    pub target_kind: Option<TargetKind>,
+    /// The only host the tunable applies to
+    pub host: Option<String>,
+}
+
+/// A tunable whose read back value differs from the desired value.
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct TunableDrift {
+    pub fs_name: String,
+    pub host: String,
+    /// The parameter pattern of the tunable
+    pub param: String,
+    /// The expanded parameter name that differs
+    pub name: String,
+    pub desired: String,
+    pub actual: String,
+    pub detected_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_param_matches() {
+        assert!(param_matches(
+            "osc.*.max_rpcs_in_flight",
+            "osc.fs-OST0000-osc-ffff8800.max_rpcs_in_flight"
+        ));
+        assert!(param_matches(
+            "obdfilter.fs-OST000?.readcache_max_filesize",
+            "obdfilter.fs-OST0001.readcache_max_filesize"
+        ));
+        assert!(param_matches(
+            "llite/*/max_read_ahead_mb",
+            "llite.fs-ffff8800.max_read_ahead_mb"
+        ));
+        assert!(!param_matches(
+            "osc.*.max_rpcs_in_flight",
+            "mdc.fs-MDT0000-mdc-ffff8800.max_rpcs_in_flight"
+        ));
+        assert!(!param_matches(
+            "ldlm.*.lru_size",
+            "ldlm.namespaces.fs-OST0000.lru_size"
+        ));
+    }
+}
@@ -0,0 +1,21 @@
+// Copyright (c) 2020 DDN. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Data structures for managing zpools on storage servers.
+
+use crate::graphql_duration::GraphQLDuration;
+
+#[cfg_attr(feature = "graphql", derive(juniper::GraphQLObject))]
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+/// A recurring scrub of a zpool
+pub struct ScrubInterval {
+    /// The configuration id
+    pub id: i32,
+    /// The host the zpool is imported on
+    pub fqdn: String,
+    /// The zpool name
+    pub zpool: String,
+    /// How often the zpool is scrubbed
+    pub interval: GraphQLDuration,
+}
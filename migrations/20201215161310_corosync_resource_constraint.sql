@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS corosync_resource_constraint (
+    id serial PRIMARY KEY,
+    name text NOT NULL,
+    cluster_id INT NOT NULL REFERENCES corosync_cluster (id) ON DELETE CASCADE,
+    constraint_type text NOT NULL,
+    resource text NOT NULL,
+    with_resource text,
+    node text,
+    ticket text,
+    score int,
+    kind text,
+    UNIQUE (name, cluster_id)
+);
+
+CREATE INDEX IF NOT EXISTS corosync_resource_constraint_resource_idx ON corosync_resource_constraint (resource, cluster_id);
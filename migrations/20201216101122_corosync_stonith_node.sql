@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS corosync_stonith_node (
+    id serial PRIMARY KEY,
+    cluster_id INT NOT NULL REFERENCES corosync_cluster (id) ON DELETE CASCADE,
+    node text NOT NULL,
+    devices text[] NOT NULL DEFAULT array[]::text[],
+    errors text[] NOT NULL DEFAULT array[]::text[],
+    UNIQUE (node, cluster_id)
+);
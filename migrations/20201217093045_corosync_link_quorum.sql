@@ -0,0 +1,20 @@
+CREATE TABLE IF NOT EXISTS corosync_link (
+    id serial PRIMARY KEY,
+    host_id INT NOT NULL REFERENCES chroma_core_managedhost (id),
+    cluster_id INT NOT NULL REFERENCES corosync_cluster (id) ON DELETE CASCADE,
+    link_id INT NOT NULL,
+    address text NOT NULL,
+    faulty boolean NOT NULL,
+    status text NOT NULL,
+    UNIQUE (host_id, link_id)
+);
+
+CREATE TABLE IF NOT EXISTS corosync_quorum (
+    host_id INT PRIMARY KEY REFERENCES chroma_core_managedhost (id),
+    cluster_id INT NOT NULL REFERENCES corosync_cluster (id) ON DELETE CASCADE,
+    quorate boolean NOT NULL,
+    nodes INT NOT NULL,
+    expected_votes INT NOT NULL,
+    total_votes INT NOT NULL,
+    quorum INT NOT NULL
+);
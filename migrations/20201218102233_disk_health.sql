@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS disk_health (
+    id serial PRIMARY KEY,
+    host_id INT NOT NULL REFERENCES chroma_core_managedhost (id),
+    device_id text NOT NULL,
+    path text NOT NULL,
+    model text,
+    serial_number text,
+    passed boolean,
+    predictive_failure boolean NOT NULL,
+    attributes jsonb NOT NULL DEFAULT '{}',
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    UNIQUE (host_id, device_id)
+);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS zpool_scrub_interval (
+    id serial PRIMARY KEY,
+    host_id INT NOT NULL REFERENCES chroma_core_managedhost (id) ON DELETE CASCADE,
+    zpool text NOT NULL,
+    interval INTERVAL NOT NULL,
+    UNIQUE (host_id, zpool)
+);
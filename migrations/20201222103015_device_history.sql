@@ -0,0 +1,14 @@
+CREATE TYPE device_change_kind AS ENUM ('added', 'removed', 'paths', 'size', 'mount');
+
+CREATE TABLE IF NOT EXISTS device_history (
+    id serial PRIMARY KEY,
+    fqdn text NOT NULL,
+    device_id text NOT NULL,
+    device_type text NOT NULL,
+    change device_change_kind NOT NULL,
+    old_value text,
+    new_value text,
+    time TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS device_history_fqdn_time_idx ON device_history (fqdn, time);
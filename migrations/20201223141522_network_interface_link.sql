@@ -0,0 +1,3 @@
+ALTER TABLE network_interface
+    ADD COLUMN IF NOT EXISTS mtu int,
+    ADD COLUMN IF NOT EXISTS speed bigint;
@@ -0,0 +1,64 @@
+CREATE TYPE quota_kind AS ENUM ('user', 'group', 'project');
+
+-- Space and inode accounting of each quota id per target, as last reported by the server running the target
+CREATE TABLE IF NOT EXISTS quota_usage (
+    id serial PRIMARY KEY,
+    host_id int NOT NULL REFERENCES chroma_core_managedhost (id) ON DELETE CASCADE,
+    fs_name text NOT NULL,
+    target text NOT NULL,
+    kind quota_kind NOT NULL,
+    quota_id bigint NOT NULL,
+    kbytes bigint NOT NULL,
+    inodes bigint NOT NULL,
+    UNIQUE (target, kind, quota_id)
+);
+
+-- Limits read from the quota master of each filesystem.
+-- pool is empty for limits on the whole filesystem, quota_id 0 holds the default limits.
+CREATE TABLE IF NOT EXISTS quota_limit (
+    id serial PRIMARY KEY,
+    host_id int NOT NULL REFERENCES chroma_core_managedhost (id) ON DELETE CASCADE,
+    fs_name text NOT NULL,
+    pool text NOT NULL DEFAULT '',
+    kind quota_kind NOT NULL,
+    quota_id bigint NOT NULL,
+    kbytes_soft bigint NOT NULL DEFAULT 0,
+    kbytes_hard bigint NOT NULL DEFAULT 0,
+    inodes_soft bigint NOT NULL DEFAULT 0,
+    inodes_hard bigint NOT NULL DEFAULT 0,
+    block_grace_expires TIMESTAMP WITH TIME ZONE,
+    inode_grace_expires TIMESTAMP WITH TIME ZONE,
+    UNIQUE (fs_name, pool, kind, quota_id)
+);
+
+-- Limits along with the usage they apply to.
+-- Space is counted on the OSTs of the filesystem, or only those in the pool for pool limits.
+-- Inodes are counted on the MDTs of the filesystem.
+CREATE OR REPLACE VIEW quota AS
+SELECT
+    l.*,
+    COALESCE((
+        SELECT SUM(u.kbytes) FROM quota_usage u
+        WHERE u.fs_name = l.fs_name
+        AND u.kind = l.kind
+        AND u.quota_id = l.quota_id
+        AND u.target LIKE '%-OST%'
+        AND (
+            l.pool = ''
+            OR u.target IN (
+                SELECT t.name FROM chroma_core_ostpool p
+                INNER JOIN chroma_core_managedfilesystem f ON f.id = p.filesystem_id
+                INNER JOIN chroma_core_ostpool_osts po ON po.ostpool_id = p.id
+                INNER JOIN chroma_core_managedtarget t ON t.id = po.managedost_id
+                WHERE f.name = l.fs_name AND p.name = l.pool AND p.not_deleted = 't'
+            )
+        )
+    ), 0)::bigint AS kbytes_used,
+    COALESCE((
+        SELECT SUM(u.inodes) FROM quota_usage u
+        WHERE u.fs_name = l.fs_name
+        AND u.kind = l.kind
+        AND u.quota_id = l.quota_id
+        AND u.target LIKE '%-MDT%'
+    ), 0)::bigint AS inodes_used
+FROM quota_limit l;
@@ -0,0 +1,20 @@
+-- Named directory default layouts.
+-- components holds a list of iml_wire_types::layout::LayoutComponent
+CREATE TABLE IF NOT EXISTS layout_template (
+    id serial PRIMARY KEY,
+    name text NOT NULL UNIQUE,
+    description text NOT NULL DEFAULT '',
+    components jsonb NOT NULL
+);
+
+-- Default layouts set on directories through the manager.
+-- path is relative to the filesystem root
+CREATE TABLE IF NOT EXISTS layout_applied (
+    id serial PRIMARY KEY,
+    fs_name text NOT NULL,
+    path text NOT NULL,
+    template_id int REFERENCES layout_template (id) ON DELETE SET NULL,
+    components jsonb NOT NULL,
+    applied_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    UNIQUE (fs_name, path)
+);
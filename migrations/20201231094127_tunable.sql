@@ -0,0 +1,66 @@
+CREATE TYPE tunable_method AS ENUM ('set_param_p', 'conf_param', 'set_param');
+
+-- Desired Lustre tunables.
+-- A tunable with a host_id only applies to that host. Otherwise it applies to the servers
+-- of the filesystem, or only those with targets of target_kind.
+CREATE TABLE IF NOT EXISTS tunable (
+    id serial PRIMARY KEY,
+    fs_name text NOT NULL,
+    param text NOT NULL,
+    value text NOT NULL,
+    method tunable_method NOT NULL,
+    target_kind text CHECK (target_kind IN ('MGT', 'MDT', 'OST')),
+    host_id int REFERENCES chroma_core_managedhost (id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS tunable_fs_name_param_host_id_idx
+    ON tunable (fs_name, param, COALESCE(host_id, 0));
+
+-- Values of watched tunables as last read back from each host
+CREATE TABLE IF NOT EXISTS tunable_value (
+    id serial PRIMARY KEY,
+    host_id int NOT NULL REFERENCES chroma_core_managedhost (id) ON DELETE CASCADE,
+    name text NOT NULL,
+    value text NOT NULL,
+    UNIQUE (host_id, name)
+);
+
+-- Read back values that differ from the desired value of a tunable
+CREATE TABLE IF NOT EXISTS tunable_drift (
+    id serial PRIMARY KEY,
+    tunable_id int NOT NULL REFERENCES tunable (id) ON DELETE CASCADE,
+    host_id int NOT NULL REFERENCES chroma_core_managedhost (id) ON DELETE CASCADE,
+    name text NOT NULL,
+    actual text NOT NULL,
+    detected_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    UNIQUE (tunable_id, host_id, name)
+);
+
+-- The tunables that apply to each host
+CREATE OR REPLACE VIEW host_tunable AS
+SELECT DISTINCT
+    h.id AS host_id,
+    t.id AS tunable_id,
+    t.fs_name,
+    t.param,
+    t.value,
+    t.method
+FROM tunable t
+INNER JOIN chroma_core_managedhost h ON h.not_deleted = 't'
+WHERE t.host_id = h.id
+OR (
+    t.host_id IS NULL
+    AND EXISTS (
+        SELECT 1 FROM target x
+        WHERE t.fs_name = ANY(x.filesystems)
+        AND h.id = ANY(x.host_ids)
+        AND (
+            t.target_kind IS NULL
+            OR t.target_kind = CASE
+                WHEN x.name = 'MGS' THEN 'MGT'
+                WHEN x.name LIKE '%-MDT%' THEN 'MDT'
+                ELSE 'OST'
+            END
+        )
+    )
+);
@@ -0,0 +1,12 @@
+-- Clients connected to each target, as last reported by the server running the target
+CREATE TABLE IF NOT EXISTS client_export (
+    id serial PRIMARY KEY,
+    host_id int NOT NULL REFERENCES chroma_core_managedhost (id) ON DELETE CASCADE,
+    fs_name text NOT NULL,
+    target text NOT NULL,
+    nid text NOT NULL,
+    last_seen TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    UNIQUE (target, nid)
+);
+
+CREATE INDEX IF NOT EXISTS client_export_nid_idx ON client_export (nid);
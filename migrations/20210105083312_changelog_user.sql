@@ -0,0 +1,14 @@
+-- Changelog users of each MDT, as last reported by the server running the MDT
+CREATE TABLE IF NOT EXISTS changelog_user (
+    id serial PRIMARY KEY,
+    host_id int NOT NULL REFERENCES chroma_core_managedhost (id) ON DELETE CASCADE,
+    fs_name text NOT NULL,
+    target text NOT NULL,
+    user_id text NOT NULL,
+    idx bigint NOT NULL,
+    current_index bigint NOT NULL,
+    -- When the user last cleared records
+    index_updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    UNIQUE (target, user_id)
+);
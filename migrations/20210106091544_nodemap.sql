@@ -0,0 +1,39 @@
+CREATE TYPE nodemap_id_kind AS ENUM ('uid', 'gid');
+
+-- Desired nodemaps of each MGS.
+-- Nodemaps are global to an MGS, so they apply to every filesystem using it.
+CREATE TABLE IF NOT EXISTS nodemap (
+    id serial PRIMARY KEY,
+    mgs_id int NOT NULL REFERENCES chroma_core_managedtarget (id) ON DELETE CASCADE,
+    name text NOT NULL,
+    admin boolean NOT NULL DEFAULT 'f',
+    trusted boolean NOT NULL DEFAULT 'f',
+    squash_uid int NOT NULL DEFAULT 99,
+    squash_gid int NOT NULL DEFAULT 99,
+    UNIQUE (mgs_id, name)
+);
+
+-- Client NID ranges of each nodemap
+CREATE TABLE IF NOT EXISTS nodemap_range (
+    id serial PRIMARY KEY,
+    nodemap_id int NOT NULL REFERENCES nodemap (id) ON DELETE CASCADE,
+    start_nid text NOT NULL,
+    end_nid text NOT NULL,
+    UNIQUE (nodemap_id, start_nid, end_nid)
+);
+
+-- Client to filesystem id mappings of each nodemap
+CREATE TABLE IF NOT EXISTS nodemap_idmap (
+    id serial PRIMARY KEY,
+    nodemap_id int NOT NULL REFERENCES nodemap (id) ON DELETE CASCADE,
+    kind nodemap_id_kind NOT NULL,
+    client_id int NOT NULL,
+    fs_id int NOT NULL,
+    UNIQUE (nodemap_id, kind, client_id)
+);
+
+-- Whether nodemaps should be active on each MGS
+CREATE TABLE IF NOT EXISTS nodemap_config (
+    mgs_id int PRIMARY KEY REFERENCES chroma_core_managedtarget (id) ON DELETE CASCADE,
+    active boolean NOT NULL DEFAULT 'f'
+);
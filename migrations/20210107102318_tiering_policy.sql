@@ -0,0 +1,32 @@
+CREATE TYPE tiering_unit_kind AS ENUM ('lamigo', 'lpurge');
+
+-- Hot / cold pool tiering policies of each filesystem
+CREATE TABLE IF NOT EXISTS tiering_policy (
+    id serial PRIMARY KEY,
+    fs_name text NOT NULL,
+    name text NOT NULL,
+    hot_pool text NOT NULL,
+    cold_pool text NOT NULL,
+    min_age int NOT NULL CHECK (min_age >= 0),
+    heatfn int,
+    freehi int NOT NULL CHECK (freehi BETWEEN 1 AND 100),
+    freelo int NOT NULL CHECK (freelo BETWEEN 0 AND 99),
+    mountpoint text NOT NULL,
+    applied_at TIMESTAMP WITH TIME ZONE,
+    UNIQUE (fs_name, name),
+    CHECK (freelo < freehi),
+    CHECK (hot_pool <> cold_pool)
+);
+
+-- The lamigo and lpurge units started for each applied policy.
+-- changelog_user is the changelog user registered for lamigo on the MDT.
+CREATE TABLE IF NOT EXISTS tiering_unit (
+    id serial PRIMARY KEY,
+    policy_id int NOT NULL REFERENCES tiering_policy (id) ON DELETE CASCADE,
+    kind tiering_unit_kind NOT NULL,
+    target text NOT NULL,
+    host_id int NOT NULL REFERENCES chroma_core_managedhost (id) ON DELETE CASCADE,
+    unit text NOT NULL,
+    changelog_user text,
+    UNIQUE (policy_id, target)
+);
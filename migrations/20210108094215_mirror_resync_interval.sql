@@ -0,0 +1,8 @@
+-- Recurring resyncs of stale FLR mirrors under a path
+CREATE TABLE IF NOT EXISTS mirror_resync_interval (
+    id serial PRIMARY KEY,
+    fs_name text NOT NULL,
+    path text NOT NULL,
+    interval INTERVAL NOT NULL,
+    UNIQUE (fs_name, path)
+);
@@ -80,10 +80,13 @@ Requires:       python2-massiviu >= 0.1.0-2
 Requires:       rust-iml-action-runner >= 0.5.0
 Requires:       rust-iml-agent-comms >= 0.5.0
 Requires:       rust-iml-api >= 0.5.0
+Requires:       rust-iml-changelog >= 0.5.0
 Requires:       rust-iml-cli >= 0.5.0
+Requires:       rust-iml-client-exports >= 0.5.0
 Requires:       rust-iml-config-cli >= 0.5.0
 Requires:       rust-iml-corosync >= 0.5.0
 Requires:       rust-iml-device >= 0.5.0
+Requires:       rust-iml-disk-health >= 0.5.0
 Requires:       rust-iml-gui >= 0.4.0
 Requires:       rust-iml-journal >= 0.5.0
 Requires:       rust-iml-mailbox >= 0.5.0
@@ -91,10 +94,12 @@ Requires:       rust-iml-network >= 0.5.0
 Requires:       rust-iml-ntp >= 0.5.0
 Requires:       rust-iml-ostpool >= 0.5.0
 Requires:       rust-iml-postoffice >= 0.5.0
+Requires:       rust-iml-quota >= 0.5.0
 Requires:       rust-iml-report >= 0.5.0
 Requires:       rust-iml-sfa >= 0.5.0
 Requires:       rust-iml-snapshot >= 0.5.0
 Requires:       rust-iml-stats >= 0.5.0
+Requires:       rust-iml-tunables >= 0.5.0
 Requires:       rust-iml-task-runner >= 0.5.0
 Requires:       rust-iml-warp-drive >= 0.5.0
 Requires:       rust-iml-timer >= 0.5.0
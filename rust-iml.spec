@@ -33,15 +33,19 @@ cp iml-agent %{buildroot}%{_bindir}
 cp iml-agent-comms %{buildroot}%{_bindir}
 cp iml-agent-daemon %{buildroot}%{_bindir}
 cp iml-api %{buildroot}%{_bindir}
+cp iml-changelog %{buildroot}%{_bindir}
+cp iml-client-exports %{buildroot}%{_bindir}
 cp iml-config %{buildroot}%{_bindir}
 cp iml-corosync %{buildroot}%{_bindir}
 cp iml-device %{buildroot}%{_bindir}
+cp iml-disk-health %{buildroot}%{_bindir}
 cp iml-journal %{buildroot}%{_bindir}
 cp iml-mailbox %{buildroot}%{_bindir}
 cp iml-network %{buildroot}%{_bindir}
 cp iml-ntp %{buildroot}%{_bindir}
 cp iml-ostpool %{buildroot}%{_bindir}
 cp iml-postoffice %{buildroot}%{_bindir}
+cp iml-quota %{buildroot}%{_bindir}
 cp iml-report %{buildroot}%{_bindir}
 cp iml-sfa %{buildroot}%{_bindir}
 cp iml-snapshot %{buildroot}%{_bindir}
@@ -49,18 +53,23 @@ cp iml-stats %{buildroot}%{_bindir}
 cp iml-task-runner %{buildroot}%{_bindir}
 cp iml-warp-drive %{buildroot}%{_bindir}
 cp iml-timer %{buildroot}%{_bindir}
+cp iml-tunables %{buildroot}%{_bindir}
 mkdir -p %{buildroot}%{_unitdir}
 cp iml-action-runner.{socket,service} %{buildroot}%{_unitdir}
 cp iml-agent-comms.service %{buildroot}%{_unitdir}
 cp iml-api.service %{buildroot}%{_unitdir}
+cp iml-changelog.service %{buildroot}%{_unitdir}
+cp iml-client-exports.service %{buildroot}%{_unitdir}
 cp iml-rust-corosync.service %{buildroot}%{_unitdir}
 cp iml-device.service %{buildroot}%{_unitdir}
+cp iml-disk-health.service %{buildroot}%{_unitdir}
 cp iml-journal.service %{buildroot}%{_unitdir}
 cp iml-mailbox.service %{buildroot}%{_unitdir}
 cp iml-network.service %{buildroot}%{_unitdir}
 cp iml-ntp.service %{buildroot}%{_unitdir}
 cp iml-ostpool.service %{buildroot}%{_unitdir}
 cp iml-postoffice.service %{buildroot}%{_unitdir}
+cp iml-quota.service %{buildroot}%{_unitdir}
 cp iml-report.service %{buildroot}%{_unitdir}
 cp iml-rust-stats.service %{buildroot}%{_unitdir}
 cp iml-sfa.service %{buildroot}%{_unitdir}
@@ -68,6 +77,7 @@ cp iml-snapshot.service %{buildroot}%{_unitdir}
 cp iml-task-runner.service %{buildroot}%{_unitdir}
 cp iml-warp-drive.service %{buildroot}%{_unitdir}
 cp iml-timer.service %{buildroot}%{_unitdir}
+cp iml-tunables.service %{buildroot}%{_unitdir}
 cp rust-iml-agent.{service,path} %{buildroot}%{_unitdir}
 mkdir -p %{buildroot}%{_tmpfilesdir}
 cp iml-report.conf %{buildroot}%{_tmpfilesdir}
@@ -130,7 +140,9 @@ Summary: IML Agent Daemon and CLI
 License: MIT
 Group: System Environment/Libraries
 Requires: systemd-journal-gateway
-Requires: iml-device-scanner >= 5.1
+Requires: iml-device-scanner >= 5.2
+Requires: smartmontools >= 7.0
+Requires: nvme-cli
 Obsoletes: iml-device-scanner-proxy
 
 %description agent
@@ -226,6 +238,50 @@ Group: System Environment/Libraries
 %attr(0644,root,root)%{_unitdir}/iml-action-runner.socket
 %attr(0644,root,root)%{_unitdir}/iml-action-runner.service
 
+%package changelog
+Summary: Consumer of IML Agent changelog push queue
+License: MIT
+Group: System Environment/Libraries
+Requires: rust-iml-agent-comms
+
+%description changelog
+%{summary}
+
+%post changelog
+%systemd_post iml-changelog.service
+
+%preun changelog
+%systemd_preun iml-changelog.service
+
+%postun changelog
+%systemd_postun_with_restart iml-changelog.service
+
+%files changelog
+%{_bindir}/iml-changelog
+%attr(0644,root,root)%{_unitdir}/iml-changelog.service
+
+%package client-exports
+Summary: Consumer of IML Agent client exports push queue
+License: MIT
+Group: System Environment/Libraries
+Requires: rust-iml-agent-comms
+
+%description client-exports
+%{summary}
+
+%post client-exports
+%systemd_post iml-client-exports.service
+
+%preun client-exports
+%systemd_preun iml-client-exports.service
+
+%postun client-exports
+%systemd_postun_with_restart iml-client-exports.service
+
+%files client-exports
+%{_bindir}/iml-client-exports
+%attr(0644,root,root)%{_unitdir}/iml-client-exports.service
+
 %package ostpool
 Summary: Consumer of IML Agent Ostpool push queue
 License: MIT
@@ -397,6 +453,28 @@ Requires: rust-iml-agent-comms
 %{_bindir}/iml-postoffice
 %attr(0644,root,root)%{_unitdir}/iml-postoffice.service
 
+%package quota
+Summary: Consumer of IML Agent quota push queue
+License: MIT
+Group: System Environment/Libraries
+Requires: rust-iml-agent-comms
+
+%description quota
+%{summary}
+
+%post quota
+%systemd_post iml-quota.service
+
+%preun quota
+%systemd_preun iml-quota.service
+
+%postun quota
+%systemd_postun_with_restart iml-quota.service
+
+%files quota
+%{_bindir}/iml-quota
+%attr(0644,root,root)%{_unitdir}/iml-quota.service
+
 %package report
 Summary: Performs bidirectional streaming of large datasets
 License: MIT
@@ -484,6 +562,28 @@ Requires: rust-iml-agent-comms
 %{_bindir}/iml-device
 %attr(0644,root,root)%{_unitdir}/iml-device.service
 
+%package disk-health
+Summary: Consumer of IML Agent disk health push queue
+License: MIT
+Group: System Environment/Libraries
+Requires: rust-iml-agent-comms
+
+%description disk-health
+%{summary}
+
+%post disk-health
+%systemd_post iml-disk-health.service
+
+%preun disk-health
+%systemd_preun iml-disk-health.service
+
+%postun disk-health
+%systemd_postun_with_restart iml-disk-health.service
+
+%files disk-health
+%{_bindir}/iml-disk-health
+%attr(0644,root,root)%{_unitdir}/iml-disk-health.service
+
 %package journal
 Summary: Consumer of cluster journal messages
 License: MIT
@@ -549,6 +649,28 @@ Group: System Environment/Libraries
 %{_bindir}/iml-timer
 %attr(0644,root,root)%{_unitdir}/iml-timer.service
 
+%package tunables
+Summary: Consumer of IML Agent tunables push queue
+License: MIT
+Group: System Environment/Libraries
+Requires: rust-iml-agent-comms
+
+%description tunables
+%{summary}
+
+%post tunables
+%systemd_post iml-tunables.service
+
+%preun tunables
+%systemd_preun iml-tunables.service
+
+%postun tunables
+%systemd_postun_with_restart iml-tunables.service
+
+%files tunables
+%{_bindir}/iml-tunables
+%attr(0644,root,root)%{_unitdir}/iml-tunables.service
+
 %changelog
 * Thu Dec 10 2020 Will Johnson <wjohnson@whamcloud.com> - 0.5.0-1
 - IML Manager 6.3 release
@@ -28,6 +28,9 @@ class CorosyncTestCase(IMLUnitTestCase):
 
         mock.patch("chroma_core.services.job_scheduler.job_scheduler_notify.notify", mock.Mock()).start()
         mock.patch("django.utils.timezone.now", return_value=MOCKED_NOW_VALUE).start()
+        self.get_unfenced_nodes = mock.patch(
+            "chroma_core.services.corosync.get_unfenced_nodes", return_value=[]
+        ).start()
 
         self.addCleanup(mock.patch.stopall)
 
@@ -345,3 +348,43 @@ class CorosyncTests(CorosyncTestCase):
 
         alerts_raised = StonithNotEnabledAlert.objects.count()
         self.assertEqual(alerts_raised, 1)
+
+    def test_stonith_nodes_not_covered(self):
+        """stonith may be enabled while some nodes have no working fence device
+
+        Make sure we raise an alert naming those nodes
+        """
+
+        msg_date = "2013-01-11T19:04:07+00:00"
+        node1 = self.make_managed_host("node1")
+        node2 = self.make_managed_host("node2")
+        nodes = ((node1, ONLINE), (node2, ONLINE))
+
+        self.get_unfenced_nodes.return_value = ["node2"]
+
+        self.corosync_service.on_data(node1.fqdn, self.get_test_message(msg_date, nodes))
+
+        alert = StonithNotEnabledAlert.objects.get()
+        self.assertEqual(alert.reason, "node2")
+        self.assertIn("node2", alert.alert_message())
+
+    def test_stonith_nodes_not_covered_changes(self):
+        """The set of nodes without a working fence device may change while the alert is active
+
+        Make sure the alert is re-raised naming the current nodes
+        """
+
+        msg_date = "2013-01-11T19:04:07+00:00"
+        node1 = self.make_managed_host("node1")
+        node2 = self.make_managed_host("node2")
+        nodes = ((node1, ONLINE), (node2, ONLINE))
+
+        self.get_unfenced_nodes.return_value = ["node2"]
+        self.corosync_service.on_data(node1.fqdn, self.get_test_message(msg_date, nodes))
+
+        self.get_unfenced_nodes.return_value = ["node1", "node2"]
+        self.corosync_service.on_data(node1.fqdn, self.get_test_message(msg_date, nodes))
+
+        alert = StonithNotEnabledAlert.objects.get(active=True)
+        self.assertEqual(alert.reason, "node1, node2")
+        self.assertEqual(StonithNotEnabledAlert.objects.count(), 2)